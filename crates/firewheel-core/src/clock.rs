@@ -59,6 +59,61 @@ pub enum EventInstant {
 
 #[cfg(feature = "scheduled_events")]
 impl EventInstant {
+    /// An event that should happen the given number of seconds after the
+    /// time of the given audio clock.
+    ///
+    /// Unlike [`EventInstant::DelaySeconds`], the delay is relative to the
+    /// time the clock was read on the main thread rather than to the time the
+    /// processor receives the event, so events created from the same clock
+    /// reading stay aligned with each other.
+    pub fn after_seconds(clock: &AudioClock, seconds: f64) -> Self {
+        Self::AtClockSeconds(clock.seconds + DurationSeconds(seconds))
+    }
+
+    /// An event that should happen the given number of samples (of a single
+    /// channel of audio) after the time of the given audio clock.
+    ///
+    /// Unlike [`EventInstant::DelaySamples`], the delay is relative to the
+    /// time the clock was read on the main thread rather than to the time the
+    /// processor receives the event, so events created from the same clock
+    /// reading stay aligned with each other.
+    pub fn after_samples(clock: &AudioClock, samples: i64) -> Self {
+        Self::AtClockSamples(clock.samples + DurationSamples(samples))
+    }
+
+    /// An event that should happen when the clock reaches the given time in
+    /// seconds.
+    pub const fn at_seconds(seconds: f64) -> Self {
+        Self::AtClockSeconds(InstantSeconds(seconds))
+    }
+
+    /// An event that should happen when the clock reaches the given time in
+    /// samples (of a single channel of audio).
+    pub const fn at_sample(sample: i64) -> Self {
+        Self::AtClockSamples(InstantSamples(sample))
+    }
+
+    /// An event that should happen on the next point on the grid of the given
+    /// musical subdivision after the time of the given audio clock.
+    ///
+    /// Returns `None` if there is no musical transport or if it is not
+    /// currently playing.
+    ///
+    /// See [`InstantMusical::next_multiple_of`].
+    #[cfg(feature = "musical_transport")]
+    pub fn next_musical_subdivision(
+        clock: &AudioClock,
+        subdivision: DurationMusical,
+    ) -> Option<Self> {
+        if !clock.transport_is_playing {
+            return None;
+        }
+
+        clock
+            .musical
+            .map(|musical| Self::AtClockMusical(musical.next_multiple_of(subdivision)))
+    }
+
     pub fn is_musical(&self) -> bool {
         #[cfg(feature = "musical_transport")]
         return matches!(self, EventInstant::AtClockMusical(_));
@@ -274,6 +329,13 @@ impl DurationSeconds {
     pub fn to_samples(self, sample_rate: NonZeroU32) -> DurationSamples {
         DurationSamples(seconds_to_samples(self.0, sample_rate))
     }
+
+    /// Convert to the corresponding duration in musical beats at a constant
+    /// tempo.
+    #[cfg(feature = "musical_transport")]
+    pub fn to_musical(self, beats_per_minute: f64) -> DurationMusical {
+        DurationMusical(self.0 * beats_per_minute / 60.0)
+    }
 }
 
 fn seconds_to_samples(seconds: f64, sample_rate: NonZeroU32) -> i64 {
@@ -485,6 +547,19 @@ impl DurationSamples {
     pub fn to_seconds(self, sample_rate: NonZeroU32, sample_rate_recip: f64) -> DurationSeconds {
         DurationSeconds(samples_to_seconds(self.0, sample_rate, sample_rate_recip))
     }
+
+    /// Convert to the corresponding duration in musical beats at a constant
+    /// tempo.
+    #[cfg(feature = "musical_transport")]
+    pub fn to_musical(
+        self,
+        beats_per_minute: f64,
+        sample_rate: NonZeroU32,
+        sample_rate_recip: f64,
+    ) -> DurationMusical {
+        self.to_seconds(sample_rate, sample_rate_recip)
+            .to_musical(beats_per_minute)
+    }
 }
 
 /// (whole seconds, samples *after* whole seconds)
@@ -670,6 +745,85 @@ impl InstantMusical {
         self.to_seconds_with_spb(seconds_per_beat)
             .to_samples(sample_rate)
     }
+
+    /// Convert to the corresponding time in seconds using the given transport.
+    ///
+    /// This is the inverse of [`InstantSeconds::to_musical`].
+    pub fn to_seconds_with_transport(
+        self,
+        transport: &MusicalTransport,
+        transport_start: InstantSeconds,
+        speed_multiplier: f64,
+    ) -> InstantSeconds {
+        transport.musical_to_seconds(self, transport_start, speed_multiplier)
+    }
+
+    /// Convert to the corresponding time in samples using the given transport.
+    ///
+    /// This is the inverse of [`InstantSamples::to_musical`].
+    pub fn to_samples_with_transport(
+        self,
+        transport: &MusicalTransport,
+        transport_start: InstantSamples,
+        speed_multiplier: f64,
+        sample_rate: NonZeroU32,
+    ) -> InstantSamples {
+        transport.musical_to_samples(self, transport_start, speed_multiplier, sample_rate)
+    }
+
+    /// Returns the first point on the grid of the given subdivision that is
+    /// at or after this instant.
+    ///
+    /// For example, a subdivision of `DurationMusical(0.25)` quantizes to the
+    /// next sixteenth note in 4/4 time. If `subdivision` is not greater than
+    /// zero, then this instant is returned unchanged.
+    pub fn next_multiple_of(&self, subdivision: DurationMusical) -> InstantMusical {
+        if subdivision.0 <= 0.0 {
+            return *self;
+        }
+
+        InstantMusical((self.0 / subdivision.0).ceil() * subdivision.0)
+    }
+
+    /// Convert to the corresponding position in bars, beats, and ticks.
+    ///
+    /// If `beats_per_bar` is `0`, then it will be treated as `1`.
+    pub fn to_position(&self, beats_per_bar: u32) -> MusicalPosition {
+        let beats_per_bar = i64::from(beats_per_bar.max(1));
+
+        let total_ticks = (self.0 * f64::from(MusicalPosition::TICKS_PER_BEAT)).floor() as i64;
+        let total_beats = total_ticks.div_euclid(i64::from(MusicalPosition::TICKS_PER_BEAT));
+
+        MusicalPosition {
+            bar: total_beats.div_euclid(beats_per_bar),
+            beat: total_beats.rem_euclid(beats_per_bar) as u32,
+            tick: total_ticks.rem_euclid(i64::from(MusicalPosition::TICKS_PER_BEAT)) as u32,
+        }
+    }
+}
+
+/// A musical position in bars, beats, and ticks.
+///
+/// All fields are zero-based, so the very start of the transport is bar `0`,
+/// beat `0`, tick `0`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg(feature = "musical_transport")]
+pub struct MusicalPosition {
+    /// The bar. This can be negative if the playhead is before the start of
+    /// the transport.
+    pub bar: i64,
+    /// The beat within the bar, in the range `[0, beats_per_bar)`.
+    pub beat: u32,
+    /// The tick within the beat, in the range `[0, TICKS_PER_BEAT)`.
+    pub tick: u32,
+}
+
+#[cfg(feature = "musical_transport")]
+impl MusicalPosition {
+    /// The number of ticks in a single beat.
+    pub const TICKS_PER_BEAT: u32 = 960;
 }
 
 /// An audio clock duration in units of musical beats.
@@ -687,6 +841,31 @@ impl DurationMusical {
     pub const fn new(beats: f64) -> Self {
         Self(beats)
     }
+
+    /// Convert to the corresponding duration in seconds at a constant tempo.
+    pub fn to_seconds(self, beats_per_minute: f64) -> DurationSeconds {
+        DurationSeconds(self.0 * 60.0 / beats_per_minute)
+    }
+
+    /// Convert to the corresponding duration in samples at a constant tempo.
+    pub fn to_samples(self, beats_per_minute: f64, sample_rate: NonZeroU32) -> DurationSamples {
+        self.to_seconds(beats_per_minute).to_samples(sample_rate)
+    }
+
+    /// Convert to the corresponding duration in seconds.
+    pub fn to_seconds_with_spb(self, seconds_per_beat: f64) -> DurationSeconds {
+        DurationSeconds(self.0 * seconds_per_beat)
+    }
+
+    /// Convert to the corresponding duration in samples.
+    pub fn to_samples_with_spb(
+        self,
+        seconds_per_beat: f64,
+        sample_rate: NonZeroU32,
+    ) -> DurationSamples {
+        self.to_seconds_with_spb(seconds_per_beat)
+            .to_samples(sample_rate)
+    }
 }
 
 #[cfg(feature = "musical_transport")]
@@ -872,3 +1051,18 @@ pub struct AudioClock {
     /// account.
     pub update_instant: Option<Instant>,
 }
+
+#[cfg(all(test, feature = "musical_transport"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn musical_position_from_beats() {
+        let pos = |bar, beat, tick| MusicalPosition { bar, beat, tick };
+
+        assert_eq!(InstantMusical(0.0).to_position(4), pos(0, 0, 0));
+        assert_eq!(InstantMusical(5.5).to_position(4), pos(1, 1, 480));
+        assert_eq!(InstantMusical(6.0).to_position(3), pos(2, 0, 0));
+        assert_eq!(InstantMusical(-0.25).to_position(4), pos(-1, 3, 720));
+    }
+}
@@ -1,4 +1,5 @@
 mod dynamic_transport;
+mod ramped_transport;
 mod static_transport;
 
 use bevy_platform::prelude::Vec;
@@ -7,10 +8,14 @@ use bevy_platform::sync::Arc;
 use core::{fmt::Debug, num::NonZeroU32, ops::Range};
 
 pub use dynamic_transport::{DynamicTransport, TransportKeyframe};
+pub use ramped_transport::RampedTransport;
 pub use static_transport::StaticTransport;
 
 use crate::{
-    clock::{DurationSeconds, EventInstant, InstantMusical, InstantSamples, InstantSeconds},
+    clock::{
+        DurationMusical, DurationSeconds, EventInstant, InstantMusical, InstantSamples,
+        InstantSeconds,
+    },
     diff::Notify,
 };
 
@@ -23,6 +28,9 @@ pub enum MusicalTransport {
     /// immediately jumps from one keyframe to another (the tempo is *NOT*
     /// linearly interpolated between keyframes).
     Dynamic(Arc<DynamicTransport>),
+    /// A musical transport which smoothly ramps from one tempo to another
+    /// (i.e. an accelerando or a ritardando).
+    Ramped(RampedTransport),
 }
 
 impl MusicalTransport {
@@ -57,6 +65,9 @@ impl MusicalTransport {
             MusicalTransport::Dynamic(t) => {
                 t.musical_to_seconds(musical, transport_start, speed_multiplier)
             }
+            MusicalTransport::Ramped(t) => {
+                t.musical_to_seconds(musical, transport_start, speed_multiplier)
+            }
         }
     }
 
@@ -83,6 +94,9 @@ impl MusicalTransport {
             MusicalTransport::Dynamic(t) => {
                 t.musical_to_samples(musical, transport_start, speed_multiplier, sample_rate)
             }
+            MusicalTransport::Ramped(t) => {
+                t.musical_to_samples(musical, transport_start, speed_multiplier, sample_rate)
+            }
         }
     }
 
@@ -108,6 +122,9 @@ impl MusicalTransport {
             MusicalTransport::Dynamic(t) => {
                 t.seconds_to_musical(seconds, transport_start, speed_multiplier)
             }
+            MusicalTransport::Ramped(t) => {
+                t.seconds_to_musical(seconds, transport_start, speed_multiplier)
+            }
         }
     }
 
@@ -144,6 +161,13 @@ impl MusicalTransport {
                 sample_rate,
                 sample_rate_recip,
             ),
+            MusicalTransport::Ramped(t) => t.samples_to_musical(
+                sample_time,
+                transport_start,
+                speed_multiplier,
+                sample_rate,
+                sample_rate_recip,
+            ),
         }
     }
 
@@ -166,6 +190,9 @@ impl MusicalTransport {
             MusicalTransport::Dynamic(t) => {
                 t.delta_seconds_from(from, delta_seconds, speed_multiplier)
             }
+            MusicalTransport::Ramped(t) => {
+                t.delta_seconds_from(from, delta_seconds, speed_multiplier)
+            }
         }
     }
 
@@ -178,6 +205,7 @@ impl MusicalTransport {
         match self {
             MusicalTransport::Static(t) => t.bpm_at_musical(musical, speed_multiplier),
             MusicalTransport::Dynamic(t) => t.bpm_at_musical(musical, speed_multiplier),
+            MusicalTransport::Ramped(t) => t.bpm_at_musical(musical, speed_multiplier),
         }
     }
 
@@ -202,6 +230,9 @@ impl MusicalTransport {
             MusicalTransport::Dynamic(t) => {
                 t.proc_transport_info(frames, playhead, speed_multiplier, sample_rate)
             }
+            MusicalTransport::Ramped(t) => {
+                t.proc_transport_info(frames, playhead, speed_multiplier, sample_rate)
+            }
         }
     }
 
@@ -228,6 +259,9 @@ impl MusicalTransport {
             MusicalTransport::Dynamic(t) => {
                 t.transport_start(now, playhead, speed_multiplier, sample_rate)
             }
+            MusicalTransport::Ramped(t) => {
+                t.transport_start(now, playhead, speed_multiplier, sample_rate)
+            }
         }
     }
 }
@@ -353,6 +387,12 @@ pub struct TransportState {
 
     /// If this is `Some`, then the transport will continuously loop the given region.
     pub loop_range: Option<Range<InstantMusical>>,
+
+    /// The number of beats in a single bar, used by nodes to get the current
+    /// [`MusicalPosition`](crate::clock::MusicalPosition).
+    ///
+    /// By default this is set to `4`.
+    pub beats_per_bar: u32,
 }
 
 impl TransportState {
@@ -364,6 +404,23 @@ impl TransportState {
             beats_per_minute.map(|bpm| MusicalTransport::Static(StaticTransport::new(bpm)));
     }
 
+    /// Set the transport to ramp from `start_bpm` to `end_bpm` over
+    /// `ramp_duration` beats, starting at `ramp_start` ([`RampedTransport`]).
+    pub fn set_ramped_transport(
+        &mut self,
+        start_bpm: f64,
+        end_bpm: f64,
+        ramp_start: InstantMusical,
+        ramp_duration: DurationMusical,
+    ) {
+        self.transport = Some(MusicalTransport::Ramped(RampedTransport::new(
+            start_bpm,
+            end_bpm,
+            ramp_start,
+            ramp_duration,
+        )));
+    }
+
     /// Get the beats per minute of the current static transport.
     ///
     /// Returns `None` if `transport` is `None` or if `transport` is not
@@ -398,6 +455,7 @@ impl Default for TransportState {
             speed: TransportSpeed::default(),
             stop_at: None,
             loop_range: None,
+            beats_per_bar: 4,
         }
     }
 }
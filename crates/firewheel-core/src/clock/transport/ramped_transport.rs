@@ -0,0 +1,289 @@
+use core::num::NonZeroU32;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use crate::clock::{
+    DurationMusical, DurationSeconds, InstantMusical, InstantSamples, InstantSeconds,
+    ProcTransportInfo,
+};
+
+/// Slopes (in beats per minute per beat) smaller than this are treated as a
+/// constant tempo to avoid dividing by (nearly) zero.
+const MIN_SLOPE: f64 = 1e-9;
+
+/// A musical transport which smoothly ramps from one tempo to another, i.e.
+/// for an accelerando or a ritardando.
+///
+/// The tempo is [`RampedTransport::start_bpm`] until
+/// [`RampedTransport::ramp_start`], then changes linearly (in musical time)
+/// over [`RampedTransport::ramp_duration`] to [`RampedTransport::end_bpm`],
+/// after which it stays constant.
+///
+/// For a single constant tempo, prefer to use a
+/// [`StaticTransport`](super::StaticTransport).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RampedTransport {
+    /// The tempo in beats per minute before the ramp.
+    ///
+    /// This must be greater than `0.0`.
+    pub start_bpm: f64,
+    /// The tempo in beats per minute at the end of the ramp.
+    ///
+    /// This must be greater than `0.0`.
+    pub end_bpm: f64,
+    /// The instant the ramp starts.
+    pub ramp_start: InstantMusical,
+    /// The length of the ramp in musical beats.
+    ///
+    /// If this is `0.0`, then the tempo jumps to `end_bpm` at `ramp_start`.
+    pub ramp_duration: DurationMusical,
+}
+
+impl RampedTransport {
+    pub const fn new(
+        start_bpm: f64,
+        end_bpm: f64,
+        ramp_start: InstantMusical,
+        ramp_duration: DurationMusical,
+    ) -> Self {
+        Self {
+            start_bpm,
+            end_bpm,
+            ramp_start,
+            ramp_duration,
+        }
+    }
+
+    pub fn musical_to_seconds(
+        &self,
+        musical: InstantMusical,
+        transport_start: InstantSeconds,
+        speed_multiplier: f64,
+    ) -> InstantSeconds {
+        transport_start + DurationSeconds(self.beats_to_seconds(musical.0) / speed_multiplier)
+    }
+
+    pub fn musical_to_samples(
+        &self,
+        musical: InstantMusical,
+        transport_start: InstantSamples,
+        speed_multiplier: f64,
+        sample_rate: NonZeroU32,
+    ) -> InstantSamples {
+        transport_start
+            + DurationSeconds(self.beats_to_seconds(musical.0) / speed_multiplier)
+                .to_samples(sample_rate)
+    }
+
+    pub fn seconds_to_musical(
+        &self,
+        seconds: InstantSeconds,
+        transport_start: InstantSeconds,
+        speed_multiplier: f64,
+    ) -> InstantMusical {
+        InstantMusical(self.seconds_to_beats((seconds - transport_start).0 * speed_multiplier))
+    }
+
+    pub fn samples_to_musical(
+        &self,
+        sample_time: InstantSamples,
+        transport_start: InstantSamples,
+        speed_multiplier: f64,
+        sample_rate: NonZeroU32,
+        sample_rate_recip: f64,
+    ) -> InstantMusical {
+        InstantMusical(
+            self.seconds_to_beats(
+                (sample_time - transport_start)
+                    .to_seconds(sample_rate, sample_rate_recip)
+                    .0
+                    * speed_multiplier,
+            ),
+        )
+    }
+
+    pub fn delta_seconds_from(
+        &self,
+        from: InstantMusical,
+        delta_seconds: DurationSeconds,
+        speed_multiplier: f64,
+    ) -> InstantMusical {
+        InstantMusical(
+            self.seconds_to_beats(
+                self.beats_to_seconds(from.0) + delta_seconds.0 * speed_multiplier,
+            ),
+        )
+    }
+
+    pub fn bpm_at_musical(&self, musical: InstantMusical, speed_multiplier: f64) -> f64 {
+        self.bpm_at_beats(musical.0) * speed_multiplier
+    }
+
+    pub fn transport_start(
+        &self,
+        now: InstantSamples,
+        playhead: InstantMusical,
+        speed_multiplier: f64,
+        sample_rate: NonZeroU32,
+    ) -> InstantSamples {
+        now - DurationSeconds(self.beats_to_seconds(playhead.0) / speed_multiplier)
+            .to_samples(sample_rate)
+    }
+
+    /// Return information about this transport for this processing block.
+    ///
+    /// The block is split at the start and the end of the ramp. Within the
+    /// ramp, the reported tempo is the tempo at the first frame of the block.
+    pub fn proc_transport_info(
+        &self,
+        mut frames: usize,
+        playhead: InstantMusical,
+        speed_multiplier: f64,
+        sample_rate: NonZeroU32,
+    ) -> ProcTransportInfo {
+        let ramp_end = self.ramp_start.0 + self.ramp_duration.0.max(0.0);
+
+        let next_boundary = if playhead.0 < self.ramp_start.0 {
+            Some(self.ramp_start.0)
+        } else if playhead.0 < ramp_end {
+            Some(ramp_end)
+        } else {
+            None
+        };
+
+        if let Some(boundary) = next_boundary {
+            let frames_left = DurationSeconds(
+                (self.beats_to_seconds(boundary) - self.beats_to_seconds(playhead.0))
+                    / speed_multiplier,
+            )
+            .to_samples(sample_rate)
+            .0
+            .max(1) as usize;
+
+            frames = frames.min(frames_left);
+        }
+
+        ProcTransportInfo {
+            frames,
+            beats_per_minute: self.bpm_at_musical(playhead, speed_multiplier),
+        }
+    }
+
+    /// The slope of the ramp in beats per minute per beat, or `None` if the
+    /// ramp is instantaneous.
+    fn slope(&self) -> Option<f64> {
+        (self.ramp_duration.0 > 0.0).then(|| (self.end_bpm - self.start_bpm) / self.ramp_duration.0)
+    }
+
+    fn bpm_at_beats(&self, beats: f64) -> f64 {
+        if beats < self.ramp_start.0 {
+            return self.start_bpm;
+        }
+
+        match self.slope() {
+            Some(slope) if beats < self.ramp_start.0 + self.ramp_duration.0 => {
+                self.start_bpm + slope * (beats - self.ramp_start.0)
+            }
+            _ => self.end_bpm,
+        }
+    }
+
+    /// The number of seconds it takes to play the first `beats` beats of the
+    /// ramp (at a speed multiplier of `1.0`).
+    ///
+    /// The tempo is `bpm(b) = start_bpm + slope * b`, so the time is the
+    /// integral of `60 / bpm(b)`, which is `60 / slope * ln(bpm(b) / start_bpm)`.
+    fn seconds_in_ramp(&self, beats: f64, slope: f64) -> f64 {
+        if slope.abs() < MIN_SLOPE {
+            beats * 60.0 / self.start_bpm
+        } else {
+            60.0 / slope * (slope * beats / self.start_bpm).ln_1p()
+        }
+    }
+
+    /// The inverse of [`RampedTransport::seconds_in_ramp`].
+    fn beats_in_ramp(&self, seconds: f64, slope: f64) -> f64 {
+        if slope.abs() < MIN_SLOPE {
+            seconds * self.start_bpm / 60.0
+        } else {
+            self.start_bpm / slope * (slope * seconds / 60.0).exp_m1()
+        }
+    }
+
+    fn beats_to_seconds(&self, beats: f64) -> f64 {
+        let ramp_start = self.ramp_start.0;
+
+        if beats <= ramp_start {
+            return beats * 60.0 / self.start_bpm;
+        }
+
+        let ramp_start_seconds = ramp_start * 60.0 / self.start_bpm;
+
+        let (ramp_beats, ramp_seconds) = match self.slope() {
+            Some(slope) => {
+                let ramp_beats = (beats - ramp_start).min(self.ramp_duration.0);
+                (ramp_beats, self.seconds_in_ramp(ramp_beats, slope))
+            }
+            None => (0.0, 0.0),
+        };
+
+        ramp_start_seconds + ramp_seconds + (beats - ramp_start - ramp_beats) * 60.0 / self.end_bpm
+    }
+
+    fn seconds_to_beats(&self, seconds: f64) -> f64 {
+        let ramp_start_seconds = self.ramp_start.0 * 60.0 / self.start_bpm;
+
+        if seconds <= ramp_start_seconds {
+            return seconds * self.start_bpm / 60.0;
+        }
+
+        let (ramp_beats, ramp_seconds) = match self.slope() {
+            Some(slope) => {
+                let total_ramp_seconds = self.seconds_in_ramp(self.ramp_duration.0, slope);
+                let seconds_into_ramp = seconds - ramp_start_seconds;
+
+                if seconds_into_ramp < total_ramp_seconds {
+                    return self.ramp_start.0 + self.beats_in_ramp(seconds_into_ramp, slope);
+                }
+
+                (self.ramp_duration.0, total_ramp_seconds)
+            }
+            None => (0.0, 0.0),
+        };
+
+        self.ramp_start.0
+            + ramp_beats
+            + (seconds - ramp_start_seconds - ramp_seconds) * self.end_bpm / 60.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ramp_integrates_tempo() {
+        // 60 -> 120 bpm over 4 beats, starting at beat 2.
+        let transport =
+            RampedTransport::new(60.0, 120.0, InstantMusical(2.0), DurationMusical(4.0));
+
+        // Before the ramp, one beat is one second.
+        assert!((transport.beats_to_seconds(2.0) - 2.0).abs() < 1e-9);
+        // The ramp takes `60 / 15 * ln(2)` seconds.
+        let ramp_seconds = 4.0 * core::f64::consts::LN_2;
+        assert!((transport.beats_to_seconds(6.0) - (2.0 + ramp_seconds)).abs() < 1e-9);
+        // After the ramp, one beat is half a second.
+        assert!((transport.beats_to_seconds(8.0) - (3.0 + ramp_seconds)).abs() < 1e-9);
+
+        for beats in [0.0, 1.5, 2.0, 3.0, 5.999, 6.0, 10.0] {
+            let seconds = transport.beats_to_seconds(beats);
+            assert!((transport.seconds_to_beats(seconds) - beats).abs() < 1e-9);
+        }
+
+        assert_eq!(transport.bpm_at_musical(InstantMusical(4.0), 1.0), 90.0);
+        assert_eq!(transport.bpm_at_musical(InstantMusical(7.0), 2.0), 240.0);
+    }
+}
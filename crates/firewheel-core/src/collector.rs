@@ -275,6 +275,17 @@ impl<T: ?Sized + Send + Sync + 'static> ArcGc<T, GlobalRtGc> {
     }
 }
 
+impl<T: ?Sized + Send + Sync + 'static> ArcGc<T, GlobalRtGc> {
+    /// The number of [`ArcGc`] instances pointing to this allocation.
+    ///
+    /// This does not include the internal reference held by the
+    /// garbage collector.
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        Arc::strong_count(&this.data) - 1
+    }
+}
+
 impl ArcGc<dyn Any + Send + Sync + 'static, GlobalRtGc> {
     /// Construct a type-erased [`ArcGc`].
     ///
@@ -0,0 +1,136 @@
+use bevy_platform::sync::Arc;
+
+use crate::{
+    collector::ArcGc,
+    diff::{Diff, EventQueue, Patch, PatchError, PathBuilder, RealtimeClone},
+    event::ParamData,
+};
+
+/// A shared, immutable string that can be used as a parameter, such
+/// as a label or a file path.
+///
+/// The string is stored in an [`ArcGc`], so cloning it (and sending it
+/// to the audio thread) never allocates, and the allocation is only ever
+/// dropped on the collector thread.
+///
+/// Unlike a raw [`ArcGc`], diffing compares the contents of the string,
+/// so an event is only generated when the string actually changes.
+#[derive(Clone)]
+pub struct ArcStr(ArcGc<str>);
+
+impl ArcStr {
+    /// Construct a new [`ArcStr`] by copying the given string into a
+    /// new allocation.
+    pub fn new(s: &str) -> Self {
+        Self(ArcGc::new_unsized(|| Arc::<str>::from(s)))
+    }
+
+    /// Construct an [`ArcStr`] from an existing shared allocation.
+    pub fn from_arc_gc(s: ArcGc<str>) -> Self {
+        Self(s)
+    }
+
+    /// The shared allocation backing this string.
+    pub fn as_arc_gc(&self) -> &ArcGc<str> {
+        &self.0
+    }
+
+    /// Get the string as a `&str`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if both strings point to the same allocation.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        ArcGc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl Default for ArcStr {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl core::ops::Deref for ArcStr {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ArcStr {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for ArcStr {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<ArcGc<str>> for ArcStr {
+    fn from(value: ArcGc<str>) -> Self {
+        Self(value)
+    }
+}
+
+impl PartialEq for ArcStr {
+    fn eq(&self, other: &Self) -> bool {
+        Self::ptr_eq(self, other) || *self.0 == *other.0
+    }
+}
+
+impl Eq for ArcStr {}
+
+impl PartialEq<str> for ArcStr {
+    fn eq(&self, other: &str) -> bool {
+        *self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for ArcStr {
+    fn eq(&self, other: &&str) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl core::fmt::Debug for ArcStr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl core::fmt::Display for ArcStr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl RealtimeClone for ArcStr {}
+
+impl Diff for ArcStr {
+    fn diff<E: EventQueue>(&self, baseline: &Self, path: PathBuilder, event_queue: &mut E) {
+        if self != baseline {
+            event_queue.push_param(ParamData::String(self.0.clone()), path);
+        }
+    }
+}
+
+impl Patch for ArcStr {
+    type Patch = Self;
+
+    fn patch(data: &ParamData, _: &[u32]) -> Result<Self::Patch, PatchError> {
+        match data {
+            ParamData::String(s) => Ok(Self(s.clone())),
+            _ => Err(PatchError::InvalidData),
+        }
+    }
+
+    fn apply(&mut self, patch: Self::Patch) {
+        *self = patch;
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
     diff::{Notify, RealtimeClone, notify::NotifyID},
     dsp::volume::Volume,
     event::{NodeEventType, ParamData},
+    param::time::{Milliseconds, Seconds},
     vector::{Vec2, Vec3},
 };
 
@@ -148,6 +149,8 @@ primitive_diff!(InstantSamples, InstantSamples);
 primitive_diff!(DurationSamples, DurationSamples);
 primitive_diff!(InstantSeconds, InstantSeconds);
 primitive_diff!(DurationSeconds, DurationSeconds);
+primitive_diff!(Milliseconds, F32);
+primitive_diff!(Seconds, F64);
 
 #[cfg(feature = "musical_transport")]
 primitive_diff!(InstantMusical, InstantMusical);
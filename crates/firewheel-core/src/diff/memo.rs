@@ -31,6 +31,18 @@ impl<T: Diff + Clone> Memo<T> {
             .diff(&self.baseline, PathBuilder::default(), event_queue);
         self.baseline = self.value.clone();
     }
+
+    /// Modify the inner value without generating any events for the change.
+    ///
+    /// This is useful when the node already knows about the change by other
+    /// means, such as when a parameter was automated with
+    /// `FirewheelContext::schedule_automation`. Any changes made before
+    /// calling this which have not been sent yet are still sent on the next
+    /// call to [`Memo::update_memo`].
+    pub fn modify_without_diff(&mut self, f: impl Fn(&mut T)) {
+        f(&mut self.value);
+        f(&mut self.baseline);
+    }
 }
 
 impl<T> core::ops::Deref for Memo<T> {
@@ -213,11 +213,13 @@ use crate::{
 
 use smallvec::SmallVec;
 
+mod arc_str;
 mod collections;
 mod leaf;
 mod memo;
 mod notify;
 
+pub use arc_str::ArcStr;
 pub use memo::Memo;
 pub use notify::{Notify, NotifyID};
 
@@ -632,6 +634,81 @@ pub trait Patch {
     ///
     /// [`drain_patches`]: crate::event::ProcEvents::drain_patches
     fn apply(&mut self, patch: Self::Patch);
+
+    /// Construct and apply patches from a list of node events.
+    ///
+    /// Events besides [`NodeEventType::Param`] are skipped, and any
+    /// events that fail to produce a patch are silently discarded. Use
+    /// [`apply_list_report`][Patch::apply_list_report] to find out which
+    /// events failed.
+    fn apply_list<'a>(&mut self, events: impl IntoIterator<Item = &'a NodeEventType>)
+    where
+        Self: Sized,
+    {
+        for event in events {
+            if let Some(patch) = Self::patch_event(event) {
+                self.apply(patch);
+            }
+        }
+    }
+
+    /// Construct and apply patches from a list of node events, returning
+    /// the path and error of each event which could not be applied.
+    ///
+    /// All valid patches are still applied, so a single malformed event
+    /// won't abort the whole batch. Events besides [`NodeEventType::Param`]
+    /// are skipped and are not reported.
+    ///
+    /// This is intended for non-realtime contexts, such as syncing
+    /// parameters over a network, where it is useful to detect mismatched
+    /// versions of a producer and consumer.
+    ///
+    /// ```
+    /// # use firewheel_core::{diff::{Patch, PathBuilder, Diff}, event::*};
+    /// #[derive(Diff, Patch, Default, Clone)]
+    /// struct FilterParams {
+    ///     frequency: f32,
+    ///     quality: f32,
+    /// }
+    ///
+    /// let mut events = Vec::new();
+    /// events.push(NodeEventType::Param {
+    ///     data: ParamData::F32(440.0),
+    ///     path: PathBuilder::default().with(0).build(),
+    /// });
+    /// events.push(NodeEventType::Param {
+    ///     data: ParamData::Bool(true),
+    ///     path: PathBuilder::default().with(1).build(),
+    /// });
+    ///
+    /// let mut params = FilterParams::default();
+    /// let errors = params.apply_list_report(&events);
+    ///
+    /// assert_eq!(params.frequency, 440.0);
+    /// assert_eq!(errors.len(), 1);
+    /// ```
+    fn apply_list_report<'a>(
+        &mut self,
+        events: impl IntoIterator<Item = &'a NodeEventType>,
+    ) -> SmallVec<[(ParamPath, PatchError); 4]>
+    where
+        Self: Sized,
+    {
+        let mut errors = SmallVec::new();
+
+        for event in events {
+            let NodeEventType::Param { data, path } = event else {
+                continue;
+            };
+
+            match Self::patch(data, path) {
+                Ok(patch) => self.apply(patch),
+                Err(e) => errors.push((path.clone(), e)),
+            }
+        }
+
+        errors
+    }
 }
 
 /// A trait which signifies that a struct implements `Clone`, cloning
@@ -716,7 +793,7 @@ impl EventQueue for Vec<NodeEventType> {
 
 /// An error encountered when patching a type
 /// from [`ParamData`].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PatchError {
     /// The provided path does not match any children.
     InvalidPath,
@@ -758,6 +835,38 @@ mod test {
         assert_eq!(a, b);
     }
 
+    #[test]
+    fn test_apply_list_report() {
+        let events = [
+            NodeEventType::Param {
+                data: ParamData::F32(0.5),
+                path: PathBuilder::default().with(0).build(),
+            },
+            // Invalid data
+            NodeEventType::Param {
+                data: ParamData::F32(1.0),
+                path: PathBuilder::default().with(1).build(),
+            },
+            // Invalid path
+            NodeEventType::Param {
+                data: ParamData::Bool(true),
+                path: PathBuilder::default().with(7).build(),
+            },
+            NodeEventType::Param {
+                data: ParamData::Bool(true),
+                path: PathBuilder::default().with(1).build(),
+            },
+        ];
+
+        let mut value = StructDiff { a: 1.0, b: false };
+        let errors = value.apply_list_report(&events);
+
+        assert_eq!(value, StructDiff { a: 0.5, b: true });
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0], (ParamPath::Single(1), PatchError::InvalidData));
+        assert_eq!(errors[1], (ParamPath::Single(7), PatchError::InvalidPath));
+    }
+
     #[derive(Debug, Clone, Diff, Patch, PartialEq)]
     enum DiffingExample {
         Unit,
@@ -790,4 +899,34 @@ mod test {
         baseline.apply(DiffingExample::patch_event(&messages.pop().unwrap()).unwrap());
         assert_eq!(baseline, value);
     }
+
+    #[derive(Debug, Clone, Diff, Patch, PartialEq)]
+    struct StringDiff {
+        label: ArcStr,
+    }
+
+    #[test]
+    fn test_string_diff() {
+        let mut baseline = StringDiff {
+            label: ArcStr::new("foo"),
+        };
+
+        // Equal contents in a different allocation should not produce an event.
+        let value = StringDiff {
+            label: ArcStr::new("foo"),
+        };
+        let mut messages = Vec::new();
+        value.diff(&baseline, PathBuilder::default(), &mut messages);
+        assert!(messages.is_empty());
+
+        let value = StringDiff {
+            label: ArcStr::new("bar"),
+        };
+        value.diff(&baseline, PathBuilder::default(), &mut messages);
+        assert_eq!(messages.len(), 1);
+
+        baseline.apply(StringDiff::patch_event(&messages.pop().unwrap()).unwrap());
+        assert_eq!(baseline, value);
+        assert!(ArcStr::ptr_eq(&baseline.label, &value.label));
+    }
 }
@@ -114,6 +114,18 @@ impl<T> Notify<T> {
         }
     }
 
+    /// Construct a new [`Notify`] with a [`NotifyID::DANGLING`] ID.
+    ///
+    /// Unlike [`Notify::new`], this can be used in `const` contexts. Diffing
+    /// two dangling instances will not produce an event, but the instance
+    /// is assigned a valid ID as soon as it is mutated or notified.
+    pub const fn dangling(value: T) -> Self {
+        Self {
+            value,
+            id: NotifyID::DANGLING,
+        }
+    }
+
     pub(crate) fn from_raw(value: T, id: NotifyID) -> Self {
         Self { value, id }
     }
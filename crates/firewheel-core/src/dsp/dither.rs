@@ -0,0 +1,162 @@
+//! Dithering for converting floating point audio to integer PCM samples, i.e.
+//! when exporting to a 16-bit file.
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::{Vec, vec};
+
+const DEFAULT_SEED: u32 = 17;
+
+/// The type of dither applied by a [`Quantizer`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DitherMode {
+    /// No dither is applied, and samples are rounded to the nearest integer.
+    ///
+    /// This causes quantization distortion on quiet signals (i.e. fading
+    /// tails of reverbs).
+    None,
+    /// Triangular probability density function dither with an amplitude of
+    /// one least significant bit.
+    ///
+    /// This turns quantization distortion into a constant, signal-independent
+    /// noise floor.
+    #[default]
+    Tpdf,
+    /// TPDF dither where the quantization error is fed back to push the noise
+    /// floor towards higher frequencies, where the ear is less sensitive.
+    ///
+    /// This results in a lower perceived noise floor than
+    /// [`DitherMode::Tpdf`], at the cost of a slightly higher total amount of
+    /// noise.
+    NoiseShapedTpdf,
+}
+
+/// Converts interleaved floating point samples in the range `[-1.0, 1.0]` to
+/// integer samples, optionally applying dither.
+///
+/// The quantizer keeps state across calls (the state of the random number
+/// generator and the noise shaping filter), so use a single quantizer for
+/// the whole stream.
+pub struct Quantizer {
+    mode: DitherMode,
+    num_channels: usize,
+    rng_state: u32,
+    /// The quantization error of the previous sample in each channel, used
+    /// for noise shaping.
+    error: Vec<f32>,
+}
+
+impl Quantizer {
+    /// Create a new quantizer for interleaved audio with the given number of
+    /// channels.
+    pub fn new(mode: DitherMode, num_channels: usize) -> Self {
+        Self {
+            mode,
+            num_channels: num_channels.max(1),
+            rng_state: DEFAULT_SEED,
+            error: vec![0.0; num_channels.max(1)],
+        }
+    }
+
+    /// The type of dither applied by this quantizer.
+    pub fn mode(&self) -> DitherMode {
+        self.mode
+    }
+
+    /// Set the type of dither applied by this quantizer.
+    pub fn set_mode(&mut self, mode: DitherMode) {
+        self.mode = mode;
+        self.reset();
+    }
+
+    /// Reset the state of the noise shaping filter.
+    pub fn reset(&mut self) {
+        self.error.fill(0.0);
+    }
+
+    /// Convert interleaved samples to 16-bit integers.
+    ///
+    /// Only `input.len().min(output.len())` samples are converted.
+    pub fn quantize_i16(&mut self, input: &[f32], output: &mut [i16]) {
+        for (i, (s, out)) in input.iter().zip(output.iter_mut()).enumerate() {
+            *out = self.quantize(*s, i % self.num_channels, 16) as i16;
+        }
+    }
+
+    /// Convert interleaved samples to 24-bit integers, stored in the lower
+    /// 24 bits of an `i32`.
+    ///
+    /// Only `input.len().min(output.len())` samples are converted.
+    pub fn quantize_i24(&mut self, input: &[f32], output: &mut [i32]) {
+        for (i, (s, out)) in input.iter().zip(output.iter_mut()).enumerate() {
+            *out = self.quantize(*s, i % self.num_channels, 24);
+        }
+    }
+
+    #[inline]
+    fn quantize(&mut self, s: f32, channel: usize, bits: u32) -> i32 {
+        let max = ((1i32 << (bits - 1)) - 1) as f32;
+        let min = -((1i32 << (bits - 1)) as f32);
+
+        // Scale so that one least significant bit is `1.0`.
+        let mut s = s * -min;
+
+        if self.mode == DitherMode::NoiseShapedTpdf {
+            s -= self.error[channel];
+        }
+
+        let dither = match self.mode {
+            DitherMode::None => 0.0,
+            DitherMode::Tpdf | DitherMode::NoiseShapedTpdf => self.next_unit() + self.next_unit(),
+        };
+
+        let q = (s + dither).round().clamp(min, max);
+
+        if self.mode == DitherMode::NoiseShapedTpdf {
+            // Limit the error so that the feedback stays stable while
+            // clipping.
+            self.error[channel] = (q - s).clamp(-1.0, 1.0);
+        }
+
+        q as i32
+    }
+
+    /// Get a random value in the range `[-0.5, 0.5)`.
+    #[inline(always)]
+    fn next_unit(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+
+        (self.rng_state as f32 * (1.0 / 4_294_967_296.0)) - 0.5
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quantize_i16() {
+        let mut quantizer = Quantizer::new(DitherMode::None, 1);
+        let mut out = [0i16; 4];
+        quantizer.quantize_i16(&[0.0, 1.0, -1.0, 0.5], &mut out);
+        assert_eq!(out, [0, i16::MAX, i16::MIN, 16384]);
+
+        // TPDF dither spans +/-1 least significant bit. Noise shaping also
+        // feeds back up to 1 LSB of the previous error, so it can reach 2.
+        for (mode, max_lsb) in [(DitherMode::Tpdf, 1), (DitherMode::NoiseShapedTpdf, 2)] {
+            let mut quantizer = Quantizer::new(mode, 2);
+            let input = [0.0; 256];
+            let mut out = [0i16; 256];
+            quantizer.quantize_i16(&input, &mut out);
+
+            assert!(out.iter().all(|s| s.abs() <= max_lsb));
+            assert!(out.iter().any(|s| *s != 0));
+        }
+    }
+}
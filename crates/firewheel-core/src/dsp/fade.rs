@@ -23,17 +23,21 @@ pub enum FadeCurve {
     /// center.
     #[default]
     EqualPower3dB = 0,
+    /// A compromise between [`FadeCurve::EqualPower3dB`] and
+    /// [`FadeCurve::EqualPower6dB`], where each input will be at -4.5dB at
+    /// center.
+    EqualPower4_5dB = 4,
     /// Same as [`FadeCurve::EqualPower3dB`], but each input will be at -6dB
     /// at center which may be better for some signals.
-    EqualPower6dB,
+    EqualPower6dB = 1,
     /// This is cheaper to compute than [`FadeCurve::EqualPower3dB`], but is less
     /// accurate in its perception of constant volume.
-    SquareRoot,
+    SquareRoot = 2,
     /// The cheapest to compute, but is the least accurate in its perception of
     /// constant volume for some signals (though if the signals are highly
     /// correlated such as a wet/dry mix, then this mode may actually provide
     /// better results.)
-    Linear,
+    Linear = 3,
 }
 
 impl FadeCurve {
@@ -55,6 +59,13 @@ impl FadeCurve {
 
                     (fade_cos, fade_sin)
                 }
+                Self::EqualPower4_5dB => {
+                    let fade = FRAC_PI_2 * fade;
+                    let fade_cos = fade.cos();
+                    let fade_sin = fade.sin();
+
+                    (fade_cos * fade_cos.sqrt(), fade_sin * fade_sin.sqrt())
+                }
                 Self::EqualPower6dB => {
                     let fade = FRAC_PI_2 * fade;
                     let fade_cos = fade.cos();
@@ -88,6 +99,13 @@ impl FadeCurve {
 
                     (fade_cos, fade_sin)
                 }
+                Self::EqualPower4_5dB => {
+                    let fade = FRAC_PI_2 * fade;
+                    let fade_cos = fade.cos();
+                    let fade_sin = fade.sin();
+
+                    (fade_cos * fade_cos.sqrt(), fade_sin * fade_sin.sqrt())
+                }
                 Self::EqualPower6dB => {
                     let fade = FRAC_PI_2 * fade;
                     let fade_cos = fade.cos();
@@ -128,6 +146,7 @@ impl FadeCurve {
             1 => Self::EqualPower6dB,
             2 => Self::SquareRoot,
             3 => Self::Linear,
+            4 => Self::EqualPower4_5dB,
             _ => Self::EqualPower3dB,
         }
     }
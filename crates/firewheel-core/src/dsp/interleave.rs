@@ -0,0 +1,211 @@
+//! Helpers for converting between interleaved and de-interleaved buffers,
+//! useful for audio backends.
+
+/// Interleave the given de-interleaved channels into `interleaved`.
+///
+/// The number of frames is `interleaved.len() / num_interleaved_channels`.
+///
+/// If there are more channels in `channels` than `num_interleaved_channels`,
+/// then the extra channels are ignored. If there are fewer, then the extra
+/// interleaved channels are filled with zeros.
+///
+/// # Panics
+/// Panics if `num_interleaved_channels` is `0`, or if any of the channels in
+/// `channels` that are used is shorter than the number of frames.
+pub fn interleave<V: AsRef<[f32]>>(
+    channels: &[V],
+    interleaved: &mut [f32],
+    num_interleaved_channels: usize,
+) {
+    assert_ne!(num_interleaved_channels, 0);
+
+    let frames = interleaved.len() / num_interleaved_channels;
+    let interleaved = &mut interleaved[..frames * num_interleaved_channels];
+
+    // Provide optimized loops for the most common layouts.
+    match (num_interleaved_channels, channels.len()) {
+        (1, 1..) => {
+            interleaved.copy_from_slice(&channels[0].as_ref()[..frames]);
+        }
+        (2, 2..) => {
+            let ch0 = &channels[0].as_ref()[..frames];
+            let ch1 = &channels[1].as_ref()[..frames];
+
+            for (dst, (&s0, &s1)) in interleaved
+                .chunks_exact_mut(2)
+                .zip(ch0.iter().zip(ch1.iter()))
+            {
+                dst[0] = s0;
+                dst[1] = s1;
+            }
+        }
+        _ => {
+            for (ch_i, ch) in channels.iter().enumerate().take(num_interleaved_channels) {
+                let ch = &ch.as_ref()[..frames];
+
+                for (dst, &s) in interleaved
+                    .chunks_exact_mut(num_interleaved_channels)
+                    .zip(ch.iter())
+                {
+                    dst[ch_i] = s;
+                }
+            }
+
+            if channels.len() < num_interleaved_channels {
+                for dst in interleaved.chunks_exact_mut(num_interleaved_channels) {
+                    dst[channels.len()..].fill(0.0);
+                }
+            }
+        }
+    }
+}
+
+/// De-interleave `interleaved` into the given channels.
+///
+/// The number of frames is `interleaved.len() / num_interleaved_channels`.
+///
+/// If there are more channels in `channels` than `num_interleaved_channels`,
+/// then the extra channels are filled with zeros. If there are fewer, then
+/// the extra interleaved channels are ignored.
+///
+/// # Panics
+/// Panics if `num_interleaved_channels` is `0`, or if any of the channels in
+/// `channels` is shorter than the number of frames.
+pub fn deinterleave<V: AsMut<[f32]>>(
+    interleaved: &[f32],
+    num_interleaved_channels: usize,
+    channels: &mut [V],
+) {
+    assert_ne!(num_interleaved_channels, 0);
+
+    let frames = interleaved.len() / num_interleaved_channels;
+    let interleaved = &interleaved[..frames * num_interleaved_channels];
+
+    // Provide optimized loops for the most common layouts.
+    match (num_interleaved_channels, channels.len()) {
+        (_, 0) => {}
+        (1, _) => {
+            channels[0].as_mut()[..frames].copy_from_slice(interleaved);
+        }
+        (2, 2..) => {
+            let (ch0, rest) = channels.split_first_mut().unwrap();
+            let ch0 = &mut ch0.as_mut()[..frames];
+            let ch1 = &mut rest[0].as_mut()[..frames];
+
+            for (src, (s0, s1)) in interleaved
+                .chunks_exact(2)
+                .zip(ch0.iter_mut().zip(ch1.iter_mut()))
+            {
+                *s0 = src[0];
+                *s1 = src[1];
+            }
+        }
+        _ => {
+            for (ch_i, ch) in channels
+                .iter_mut()
+                .enumerate()
+                .take(num_interleaved_channels)
+            {
+                let ch = &mut ch.as_mut()[..frames];
+
+                for (src, s) in interleaved
+                    .chunks_exact(num_interleaved_channels)
+                    .zip(ch.iter_mut())
+                {
+                    *s = src[ch_i];
+                }
+            }
+        }
+    }
+
+    for ch in channels.iter_mut().skip(num_interleaved_channels) {
+        ch.as_mut()[..frames].fill(0.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_channels(num_channels: usize, frames: usize) -> Vec<Vec<f32>> {
+        (0..num_channels)
+            .map(|ch_i| {
+                (0..frames)
+                    .map(|i| (ch_i * 1000 + i) as f32)
+                    .collect::<Vec<f32>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        const FRAMES: usize = 37;
+
+        for num_channels in 1..=5 {
+            let channels = test_channels(num_channels, FRAMES);
+
+            let mut interleaved = vec![0.0; FRAMES * num_channels];
+            interleave(&channels, &mut interleaved, num_channels);
+
+            for (i, frame) in interleaved.chunks_exact(num_channels).enumerate() {
+                for (ch_i, &s) in frame.iter().enumerate() {
+                    assert_eq!(s, channels[ch_i][i]);
+                }
+            }
+
+            let mut out_channels = vec![vec![-1.0; FRAMES]; num_channels];
+            deinterleave(&interleaved, num_channels, &mut out_channels);
+
+            assert_eq!(out_channels, channels);
+        }
+    }
+
+    #[test]
+    fn channel_count_mismatch() {
+        const FRAMES: usize = 8;
+
+        for num_channels in 1..=3 {
+            let channels = test_channels(num_channels, FRAMES);
+
+            // More interleaved channels than source channels.
+            let num_interleaved_channels = num_channels + 1;
+            let mut interleaved = vec![-1.0; FRAMES * num_interleaved_channels];
+            interleave(&channels, &mut interleaved, num_interleaved_channels);
+
+            for (i, frame) in interleaved
+                .chunks_exact(num_interleaved_channels)
+                .enumerate()
+            {
+                for ch_i in 0..num_channels {
+                    assert_eq!(frame[ch_i], channels[ch_i][i]);
+                }
+                assert_eq!(frame[num_channels], 0.0);
+            }
+
+            // More destination channels than interleaved channels.
+            let mut out_channels = vec![vec![-1.0; FRAMES]; num_interleaved_channels + 1];
+            deinterleave(&interleaved, num_interleaved_channels, &mut out_channels);
+
+            assert_eq!(&out_channels[..num_channels], &channels[..]);
+            assert!(
+                out_channels[num_channels..]
+                    .iter()
+                    .flatten()
+                    .all(|&s| s == 0.0)
+            );
+
+            // Fewer interleaved channels than source channels.
+            if num_channels > 1 {
+                let mut interleaved = vec![-1.0; FRAMES];
+                interleave(&channels, &mut interleaved, 1);
+                assert_eq!(interleaved, channels[0]);
+
+                let mut out_channels = vec![vec![-1.0; FRAMES]; 1];
+                let mut interleaved = vec![0.0; FRAMES * num_channels];
+                interleave(&channels, &mut interleaved, num_channels);
+                deinterleave(&interleaved, num_channels, &mut out_channels);
+                assert_eq!(out_channels[0], channels[0]);
+            }
+        }
+    }
+}
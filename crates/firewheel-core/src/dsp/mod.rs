@@ -2,8 +2,10 @@ pub mod algo;
 pub mod buffer;
 pub mod coeff_update;
 pub mod declick;
+pub mod dither;
 pub mod distance_attenuation;
 pub mod fade;
 pub mod filter;
+pub mod interleave;
 pub mod mix;
 pub mod volume;
@@ -127,6 +127,60 @@ impl Volume {
     pub fn as_decibel_variant(&self) -> Self {
         Self::Decibels(self.decibels())
     }
+
+    /// Construct a [`Volume`] value from a normalized UI value (i.e. the
+    /// position of a volume slider), where `0.0` is silence and `1.0` is unity
+    /// gain, mapped through the given perceptual curve.
+    ///
+    /// The returned value is a [`Volume::Linear`] value. Use
+    /// [`Volume::as_decibel_variant`] to convert it to decibels.
+    pub fn from_perceptual(value: f32, curve: PerceptualCurve) -> Self {
+        Self::Linear(amp_to_linear_volume_clamped(
+            perceptual_to_amp(value, curve),
+            0.0,
+        ))
+    }
+
+    /// Get the volume as a normalized UI value (i.e. the position of a volume
+    /// slider), where `0.0` is silence and `1.0` is unity gain, mapped through
+    /// the given perceptual curve.
+    ///
+    /// This is the inverse of [`Volume::from_perceptual`].
+    pub fn perceptual(&self, curve: PerceptualCurve) -> f32 {
+        amp_to_perceptual(self.amp(), curve)
+    }
+}
+
+/// A curve which maps a normalized UI value (i.e. the position of a volume
+/// slider) to raw amplitude in a way that sounds natural to the listener.
+///
+/// The amplitude is the UI value raised to the power of the curve's
+/// [exponent](PerceptualCurve::exponent). Higher exponents give more
+/// resolution near silence.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PerceptualCurve {
+    /// `amp = x^2`
+    ///
+    /// This is the same curve used by [`Volume::Linear`].
+    Quadratic,
+    /// `amp = x^3`
+    #[default]
+    Cubic,
+    /// `amp = x^4`
+    Quartic,
+}
+
+impl PerceptualCurve {
+    /// The exponent applied to the UI value to get the raw amplitude.
+    pub const fn exponent(&self) -> f32 {
+        match self {
+            Self::Quadratic => 2.0,
+            Self::Cubic => 3.0,
+            Self::Quartic => 4.0,
+        }
+    }
 }
 
 impl Default for Volume {
@@ -298,6 +352,30 @@ pub fn amp_to_linear_volume_clamped(amp: f32, min_amp: f32) -> f32 {
     if amp <= min_amp { 0.0 } else { amp.sqrt() }
 }
 
+/// Map the normalized UI value (where `0.0` means mute and `1.0` means unity
+/// gain) through the given perceptual curve to the corresponding raw
+/// amplitude value (not decibels) for use in DSP. Values above `1.0` are
+/// allowed.
+#[inline]
+pub fn perceptual_to_amp(value: f32, curve: PerceptualCurve) -> f32 {
+    if value <= 0.0 {
+        0.0
+    } else {
+        value.powf(curve.exponent())
+    }
+}
+
+/// Map the raw amplitude (where `0.0` means mute and `1.0` means unity gain)
+/// to the corresponding normalized UI value using the given perceptual curve.
+#[inline]
+pub fn amp_to_perceptual(amp: f32, curve: PerceptualCurve) -> f32 {
+    if amp <= 0.0 {
+        0.0
+    } else {
+        amp.powf(curve.exponent().recip())
+    }
+}
+
 /// A struct that converts a value in decibels to a normalized range used in
 /// meters.
 #[derive(Debug, Clone, Copy, PartialEq)]
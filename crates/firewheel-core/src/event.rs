@@ -6,9 +6,13 @@ use bevy_platform::prelude::{Box, Vec};
 use crate::{
     clock::{DurationSamples, DurationSeconds, InstantSamples, InstantSeconds},
     collector::{ArcGc, OwnedGc},
-    diff::{Notify, ParamPath},
+    diff::{ArcStr, Notify, ParamPath},
     dsp::volume::Volume,
     node::NodeID,
+    param::{
+        automation::Automation,
+        time::{Milliseconds, Seconds},
+    },
     vector::{Vec2, Vec3},
 };
 
@@ -75,12 +79,58 @@ pub enum NodeEventType {
         /// The path to the parameter.
         path: ParamPath,
     },
+    /// Make a parameter follow an automation curve, starting at the time
+    /// this event is received.
+    ///
+    /// Only nodes which support automation respond to this event (see
+    /// [`ProcEvents::drain_patches_with_automation`]). Other nodes ignore it.
+    ///
+    /// Prefer to use `FirewheelContext::schedule_automation` instead.
+    Automation {
+        /// The automation curve.
+        automation: Automation,
+        /// The path to the parameter.
+        path: ParamPath,
+    },
     /// Set the bypass state of the node.
     SetBypassed(bool),
+    /// Set the mute state of the node.
+    ///
+    /// A muted node is still processed, but its outputs are faded to silence.
+    SetMuted(bool),
+    /// Clear the internal state of the node (i.e. filter histories and delay
+    /// lines).
+    ///
+    /// This is handled by the engine instead of the node. The engine calls
+    /// [`AudioNodeProcessor::reset_state`] right before the node receives
+    /// the rest of the events in the same processing block.
+    ///
+    /// Prefer to use `FirewheelContext::reset_node` instead.
+    ///
+    /// [`AudioNodeProcessor::reset_state`]: crate::node::AudioNodeProcessor::reset_state
+    Reset,
     /// Custom event type stored on the heap.
     Custom(OwnedGc<Box<dyn Any + Send + 'static>>),
     /// Custom event type stored on the stack as raw bytes.
     CustomBytes([u8; 36]),
+    /// Request a snapshot of the node's current state.
+    ///
+    /// This is handled by the engine instead of the node. As soon as the
+    /// event is received by the audio thread, the engine calls
+    /// [`AudioNodeProcessor::snapshot_state`] with the preallocated
+    /// `snapshot`, and then sends the snapshot back to the context. Any
+    /// scheduled time on this event is ignored.
+    ///
+    /// Prefer to use `FirewheelContext::request_state` instead.
+    ///
+    /// [`AudioNodeProcessor::snapshot_state`]: crate::node::AudioNodeProcessor::snapshot_state
+    RequestState {
+        /// The preallocated snapshot to fill in.
+        snapshot: OwnedGc<Box<dyn Any + Send + 'static>>,
+        /// Set to `true` once the node's processor has filled in the
+        /// snapshot.
+        filled: bool,
+    },
     #[cfg(feature = "midi_events")]
     MIDI(MidiMessage<'static>),
 }
@@ -163,9 +213,20 @@ impl core::fmt::Debug for NodeEventType {
                 .field("data", &data)
                 .field("path", &path)
                 .finish(),
+            NodeEventType::Automation { automation, path } => f
+                .debug_struct("Automation")
+                .field("automation", &automation)
+                .field("path", &path)
+                .finish(),
             NodeEventType::Custom(_) => f.debug_tuple("Custom").finish_non_exhaustive(),
             NodeEventType::CustomBytes(f0) => f.debug_tuple("CustomBytes").field(&f0).finish(),
             NodeEventType::SetBypassed(b) => f.debug_tuple("SetBypassed").field(&b).finish(),
+            NodeEventType::SetMuted(b) => f.debug_tuple("SetMuted").field(&b).finish(),
+            NodeEventType::Reset => f.write_str("Reset"),
+            NodeEventType::RequestState { filled, .. } => f
+                .debug_struct("RequestState")
+                .field("filled", &filled)
+                .finish_non_exhaustive(),
             #[cfg(feature = "midi_events")]
             NodeEventType::MIDI(f0) => f.debug_tuple("MIDI").field(&f0).finish(),
         }
@@ -198,6 +259,11 @@ pub enum ParamData {
     #[cfg(feature = "musical_transport")]
     DurationMusical(DurationMusical),
 
+    /// A shared, immutable string such as a label or a file path.
+    ///
+    /// See [`ArcStr`].
+    String(ArcGc<str>),
+
     /// Custom type stored on the heap.
     Any(ArcGc<dyn Any + Send + Sync>),
 
@@ -223,6 +289,11 @@ impl ParamData {
         }
     }
 
+    /// Construct a [`ParamData::String`] variant.
+    pub fn string(s: &str) -> Self {
+        Self::String(ArcStr::new(s).as_arc_gc().clone())
+    }
+
     /// Try to downcast [`ParamData::Any`] into `T`.
     ///
     /// If this enum doesn't hold [`ParamData::Any`] or the downcast fails,
@@ -235,6 +306,12 @@ impl ParamData {
     }
 }
 
+impl From<ArcStr> for ParamData {
+    fn from(value: ArcStr) -> Self {
+        Self::String(value.as_arc_gc().clone())
+    }
+}
+
 macro_rules! param_data_from {
     ($ty:ty, $variant:ident) => {
         impl From<$ty> for ParamData {
@@ -311,6 +388,8 @@ param_data_from!(InstantSeconds, InstantSeconds);
 param_data_from!(DurationSeconds, DurationSeconds);
 param_data_from!(InstantSamples, InstantSamples);
 param_data_from!(DurationSamples, DurationSamples);
+param_data_from!(Milliseconds, F32);
+param_data_from!(Seconds, F64);
 #[cfg(feature = "musical_transport")]
 param_data_from!(InstantMusical, InstantMusical);
 #[cfg(feature = "musical_transport")]
@@ -484,6 +563,59 @@ impl<'a> ProcEvents<'a> {
         self.drain().into_iter().filter_map(|e| T::patch_event(&e))
     }
 
+    /// Iterate over patches for `T` along with any automation curves for them,
+    /// draining the events from the list.
+    ///
+    /// The iterator returns `(patch, Option<automation>)`. For an
+    /// [`NodeEventType::Automation`] event, `patch` holds the end value of
+    /// the automation, and `automation` holds the curve to follow (i.e. with
+    /// [`SmoothedParam::start_automation`]). Otherwise `automation` is `None`.
+    ///
+    /// Only `f32` parameters and [`Volume`] parameters can be automated. The
+    /// values of an automation for a [`Volume`] parameter are in decibels.
+    ///
+    /// ```
+    /// # use core::num::NonZeroU32;
+    /// # use firewheel_core::{diff::*, event::ProcEvents, param::{automation::AutomationScale, smoother::SmoothedParam}};
+    /// # fn for_each_example(mut event_list: ProcEvents, cutoff: &mut SmoothedParam, sample_rate: NonZeroU32) {
+    /// #[derive(Patch, Default)]
+    /// struct FilterNode {
+    ///     frequency: f32,
+    /// }
+    ///
+    /// for (patch, automation) in event_list.drain_patches_with_automation::<FilterNode>() {
+    ///     match (patch, automation) {
+    ///         (FilterNodePatch::Frequency(_), Some(automation)) => {
+    ///             cutoff.start_automation(automation, AutomationScale::Linear, sample_rate);
+    ///         }
+    ///         (FilterNodePatch::Frequency(frequency), None) => {
+    ///             cutoff.set_value(frequency);
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Errors produced while constructing patches are simply skipped.
+    ///
+    /// [`SmoothedParam::start_automation`]: crate::param::smoother::SmoothedParam::start_automation
+    pub fn drain_patches_with_automation<'b, T: crate::diff::Patch>(
+        &'b mut self,
+    ) -> impl IntoIterator<Item = (<T as crate::diff::Patch>::Patch, Option<Automation>)> + use<'b, T>
+    {
+        self.drain().into_iter().filter_map(|e| match &e {
+            NodeEventType::Automation { automation, path } => {
+                T::patch(&ParamData::F32(automation.to), path)
+                    .or_else(|_| {
+                        T::patch(&ParamData::Volume(Volume::Decibels(automation.to)), path)
+                    })
+                    .ok()
+                    .map(|patch| (patch, Some(*automation)))
+            }
+            _ => T::patch_event(&e).map(|patch| (patch, None)),
+        })
+    }
+
     /// Iterate over patches for `T`, draining the events from the list, while also
     /// returning the timestamp the event was scheduled for.
     ///
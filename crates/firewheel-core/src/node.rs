@@ -14,7 +14,7 @@ use bevy_platform::collections::hash_map::{Entry, HashMap};
 #[cfg(not(feature = "std"))]
 use bevy_platform::prelude::{Box, Vec};
 
-use crate::dsp::buffer::ConstSequentialBuffer;
+use crate::dsp::buffer::{ConstSequentialBuffer, SequentialBuffer};
 use crate::dsp::volume::is_buffer_silent;
 use crate::log::RealtimeLogger;
 use crate::mask::{ConnectedMask, ConstantMask, MaskType, SilenceMask};
@@ -22,6 +22,7 @@ use crate::{
     StreamInfo,
     channel_config::{ChannelConfig, ChannelCount},
     clock::{DurationSamples, InstantSamples, InstantSeconds},
+    diff::Notify,
     dsp::declick::DeclickValues,
     event::{NodeEvent, NodeEventType, ProcEvents},
 };
@@ -30,7 +31,7 @@ use crate::{
 use crate::clock::EventInstant;
 
 #[cfg(feature = "musical_transport")]
-use crate::clock::{InstantMusical, MusicalTransport};
+use crate::clock::{InstantMusical, MusicalPosition, MusicalTransport};
 
 /// A globally unique identifier for a node.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -91,6 +92,7 @@ pub struct AudioNodeInfo {
     custom_state: Option<Box<dyn Any>>,
     latency_frames: u32,
     in_place_buffers: bool,
+    continue_processing_while_silent: bool,
 }
 
 impl AudioNodeInfo {
@@ -106,6 +108,7 @@ impl AudioNodeInfo {
             custom_state: None,
             latency_frames: 0,
             in_place_buffers: false,
+            continue_processing_while_silent: false,
         }
     }
 
@@ -160,6 +163,9 @@ impl AudioNodeInfo {
 
     /// Set the latency of this node in frames (samples in a single channel of audio).
     ///
+    /// This is used by the graph compiler to calculate the total latency of each
+    /// path to the graph output node.
+    ///
     /// By default this is set to `0`.
     pub const fn latency_frames(mut self, latency_frames: u32) -> Self {
         self.latency_frames = latency_frames;
@@ -182,6 +188,23 @@ impl AudioNodeInfo {
         self.in_place_buffers = in_place_buffers;
         self
     }
+
+    /// If set to `true`, then the processor will never skip calling
+    /// [`AudioNodeProcessor::process`] on this node because its inputs are
+    /// silent, even if [`AudioNodeProcessor::tail_frames`] reports that its
+    /// tail has elapsed.
+    ///
+    /// Set this for nodes that keep producing output regardless of their
+    /// inputs, or whose tail cannot be predicted ahead of time.
+    ///
+    /// By default this is set to `false`.
+    pub const fn continue_processing_while_silent(
+        mut self,
+        continue_processing_while_silent: bool,
+    ) -> Self {
+        self.continue_processing_while_silent = continue_processing_while_silent;
+        self
+    }
 }
 
 impl Default for AudioNodeInfo {
@@ -199,6 +222,7 @@ impl From<AudioNodeInfo> for AudioNodeInfoInner {
             custom_state: value.custom_state,
             latency_frames: value.latency_frames,
             in_place_buffers: value.in_place_buffers,
+            continue_processing_while_silent: value.continue_processing_while_silent,
         }
     }
 }
@@ -212,6 +236,7 @@ pub struct AudioNodeInfoInner {
     pub custom_state: Option<Box<dyn Any>>,
     pub latency_frames: u32,
     pub in_place_buffers: bool,
+    pub continue_processing_while_silent: bool,
 }
 
 /// A trait representing a node in a Firewheel audio graph.
@@ -299,6 +324,50 @@ pub trait AudioNode {
     }
 }
 
+/// An [`AudioNode`] with internal state (i.e. filter state, a delay line, or
+/// a reverb tail) which can be flushed to silence without reconstructing its
+/// processor.
+///
+/// This is useful for avoiding stale tails when the context of a sound
+/// changes abruptly, such as when the listener teleports or the scene
+/// changes.
+///
+/// Implementors expose a `reset` [`Notify`] parameter. Calling
+/// [`ResettableNode::reset`] and then diffing the node sends an event which
+/// causes the processor to clear its internal state.
+///
+/// Any node can also be reset with `FirewheelContext::reset_node`, as long
+/// as its processor implements [`AudioNodeProcessor::reset_state`].
+pub trait ResettableNode: AudioNode {
+    /// The parameter which triggers a reset when it is notified.
+    fn reset_notify(&mut self) -> &mut Notify<()>;
+
+    /// Request that the processor clears its internal state.
+    ///
+    /// This takes effect the next time this node is diffed.
+    fn reset(&mut self) {
+        self.reset_notify().notify();
+    }
+}
+
+/// An [`AudioNode`] which is played with notes, such as a single voice of a
+/// polyphonic instrument.
+///
+/// Calling these methods only modifies the parameters of the node. The
+/// changes take effect the next time this node is diffed.
+pub trait VoiceNode: AudioNode {
+    /// Start playing the given note, retriggering the voice if it is already
+    /// playing.
+    ///
+    /// * `note` - The MIDI note number in the range `[0, 127]`, where `60` is
+    ///   middle C.
+    /// * `velocity` - The velocity of the note in the range `[0.0, 1.0]`.
+    fn note_on(&mut self, note: u8, velocity: f32);
+
+    /// Release the currently playing note.
+    fn note_off(&mut self);
+}
+
 /// A context for [`AudioNode::construct_processor`].
 pub struct ConstructProcessorContext<'a> {
     /// The ID of this audio node.
@@ -516,6 +585,20 @@ pub trait AudioNodeProcessor: 'static + Send {
         let _ = bypassed;
     }
 
+    /// Clear the internal state of this node (i.e. filter histories, delay
+    /// lines, and reverb tails), requested with
+    /// `FirewheelContext::reset_node`.
+    ///
+    /// This is called right before [`AudioNodeProcessor::events`] in the
+    /// same processing block, so any parameters sent along with the reset
+    /// are applied afterwards.
+    ///
+    /// This is always called in a realtime thread, so do not perform any
+    /// realtime-unsafe operations.
+    ///
+    /// By default this does nothing.
+    fn reset_state(&mut self) {}
+
     /// Process the given block of audio.
     ///
     /// * `info` - Information about this processing block.
@@ -544,6 +627,70 @@ pub trait AudioNodeProcessor: 'static + Send {
         ProcessStatus::Bypass
     }
 
+    /// The number of frames this node keeps producing output after all of its
+    /// inputs go silent (i.e. the length of a reverb tail), or `None` if the
+    /// length is unknown or infinite.
+    ///
+    /// Once all inputs of this node have been silent for longer than its
+    /// tail, the processor stops calling [`AudioNodeProcessor::process`] and
+    /// treats the outputs as silent until new input or events arrive. This
+    /// lets idle chains of effects sleep. Nodes which produce sound without
+    /// any input (i.e. oscillators) should return `None`, or opt out entirely
+    /// with [`AudioNodeInfo::continue_processing_while_silent`].
+    ///
+    /// This is always called in a realtime thread, so do not perform any
+    /// realtime-unsafe operations.
+    ///
+    /// By default this returns `None`.
+    fn tail_frames(&self) -> Option<u64> {
+        None
+    }
+
+    /// Return `true` if this node currently has nothing to process (i.e. a
+    /// sampler which has finished playing).
+    ///
+    /// While this returns `true`, the node has no pending events, and all of
+    /// its inputs are silent (or it has no inputs), the processor skips
+    /// calling [`AudioNodeProcessor::process`] and treats the outputs as
+    /// silent. The node is woken up again as soon as an event is queued for
+    /// it or one of its inputs becomes non-silent.
+    ///
+    /// Unlike [`AudioNodeProcessor::tail_frames`], this also applies to nodes
+    /// which produce sound without any input, and to nodes which use
+    /// [`AudioNodeInfo::continue_processing_while_silent`].
+    ///
+    /// This is always called in a realtime thread, so do not perform any
+    /// realtime-unsafe operations.
+    ///
+    /// By default this returns `false`.
+    fn is_idle(&self) -> bool {
+        false
+    }
+
+    /// Fill in a snapshot of this processor's current state, requested with
+    /// `FirewheelContext::request_state`.
+    ///
+    /// The snapshot is preallocated on the main thread, so downcast it to the
+    /// type the node documents and write into it (i.e. a filter could report
+    /// its current smoothed cutoff frequency). This is useful for reporting
+    /// state which does not warrant dedicated shared atomics.
+    ///
+    /// To support reading back the current value of parameters with
+    /// `FirewheelContext::request_param_value`, fill in a
+    /// [`ParamValueSnapshot`](crate::param::snapshot::ParamValueSnapshot).
+    ///
+    /// Return `true` if the snapshot was filled in, or `false` if it was not
+    /// the expected type.
+    ///
+    /// This is always called in a realtime thread, so do not perform any
+    /// realtime-unsafe operations.
+    ///
+    /// By default this returns `false`.
+    fn snapshot_state(&self, snapshot: &mut dyn Any) -> bool {
+        let _ = snapshot;
+        false
+    }
+
     /// Called when the audio stream has been stopped.
     ///
     /// This may or may not be called in a realtime thread, so prefer not
@@ -570,6 +717,9 @@ impl AudioNodeProcessor for Box<dyn AudioNodeProcessor> {
     fn bypassed(&mut self, bypassed: bool) {
         self.as_mut().bypassed(bypassed);
     }
+    fn reset_state(&mut self) {
+        self.as_mut().reset_state();
+    }
     fn process(
         &mut self,
         info: &ProcInfo,
@@ -578,6 +728,15 @@ impl AudioNodeProcessor for Box<dyn AudioNodeProcessor> {
     ) -> ProcessStatus {
         self.as_mut().process(info, buffers, extra)
     }
+    fn tail_frames(&self) -> Option<u64> {
+        self.as_ref().tail_frames()
+    }
+    fn is_idle(&self) -> bool {
+        self.as_ref().is_idle()
+    }
+    fn snapshot_state(&self, snapshot: &mut dyn Any) -> bool {
+        self.as_ref().snapshot_state(snapshot)
+    }
     fn stream_stopped(&mut self, context: &mut ProcStreamCtx) {
         self.as_mut().stream_stopped(context)
     }
@@ -648,6 +807,22 @@ impl<'a, 'b> ProcBuffers<'a, 'b> {
             ProcessStatus::OutputsModified
         }
     }
+
+    /// The status to return after processing an effect with an internal tail
+    /// (i.e. a reverb, a delay, or a resonant filter).
+    ///
+    /// If the first `num_inputs` input channels are silent, then the output
+    /// buffers are checked for silence with
+    /// [`ProcBuffers::check_for_silence_on_outputs`], so that the node gets
+    /// skipped by [`ProcInfo::can_skip_effect`] once its tail has decayed.
+    /// Otherwise, [`ProcessStatus::OutputsModified`] is returned.
+    pub fn tail_status(&self, info: &ProcInfo, num_inputs: usize, min_amp: f32) -> ProcessStatus {
+        if info.in_silence_mask.all_channels_silent(num_inputs) {
+            self.check_for_silence_on_outputs(min_amp)
+        } else {
+            ProcessStatus::OutputsModified
+        }
+    }
 }
 
 /// Extra buffers and utilities for [`AudioNodeProcessor::process`]
@@ -659,6 +834,17 @@ pub struct ProcExtra {
     /// data.
     pub scratch_buffers: ConstSequentialBuffer<f32, NUM_SCRATCH_BUFFERS>,
 
+    /// Additional scratch buffers for nodes which need more than
+    /// [`NUM_SCRATCH_BUFFERS`] scratch buffers (i.e. a many-voice unison
+    /// oscillator or a multi-band equalizer).
+    ///
+    /// The number of buffers is configured with
+    /// `FirewheelConfig::num_extra_scratch_buffers`. If that is set to `0`,
+    /// then this will be `None`. Each buffer has a length of
+    /// [`StreamInfo::max_block_frames`], and they are shared across all nodes,
+    /// so assume that they contain junk data.
+    pub extra_scratch_buffers: Option<SequentialBuffer<f32>>,
+
     /// A buffer of values that linearly ramp up/down between `0.0` and `1.0`
     /// which can be used to implement efficient declicking when
     /// pausing/resuming/stopping.
@@ -672,7 +858,7 @@ pub struct ProcExtra {
 }
 
 /// Information for [`AudioNodeProcessor::process`]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ProcInfo {
     /// The number of frames (samples in a single channel of audio) in
     /// this processing block.
@@ -786,6 +972,20 @@ pub struct ProcInfo {
 }
 
 impl ProcInfo {
+    /// A fast path for effect nodes. Returns `true` if the first `num_inputs`
+    /// input channels are silent and the node can return
+    /// [`ProcessStatus::ClearAllOutputs`] without processing.
+    ///
+    /// * `has_tail` - Set this to `true` if the effect keeps producing output
+    ///   after its input goes silent (i.e. a reverb, a delay, or a resonant
+    ///   filter). In that case, the node is only skipped once its output from
+    ///   the previous block was also silent. Use [`ProcBuffers::tail_status`]
+    ///   to report when the tail has decayed.
+    pub fn can_skip_effect(&self, num_inputs: usize, has_tail: bool) -> bool {
+        self.in_silence_mask.all_channels_silent(num_inputs)
+            && (!has_tail || self.prev_output_was_silent)
+    }
+
     /// The current time of the audio clock at the first frame in this
     /// processing block, equal to the total number of seconds of data that
     /// have been processed since this Firewheel context was first started.
@@ -876,6 +1076,19 @@ impl ProcInfo {
             .unwrap_or(false)
     }
 
+    /// Get the position of the transport in bars, beats, and ticks at the
+    /// first frame of this processing block.
+    ///
+    /// Unlike [`ProcInfo::playhead`], this also returns the position while the
+    /// transport is paused. Use [`ProcInfo::transport_is_playing`] to check
+    /// whether or not the transport is playing.
+    ///
+    /// If there is no active transport, then this will return `None`.
+    #[cfg(feature = "musical_transport")]
+    pub fn musical_position(&self) -> Option<MusicalPosition> {
+        self.transport_info.as_ref().map(|t| t.position())
+    }
+
     /// Converts the given musical time to the corresponding time in samples.
     ///
     /// If there is no musical transport or the transport is not currently playing,
@@ -918,6 +1131,15 @@ pub struct TransportInfo {
     /// means no change in speed, a value less than `1.0` means a decrease in
     /// speed, and a value greater than `1.0` means an increase in speed.
     pub speed_multiplier: f64,
+
+    /// The playhead of the transport at the first frame of this process block.
+    ///
+    /// If the transport is not currently playing, then this is the position
+    /// the transport is paused at.
+    pub playhead: InstantMusical,
+
+    /// The number of beats in a single bar.
+    pub beats_per_bar: u32,
 }
 
 #[cfg(feature = "musical_transport")]
@@ -927,6 +1149,12 @@ impl TransportInfo {
     pub const fn playing(&self) -> bool {
         self.start_clock_samples.is_some()
     }
+
+    /// The position of the playhead in bars, beats, and ticks at the first
+    /// frame of this process block.
+    pub fn position(&self) -> MusicalPosition {
+        self.playhead.to_position(self.beats_per_bar)
+    }
 }
 
 bitflags::bitflags! {
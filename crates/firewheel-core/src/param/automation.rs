@@ -0,0 +1,243 @@
+//! Curves for automating a parameter between two values over a span of time.
+//!
+//! Unlike diffing, which sends discrete changes, an [`Automation`] is sent to
+//! a node once with [`NodeEventType::Automation`], and the node then follows
+//! the curve sample-accurately (i.e. with
+//! [`SmoothedParam::start_automation`]).
+//!
+//! [`NodeEventType::Automation`]: crate::event::NodeEventType::Automation
+//! [`SmoothedParam::start_automation`]: crate::param::smoother::SmoothedParam::start_automation
+
+use core::num::NonZeroU32;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use crate::dsp::volume::db_to_amp;
+
+/// The shape of an [`Automation`] between its start and end values.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AutomationCurve {
+    /// Move at a constant rate.
+    #[default]
+    Linear,
+    /// Move by a constant ratio, which sounds even for values such as
+    /// frequencies.
+    ///
+    /// This requires both values to be non-zero and to have the same sign.
+    /// Otherwise this falls back to [`AutomationCurve::Linear`].
+    Exponential,
+    /// A cubic Bézier curve from the start value to the end value, where `c1`
+    /// and `c2` are the two control points expressed as a fraction of the
+    /// distance between the start and end value.
+    ///
+    /// For example, `c1 = 0.0, c2 = 1.0` gives an ease-in-out curve, and
+    /// `c1 = 1.0 / 3.0, c2 = 2.0 / 3.0` is the same as a linear curve.
+    Bezier { c1: f32, c2: f32 },
+}
+
+impl AutomationCurve {
+    /// Map the progress of an automation in the range `[0.0, 1.0]` to the
+    /// fraction of the distance between the start and end value.
+    ///
+    /// This is the identity for [`AutomationCurve::Exponential`], since its
+    /// shape depends on the values being automated.
+    pub fn ease(&self, progress: f32) -> f32 {
+        let t = progress.clamp(0.0, 1.0);
+
+        match *self {
+            Self::Linear | Self::Exponential => t,
+            Self::Bezier { c1, c2 } => {
+                let inv_t = 1.0 - t;
+                3.0 * inv_t * inv_t * t * c1 + 3.0 * inv_t * t * t * c2 + t * t * t
+            }
+        }
+    }
+}
+
+/// A parameter which follows a curve from one value to another over a span
+/// of time.
+///
+/// The automation starts when the event carrying it is received by the
+/// node, so schedule the event to choose the start time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Automation {
+    /// The shape of the curve.
+    pub curve: AutomationCurve,
+    /// The value at the start of the automation.
+    pub from: f32,
+    /// The value at the end of the automation. The parameter stays at this
+    /// value once the automation has finished.
+    pub to: f32,
+    /// The length of the automation in seconds.
+    pub duration_seconds: f32,
+}
+
+impl Automation {
+    pub const fn new(curve: AutomationCurve, from: f32, to: f32, duration_seconds: f32) -> Self {
+        Self {
+            curve,
+            from,
+            to,
+            duration_seconds,
+        }
+    }
+
+    /// The value of the automation at the given progress in the range
+    /// `[0.0, 1.0]`.
+    pub fn value_at(&self, progress: f32) -> f32 {
+        let t = self.curve.ease(progress);
+
+        if self.curve == AutomationCurve::Exponential
+            && self.from != 0.0
+            && self.to != 0.0
+            && self.from.is_sign_positive() == self.to.is_sign_positive()
+        {
+            self.from * (self.to / self.from).powf(t)
+        } else {
+            self.from + (self.to - self.from) * t
+        }
+    }
+
+    /// The length of the automation in frames (samples in a single channel
+    /// of audio).
+    pub fn duration_frames(&self, sample_rate: NonZeroU32) -> u32 {
+        (self.duration_seconds.max(0.0) as f64 * sample_rate.get() as f64).round() as u32
+    }
+}
+
+/// The units the values of an [`Automation`] are in, relative to the units of
+/// the parameter being automated.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutomationScale {
+    /// The values are in the same units as the parameter.
+    #[default]
+    Linear,
+    /// The values are in decibels, and the parameter is a raw amplitude.
+    ///
+    /// This is used for volume parameters, so that a linear curve sounds
+    /// like an even fade.
+    Decibels,
+}
+
+/// The state of an [`Automation`] which is being played back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Automator {
+    automation: Automation,
+    scale: AutomationScale,
+    frame: u32,
+    duration_frames: u32,
+    duration_frames_recip: f32,
+}
+
+impl Automator {
+    pub fn new(automation: Automation, scale: AutomationScale, sample_rate: NonZeroU32) -> Self {
+        let duration_frames = automation.duration_frames(sample_rate);
+
+        Self {
+            automation,
+            scale,
+            frame: 0,
+            duration_frames,
+            duration_frames_recip: if duration_frames == 0 {
+                0.0
+            } else {
+                (duration_frames as f32).recip()
+            },
+        }
+    }
+
+    /// The automation being played back.
+    pub fn automation(&self) -> &Automation {
+        &self.automation
+    }
+
+    /// Returns `true` if the automation has reached its end value.
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.duration_frames
+    }
+
+    /// The value at the end of the automation, in the units of the
+    /// parameter.
+    pub fn end_value(&self) -> f32 {
+        self.scaled(self.automation.to)
+    }
+
+    /// Return the value for the current frame and advance by one frame.
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        if self.is_finished() {
+            return self.end_value();
+        }
+
+        let progress = self.frame as f32 * self.duration_frames_recip;
+        self.frame += 1;
+
+        self.scaled(self.automation.value_at(progress))
+    }
+
+    #[inline]
+    fn scaled(&self, value: f32) -> f32 {
+        match self.scale {
+            AutomationScale::Linear => value,
+            AutomationScale::Decibels => db_to_amp(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn curve_endpoints() {
+        for curve in [
+            AutomationCurve::Linear,
+            AutomationCurve::Exponential,
+            AutomationCurve::Bezier { c1: 0.0, c2: 1.0 },
+        ] {
+            let automation = Automation::new(curve, 100.0, 1_000.0, 1.0);
+
+            assert!((automation.value_at(0.0) - 100.0).abs() < 0.001);
+            assert!((automation.value_at(1.0) - 1_000.0).abs() < 0.001);
+        }
+
+        let exp = Automation::new(AutomationCurve::Exponential, 100.0, 1_000.0, 1.0);
+        assert!((exp.value_at(0.5) - 316.2278).abs() < 0.01);
+
+        let bezier = Automation::new(
+            AutomationCurve::Bezier {
+                c1: 1.0 / 3.0,
+                c2: 2.0 / 3.0,
+            },
+            0.0,
+            1.0,
+            1.0,
+        );
+        assert!((bezier.value_at(0.25) - 0.25).abs() < 0.0001);
+    }
+
+    #[test]
+    fn automator_reaches_end() {
+        let sample_rate = NonZeroU32::new(1_000).unwrap();
+        let mut automator = Automator::new(
+            Automation::new(AutomationCurve::Linear, 0.0, 1.0, 0.01),
+            AutomationScale::Linear,
+            sample_rate,
+        );
+
+        let mut prev = -1.0;
+        for _ in 0..10 {
+            let value = automator.next_value();
+            assert!(value > prev);
+            prev = value;
+        }
+
+        assert!(automator.is_finished());
+        assert_eq!(automator.next_value(), 1.0);
+    }
+}
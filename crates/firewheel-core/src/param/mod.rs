@@ -1,2 +1,5 @@
+pub mod automation;
 pub mod range;
 pub mod smoother;
+pub mod snapshot;
+pub mod time;
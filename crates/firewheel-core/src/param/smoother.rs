@@ -6,23 +6,38 @@ use bevy_platform::prelude::Vec;
 use crate::{
     StreamInfo,
     dsp::filter::smoothing_filter::{self, SmoothingFilter, SmoothingFilterCoeff},
+    param::automation::{Automation, AutomationScale, Automator},
 };
 
 const MIN_SMOOTH_SECONDS: f32 = 0.00001;
 
 /// The configuration for a [`SmoothedParam`]
+///
+/// The factory nodes expose the smoothing time as a `smooth_seconds`
+/// parameter (e.g. `VolumeNode::smooth_seconds`), so it can be chosen per
+/// node and changed at any time, such as for snappy filter sweeps or gentle
+/// volume changes.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SmootherConfig {
     /// The amount of smoothing in seconds
     ///
-    /// By default this is set to 5 milliseconds.
+    /// By default this is set to [`smoothing_filter::DEFAULT_SMOOTH_SECONDS`]
+    /// (23 milliseconds).
     pub smooth_seconds: f32,
     /// The threshold at which the smoothing will complete
     ///
     /// By default this is set to `0.001`.
     pub settle_epsilon: f32,
+    /// If `Some`, then the smoothed value changes by at most this amount per
+    /// second.
+    ///
+    /// This makes even a large jump in the target value (i.e. from a batch of
+    /// diffs sent after the main thread stalled) glide instead of snapping.
+    ///
+    /// By default this is set to `None`.
+    pub max_slew_per_second: Option<f32>,
 }
 
 impl Default for SmootherConfig {
@@ -30,6 +45,7 @@ impl Default for SmootherConfig {
         Self {
             smooth_seconds: smoothing_filter::DEFAULT_SMOOTH_SECONDS,
             settle_epsilon: smoothing_filter::DEFAULT_SETTLE_EPSILON,
+            max_slew_per_second: None,
         }
     }
 }
@@ -43,6 +59,10 @@ pub struct SmoothedParam {
     coeff: SmoothingFilterCoeff,
     smooth_secs: f32,
     settle_epsilon: f32,
+    max_slew_per_second: Option<f32>,
+    /// The maximum change per sample, or infinity if there is no slew limit.
+    max_slew_per_sample: f32,
+    automator: Option<Automator>,
 }
 
 impl SmoothedParam {
@@ -60,6 +80,9 @@ impl SmoothedParam {
             coeff,
             smooth_secs,
             settle_epsilon,
+            max_slew_per_second: config.max_slew_per_second,
+            max_slew_per_sample: max_slew_per_sample(config.max_slew_per_second, sample_rate),
+            automator: None,
         }
     }
 
@@ -68,62 +91,139 @@ impl SmoothedParam {
         self.target_value
     }
 
+    /// The current value of the parameter, including any smoothing and
+    /// automation which is in progress.
+    pub fn current_value(&self) -> f32 {
+        self.filter.z1
+    }
+
     /// Set the target value of the parameter.
+    ///
+    /// This cancels any automation which is in progress, smoothing from the
+    /// current value of the automation to the new target value.
     pub fn set_value(&mut self, value: f32) {
+        self.automator = None;
         self.target_value = value;
         self.target_times_a = value * self.coeff.a0;
     }
 
+    /// Make the parameter follow the given automation curve, starting from
+    /// the next sample.
+    ///
+    /// The target value is set to the end value of the automation, which the
+    /// parameter stays at once the automation has finished. Calling
+    /// [`SmoothedParam::set_value`] cancels the automation.
+    ///
+    /// * `scale` - The units the values of the automation are in. See
+    ///   [`AutomationScale`].
+    pub fn start_automation(
+        &mut self,
+        automation: Automation,
+        scale: AutomationScale,
+        sample_rate: NonZeroU32,
+    ) {
+        let automator = Automator::new(automation, scale, sample_rate);
+        let end_value = automator.end_value();
+
+        self.target_value = end_value;
+        self.target_times_a = end_value * self.coeff.a0;
+
+        if automator.is_finished() {
+            self.automator = None;
+            self.filter = SmoothingFilter::new(end_value);
+        } else {
+            self.automator = Some(automator);
+        }
+    }
+
+    /// Returns `true` if the parameter is currently following an automation
+    /// curve.
+    pub fn is_automating(&self) -> bool {
+        self.automator.is_some()
+    }
+
     /// Settle the filter if its state is close enough to the target value.
     ///
     /// Returns `true` if this filter is settled, `false` if not.
     pub fn settle(&mut self) -> bool {
-        self.filter.settle(self.target_value, self.settle_epsilon)
+        if self.automator.is_some_and(|a| a.is_finished()) {
+            self.automator = None;
+            self.filter = SmoothingFilter::new(self.target_value);
+        }
+
+        self.automator.is_none() && self.filter.settle(self.target_value, self.settle_epsilon)
     }
 
     /// Returns `true` if this parameter is currently smoothing this process cycle,
     /// `false` if not.
     pub fn is_smoothing(&self) -> bool {
-        !self.filter.has_settled(self.target_value)
+        !self.has_settled()
     }
 
     /// Returns `false` if this parameter is currently smoothing this process cycle,
     /// `true` if not.
     pub fn has_settled(&self) -> bool {
-        self.filter.has_settled(self.target_value)
+        self.automator.is_none() && self.filter.has_settled(self.target_value)
     }
 
     /// Returns `true` if this parameter has settled to the given value, `false`
     /// if not.
     pub fn has_settled_at(&self, value: f32) -> bool {
-        self.target_value == value && self.filter.has_settled(self.target_value)
+        self.target_value == value && self.has_settled()
     }
 
     /// Returns `true` if this parameter has settled to a value less than or
     /// equal to the given value, `false` if not.
     pub fn has_settled_at_or_below(&self, value: f32) -> bool {
-        self.target_value <= value && self.filter.has_settled(self.target_value)
+        self.target_value <= value && self.has_settled()
     }
 
     /// Reset the internal smoothing filter to the current target value.
+    ///
+    /// This also skips to the end of any automation which is in progress.
     pub fn reset_to_target(&mut self) {
+        self.automator = None;
         self.filter = SmoothingFilter::new(self.target_value);
     }
 
     /// Return the next smoothed value.
     #[inline(always)]
     pub fn next_smoothed(&mut self) -> f32 {
-        self.filter
-            .process_sample_a(self.target_times_a, self.coeff.b1)
+        if let Some(automator) = &mut self.automator {
+            // Keep the filter in sync so that smoothing resumes from the
+            // current value if the automation is canceled.
+            self.filter.z1 = automator.next_value();
+            self.filter.z1
+        } else if self.max_slew_per_sample.is_finite() {
+            let prev = self.filter.z1;
+            let next = self
+                .filter
+                .process_sample_a(self.target_times_a, self.coeff.b1);
+
+            self.filter.z1 = next.clamp(
+                prev - self.max_slew_per_sample,
+                prev + self.max_slew_per_sample,
+            );
+            self.filter.z1
+        } else {
+            self.filter
+                .process_sample_a(self.target_times_a, self.coeff.b1)
+        }
     }
 
     /// Fill the given buffer with the smoothed values.
     pub fn process_into_buffer(&mut self, buffer: &mut [f32]) {
         if self.is_smoothing() {
-            self.filter
-                .process_into_buffer(buffer, self.target_value, self.coeff);
-
-            self.filter.settle(self.target_value, self.settle_epsilon);
+            if self.automator.is_some() || self.max_slew_per_sample.is_finite() {
+                for s in buffer.iter_mut() {
+                    *s = self.next_smoothed();
+                }
+            } else {
+                self.filter
+                    .process_into_buffer(buffer, self.target_value, self.coeff);
+            }
+
+            self.settle();
         } else {
             buffer.fill(self.target_value);
         }
@@ -134,12 +234,30 @@ impl SmoothedParam {
         self.smooth_secs = seconds;
     }
 
+    /// Set the maximum amount the smoothed value can change per second, or
+    /// `None` to disable the slew limit.
+    pub fn set_max_slew_per_second(
+        &mut self,
+        max_slew_per_second: Option<f32>,
+        sample_rate: NonZeroU32,
+    ) {
+        self.max_slew_per_second = max_slew_per_second;
+        self.max_slew_per_sample = max_slew_per_sample(max_slew_per_second, sample_rate);
+    }
+
     /// Update the sample rate.
     pub fn update_sample_rate(&mut self, sample_rate: NonZeroU32) {
         self.coeff = SmoothingFilterCoeff::new(sample_rate, self.smooth_secs);
+        self.max_slew_per_sample = max_slew_per_sample(self.max_slew_per_second, sample_rate);
     }
 }
 
+fn max_slew_per_sample(max_slew_per_second: Option<f32>, sample_rate: NonZeroU32) -> f32 {
+    max_slew_per_second
+        .map(|slew| slew.abs() / sample_rate.get() as f32)
+        .unwrap_or(f32::INFINITY)
+}
+
 /// A helper struct to smooth an f32 parameter, along with a buffer of smoothed values.
 #[derive(Debug, Clone)]
 pub struct SmoothedParamBuffer {
@@ -172,6 +290,18 @@ impl SmoothedParamBuffer {
         self.smoother.set_value(value);
     }
 
+    /// Make the parameter follow the given automation curve. See
+    /// [`SmoothedParam::start_automation`].
+    pub fn start_automation(
+        &mut self,
+        automation: Automation,
+        scale: AutomationScale,
+        sample_rate: NonZeroU32,
+    ) {
+        self.smoother
+            .start_automation(automation, scale, sample_rate);
+    }
+
     /// Reset the smoother.
     pub fn reset(&mut self) {
         if self.smoother.is_smoothing() || !self.buffer_is_constant {
@@ -237,3 +367,72 @@ impl SmoothedParamBuffer {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::param::automation::AutomationCurve;
+
+    #[test]
+    fn slew_limit() {
+        let sample_rate = NonZeroU32::new(48_000).unwrap();
+
+        let mut param = SmoothedParam::new(
+            0.0,
+            SmootherConfig {
+                max_slew_per_second: Some(48_000.0),
+                ..Default::default()
+            },
+            sample_rate,
+        );
+
+        param.set_value(100_000.0);
+
+        let mut buffer = [0.0; 16];
+        param.process_into_buffer(&mut buffer);
+
+        let mut prev = 0.0;
+        for &s in buffer.iter() {
+            assert!(s - prev <= 1.0 + f32::EPSILON);
+            prev = s;
+        }
+        assert!(param.is_smoothing());
+
+        param.set_max_slew_per_second(None, sample_rate);
+        param.process_into_buffer(&mut buffer);
+        assert!(buffer[0] - prev > 1.0);
+    }
+
+    #[test]
+    fn automation() {
+        let sample_rate = NonZeroU32::new(1_000).unwrap();
+
+        let mut param = SmoothedParam::new(0.0, SmootherConfig::default(), sample_rate);
+
+        param.start_automation(
+            Automation::new(AutomationCurve::Linear, 0.0, 1.0, 0.01),
+            AutomationScale::Linear,
+            sample_rate,
+        );
+        assert_eq!(param.target_value(), 1.0);
+
+        let mut buffer = [0.0; 16];
+        param.process_into_buffer(&mut buffer);
+
+        assert_eq!(buffer[0], 0.0);
+        assert!((buffer[5] - 0.5).abs() < 0.0001);
+        assert_eq!(buffer[15], 1.0);
+        assert!(param.has_settled());
+
+        // Setting a value cancels the automation.
+        param.start_automation(
+            Automation::new(AutomationCurve::Linear, 1.0, 0.0, 1.0),
+            AutomationScale::Linear,
+            sample_rate,
+        );
+        param.next_smoothed();
+        param.set_value(2.0);
+        assert!(!param.is_automating());
+        assert!(param.next_smoothed() > 1.0);
+    }
+}
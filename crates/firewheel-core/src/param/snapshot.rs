@@ -0,0 +1,91 @@
+//! Reading back the current value of a parameter from a node's processor.
+
+use core::any::Any;
+
+use crate::diff::ParamPath;
+
+/// A snapshot of the current value of a parameter in a node's processor,
+/// requested with `FirewheelContext::request_param_value`.
+///
+/// Unlike the parameters on the main thread, this is the value which is
+/// currently audible, including any smoothing and automation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamValueSnapshot {
+    /// The path to the parameter.
+    pub path: ParamPath,
+    /// The current value of the parameter, or `None` if it has not been
+    /// filled in by the processor.
+    ///
+    /// The value is in the same units as the parameter. The value of a
+    /// `Volume` parameter is in decibels.
+    pub value: Option<f32>,
+}
+
+impl ParamValueSnapshot {
+    pub const fn new(path: ParamPath) -> Self {
+        Self { path, value: None }
+    }
+
+    /// A helper for implementing
+    /// [`AudioNodeProcessor::snapshot_state`](crate::node::AudioNodeProcessor::snapshot_state).
+    ///
+    /// If `snapshot` is a [`ParamValueSnapshot`], then its value is set to
+    /// the value returned by `value_at` for its path. Return `None` from
+    /// `value_at` for parameters which cannot be read back.
+    ///
+    /// Returns `true` if the snapshot was filled in.
+    ///
+    /// ```
+    /// # use core::any::Any;
+    /// # use firewheel_core::param::{smoother::SmoothedParam, snapshot::ParamValueSnapshot};
+    /// # fn snapshot_state(cutoff_hz: &SmoothedParam, snapshot: &mut dyn Any) -> bool {
+    /// ParamValueSnapshot::fill(snapshot, |path| match path {
+    ///     [0] => Some(cutoff_hz.current_value()),
+    ///     _ => None,
+    /// })
+    /// # }
+    /// ```
+    pub fn fill(snapshot: &mut dyn Any, value_at: impl FnOnce(&[u32]) -> Option<f32>) -> bool {
+        let Some(snapshot) = snapshot.downcast_mut::<Self>() else {
+            return false;
+        };
+
+        snapshot.value = value_at(&snapshot.path);
+        snapshot.value.is_some()
+    }
+
+    /// Returns `true` if `a` and `b` are both snapshots of the same parameter.
+    ///
+    /// Returns `false` if either is not a [`ParamValueSnapshot`].
+    pub fn is_same_param(a: &dyn Any, b: &dyn Any) -> bool {
+        match (a.downcast_ref::<Self>(), b.downcast_ref::<Self>()) {
+            (Some(a), Some(b)) => a.path == b.path,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fill_matching_path() {
+        let value_at = |path: &[u32]| match path {
+            [1] => Some(0.5),
+            _ => None,
+        };
+
+        let mut snapshot = ParamValueSnapshot::new(ParamPath::Single(1));
+        assert!(ParamValueSnapshot::fill(&mut snapshot, value_at));
+        assert_eq!(snapshot.value, Some(0.5));
+
+        let mut snapshot = ParamValueSnapshot::new(ParamPath::Single(0));
+        assert!(!ParamValueSnapshot::fill(&mut snapshot, value_at));
+        assert_eq!(snapshot.value, None);
+
+        let mut other = 0u32;
+        assert!(!ParamValueSnapshot::fill(&mut other, value_at));
+        assert!(!ParamValueSnapshot::is_same_param(&snapshot, &other));
+    }
+}
@@ -0,0 +1,170 @@
+//! Diffable newtypes for timing parameters, which make the unit of a
+//! parameter explicit (i.e. to avoid confusing seconds and milliseconds).
+
+use core::{num::NonZeroU32, time::Duration};
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use crate::clock::{DurationSamples, DurationSeconds};
+
+/// A length of time in milliseconds, i.e. the attack time of an envelope.
+#[repr(transparent)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Milliseconds(pub f32);
+
+impl Milliseconds {
+    pub const ZERO: Self = Self(0.0);
+
+    pub const fn new(milliseconds: f32) -> Self {
+        Self(milliseconds)
+    }
+
+    pub const fn to_seconds(self) -> Seconds {
+        Seconds(self.0 as f64 / 1_000.0)
+    }
+
+    /// Convert to the corresponding number of frames (samples in a single
+    /// channel of audio), rounded to the nearest frame.
+    ///
+    /// Negative lengths result in `0`.
+    pub fn to_frames(self, sample_rate: NonZeroU32) -> usize {
+        self.to_seconds().to_frames(sample_rate)
+    }
+
+    pub fn to_samples(self, sample_rate: NonZeroU32) -> DurationSamples {
+        self.to_seconds().to_samples(sample_rate)
+    }
+}
+
+/// A length of time in seconds, i.e. the decay time of a reverb.
+#[repr(transparent)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Seconds(pub f64);
+
+impl Seconds {
+    pub const ZERO: Self = Self(0.0);
+
+    pub const fn new(seconds: f64) -> Self {
+        Self(seconds)
+    }
+
+    pub const fn to_milliseconds(self) -> Milliseconds {
+        Milliseconds((self.0 * 1_000.0) as f32)
+    }
+
+    /// Convert to the corresponding number of frames (samples in a single
+    /// channel of audio), rounded to the nearest frame.
+    ///
+    /// Negative lengths result in `0`.
+    pub fn to_frames(self, sample_rate: NonZeroU32) -> usize {
+        (self.0.max(0.0) * f64::from(sample_rate.get())).round() as usize
+    }
+
+    pub fn to_samples(self, sample_rate: NonZeroU32) -> DurationSamples {
+        DurationSeconds(self.0).to_samples(sample_rate)
+    }
+}
+
+impl From<f32> for Milliseconds {
+    fn from(value: f32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Milliseconds> for f32 {
+    fn from(value: Milliseconds) -> Self {
+        value.0
+    }
+}
+
+impl From<f64> for Seconds {
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Seconds> for f64 {
+    fn from(value: Seconds) -> Self {
+        value.0
+    }
+}
+
+impl From<Seconds> for Milliseconds {
+    fn from(value: Seconds) -> Self {
+        value.to_milliseconds()
+    }
+}
+
+impl From<Milliseconds> for Seconds {
+    fn from(value: Milliseconds) -> Self {
+        value.to_seconds()
+    }
+}
+
+impl From<DurationSeconds> for Seconds {
+    fn from(value: DurationSeconds) -> Self {
+        Self(value.0)
+    }
+}
+
+impl From<Seconds> for DurationSeconds {
+    fn from(value: Seconds) -> Self {
+        Self(value.0)
+    }
+}
+
+impl From<DurationSeconds> for Milliseconds {
+    fn from(value: DurationSeconds) -> Self {
+        Seconds::from(value).into()
+    }
+}
+
+impl From<Milliseconds> for DurationSeconds {
+    fn from(value: Milliseconds) -> Self {
+        value.to_seconds().into()
+    }
+}
+
+impl From<Duration> for Seconds {
+    fn from(value: Duration) -> Self {
+        Self(value.as_secs_f64())
+    }
+}
+
+impl From<Duration> for Milliseconds {
+    fn from(value: Duration) -> Self {
+        Seconds::from(value).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn time_conversions() {
+        let sample_rate = NonZeroU32::new(48_000).unwrap();
+
+        assert_eq!(Milliseconds(250.0).to_seconds(), Seconds(0.25));
+        assert_eq!(Seconds(0.5).to_milliseconds(), Milliseconds(500.0));
+        assert_eq!(Milliseconds(10.0).to_frames(sample_rate), 480);
+        assert_eq!(Milliseconds(-10.0).to_frames(sample_rate), 0);
+        assert_eq!(
+            Seconds(1.0).to_samples(sample_rate),
+            DurationSamples(48_000)
+        );
+        assert_eq!(
+            Milliseconds::from(Duration::from_millis(20)),
+            Milliseconds(20.0)
+        );
+        assert_eq!(
+            DurationSeconds::from(Milliseconds(1_500.0)),
+            DurationSeconds(1.5)
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use core::{fmt::Debug, num::NonZeroU32, time::Duration};
 use std::sync::{
     Arc,
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     mpsc,
 };
 
@@ -14,7 +14,7 @@ use cpal::{
     SampleFormat,
     traits::{DeviceTrait, HostTrait, StreamTrait},
 };
-use firewheel_core::node::StreamStatus;
+use firewheel_core::{dsp::volume::Volume, node::StreamStatus};
 use firewheel_graph::{
     ActivateInfo, FirewheelContext,
     backend::BackendProcessInfo,
@@ -33,6 +33,14 @@ use tracing::{error, info, warn};
 const DEFAULT_MAX_BLOCK_FRAMES: u32 = 1024;
 const BUILD_STREAM_TIMEOUT: Duration = Duration::from_secs(5);
 const UNDERRUN_LOG_COOLDOWN: Duration = Duration::from_secs(3);
+const SWITCH_DEVICE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Some hosts report a minimum buffer size which is unrealistically small
+/// (i.e. a single frame), so don't go below this when requesting the lowest
+/// latency.
+const LOWEST_LATENCY_MIN_BLOCK_FRAMES: u32 = 32;
+/// The maximum number of blocks of extra input latency that can be added
+/// by [`CpalInputConfig::grow_buffer_after_underflows`].
+const MAX_ADAPTIVE_INPUT_LATENCY_BLOCKS: usize = 4;
 
 /// The configuration of an output audio stream in the CPAL backend.
 #[derive(Debug, Clone, PartialEq)]
@@ -69,6 +77,29 @@ pub struct CpalOutputConfig {
     ///
     /// By default this is set to `true`.
     pub fallback: bool,
+
+    /// If `true`, then the smallest buffer size supported by the device at
+    /// the chosen sample rate will be used instead of
+    /// [`CpalOutputConfig::desired_block_frames`]. Useful for applications
+    /// such as rhythm games which need the lowest possible latency.
+    ///
+    /// If the device does not report a supported range of buffer sizes, then
+    /// this falls back to `desired_block_frames`.
+    ///
+    /// This currently has no effect on iOS platforms.
+    ///
+    /// By default this is set to `false`.
+    pub prefer_lowest_latency: bool,
+
+    /// Request that the output device be opened in exclusive mode (i.e. WASAPI
+    /// exclusive mode) where the host supports it.
+    ///
+    /// CPAL does not currently expose exclusive mode for any host, so for now
+    /// this falls back to shared mode and implies
+    /// [`CpalOutputConfig::prefer_lowest_latency`].
+    ///
+    /// By default this is set to `false`.
+    pub exclusive: bool,
 }
 
 impl Default for CpalOutputConfig {
@@ -79,6 +110,8 @@ impl Default for CpalOutputConfig {
             desired_sample_rate: None,
             desired_block_frames: Some(DEFAULT_MAX_BLOCK_FRAMES),
             fallback: true,
+            prefer_lowest_latency: false,
+            exclusive: false,
         }
     }
 }
@@ -122,6 +155,39 @@ pub struct CpalInputConfig {
     ///
     /// By default this is set to `false`.
     pub fail_on_no_input: bool,
+
+    /// If `Some`, then this maps hardware input channels to graph input
+    /// channels, where `channel_map[i]` is the index of the hardware input
+    /// channel which feeds graph input channel `i`. For example,
+    /// `Some(vec![2])` feeds only the third channel of the device into a
+    /// single (mono) graph input.
+    ///
+    /// The number of graph input channels will be equal to the length of
+    /// this map. Any hardware channel index which is out of range for the
+    /// device will result in silence.
+    ///
+    /// If `None`, then all hardware input channels are fed 1:1 into the
+    /// graph inputs.
+    ///
+    /// By default this is set to `None`.
+    pub channel_map: Option<Vec<usize>>,
+
+    /// The gain applied to the input signal before it is sent to the
+    /// graph.
+    ///
+    /// By default this is set to [`Volume::UNITY_GAIN`].
+    pub input_gain: Volume,
+
+    /// If `Some`, then after this many underflows of the input buffer, the
+    /// amount of input data buffered ahead is grown by one block. This
+    /// trades a bit of extra input latency for fewer glitches on systems
+    /// where the input and output streams are poorly synchronized.
+    ///
+    /// The input buffer is grown by at most 4 blocks in total. The amount
+    /// it has been grown by is reported in [`XrunStats`].
+    ///
+    /// By default this is set to `None`.
+    pub grow_buffer_after_underflows: Option<u32>,
 }
 
 impl Default for CpalInputConfig {
@@ -133,6 +199,9 @@ impl Default for CpalInputConfig {
             channel_config: ResamplingChannelConfig::default(),
             fallback: true,
             fail_on_no_input: false,
+            channel_map: None,
+            input_gain: Volume::UNITY_GAIN,
+            grow_buffer_after_underflows: None,
         }
     }
 }
@@ -300,12 +369,69 @@ pub struct CpalStreamInfo {
     pub num_stream_out_channels: u32,
     /// The latency of the input to output stream in seconds.
     pub input_to_output_latency_seconds: f64,
+    /// The latency of a single block of the output stream in seconds, if a
+    /// fixed buffer size was used.
+    ///
+    /// This does not include any additional latency added by the OS or the
+    /// audio driver.
+    pub output_block_latency_seconds: Option<f64>,
     /// The ID of the output audio device.
     pub out_device_id: Option<DeviceId>,
     /// The ID of the input audio device.
     pub in_device_id: Option<DeviceId>,
 }
 
+/// Statistics about underflows and overflows (xruns) which have occurred in a
+/// CPAL audio stream.
+///
+/// This can be used to decide whether or not to raise
+/// [`CpalOutputConfig::desired_block_frames`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XrunStats {
+    /// The number of times the output stream underflowed (the audio thread
+    /// didn't fill the buffer in time).
+    pub output_underflows: u64,
+    /// The total number of frames estimated to have been dropped because of
+    /// output underflows.
+    pub dropped_frames: u64,
+    /// The number of times there was not enough data in the input buffer.
+    pub input_underflows: u64,
+    /// The number of times the input buffer overflowed and some input data
+    /// was discarded.
+    pub input_overflows: u64,
+    /// The number of frames the input buffer has been grown by because of
+    /// [`CpalInputConfig::grow_buffer_after_underflows`].
+    pub extra_input_latency_frames: u64,
+}
+
+#[derive(Default)]
+struct SharedXrunStats {
+    output_underflows: AtomicU64,
+    dropped_frames: AtomicU64,
+    input_underflows: AtomicU64,
+    input_overflows: AtomicU64,
+    extra_input_latency_frames: AtomicU64,
+}
+
+impl SharedXrunStats {
+    fn load(&self) -> XrunStats {
+        XrunStats {
+            output_underflows: self.output_underflows.load(Ordering::Relaxed),
+            dropped_frames: self.dropped_frames.load(Ordering::Relaxed),
+            input_underflows: self.input_underflows.load(Ordering::Relaxed),
+            input_overflows: self.input_overflows.load(Ordering::Relaxed),
+            extra_input_latency_frames: self.extra_input_latency_frames.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.output_underflows.store(0, Ordering::Relaxed);
+        self.dropped_frames.store(0, Ordering::Relaxed);
+        self.input_underflows.store(0, Ordering::Relaxed);
+        self.input_overflows.store(0, Ordering::Relaxed);
+    }
+}
+
 /// The system audio hosts (APIs) that are available on this system.
 ///
 /// The first host in the list is the default one for the system.
@@ -337,6 +463,7 @@ pub struct CpalStream {
     stream_info: CpalStreamInfo,
     input_stream_running: Option<Arc<AtomicBool>>,
     output_stream_running: Arc<AtomicBool>,
+    xrun_stats: Arc<SharedXrunStats>,
 }
 
 impl CpalStream {
@@ -473,6 +600,22 @@ impl CpalStream {
         let num_out_channels = default_config.channels() as usize;
         assert_ne!(num_out_channels, 0);
 
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        if config.output.exclusive {
+            warn!(
+                "Exclusive mode is not supported by CPAL. Falling back to shared mode with the lowest supported latency..."
+            );
+        }
+
+        #[cfg(not(target_os = "ios"))]
+        let desired_block_frames = if config.output.prefer_lowest_latency || config.output.exclusive
+        {
+            lowest_supported_block_frames(&out_device, sample_rate, num_out_channels)
+                .or(desired_block_frames)
+        } else {
+            desired_block_frames
+        };
+
         let desired_buffer_size = if let Some(samples) = desired_block_frames {
             cpal::BufferSize::Fixed(samples)
         } else {
@@ -490,6 +633,11 @@ impl CpalStream {
             cpal::BufferSize::Fixed(f) => f as usize,
         };
 
+        let output_block_latency_seconds = match out_stream_config.buffer_size {
+            cpal::BufferSize::Default => None,
+            cpal::BufferSize::Fixed(f) => Some(f64::from(f) / f64::from(sample_rate)),
+        };
+
         let (err_to_cx_tx, from_err_rx) = mpsc::channel();
 
         let mut input_stream = StartInputStreamResult::NotStarted;
@@ -504,6 +652,7 @@ impl CpalStream {
         let (
             in_stream_handle,
             input_stream_cons,
+            input_mapping,
             num_stream_in_channels,
             in_device_id,
             input_to_output_latency_seconds,
@@ -511,6 +660,7 @@ impl CpalStream {
         ) = if let StartInputStreamResult::Started {
             stream_handle,
             cons,
+            input_mapping,
             num_stream_in_channels,
             in_device_id,
             input_stream_running,
@@ -521,13 +671,14 @@ impl CpalStream {
             (
                 Some(stream_handle),
                 Some(cons),
+                input_mapping,
                 num_stream_in_channels,
                 in_device_id,
                 input_to_output_latency_seconds,
                 Some(input_stream_running),
             )
         } else {
-            (None, None, 0, None, 0.0, None)
+            (None, None, InputMapping::default(), 0, None, 0.0, None)
         };
 
         let activate_info = ActivateInfo {
@@ -541,6 +692,7 @@ impl CpalStream {
         let processor = cx.activate(activate_info)?;
 
         let output_stream_running = Arc::new(AtomicBool::new(true));
+        let xrun_stats = Arc::new(SharedXrunStats::default());
 
         let mut callback = OutputCallback::new(
             num_out_channels,
@@ -548,9 +700,15 @@ impl CpalStream {
             out_stream_config.sample_rate,
             processor,
             input_stream_cons,
+            input_mapping,
             err_to_cx_tx.clone(),
             input_stream_running.as_ref().map(Arc::clone),
             Arc::clone(&output_stream_running),
+            Arc::clone(&xrun_stats),
+            config
+                .input
+                .as_ref()
+                .and_then(|c| c.grow_buffer_after_underflows),
         );
 
         let out_sample_format = default_config.sample_format();
@@ -638,6 +796,7 @@ impl CpalStream {
             num_stream_in_channels: activate_info.num_stream_in_channels,
             num_stream_out_channels: activate_info.num_stream_out_channels,
             input_to_output_latency_seconds: activate_info.input_to_output_latency_seconds,
+            output_block_latency_seconds,
             out_device_id,
             in_device_id,
         };
@@ -649,14 +808,66 @@ impl CpalStream {
             stream_info,
             input_stream_running,
             output_stream_running,
+            xrun_stats,
         })
     }
 
+    /// Stop this audio stream and start a new one with the given configuration,
+    /// handing the existing audio graph over to the new stream.
+    ///
+    /// Unlike dropping this stream and calling [`CpalStream::new`] manually,
+    /// this waits for the audio thread to hand the graph back before starting
+    /// the new stream. The state of all nodes is preserved, and the graph is
+    /// only recompiled if the sample rate, block size, or number of channels
+    /// changed.
+    ///
+    /// Use [`HostEnumerator::output_devices`] to get the list of available
+    /// output devices to choose from.
+    ///
+    /// If this returns an error, then the previous stream has already been
+    /// stopped, and a new stream must be created with [`CpalStream::new`].
+    /// On WebAssembly this does not wait for the audio thread, so it may
+    /// return [`StartStreamError::AlreadyActive`].
+    pub fn switch_output_device(
+        self,
+        cx: &mut FirewheelContext,
+        config: CpalConfig,
+    ) -> Result<Self, StartStreamError> {
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        info!("Switching CPAL audio stream to a new device...");
+
+        // Dropping the stream handles stops the audio thread, which drops the
+        // processor and sends it back to the context.
+        drop(self);
+
+        #[cfg(not(target_family = "wasm"))]
+        if cx.deactivate_blocking(SWITCH_DEVICE_TIMEOUT).is_err() {
+            return Err(StartStreamError::AlreadyActive);
+        }
+
+        Self::new(cx, config)
+    }
+
     /// Information about the running audio stream
     pub fn info(&self) -> &CpalStreamInfo {
         &self.stream_info
     }
 
+    /// Get the statistics about underflows and overflows (xruns) which have
+    /// occurred since the stream was started or since the last call to
+    /// [`CpalStream::reset_xrun_stats`].
+    pub fn xrun_stats(&self) -> XrunStats {
+        self.xrun_stats.load()
+    }
+
+    /// Reset the underflow and overflow counters in [`XrunStats`] to zero.
+    ///
+    /// Note, [`XrunStats::extra_input_latency_frames`] is not reset since the
+    /// input buffer stays grown.
+    pub fn reset_xrun_stats(&self) {
+        self.xrun_stats.reset();
+    }
+
     /// Poll the status of the audio stream and log any errors/warnings that have occurred.
     ///
     /// Note, if an error is returned, it doesn't always mean that the stream has stopped.
@@ -843,6 +1054,22 @@ fn start_input_stream(
     let num_in_channels = default_config.channels() as usize;
     assert_ne!(num_in_channels, 0);
 
+    let input_mapping = InputMapping {
+        channel_map: config.channel_map.clone(),
+        gain: config.input_gain.amp(),
+    };
+    let num_stream_in_channels = input_mapping.num_graph_channels(num_in_channels);
+
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    if let Some(channel_map) = &config.channel_map {
+        for &hw_channel in channel_map.iter().filter(|&&ch| ch >= num_in_channels) {
+            warn!(
+                "Input channel map references hardware channel {} but the input device only has {} channels. That graph input will be silent",
+                hw_channel, num_in_channels
+            );
+        }
+    }
+
     let desired_buffer_size = if let Some(samples) = desired_block_frames {
         cpal::BufferSize::Fixed(samples)
     } else {
@@ -977,7 +1204,8 @@ fn start_input_stream(
     Ok(StartInputStreamResult::Started {
         stream_handle,
         cons,
-        num_stream_in_channels: num_in_channels as u32,
+        input_mapping,
+        num_stream_in_channels: num_stream_in_channels as u32,
         in_device_id,
         input_stream_running,
     })
@@ -989,12 +1217,73 @@ enum StartInputStreamResult {
     Started {
         stream_handle: cpal::Stream,
         cons: fixed_resample::ResamplingCons<f32>,
+        input_mapping: InputMapping,
         num_stream_in_channels: u32,
         in_device_id: Option<DeviceId>,
         input_stream_running: Arc<AtomicBool>,
     },
 }
 
+/// Maps hardware input channels to graph input channels and applies input
+/// gain.
+#[derive(Debug, Clone, PartialEq)]
+struct InputMapping {
+    channel_map: Option<Vec<usize>>,
+    gain: f32,
+}
+
+impl Default for InputMapping {
+    fn default() -> Self {
+        Self {
+            channel_map: None,
+            gain: 1.0,
+        }
+    }
+}
+
+impl InputMapping {
+    fn num_graph_channels(&self, num_hw_channels: usize) -> usize {
+        self.channel_map
+            .as_ref()
+            .map(|m| m.len())
+            .unwrap_or(num_hw_channels)
+    }
+
+    /// Fill the interleaved `graph_buffer` from the interleaved `hw_buffer`.
+    ///
+    /// If there is no channel map, then the hardware input was read directly
+    /// into `graph_buffer`, and only the gain is applied.
+    fn apply(
+        &self,
+        hw_buffer: &[f32],
+        graph_buffer: &mut [f32],
+        num_hw_channels: usize,
+        frames: usize,
+    ) {
+        let Some(channel_map) = &self.channel_map else {
+            if self.gain != 1.0 {
+                for s in graph_buffer.iter_mut() {
+                    *s *= self.gain;
+                }
+            }
+            return;
+        };
+
+        if channel_map.is_empty() {
+            return;
+        }
+
+        for (hw_frame, graph_frame) in hw_buffer[..frames * num_hw_channels]
+            .chunks_exact(num_hw_channels)
+            .zip(graph_buffer.chunks_exact_mut(channel_map.len()))
+        {
+            for (s, &hw_channel) in graph_frame.iter_mut().zip(channel_map.iter()) {
+                *s = hw_frame.get(hw_channel).copied().unwrap_or(0.0) * self.gain;
+            }
+        }
+    }
+}
+
 struct InputCallback {
     prod: ResamplingProd<f32>,
     err_to_cx_tx: mpsc::Sender<IoStreamError>,
@@ -1027,10 +1316,21 @@ struct OutputCallback {
     prev_instant: Option<Instant>,
     stream_start_instant: Instant,
     input_stream_cons: Option<fixed_resample::ResamplingCons<f32>>,
+    input_mapping: InputMapping,
+    /// The raw hardware input, only used if there is a channel map.
+    hw_input_buffer: Vec<f32>,
     input_buffer: Vec<f32>,
     err_to_cx_tx: mpsc::Sender<IoStreamError>,
     input_stream_running: Option<Arc<AtomicBool>>,
     output_stream_running: Arc<AtomicBool>,
+    xrun_stats: Arc<SharedXrunStats>,
+    grow_input_after_underflows: Option<u32>,
+    input_underflows_since_grow: u32,
+    extra_input_latency_frames: usize,
+    max_extra_input_latency_frames: usize,
+    /// The number of frames of input left to buffer up before reading from
+    /// the input stream again.
+    input_frames_to_buffer: usize,
 }
 
 impl OutputCallback {
@@ -1041,16 +1341,30 @@ impl OutputCallback {
         sample_rate: u32,
         processor: FirewheelProcessor,
         input_stream_cons: Option<fixed_resample::ResamplingCons<f32>>,
+        input_mapping: InputMapping,
         err_to_cx_tx: mpsc::Sender<IoStreamError>,
         input_stream_running: Option<Arc<AtomicBool>>,
         output_stream_running: Arc<AtomicBool>,
+        xrun_stats: Arc<SharedXrunStats>,
+        grow_input_after_underflows: Option<u32>,
     ) -> Self {
         let stream_start_instant = Instant::now();
 
-        let input_buffer = if let Some(cons) = &input_stream_cons {
-            scratch_vec(max_block_frames * cons.num_channels())
+        let (hw_input_buffer, input_buffer) = if let Some(cons) = &input_stream_cons {
+            let hw_input_buffer = if input_mapping.channel_map.is_some() {
+                scratch_vec(max_block_frames * cons.num_channels())
+            } else {
+                Vec::new()
+            };
+
+            (
+                hw_input_buffer,
+                scratch_vec(
+                    max_block_frames * input_mapping.num_graph_channels(cons.num_channels()),
+                ),
+            )
         } else {
-            Vec::new()
+            (Vec::new(), Vec::new())
         };
 
         Self {
@@ -1062,10 +1376,18 @@ impl OutputCallback {
             prev_instant: None,
             stream_start_instant,
             input_stream_cons,
+            input_mapping,
+            hw_input_buffer,
             input_buffer,
             err_to_cx_tx,
             input_stream_running,
             output_stream_running,
+            xrun_stats,
+            grow_input_after_underflows,
+            input_underflows_since_grow: 0,
+            extra_input_latency_frames: 0,
+            max_extra_input_latency_frames: max_block_frames * MAX_ADAPTIVE_INPUT_LATENCY_BLOCKS,
+            input_frames_to_buffer: 0,
         }
     }
 
@@ -1087,9 +1409,18 @@ impl OutputCallback {
 
             (underflow, dropped_frames)
         } else {
-            self.prev_instant = Some(process_timestamp);
             (false, 0)
         };
+        self.prev_instant = Some(process_timestamp);
+
+        if underflow {
+            self.xrun_stats
+                .output_underflows
+                .fetch_add(1, Ordering::Relaxed);
+            self.xrun_stats
+                .dropped_frames
+                .fetch_add(u64::from(dropped_frames), Ordering::Relaxed);
+        }
 
         // Calculate the next predicted stream time to detect underflows.
         //
@@ -1156,25 +1487,73 @@ impl OutputCallback {
 
         let (num_in_channels, input_stream_status) = if let Some(cons) = &mut self.input_stream_cons
         {
-            let num_in_channels = cons.num_channels();
+            let num_hw_channels = cons.num_channels();
+            let num_in_channels = self.input_mapping.num_graph_channels(num_hw_channels);
             let num_input_samples = frames * num_in_channels;
 
-            if self
+            if self.input_frames_to_buffer > 0 {
+                // Let the input buffer fill up by not reading from it for a
+                // bit.
+                self.input_frames_to_buffer = self.input_frames_to_buffer.saturating_sub(frames);
+                self.input_buffer[..num_input_samples].fill(0.0);
+
+                (num_in_channels, StreamStatus::empty())
+            } else if self
                 .input_stream_running
                 .as_ref()
                 .unwrap()
                 .load(Ordering::Relaxed)
             {
-                let status =
-                    cons.read_interleaved(&mut self.input_buffer[..num_input_samples], false);
+                let hw_buffer = if self.input_mapping.channel_map.is_some() {
+                    &mut self.hw_input_buffer[..frames * num_hw_channels]
+                } else {
+                    &mut self.input_buffer[..num_input_samples]
+                };
+
+                let status = cons.read_interleaved(hw_buffer, false);
+
+                self.input_mapping.apply(
+                    &self.hw_input_buffer,
+                    &mut self.input_buffer[..num_input_samples],
+                    num_hw_channels,
+                    frames,
+                );
 
                 let status = match status {
                     ReadStatus::UnderflowOccurred { num_frames_read: _ } => {
+                        self.xrun_stats
+                            .input_underflows
+                            .fetch_add(1, Ordering::Relaxed);
+
+                        if let Some(threshold) = self.grow_input_after_underflows {
+                            self.input_underflows_since_grow += 1;
+
+                            if self.input_underflows_since_grow >= threshold
+                                && self.extra_input_latency_frames
+                                    < self.max_extra_input_latency_frames
+                            {
+                                self.input_underflows_since_grow = 0;
+                                self.extra_input_latency_frames += frames;
+                                self.input_frames_to_buffer = frames;
+
+                                self.xrun_stats.extra_input_latency_frames.store(
+                                    self.extra_input_latency_frames as u64,
+                                    Ordering::Relaxed,
+                                );
+                            }
+                        }
+
                         StreamStatus::OUTPUT_UNDERFLOW
                     }
                     ReadStatus::OverflowCorrected {
                         num_frames_discarded: _,
-                    } => StreamStatus::INPUT_OVERFLOW,
+                    } => {
+                        self.xrun_stats
+                            .input_overflows
+                            .fetch_add(1, Ordering::Relaxed);
+
+                        StreamStatus::INPUT_OVERFLOW
+                    }
                     _ => StreamStatus::empty(),
                 };
 
@@ -1333,6 +1712,29 @@ pub enum IoStreamError {
     Output(cpal::Error),
 }
 
+/// Find the smallest buffer size supported by the device for the given sample
+/// rate and number of channels.
+#[cfg(not(target_os = "ios"))]
+fn lowest_supported_block_frames(
+    device: &cpal::Device,
+    sample_rate: u32,
+    num_channels: usize,
+) -> Option<u32> {
+    device
+        .supported_output_configs()
+        .ok()?
+        .filter(|c| {
+            c.channels() as usize == num_channels && c.try_with_sample_rate(sample_rate).is_some()
+        })
+        .filter_map(|c| match *c.buffer_size() {
+            cpal::SupportedBufferSize::Range { min, max } => {
+                Some(min.max(LOWEST_LATENCY_MIN_BLOCK_FRAMES).min(max))
+            }
+            cpal::SupportedBufferSize::Unknown => None,
+        })
+        .min()
+}
+
 fn scratch_vec(len: usize) -> Vec<f32> {
     let mut v = Vec::new();
     v.reserve_exact(len);
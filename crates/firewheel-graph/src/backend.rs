@@ -2,6 +2,10 @@ use bevy_platform::time::Instant;
 use core::time::Duration;
 use firewheel_core::node::StreamStatus;
 
+mod manual;
+
+pub use manual::{ManualBackend, ManualBackendConfig};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BackendProcessInfo {
     pub frames: usize,
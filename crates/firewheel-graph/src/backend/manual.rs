@@ -0,0 +1,232 @@
+use core::{num::NonZeroU32, time::Duration};
+
+use audioadapter_buffers::direct::InterleavedSlice;
+use firewheel_core::{
+    dsp::dither::{DitherMode, Quantizer},
+    node::StreamStatus,
+};
+
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::{Vec, vec};
+
+use crate::{
+    ActivateInfo, FirewheelContext,
+    backend::BackendProcessInfo,
+    error::{ActivateError, UpdateError},
+    processor::FirewheelProcessor,
+};
+
+/// The configuration of a [`ManualBackend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ManualBackendConfig {
+    /// The sample rate of the stream.
+    ///
+    /// By default this is set to `48000`.
+    pub sample_rate: NonZeroU32,
+    /// The maximum number of frames the processor processes in a single
+    /// block. Calls to [`ManualBackend::process`] with more frames than this
+    /// are split into multiple blocks.
+    ///
+    /// By default this is set to `1024`.
+    pub max_block_frames: NonZeroU32,
+    /// The number of input channels in the stream.
+    ///
+    /// By default this is set to `0`.
+    pub num_in_channels: u32,
+    /// The number of output channels in the stream.
+    ///
+    /// By default this is set to `2`.
+    pub num_out_channels: u32,
+    /// The dither applied when converting the output to integer samples with
+    /// [`ManualBackend::process_i16`].
+    ///
+    /// By default this is set to [`DitherMode::Tpdf`].
+    pub dither: DitherMode,
+}
+
+impl Default for ManualBackendConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: NonZeroU32::new(48_000).unwrap(),
+            max_block_frames: NonZeroU32::new(1024).unwrap(),
+            num_in_channels: 0,
+            num_out_channels: 2,
+            dither: DitherMode::default(),
+        }
+    }
+}
+
+/// A minimal backend which processes the audio graph whenever
+/// [`ManualBackend::process`] is called.
+///
+/// This backend only requires an allocator, so it can be used in `no_std`
+/// environments such as embedded targets, where the platform's audio driver
+/// or DMA interrupt calls into the audio graph. It is also useful for
+/// rendering audio offline.
+///
+/// The context is deactivated when this struct is dropped.
+pub struct ManualBackend {
+    processor: FirewheelProcessor,
+    config: ManualBackendConfig,
+    frames_processed: u64,
+    quantizer: Quantizer,
+    /// The floating point output used by [`ManualBackend::process_i16`].
+    scratch_out: Vec<f32>,
+}
+
+impl ManualBackend {
+    /// Activate the given [`FirewheelContext`] with this backend.
+    pub fn new(
+        cx: &mut FirewheelContext,
+        config: ManualBackendConfig,
+    ) -> Result<Self, ActivateError> {
+        let processor = cx.activate(ActivateInfo {
+            sample_rate: config.sample_rate,
+            max_block_frames: config.max_block_frames,
+            num_stream_in_channels: config.num_in_channels,
+            num_stream_out_channels: config.num_out_channels,
+            input_to_output_latency_seconds: 0.0,
+        })?;
+
+        Ok(Self {
+            processor,
+            config,
+            frames_processed: 0,
+            quantizer: Quantizer::new(config.dither, config.num_out_channels as usize),
+            scratch_out: vec![
+                0.0;
+                config.max_block_frames.get() as usize
+                    * config.num_out_channels as usize
+            ],
+        })
+    }
+
+    /// Process `frames` frames of the audio graph.
+    ///
+    /// * `input` - The interleaved input buffer. If the stream has no input
+    ///   channels, then this can be empty.
+    /// * `output` - The interleaved output buffer.
+    ///
+    /// # Panics
+    /// Panics if `input` is shorter than `frames * num_in_channels`, or if
+    /// `output` is shorter than `frames * num_out_channels`.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32], frames: usize) {
+        let num_in_channels = self.config.num_in_channels as usize;
+        let num_out_channels = self.config.num_out_channels as usize;
+        let max_block_frames = self.config.max_block_frames.get() as usize;
+
+        let input = &input[..frames * num_in_channels];
+        let output = &mut output[..frames * num_out_channels];
+
+        let sample_rate_recip = (self.config.sample_rate.get() as f64).recip();
+
+        let mut frames_processed = 0;
+        while frames_processed < frames {
+            let block_frames = (frames - frames_processed).min(max_block_frames);
+
+            let block_in = &input[frames_processed * num_in_channels
+                ..(frames_processed + block_frames) * num_in_channels];
+            let block_out = &mut output[frames_processed * num_out_channels
+                ..(frames_processed + block_frames) * num_out_channels];
+
+            self.processor.process(
+                &InterleavedSlice::new(block_in, num_in_channels, block_frames).unwrap(),
+                &mut InterleavedSlice::new_mut(block_out, num_out_channels, block_frames).unwrap(),
+                BackendProcessInfo {
+                    frames: block_frames,
+                    process_timestamp: None,
+                    duration_since_stream_start: Duration::from_secs_f64(
+                        self.frames_processed as f64 * sample_rate_recip,
+                    ),
+                    input_stream_status: StreamStatus::empty(),
+                    output_stream_status: StreamStatus::empty(),
+                    dropped_frames: 0,
+                    process_to_playback_delay: None,
+                },
+            );
+
+            frames_processed += block_frames;
+            self.frames_processed += block_frames as u64;
+        }
+    }
+
+    /// Send all queued events to the processor with
+    /// [`FirewheelContext::flush_events_now`], and then process `frames`
+    /// frames of the audio graph.
+    ///
+    /// The events are guaranteed to be received at the start of the first
+    /// processed block, which makes the timing of tests deterministic.
+    ///
+    /// See [`ManualBackend::process`] for a description of the arguments.
+    pub fn flush_and_process(
+        &mut self,
+        cx: &mut FirewheelContext,
+        input: &[f32],
+        output: &mut [f32],
+        frames: usize,
+    ) -> Result<(), UpdateError> {
+        cx.flush_events_now()?;
+        self.process(input, output, frames);
+
+        Ok(())
+    }
+
+    /// Process `frames` frames of the audio graph, and convert the output to
+    /// 16-bit integer samples using the dither in
+    /// [`ManualBackendConfig::dither`].
+    ///
+    /// This is useful when rendering audio offline to 16-bit PCM (i.e. a WAV
+    /// file), where simply truncating the samples causes quantization
+    /// distortion on quiet signals.
+    ///
+    /// * `input` - The interleaved input buffer. If the stream has no input
+    ///   channels, then this can be empty.
+    /// * `output` - The interleaved output buffer.
+    ///
+    /// # Panics
+    /// Panics if `input` is shorter than `frames * num_in_channels`, or if
+    /// `output` is shorter than `frames * num_out_channels`.
+    pub fn process_i16(&mut self, input: &[f32], output: &mut [i16], frames: usize) {
+        let num_in_channels = self.config.num_in_channels as usize;
+        let num_out_channels = self.config.num_out_channels as usize;
+        let max_block_frames = self.config.max_block_frames.get() as usize;
+
+        let input = &input[..frames * num_in_channels];
+        let output = &mut output[..frames * num_out_channels];
+
+        let mut scratch_out = core::mem::take(&mut self.scratch_out);
+
+        let mut frames_processed = 0;
+        while frames_processed < frames {
+            let block_frames = (frames - frames_processed).min(max_block_frames);
+            let block_out = &mut scratch_out[..block_frames * num_out_channels];
+
+            self.process(
+                &input[frames_processed * num_in_channels
+                    ..(frames_processed + block_frames) * num_in_channels],
+                block_out,
+                block_frames,
+            );
+
+            self.quantizer.quantize_i16(
+                block_out,
+                &mut output[frames_processed * num_out_channels
+                    ..(frames_processed + block_frames) * num_out_channels],
+            );
+
+            frames_processed += block_frames;
+        }
+
+        self.scratch_out = scratch_out;
+    }
+
+    /// The configuration of this backend.
+    pub fn config(&self) -> &ManualBackendConfig {
+        &self.config
+    }
+
+    /// The total number of frames that have been processed.
+    pub fn frames_processed(&self) -> u64 {
+        self.frames_processed
+    }
+}
@@ -10,10 +10,12 @@ use firewheel_core::node::{NodeError, ProcStore};
 use firewheel_core::{
     StreamInfo,
     channel_config::{ChannelConfig, ChannelCount},
-    diff::EventQueue,
+    collector::OwnedGc,
+    diff::{Diff, EventQueue, Memo, ParamPath},
     dsp::declick::DeclickValues,
     event::{NodeEvent, NodeEventType},
     node::{AudioNode, DynAudioNode, NodeID},
+    param::snapshot::ParamValueSnapshot,
 };
 use firewheel_core::{
     dsp::volume::Volume,
@@ -35,7 +37,7 @@ use firewheel_core::clock::{AudioClock, DurationSeconds};
 #[cfg(all(not(feature = "std"), feature = "musical_transport"))]
 use bevy_platform::prelude::Box;
 #[cfg(not(feature = "std"))]
-use bevy_platform::prelude::Vec;
+use bevy_platform::prelude::{String, Vec};
 
 use crate::{
     error::{ActivateError, RemoveNodeError},
@@ -43,7 +45,7 @@ use crate::{
 };
 use crate::{
     error::{AddEdgeError, UpdateError},
-    graph::{AudioGraph, Edge, EdgeID, NodeEntry, PortIdx},
+    graph::{AudioGraph, Connection, Edge, EdgeID, GraphStats, NodeEntry, PortIdx},
     processor::{
         ContextToProcessorMsg, FirewheelProcessor, FirewheelProcessorInner, ProcessorToContextMsg,
     },
@@ -51,15 +53,21 @@ use crate::{
 use crate::{
     error::{CompileGraphError, DeactivateError},
     processor::{
-        BufferOutOfSpaceMode, FirewheelProcessorConfig, ProfilingData,
+        BufferOutOfSpaceMode, FirewheelProcessorConfig, LimiterConfig, ProfilingData,
         profiling::{ProfilerRx, ProfilerTx},
     },
 };
 
+#[cfg(feature = "node_profiling")]
+use crate::processor::NodeProfileData;
+
 #[cfg(feature = "scheduled_events")]
 use crate::processor::{ClearScheduledEventsEvent, SharedClock};
 #[cfg(feature = "scheduled_events")]
-use firewheel_core::clock::EventInstant;
+use firewheel_core::{
+    clock::EventInstant,
+    param::automation::{Automation, AutomationCurve},
+};
 
 #[cfg(feature = "musical_transport")]
 use firewheel_core::clock::TransportState;
@@ -163,6 +171,113 @@ pub struct FirewheelConfig {
     ///
     /// By default this is set to `Some(Volume::Decibels(-70.0)`.
     pub clamp_graph_inputs_below: Option<Volume>,
+
+    /// If `true`, then the graph compiler will insert delays on the inputs of
+    /// nodes whose incoming paths have differing latencies (as reported by
+    /// [`AudioNodeInfo::latency_frames`]), so that all inputs to a node are
+    /// time-aligned.
+    ///
+    /// This avoids phasing issues (comb filtering) when mixing parallel paths
+    /// together, such as a dry path with a path containing a convolution node.
+    ///
+    /// When the graph is recompiled, an inserted delay keeps its state as
+    /// long as it is on the same edge and has the same length. If the length
+    /// changes (i.e. because a node was added to a parallel path), then the
+    /// delay restarts from silence, which may cause an audible gap on that
+    /// edge.
+    ///
+    /// By default this is set to `false`.
+    ///
+    /// [`AudioNodeInfo::latency_frames`]: firewheel_core::node::AudioNodeInfo::latency_frames
+    pub auto_latency_compensation: bool,
+
+    /// If `true`, then newly connected edges will be faded in and removed
+    /// edges will be faded out over [`FirewheelConfig::declick_seconds`],
+    /// preventing audible clicks when connecting or disconnecting a signal
+    /// path while audio is playing.
+    ///
+    /// Note, edges which are removed as a result of removing a node are
+    /// not faded out.
+    ///
+    /// By default this is set to `false`.
+    pub declick_connections: bool,
+
+    /// If `Some`, then a look-ahead brickwall limiter is applied to the final
+    /// output of the audio graph, preventing the output from exceeding the
+    /// configured ceiling without the distortion caused by hard clipping.
+    ///
+    /// The limiter is applied after [`FirewheelFlags::detect_clipping_on_output`]
+    /// and before [`FirewheelFlags::hard_clip_outputs`].
+    ///
+    /// The look-ahead of the limiter is reported as latency, see
+    /// [`FirewheelContext::graph_output_latency_frames`].
+    ///
+    /// By default this is set to `None`.
+    pub output_limiter: Option<LimiterConfig>,
+
+    /// If `Some`, then the audio graph is processed in blocks of at most this
+    /// many frames (samples in a single channel of audio), regardless of the
+    /// block size given by the audio backend.
+    ///
+    /// Smaller blocks increase the granularity of parameter updates and can
+    /// reduce the worst-case cost of nodes with heavy per-block overhead, at
+    /// the cost of some extra processing overhead per backend block. This
+    /// has no effect if the backend's maximum block size is already smaller.
+    ///
+    /// Nodes will see this value as [`StreamInfo::max_block_frames`].
+    ///
+    /// By default this is set to `None`.
+    pub max_internal_block_frames: Option<NonZeroU32>,
+
+    /// The minimum amount of time in seconds without any changes to the
+    /// structure of the audio graph (adding/removing nodes and edges) before
+    /// the graph is recompiled.
+    ///
+    /// All changes made between two calls to [`FirewheelContext::update`]
+    /// are always compiled together. Setting this above `0.0` additionally
+    /// coalesces changes made across several updates, which is useful for
+    /// node graph editors where a user may make many connections in quick
+    /// succession. The recompile is never deferred by more than four times
+    /// this amount. A value which is not finite disables debouncing.
+    ///
+    /// Note, newly added nodes and edges are not heard until the graph is
+    /// recompiled.
+    ///
+    /// By default this is set to `0.0`.
+    pub compile_debounce_seconds: f32,
+
+    /// The number of scratch buffers to allocate for nodes in addition to the
+    /// [`NUM_SCRATCH_BUFFERS`] buffers that are always available.
+    ///
+    /// Nodes access these via [`ProcExtra::extra_scratch_buffers`]. Increase
+    /// this if you use nodes which need a large number of scratch buffers
+    /// (i.e. a many-voice unison oscillator or a multi-band equalizer).
+    ///
+    /// By default this is set to `0`.
+    ///
+    /// [`NUM_SCRATCH_BUFFERS`]: firewheel_core::node::NUM_SCRATCH_BUFFERS
+    /// [`ProcExtra::extra_scratch_buffers`]: firewheel_core::node::ProcExtra::extra_scratch_buffers
+    pub num_extra_scratch_buffers: usize,
+
+    /// If `true`, then nodes which do not depend on each other are processed
+    /// in parallel on a small pool of worker threads alongside the audio
+    /// thread. The worker threads request real-time priority, and they spin
+    /// briefly while waiting for work before going to sleep.
+    ///
+    /// This can help large graphs with many independent branches on multicore
+    /// machines, at the cost of extra memory (buffers are no longer reused
+    /// between nodes) and some synchronization overhead each block. Small
+    /// graphs are usually faster when processed on a single thread.
+    ///
+    /// Note, while this is enabled, nodes (other than nodes with no inputs or
+    /// outputs) are given per-thread scratch buffers and loggers, and an empty
+    /// [`ProcStore`]. The per-node profiling data of each node is the time it
+    /// took to process on whichever thread processed it.
+    ///
+    /// This has no effect if the `std` feature is disabled.
+    ///
+    /// By default this is set to `false`.
+    pub parallel_processing: bool,
 }
 
 impl Default for FirewheelConfig {
@@ -183,6 +298,13 @@ impl Default for FirewheelConfig {
             logger_config: RealtimeLoggerConfig::default(),
             proc_store_capacity: 8,
             clamp_graph_inputs_below: Some(Volume::Decibels(-70.0)),
+            auto_latency_compensation: false,
+            declick_connections: false,
+            output_limiter: None,
+            max_internal_block_frames: None,
+            compile_debounce_seconds: 0.0,
+            num_extra_scratch_buffers: 0,
+            parallel_processing: false,
         }
     }
 }
@@ -285,6 +407,9 @@ pub(crate) struct ProcessorChannel {
     pub(crate) profiler_tx: ProfilerTx,
     #[cfg(feature = "scheduled_events")]
     pub(crate) shared_clock_input: triple_buffer::Input<SharedClock>,
+    /// A logger for each thread which processes nodes in parallel.
+    #[cfg(feature = "std")]
+    pub(crate) thread_loggers: Vec<RealtimeLogger>,
 }
 
 /// A Firewheel context
@@ -296,6 +421,8 @@ pub struct FirewheelContext {
     processor_drop_flag: Option<Arc<AtomicBool>>,
     profiler_rx: ProfilerRx,
     logger_rx: RealtimeLoggerMainThread,
+    #[cfg(feature = "std")]
+    thread_logger_rxs: Vec<RealtimeLoggerMainThread>,
 
     pending_processor_channel: Option<ProcessorChannel>,
     processor_drop_rx: Option<ringbuf::HeapCons<FirewheelProcessorInner>>,
@@ -321,6 +448,12 @@ pub struct FirewheelContext {
     #[cfg(feature = "scheduled_events")]
     queued_clear_scheduled_events: Vec<ClearScheduledEventsEvent>,
 
+    // State snapshots received from node processors which have not been
+    // taken yet.
+    state_snapshots: Vec<(NodeID, Box<dyn Any + Send>)>,
+
+    output_gain: Volume,
+
     config: FirewheelConfig,
 }
 
@@ -346,6 +479,16 @@ impl FirewheelContext {
             triple_buffer::triple_buffer(&SharedClock::default());
 
         let (logger, logger_rx) = firewheel_core::log::realtime_logger(config.logger_config);
+
+        // Each thread which processes nodes in parallel gets its own logger.
+        #[cfg(feature = "std")]
+        let (thread_loggers, thread_logger_rxs): (Vec<_>, Vec<_>) = if graph.num_threads() > 1 {
+            (0..graph.num_threads())
+                .map(|_| firewheel_core::log::realtime_logger(config.logger_config))
+                .unzip()
+        } else {
+            (Vec::new(), Vec::new())
+        };
         let (profiler_tx, profiler_rx) = crate::processor::profiling::profiler_channel(
             config.initial_node_capacity as usize,
             #[cfg(feature = "node_profiling")]
@@ -362,6 +505,8 @@ impl FirewheelContext {
             processor_drop_flag: None,
             profiler_rx,
             logger_rx,
+            #[cfg(feature = "std")]
+            thread_logger_rxs,
             pending_processor_channel: Some(ProcessorChannel {
                 shared_flags: Arc::clone(&shared_flags),
                 from_context_rx,
@@ -371,6 +516,8 @@ impl FirewheelContext {
                 profiler_tx,
                 #[cfg(feature = "scheduled_events")]
                 shared_clock_input,
+                #[cfg(feature = "std")]
+                thread_loggers,
             }),
             processor_drop_rx: None,
             #[cfg(feature = "scheduled_events")]
@@ -388,6 +535,8 @@ impl FirewheelContext {
             initial_event_group_capacity,
             #[cfg(feature = "scheduled_events")]
             queued_clear_scheduled_events: Vec::new(),
+            state_snapshots: Vec::new(),
+            output_gain: Volume::UNITY_GAIN,
             config,
         }
     }
@@ -469,6 +618,11 @@ impl FirewheelContext {
     /// Use [`FirewheelContext::is_active`] to check if the context is ready to
     /// be activated.
     ///
+    /// If the context was previously activated, then the state of all nodes
+    /// is carried over to the new stream. The graph is only recompiled if the
+    /// sample rate, the maximum block size, or the number of channels of the
+    /// stream changed.
+    ///
     /// Note, in rare cases where the audio thread crashes without cleanly dropping
     /// its contents, this may never succeed. Consider adding a timeout to avoid
     /// deadlocking.
@@ -493,6 +647,13 @@ impl FirewheelContext {
             self.sample_rate
         };
 
+        // The processor splits each backend block into blocks of at most
+        // `max_block_frames`.
+        let max_block_frames = match self.config.max_internal_block_frames {
+            Some(cap) => max_block_frames.min(cap),
+            None => max_block_frames,
+        };
+
         let stream_info = StreamInfo {
             sample_rate,
             sample_rate_recip: (sample_rate.get() as f64).recip(),
@@ -510,7 +671,22 @@ impl FirewheelContext {
         self.sample_rate = stream_info.sample_rate;
         self.sample_rate_recip = stream_info.sample_rate_recip;
 
-        let schedule = self.graph.compile(&stream_info)?;
+        // When reusing the processor from a previous stream with the same
+        // configuration, the processor already holds an up-to-date schedule.
+        let needs_compile = maybe_proc_channel.is_some()
+            || self.graph.needs_compile()
+            || self.stream_info.as_ref().is_none_or(|prev| {
+                prev.sample_rate != stream_info.sample_rate
+                    || prev.max_block_frames != stream_info.max_block_frames
+                    || prev.num_stream_in_channels != stream_info.num_stream_in_channels
+                    || prev.num_stream_out_channels != stream_info.num_stream_out_channels
+            });
+
+        let schedule = if needs_compile {
+            Some(self.graph.compile(&stream_info)?)
+        } else {
+            None
+        };
 
         let (drop_tx, drop_rx) = ringbuf::HeapRb::<FirewheelProcessorInner>::new(1).split();
 
@@ -524,7 +700,9 @@ impl FirewheelContext {
                         .config
                         .clamp_graph_inputs_below
                         .map(|v| v.amp()),
+                    output_limiter: self.config.output_limiter,
                     node_event_buffer_capacity: self.config.event_queue_capacity,
+                    num_extra_scratch_buffers: self.config.num_extra_scratch_buffers,
                     #[cfg(feature = "scheduled_events")]
                     scheduled_event_buffer_capacity: self.config.scheduled_event_capacity,
                 },
@@ -543,9 +721,10 @@ impl FirewheelContext {
             processor
         };
 
-        if self
-            .send_message_to_processor(ContextToProcessorMsg::NewSchedule(schedule))
-            .is_err()
+        if let Some(schedule) = schedule
+            && self
+                .send_message_to_processor(ContextToProcessorMsg::NewSchedule(schedule))
+                .is_err()
         {
             panic!("Firewheel message channel is full!");
         }
@@ -606,6 +785,48 @@ impl FirewheelContext {
         }
     }
 
+    /// The total latency in frames (samples in a single channel of audio) of
+    /// the longest path to the graph output node, accumulated from the latency
+    /// each node declares with [`AudioNodeInfo::latency_frames`].
+    ///
+    /// This reflects the most recently compiled graph, so changes made to the
+    /// graph will only be reflected after the next call to
+    /// [`FirewheelContext::update`].
+    ///
+    /// This includes the look-ahead of [`FirewheelConfig::output_limiter`],
+    /// but it does not include the latency of the audio stream itself.
+    ///
+    /// Returns `None` if the context is not currently active.
+    ///
+    /// [`AudioNodeInfo::latency_frames`]: firewheel_core::node::AudioNodeInfo::latency_frames
+    pub fn graph_output_latency_frames(&self) -> Option<u32> {
+        if self.is_active() {
+            Some(self.graph.graph_out_latency_frames())
+        } else {
+            None
+        }
+    }
+
+    /// Statistics about the most recently compiled graph, such as the number
+    /// of nodes and edges and the depth of the longest path.
+    ///
+    /// This is cheap to call, and can be used to gauge the complexity of the
+    /// graph without profiling it (i.e. to decide when to cap voice counts or
+    /// to switch to cheaper effects on weak hardware).
+    ///
+    /// This reflects the most recently compiled graph, so changes made to the
+    /// graph will only be reflected after the next call to
+    /// [`FirewheelContext::update`].
+    ///
+    /// Returns `None` if the context is not currently active.
+    pub fn graph_stats(&self) -> Option<GraphStats> {
+        if self.is_active() {
+            Some(self.graph.graph_stats())
+        } else {
+            None
+        }
+    }
+
     /// Get the current time of the audio clock, without accounting for the delay
     /// between when the clock was last updated and now.
     ///
@@ -791,6 +1012,30 @@ impl FirewheelContext {
             .map_err(|(_, e)| e)
     }
 
+    /// The master gain applied to the final output of the audio graph.
+    pub fn output_gain(&self) -> Volume {
+        self.output_gain
+    }
+
+    /// Set the master gain applied to the final output of the audio graph.
+    ///
+    /// Changes to the gain are smoothed to avoid clicks. The gain is applied
+    /// before [`FirewheelConfig::output_limiter`] and
+    /// [`FirewheelFlags::hard_clip_outputs`].
+    ///
+    /// This can be set while the context is active or inactive.
+    ///
+    /// If the message channel is full, then this will return an error.
+    pub fn set_output_gain(&mut self, gain: Volume) -> Result<(), UpdateError> {
+        if self.output_gain == gain {
+            return Ok(());
+        }
+        self.output_gain = gain;
+
+        self.send_message_to_processor(ContextToProcessorMsg::SetOutputGain(gain.amp()))
+            .map_err(|(_, e)| e)
+    }
+
     /// Returns `true` if both the `FirewheelFlags::VALIDATE_OUTPUT_DOES_NOT_CLIP`
     /// flag is set and a sample in the final output buffer fell outside the range
     /// `[-1.0, 1.0]`.
@@ -807,42 +1052,89 @@ impl FirewheelContext {
         self.profiler_rx.fetch_info()
     }
 
-    /// Update the firewheel context.
+    /// Retrieve the latest performance profiling data of the given node.
     ///
-    /// This must be called regularly (i.e. once every frame).
-    pub fn update(&mut self) -> Result<(), UpdateError> {
-        self.logger_rx.flush(
-            |msg| {
-                #[cfg(feature = "tracing")]
-                tracing::error!("{}", msg);
+    /// Returns `None` if the node does not exist in the most recent data
+    /// or if [`FirewheelFlags::profile_nodes`] is set to `false`.
+    #[cfg(feature = "node_profiling")]
+    pub fn node_profile(&mut self, node_id: NodeID) -> Option<NodeProfileData> {
+        self.profiler_rx.fetch_info().node(node_id).copied()
+    }
 
-                #[cfg(all(feature = "log", not(feature = "tracing")))]
-                log::error!("{}", msg);
+    /// Returns `true` if the graph has changed since it was last compiled,
+    /// meaning that it will be recompiled on the next call to
+    /// [`FirewheelContext::update`].
+    pub fn needs_compile(&self) -> bool {
+        self.graph.needs_compile()
+    }
 
-                let _ = msg;
-            },
-            |msg| {
-                #[cfg(feature = "tracing")]
-                tracing::debug!("{}", msg);
+    /// Defer compiling the graph until [`FirewheelContext::end_batch`] is
+    /// called, so that a flurry of edits (i.e. in a node graph editor) results
+    /// in exactly one recompile.
+    ///
+    /// Events are still sent to the processor while a batch is in progress,
+    /// but newly added nodes and edges are not heard until the batch has
+    /// ended and the graph is recompiled.
+    ///
+    /// Batches can be nested, in which case the graph is not compiled until
+    /// the outermost batch has ended.
+    pub fn begin_batch(&mut self) {
+        self.graph.begin_batch();
+    }
 
-                #[cfg(all(feature = "log", not(feature = "tracing")))]
-                log::debug!("{}", msg);
+    /// End a batch of edits started with [`FirewheelContext::begin_batch`].
+    ///
+    /// The graph is recompiled on the next call to
+    /// [`FirewheelContext::update`] if it was changed during the batch.
+    pub fn end_batch(&mut self) {
+        self.graph.end_batch();
+    }
 
-                let _ = msg;
-            },
-        );
+    /// Update the firewheel context.
+    ///
+    /// This must be called regularly (i.e. once every frame).
+    pub fn update(&mut self) -> Result<(), UpdateError> {
+        flush_logger(&mut self.logger_rx);
+        #[cfg(feature = "std")]
+        for logger_rx in self.thread_logger_rxs.iter_mut() {
+            flush_logger(logger_rx);
+        }
 
         firewheel_core::collector::GlobalRtGc::collect();
 
         for msg in self.from_processor_rx.pop_iter() {
             match msg {
                 ProcessorToContextMsg::DropEventGroup(mut event_group) => {
-                    event_group.clear();
+                    // The only events left in the group are state requests.
+                    for event in event_group.drain(..) {
+                        if let NodeEventType::RequestState {
+                            mut snapshot,
+                            filled: true,
+                        } = event.event
+                        {
+                            let mut state: Box<dyn Any + Send> = Box::new(());
+                            snapshot.swap(&mut state);
+
+                            // Snapshots of different parameters on the same node
+                            // are kept separately.
+                            self.state_snapshots.retain(|(id, s)| {
+                                *id != event.node_id
+                                    || (**s).type_id() != (*state).type_id()
+                                    || (s.is::<ParamValueSnapshot>()
+                                        && !ParamValueSnapshot::is_same_param(&**s, &*state))
+                            });
+                            self.state_snapshots.push((event.node_id, state));
+                        }
+                    }
+
                     self.event_group_pool.push(event_group);
                 }
                 ProcessorToContextMsg::DropSchedule(schedule_data) => {
                     self.graph.drop_old_schedule_data(schedule_data);
                 }
+                ProcessorToContextMsg::EdgeFadeFinished(fade_id) => {
+                    self.graph.edge_fade_finished(fade_id);
+                }
                 #[cfg(feature = "musical_transport")]
                 ProcessorToContextMsg::DropTransportState(transport_state) => {
                     if self.transport_state_alloc_reuse.is_none() {
@@ -859,8 +1151,41 @@ impl FirewheelContext {
         self.graph
             .update(self.stream_info.as_ref(), &mut self.event_group);
 
+        self.flush_to_processor(false)
+    }
+
+    /// Immediately send all queued events, along with any pending changes to
+    /// the graph, to the processor.
+    ///
+    /// Unlike [`FirewheelContext::update`], this ignores
+    /// [`FirewheelConfig::compile_debounce_seconds`]. This is intended for
+    /// offline rendering and tests, where calling this before processing a
+    /// block (i.e. with [`ManualBackend::flush_and_process`]) guarantees that
+    /// the processor receives the events at the start of that block.
+    ///
+    /// Note, when using a realtime backend, the events are still only
+    /// received at the start of the next block processed by the audio
+    /// thread.
+    ///
+    /// [`ManualBackend::flush_and_process`]: crate::backend::ManualBackend::flush_and_process
+    pub fn flush_events_now(&mut self) -> Result<(), UpdateError> {
+        self.graph
+            .update(self.stream_info.as_ref(), &mut self.event_group);
+
+        self.flush_to_processor(true)
+    }
+
+    /// Compile the graph if needed, and send any queued messages to the
+    /// processor.
+    fn flush_to_processor(&mut self, ignore_debounce: bool) -> Result<(), UpdateError> {
         if self.is_active() {
-            if self.graph.needs_compile() {
+            let compile = if ignore_debounce {
+                self.graph.needs_compile() && !self.graph.is_batching()
+            } else {
+                self.graph.compile_is_due()
+            };
+
+            if compile {
                 let schedule_data = self.graph.compile(self.stream_info.as_ref().unwrap())?;
 
                 if let Err((msg, e)) = self
@@ -958,7 +1283,7 @@ impl FirewheelContext {
     ) -> Result<NodeID, NodeError> {
         let node_id = self.add_node(node, config)?;
         if bypassed {
-            self.queue_event_for(node_id, NodeEventType::SetBypassed(true));
+            self.set_node_bypassed(node_id, true);
         }
         Ok(node_id)
     }
@@ -972,7 +1297,7 @@ impl FirewheelContext {
     ) -> Result<NodeID, NodeError> {
         let node_id = self.graph.add_dyn_node(node)?;
         if bypassed {
-            self.queue_event_for(node_id, NodeEventType::SetBypassed(true));
+            self.set_node_bypassed(node_id, true);
         }
         Ok(node_id)
     }
@@ -988,7 +1313,17 @@ impl FirewheelContext {
     /// This will return an error if the ID is of the graph input or graph
     /// output node.
     pub fn remove_node(&mut self, node_id: NodeID) -> Result<SmallVec<[Edge; 4]>, RemoveNodeError> {
-        self.graph.remove_node(node_id, false)
+        let was_soloed = self.node_is_soloed(node_id) == Some(true);
+
+        let removed_edges = self.graph.remove_node(node_id, false)?;
+
+        self.state_snapshots.retain(|(id, _)| *id != node_id);
+
+        if was_soloed {
+            self.sync_output_muted();
+        }
+
+        Ok(removed_edges)
     }
 
     /// Returns `true` if the node exists in the graph.
@@ -1048,6 +1383,89 @@ impl FirewheelContext {
         self.graph.edges()
     }
 
+    /// Serialize the current nodes and edges of the graph into the
+    /// [Graphviz DOT](https://graphviz.org/doc/info/lang.html) format.
+    ///
+    /// Each node is labeled with its debug name and its input and output
+    /// ports, including the graph input and output nodes. Bypassed nodes are
+    /// drawn dashed, and nodes whose outputs are muted are drawn grayed out.
+    ///
+    /// This is useful for debugging routing issues by pasting the output into
+    /// any DOT viewer.
+    pub fn dump_graph_dot(&self) -> String {
+        use core::fmt::Write;
+
+        fn port_labels(out: &mut String, prefix: &str, num_ports: u32) {
+            for i in 0..num_ports {
+                if i > 0 {
+                    out.push('|');
+                }
+                let _ = write!(out, "<{prefix}{i}> {i}");
+            }
+        }
+
+        let graph_in_id = self.graph_in_node_id();
+        let graph_out_id = self.graph_out_node_id();
+
+        let mut out =
+            String::from("digraph firewheel {\n    rankdir=LR;\n    node [shape=record];\n");
+
+        let mut nodes: Vec<&NodeEntry> = self.nodes().collect();
+        nodes.sort_unstable_by_key(|n| n.id);
+
+        for node in nodes {
+            let num_inputs = node.info.channel_config.num_inputs.get();
+            let num_outputs = node.info.channel_config.num_outputs.get();
+
+            let _ = write!(out, "    n{} [label=\"{{", node.id.0.to_bits());
+            if num_inputs > 0 {
+                out.push('{');
+                port_labels(&mut out, "i", num_inputs);
+                out.push_str("}|");
+            }
+            for c in node.info.debug_name.chars() {
+                if matches!(c, '"' | '\\' | '{' | '}' | '|' | '<' | '>') {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+            if num_outputs > 0 {
+                out.push_str("|{");
+                port_labels(&mut out, "o", num_outputs);
+                out.push('}');
+            }
+            out.push_str("}\"");
+
+            if node.id == graph_in_id || node.id == graph_out_id {
+                out.push_str(", style=bold");
+            } else if node.bypassed {
+                out.push_str(", style=dashed");
+            }
+            if node.output_muted {
+                out.push_str(", color=gray, fontcolor=gray");
+            }
+
+            out.push_str("];\n");
+        }
+
+        let mut edges: Vec<&Edge> = self.edges().collect();
+        edges.sort_unstable_by_key(|e| (e.src_node, e.src_port, e.dst_node, e.dst_port));
+
+        for edge in edges {
+            let _ = writeln!(
+                out,
+                "    n{}:o{} -> n{}:i{};",
+                edge.src_node.0.to_bits(),
+                edge.src_port,
+                edge.dst_node.0.to_bits(),
+                edge.dst_port,
+            );
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
     /// Set the number of input and output channels to and from the audio graph.
     ///
     /// Returns the list of edges that were removed.
@@ -1071,6 +1489,9 @@ impl FirewheelContext {
     ///   expensive, so avoid enabling this when calling this method many times
     ///   in a row.
     ///
+    /// Connecting a node to itself is always rejected with
+    /// [`AddEdgeError::SelfConnection`], regardless of `check_for_cycles`.
+    ///
     /// If successful, then this returns a list of edge IDs in order.
     ///
     /// If this returns an error, then the audio graph has not been
@@ -1086,6 +1507,23 @@ impl FirewheelContext {
             .connect(src_node, dst_node, ports_src_dst, check_for_cycles, false)
     }
 
+    /// The same as [`FirewheelContext::connect`], except that this also
+    /// returns whether the graph was changed.
+    ///
+    /// The returned `bool` is `true` if any new edges were added, meaning
+    /// that the graph needs to be recompiled. It is `false` if all of the
+    /// given connections already existed.
+    pub fn connect_with_status(
+        &mut self,
+        src_node: NodeID,
+        dst_node: NodeID,
+        ports_src_dst: &[(PortIdx, PortIdx)],
+        check_for_cycles: bool,
+    ) -> Result<(SmallVec<[EdgeID; 4]>, bool), AddEdgeError> {
+        self.graph
+            .connect_with_status(src_node, dst_node, ports_src_dst, check_for_cycles, false)
+    }
+
     /// Connect two nodes in the graph, connecting output port 0 to input port
     /// 0, output port 1 to input port 1, etc.
     ///
@@ -1195,6 +1633,88 @@ impl FirewheelContext {
             .connect(src_node, dst_node, ports_src_dst, check_for_cycles, false)
     }
 
+    /// Connect all output ports of a node to all input ports of a second node,
+    /// mixing the channels when the number of ports differ.
+    ///
+    /// * `src_node` - The ID of the source node.
+    /// * `dst_node` - The ID of the destination node.
+    /// * `check_for_cycles` - If `true`, then this will run a check to
+    ///   see if adding these edges will create a cycle in the graph, and
+    ///   return an error if it does. Note, checking for cycles can be quite
+    ///   expensive, so avoid enabling this when calling this method many times
+    ///   in a row.
+    ///
+    /// ## Behavior
+    ///
+    /// * If `num_out_ports_on_src_node == num_in_ports_on_dst_node`, then src
+    ///   port 0 will be connected to dst port 0, src port 1 to dst port 1, etc.
+    /// * If `num_out_ports_on_src_node > num_in_ports_on_dst_node`, then the
+    ///   src ports are folded down onto the dst ports, with src port `i` being
+    ///   connected to dst port `i % num_in_ports_on_dst_node`. The gain of each
+    ///   edge is set so that the signals are averaged instead of summed (i.e. a
+    ///   stereo signal connected to a mono input is `(L + R) * 0.5`).
+    /// * If `num_out_ports_on_src_node < num_in_ports_on_dst_node`, then the
+    ///   src ports are spread across the dst ports, with dst port `i` being
+    ///   connected to src port `i % num_out_ports_on_src_node` (i.e. a mono
+    ///   signal connected to a stereo input is sent to both channels).
+    ///
+    /// If successful, then this returns a list of edge IDs in order.
+    ///
+    /// If this returns an error, then the audio graph has not been
+    /// modified.
+    pub fn connect_mixed(
+        &mut self,
+        src_node: NodeID,
+        dst_node: NodeID,
+        check_for_cycles: bool,
+    ) -> Result<SmallVec<[EdgeID; 4]>, AddEdgeError> {
+        let num_src_out_ports = self
+            .node_info(src_node)
+            .ok_or(AddEdgeError::SrcNodeNotFound(src_node))?
+            .info
+            .channel_config
+            .num_outputs
+            .get();
+        let num_dst_in_ports = self
+            .node_info(dst_node)
+            .ok_or(AddEdgeError::DstNodeNotFound(dst_node))?
+            .info
+            .channel_config
+            .num_inputs
+            .get();
+
+        if num_src_out_ports == 0 || num_dst_in_ports == 0 {
+            return Ok(SmallVec::new());
+        }
+
+        let ports_src_dst: SmallVec<[(PortIdx, PortIdx); 8]> =
+            if num_src_out_ports >= num_dst_in_ports {
+                (0..num_src_out_ports)
+                    .map(|i| (i, i % num_dst_in_ports))
+                    .collect()
+            } else {
+                (0..num_dst_in_ports)
+                    .map(|i| (i % num_src_out_ports, i))
+                    .collect()
+            };
+
+        let edge_ids =
+            self.graph
+                .connect(src_node, dst_node, &ports_src_dst, check_for_cycles, false)?;
+
+        if num_src_out_ports > num_dst_in_ports {
+            for (&edge_id, &(_, dst_port)) in edge_ids.iter().zip(ports_src_dst.iter()) {
+                // The number of src ports folded onto this dst port.
+                let num_summed = ports_src_dst.iter().filter(|(_, d)| *d == dst_port).count();
+
+                self.graph
+                    .set_edge_gain(edge_id, (num_summed as f32).recip());
+            }
+        }
+
+        Ok(edge_ids)
+    }
+
     /// Remove connections (edges) between two nodes from the graph.
     ///
     /// * `src_node` - The ID of the source node.
@@ -1203,7 +1723,9 @@ impl FirewheelContext {
     ///   where the first value in a tuple is the output port on `src_node`,
     ///   and the second value in that tuple is the input port on `dst_node`.
     ///
-    /// Returns the list of edges that were successfully removed.
+    /// Returns the list of edges that were successfully removed. If the list
+    /// is empty, then the graph was not changed and does not need to be
+    /// recompiled.
     pub fn disconnect(
         &mut self,
         src_node: NodeID,
@@ -1234,11 +1756,83 @@ impl FirewheelContext {
         self.graph.disconnect_by_edge_id(edge_id, false)
     }
 
+    /// Remove and add connections (edges) in the graph as a single atomic
+    /// operation.
+    ///
+    /// Because all changes are applied before the graph is recompiled, the
+    /// schedule sent to the processor never contains an intermediate state
+    /// where the routing is only partially changed.
+    ///
+    /// * `remove` - The connections to remove. Connections that do not exist
+    ///   in the graph are ignored.
+    /// * `add` - The connections to add.
+    /// * `check_for_cycles` - If `true`, then this will run a check to
+    ///   see if the new routing creates a cycle in the graph, and return an
+    ///   error if it does. Note, checking for cycles can be quite expensive.
+    ///
+    /// If successful, then this returns a list of edge IDs for each connection
+    /// in `add` in order.
+    ///
+    /// If this returns an error, then the audio graph has not been
+    /// modified.
+    pub fn rewire(
+        &mut self,
+        remove: &[Connection],
+        add: &[Connection],
+        check_for_cycles: bool,
+    ) -> Result<SmallVec<[EdgeID; 4]>, AddEdgeError> {
+        let mut new_edge_ids = SmallVec::new();
+
+        self.try_modify_graph(|cx| {
+            for c in remove.iter() {
+                cx.graph
+                    .disconnect(c.src_node, c.dst_node, &[(c.src_port, c.dst_port)]);
+            }
+
+            for c in add.iter() {
+                new_edge_ids.extend(cx.graph.connect(
+                    c.src_node,
+                    c.dst_node,
+                    &[(c.src_port, c.dst_port)],
+                    false,
+                    false,
+                )?);
+            }
+
+            if check_for_cycles && cx.graph.cycle_detected() {
+                return Err(AddEdgeError::CycleDetected);
+            }
+
+            Ok(())
+        })?;
+
+        Ok(new_edge_ids)
+    }
+
     /// Get information about the given [Edge]
     pub fn edge(&self, edge_id: EdgeID) -> Option<&Edge> {
         self.graph.edge(edge_id)
     }
 
+    /// Set the linear gain applied to the signal of the given edge.
+    ///
+    /// By default every edge has a gain of `1.0`. Note, changing the gain of
+    /// an edge causes the audio graph to be recompiled, so this is not
+    /// suitable for automating a volume. Use a `VolumeNode` for that instead.
+    ///
+    /// Returns `false` if the edge does not exist in the audio graph.
+    pub fn set_edge_gain(&mut self, edge_id: EdgeID, gain: f32) -> bool {
+        self.graph.set_edge_gain(edge_id, gain)
+    }
+
+    /// The linear gain applied to the signal of the given edge.
+    ///
+    /// If the edge does not exist in the audio graph, then `None` will be
+    /// returned.
+    pub fn edge_gain(&self, edge_id: EdgeID) -> Option<f32> {
+        self.graph.edge_gain(edge_id)
+    }
+
     /// Runs a check to see if a cycle exists in the audio graph. If a cycle
     /// exists, an error is returned.
     ///
@@ -1274,16 +1868,246 @@ impl FirewheelContext {
         });
     }
 
-    /// Queue a [`NodeEventType::SetBypassed`] event for the given node.
-    pub fn queue_bypassed_for(&mut self, node_id: NodeID, bypassed: bool) {
-        self.queue_event(NodeEvent {
+    /// Request a snapshot of the current state of the given node's processor.
+    ///
+    /// The snapshot is allocated here as `T::default()`, and then it is filled
+    /// in on the audio thread by [`AudioNodeProcessor::snapshot_state`]. Once
+    /// it has been received in a later call to [`FirewheelContext::update`],
+    /// retrieve it with [`FirewheelContext::take_state_snapshot`].
+    ///
+    /// Nodes which support this document the type `T` to use.
+    ///
+    /// Note, this request will not be sent until the event queue is flushed
+    /// in [`FirewheelContext::update`].
+    ///
+    /// [`AudioNodeProcessor::snapshot_state`]: firewheel_core::node::AudioNodeProcessor::snapshot_state
+    pub fn request_state<T: Default + Send + 'static>(&mut self, node_id: NodeID) {
+        self.queue_event_for(
             node_id,
-            #[cfg(feature = "scheduled_events")]
-            time: None,
-            event: NodeEventType::SetBypassed(bypassed),
+            NodeEventType::RequestState {
+                snapshot: OwnedGc::new(Box::new(T::default())),
+                filled: false,
+            },
+        );
+    }
+
+    /// Take the most recent state snapshot of type `T` which has been received
+    /// from the given node, requested with [`FirewheelContext::request_state`].
+    ///
+    /// Returns `None` if no such snapshot has been received yet, or if the
+    /// node does not support snapshots of type `T`.
+    pub fn take_state_snapshot<T: Send + 'static>(&mut self, node_id: NodeID) -> Option<T> {
+        let i = self
+            .state_snapshots
+            .iter()
+            .position(|(id, s)| *id == node_id && s.is::<T>())?;
+
+        let (_, state) = self.state_snapshots.swap_remove(i);
+
+        state.downcast::<T>().ok().map(|s| *s)
+    }
+
+    /// Request the current value of a parameter from the given node's
+    /// processor.
+    ///
+    /// Unlike the parameters stored on the main thread, this is the value
+    /// which is currently audible, including any smoothing and automation
+    /// which is in progress. Once the value has been received in a later call
+    /// to [`FirewheelContext::update`], retrieve it with
+    /// [`FirewheelContext::take_param_value`].
+    ///
+    /// * `path` - The path to the parameter. For a field of a node with a
+    ///   derived [`Patch`] type, this is `ParamPath::Single(i)`, where `i` is
+    ///   the index of the field.
+    ///
+    /// Only nodes which fill in a [`ParamValueSnapshot`] in
+    /// [`AudioNodeProcessor::snapshot_state`] support this.
+    ///
+    /// Note, this request will not be sent until the event queue is flushed
+    /// in [`FirewheelContext::update`].
+    ///
+    /// [`Patch`]: firewheel_core::diff::Patch
+    /// [`AudioNodeProcessor::snapshot_state`]: firewheel_core::node::AudioNodeProcessor::snapshot_state
+    pub fn request_param_value(&mut self, node_id: NodeID, path: ParamPath) {
+        self.queue_event_for(
+            node_id,
+            NodeEventType::RequestState {
+                snapshot: OwnedGc::new(Box::new(ParamValueSnapshot::new(path))),
+                filled: false,
+            },
+        );
+    }
+
+    /// Take the most recent value of a parameter which has been received
+    /// from the given node, requested with
+    /// [`FirewheelContext::request_param_value`].
+    ///
+    /// The value is in the same units as the parameter. The value of a
+    /// `Volume` parameter is in decibels.
+    ///
+    /// Returns `None` if no such value has been received yet, or if the node
+    /// does not support reading back the parameter.
+    pub fn take_param_value(&mut self, node_id: NodeID, path: &ParamPath) -> Option<f32> {
+        let i = self.state_snapshots.iter().position(|(id, s)| {
+            *id == node_id
+                && s.downcast_ref::<ParamValueSnapshot>()
+                    .is_some_and(|s| s.path == *path)
+        })?;
+
+        let (_, state) = self.state_snapshots.swap_remove(i);
+
+        state.downcast::<ParamValueSnapshot>().ok()?.value
+    }
+
+    /// Set whether or not the given node is bypassed.
+    ///
+    /// When a node is bypassed, the engine skips processing it and routes
+    /// its inputs directly to its outputs (any extra outputs are silenced).
+    /// This works for any node without the node needing to opt in, and the
+    /// transition is declicked.
+    ///
+    /// Returns `false` if the node does not exist.
+    ///
+    /// Note, this will not take effect until the event queue is flushed in
+    /// [`FirewheelContext::update`].
+    pub fn set_node_bypassed(&mut self, node_id: NodeID, bypassed: bool) -> bool {
+        match self.graph.set_node_bypassed(node_id, bypassed) {
+            Some(changed) => {
+                if changed {
+                    self.queue_event_for(node_id, NodeEventType::SetBypassed(bypassed));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Clear the internal state of the given node (i.e. filter histories,
+    /// delay lines, and reverb tails).
+    ///
+    /// This is useful for avoiding artifacts after a big jump in parameters,
+    /// or for cutting off stale tails when the scene changes. The node's
+    /// processor must implement [`AudioNodeProcessor::reset_state`],
+    /// otherwise this does nothing.
+    ///
+    /// Returns `false` if the node does not exist.
+    ///
+    /// Note, this will not take effect until the event queue is flushed in
+    /// [`FirewheelContext::update`].
+    ///
+    /// [`AudioNodeProcessor::reset_state`]: firewheel_core::node::AudioNodeProcessor::reset_state
+    pub fn reset_node(&mut self, node_id: NodeID) -> bool {
+        if self.graph.node_info(node_id).is_none() {
+            return false;
+        }
+
+        self.queue_event_for(node_id, NodeEventType::Reset);
+        true
+    }
+
+    /// Returns whether or not the given node is bypassed.
+    ///
+    /// Returns `None` if the node does not exist.
+    pub fn node_is_bypassed(&self, node_id: NodeID) -> Option<bool> {
+        self.graph
+            .node_info(node_id)
+            .map(|node_entry| node_entry.bypassed)
+    }
+
+    /// Set whether or not the given node is muted.
+    ///
+    /// A muted node is still processed, but the engine fades its outputs to
+    /// silence. This works for any node without the node needing to opt in.
+    ///
+    /// Calling this adds the node to the mute/solo group (see
+    /// [`FirewheelContext::set_solo`]). The mute state is kept separately
+    /// from the solo state, so a node that was muted before another node was
+    /// soloed stays muted once the solo is cleared.
+    ///
+    /// Returns `false` if the node does not exist.
+    ///
+    /// Note, this will not take effect until the event queue is flushed in
+    /// [`FirewheelContext::update`].
+    pub fn set_mute(&mut self, node_id: NodeID, muted: bool) -> bool {
+        if !self
+            .graph
+            .set_node_mute_solo(node_id, |state| state.muted = muted)
+        {
+            return false;
+        }
+
+        self.sync_output_muted();
+        true
+    }
+
+    /// Set whether or not the given node is soloed.
+    ///
+    /// While any node is soloed, the outputs of all other nodes in the
+    /// mute/solo group that are not soloed are muted. Once no nodes are
+    /// soloed, every node returns to its own mute state.
+    ///
+    /// Only nodes that have been passed to [`FirewheelContext::set_mute`] or
+    /// [`FirewheelContext::set_solo`] are part of the mute/solo group, so
+    /// call `set_mute(node_id, false)` on each of your mixer channels when
+    /// creating them. Nodes outside of the group (such as effects on a
+    /// shared bus) are never muted by soloing.
+    ///
+    /// Returns `false` if the node does not exist.
+    ///
+    /// Note, this will not take effect until the event queue is flushed in
+    /// [`FirewheelContext::update`].
+    pub fn set_solo(&mut self, node_id: NodeID, soloed: bool) -> bool {
+        if !self
+            .graph
+            .set_node_mute_solo(node_id, |state| state.soloed = soloed)
+        {
+            return false;
+        }
+
+        self.sync_output_muted();
+        true
+    }
+
+    /// Returns whether or not the given node is muted with
+    /// [`FirewheelContext::set_mute`].
+    ///
+    /// Returns `None` if the node does not exist.
+    pub fn node_is_muted(&self, node_id: NodeID) -> Option<bool> {
+        self.graph
+            .node_info(node_id)
+            .map(|node_entry| node_entry.mute_solo.is_some_and(|s| s.muted))
+    }
+
+    /// Returns whether or not the given node is soloed.
+    ///
+    /// Returns `None` if the node does not exist.
+    pub fn node_is_soloed(&self, node_id: NodeID) -> Option<bool> {
+        self.graph
+            .node_info(node_id)
+            .map(|node_entry| node_entry.mute_solo.is_some_and(|s| s.soloed))
+    }
+
+    fn sync_output_muted(&mut self) {
+        let event_group = &mut self.event_group;
+
+        self.graph.sync_output_muted(|node_id, muted| {
+            event_group.push(NodeEvent {
+                node_id,
+                #[cfg(feature = "scheduled_events")]
+                time: None,
+                event: NodeEventType::SetMuted(muted),
+            });
         });
     }
 
+    /// Set whether or not the given node is bypassed.
+    ///
+    /// This is the same as [`FirewheelContext::set_node_bypassed`], except
+    /// that it does nothing if the node does not exist.
+    pub fn queue_bypassed_for(&mut self, node_id: NodeID, bypassed: bool) {
+        self.set_node_bypassed(node_id, bypassed);
+    }
+
     /// Queue an event at a certain time, to be sent to an audio node's processor.
     ///
     /// If `time` is `None`, then the event will occur as soon as the node's
@@ -1305,6 +2129,60 @@ impl FirewheelContext {
         });
     }
 
+    /// Schedule a parameter of a node to follow an automation curve.
+    ///
+    /// * `node_id` - The ID of the node.
+    /// * `path` - The path to the parameter. For a top-level field of a
+    ///   derived [`Patch`] type, this is `ParamPath::Single(i)`, where `i` is
+    ///   the index of the field in declaration order.
+    /// * `curve` - The shape of the curve.
+    /// * `from` - The value at the start of the automation.
+    /// * `to` - The value at the end of the automation. The parameter stays
+    ///   at this value once the automation has finished.
+    /// * `start` - The time the automation starts. If `None`, then it starts
+    ///   as soon as the node's processor receives the event.
+    /// * `duration` - The length of the automation.
+    ///
+    /// The curve is evaluated sample-accurately by the node. Only `f32` and
+    /// `Volume` parameters of nodes which support automation can be
+    /// automated, and the values for a `Volume` parameter are in decibels.
+    /// See [`ProcEvents::drain_patches_with_automation`].
+    ///
+    /// Note, the automation changes the parameter on the audio thread only.
+    /// Set the parameter to `to` on the main thread as well with
+    /// [`Memo::modify_without_diff`], so that a later diff does not undo it.
+    /// Sending a regular patch for the parameter cancels the automation.
+    ///
+    /// Note, this event will not be sent until the event queue is flushed
+    /// in [`FirewheelContext::update`].
+    ///
+    /// [`Patch`]: firewheel_core::diff::Patch
+    /// [`ProcEvents::drain_patches_with_automation`]: firewheel_core::event::ProcEvents::drain_patches_with_automation
+    #[cfg(feature = "scheduled_events")]
+    #[expect(
+        clippy::too_many_arguments,
+        reason = "Mirrors the fields of an automation event"
+    )]
+    pub fn schedule_automation(
+        &mut self,
+        node_id: NodeID,
+        path: ParamPath,
+        curve: AutomationCurve,
+        from: f32,
+        to: f32,
+        start: Option<EventInstant>,
+        duration: DurationSeconds,
+    ) {
+        self.schedule_event_for(
+            node_id,
+            NodeEventType::Automation {
+                automation: Automation::new(curve, from, to, duration.0 as f32),
+                path,
+            },
+            start,
+        );
+    }
+
     /// Construct a [`ContextQueue`] for diffing.
     ///
     /// Returns `None` if the node does not exist in the graph.
@@ -1334,6 +2212,31 @@ impl FirewheelContext {
         }
     }
 
+    /// Diff a collection of memoized node parameters, queueing the resulting
+    /// events for each node.
+    ///
+    /// All of the events are pushed into the same event group, which is sent
+    /// to the processor in the next call to [`FirewheelContext::update`]. This
+    /// is useful when there are many nodes of the same type, such as the
+    /// channel strips of a mixer.
+    ///
+    /// ```
+    /// # use firewheel_core::{diff::{Diff, Memo}, node::NodeID};
+    /// # use firewheel_graph::FirewheelContext;
+    /// # fn diff_all<T: Diff + Clone>(
+    /// #     context: &mut FirewheelContext,
+    /// #     strips: &mut [(NodeID, Memo<T>)],
+    /// # ) {
+    /// let mut strips: Vec<_> = strips.iter_mut().map(|(id, params)| (*id, params)).collect();
+    /// context.diff_all(&mut strips);
+    /// # }
+    /// ```
+    pub fn diff_all<T: Diff + Clone>(&mut self, nodes: &mut [(NodeID, &mut Memo<T>)]) {
+        for (node_id, params) in nodes.iter_mut() {
+            params.update_memo(&mut self.event_queue(*node_id));
+        }
+    }
+
     /// Cancel scheduled events for all nodes.
     ///
     /// This will clear all events that have been scheduled since the last call to
@@ -1380,6 +2283,29 @@ impl FirewheelContext {
     }
 }
 
+fn flush_logger(logger_rx: &mut RealtimeLoggerMainThread) {
+    logger_rx.flush(
+        |msg| {
+            #[cfg(feature = "tracing")]
+            tracing::error!("{}", msg);
+
+            #[cfg(all(feature = "log", not(feature = "tracing")))]
+            log::error!("{}", msg);
+
+            let _ = msg;
+        },
+        |msg| {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("{}", msg);
+
+            #[cfg(all(feature = "log", not(feature = "tracing")))]
+            log::debug!("{}", msg);
+
+            let _ = msg;
+        },
+    );
+}
+
 impl Drop for FirewheelContext {
     fn drop(&mut self) {
         // Wait for the processor to be drop to avoid deallocating it on
@@ -1429,8 +2355,19 @@ pub struct ContextQueue<'a> {
 
 impl ContextQueue<'_> {
     /// Send an event to set the bypass state of the node.
+    ///
+    /// Like [`FirewheelContext::set_node_bypassed`], this keeps track of the
+    /// bypass state of the node, and no event is sent if the state did not
+    /// change.
     pub fn push_bypassed(&mut self, bypassed: bool) {
-        self.push(NodeEventType::SetBypassed(bypassed));
+        if self.context.graph.set_node_bypassed(self.id, bypassed) == Some(true) {
+            self.push(NodeEventType::SetBypassed(bypassed));
+        }
+    }
+
+    /// Send an event to clear the internal state of the node.
+    pub fn push_reset(&mut self) {
+        self.push(NodeEventType::Reset);
     }
 }
 
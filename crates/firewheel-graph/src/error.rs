@@ -37,6 +37,12 @@ pub enum AddEdgeError {
     /// This edge would have created a cycle in the graph.
     #[error("Could not add edge: cycle was detected")]
     CycleDetected,
+    /// The source and destination node are the same node.
+    ///
+    /// Connecting a node to itself is always rejected, even if checking for
+    /// cycles is disabled.
+    #[error("Could not add edge: node {0:?} cannot be connected to itself")]
+    SelfConnection(NodeID),
 }
 
 /// An error occurred while attempting to compile the audio graph
@@ -1,6 +1,7 @@
 use core::any::Any;
 use core::fmt::Debug;
 use core::hash::Hash;
+use core::time::Duration;
 
 #[cfg(not(feature = "std"))]
 use alloc::string::ToString;
@@ -8,8 +9,10 @@ use alloc::string::ToString;
 use bevy_platform::prelude::{Box, Vec};
 
 use bevy_platform::collections::HashMap;
+use bevy_platform::time::Instant;
 use firewheel_core::StreamInfo;
 use firewheel_core::channel_config::{ChannelConfig, ChannelCount};
+use firewheel_core::dsp::declick::DeclickValues;
 use firewheel_core::event::NodeEvent;
 use firewheel_core::node::{ConstructProcessorContext, NodeError, UpdateContext};
 use smallvec::SmallVec;
@@ -24,14 +27,19 @@ use firewheel_core::node::{
 };
 
 pub(crate) use self::compiler::{
-    CompiledSchedule, NodeHeapData, ProcessNodeInfo, ScheduleHeapData,
+    CompiledSchedule, EdgeFade, EdgeGain, NodeHeapData, NodeOutputsInfo, ParallelLevel,
+    ProcessNodeInfo, ScheduleHeapData,
 };
 
-pub use self::compiler::{Edge, EdgeID, NodeEntry, PortIdx};
+pub use self::compiler::{Connection, Edge, EdgeID, GraphStats, MuteSoloState, NodeEntry, PortIdx};
 
 mod compiler;
 mod dummy_node;
 
+/// The maximum number of times [`FirewheelConfig::compile_debounce_seconds`]
+/// a recompile can be deferred by since the first uncompiled edit.
+const MAX_DEBOUNCE_MULTIPLIER: u32 = 4;
+
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 struct EdgeHash {
     pub src_node: NodeID,
@@ -40,6 +48,23 @@ struct EdgeHash {
     pub dst_port: PortIdx,
 }
 
+/// An edge which is currently being faded in or out.
+#[derive(Debug, Clone, Copy)]
+struct DeclickingEdge {
+    /// The edge being faded. If this edge is fading out, then it has already
+    /// been removed from the graph.
+    edge: Edge,
+    /// The ID used to carry over the state of the fade between schedules.
+    state_id: EdgeID,
+    fade_in: bool,
+    /// The gain of the edge at the time it was removed. This is only used
+    /// for edges which are fading out.
+    gain: f32,
+    /// A unique ID for this fade, used by the processor to report when the
+    /// fade has finished.
+    fade_id: u64,
+}
+
 /// The audio graph interface.
 pub(crate) struct AudioGraph {
     nodes: Arena<NodeEntry>,
@@ -50,6 +75,13 @@ pub(crate) struct AudioGraph {
     graph_out_id: NodeID,
     graph_channel_config: ChannelConfig,
     needs_compile: bool,
+    /// The time of the first edit which has not been compiled yet.
+    first_pending_edit: Option<Instant>,
+    /// The time of the most recent edit.
+    last_edit: Option<Instant>,
+    compile_debounce: Duration,
+    /// The number of nested batches of edits which are in progress.
+    batch_depth: u32,
 
     nodes_to_remove_from_schedule: Vec<NodeID>,
     active_nodes_to_remove: HashMap<NodeID, NodeEntry>,
@@ -57,6 +89,16 @@ pub(crate) struct AudioGraph {
 
     prev_node_arena_capacity: usize,
     prev_buffer_capacity: usize,
+    graph_out_latency_frames: u32,
+    graph_stats: GraphStats,
+    auto_latency_compensation: bool,
+    /// The number of threads the schedule is processed on.
+    num_threads: usize,
+    declick_connections: bool,
+    declicking_edges: Vec<DeclickingEdge>,
+    next_fade_id: u64,
+    /// The linear gain of each edge which has a gain other than `1.0`.
+    edge_gains: HashMap<EdgeID, f32>,
 
     modify_guard_stack: Vec<ModifyGraphGuard>,
 }
@@ -70,12 +112,19 @@ impl AudioGraph {
                 num_inputs: ChannelCount::ZERO,
                 num_outputs: config.num_graph_inputs,
             },
+            latency_frames: 0,
         };
         let graph_out_config = DummyNodeConfig {
             channel_config: ChannelConfig {
                 num_inputs: config.num_graph_outputs,
                 num_outputs: ChannelCount::ZERO,
             },
+            // The output limiter delays the output of the graph by its
+            // look-ahead.
+            latency_frames: config
+                .output_limiter
+                .map(|limiter| limiter.lookahead_frames)
+                .unwrap_or(0),
         };
 
         let graph_in_id = NodeID(
@@ -94,12 +143,23 @@ impl AudioGraph {
                 AudioNodeInfo::new()
                     .debug_name("graph_out")
                     .channel_config(graph_out_config.channel_config)
+                    .latency_frames(graph_out_config.latency_frames)
                     .into(),
                 Box::new(Constructor::new(DummyNode, Some(graph_out_config))),
             )),
         );
         nodes[graph_out_id.0].id = graph_out_id;
 
+        // Processing in parallel requires spawning threads.
+        #[cfg(feature = "std")]
+        let num_threads = if config.parallel_processing {
+            1 + crate::processor::num_worker_threads()
+        } else {
+            1
+        };
+        #[cfg(not(feature = "std"))]
+        let num_threads = 1;
+
         Self {
             nodes,
             edges: Arena::with_capacity(config.initial_edge_capacity as usize),
@@ -111,6 +171,13 @@ impl AudioGraph {
                 num_outputs: config.num_graph_outputs,
             },
             needs_compile: true,
+            first_pending_edit: None,
+            last_edit: None,
+            // Values which are NaN, infinite, or too large to represent
+            // disable debouncing rather than panicking.
+            compile_debounce: Duration::try_from_secs_f32(config.compile_debounce_seconds.max(0.0))
+                .unwrap_or(Duration::ZERO),
+            batch_depth: 0,
             nodes_to_remove_from_schedule: Vec::with_capacity(
                 config.initial_node_capacity as usize,
             ),
@@ -118,6 +185,14 @@ impl AudioGraph {
             nodes_to_call_update_method: Vec::new(),
             prev_node_arena_capacity: 0,
             prev_buffer_capacity: 0,
+            graph_out_latency_frames: 0,
+            graph_stats: GraphStats::default(),
+            auto_latency_compensation: config.auto_latency_compensation,
+            num_threads,
+            declick_connections: config.declick_connections,
+            declicking_edges: Vec::new(),
+            next_fade_id: 0,
+            edge_gains: HashMap::default(),
             modify_guard_stack: Vec::new(),
         }
     }
@@ -212,7 +287,7 @@ impl AudioGraph {
             self.nodes_to_call_update_method.push(new_id);
         }
 
-        self.needs_compile = true;
+        self.mark_edited();
 
         if let Some(guard) = self.modify_guard_stack.last_mut() {
             guard.new_nodes.push(new_id);
@@ -236,7 +311,7 @@ impl AudioGraph {
             self.nodes_to_call_update_method.push(new_id);
         }
 
-        self.needs_compile = true;
+        self.mark_edited();
 
         if let Some(guard) = self.modify_guard_stack.last_mut() {
             guard.new_nodes.push(new_id);
@@ -288,7 +363,7 @@ impl AudioGraph {
             ));
         }
 
-        self.needs_compile = true;
+        self.mark_edited();
 
         if !is_restoring_graph_state && let Some(guard) = self.modify_guard_stack.last_mut() {
             guard.removed_nodes.push(node_entry);
@@ -327,6 +402,58 @@ impl AudioGraph {
         self.node_state_dyn_mut(id).and_then(|s| s.downcast_mut())
     }
 
+    /// Set the bypass state of a node.
+    ///
+    /// Returns `None` if the node does not exist, otherwise returns `true`
+    /// if the bypass state changed.
+    pub(crate) fn set_node_bypassed(&mut self, id: NodeID, bypassed: bool) -> Option<bool> {
+        self.nodes.get_mut(id.0).map(|node_entry| {
+            let changed = node_entry.bypassed != bypassed;
+            node_entry.bypassed = bypassed;
+            changed
+        })
+    }
+
+    /// Modify the mute/solo state of a node, adding the node to the mute/solo
+    /// group if it is not already a part of it.
+    ///
+    /// Returns `false` if the node does not exist.
+    pub(crate) fn set_node_mute_solo(
+        &mut self,
+        id: NodeID,
+        f: impl FnOnce(&mut MuteSoloState),
+    ) -> bool {
+        let Some(node_entry) = self.nodes.get_mut(id.0) else {
+            return false;
+        };
+
+        (f)(node_entry.mute_solo.get_or_insert_default());
+
+        true
+    }
+
+    /// Recompute which nodes have their outputs muted from the mute/solo
+    /// state of each node.
+    ///
+    /// `on_changed` is called for every node whose state changed.
+    pub(crate) fn sync_output_muted(&mut self, mut on_changed: impl FnMut(NodeID, bool)) {
+        let any_soloed = self
+            .nodes
+            .iter()
+            .any(|(_, n)| n.mute_solo.is_some_and(|s| s.soloed));
+
+        for (_, node_entry) in self.nodes.iter_mut() {
+            let output_muted = node_entry
+                .mute_solo
+                .is_some_and(|s| s.muted || (any_soloed && !s.soloed));
+
+            if node_entry.output_muted != output_muted {
+                node_entry.output_muted = output_muted;
+                (on_changed)(node_entry.id, output_muted);
+            }
+        }
+    }
+
     /// Get a type-erased, mutable reference to the custom state of a node.
     pub fn node_state_dyn_mut(&mut self, id: NodeID) -> Option<&mut dyn Any> {
         self.nodes
@@ -369,7 +496,7 @@ impl AudioGraph {
                 }
             }
 
-            self.needs_compile = true;
+            self.mark_edited();
         }
 
         let graph_out_node = self.nodes.get_mut(self.graph_in_id.0).unwrap();
@@ -388,7 +515,7 @@ impl AudioGraph {
                 }
             }
 
-            self.needs_compile = true;
+            self.mark_edited();
         }
 
         self.graph_channel_config = channel_config;
@@ -421,6 +548,27 @@ impl AudioGraph {
         check_for_cycles: bool,
         is_restoring_graph_state: bool,
     ) -> Result<SmallVec<[EdgeID; 4]>, AddEdgeError> {
+        self.connect_with_status(
+            src_node,
+            dst_node,
+            ports_src_dst,
+            check_for_cycles,
+            is_restoring_graph_state,
+        )
+        .map(|(edge_ids, _)| edge_ids)
+    }
+
+    /// The same as [`AudioGraph::connect`], except that this also returns
+    /// `true` if any new edges were added, meaning that the graph needs to
+    /// be recompiled.
+    pub fn connect_with_status(
+        &mut self,
+        src_node: NodeID,
+        dst_node: NodeID,
+        ports_src_dst: &[(PortIdx, PortIdx)],
+        check_for_cycles: bool,
+        is_restoring_graph_state: bool,
+    ) -> Result<(SmallVec<[EdgeID; 4]>, bool), AddEdgeError> {
         let src_node_entry = self
             .nodes
             .get(src_node.0)
@@ -431,7 +579,7 @@ impl AudioGraph {
             .ok_or(AddEdgeError::DstNodeNotFound(dst_node))?;
 
         if src_node.0 == dst_node.0 {
-            return Err(AddEdgeError::CycleDetected);
+            return Err(AddEdgeError::SelfConnection(src_node));
         }
 
         for (src_port, dst_port) in ports_src_dst.iter().copied() {
@@ -452,6 +600,7 @@ impl AudioGraph {
         }
 
         let mut edge_ids = SmallVec::new();
+        let mut new_edges: SmallVec<[Edge; 4]> = SmallVec::new();
 
         for (src_port, dst_port) in ports_src_dst.iter().copied() {
             if let Some(id) = self.existing_edges.get(&EdgeHash {
@@ -484,11 +633,12 @@ impl AudioGraph {
             );
 
             edge_ids.push(new_edge_id);
+            new_edges.push(self.edges[new_edge_id.0]);
         }
 
         if check_for_cycles && self.cycle_detected() {
-            for edge_id in edge_ids {
-                self.disconnect_by_edge_id(edge_id, true);
+            for edge in new_edges {
+                self.disconnect_by_edge_id(edge.id, true);
             }
 
             return Err(AddEdgeError::CycleDetected);
@@ -497,9 +647,28 @@ impl AudioGraph {
             guard.new_edges.extend_from_slice(&edge_ids);
         }
 
-        self.needs_compile = true;
+        if is_restoring_graph_state {
+            // The restored edges were never audibly removed, so cancel any
+            // fade outs on them.
+            self.declicking_edges.retain(|d| {
+                d.fade_in
+                    || !new_edges
+                        .iter()
+                        .any(|e| Connection::from(*e) == Connection::from(d.edge))
+            });
+        } else if self.declick_connections {
+            for edge in new_edges.iter() {
+                self.fade_in_edge(*edge);
+            }
+        }
+
+        // Connecting edges which already exist does not change the graph.
+        let changed = !new_edges.is_empty();
+        if changed {
+            self.mark_edited();
+        }
 
-        Ok(edge_ids)
+        Ok((edge_ids, changed))
     }
 
     /// Remove connections (edges) between two nodes from the graph.
@@ -577,6 +746,7 @@ impl AudioGraph {
         is_restoring_graph_state: bool,
     ) -> Option<Edge> {
         if let Some(edge) = self.edges.remove(edge_id.0) {
+            let gain = self.edge_gains.remove(&edge_id).unwrap_or(1.0);
             self.existing_edges.remove(&EdgeHash {
                 src_node: edge.src_node,
                 src_port: edge.src_port,
@@ -584,18 +754,126 @@ impl AudioGraph {
                 dst_port: edge.dst_port,
             });
 
-            self.needs_compile = true;
+            self.mark_edited();
 
             if !is_restoring_graph_state && let Some(guard) = self.modify_guard_stack.last_mut() {
                 guard.removed_edges.push(edge);
             }
 
+            if is_restoring_graph_state {
+                self.declicking_edges.retain(|d| d.edge.id != edge_id);
+            } else if self.declick_connections {
+                self.fade_out_edge(edge, gain);
+            }
+
             Some(edge)
         } else {
             None
         }
     }
 
+    fn fade_in_edge(&mut self, edge: Edge) {
+        // If the same connection is still fading out, then reverse that fade
+        // instead so that the signal remains continuous.
+        let state_id = match self
+            .declicking_edges
+            .iter()
+            .position(|d| !d.fade_in && Connection::from(d.edge) == Connection::from(edge))
+        {
+            Some(i) => self.declicking_edges.swap_remove(i).state_id,
+            None => edge.id,
+        };
+
+        let fade_id = self.new_fade_id();
+        self.declicking_edges.push(DeclickingEdge {
+            edge,
+            state_id,
+            fade_in: true,
+            gain: 1.0,
+            fade_id,
+        });
+    }
+
+    fn fade_out_edge(&mut self, edge: Edge, gain: f32) {
+        // Edges which were removed as a result of removing a node cannot be
+        // faded out.
+        if !self.edge_is_valid(&edge) {
+            self.declicking_edges.retain(|d| d.edge.id != edge.id);
+            return;
+        }
+
+        // If this edge is still fading in, then reverse that fade instead.
+        let state_id = match self
+            .declicking_edges
+            .iter()
+            .position(|d| d.fade_in && d.edge.id == edge.id)
+        {
+            Some(i) => self.declicking_edges.swap_remove(i).state_id,
+            None => edge.id,
+        };
+
+        let fade_id = self.new_fade_id();
+        self.declicking_edges.push(DeclickingEdge {
+            edge,
+            state_id,
+            fade_in: false,
+            gain,
+            fade_id,
+        });
+    }
+
+    fn new_fade_id(&mut self) -> u64 {
+        let fade_id = self.next_fade_id;
+        self.next_fade_id += 1;
+        fade_id
+    }
+
+    /// Returns `true` if both nodes of the given edge exist and its ports
+    /// are in range.
+    fn edge_is_valid(&self, edge: &Edge) -> bool {
+        let (Some(src_node_entry), Some(dst_node_entry)) = (
+            self.nodes.get(edge.src_node.0),
+            self.nodes.get(edge.dst_node.0),
+        ) else {
+            return false;
+        };
+
+        edge.src_port < src_node_entry.info.channel_config.num_outputs.get()
+            && edge.dst_port < dst_node_entry.info.channel_config.num_inputs.get()
+    }
+
+    /// Set the linear gain applied to the signal of the given edge.
+    ///
+    /// Returns `false` if the edge does not exist in this graph.
+    pub fn set_edge_gain(&mut self, edge_id: EdgeID, gain: f32) -> bool {
+        if !self.edges.contains(edge_id.0) {
+            return false;
+        }
+
+        let prev_gain = if gain == 1.0 {
+            self.edge_gains.remove(&edge_id)
+        } else {
+            self.edge_gains.insert(edge_id, gain)
+        };
+
+        if prev_gain.unwrap_or(1.0) != gain {
+            self.mark_edited();
+        }
+
+        true
+    }
+
+    /// The linear gain applied to the signal of the given edge.
+    ///
+    /// If the edge does not exist in this graph, then `None` will be returned.
+    pub fn edge_gain(&self, edge_id: EdgeID) -> Option<f32> {
+        if !self.edges.contains(edge_id.0) {
+            return None;
+        }
+
+        Some(self.edge_gains.get(&edge_id).copied().unwrap_or(1.0))
+    }
+
     /// Get information about the given [Edge]
     pub fn edge(&self, edge_id: EdgeID) -> Option<&Edge> {
         self.edges.get(edge_id.0)
@@ -658,6 +936,81 @@ impl AudioGraph {
         self.needs_compile
     }
 
+    /// Returns `true` if the graph needs to be compiled and the debounce
+    /// time has elapsed since the last edit.
+    ///
+    /// The compile is never deferred by more than
+    /// [`MAX_DEBOUNCE_MULTIPLIER`] times the debounce time since the first
+    /// pending edit, so a continuous stream of edits still gets compiled.
+    pub(crate) fn compile_is_due(&self) -> bool {
+        if !self.needs_compile || self.is_batching() {
+            return false;
+        }
+
+        if self.compile_debounce.is_zero() {
+            return true;
+        }
+
+        let (Some(now), Some(first_pending_edit), Some(last_edit)) =
+            (crate::time::now(), self.first_pending_edit, self.last_edit)
+        else {
+            return true;
+        };
+
+        now.duration_since(last_edit) >= self.compile_debounce
+            || now.duration_since(first_pending_edit)
+                >= self
+                    .compile_debounce
+                    .saturating_mul(MAX_DEBOUNCE_MULTIPLIER)
+    }
+
+    /// Defer compiling the graph until [`AudioGraph::end_batch`] is called.
+    ///
+    /// Batches can be nested, in which case the graph is not compiled until
+    /// the outermost batch has ended.
+    pub fn begin_batch(&mut self) {
+        self.batch_depth += 1;
+    }
+
+    /// End a batch started with [`AudioGraph::begin_batch`].
+    pub fn end_batch(&mut self) {
+        self.batch_depth = self.batch_depth.saturating_sub(1);
+    }
+
+    /// Returns `true` if a batch of edits is in progress.
+    pub fn is_batching(&self) -> bool {
+        self.batch_depth > 0
+    }
+
+    /// Flag that the graph was edited and needs to be recompiled.
+    fn mark_edited(&mut self) {
+        self.needs_compile = true;
+
+        if !self.compile_debounce.is_zero() {
+            self.last_edit = crate::time::now();
+            if self.first_pending_edit.is_none() {
+                self.first_pending_edit = self.last_edit;
+            }
+        }
+    }
+
+    /// The total latency in frames of the longest path to the graph output
+    /// node in the most recently compiled schedule.
+    pub(crate) fn graph_out_latency_frames(&self) -> u32 {
+        self.graph_out_latency_frames
+    }
+
+    /// Statistics about the most recently compiled schedule.
+    pub(crate) fn graph_stats(&self) -> GraphStats {
+        self.graph_stats
+    }
+
+    /// The number of threads the schedule is processed on.
+    #[cfg(feature = "std")]
+    pub(crate) fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
     pub(crate) fn on_schedule_send_failed(&mut self, failed_schedule: Box<ScheduleHeapData>) {
         self.needs_compile = true;
         self.prev_buffer_capacity = 0;
@@ -672,15 +1025,22 @@ impl AudioGraph {
     pub(crate) fn deactivate(&mut self) {
         self.needs_compile = true;
         self.prev_buffer_capacity = 0;
+        self.declicking_edges.clear();
     }
 
     pub(crate) fn compile(
         &mut self,
         stream_info: &StreamInfo,
     ) -> Result<Box<ScheduleHeapData>, CompileGraphError> {
-        let schedule = self.compile_internal(stream_info.max_block_frames.get() as usize)?;
+        let mut schedule = self.compile_internal(stream_info.max_block_frames.get() as usize)?;
+
+        if schedule.has_edge_fades() {
+            schedule.set_declick_values(DeclickValues::new(stream_info.declick_frames));
+        }
 
         let buffer_capacity = schedule.buffer_capacity();
+        let graph_out_latency_frames = schedule.graph_out_latency_frames();
+        let graph_stats = schedule.graph_stats();
 
         let mut new_node_processors = Vec::new();
         for (_, entry) in self.nodes.iter_mut() {
@@ -703,6 +1063,9 @@ impl AudioGraph {
                         })?,
                     is_pre_process: entry.info.channel_config.is_empty(),
                     in_place_buffers: entry.info.in_place_buffers,
+                    continue_processing_while_silent: entry.info.continue_processing_while_silent,
+                    bypassed: entry.bypassed,
+                    muted: entry.output_muted,
                 });
             }
         }
@@ -730,10 +1093,14 @@ impl AudioGraph {
             new_node_processors,
             new_arena,
             new_profiler_heap_data,
+            self.nodes.capacity(),
         ));
 
         self.needs_compile = false;
+        self.first_pending_edit = None;
         self.prev_buffer_capacity = buffer_capacity;
+        self.graph_out_latency_frames = graph_out_latency_frames;
+        self.graph_stats = graph_stats;
 
         #[cfg(feature = "tracing")]
         tracing::debug!("compiled new audio graph: {:?}", &schedule_data);
@@ -750,14 +1117,88 @@ impl AudioGraph {
     ) -> Result<CompiledSchedule, CompileGraphError> {
         assert!(max_block_frames > 0);
 
-        compiler::compile(
+        let mut edge_gains: Vec<EdgeGain> = self
+            .edge_gains
+            .iter()
+            .map(|(&edge_id, &gain)| EdgeGain { edge_id, gain })
+            .collect();
+
+        if self.declicking_edges.is_empty() {
+            return compiler::compile(
+                &mut self.nodes,
+                &mut self.edges,
+                self.graph_in_id,
+                self.graph_out_id,
+                max_block_frames,
+                self.prev_buffer_capacity,
+                self.auto_latency_compensation,
+                self.num_threads,
+                &[],
+                &edge_gains,
+            );
+        }
+
+        let mut declicking_edges = core::mem::take(&mut self.declicking_edges);
+        declicking_edges.retain(|d| self.edge_is_valid(&d.edge));
+        self.declicking_edges = declicking_edges;
+
+        // Temporarily add the edges which are fading out back into the graph
+        // so that the compiler can schedule their fades.
+        let mut edge_fades = Vec::with_capacity(self.declicking_edges.len());
+        let mut fading_out_edge_ids: SmallVec<[EdgeID; 4]> = SmallVec::new();
+        for d in self.declicking_edges.iter() {
+            let edge_id = if d.fade_in {
+                d.edge.id
+            } else {
+                let edge_id = EdgeID(self.edges.insert(d.edge));
+                self.edges[edge_id.0].id = edge_id;
+                fading_out_edge_ids.push(edge_id);
+
+                if d.gain != 1.0 {
+                    edge_gains.push(EdgeGain {
+                        edge_id,
+                        gain: d.gain,
+                    });
+                }
+
+                edge_id
+            };
+
+            edge_fades.push(EdgeFade {
+                edge_id,
+                state_id: d.state_id,
+                fade_id: d.fade_id,
+                fade_in: d.fade_in,
+            });
+        }
+
+        let res = compiler::compile(
             &mut self.nodes,
             &mut self.edges,
             self.graph_in_id,
             self.graph_out_id,
             max_block_frames,
             self.prev_buffer_capacity,
-        )
+            self.auto_latency_compensation,
+            self.num_threads,
+            &edge_fades,
+            &edge_gains,
+        );
+
+        for edge_id in fading_out_edge_ids.iter() {
+            self.edges.remove(edge_id.0);
+        }
+
+        if let Err(CompileGraphError::CycleDetected) = &res
+            && !fading_out_edge_ids.is_empty()
+        {
+            // The edges which are fading out form a cycle with the new edges,
+            // so don't fade them out.
+            self.declicking_edges.retain(|d| d.fade_in);
+            return self.compile_internal(max_block_frames);
+        }
+
+        res
     }
 
     pub(crate) fn update(
@@ -785,6 +1226,24 @@ impl AudioGraph {
         }
     }
 
+    /// Called when the processor reports that the given edge fade has
+    /// finished. If an edge has finished fading out, then the graph is
+    /// recompiled to remove it from the schedule.
+    pub(crate) fn edge_fade_finished(&mut self, fade_id: u64) {
+        let Some(i) = self
+            .declicking_edges
+            .iter()
+            .position(|d| d.fade_id == fade_id)
+        else {
+            // The fade was reversed or cancelled before it finished.
+            return;
+        };
+
+        if !self.declicking_edges.swap_remove(i).fade_in {
+            self.needs_compile = true;
+        }
+    }
+
     pub(crate) fn drop_old_schedule_data(&mut self, mut data: Box<ScheduleHeapData>) {
         for n in data.removed_nodes.drain(..) {
             let id = n.id;
@@ -1,4 +1,5 @@
 use alloc::{collections::VecDeque, rc::Rc};
+use firewheel_core::dsp::declick::Declicker;
 use firewheel_core::node::{AudioNodeInfoInner, DynAudioNode, NodeID};
 use smallvec::SmallVec;
 use thunderdome::Arena;
@@ -10,8 +11,13 @@ use crate::error::CompileGraphError;
 
 mod schedule;
 
-pub(crate) use schedule::{CompiledSchedule, NodeHeapData, ProcessNodeInfo, ScheduleHeapData};
-use schedule::{InBufferAssignment, OutBufferAssignment, PreProcNode, ScheduledNode};
+pub(crate) use schedule::{
+    CompiledSchedule, NodeHeapData, NodeOutputsInfo, ParallelLevel, ProcessNodeInfo,
+    ScheduleHeapData,
+};
+use schedule::{
+    InBufferAssignment, OutBufferAssignment, PreProcNode, ScheduleLevels, ScheduledNode,
+};
 
 pub struct NodeEntry {
     pub id: NodeID,
@@ -20,6 +26,19 @@ pub struct NodeEntry {
     /// this field must remain !Send
     pub dyn_node: Box<dyn DynAudioNode>,
     pub processor_constructed: bool,
+    /// Whether or not this node is currently set to be bypassed.
+    ///
+    /// See [`FirewheelContext::set_node_bypassed`](crate::FirewheelContext::set_node_bypassed).
+    pub bypassed: bool,
+    /// The mute/solo state of this node, or `None` if this node is not part
+    /// of the mute/solo group.
+    ///
+    /// See [`FirewheelContext::set_mute`](crate::FirewheelContext::set_mute)
+    /// and [`FirewheelContext::set_solo`](crate::FirewheelContext::set_solo).
+    pub mute_solo: Option<MuteSoloState>,
+    /// Whether or not the outputs of this node are currently silenced, either
+    /// because it is muted or because another node is soloed.
+    pub output_muted: bool,
     /// The edges connected to this node's input ports.
     incoming: SmallVec<[Edge; 4]>,
     /// The edges connected to this node's output ports.
@@ -37,15 +56,48 @@ impl NodeEntry {
             info,
             dyn_node,
             processor_constructed: false,
+            bypassed: false,
+            mute_solo: None,
+            output_muted: false,
             incoming: SmallVec::new(),
             outgoing: SmallVec::new(),
         }
     }
 }
 
+/// The mute/solo state of a node in the mute/solo group.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MuteSoloState {
+    /// Whether or not the node is muted.
+    pub muted: bool,
+    /// Whether or not the node is soloed.
+    pub soloed: bool,
+}
+
 /// The index of an input/output port on a particular node.
 pub type PortIdx = u32;
 
+/// Statistics about the most recently compiled audio graph.
+///
+/// These can be used as a cheap estimate of the complexity of the graph,
+/// i.e. to decide whether to reduce voice counts or switch to cheaper
+/// effects on weak hardware.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GraphStats {
+    /// The total number of nodes in the graph, including the graph input
+    /// and output nodes.
+    pub num_nodes: usize,
+    /// The total number of edges in the graph.
+    pub num_edges: usize,
+    /// The number of edges in the longest path through the graph.
+    pub max_depth: usize,
+    /// The maximum number of nodes at the same depth in the graph.
+    ///
+    /// Nodes at the same depth do not depend on each other, so this is an
+    /// estimate of how many nodes could be processed in parallel.
+    pub max_width: usize,
+}
+
 /// A globally unique identifier for an [Edge].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct EdgeID(pub(super) thunderdome::Index);
@@ -65,6 +117,47 @@ pub struct Edge {
     pub dst_port: PortIdx,
 }
 
+/// A description of a connection from a source node and port to a
+/// destination node and port.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Connection {
+    /// The ID of the source node.
+    pub src_node: NodeID,
+    /// The index of the output port on the source node.
+    pub src_port: PortIdx,
+    /// The ID of the destination node.
+    pub dst_node: NodeID,
+    /// The index of the input port on the destination node.
+    pub dst_port: PortIdx,
+}
+
+impl Connection {
+    pub const fn new(
+        src_node: NodeID,
+        src_port: PortIdx,
+        dst_node: NodeID,
+        dst_port: PortIdx,
+    ) -> Self {
+        Self {
+            src_node,
+            src_port,
+            dst_node,
+            dst_port,
+        }
+    }
+}
+
+impl From<Edge> for Connection {
+    fn from(edge: Edge) -> Self {
+        Self {
+            src_node: edge.src_node,
+            src_port: edge.src_port,
+            dst_node: edge.dst_node,
+            dst_port: edge.dst_port,
+        }
+    }
+}
+
 /// A reference to an abstract buffer during buffer allocation.
 #[derive(Debug, Clone, Copy)]
 struct BufferRef {
@@ -82,15 +175,18 @@ struct BufferAllocator {
     free_list: Vec<BufferRef>,
     /// The maximum number of buffers used
     count: usize,
+    /// Whether released buffers may be reallocated
+    reuse_buffers: bool,
 }
 
 impl BufferAllocator {
     /// Create a new allocator, `num_types` defines the number
     /// of buffer types we may allocate.
-    fn new(initial_capacity: usize) -> Self {
+    fn new(initial_capacity: usize, reuse_buffers: bool) -> Self {
         Self {
             free_list: Vec::with_capacity(initial_capacity),
             count: 0,
+            reuse_buffers,
         }
     }
 
@@ -109,7 +205,7 @@ impl BufferAllocator {
 
     /// Release a BufferRef
     fn release(&mut self, buffer_ref: Rc<BufferRef>) {
-        if Rc::strong_count(&buffer_ref) == 1 {
+        if self.reuse_buffers && Rc::strong_count(&buffer_ref) == 1 {
             self.free_list.push(BufferRef {
                 idx: buffer_ref.idx,
                 generation: buffer_ref.generation + 1,
@@ -131,6 +227,10 @@ pub fn compile(
     graph_out_id: NodeID,
     max_block_frames: usize,
     prev_buffer_capacity: usize,
+    auto_latency_compensation: bool,
+    num_threads: usize,
+    edge_fades: &[EdgeFade],
+    edge_gains: &[EdgeGain],
 ) -> Result<CompiledSchedule, CompileGraphError> {
     Ok(GraphIR::preprocess(
         nodes,
@@ -139,8 +239,13 @@ pub fn compile(
         graph_out_id,
         max_block_frames,
         prev_buffer_capacity,
+        auto_latency_compensation,
+        num_threads,
+        edge_fades,
+        edge_gains,
     )
     .sort_topologically(true)?
+    .solve_latency_requirements()
     .solve_buffer_requirements()?
     .merge())
 }
@@ -152,8 +257,19 @@ pub fn cycle_detected<'a>(
     graph_out_id: NodeID,
 ) -> bool {
     matches!(
-        GraphIR::preprocess(nodes, edges, graph_in_id, graph_out_id, 0, 0)
-            .sort_topologically(false),
+        GraphIR::preprocess(
+            nodes,
+            edges,
+            graph_in_id,
+            graph_out_id,
+            0,
+            0,
+            false,
+            1,
+            &[],
+            &[],
+        )
+        .sort_topologically(false),
         Err(CompileGraphError::CycleDetected)
     )
 }
@@ -177,6 +293,22 @@ struct GraphIR<'a> {
     max_in_buffers: usize,
     max_out_buffers: usize,
     max_block_frames: usize,
+    /// The total latency in frames of the longest path to the graph output node.
+    graph_out_latency_frames: u32,
+    /// The total latency in frames of the output of each node, indexed by the
+    /// slot of the node ID.
+    output_latencies: Vec<u32>,
+    /// Whether to insert delays on the inputs of nodes to time-align paths with
+    /// differing latencies.
+    auto_latency_compensation: bool,
+    /// The number of threads the schedule is processed on. If this is greater
+    /// than `1`, then the nodes are grouped into levels which can be processed
+    /// in parallel.
+    num_threads: usize,
+    /// The edges which should be faded in or out to avoid clicks.
+    edge_fades: &'a [EdgeFade],
+    /// The edges which have a gain applied to them.
+    edge_gains: &'a [EdgeGain],
 
     prev_buffer_capacity: usize,
 }
@@ -191,6 +323,10 @@ impl<'a> GraphIR<'a> {
         graph_out_id: NodeID,
         max_block_frames: usize,
         prev_buffer_capacity: usize,
+        auto_latency_compensation: bool,
+        num_threads: usize,
+        edge_fades: &'a [EdgeFade],
+        edge_gains: &'a [EdgeGain],
     ) -> Self {
         assert!(nodes.contains(graph_in_id.0));
         assert!(nodes.contains(graph_out_id.0));
@@ -219,6 +355,12 @@ impl<'a> GraphIR<'a> {
             max_in_buffers: 0,
             max_out_buffers: 0,
             max_block_frames,
+            graph_out_latency_frames: 0,
+            output_latencies: vec![],
+            auto_latency_compensation,
+            num_threads,
+            edge_fades,
+            edge_gains,
             prev_buffer_capacity,
         }
     }
@@ -308,8 +450,34 @@ impl<'a> GraphIR<'a> {
         Ok(self)
     }
 
+    /// Accumulate the latency reported by each node along every path in the
+    /// graph, and find the total latency of the longest path to the graph
+    /// output node.
+    fn solve_latency_requirements(mut self) -> Self {
+        self.output_latencies.clear();
+        self.output_latencies.resize(self.nodes.capacity(), 0);
+
+        // Because the schedule is sorted topologically, the latencies of all
+        // nodes connected to this node's inputs are already known.
+        for entry in self.schedule.iter() {
+            let node_entry = &self.nodes[entry.id.0];
+
+            let output_latency = input_latency(node_entry, &self.output_latencies)
+                .saturating_add(node_entry.info.latency_frames);
+
+            if entry.id == self.graph_out_id {
+                self.graph_out_latency_frames = output_latency;
+            } else {
+                self.output_latencies[entry.id.0.slot() as usize] = output_latency;
+            }
+        }
+
+        self
+    }
+
     fn solve_buffer_requirements(mut self) -> Result<Self, CompileGraphError> {
-        let mut allocator = BufferAllocator::new(64);
+        // Nodes which are processed in parallel must never share buffers.
+        let mut allocator = BufferAllocator::new(64, self.num_threads == 1);
         let mut assignment_table: Arena<Rc<BufferRef>> =
             Arena::with_capacity(self.edges.capacity());
         let mut buffers_to_release: Vec<Rc<BufferRef>> = Vec::with_capacity(64);
@@ -322,6 +490,34 @@ impl<'a> GraphIR<'a> {
             let num_inputs = node_entry.info.channel_config.num_inputs.get() as usize;
             let num_outputs = node_entry.info.channel_config.num_outputs.get() as usize;
 
+            // The number of frames each incoming edge must be delayed by in order
+            // for all inputs to this node to be time-aligned.
+            let node_input_latency = if self.auto_latency_compensation {
+                input_latency(node_entry, &self.output_latencies)
+            } else {
+                0
+            };
+            let compensation_frames = |edge: &Edge| -> u32 {
+                node_input_latency
+                    .saturating_sub(self.output_latencies[edge.src_node.0.slot() as usize])
+            };
+            let edge_fade = |edge: &Edge| -> Option<&EdgeFade> {
+                self.edge_fades.iter().find(|fade| fade.edge_id == edge.id)
+            };
+            let edge_gain = |edge: &Edge| -> Option<f32> {
+                self.edge_gains
+                    .iter()
+                    .find(|gain| gain.edge_id == edge.id)
+                    .map(|gain| gain.gain)
+            };
+            // Inserted delays, fades, and gains are processed before any of the sums
+            // of this node, so their output buffers must not alias the inputs of a sum.
+            let has_inserted_inputs = node_entry.incoming.iter().any(|edge| {
+                compensation_frames(edge) > 0
+                    || edge_fade(edge).is_some()
+                    || edge_gain(edge).is_some()
+            });
+
             buffers_to_release.clear();
             if buffers_to_release.capacity() < num_inputs + num_outputs {
                 buffers_to_release
@@ -357,9 +553,34 @@ impl<'a> GraphIR<'a> {
                     // Case 2: The port is an input, and has exactly one incoming edge. Lookup the
                     //         corresponding buffer and assign it. Buffer should not be cleared.
                     //         Release the buffer once the node assignments are done.
+                    //         If the edge needs latency compensation, declicking, or a gain,
+                    //         then the assigned buffer is the output of the inserted
+                    //         delay/fade/gain.
                     let buffer = assignment_table
                         .remove(edges[0].id.0)
                         .expect("No buffer assigned to edge!");
+                    let buffer = insert_delay(
+                        edges[0],
+                        compensation_frames(edges[0]),
+                        buffer,
+                        &mut allocator,
+                        &mut entry.delay_inputs,
+                        &mut buffers_to_release,
+                    );
+                    let buffer = insert_fade(
+                        edge_fade(edges[0]),
+                        buffer,
+                        &mut allocator,
+                        &mut entry.fade_inputs,
+                        &mut buffers_to_release,
+                    );
+                    let buffer = insert_gain(
+                        edge_gain(edges[0]),
+                        buffer,
+                        &mut allocator,
+                        &mut entry.gain_inputs,
+                        &mut buffers_to_release,
+                    );
                     entry.input_buffers.push(InBufferAssignment {
                         buffer_index: buffer.idx,
                         //generation: buffer.generation,
@@ -377,19 +598,60 @@ impl<'a> GraphIR<'a> {
                         //generation: sum_buffer.generation,
                     };
 
-                    // The sum inputs are the corresponding output buffers of the incoming edges.
+                    // The sum inputs are the corresponding output buffers of the incoming edges
+                    // (or the outputs of the inserted delays/fades/gains if the edges need
+                    // latency compensation, declicking, or a gain).
                     let sum_inputs = edges
                         .iter()
-                        .map(|edge| {
+                        .map(|&edge| {
                             let buf = assignment_table
                                 .remove(edge.id.0)
                                 .expect("No buffer assigned to edge!");
+
+                            let delay_frames = compensation_frames(edge);
+                            let fade = edge_fade(edge);
+                            let gain = edge_gain(edge);
+                            if delay_frames > 0 || fade.is_some() || gain.is_some() {
+                                let buf = insert_delay(
+                                    edge,
+                                    delay_frames,
+                                    buf,
+                                    &mut allocator,
+                                    &mut entry.delay_inputs,
+                                    &mut buffers_to_release,
+                                );
+                                let buf = insert_fade(
+                                    fade,
+                                    buf,
+                                    &mut allocator,
+                                    &mut entry.fade_inputs,
+                                    &mut buffers_to_release,
+                                );
+                                let buf = insert_gain(
+                                    gain,
+                                    buf,
+                                    &mut allocator,
+                                    &mut entry.gain_inputs,
+                                    &mut buffers_to_release,
+                                );
+                                let assignment = InBufferAssignment {
+                                    buffer_index: buf.idx,
+                                    should_clear: false,
+                                };
+                                buffers_to_release.push(buf);
+                                return assignment;
+                            }
+
                             let assignment = InBufferAssignment {
                                 buffer_index: buf.idx,
                                 //generation: buf.generation,
                                 should_clear: false,
                             };
-                            allocator.release(buf);
+                            if has_inserted_inputs {
+                                buffers_to_release.push(buf);
+                            } else {
+                                allocator.release(buf);
+                            }
                             assignment
                         })
                         .collect();
@@ -461,6 +723,13 @@ impl<'a> GraphIR<'a> {
 
     /// Merge the GraphIR into a [CompiledSchedule].
     fn merge(self) -> CompiledSchedule {
+        let graph_stats = self.graph_stats();
+        let levels = if self.num_threads > 1 {
+            self.schedule_levels()
+        } else {
+            ScheduleLevels::default()
+        };
+
         CompiledSchedule::new(
             self.pre_proc_nodes,
             self.schedule,
@@ -468,9 +737,224 @@ impl<'a> GraphIR<'a> {
             self.max_out_buffers,
             self.max_block_frames,
             self.graph_in_id,
+            self.graph_out_latency_frames,
+            graph_stats,
+            levels,
+            self.num_threads,
             self.prev_buffer_capacity,
         )
     }
+
+    /// The depth of each node in the schedule, where the depth of a node is
+    /// the length of the longest path from a node with no inputs to it.
+    ///
+    /// The returned depths are in the same order as the schedule.
+    fn node_depths(&self) -> Vec<usize> {
+        let mut depths_by_slot = vec![0usize; self.nodes.capacity()];
+
+        // Because the schedule is sorted topologically, the depths of all
+        // nodes connected to this node's inputs are already known.
+        self.schedule
+            .iter()
+            .map(|entry| {
+                let depth = self.nodes[entry.id.0]
+                    .incoming
+                    .iter()
+                    .map(|edge| depths_by_slot[edge.src_node.0.slot() as usize] + 1)
+                    .max()
+                    .unwrap_or(0);
+
+                depths_by_slot[entry.id.0.slot() as usize] = depth;
+                depth
+            })
+            .collect()
+    }
+
+    /// Group the nodes in the schedule into levels by depth. No two nodes in
+    /// the same level are connected to each other, so the nodes in a level
+    /// can be processed in parallel once all previous levels are processed.
+    fn schedule_levels(&self) -> ScheduleLevels {
+        let depths = self.node_depths();
+
+        // The graph input node is processed before the schedule.
+        let mut node_indices: Vec<usize> = (0..self.schedule.len())
+            .filter(|&i| self.schedule[i].id != self.graph_in_id)
+            .collect();
+        node_indices.sort_by_key(|&i| depths[i]);
+
+        let mut level_ends = Vec::new();
+        for i in 1..=node_indices.len() {
+            if i == node_indices.len() || depths[node_indices[i]] != depths[node_indices[i - 1]] {
+                level_ends.push(i);
+            }
+        }
+
+        ScheduleLevels {
+            node_indices,
+            level_ends,
+        }
+    }
+
+    /// Gather statistics about the graph.
+    fn graph_stats(&self) -> GraphStats {
+        let depths = self.node_depths();
+        let mut widths = vec![0usize; self.schedule.len()];
+        let mut max_depth = 0;
+
+        for &depth in depths.iter() {
+            widths[depth] += 1;
+            max_depth = max_depth.max(depth);
+        }
+
+        GraphStats {
+            num_nodes: self.nodes.len(),
+            num_edges: self.edges.len(),
+            max_depth,
+            max_width: widths.iter().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+/// The total latency in frames of the inputs of the given node.
+fn input_latency(node_entry: &NodeEntry, output_latencies: &[u32]) -> u32 {
+    node_entry
+        .incoming
+        .iter()
+        .map(|edge| output_latencies[edge.src_node.0.slot() as usize])
+        .max()
+        .unwrap_or(0)
+}
+
+/// Insert a delay which compensates for the latency of the given edge.
+///
+/// If `delay_frames` is `0`, then no delay is inserted and `buffer` is returned.
+/// Otherwise, the source buffer is released once the node assignments are done,
+/// and the newly acquired output buffer of the delay is returned.
+fn insert_delay(
+    edge: &Edge,
+    delay_frames: u32,
+    buffer: Rc<BufferRef>,
+    allocator: &mut BufferAllocator,
+    delay_inputs: &mut Vec<InsertedDelay>,
+    buffers_to_release: &mut Vec<Rc<BufferRef>>,
+) -> Rc<BufferRef> {
+    if delay_frames == 0 {
+        return buffer;
+    }
+
+    let delayed_buffer = allocator.acquire();
+
+    delay_inputs.push(InsertedDelay::new(
+        edge.id,
+        InBufferAssignment {
+            buffer_index: buffer.idx,
+            should_clear: false,
+        },
+        OutBufferAssignment {
+            buffer_index: delayed_buffer.idx,
+        },
+        delay_frames,
+    ));
+
+    buffers_to_release.push(buffer);
+
+    delayed_buffer
+}
+
+/// Insert a fade which declicks the given edge.
+///
+/// If `fade` is `None`, then no fade is inserted and `buffer` is returned.
+/// Otherwise, the source buffer is released once the node assignments are done,
+/// and the newly acquired output buffer of the fade is returned.
+fn insert_fade(
+    fade: Option<&EdgeFade>,
+    buffer: Rc<BufferRef>,
+    allocator: &mut BufferAllocator,
+    fade_inputs: &mut Vec<InsertedFade>,
+    buffers_to_release: &mut Vec<Rc<BufferRef>>,
+) -> Rc<BufferRef> {
+    let Some(fade) = fade else {
+        return buffer;
+    };
+
+    let faded_buffer = allocator.acquire();
+
+    fade_inputs.push(InsertedFade {
+        state_id: fade.state_id,
+        fade_id: fade.fade_id,
+        input_buffer: InBufferAssignment {
+            buffer_index: buffer.idx,
+            should_clear: false,
+        },
+        output_buffer: OutBufferAssignment {
+            buffer_index: faded_buffer.idx,
+        },
+        fade_in: fade.fade_in,
+        declicker: Declicker::from_enabled(!fade.fade_in),
+        started: false,
+        finish_reported: false,
+    });
+
+    buffers_to_release.push(buffer);
+
+    faded_buffer
+}
+
+/// Insert a gain which scales the signal of the given edge.
+///
+/// If `gain` is `None`, then no gain is inserted and `buffer` is returned.
+/// Otherwise, the source buffer is released once the node assignments are done,
+/// and the newly acquired output buffer of the gain is returned.
+fn insert_gain(
+    gain: Option<f32>,
+    buffer: Rc<BufferRef>,
+    allocator: &mut BufferAllocator,
+    gain_inputs: &mut Vec<InsertedGain>,
+    buffers_to_release: &mut Vec<Rc<BufferRef>>,
+) -> Rc<BufferRef> {
+    let Some(gain) = gain else {
+        return buffer;
+    };
+
+    let scaled_buffer = allocator.acquire();
+
+    gain_inputs.push(InsertedGain {
+        input_buffer: InBufferAssignment {
+            buffer_index: buffer.idx,
+            should_clear: false,
+        },
+        output_buffer: OutBufferAssignment {
+            buffer_index: scaled_buffer.idx,
+        },
+        gain,
+    });
+
+    buffers_to_release.push(buffer);
+
+    scaled_buffer
+}
+
+/// An edge which should be faded in or out to avoid clicks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EdgeFade {
+    /// The ID of the edge in the graph being compiled.
+    pub edge_id: EdgeID,
+    /// The ID used to carry over the state of the fade between schedules.
+    pub state_id: EdgeID,
+    /// A unique ID for this fade, used by the processor to report when the
+    /// fade has finished.
+    pub fade_id: u64,
+    /// `true` to fade the edge in, `false` to fade the edge out.
+    pub fade_in: bool,
+}
+
+/// An edge which has a gain applied to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct EdgeGain {
+    /// The ID of the edge in the graph being compiled.
+    pub edge_id: EdgeID,
+    /// The linear gain applied to the signal of the edge.
+    pub gain: f32,
 }
 
 #[derive(Debug, Clone)]
@@ -478,3 +962,61 @@ struct InsertedSum {
     input_buffers: SmallVec<[InBufferAssignment; 4]>,
     output_buffer: OutBufferAssignment,
 }
+
+/// A delay inserted on an edge to compensate for the latency of parallel
+/// paths in the graph.
+#[derive(Clone)]
+struct InsertedDelay {
+    edge_id: EdgeID,
+    input_buffer: InBufferAssignment,
+    output_buffer: OutBufferAssignment,
+    delay_line: Vec<f32>,
+    ptr: usize,
+    num_silent_frames: usize,
+}
+
+impl InsertedDelay {
+    fn new(
+        edge_id: EdgeID,
+        input_buffer: InBufferAssignment,
+        output_buffer: OutBufferAssignment,
+        delay_frames: u32,
+    ) -> Self {
+        Self {
+            edge_id,
+            input_buffer,
+            output_buffer,
+            delay_line: vec![0.0; delay_frames as usize],
+            ptr: 0,
+            num_silent_frames: delay_frames as usize,
+        }
+    }
+
+    fn delay_frames(&self) -> usize {
+        self.delay_line.len()
+    }
+}
+
+/// A fade inserted on an edge to declick a newly connected or disconnected
+/// path in the graph.
+#[derive(Debug, Clone)]
+struct InsertedFade {
+    state_id: EdgeID,
+    fade_id: u64,
+    input_buffer: InBufferAssignment,
+    output_buffer: OutBufferAssignment,
+    fade_in: bool,
+    declicker: Declicker,
+    started: bool,
+    /// Whether the processor has reported that this fade has finished.
+    finish_reported: bool,
+}
+
+/// A gain inserted on an edge, i.e. to compensate for the increase in level
+/// when summing several channels into one.
+#[derive(Debug, Clone)]
+struct InsertedGain {
+    input_buffer: InBufferAssignment,
+    output_buffer: OutBufferAssignment,
+    gain: f32,
+}
@@ -6,14 +6,17 @@ use thunderdome::Arena;
 
 use firewheel_core::{
     channel_config::MAX_CHANNELS,
-    dsp::buffer::SequentialBuffer,
+    dsp::{
+        buffer::SequentialBuffer,
+        declick::{DeclickFadeCurve, DeclickValues, Declicker},
+    },
     mask::{ConnectedMask, ConstantMask, MaskType, SilenceMask},
     node::{AudioNodeProcessor, ProcBuffers, ProcessStatus},
 };
 
-use crate::processor::profiling::ProfilerHeapData;
+use crate::processor::{NodeEntryPtr, profiling::ProfilerHeapData};
 
-use super::{InsertedSum, NodeID};
+use super::{EdgeID, GraphStats, InsertedDelay, InsertedFade, InsertedGain, InsertedSum, NodeID};
 
 #[cfg(not(feature = "std"))]
 use bevy_platform::prelude::{Box, Vec, vec};
@@ -58,6 +61,14 @@ pub(super) struct ScheduledNode {
     pub is_in_place_buffers: bool,
 
     pub sum_inputs: Vec<InsertedSum>,
+    /// Delays inserted on the inputs to compensate for latency.
+    pub delay_inputs: Vec<InsertedDelay>,
+    /// Fades inserted on the inputs to declick newly connected or
+    /// disconnected edges.
+    pub fade_inputs: Vec<InsertedFade>,
+    /// Gains inserted on the inputs of edges which have a gain applied to
+    /// them.
+    pub gain_inputs: Vec<InsertedGain>,
 }
 
 impl ScheduledNode {
@@ -72,6 +83,9 @@ impl ScheduledNode {
             node_wants_in_place_buffers,
             is_in_place_buffers: false,
             sum_inputs: Vec::new(),
+            delay_inputs: Vec::new(),
+            fade_inputs: Vec::new(),
+            gain_inputs: Vec::new(),
         }
     }
 }
@@ -86,6 +100,62 @@ impl Debug for ScheduledNode {
             self.id.0.generation()
         )?;
 
+        if !self.delay_inputs.is_empty() {
+            write!(f, " | delays: [")?;
+
+            for (i, delay) in self.delay_inputs.iter().enumerate() {
+                write!(
+                    f,
+                    "{{ in: {}, out: {}, frames: {} }}",
+                    delay.input_buffer.buffer_index,
+                    delay.output_buffer.buffer_index,
+                    delay.delay_frames()
+                )?;
+
+                if i != self.delay_inputs.len() - 1 {
+                    write!(f, ", ")?;
+                }
+            }
+
+            write!(f, "]")?;
+        }
+
+        if !self.fade_inputs.is_empty() {
+            write!(f, " | fades: [")?;
+
+            for (i, fade) in self.fade_inputs.iter().enumerate() {
+                write!(
+                    f,
+                    "{{ in: {}, out: {}, fade_in: {} }}",
+                    fade.input_buffer.buffer_index, fade.output_buffer.buffer_index, fade.fade_in
+                )?;
+
+                if i != self.fade_inputs.len() - 1 {
+                    write!(f, ", ")?;
+                }
+            }
+
+            write!(f, "]")?;
+        }
+
+        if !self.gain_inputs.is_empty() {
+            write!(f, " | gains: [")?;
+
+            for (i, gain) in self.gain_inputs.iter().enumerate() {
+                write!(
+                    f,
+                    "{{ in: {}, out: {}, gain: {} }}",
+                    gain.input_buffer.buffer_index, gain.output_buffer.buffer_index, gain.gain
+                )?;
+
+                if i != self.gain_inputs.len() - 1 {
+                    write!(f, ", ")?;
+                }
+            }
+
+            write!(f, "]")?;
+        }
+
         if !self.sum_inputs.is_empty() {
             write!(f, " | sums: [")?;
 
@@ -177,6 +247,9 @@ pub(crate) struct NodeHeapData {
     pub processor: Box<dyn AudioNodeProcessor>,
     pub is_pre_process: bool,
     pub in_place_buffers: bool,
+    pub continue_processing_while_silent: bool,
+    pub bypassed: bool,
+    pub muted: bool,
 }
 
 pub struct ScheduleHeapData {
@@ -186,6 +259,10 @@ pub struct ScheduleHeapData {
     pub(crate) new_node_processors: Vec<NodeHeapData>,
     pub(crate) new_node_arena: Option<Arena<crate::processor::NodeEntry>>,
     pub(crate) new_profiler_heap_data: Option<ProfilerHeapData>,
+    /// Pointers to the processor's node entries indexed by the slot of the
+    /// node ID. This is only allocated if the schedule is processed in
+    /// parallel.
+    pub(crate) node_entries: Vec<NodeEntryPtr>,
 }
 
 impl ScheduleHeapData {
@@ -195,9 +272,16 @@ impl ScheduleHeapData {
         new_node_processors: Vec<NodeHeapData>,
         new_node_arena: Option<Arena<crate::processor::NodeEntry>>,
         new_profiler_heap_data: Option<ProfilerHeapData>,
+        node_capacity: usize,
     ) -> Self {
         let num_nodes_to_remove = nodes_to_remove.len();
 
+        let node_entries = if schedule.is_parallel() {
+            vec![NodeEntryPtr::default(); node_capacity]
+        } else {
+            Vec::new()
+        };
+
         Self {
             schedule,
             nodes_to_remove,
@@ -205,6 +289,7 @@ impl ScheduleHeapData {
             new_node_processors,
             new_node_arena,
             new_profiler_heap_data,
+            node_entries,
         }
     }
 }
@@ -237,6 +322,16 @@ impl BufferFlags {
     }
 }
 
+/// The nodes in a [CompiledSchedule] grouped into levels, where the nodes in
+/// each level do not depend on each other.
+#[derive(Debug, Default, Clone)]
+pub(super) struct ScheduleLevels {
+    /// The indices of the nodes in the schedule, sorted by level.
+    pub node_indices: Vec<usize>,
+    /// The end of each level in `node_indices`.
+    pub level_ends: Vec<usize>,
+}
+
 /// A [CompiledSchedule] is the output of the graph compiler.
 pub struct CompiledSchedule {
     pre_proc_nodes: Vec<PreProcNode>,
@@ -250,8 +345,30 @@ pub struct CompiledSchedule {
 
     bypass_declick_buffer: SequentialBuffer<f32>,
 
+    /// The levels to process the schedule in when processing in parallel.
+    /// This is empty if the schedule is processed on a single thread.
+    levels: ScheduleLevels,
+    /// The bypass declick buffers used by the worker threads when
+    /// processing in parallel.
+    worker_bypass_declick_buffers: Vec<SequentialBuffer<f32>>,
+
     max_block_frames: usize,
     graph_in_node_id: NodeID,
+    graph_out_latency_frames: u32,
+    graph_stats: GraphStats,
+    /// The values used to process the fades on declicked edges. This is only
+    /// `Some` if the schedule contains any such fades.
+    declick_values: Option<DeclickValues>,
+
+    /// The location of each inserted delay in the schedule as
+    /// `(edge_id, node index, delay index)`, sorted by edge ID.
+    ///
+    /// This is built on the main thread so that the audio thread does not
+    /// need to search the whole schedule when carrying over state.
+    delay_lookup: Vec<(EdgeID, usize, usize)>,
+    /// The location of each inserted fade in the schedule as
+    /// `(state_id, node index, fade index)`, sorted by state ID.
+    fade_lookup: Vec<(EdgeID, usize, usize)>,
 }
 
 impl Debug for CompiledSchedule {
@@ -278,6 +395,11 @@ impl Debug for CompiledSchedule {
 
         writeln!(f, "    num_buffers: {}", self.num_buffers)?;
         writeln!(f, "    max_block_frames: {}", self.max_block_frames)?;
+        writeln!(
+            f,
+            "    graph_out_latency_frames: {}",
+            self.graph_out_latency_frames
+        )?;
         writeln!(
             f,
             "    reuse_buffer_allocation: {}",
@@ -297,10 +419,21 @@ impl CompiledSchedule {
         max_num_node_out_buffers: usize,
         max_block_frames: usize,
         graph_in_node_id: NodeID,
+        graph_out_latency_frames: u32,
+        graph_stats: GraphStats,
+        levels: ScheduleLevels,
+        num_threads: usize,
         prev_buffer_capacity: usize,
     ) -> Self {
         assert!(max_block_frames <= u16::MAX as usize);
 
+        let new_bypass_declick_buffer = || {
+            SequentialBuffer::new(
+                NonZeroUsize::new(max_num_node_out_buffers).unwrap_or(NonZeroUsize::MIN),
+                max_block_frames,
+            )
+        };
+
         let reuse_buffer_allocation = num_buffers <= prev_buffer_capacity;
 
         let (buffer_capacity, buffers, buffer_flags) = if reuse_buffer_allocation {
@@ -323,24 +456,114 @@ impl CompiledSchedule {
             )
         };
 
+        let mut delay_lookup: Vec<(EdgeID, usize, usize)> = schedule
+            .iter()
+            .enumerate()
+            .flat_map(|(node_i, n)| {
+                n.delay_inputs
+                    .iter()
+                    .enumerate()
+                    .map(move |(delay_i, d)| (d.edge_id, node_i, delay_i))
+            })
+            .collect();
+        delay_lookup.sort_unstable_by_key(|(id, _, _)| id.0);
+
+        let mut fade_lookup: Vec<(EdgeID, usize, usize)> = schedule
+            .iter()
+            .enumerate()
+            .flat_map(|(node_i, n)| {
+                n.fade_inputs
+                    .iter()
+                    .enumerate()
+                    .map(move |(fade_i, f)| (f.state_id, node_i, fade_i))
+            })
+            .collect();
+        fade_lookup.sort_unstable_by_key(|(id, _, _)| id.0);
+
         Self {
             pre_proc_nodes,
             schedule,
             buffers,
             buffer_flags,
             num_buffers,
-            bypass_declick_buffer: SequentialBuffer::new(
-                NonZeroUsize::new(max_num_node_out_buffers).unwrap_or(NonZeroUsize::MIN),
-                max_block_frames,
-            ),
+            bypass_declick_buffer: new_bypass_declick_buffer(),
+            levels,
+            worker_bypass_declick_buffers: (1..num_threads)
+                .map(|_| new_bypass_declick_buffer())
+                .collect(),
             max_block_frames,
             graph_in_node_id,
+            graph_out_latency_frames,
+            graph_stats,
+            declick_values: None,
             reuse_buffer_allocation,
             buffer_capacity,
+            delay_lookup,
+            fade_lookup,
         }
     }
 
+    fn find_delay(&self, edge_id: EdgeID) -> Option<&InsertedDelay> {
+        let i = self
+            .delay_lookup
+            .binary_search_by_key(&edge_id.0, |(id, _, _)| id.0)
+            .ok()?;
+        let (_, node_i, delay_i) = self.delay_lookup[i];
+
+        Some(&self.schedule[node_i].delay_inputs[delay_i])
+    }
+
+    fn find_fade(&self, state_id: EdgeID) -> Option<&InsertedFade> {
+        let i = self
+            .fade_lookup
+            .binary_search_by_key(&state_id.0, |(id, _, _)| id.0)
+            .ok()?;
+        let (_, node_i, fade_i) = self.fade_lookup[i];
+
+        Some(&self.schedule[node_i].fade_inputs[fade_i])
+    }
+
+    /// Carry over the state of `old_schedule` into this schedule.
+    ///
+    /// The state of a latency compensation delay is only carried over if the
+    /// old schedule has a delay on the same edge with the same length. If the
+    /// length changed, then the old delayed signal no longer lines up with
+    /// the other paths, so the delay restarts from silence.
     pub(crate) fn sync_new_buffers(&mut self, old_schedule: &mut CompiledSchedule) {
+        // Carry over the state of any latency compensation delays which are
+        // still in use so that the delayed signals remain continuous.
+        for &(edge_id, node_i, delay_i) in self.delay_lookup.iter() {
+            let new_delay = &mut self.schedule[node_i].delay_inputs[delay_i];
+
+            if let Some(old_delay) = old_schedule
+                .find_delay(edge_id)
+                .filter(|d| d.delay_frames() == new_delay.delay_frames())
+            {
+                new_delay.delay_line.copy_from_slice(&old_delay.delay_line);
+                new_delay.ptr = old_delay.ptr;
+                new_delay.num_silent_frames = old_delay.num_silent_frames;
+            }
+        }
+
+        // Likewise, carry over the state of any fades which are still in
+        // progress.
+        for &(state_id, node_i, fade_i) in self.fade_lookup.iter() {
+            let new_fade = &mut self.schedule[node_i].fade_inputs[fade_i];
+
+            if let Some(old_fade) = old_schedule.find_fade(state_id).filter(|f| f.started) {
+                new_fade.declicker = old_fade.declicker;
+                new_fade.started = true;
+                new_fade.finish_reported =
+                    old_fade.finish_reported && old_fade.fade_id == new_fade.fade_id;
+
+                if let Some(declick_values) = &self.declick_values {
+                    new_fade
+                        .declicker
+                        .fade_to_enabled(new_fade.fade_in, declick_values);
+                }
+            }
+        }
+
         if self.reuse_buffer_allocation {
             assert_eq!(old_schedule.max_block_frames, self.max_block_frames);
 
@@ -380,6 +603,39 @@ impl CompiledSchedule {
         self.graph_in_node_id
     }
 
+    /// The total latency in frames of the longest path to the graph output node.
+    pub(crate) fn graph_out_latency_frames(&self) -> u32 {
+        self.graph_out_latency_frames
+    }
+
+    /// Statistics about the compiled graph.
+    pub(crate) fn graph_stats(&self) -> GraphStats {
+        self.graph_stats
+    }
+
+    pub(crate) fn has_edge_fades(&self) -> bool {
+        !self.fade_lookup.is_empty()
+    }
+
+    /// Call `report` with the ID of each edge fade which has finished and
+    /// has not been reported yet.
+    ///
+    /// If `report` returns `false`, then the fade will be reported again
+    /// the next time this is called.
+    pub(crate) fn report_finished_fades(&mut self, mut report: impl FnMut(u64) -> bool) {
+        for &(_, node_i, fade_i) in self.fade_lookup.iter() {
+            let fade = &mut self.schedule[node_i].fade_inputs[fade_i];
+
+            if fade.started && !fade.finish_reported && fade.declicker.has_settled() {
+                fade.finish_reported = report(fade.fade_id);
+            }
+        }
+    }
+
+    pub(crate) fn set_declick_values(&mut self, declick_values: DeclickValues) {
+        self.declick_values = Some(declick_values);
+    }
+
     pub(crate) fn max_block_frames(&self) -> usize {
         self.max_block_frames
     }
@@ -520,15 +776,51 @@ impl CompiledSchedule {
         frames: usize,
         force_clear_buffers: bool,
         mut process: impl FnMut(ProcessNodeInfo<'_, '_>) -> ProcessStatus,
+        mut node_processed: Option<&mut dyn FnMut(NodeOutputsInfo<'_, '_>)>,
     ) {
-        let frames = frames.min(self.max_block_frames);
-        let frames_u16 = frames as u16;
-        let buffers_ptr = self.buffers.as_mut_ptr();
-        let max_block_frames = self.max_block_frames;
+        self.process_pre_proc_nodes(&mut process);
 
-        let mut inputs: ArrayVec<&[f32], MAX_CHANNELS> = ArrayVec::new();
-        let mut outputs: ArrayVec<&mut [f32], MAX_CHANNELS> = ArrayVec::new();
+        let bufs = self.buffer_ptrs(frames, force_clear_buffers);
 
+        for scheduled_node in self
+            .schedule
+            .iter_mut()
+            .filter(|n| n.id != self.graph_in_node_id)
+        {
+            // SAFETY: `bufs` is derived from `&mut self`, and only one node is
+            // processed at a time.
+            unsafe {
+                process_scheduled_node(
+                    scheduled_node,
+                    bufs,
+                    self.declick_values.as_ref(),
+                    &mut self.bypass_declick_buffer,
+                    &mut process,
+                );
+            }
+
+            if let Some(node_processed) = node_processed.as_mut() {
+                // SAFETY: `bufs` is derived from `&mut self`, and the node has
+                // finished processing.
+                unsafe {
+                    report_node_outputs(scheduled_node, bufs, &mut **node_processed);
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if this schedule was compiled to be processed on
+    /// multiple threads with [`CompiledSchedule::process_parallel`].
+    pub(crate) fn is_parallel(&self) -> bool {
+        !self.worker_bypass_declick_buffers.is_empty()
+    }
+
+    /// Process the nodes which have zero inputs and outputs. These are
+    /// processed before all other nodes in the graph.
+    pub(crate) fn process_pre_proc_nodes(
+        &mut self,
+        mut process: impl FnMut(ProcessNodeInfo<'_, '_>) -> ProcessStatus,
+    ) {
         for pre_proc_node in self
             .pre_proc_nodes
             .iter()
@@ -549,376 +841,775 @@ impl CompiledSchedule {
                 bypass_declick_buffer: &mut self.bypass_declick_buffer,
             });
         }
+    }
 
-        for scheduled_node in self
-            .schedule
-            .iter()
-            .filter(|n| n.id != self.graph_in_node_id)
-        {
-            for inserted_sum in scheduled_node.sum_inputs.iter() {
-                // SAFETY: buffers_ptr is derived from &mut self.buffers.
-                // Buffer indices in sum_inputs are guaranteed non-overlapping by
-                // the buffer allocator, and the buffer indices are guaranteed to
-                // be in bounds by the buffer allocator.
-                unsafe {
-                    sum_inputs(
-                        inserted_sum,
-                        buffers_ptr,
-                        &mut self.buffer_flags,
-                        max_block_frames,
-                        frames,
-                    );
+    /// Process the schedule one level at a time, where the nodes in each level
+    /// do not depend on each other.
+    ///
+    /// `process_level` must process every node in the given level exactly once
+    /// before returning. The nodes in a level may be processed in any order and
+    /// on any thread.
+    ///
+    /// Unlike [`CompiledSchedule::process`], this does not process the pre
+    /// process nodes. Use [`CompiledSchedule::process_pre_proc_nodes`] to
+    /// process those first.
+    pub(crate) fn process_parallel(
+        &mut self,
+        frames: usize,
+        force_clear_buffers: bool,
+        mut process_level: impl FnMut(&mut ParallelLevel<'_>),
+        mut node_processed: Option<&mut dyn FnMut(NodeOutputsInfo<'_, '_>)>,
+    ) {
+        debug_assert!(self.is_parallel());
+
+        let bufs = self.buffer_ptrs(frames, force_clear_buffers);
+        let nodes = self.schedule.as_mut_ptr();
+
+        let mut level_start = 0;
+        for &level_end in self.levels.level_ends.iter() {
+            (process_level)(&mut ParallelLevel {
+                node_indices: &mut self.levels.node_indices[level_start..level_end],
+                nodes,
+                bufs,
+                declick_values: self.declick_values.as_ref(),
+                bypass_declick_buffer: &raw mut self.bypass_declick_buffer,
+                worker_bypass_declick_buffers: self.worker_bypass_declick_buffers.as_mut_ptr(),
+                num_threads: self.worker_bypass_declick_buffers.len() + 1,
+            });
+
+            if let Some(node_processed) = node_processed.as_mut() {
+                for &node_i in self.levels.node_indices[level_start..level_end].iter() {
+                    // SAFETY: `bufs` and `nodes` are derived from `&mut self`, and
+                    // all nodes in this level have finished processing.
+                    unsafe {
+                        report_node_outputs(&*nodes.add(node_i), bufs, &mut **node_processed);
+                    }
                 }
             }
 
-            let mut in_silence_mask = SilenceMask::NONE_SILENT;
-            let mut out_silence_mask = SilenceMask::NONE_SILENT;
-            let mut in_constant_mask = ConstantMask::NONE_CONSTANT;
-            let mut out_constant_mask = ConstantMask::NONE_CONSTANT;
+            level_start = level_end;
+        }
+    }
 
-            inputs.clear();
-            outputs.clear();
+    fn buffer_ptrs(&mut self, frames: usize, force_clear_buffers: bool) -> BufferPtrs {
+        BufferPtrs {
+            buffers: self.buffers.as_mut_ptr(),
+            flags: self.buffer_flags.as_mut_ptr(),
+            max_block_frames: self.max_block_frames,
+            frames: frames.min(self.max_block_frames),
+            force_clear_buffers,
+        }
+    }
+}
 
-            let copy_in_place_buffers =
-                scheduled_node.node_wants_in_place_buffers && !scheduled_node.is_in_place_buffers;
+/// The nodes in a single level of a [CompiledSchedule]. These nodes do not
+/// depend on each other, so they can be processed in parallel.
+pub(crate) struct ParallelLevel<'a> {
+    node_indices: &'a mut [usize],
+    nodes: *mut ScheduledNode,
+    bufs: BufferPtrs,
+    declick_values: Option<&'a DeclickValues>,
+    bypass_declick_buffer: *mut SequentialBuffer<f32>,
+    worker_bypass_declick_buffers: *mut SequentialBuffer<f32>,
+    num_threads: usize,
+}
 
-            if copy_in_place_buffers {
-                for (in_buf, out_buf) in scheduled_node
-                    .input_buffers
-                    .iter()
-                    .zip(scheduled_node.output_buffers.iter())
-                {
-                    // SAFETY: Input and output buffer indices are guaranteed distinct by the buffer
-                    // allocator when `!scheduled_node.node_wants_in_place_buffers || !scheduled_node.is_in_place_buffers`,
-                    // and the buffer indices are guaranteed to be in bounds by the buffer allocator.
-                    let (in_buf_slice, out_buf_slice) = unsafe {
-                        (
-                            core::slice::from_raw_parts_mut(
-                                buffers_ptr.add(in_buf.buffer_index * max_block_frames),
-                                frames,
-                            ),
-                            core::slice::from_raw_parts_mut(
-                                buffers_ptr.add(out_buf.buffer_index * max_block_frames),
-                                frames,
-                            ),
-                        )
-                    };
+// SAFETY: The nodes in a level never write to the same buffers, and
+// `ParallelLevel::process_node` requires that each node and each thread index
+// are only used by one thread at a time.
+unsafe impl Send for ParallelLevel<'_> {}
+unsafe impl Sync for ParallelLevel<'_> {}
 
-                    let in_flag = *flag_mut(&mut self.buffer_flags, in_buf.buffer_index);
-                    let out_flag = flag_mut(&mut self.buffer_flags, out_buf.buffer_index);
+impl ParallelLevel<'_> {
+    /// The number of nodes in this level.
+    pub fn len(&self) -> usize {
+        self.node_indices.len()
+    }
 
-                    if in_buf.should_clear {
-                        if !out_flag.silent || force_clear_buffers {
-                            out_buf_slice.fill(0.0);
-                            out_flag.set_silent(true, frames_u16);
-                        }
-                    } else {
-                        if in_flag.constant {
-                            if !out_flag.constant
-                                || out_buf_slice[0] != in_buf_slice[0]
-                                || force_clear_buffers
-                            {
-                                out_buf_slice.fill(in_buf_slice[0]);
-                            }
-                        } else {
-                            out_buf_slice.copy_from_slice(in_buf_slice);
-                        }
+    /// The ID of the `i`th node in this level.
+    pub fn node_id(&self, i: usize) -> NodeID {
+        // SAFETY: The node indices are in bounds of the schedule, and the IDs
+        // of the nodes are never modified while processing.
+        unsafe { (*self.nodes.add(self.node_indices[i])).id }
+    }
 
-                        *out_flag = in_flag;
-                    }
-                }
+    /// Move the nodes for which `f` returns `true` to the front of this level,
+    /// returning the number of such nodes.
+    pub fn move_to_front(&mut self, mut f: impl FnMut(NodeID) -> bool) -> usize {
+        let mut num_moved = 0;
+
+        for i in 0..self.node_indices.len() {
+            if (f)(self.node_id(i)) {
+                self.node_indices.swap(num_moved, i);
+                num_moved += 1;
             }
+        }
 
-            let skip_inputs = if copy_in_place_buffers {
-                scheduled_node
-                    .input_buffers
-                    .len()
-                    .min(scheduled_node.output_buffers.len())
+        num_moved
+    }
+
+    /// Process the `i`th node in this level on the thread with the given index.
+    ///
+    /// # Safety
+    ///
+    /// - Each node in this level must be processed exactly once.
+    /// - A `thread_index` must not be used by more than one thread at a time.
+    pub unsafe fn process_node(
+        &self,
+        i: usize,
+        thread_index: usize,
+        process: impl FnOnce(ProcessNodeInfo<'_, '_>) -> ProcessStatus,
+    ) {
+        assert!(thread_index < self.num_threads);
+
+        // SAFETY: The caller guarantees that this node and this thread's bypass
+        // declick buffer are not accessed by any other thread, and the nodes in
+        // a level never write to the same buffers or read the buffers written
+        // to by another node in the same level.
+        unsafe {
+            let bypass_declick_buffer = if thread_index == 0 {
+                &mut *self.bypass_declick_buffer
             } else {
-                0
+                &mut *self.worker_bypass_declick_buffers.add(thread_index - 1)
             };
 
-            for (i, b) in scheduled_node
-                .input_buffers
-                .iter()
-                .skip(skip_inputs)
-                .enumerate()
-            {
-                // SAFETY: Input buffer indices within a single node are guaranteed
-                // non-overlapping by the buffer allocator, and the buffer indices
-                // are guaranteed to be in bounds by the buffer allocator.
-                let buf = unsafe {
-                    core::slice::from_raw_parts_mut(
-                        buffers_ptr.add(b.buffer_index * max_block_frames),
-                        frames,
-                    )
-                };
+            process_scheduled_node(
+                &mut *self.nodes.add(self.node_indices[i]),
+                self.bufs,
+                self.declick_values,
+                bypass_declick_buffer,
+                process,
+            );
+        }
+    }
+}
 
-                let flag = flag_mut(&mut self.buffer_flags, b.buffer_index);
+/// Pointers to the buffers of a [CompiledSchedule] while it is being processed.
+#[derive(Clone, Copy)]
+struct BufferPtrs {
+    buffers: *mut f32,
+    flags: *mut BufferFlags,
+    max_block_frames: usize,
+    frames: usize,
+    force_clear_buffers: bool,
+}
 
-                if b.should_clear && (!flag.silent || force_clear_buffers) {
-                    buf.fill(0.0);
-                    flag.set_silent(true, frames_u16);
-                }
+impl BufferPtrs {
+    /// # Safety
+    ///
+    /// `buffer_index` must be in bounds, and the buffer must not be written to
+    /// while the returned slice is alive.
+    #[inline]
+    unsafe fn buffer<'a>(self, buffer_index: usize) -> &'a [f32] {
+        // SAFETY: The caller upholds the safety requirements.
+        unsafe {
+            core::slice::from_raw_parts(
+                self.buffers.add(buffer_index * self.max_block_frames),
+                self.frames,
+            )
+        }
+    }
 
-                if !scheduled_node.node_wants_in_place_buffers
-                    || i + skip_inputs >= scheduled_node.output_buffers.len()
-                {
-                    in_silence_mask.set_channel(i, flag.silent);
-                    in_constant_mask.set_channel(i, flag.constant);
+    /// # Safety
+    ///
+    /// `buffer_index` must be in bounds, and the buffer must not be accessed
+    /// by anything else while the returned slice is alive.
+    #[inline]
+    unsafe fn buffer_mut<'a>(self, buffer_index: usize) -> &'a mut [f32] {
+        // SAFETY: The caller upholds the safety requirements.
+        unsafe {
+            core::slice::from_raw_parts_mut(
+                self.buffers.add(buffer_index * self.max_block_frames),
+                self.frames,
+            )
+        }
+    }
 
-                    inputs.push(buf);
-                }
-            }
+    /// # Safety
+    ///
+    /// `buffer_index` must be in bounds, and the flag must not be written to
+    /// by another thread at the same time.
+    #[inline]
+    unsafe fn flag(self, buffer_index: usize) -> BufferFlags {
+        // SAFETY: The caller upholds the safety requirements.
+        unsafe { self.flags.add(buffer_index).read() }
+    }
 
-            for (i, b) in scheduled_node.output_buffers.iter().enumerate() {
-                // SAFETY: Output buffer indices within a single node are guaranteed
-                // non-overlapping by the buffer allocator, and the buffer indices
-                // are guaranteed to be in bounds by the buffer allocator.
-                let buf = unsafe {
-                    core::slice::from_raw_parts_mut(
-                        buffers_ptr.add(b.buffer_index * max_block_frames),
-                        frames,
-                    )
-                };
+    /// # Safety
+    ///
+    /// `buffer_index` must be in bounds, and the flag must not be accessed by
+    /// anything else while the returned reference is alive.
+    #[inline]
+    unsafe fn flag_mut<'a>(self, buffer_index: usize) -> &'a mut BufferFlags {
+        // SAFETY: The caller upholds the safety requirements.
+        unsafe { &mut *self.flags.add(buffer_index) }
+    }
+}
 
-                let flag = flag_mut(&mut self.buffer_flags, b.buffer_index);
+/// Process a single node in the schedule, along with the delays, fades, gains,
+/// and sums inserted on its inputs.
+///
+/// # Safety
+///
+/// - `bufs` must be valid for all buffer indices referenced by `scheduled_node`.
+/// - No other node may access the buffers written to by this node, or write to
+///   the buffers read by this node, while it is being processed.
+unsafe fn process_scheduled_node(
+    scheduled_node: &mut ScheduledNode,
+    bufs: BufferPtrs,
+    declick_values: Option<&DeclickValues>,
+    bypass_declick_buffer: &mut SequentialBuffer<f32>,
+    process: impl FnOnce(ProcessNodeInfo<'_, '_>) -> ProcessStatus,
+) {
+    let frames_u16 = bufs.frames as u16;
+    let force_clear_buffers = bufs.force_clear_buffers;
+
+    for inserted_delay in scheduled_node.delay_inputs.iter_mut() {
+        // SAFETY: The input and output buffer indices of an inserted delay are
+        // guaranteed distinct by the buffer allocator, and the buffer
+        // indices are guaranteed to be in bounds by the buffer allocator.
+        unsafe { delay_input(inserted_delay, bufs) };
+    }
 
-                let clear_buffer = if scheduled_node.node_wants_in_place_buffers
-                    && i < scheduled_node.input_buffers.len()
-                {
-                    // buffer has already been processed as an input above
-                    false
-                } else {
-                    force_clear_buffers
-                };
+    if let Some(declick_values) = declick_values {
+        for inserted_fade in scheduled_node.fade_inputs.iter_mut() {
+            // SAFETY: The input and output buffer indices of an inserted fade are
+            // guaranteed distinct by the buffer allocator, and the buffer
+            // indices are guaranteed to be in bounds by the buffer allocator.
+            unsafe { fade_input(inserted_fade, declick_values, bufs) };
+        }
+    }
 
-                if clear_buffer {
-                    buf.fill(0.0);
-                    flag.set_silent(true, frames_u16);
-                }
+    for inserted_gain in scheduled_node.gain_inputs.iter() {
+        // SAFETY: The input and output buffer indices of an inserted gain are
+        // guaranteed distinct by the buffer allocator, and the buffer
+        // indices are guaranteed to be in bounds by the buffer allocator.
+        unsafe { gain_input(inserted_gain, bufs) };
+    }
 
-                out_silence_mask.set_channel(i, flag.silent);
-                out_constant_mask.set_channel(i, flag.constant);
+    for inserted_sum in scheduled_node.sum_inputs.iter() {
+        // SAFETY: Buffer indices in sum_inputs are guaranteed non-overlapping by
+        // the buffer allocator, and the buffer indices are guaranteed to
+        // be in bounds by the buffer allocator.
+        unsafe { sum_inputs(inserted_sum, bufs) };
+    }
 
-                outputs.push(buf);
-            }
+    let mut in_silence_mask = SilenceMask::NONE_SILENT;
+    let mut out_silence_mask = SilenceMask::NONE_SILENT;
+    let mut in_constant_mask = ConstantMask::NONE_CONSTANT;
+    let mut out_constant_mask = ConstantMask::NONE_CONSTANT;
 
-            let status = (process)(ProcessNodeInfo {
-                node_id: scheduled_node.id,
-                in_silence_mask,
-                out_silence_mask,
-                in_constant_mask,
-                out_constant_mask,
-                in_connected_mask: scheduled_node.in_connected_mask,
-                out_connected_mask: scheduled_node.out_connected_mask,
-                proc_buffers: ProcBuffers {
-                    inputs: inputs.as_slice(),
-                    outputs: outputs.as_mut_slice(),
-                },
-                bypass_declick_buffer: &mut self.bypass_declick_buffer,
-            });
+    let mut inputs: ArrayVec<&[f32], MAX_CHANNELS> = ArrayVec::new();
+    let mut outputs: ArrayVec<&mut [f32], MAX_CHANNELS> = ArrayVec::new();
 
-            match status {
-                ProcessStatus::ClearAllOutputs => {
-                    // Clear output buffers which need cleared.
-                    for b in scheduled_node.output_buffers.iter() {
-                        let flag = flag_mut(&mut self.buffer_flags, b.buffer_index);
-
-                        if !flag.silent || force_clear_buffers {
-                            // SAFETY: Each buffer index is used once per iteration, and the buffer
-                            // indices are guaranteed to be in bounds by the buffer allocator.
-                            unsafe {
-                                core::slice::from_raw_parts_mut(
-                                    buffers_ptr.add(b.buffer_index * max_block_frames),
-                                    frames,
-                                )
-                            }
-                            .fill(0.0);
-                            flag.set_silent(true, frames_u16);
-                        }
-                    }
-                }
-                ProcessStatus::Bypass => {
-                    if !scheduled_node.node_wants_in_place_buffers {
-                        for (in_buf, out_buf) in scheduled_node
-                            .input_buffers
-                            .iter()
-                            .zip(scheduled_node.output_buffers.iter())
-                        {
-                            let in_flag = *flag_mut(&mut self.buffer_flags, in_buf.buffer_index);
-                            let out_flag = flag_mut(&mut self.buffer_flags, out_buf.buffer_index);
-
-                            // SAFETY: Input and output buffer indices are guaranteed distinct by the buffer
-                            // allocator when `!scheduled_node.node_wants_in_place_buffers || !scheduled_node.is_in_place_buffers`,
-                            // and the buffer indices are guaranteed to be in bounds by the buffer allocator.
-                            let (in_buf_slice, out_buf_slice) = unsafe {
-                                (
-                                    core::slice::from_raw_parts_mut(
-                                        buffers_ptr.add(in_buf.buffer_index * max_block_frames),
-                                        frames,
-                                    ),
-                                    core::slice::from_raw_parts_mut(
-                                        buffers_ptr.add(out_buf.buffer_index * max_block_frames),
-                                        frames,
-                                    ),
-                                )
-                            };
-
-                            if in_flag.constant {
-                                if !out_flag.constant
-                                    || out_buf_slice[0] != in_buf_slice[0]
-                                    || force_clear_buffers
-                                {
-                                    out_buf_slice.fill(in_buf_slice[0]);
-                                }
-                            } else {
-                                out_buf_slice.copy_from_slice(in_buf_slice);
-                            }
-
-                            *out_flag = in_flag;
-                        }
-                    } // Else input has already been copied to output
+    let copy_in_place_buffers =
+        scheduled_node.node_wants_in_place_buffers && !scheduled_node.is_in_place_buffers;
 
-                    for b in scheduled_node
-                        .output_buffers
-                        .iter()
-                        .skip(scheduled_node.input_buffers.len())
-                    {
-                        let s = flag_mut(&mut self.buffer_flags, b.buffer_index);
-
-                        if !s.silent || force_clear_buffers {
-                            // SAFETY: Each buffer index is used once per iteration, and the buffer indices
-                            // are guaranteed to be in bounds by the buffer allocator.
-                            unsafe {
-                                core::slice::from_raw_parts_mut(
-                                    buffers_ptr.add(b.buffer_index * max_block_frames),
-                                    frames,
-                                )
-                            }
-                            .fill(0.0);
-                            s.set_silent(true, frames_u16);
-                        }
-                    }
+    if copy_in_place_buffers {
+        for (in_buf, out_buf) in scheduled_node
+            .input_buffers
+            .iter()
+            .zip(scheduled_node.output_buffers.iter())
+        {
+            // SAFETY: Input and output buffer indices are guaranteed distinct by the buffer
+            // allocator when `!scheduled_node.node_wants_in_place_buffers || !scheduled_node.is_in_place_buffers`,
+            // and the buffer indices are guaranteed to be in bounds by the buffer allocator.
+            let (in_buf_slice, out_buf_slice, in_flag, out_flag) = unsafe {
+                (
+                    bufs.buffer(in_buf.buffer_index),
+                    bufs.buffer_mut(out_buf.buffer_index),
+                    bufs.flag(in_buf.buffer_index),
+                    bufs.flag_mut(out_buf.buffer_index),
+                )
+            };
+
+            if in_buf.should_clear {
+                if !out_flag.silent || force_clear_buffers {
+                    out_buf_slice.fill(0.0);
+                    out_flag.set_silent(true, frames_u16);
                 }
-                ProcessStatus::OutputsModified => {
-                    for b in scheduled_node.output_buffers.iter() {
-                        flag_mut(&mut self.buffer_flags, b.buffer_index)
-                            .set_silent(false, frames_u16);
+            } else {
+                if in_flag.constant {
+                    if !out_flag.constant
+                        || out_buf_slice[0] != in_buf_slice[0]
+                        || force_clear_buffers
+                    {
+                        out_buf_slice.fill(in_buf_slice[0]);
                     }
+                } else {
+                    out_buf_slice.copy_from_slice(in_buf_slice);
                 }
-                ProcessStatus::OutputsModifiedWithMask(out_mask) => match out_mask {
-                    MaskType::Silence(silence_mask) => {
-                        for (i, b) in scheduled_node.output_buffers.iter().enumerate() {
-                            flag_mut(&mut self.buffer_flags, b.buffer_index)
-                                .set_silent(silence_mask.is_channel_silent(i), frames_u16);
-                        }
-                    }
-                    MaskType::Constant(constant_mask) => {
-                        for (i, b) in scheduled_node.output_buffers.iter().enumerate() {
-                            let flag = flag_mut(&mut self.buffer_flags, b.buffer_index);
-
-                            if constant_mask.is_channel_constant(i) {
-                                flag.constant = true;
-                                // SAFETY: Each buffer index is used once per iteration, and the buffer
-                                // indices are guaranteed to be in bounds by the buffer allocator.
-                                flag.silent = unsafe {
-                                    core::slice::from_raw_parts_mut(
-                                        buffers_ptr.add(b.buffer_index * max_block_frames),
-                                        1,
-                                    )
-                                }[0] == 0.0;
-                                flag.frames = frames_u16;
-                            } else {
-                                flag.set_silent(false, frames_u16);
-                            }
-                        }
-                    }
-                },
+
+                *out_flag = in_flag;
             }
         }
     }
-}
-
-pub(crate) struct ProcessNodeInfo<'a, 'b> {
-    pub node_id: NodeID,
-    pub in_silence_mask: SilenceMask,
-    pub out_silence_mask: SilenceMask,
-    pub in_constant_mask: ConstantMask,
-    pub out_constant_mask: ConstantMask,
-    pub in_connected_mask: ConnectedMask,
-    pub out_connected_mask: ConnectedMask,
-    pub proc_buffers: ProcBuffers<'a, 'b>,
-    pub bypass_declick_buffer: &'a mut SequentialBuffer<f32>,
-}
-
-/// # Safety
-///
-/// - `buffers_ptr` must be valid for reads and writes for all buffer indices
-///   referenced by `inserted_sum`, each spanning `max_block_frames` elements.
-/// - `frames` must be less than or equal to `max_block_frames`.
-/// - The buffer regions referenced by `inserted_sum` must not alias.
-unsafe fn sum_inputs(
-    inserted_sum: &InsertedSum,
-    buffers_ptr: *mut f32,
-    buffer_flags: &mut [BufferFlags],
-    max_block_frames: usize,
-    frames: usize,
-) {
-    let mut all_buffers_silent = true;
 
-    // SAFETY: Buffer indices are guaranteed non-overlapping by the buffer allocator,
-    // and the buffer indices are guaranteed to be in bounds by the buffer allocator.
-    let out_slice = unsafe {
-        core::slice::from_raw_parts_mut(
-            buffers_ptr.add(inserted_sum.output_buffer.buffer_index * max_block_frames),
-            frames,
-        )
+    let skip_inputs = if copy_in_place_buffers {
+        scheduled_node
+            .input_buffers
+            .len()
+            .min(scheduled_node.output_buffers.len())
+    } else {
+        0
     };
 
-    if flag_mut(buffer_flags, inserted_sum.input_buffers[0].buffer_index).silent {
-        if !flag_mut(buffer_flags, inserted_sum.output_buffer.buffer_index).silent {
-            out_slice.fill(0.0);
+    for (i, b) in scheduled_node
+        .input_buffers
+        .iter()
+        .skip(skip_inputs)
+        .enumerate()
+    {
+        if b.should_clear {
+            // SAFETY: Unconnected inputs are assigned their own buffer by the buffer
+            // allocator, and the buffer indices are guaranteed to be in bounds by the
+            // buffer allocator.
+            let flag = unsafe { bufs.flag_mut(b.buffer_index) };
+
+            if !flag.silent || force_clear_buffers {
+                // SAFETY: See above.
+                unsafe { bufs.buffer_mut(b.buffer_index) }.fill(0.0);
+                flag.set_silent(true, frames_u16);
+            }
         }
-    } else {
-        // SAFETY: Input and output buffer indices are guaranteed distinct by the
-        // buffer allocator, and the buffer indices are guaranteed to be in bounds
-        // by the buffer allocator.
-        let in_slice = unsafe {
-            core::slice::from_raw_parts_mut(
-                buffers_ptr.add(inserted_sum.input_buffers[0].buffer_index * max_block_frames),
-                frames,
-            )
-        };
-        out_slice.copy_from_slice(in_slice);
 
-        all_buffers_silent = false;
+        if !scheduled_node.node_wants_in_place_buffers
+            || i + skip_inputs >= scheduled_node.output_buffers.len()
+        {
+            // SAFETY: Input buffers are never written to while this node is being
+            // processed, and the buffer indices are guaranteed to be in bounds by
+            // the buffer allocator.
+            let (buf, flag) = unsafe { (bufs.buffer(b.buffer_index), bufs.flag(b.buffer_index)) };
+
+            in_silence_mask.set_channel(i, flag.silent);
+            in_constant_mask.set_channel(i, flag.constant);
+
+            inputs.push(buf);
+        }
     }
 
-    for buf_id in inserted_sum.input_buffers.iter().skip(1) {
-        if flag_mut(buffer_flags, buf_id.buffer_index).silent {
-            // Input channel is silent, no need to add it.
-            continue;
+    for (i, b) in scheduled_node.output_buffers.iter().enumerate() {
+        // SAFETY: Output buffer indices within a single node are guaranteed
+        // non-overlapping by the buffer allocator, and the buffer indices
+        // are guaranteed to be in bounds by the buffer allocator.
+        let (buf, flag) = unsafe {
+            (
+                bufs.buffer_mut(b.buffer_index),
+                bufs.flag_mut(b.buffer_index),
+            )
+        };
+
+        let clear_buffer = if scheduled_node.node_wants_in_place_buffers
+            && i < scheduled_node.input_buffers.len()
+        {
+            // buffer has already been processed as an input above
+            false
+        } else {
+            force_clear_buffers
+        };
+
+        if clear_buffer {
+            buf.fill(0.0);
+            flag.set_silent(true, frames_u16);
+        }
+
+        out_silence_mask.set_channel(i, flag.silent);
+        out_constant_mask.set_channel(i, flag.constant);
+
+        outputs.push(buf);
+    }
+
+    let status = (process)(ProcessNodeInfo {
+        node_id: scheduled_node.id,
+        in_silence_mask,
+        out_silence_mask,
+        in_constant_mask,
+        out_constant_mask,
+        in_connected_mask: scheduled_node.in_connected_mask,
+        out_connected_mask: scheduled_node.out_connected_mask,
+        proc_buffers: ProcBuffers {
+            inputs: inputs.as_slice(),
+            outputs: outputs.as_mut_slice(),
+        },
+        bypass_declick_buffer,
+    });
+
+    match status {
+        ProcessStatus::ClearAllOutputs => {
+            // Clear output buffers which need cleared.
+            for b in scheduled_node.output_buffers.iter() {
+                // SAFETY: Each buffer index is used once per iteration, and the buffer
+                // indices are guaranteed to be in bounds by the buffer allocator.
+                let flag = unsafe { bufs.flag_mut(b.buffer_index) };
+
+                if !flag.silent || force_clear_buffers {
+                    // SAFETY: See above.
+                    unsafe { bufs.buffer_mut(b.buffer_index) }.fill(0.0);
+                    flag.set_silent(true, frames_u16);
+                }
+            }
+        }
+        ProcessStatus::Bypass => {
+            if !scheduled_node.node_wants_in_place_buffers {
+                for (in_buf, out_buf) in scheduled_node
+                    .input_buffers
+                    .iter()
+                    .zip(scheduled_node.output_buffers.iter())
+                {
+                    // SAFETY: Input and output buffer indices are guaranteed distinct by the buffer
+                    // allocator when `!scheduled_node.node_wants_in_place_buffers || !scheduled_node.is_in_place_buffers`,
+                    // and the buffer indices are guaranteed to be in bounds by the buffer allocator.
+                    let (in_buf_slice, out_buf_slice, in_flag, out_flag) = unsafe {
+                        (
+                            bufs.buffer(in_buf.buffer_index),
+                            bufs.buffer_mut(out_buf.buffer_index),
+                            bufs.flag(in_buf.buffer_index),
+                            bufs.flag_mut(out_buf.buffer_index),
+                        )
+                    };
+
+                    if in_flag.constant {
+                        if !out_flag.constant
+                            || out_buf_slice[0] != in_buf_slice[0]
+                            || force_clear_buffers
+                        {
+                            out_buf_slice.fill(in_buf_slice[0]);
+                        }
+                    } else {
+                        out_buf_slice.copy_from_slice(in_buf_slice);
+                    }
+
+                    *out_flag = in_flag;
+                }
+            } // Else input has already been copied to output
+
+            for b in scheduled_node
+                .output_buffers
+                .iter()
+                .skip(scheduled_node.input_buffers.len())
+            {
+                // SAFETY: Each buffer index is used once per iteration, and the buffer indices
+                // are guaranteed to be in bounds by the buffer allocator.
+                let s = unsafe { bufs.flag_mut(b.buffer_index) };
+
+                if !s.silent || force_clear_buffers {
+                    // SAFETY: See above.
+                    unsafe { bufs.buffer_mut(b.buffer_index) }.fill(0.0);
+                    s.set_silent(true, frames_u16);
+                }
+            }
         }
+        ProcessStatus::OutputsModified => {
+            for b in scheduled_node.output_buffers.iter() {
+                // SAFETY: The buffer indices are guaranteed to be in bounds by the
+                // buffer allocator.
+                unsafe { bufs.flag_mut(b.buffer_index) }.set_silent(false, frames_u16);
+            }
+        }
+        ProcessStatus::OutputsModifiedWithMask(out_mask) => match out_mask {
+            MaskType::Silence(silence_mask) => {
+                for (i, b) in scheduled_node.output_buffers.iter().enumerate() {
+                    // SAFETY: The buffer indices are guaranteed to be in bounds by the
+                    // buffer allocator.
+                    unsafe { bufs.flag_mut(b.buffer_index) }
+                        .set_silent(silence_mask.is_channel_silent(i), frames_u16);
+                }
+            }
+            MaskType::Constant(constant_mask) => {
+                for (i, b) in scheduled_node.output_buffers.iter().enumerate() {
+                    // SAFETY: Each buffer index is used once per iteration, and the buffer
+                    // indices are guaranteed to be in bounds by the buffer allocator.
+                    let flag = unsafe { bufs.flag_mut(b.buffer_index) };
+
+                    if constant_mask.is_channel_constant(i) {
+                        flag.constant = true;
+                        // SAFETY: See above.
+                        flag.silent =
+                            unsafe { *bufs.buffers.add(b.buffer_index * bufs.max_block_frames) }
+                                == 0.0;
+                        flag.frames = frames_u16;
+                    } else {
+                        flag.set_silent(false, frames_u16);
+                    }
+                }
+            }
+        },
+    }
+}
+
+/// Call `node_processed` with the output buffers of the given node.
+///
+/// # Safety
+///
+/// `bufs` must be valid for all buffer indices referenced by `scheduled_node`,
+/// and no node may be processing while this is called.
+unsafe fn report_node_outputs(
+    scheduled_node: &ScheduledNode,
+    bufs: BufferPtrs,
+    node_processed: &mut dyn FnMut(NodeOutputsInfo<'_, '_>),
+) {
+    let mut outputs: ArrayVec<&mut [f32], MAX_CHANNELS> = ArrayVec::new();
+    let mut out_silence_mask = SilenceMask::NONE_SILENT;
+    let mut out_constant_mask = ConstantMask::NONE_CONSTANT;
+
+    for (i, b) in scheduled_node.output_buffers.iter().enumerate() {
+        // SAFETY: Output buffer indices within a single node are guaranteed
+        // non-overlapping by the buffer allocator, and the buffer indices
+        // are guaranteed to be in bounds by the buffer allocator.
+        let (buf, flag) = unsafe { (bufs.buffer_mut(b.buffer_index), bufs.flag(b.buffer_index)) };
+
+        out_silence_mask.set_channel(i, flag.silent);
+        out_constant_mask.set_channel(i, flag.constant);
+
+        outputs.push(buf);
+    }
+
+    (node_processed)(NodeOutputsInfo {
+        node_id: scheduled_node.id,
+        outputs: outputs.as_slice(),
+        silence_mask: out_silence_mask,
+        constant_mask: out_constant_mask,
+    });
+}
+
+/// The output buffers of a node after it has finished processing.
+pub(crate) struct NodeOutputsInfo<'a, 'b> {
+    pub node_id: NodeID,
+    pub outputs: &'a [&'b mut [f32]],
+    pub silence_mask: SilenceMask,
+    pub constant_mask: ConstantMask,
+}
+
+pub(crate) struct ProcessNodeInfo<'a, 'b> {
+    pub node_id: NodeID,
+    pub in_silence_mask: SilenceMask,
+    pub out_silence_mask: SilenceMask,
+    pub in_constant_mask: ConstantMask,
+    pub out_constant_mask: ConstantMask,
+    pub in_connected_mask: ConnectedMask,
+    pub out_connected_mask: ConnectedMask,
+    pub proc_buffers: ProcBuffers<'a, 'b>,
+    pub bypass_declick_buffer: &'a mut SequentialBuffer<f32>,
+}
+
+/// # Safety
+///
+/// - `bufs` must be valid for all buffer indices referenced by `inserted_sum`.
+/// - The buffer regions referenced by `inserted_sum` must not alias.
+unsafe fn sum_inputs(inserted_sum: &InsertedSum, bufs: BufferPtrs) {
+    let mut all_buffers_silent = true;
+
+    // SAFETY: Buffer indices are guaranteed non-overlapping by the buffer allocator,
+    // and the buffer indices are guaranteed to be in bounds by the buffer allocator.
+    let (out_slice, out_flag) = unsafe {
+        (
+            bufs.buffer_mut(inserted_sum.output_buffer.buffer_index),
+            bufs.flag_mut(inserted_sum.output_buffer.buffer_index),
+        )
+    };
+
+    // SAFETY: Input and output buffer indices are guaranteed distinct by the
+    // buffer allocator, and the buffer indices are guaranteed to be in bounds
+    // by the buffer allocator.
+    if unsafe { bufs.flag(inserted_sum.input_buffers[0].buffer_index) }.silent {
+        if !out_flag.silent {
+            out_slice.fill(0.0);
+        }
+    } else {
+        // SAFETY: See above.
+        let in_slice = unsafe { bufs.buffer(inserted_sum.input_buffers[0].buffer_index) };
+        out_slice.copy_from_slice(in_slice);
 
         all_buffers_silent = false;
+    }
 
+    for buf_id in inserted_sum.input_buffers.iter().skip(1) {
         // SAFETY: Input buffer indices are guaranteed distinct from the output buffer
         // index by the buffer allocator, and the buffer indices are guaranteed to be
         // in bounds by the buffer allocator.
-        let in_slice = unsafe {
-            core::slice::from_raw_parts_mut(
-                buffers_ptr.add(buf_id.buffer_index * max_block_frames),
-                frames,
-            )
-        };
+        if unsafe { bufs.flag(buf_id.buffer_index) }.silent {
+            // Input channel is silent, no need to add it.
+            continue;
+        }
+
+        all_buffers_silent = false;
+
+        // SAFETY: See above.
+        let in_slice = unsafe { bufs.buffer(buf_id.buffer_index) };
         for (os, &is) in out_slice.iter_mut().zip(in_slice.iter()) {
             *os += is;
         }
     }
 
-    flag_mut(buffer_flags, inserted_sum.output_buffer.buffer_index)
-        .set_silent(all_buffers_silent, frames as u16);
+    out_flag.set_silent(all_buffers_silent, bufs.frames as u16);
+}
+
+/// # Safety
+///
+/// - `bufs` must be valid for all buffer indices referenced by `inserted_delay`.
+/// - The buffer regions referenced by `inserted_delay` must not alias.
+unsafe fn delay_input(inserted_delay: &mut InsertedDelay, bufs: BufferPtrs) {
+    let frames = bufs.frames;
+    let delay_frames = inserted_delay.delay_frames();
+
+    // SAFETY: Input and output buffer indices are guaranteed distinct by the
+    // buffer allocator, and the buffer indices are guaranteed to be in bounds
+    // by the buffer allocator.
+    let (in_slice, out_slice, in_flag, out_flag) = unsafe {
+        (
+            bufs.buffer(inserted_delay.input_buffer.buffer_index),
+            bufs.buffer_mut(inserted_delay.output_buffer.buffer_index),
+            bufs.flag(inserted_delay.input_buffer.buffer_index),
+            bufs.flag_mut(inserted_delay.output_buffer.buffer_index),
+        )
+    };
+
+    if in_flag.silent && inserted_delay.num_silent_frames == delay_frames {
+        // The input is silent and the delay line only contains silence, so
+        // there is no need to process the delay line.
+        if !out_flag.silent {
+            out_slice.fill(0.0);
+        }
+        out_flag.set_silent(true, frames as u16);
+
+        return;
+    }
+
+    let delay_line = &mut inserted_delay.delay_line;
+    let mut ptr = inserted_delay.ptr;
+
+    for (os, &is) in out_slice.iter_mut().zip(in_slice.iter()) {
+        *os = delay_line[ptr];
+        delay_line[ptr] = is;
+
+        ptr += 1;
+        if ptr == delay_frames {
+            ptr = 0;
+        }
+    }
+
+    inserted_delay.ptr = ptr;
+    inserted_delay.num_silent_frames = if in_flag.silent {
+        (inserted_delay.num_silent_frames + frames).min(delay_frames)
+    } else {
+        0
+    };
+
+    out_flag.set_silent(false, frames as u16);
+}
+
+/// # Safety
+///
+/// - `bufs` must be valid for all buffer indices referenced by `inserted_fade`.
+/// - The buffer regions referenced by `inserted_fade` must not alias.
+unsafe fn fade_input(
+    inserted_fade: &mut InsertedFade,
+    declick_values: &DeclickValues,
+    bufs: BufferPtrs,
+) {
+    let frames = bufs.frames;
+
+    if !inserted_fade.started {
+        inserted_fade.started = true;
+        inserted_fade
+            .declicker
+            .fade_to_enabled(inserted_fade.fade_in, declick_values);
+    }
+
+    // SAFETY: Input and output buffer indices are guaranteed distinct by the
+    // buffer allocator, and the buffer indices are guaranteed to be in bounds
+    // by the buffer allocator.
+    let (in_slice, out_slice, in_flag, out_flag) = unsafe {
+        (
+            bufs.buffer(inserted_fade.input_buffer.buffer_index),
+            bufs.buffer_mut(inserted_fade.output_buffer.buffer_index),
+            bufs.flag(inserted_fade.input_buffer.buffer_index),
+            bufs.flag_mut(inserted_fade.output_buffer.buffer_index),
+        )
+    };
+
+    if in_flag.silent || inserted_fade.declicker == Declicker::SettledAt0 {
+        if !out_flag.silent {
+            out_slice.fill(0.0);
+        }
+        out_flag.set_silent(true, frames as u16);
+
+        // Keep the fade progressing even if the input is silent.
+        if !inserted_fade.declicker.has_settled() {
+            inserted_fade.declicker.process(
+                &mut [out_slice],
+                0..frames,
+                declick_values,
+                1.0,
+                DeclickFadeCurve::EqualPower3dB,
+            );
+        }
+
+        return;
+    }
+
+    out_slice.copy_from_slice(in_slice);
+
+    if inserted_fade.declicker == Declicker::SettledAt1 {
+        *out_flag = BufferFlags {
+            silent: false,
+            constant: in_flag.constant,
+            frames: frames as u16,
+        };
+
+        return;
+    }
+
+    inserted_fade.declicker.process(
+        &mut [out_slice],
+        0..frames,
+        declick_values,
+        1.0,
+        DeclickFadeCurve::EqualPower3dB,
+    );
+
+    out_flag.set_silent(false, frames as u16);
+}
+
+/// # Safety
+///
+/// - `bufs` must be valid for all buffer indices referenced by `inserted_gain`.
+/// - The buffer regions referenced by `inserted_gain` must not alias.
+unsafe fn gain_input(inserted_gain: &InsertedGain, bufs: BufferPtrs) {
+    // SAFETY: Input and output buffer indices are guaranteed distinct by the
+    // buffer allocator, and the buffer indices are guaranteed to be in bounds
+    // by the buffer allocator.
+    let (in_slice, out_slice, in_flag, out_flag) = unsafe {
+        (
+            bufs.buffer(inserted_gain.input_buffer.buffer_index),
+            bufs.buffer_mut(inserted_gain.output_buffer.buffer_index),
+            bufs.flag(inserted_gain.input_buffer.buffer_index),
+            bufs.flag_mut(inserted_gain.output_buffer.buffer_index),
+        )
+    };
+
+    if in_flag.silent || inserted_gain.gain == 0.0 {
+        if !out_flag.silent {
+            out_slice.fill(0.0);
+        }
+        out_flag.set_silent(true, bufs.frames as u16);
+
+        return;
+    }
+
+    if in_flag.constant {
+        out_slice.fill(in_slice[0] * inserted_gain.gain);
+    } else {
+        for (os, &is) in out_slice.iter_mut().zip(in_slice.iter()) {
+            *os = is * inserted_gain.gain;
+        }
+    }
+
+    *out_flag = BufferFlags {
+        silent: false,
+        constant: in_flag.constant,
+        frames: bufs.frames as u16,
+    };
 }
 
 #[inline]
@@ -936,10 +1627,12 @@ fn flag_mut(buffer_flags: &mut [BufferFlags], buffer_index: usize) -> &mut Buffe
 mod tests {
     use crate::{
         FirewheelConfig,
+        error::AddEdgeError,
         graph::{
             AudioGraph, EdgeID,
             dummy_node::{DummyNode, DummyNodeConfig},
         },
+        processor::LimiterConfig,
     };
     use bevy_platform::collections::HashSet;
     use firewheel_core::channel_config::{ChannelConfig, ChannelCount};
@@ -1205,14 +1898,408 @@ mod tests {
         verify_node(node6, &[false], 0, &schedule, &graph);
     }
 
+    // Graph latency test:
+    //
+    //          ┌───┐
+    //   ┌──────► 1 ┼──────────┐
+    // ┌─┼─┐    └───┘          ┌▼──┐
+    // │ 0 │                   │ 4 │
+    // └─┬─┘    ┌───┐  ┌───┐   └▲──┘
+    //   └──────► 2 ┼──► 3 ┼────┘
+    //          └───┘  └───┘
+    #[test]
+    fn graph_latency_test() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::MONO,
+            num_graph_outputs: ChannelCount::MONO,
+            ..Default::default()
+        });
+
+        let node0 = graph.graph_in_node();
+        let node1 = add_dummy_node_with_latency(&mut graph, (1, 1), 256).unwrap();
+        let node2 = add_dummy_node_with_latency(&mut graph, (1, 1), 64).unwrap();
+        let node3 = add_dummy_node_with_latency(&mut graph, (1, 1), 128).unwrap();
+        let node4 = graph.graph_out_node();
+
+        graph
+            .connect(node0, node1, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node0, node2, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node2, node3, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node1, node4, &[(0, 0)], false, false)
+            .unwrap();
+
+        let schedule = graph.compile_internal(128).unwrap();
+        assert_eq!(schedule.graph_out_latency_frames(), 256);
+
+        graph
+            .connect(node3, node4, &[(0, 0)], false, false)
+            .unwrap();
+
+        let schedule = graph.compile_internal(128).unwrap();
+        assert_eq!(schedule.graph_out_latency_frames(), 256);
+
+        graph.disconnect(node1, node4, &[(0, 0)]);
+
+        let schedule = graph.compile_internal(128).unwrap();
+        assert_eq!(schedule.graph_out_latency_frames(), 192);
+    }
+
+    #[test]
+    fn output_limiter_latency_test() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::MONO,
+            num_graph_outputs: ChannelCount::MONO,
+            output_limiter: Some(LimiterConfig {
+                lookahead_frames: 64,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        let node0 = graph.graph_in_node();
+        let node1 = add_dummy_node_with_latency(&mut graph, (1, 1), 256).unwrap();
+        let node2 = graph.graph_out_node();
+
+        assert_eq!(graph.node_info(node2).unwrap().info.latency_frames, 64);
+
+        graph
+            .connect(node0, node1, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node1, node2, &[(0, 0)], false, false)
+            .unwrap();
+
+        // The look-ahead of the limiter adds to the latency of the graph.
+        let schedule = graph.compile_internal(128).unwrap();
+        assert_eq!(schedule.graph_out_latency_frames(), 256 + 64);
+    }
+
+    #[test]
+    fn graph_stats_test() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::MONO,
+            num_graph_outputs: ChannelCount::MONO,
+            ..Default::default()
+        });
+
+        let node0 = graph.graph_in_node();
+        let node1 = add_dummy_node(&mut graph, (1, 1)).unwrap();
+        let node2 = add_dummy_node(&mut graph, (1, 1)).unwrap();
+        let node3 = add_dummy_node(&mut graph, (1, 1)).unwrap();
+        let node4 = graph.graph_out_node();
+
+        graph
+            .connect(node0, node1, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node0, node2, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node2, node3, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node1, node4, &[(0, 0)], false, false)
+            .unwrap();
+
+        let schedule = graph.compile_internal(128).unwrap();
+        assert_eq!(
+            schedule.graph_stats(),
+            GraphStats {
+                num_nodes: 5,
+                num_edges: 4,
+                max_depth: 2,
+                max_width: 2,
+            }
+        );
+
+        graph
+            .connect(node3, node4, &[(0, 0)], false, false)
+            .unwrap();
+
+        let schedule = graph.compile_internal(128).unwrap();
+        assert_eq!(
+            schedule.graph_stats(),
+            GraphStats {
+                num_nodes: 5,
+                num_edges: 5,
+                max_depth: 3,
+                max_width: 2,
+            }
+        );
+    }
+
+    // Latency compensation test:
+    //
+    //          ┌───┐
+    //   ┌──────► 1 ┼──────────┐
+    // ┌─┼─┐    └───┘          ┌▼──┐
+    // │ 0 │                   │ 4 │
+    // └─┬─┘    ┌───┐  ┌───┐   └▲──┘
+    //   └──────► 2 ┼──► 3 ┼────┘
+    //          └───┘  └───┘
+    #[test]
+    fn latency_compensation_test() {
+        for auto_latency_compensation in [true, false] {
+            let mut graph = AudioGraph::new(&FirewheelConfig {
+                num_graph_inputs: ChannelCount::MONO,
+                num_graph_outputs: ChannelCount::MONO,
+                auto_latency_compensation,
+                ..Default::default()
+            });
+
+            let node0 = graph.graph_in_node();
+            let node1 = add_dummy_node_with_latency(&mut graph, (1, 1), 256).unwrap();
+            let node2 = add_dummy_node_with_latency(&mut graph, (1, 1), 64).unwrap();
+            let node3 = add_dummy_node_with_latency(&mut graph, (1, 1), 128).unwrap();
+            let node4 = graph.graph_out_node();
+
+            graph
+                .connect(node0, node1, &[(0, 0)], false, false)
+                .unwrap();
+            graph
+                .connect(node0, node2, &[(0, 0)], false, false)
+                .unwrap();
+            graph
+                .connect(node2, node3, &[(0, 0)], false, false)
+                .unwrap();
+            graph
+                .connect(node1, node4, &[(0, 0)], false, false)
+                .unwrap();
+            let edge4 = graph
+                .connect(node3, node4, &[(0, 0)], false, false)
+                .unwrap()[0];
+
+            let schedule = graph.compile_internal(128).unwrap();
+
+            #[cfg(feature = "std")]
+            dbg!(&schedule);
+
+            verify_node(node4, &[false], 1, &schedule, &graph);
+
+            for scheduled_node in schedule.schedule.iter() {
+                if scheduled_node.id != node4 || !auto_latency_compensation {
+                    assert!(scheduled_node.delay_inputs.is_empty());
+                    continue;
+                }
+
+                // The shorter path must be delayed to line up with the longer path.
+                assert_eq!(scheduled_node.delay_inputs.len(), 1);
+
+                let delay = &scheduled_node.delay_inputs[0];
+                assert_eq!(delay.edge_id, edge4);
+                assert_eq!(delay.delay_frames(), 64);
+
+                let sum_inputs = &scheduled_node.sum_inputs[0].input_buffers;
+                assert!(
+                    sum_inputs
+                        .iter()
+                        .any(|b| b.buffer_index == delay.output_buffer.buffer_index)
+                );
+                assert!(
+                    sum_inputs
+                        .iter()
+                        .all(|b| b.buffer_index != delay.input_buffer.buffer_index)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn latency_compensation_carry_over_test() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::MONO,
+            num_graph_outputs: ChannelCount::MONO,
+            auto_latency_compensation: true,
+            ..Default::default()
+        });
+
+        let graph_in = graph.graph_in_node();
+        let graph_out = graph.graph_out_node();
+        let long = add_dummy_node_with_latency(&mut graph, (1, 1), 256).unwrap();
+        let short = add_dummy_node_with_latency(&mut graph, (1, 1), 64).unwrap();
+
+        graph
+            .connect(graph_in, long, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(graph_in, short, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(long, graph_out, &[(0, 0)], false, false)
+            .unwrap();
+        let edge = graph
+            .connect(short, graph_out, &[(0, 0)], false, false)
+            .unwrap()[0];
+
+        let mut old_schedule = graph.compile_internal(128).unwrap();
+        let old_delay = delay_mut(&mut old_schedule, edge);
+        assert_eq!(old_delay.delay_frames(), 192);
+        old_delay.delay_line.fill(1.0);
+        old_delay.ptr = 7;
+        old_delay.num_silent_frames = 0;
+
+        // The state is carried over when the delay keeps the same length.
+        let mut schedule = graph.compile_internal(128).unwrap();
+        schedule.sync_new_buffers(&mut old_schedule);
+
+        let delay = delay_mut(&mut schedule, edge);
+        assert!(delay.delay_line.iter().all(|&s| s == 1.0));
+        assert_eq!(delay.ptr, 7);
+        assert_eq!(delay.num_silent_frames, 0);
+
+        // Making the parallel path longer changes the length of the delay on
+        // the same edge, so the delay restarts from silence.
+        graph.remove_node(long, false).unwrap();
+        let longer = add_dummy_node_with_latency(&mut graph, (1, 1), 512).unwrap();
+        graph
+            .connect(graph_in, longer, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(longer, graph_out, &[(0, 0)], false, false)
+            .unwrap();
+
+        let mut new_schedule = graph.compile_internal(128).unwrap();
+        new_schedule.sync_new_buffers(&mut schedule);
+
+        let new_delay = delay_mut(&mut new_schedule, edge);
+        assert_eq!(new_delay.delay_frames(), 448);
+        assert!(new_delay.delay_line.iter().all(|&s| s == 0.0));
+        assert_eq!(new_delay.ptr, 0);
+        assert_eq!(new_delay.num_silent_frames, 448);
+    }
+
+    #[test]
+    fn declick_connections_test() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::MONO,
+            num_graph_outputs: ChannelCount::MONO,
+            declick_connections: true,
+            ..Default::default()
+        });
+
+        let node0 = graph.graph_in_node();
+        let node1 = add_dummy_node(&mut graph, (1, 1)).unwrap();
+        let node2 = graph.graph_out_node();
+
+        graph
+            .connect(node0, node1, &[(0, 0)], false, false)
+            .unwrap();
+        graph
+            .connect(node1, node2, &[(0, 0)], false, false)
+            .unwrap();
+
+        let schedule = graph.compile_internal(128).unwrap();
+
+        #[cfg(feature = "std")]
+        dbg!(&schedule);
+
+        // Both newly connected edges must be faded in.
+        for scheduled_node in schedule.schedule.iter() {
+            if scheduled_node.id == node0 {
+                continue;
+            }
+
+            assert_eq!(scheduled_node.fade_inputs.len(), 1);
+
+            let fade = &scheduled_node.fade_inputs[0];
+            assert!(fade.fade_in);
+            assert_eq!(
+                scheduled_node.input_buffers[0].buffer_index,
+                fade.output_buffer.buffer_index
+            );
+        }
+
+        // A removed edge must be kept in the schedule until it has faded out.
+        graph.disconnect(node1, node2, &[(0, 0)]);
+        assert!(graph.edges.iter().all(|(_, e)| e.dst_node != node2));
+
+        let schedule = graph.compile_internal(128).unwrap();
+
+        #[cfg(feature = "std")]
+        dbg!(&schedule);
+
+        let out_node = schedule.schedule.iter().find(|n| n.id == node2).unwrap();
+        assert_eq!(out_node.fade_inputs.len(), 1);
+        assert!(!out_node.fade_inputs[0].fade_in);
+        assert!(graph.edges.iter().all(|(_, e)| e.dst_node != node2));
+    }
+
+    #[test]
+    fn edge_gain_test() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::STEREO,
+            num_graph_outputs: ChannelCount::MONO,
+            ..Default::default()
+        });
+
+        let node0 = graph.graph_in_node();
+        let node1 = graph.graph_out_node();
+
+        let edge_ids = graph
+            .connect(node0, node1, &[(0, 0), (1, 0)], false, false)
+            .unwrap();
+        for &edge_id in edge_ids.iter() {
+            assert!(graph.set_edge_gain(edge_id, 0.5));
+            assert_eq!(graph.edge_gain(edge_id), Some(0.5));
+        }
+
+        let schedule = graph.compile_internal(128).unwrap();
+
+        #[cfg(feature = "std")]
+        dbg!(&schedule);
+
+        // The gains must be applied before the edges are summed.
+        let out_node = schedule.schedule.iter().find(|n| n.id == node1).unwrap();
+        assert_eq!(out_node.gain_inputs.len(), 2);
+
+        let sum_inputs = &out_node.sum_inputs[0].input_buffers;
+        for gain in out_node.gain_inputs.iter() {
+            assert_eq!(gain.gain, 0.5);
+            assert!(
+                sum_inputs
+                    .iter()
+                    .any(|b| b.buffer_index == gain.output_buffer.buffer_index)
+            );
+        }
+
+        // Removing an edge also removes its gain.
+        graph.disconnect_by_edge_id(edge_ids[0], false);
+        assert_eq!(graph.edge_gain(edge_ids[0]), None);
+        assert!(!graph.set_edge_gain(edge_ids[0], 0.5));
+    }
+
     fn add_dummy_node(
         graph: &mut AudioGraph,
         channel_config: impl Into<ChannelConfig>,
+    ) -> Result<NodeID, NodeError> {
+        add_dummy_node_with_latency(graph, channel_config, 0)
+    }
+
+    fn delay_mut(schedule: &mut CompiledSchedule, edge_id: EdgeID) -> &mut InsertedDelay {
+        schedule
+            .schedule
+            .iter_mut()
+            .flat_map(|n| n.delay_inputs.iter_mut())
+            .find(|d| d.edge_id == edge_id)
+            .unwrap()
+    }
+
+    fn add_dummy_node_with_latency(
+        graph: &mut AudioGraph,
+        channel_config: impl Into<ChannelConfig>,
+        latency_frames: u32,
     ) -> Result<NodeID, NodeError> {
         graph.add_node(
             DummyNode,
             Some(DummyNodeConfig {
                 channel_config: channel_config.into(),
+                latency_frames,
             }),
         )
     }
@@ -1377,4 +2464,108 @@ mod tests {
 
         assert!(graph.cycle_detected());
     }
+
+    #[test]
+    fn self_connection_rejected() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::ZERO,
+            num_graph_outputs: ChannelCount::STEREO,
+            ..Default::default()
+        });
+
+        let node1 = add_dummy_node(&mut graph, (2, 2)).unwrap();
+
+        // Self-connections are rejected even if checking for cycles is disabled.
+        assert_eq!(
+            graph.connect(node1, node1, &[(0, 1)], false, false),
+            Err(AddEdgeError::SelfConnection(node1))
+        );
+        assert_eq!(graph.edges().count(), 0);
+    }
+
+    #[test]
+    fn compile_debounce() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::ZERO,
+            num_graph_outputs: ChannelCount::STEREO,
+            compile_debounce_seconds: 60.0,
+            ..Default::default()
+        });
+
+        // The initial compile is never debounced.
+        assert!(graph.compile_is_due());
+        graph
+            .compile(&firewheel_core::StreamInfo::default())
+            .unwrap();
+        assert!(!graph.needs_compile());
+
+        let node1 = add_dummy_node(&mut graph, (1, 1)).unwrap();
+        graph
+            .connect(node1, graph.graph_out_node(), &[(0, 0)], false, false)
+            .unwrap();
+
+        assert!(graph.needs_compile());
+        assert!(!graph.compile_is_due());
+
+        // Invalid debounce times disable debouncing instead of panicking.
+        for compile_debounce_seconds in [f32::INFINITY, f32::NAN, f32::MAX] {
+            let mut graph = AudioGraph::new(&FirewheelConfig {
+                num_graph_inputs: ChannelCount::ZERO,
+                num_graph_outputs: ChannelCount::STEREO,
+                compile_debounce_seconds,
+                ..Default::default()
+            });
+            graph
+                .compile(&firewheel_core::StreamInfo::default())
+                .unwrap();
+
+            let node1 = add_dummy_node(&mut graph, (1, 1)).unwrap();
+            graph
+                .connect(node1, graph.graph_out_node(), &[(0, 0)], false, false)
+                .unwrap();
+
+            assert!(graph.compile_is_due());
+        }
+    }
+
+    #[test]
+    fn compile_batch() {
+        let mut graph = AudioGraph::new(&FirewheelConfig {
+            num_graph_inputs: ChannelCount::ZERO,
+            num_graph_outputs: ChannelCount::STEREO,
+            ..Default::default()
+        });
+
+        graph
+            .compile(&firewheel_core::StreamInfo::default())
+            .unwrap();
+
+        let node1 = add_dummy_node(&mut graph, (1, 1)).unwrap();
+        let node2 = graph.graph_out_node();
+
+        graph.begin_batch();
+        graph.begin_batch();
+
+        let (_, changed) = graph
+            .connect_with_status(node1, node2, &[(0, 0)], false, false)
+            .unwrap();
+        assert!(changed);
+
+        graph.end_batch();
+        assert!(graph.needs_compile());
+        assert!(!graph.compile_is_due());
+
+        graph.end_batch();
+        assert!(graph.compile_is_due());
+        graph
+            .compile(&firewheel_core::StreamInfo::default())
+            .unwrap();
+
+        // Connecting an edge which already exists does not change the graph.
+        let (_, changed) = graph
+            .connect_with_status(node1, node2, &[(0, 0)], false, false)
+            .unwrap();
+        assert!(!changed);
+        assert!(!graph.needs_compile());
+    }
 }
@@ -12,6 +12,7 @@ pub(crate) struct DummyNode;
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct DummyNodeConfig {
     pub channel_config: ChannelConfig,
+    pub latency_frames: u32,
 }
 
 impl AudioNode for DummyNode {
@@ -20,7 +21,8 @@ impl AudioNode for DummyNode {
     fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
         Ok(AudioNodeInfo::new()
             .debug_name("dummy")
-            .channel_config(config.channel_config))
+            .channel_config(config.channel_config)
+            .latency_frames(config.latency_frames))
     }
 
     fn construct_processor(
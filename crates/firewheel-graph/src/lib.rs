@@ -4,6 +4,7 @@ pub mod backend;
 mod context;
 pub mod error;
 pub mod graph;
+pub mod pool;
 pub mod processor;
 mod time;
 
@@ -18,20 +19,118 @@ extern crate alloc;
 
 #[cfg(test)]
 mod tests {
-    use crate::{backend::BackendProcessInfo, processor::FirewheelProcessor};
+    use crate::{
+        backend::BackendProcessInfo,
+        error::AddEdgeError,
+        graph::{Connection, MuteSoloState},
+        processor::{FirewheelProcessor, RenderTap},
+    };
     use audioadapter_buffers::direct::InterleavedSlice;
     use bevy_platform::sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
     };
     use core::{num::NonZeroU32, time::Duration};
-    use firewheel_core::node::{
-        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
-        NodeError, StreamStatus,
+    use firewheel_core::{
+        channel_config::{ChannelConfig, ChannelCount},
+        node::{
+            AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
+            NodeError, ProcBuffers, ProcExtra, ProcInfo, ProcessStatus, StreamStatus,
+        },
     };
 
     use super::*;
 
+    /// A node which outputs a constant value on a single channel.
+    #[derive(Clone, Copy)]
+    struct ConstSource(f32);
+
+    impl AudioNode for ConstSource {
+        type Configuration = EmptyConfig;
+
+        fn info(&self, _: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+            Ok(AudioNodeInfo::new().channel_config(ChannelConfig {
+                num_inputs: ChannelCount::ZERO,
+                num_outputs: ChannelCount::MONO,
+            }))
+        }
+
+        fn construct_processor(
+            &self,
+            _: &Self::Configuration,
+            _: ConstructProcessorContext,
+        ) -> Result<impl AudioNodeProcessor, NodeError> {
+            Ok(*self)
+        }
+    }
+
+    impl AudioNodeProcessor for ConstSource {
+        fn process(
+            &mut self,
+            info: &ProcInfo,
+            buffers: ProcBuffers,
+            _: &mut ProcExtra,
+        ) -> ProcessStatus {
+            buffers.outputs[0][..info.frames].fill(self.0);
+            ProcessStatus::OutputsModified
+        }
+    }
+
+    /// A node which multiplies its single input channel by a constant gain.
+    #[derive(Clone, Copy)]
+    struct Scale(f32);
+
+    impl AudioNode for Scale {
+        type Configuration = EmptyConfig;
+
+        fn info(&self, _: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+            Ok(AudioNodeInfo::new().channel_config(ChannelConfig {
+                num_inputs: ChannelCount::MONO,
+                num_outputs: ChannelCount::MONO,
+            }))
+        }
+
+        fn construct_processor(
+            &self,
+            _: &Self::Configuration,
+            _: ConstructProcessorContext,
+        ) -> Result<impl AudioNodeProcessor, NodeError> {
+            Ok(*self)
+        }
+    }
+
+    impl AudioNodeProcessor for Scale {
+        fn process(
+            &mut self,
+            info: &ProcInfo,
+            buffers: ProcBuffers,
+            _: &mut ProcExtra,
+        ) -> ProcessStatus {
+            for (out_s, in_s) in buffers.outputs[0][..info.frames]
+                .iter_mut()
+                .zip(buffers.inputs[0][..info.frames].iter())
+            {
+                *out_s = *in_s * self.0;
+            }
+
+            ProcessStatus::OutputsModified
+        }
+    }
+
+    /// Flush any pending changes to the processor, and then process enough
+    /// frames for any declicking to settle. Returns the last output sample.
+    fn settled_output(cx: &mut FirewheelContext, backend: &mut backend::ManualBackend) -> f32 {
+        let mut out = [0.0; 1024];
+        backend.flush_and_process(cx, &[], &mut out, 1024).unwrap();
+        backend.process(&[], &mut out, 1024);
+
+        out[1023]
+    }
+
+    fn connections(cx: &FirewheelContext) -> Vec<Connection> {
+        cx.edges().map(|edge| Connection::from(*edge)).collect()
+    }
+
     #[test]
     // Firewheel is designed with
     // [CLAP's threading model](https://github.com/free-audio/clap/blob/main/include/clap/plugin.h)
@@ -178,11 +277,452 @@ mod tests {
 
             context.request_deactivate();
 
-            // The processor must process at least once to deactivate.
+            // The processor must process at least twice to deactivate (once to
+            // fade out the output, and once more to stop).
             process(&mut processor);
+            process(&mut processor);
+
+            assert!(!context.is_active());
 
             let _ = context;
             let _ = processor;
         }
     }
+
+    #[test]
+    fn max_internal_block_frames() {
+        #[derive(Clone)]
+        struct BlockRecorder {
+            max_frames: Arc<AtomicUsize>,
+            num_blocks: Arc<AtomicUsize>,
+        }
+
+        impl AudioNode for BlockRecorder {
+            type Configuration = EmptyConfig;
+
+            fn info(&self, _: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+                Ok(AudioNodeInfo::new().channel_config(ChannelConfig {
+                    num_inputs: ChannelCount::ZERO,
+                    num_outputs: ChannelCount::MONO,
+                }))
+            }
+
+            fn construct_processor(
+                &self,
+                _: &Self::Configuration,
+                _: ConstructProcessorContext,
+            ) -> Result<impl AudioNodeProcessor, NodeError> {
+                Ok(self.clone())
+            }
+        }
+
+        impl AudioNodeProcessor for BlockRecorder {
+            fn process(
+                &mut self,
+                info: &ProcInfo,
+                _: ProcBuffers,
+                _: &mut ProcExtra,
+            ) -> ProcessStatus {
+                self.max_frames.fetch_max(info.frames, Ordering::Relaxed);
+                self.num_blocks.fetch_add(1, Ordering::Relaxed);
+
+                ProcessStatus::ClearAllOutputs
+            }
+        }
+
+        let mut cx = FirewheelContext::new(FirewheelConfig {
+            num_graph_outputs: ChannelCount::MONO,
+            max_internal_block_frames: NonZeroU32::new(64),
+            ..Default::default()
+        });
+
+        let recorder = BlockRecorder {
+            max_frames: Arc::new(AtomicUsize::new(0)),
+            num_blocks: Arc::new(AtomicUsize::new(0)),
+        };
+        let node_id = cx.add_node(recorder.clone(), None).unwrap();
+        cx.connect(node_id, cx.graph_out_node_id(), &[(0, 0)], false)
+            .unwrap();
+
+        let mut backend = backend::ManualBackend::new(
+            &mut cx,
+            backend::ManualBackendConfig {
+                max_block_frames: NonZeroU32::new(1024).unwrap(),
+                num_out_channels: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        cx.update().unwrap();
+
+        assert_eq!(cx.stream_info().unwrap().max_block_frames.get(), 64);
+
+        // A single backend block is split into blocks of at most 64 frames.
+        let mut out = [0.0; 256];
+        backend.process(&[], &mut out, 256);
+
+        assert_eq!(recorder.max_frames.load(Ordering::Relaxed), 64);
+        assert_eq!(recorder.num_blocks.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn declicked_edge_removed_after_fade() {
+        let mut cx = FirewheelContext::new(FirewheelConfig {
+            num_graph_outputs: ChannelCount::MONO,
+            declick_connections: true,
+            ..Default::default()
+        });
+
+        let node_id = cx.add_node(ConstSource(1.0), None).unwrap();
+        cx.connect(node_id, cx.graph_out_node_id(), &[(0, 0)], false)
+            .unwrap();
+
+        let mut backend = backend::ManualBackend::new(
+            &mut cx,
+            backend::ManualBackendConfig {
+                num_out_channels: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        cx.update().unwrap();
+
+        let mut out = [0.0; 1024];
+        backend.process(&[], &mut out, 1024);
+        cx.update().unwrap();
+        assert_eq!(cx.graph_stats().unwrap().num_edges, 1);
+
+        cx.disconnect(node_id, cx.graph_out_node_id(), &[(0, 0)]);
+        cx.update().unwrap();
+
+        // The removed edge is kept in the schedule while it fades out. Since
+        // no audio has been processed, the fade cannot have finished yet, no
+        // matter how much time has passed.
+        assert_eq!(cx.graph_stats().unwrap().num_edges, 1);
+        cx.update().unwrap();
+        assert_eq!(cx.graph_stats().unwrap().num_edges, 1);
+
+        backend.process(&[], &mut out, 1024);
+        assert_eq!(out[1023], 0.0);
+
+        // Once the processor reports that the fade has finished, the edge is
+        // removed from the schedule.
+        cx.update().unwrap();
+        assert_eq!(cx.graph_stats().unwrap().num_edges, 0);
+    }
+
+    #[test]
+    fn rewire() {
+        let mut cx = FirewheelContext::new(FirewheelConfig {
+            num_graph_inputs: ChannelCount::MONO,
+            num_graph_outputs: ChannelCount::MONO,
+            ..Default::default()
+        });
+
+        let graph_in = cx.graph_in_node_id();
+        let graph_out = cx.graph_out_node_id();
+        let node_a = cx.add_node(Scale(1.0), None).unwrap();
+        let node_b = cx.add_node(Scale(1.0), None).unwrap();
+
+        let in_to_a = Connection::new(graph_in, 0, node_a, 0);
+        let a_to_out = Connection::new(node_a, 0, graph_out, 0);
+        let in_to_b = Connection::new(graph_in, 0, node_b, 0);
+        let b_to_out = Connection::new(node_b, 0, graph_out, 0);
+
+        cx.rewire(&[], &[in_to_a, a_to_out], false).unwrap();
+        assert_eq!(connections(&cx).len(), 2);
+
+        // Swap node a for node b.
+        let edge_ids = cx
+            .rewire(&[in_to_a, a_to_out], &[in_to_b, b_to_out], true)
+            .unwrap();
+
+        assert_eq!(edge_ids.len(), 2);
+        assert_eq!(Connection::from(*cx.edge(edge_ids[0]).unwrap()), in_to_b);
+        assert_eq!(Connection::from(*cx.edge(edge_ids[1]).unwrap()), b_to_out);
+
+        let edges = connections(&cx);
+        assert_eq!(edges.len(), 2);
+        assert!(edges.contains(&in_to_b) && edges.contains(&b_to_out));
+
+        // A rewire which fails must leave the existing edges intact, including
+        // the ones it would have removed.
+        let res = cx.rewire(
+            &[in_to_b],
+            &[
+                Connection::new(node_a, 0, node_b, 0),
+                Connection::new(node_b, 0, node_a, 0),
+            ],
+            true,
+        );
+        assert_eq!(res, Err(AddEdgeError::CycleDetected));
+
+        let edges = connections(&cx);
+        assert_eq!(edges.len(), 2);
+        assert!(edges.contains(&in_to_b) && edges.contains(&b_to_out));
+    }
+
+    #[test]
+    fn bypass_node() {
+        let mut cx = FirewheelContext::new(FirewheelConfig {
+            num_graph_outputs: ChannelCount::MONO,
+            ..Default::default()
+        });
+
+        let source = cx.add_node(ConstSource(0.5), None).unwrap();
+        let scale = cx.add_node(Scale(0.5), None).unwrap();
+        cx.connect(source, scale, &[(0, 0)], false).unwrap();
+        cx.connect(scale, cx.graph_out_node_id(), &[(0, 0)], false)
+            .unwrap();
+
+        let mut backend = backend::ManualBackend::new(
+            &mut cx,
+            backend::ManualBackendConfig {
+                num_out_channels: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(cx.node_is_bypassed(scale), Some(false));
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.25);
+
+        // A bypassed node passes its input through unchanged.
+        assert!(cx.set_node_bypassed(scale, true));
+        assert_eq!(cx.node_is_bypassed(scale), Some(true));
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.5);
+
+        assert!(cx.set_node_bypassed(scale, false));
+        assert_eq!(cx.node_is_bypassed(scale), Some(false));
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.25);
+
+        cx.remove_node(scale).unwrap();
+        assert!(!cx.set_node_bypassed(scale, true));
+        assert_eq!(cx.node_is_bypassed(scale), None);
+    }
+
+    #[test]
+    fn mute_solo() {
+        let mut cx = FirewheelContext::new(FirewheelConfig {
+            num_graph_outputs: ChannelCount::MONO,
+            ..Default::default()
+        });
+
+        let graph_out = cx.graph_out_node_id();
+        let node_a = cx.add_node(ConstSource(0.25), None).unwrap();
+        let node_b = cx.add_node(ConstSource(0.5), None).unwrap();
+        cx.connect(node_a, graph_out, &[(0, 0)], false).unwrap();
+        cx.connect(node_b, graph_out, &[(0, 0)], false).unwrap();
+
+        let mut backend = backend::ManualBackend::new(
+            &mut cx,
+            backend::ManualBackendConfig {
+                num_out_channels: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Add both nodes to the mute/solo group.
+        assert!(cx.set_mute(node_a, false));
+        assert!(cx.set_mute(node_b, false));
+        assert_eq!(
+            cx.node_info(node_a).unwrap().mute_solo,
+            Some(MuteSoloState::default())
+        );
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.75);
+
+        // Soloing one node mutes the others.
+        assert!(cx.set_solo(node_a, true));
+        assert_eq!(cx.node_is_soloed(node_a), Some(true));
+        assert_eq!(cx.node_is_muted(node_b), Some(false));
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.25);
+
+        // Unsoloing restores them.
+        assert!(cx.set_solo(node_a, false));
+        assert_eq!(cx.node_is_soloed(node_a), Some(false));
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.75);
+
+        // A node which was muted before the solo stays muted once the solo is
+        // cleared.
+        assert!(cx.set_mute(node_b, true));
+        assert!(cx.set_solo(node_a, true));
+        assert_eq!(
+            cx.node_info(node_a).unwrap().mute_solo,
+            Some(MuteSoloState {
+                muted: false,
+                soloed: true,
+            })
+        );
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.25);
+
+        assert!(cx.set_solo(node_a, false));
+        assert_eq!(cx.node_is_muted(node_b), Some(true));
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.25);
+
+        assert!(cx.set_mute(node_b, false));
+        assert_eq!(settled_output(&mut cx, &mut backend), 0.75);
+    }
+
+    #[test]
+    fn render_taps_in_schedule_order() {
+        /// A node which outputs the order in which it was processed.
+        #[derive(Clone)]
+        struct ProcessOrder(Arc<AtomicUsize>);
+
+        impl AudioNode for ProcessOrder {
+            type Configuration = EmptyConfig;
+
+            fn info(&self, _: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+                Ok(AudioNodeInfo::new().channel_config(ChannelConfig {
+                    num_inputs: ChannelCount::MONO,
+                    num_outputs: ChannelCount::MONO,
+                }))
+            }
+
+            fn construct_processor(
+                &self,
+                _: &Self::Configuration,
+                _: ConstructProcessorContext,
+            ) -> Result<impl AudioNodeProcessor, NodeError> {
+                Ok(self.clone())
+            }
+        }
+
+        impl AudioNodeProcessor for ProcessOrder {
+            fn process(
+                &mut self,
+                info: &ProcInfo,
+                buffers: ProcBuffers,
+                _: &mut ProcExtra,
+            ) -> ProcessStatus {
+                let order = self.0.fetch_add(1, Ordering::Relaxed) + 1;
+                buffers.outputs[0][..info.frames].fill(order as f32);
+
+                ProcessStatus::OutputsModified
+            }
+        }
+
+        const FRAMES: usize = 256;
+
+        let mut cx = FirewheelContext::new(FirewheelConfig {
+            num_graph_outputs: ChannelCount::MONO,
+            ..Default::default()
+        });
+
+        // Add the nodes in reverse so that the order of the schedule differs
+        // from the order in which the nodes were added.
+        let counter = Arc::new(AtomicUsize::new(0));
+        let node_c = cx.add_node(ProcessOrder(counter.clone()), None).unwrap();
+        let node_b = cx.add_node(ProcessOrder(counter.clone()), None).unwrap();
+        let node_a = cx.add_node(ProcessOrder(counter.clone()), None).unwrap();
+        cx.connect(node_a, node_b, &[(0, 0)], false).unwrap();
+        cx.connect(node_b, node_c, &[(0, 0)], false).unwrap();
+        cx.connect(node_c, cx.graph_out_node_id(), &[(0, 0)], false)
+            .unwrap();
+
+        let mut processor = cx
+            .activate(ActivateInfo {
+                sample_rate: NonZeroU32::new(48_000).unwrap(),
+                max_block_frames: NonZeroU32::new(FRAMES as u32).unwrap(),
+                num_stream_in_channels: 0,
+                num_stream_out_channels: 1,
+                input_to_output_latency_seconds: 0.0,
+            })
+            .unwrap();
+        cx.update().unwrap();
+
+        let mut tap_a = [0.0; FRAMES];
+        let mut tap_b = [0.0; FRAMES];
+        let mut tap_c = [0.0; FRAMES];
+        let mut out = [0.0; FRAMES];
+
+        {
+            let mut bufs_a = [&mut tap_a[..]];
+            let mut bufs_b = [&mut tap_b[..]];
+            let mut bufs_c = [&mut tap_c[..]];
+
+            processor.process_with_taps(
+                &InterleavedSlice::new(&[], 0, 0).unwrap(),
+                &mut InterleavedSlice::new_mut(&mut out, 1, FRAMES).unwrap(),
+                BackendProcessInfo {
+                    frames: FRAMES,
+                    process_timestamp: None,
+                    duration_since_stream_start: Duration::default(),
+                    input_stream_status: StreamStatus::empty(),
+                    output_stream_status: StreamStatus::empty(),
+                    dropped_frames: 0,
+                    process_to_playback_delay: None,
+                },
+                &mut [
+                    RenderTap::new(node_c, &mut bufs_c),
+                    RenderTap::new(node_a, &mut bufs_a),
+                    RenderTap::new(node_b, &mut bufs_b),
+                ],
+            );
+        }
+
+        // Each tap holds the outputs of its node at the time it was processed.
+        assert!(tap_a.iter().all(|&s| s == 1.0));
+        assert!(tap_b.iter().all(|&s| s == 2.0));
+        assert!(tap_c.iter().all(|&s| s == 3.0));
+    }
+
+    #[test]
+    fn parallel_processing_matches_serial() {
+        fn render(parallel_processing: bool) -> Vec<f32> {
+            let mut cx = FirewheelContext::new(FirewheelConfig {
+                num_graph_outputs: ChannelCount::MONO,
+                parallel_processing,
+                ..Default::default()
+            });
+
+            // Several independent branches of varying depth, so that each
+            // level of the schedule has nodes which can run in parallel.
+            let mut scales = Vec::new();
+            for branch in 0..8 {
+                let mut prev = cx
+                    .add_node(ConstSource(0.01 * (branch + 1) as f32), None)
+                    .unwrap();
+
+                for _ in 0..(branch % 3) {
+                    let scale = cx.add_node(Scale(0.5), None).unwrap();
+                    cx.connect(prev, scale, &[(0, 0)], false).unwrap();
+                    scales.push(scale);
+                    prev = scale;
+                }
+
+                cx.connect(prev, cx.graph_out_node_id(), &[(0, 0)], false)
+                    .unwrap();
+            }
+
+            let mut backend = backend::ManualBackend::new(
+                &mut cx,
+                backend::ManualBackendConfig {
+                    num_out_channels: 1,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+            let mut out = vec![0.0; 2048];
+            backend
+                .flush_and_process(&mut cx, &[], &mut out[..1024], 1024)
+                .unwrap();
+
+            // Sending an event to a node processes it on the audio thread.
+            assert!(cx.set_node_bypassed(scales[0], true));
+            backend
+                .flush_and_process(&mut cx, &[], &mut out[1024..], 1024)
+                .unwrap();
+
+            out
+        }
+
+        let serial = render(false);
+        let parallel = render(true);
+
+        assert!(serial.iter().any(|&s| s != 0.0));
+        assert_eq!(serial, parallel);
+    }
 }
@@ -0,0 +1,255 @@
+//! A pool of nodes for playing a [`VoiceNode`] polyphonically.
+
+use firewheel_core::{
+    diff::{Diff, Memo},
+    node::{NodeID, VoiceNode},
+};
+
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::Vec;
+
+use crate::{
+    FirewheelContext,
+    error::{ModifyGraphError, RemoveNodeError},
+    graph::PortIdx,
+};
+
+/// The state of a single voice in a [`NodePool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceState {
+    /// The voice has never been played.
+    Idle,
+    /// The voice is playing a note which is still held.
+    Held,
+    /// The note of this voice has been released, but the voice may still be
+    /// ringing out (i.e. during the release stage of an envelope).
+    Released,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct VoiceSlot {
+    note: Option<u8>,
+    state: VoiceState,
+    /// The value of [`NodePool::clock`] when the state of this voice last
+    /// changed.
+    stamp: u64,
+}
+
+impl VoiceSlot {
+    /// How suitable this voice is to be stolen for a new note, where a higher
+    /// score is more suitable.
+    ///
+    /// Idle voices are always chosen first, then the voice which was released
+    /// the longest time ago, and finally the voice whose note has been held
+    /// the longest.
+    fn worker_score(&self, now: u64) -> u64 {
+        const HALF: u64 = u64::MAX / 2;
+
+        let age = now.saturating_sub(self.stamp).min(HALF - 1);
+
+        match self.state {
+            VoiceState::Idle => u64::MAX,
+            VoiceState::Released => HALF + age,
+            VoiceState::Held => age,
+        }
+    }
+}
+
+struct PoolVoice<T> {
+    node_id: NodeID,
+    params: Memo<T>,
+    slot: VoiceSlot,
+}
+
+/// A pool of identical nodes which are allocated to notes, allowing a
+/// [`VoiceNode`] (i.e. a synth voice) to be played polyphonically.
+///
+/// Each note-on is given to an idle voice if there is one. Otherwise a voice
+/// is stolen, preferring the voice which was released the longest time ago,
+/// and then the voice whose note has been held the longest.
+pub struct NodePool<T: VoiceNode + Diff + Clone> {
+    voices: Vec<PoolVoice<T>>,
+    /// Incremented on every note-on and note-off.
+    clock: u64,
+}
+
+impl<T: VoiceNode + Diff + Clone + 'static> NodePool<T> {
+    /// Add `num_voices` copies of `node` to the graph, and connect each one
+    /// to `dst_node` with the given ports.
+    ///
+    /// If this returns an error, then the audio graph has not been modified.
+    pub fn new(
+        node: T,
+        config: Option<T::Configuration>,
+        num_voices: usize,
+        dst_node: NodeID,
+        ports_src_dst: &[(PortIdx, PortIdx)],
+        cx: &mut FirewheelContext,
+    ) -> Result<Self, ModifyGraphError>
+    where
+        T::Configuration: Clone,
+    {
+        let mut voices = Vec::with_capacity(num_voices);
+
+        cx.try_modify_graph(|cx| {
+            for _ in 0..num_voices {
+                let node_id = cx.add_node(node.clone(), config.clone())?;
+                cx.connect(node_id, dst_node, ports_src_dst, false)?;
+
+                voices.push(PoolVoice {
+                    node_id,
+                    params: Memo::new(node.clone()),
+                    slot: VoiceSlot {
+                        note: None,
+                        state: VoiceState::Idle,
+                        stamp: 0,
+                    },
+                });
+            }
+
+            Ok::<(), ModifyGraphError>(())
+        })?;
+
+        Ok(Self { voices, clock: 0 })
+    }
+
+    /// The number of voices in this pool.
+    pub fn num_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// The IDs of the nodes in this pool.
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeID> + '_ {
+        self.voices.iter().map(|v| v.node_id)
+    }
+
+    /// The note and the state of each voice in this pool.
+    pub fn voice_states(&self) -> impl Iterator<Item = (Option<u8>, VoiceState)> + '_ {
+        self.voices.iter().map(|v| (v.slot.note, v.slot.state))
+    }
+
+    /// Start playing the given note, and return the ID of the node it was
+    /// allocated to.
+    ///
+    /// If the note is already playing (or ringing out), then the same voice
+    /// is retriggered. Otherwise a voice is allocated (or stolen) for it.
+    ///
+    /// Returns `None` if the pool has no voices.
+    ///
+    /// * `note` - The MIDI note number in the range `[0, 127]`, where `60` is
+    ///   middle C.
+    /// * `velocity` - The velocity of the note in the range `[0.0, 1.0]`.
+    pub fn note_on(
+        &mut self,
+        note: u8,
+        velocity: f32,
+        cx: &mut FirewheelContext,
+    ) -> Option<NodeID> {
+        let index = self
+            .voices
+            .iter()
+            .position(|v| v.slot.note == Some(note))
+            .or_else(|| self.steal_voice())?;
+
+        self.clock += 1;
+
+        let voice = &mut self.voices[index];
+        voice.params.note_on(note, velocity);
+        voice.params.update_memo(&mut cx.event_queue(voice.node_id));
+        voice.slot = VoiceSlot {
+            note: Some(note),
+            state: VoiceState::Held,
+            stamp: self.clock,
+        };
+
+        Some(voice.node_id)
+    }
+
+    /// Release the given note.
+    ///
+    /// Returns `false` if the note is not currently held.
+    pub fn note_off(&mut self, note: u8, cx: &mut FirewheelContext) -> bool {
+        let Some(voice) = self
+            .voices
+            .iter_mut()
+            .find(|v| v.slot.note == Some(note) && v.slot.state == VoiceState::Held)
+        else {
+            return false;
+        };
+
+        self.clock += 1;
+
+        voice.params.note_off();
+        voice.params.update_memo(&mut cx.event_queue(voice.node_id));
+        voice.slot.state = VoiceState::Released;
+        voice.slot.stamp = self.clock;
+
+        true
+    }
+
+    /// Release all notes which are currently held.
+    pub fn all_notes_off(&mut self, cx: &mut FirewheelContext) {
+        self.clock += 1;
+
+        for voice in self.voices.iter_mut() {
+            if voice.slot.state == VoiceState::Held {
+                voice.params.note_off();
+                voice.params.update_memo(&mut cx.event_queue(voice.node_id));
+                voice.slot.state = VoiceState::Released;
+                voice.slot.stamp = self.clock;
+            }
+        }
+    }
+
+    /// Modify the parameters of every voice in this pool (i.e. to change the
+    /// waveform of a synth), and queue the resulting events.
+    pub fn update_all(&mut self, cx: &mut FirewheelContext, mut f: impl FnMut(&mut T)) {
+        for voice in self.voices.iter_mut() {
+            (f)(&mut voice.params);
+            voice.params.update_memo(&mut cx.event_queue(voice.node_id));
+        }
+    }
+
+    /// Remove all of the nodes in this pool from the graph.
+    pub fn remove(self, cx: &mut FirewheelContext) -> Result<(), RemoveNodeError> {
+        for voice in self.voices.iter() {
+            cx.remove_node(voice.node_id)?;
+        }
+
+        Ok(())
+    }
+
+    fn steal_voice(&self) -> Option<usize> {
+        self.voices
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, v)| v.slot.worker_score(self.clock))
+            .map(|(i, _)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_score_order() {
+        let slot = |state, stamp| VoiceSlot {
+            note: Some(60),
+            state,
+            stamp,
+        };
+        let now = 10;
+
+        let idle = slot(VoiceState::Idle, 0).worker_score(now);
+        let old_released = slot(VoiceState::Released, 2).worker_score(now);
+        let new_released = slot(VoiceState::Released, 8).worker_score(now);
+        let old_held = slot(VoiceState::Held, 1).worker_score(now);
+        let new_held = slot(VoiceState::Held, 9).worker_score(now);
+
+        assert!(idle > old_released);
+        assert!(old_released > new_released);
+        assert!(new_released > old_held);
+        assert!(old_held > new_held);
+    }
+}
@@ -3,12 +3,12 @@ use bevy_platform::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
 };
-use core::num::NonZeroU32;
+use core::num::{NonZeroU32, NonZeroUsize};
 use ringbuf::traits::Producer;
 use thunderdome::Arena;
 
 #[cfg(not(feature = "std"))]
-use bevy_platform::prelude::{Box, Vec};
+use bevy_platform::prelude::{Box, Vec, vec};
 
 use bevy_platform::time::Instant;
 
@@ -16,11 +16,12 @@ use firewheel_core::{
     StreamInfo,
     clock::InstantSamples,
     dsp::{
-        buffer::ConstSequentialBuffer,
+        buffer::{ConstSequentialBuffer, SequentialBuffer},
         declick::{DeclickValues, Declicker},
     },
     event::{NodeEvent, ProcEventsIndex},
-    node::{AudioNodeProcessor, ProcExtra},
+    node::{AudioNodeProcessor, NodeID, ProcExtra},
+    param::smoother::{SmoothedParam, SmootherConfig},
 };
 
 use crate::{
@@ -29,17 +30,19 @@ use crate::{
     graph::ScheduleHeapData,
     processor::{
         event_scheduler::{EventScheduler, NodeEventSchedulerData},
+        limiter::OutputLimiter,
         profiling::ProfilerTx,
     },
 };
 
+pub use limiter::LimiterConfig;
+#[cfg(feature = "node_profiling")]
+pub use profiling::NodeProfileData;
 pub use profiling::ProfilingData;
 
 #[cfg(feature = "scheduled_events")]
 use crate::context::ClearScheduledEventsType;
 #[cfg(feature = "scheduled_events")]
-use firewheel_core::node::NodeID;
-#[cfg(feature = "scheduled_events")]
 use smallvec::SmallVec;
 
 #[cfg(feature = "musical_transport")]
@@ -47,9 +50,15 @@ use firewheel_core::clock::{InstantMusical, TransportState};
 
 mod event_scheduler;
 mod handle_messages;
+mod limiter;
+#[cfg(feature = "std")]
+mod parallel;
 mod process;
 pub(crate) mod profiling;
 
+#[cfg(feature = "std")]
+pub(crate) use parallel::num_worker_threads;
+
 #[cfg(feature = "musical_transport")]
 mod transport;
 #[cfg(feature = "musical_transport")]
@@ -85,19 +94,43 @@ impl FirewheelProcessor {
         input: &dyn Adapter<'_, f32>,
         output: &mut dyn AdapterMut<'_, f32>,
         info: BackendProcessInfo,
+    ) {
+        self.process_with_taps(input, output, info, &mut []);
+    }
+
+    /// Process the audio graph, while also copying the outputs of the nodes in
+    /// `taps` into their corresponding buffers.
+    ///
+    /// This is useful for rendering separate stems of sub-mixes in a single
+    /// pass when rendering offline.
+    pub fn process_with_taps(
+        &mut self,
+        input: &dyn Adapter<'_, f32>,
+        output: &mut dyn AdapterMut<'_, f32>,
+        info: BackendProcessInfo,
+        taps: &mut [RenderTap<'_, '_>],
     ) {
         self.poll_drop_flag();
 
         if let Some(inner) = &mut self.inner {
-            inner.process(input, output, info);
+            inner.process(input, output, info, taps);
         } else {
             output.fill_frames_with(0, info.frames, &0.0);
         }
     }
 
     fn poll_drop_flag(&mut self) {
-        if self.inner.is_some() && self.drop_flag.load(Ordering::Relaxed) {
-            self.drop_inner();
+        let Some(inner) = &mut self.inner else {
+            return;
+        };
+
+        if self.drop_flag.load(Ordering::Relaxed) {
+            // Fade out the output before stopping to avoid a pop.
+            if inner.output_faded_out() {
+                self.drop_inner();
+            } else {
+                inner.fade_out_output();
+            }
         }
     }
 
@@ -118,6 +151,29 @@ impl FirewheelProcessor {
     }
 }
 
+/// A node whose outputs are copied into separate buffers while processing
+/// with [`FirewheelProcessor::process_with_taps`].
+pub struct RenderTap<'a, 'b> {
+    /// The ID of the node to tap.
+    pub node_id: NodeID,
+    /// The de-interleaved buffers to copy the outputs of the node into.
+    ///
+    /// Each buffer must be at least as long as the number of frames being
+    /// processed. Output channels of the node without a corresponding buffer
+    /// are ignored, and buffers without a corresponding output channel are
+    /// left untouched.
+    ///
+    /// If the node was not processed (i.e. because it is not connected to the
+    /// graph), then the buffers are left untouched.
+    pub buffers: &'a mut [&'b mut [f32]],
+}
+
+impl<'a, 'b> RenderTap<'a, 'b> {
+    pub fn new(node_id: NodeID, buffers: &'a mut [&'b mut [f32]]) -> Self {
+        Self { node_id, buffers }
+    }
+}
+
 pub(crate) struct FirewheelProcessorInner {
     nodes: Arena<NodeEntry>,
     schedule_data: Option<Box<ScheduleHeapData>>,
@@ -143,12 +199,23 @@ pub(crate) struct FirewheelProcessorInner {
     flags: FirewheelBitFlags,
     shared_flags: Arc<SharedFlags>,
     clamp_graph_inputs_below_amp: Option<f32>,
+    output_limiter: Option<OutputLimiter>,
+    output_gain: SmoothedParam,
+    /// Fades the final output in when the stream starts and out before the
+    /// processor is stopped to avoid pops.
+    output_declick: Declicker,
+    output_declick_gain: Vec<f32>,
 
     last_input_overflow_log_instant: Option<Instant>,
     last_output_underflow_log_instant: Option<Instant>,
 
     pub(crate) extra: ProcExtra,
 
+    /// The threads which process nodes in parallel. This is only `Some` if
+    /// parallel processing is enabled.
+    #[cfg(feature = "std")]
+    worker_pool: Option<parallel::WorkerPool>,
+
     /// If a panic occurs while processing, this flag is set to let the
     /// main thread know that it shouldn't try spawning a new audio stream
     /// with the shared `Arc<AtomicRefCell<FirewheelProcessorInner>>` object.
@@ -160,9 +227,11 @@ pub(crate) struct FirewheelProcessorConfig {
     pub immediate_event_buffer_capacity: usize,
     pub buffer_out_of_space_mode: BufferOutOfSpaceMode,
     pub clamp_graph_inputs_below_amp: Option<f32>,
+    pub output_limiter: Option<LimiterConfig>,
     pub node_event_buffer_capacity: usize,
     #[cfg(feature = "scheduled_events")]
     pub scheduled_event_buffer_capacity: usize,
+    pub num_extra_scratch_buffers: usize,
 }
 
 impl FirewheelProcessorInner {
@@ -177,9 +246,11 @@ impl FirewheelProcessorInner {
             immediate_event_buffer_capacity,
             buffer_out_of_space_mode,
             clamp_graph_inputs_below_amp,
+            output_limiter,
             node_event_buffer_capacity,
             #[cfg(feature = "scheduled_events")]
             scheduled_event_buffer_capacity,
+            num_extra_scratch_buffers,
         } = config;
 
         let ProcessorChannel {
@@ -191,8 +262,15 @@ impl FirewheelProcessorInner {
             profiler_tx,
             #[cfg(feature = "scheduled_events")]
             shared_clock_input,
+            #[cfg(feature = "std")]
+            thread_loggers,
         } = proc_channel;
 
+        let declick_values = DeclickValues::new(stream_info.declick_frames);
+
+        let mut output_declick = Declicker::SettledAt0;
+        output_declick.fade_to_1(&declick_values);
+
         Self {
             nodes: Arena::new(),
             schedule_data: None,
@@ -217,36 +295,100 @@ impl FirewheelProcessorInner {
             flags,
             shared_flags,
             clamp_graph_inputs_below_amp,
+            output_limiter: output_limiter.map(|limiter_config| {
+                OutputLimiter::new(
+                    limiter_config,
+                    stream_info.num_stream_out_channels as usize,
+                    stream_info.sample_rate.get(),
+                    stream_info.max_block_frames.get() as usize,
+                )
+            }),
+            output_gain: SmoothedParam::new(
+                1.0,
+                SmootherConfig::default(),
+                stream_info.sample_rate,
+            ),
+            output_declick,
+            output_declick_gain: vec![0.0; stream_info.max_block_frames.get() as usize],
             last_input_overflow_log_instant: None,
             last_output_underflow_log_instant: None,
             extra: ProcExtra {
                 scratch_buffers: ConstSequentialBuffer::new(
                     stream_info.max_block_frames.get() as usize
                 ),
-                declick_values: DeclickValues::new(stream_info.declick_frames),
+                extra_scratch_buffers: new_extra_scratch_buffers(
+                    num_extra_scratch_buffers,
+                    stream_info.max_block_frames.get() as usize,
+                ),
+                declick_values,
                 logger,
                 store,
             },
+            #[cfg(feature = "std")]
+            worker_pool: (!thread_loggers.is_empty()).then(|| {
+                parallel::WorkerPool::new(
+                    thread_loggers,
+                    stream_info,
+                    num_extra_scratch_buffers,
+                    node_event_buffer_capacity,
+                )
+            }),
             poisoned: false,
         }
     }
 }
 
+fn new_extra_scratch_buffers(
+    num_buffers: usize,
+    max_block_frames: usize,
+) -> Option<SequentialBuffer<f32>> {
+    NonZeroUsize::new(num_buffers)
+        .map(|num_buffers| SequentialBuffer::new(num_buffers, max_block_frames))
+}
+
 pub(crate) struct NodeEntry {
     pub processor: Box<dyn AudioNodeProcessor>,
     pub prev_output_was_silent: bool,
     pub bypass_declick: Declicker,
     pub is_bypassed: bool,
+    /// Fades the outputs of the node to silence when the node is muted.
+    pub mute_declick: Declicker,
     pub is_first_process: bool,
     pub in_place_buffers: bool,
+    /// If `true`, then this node is never put to sleep when its inputs are
+    /// silent.
+    pub continue_processing_while_silent: bool,
+    /// The number of frames all inputs of this node have been silent for.
+    pub silent_input_frames: u64,
+    /// The time in seconds spent processing this node in the current block.
+    /// This is only used when the schedule is processed in parallel.
+    #[cfg(feature = "node_profiling")]
+    pub cpu_seconds: f64,
 
     event_data: NodeEventSchedulerData,
 }
 
+/// A pointer to a [`NodeEntry`] in the processor's arena, used to access the
+/// nodes from the threads which process nodes in parallel.
+#[derive(Clone, Copy)]
+pub(crate) struct NodeEntryPtr(*mut NodeEntry);
+
+impl Default for NodeEntryPtr {
+    fn default() -> Self {
+        Self(core::ptr::null_mut())
+    }
+}
+
+// SAFETY: The pointers are only dereferenced while processing, during which
+// each node is only accessed by one thread at a time.
+unsafe impl Send for NodeEntryPtr {}
+unsafe impl Sync for NodeEntryPtr {}
+
 pub(crate) enum ContextToProcessorMsg {
     EventGroup(Vec<NodeEvent>),
     NewSchedule(Box<ScheduleHeapData>),
     SetFlags(FirewheelBitFlags),
+    SetOutputGain(f32),
     #[cfg(feature = "musical_transport")]
     SetTransportState(Box<TransportState>),
     #[cfg(feature = "scheduled_events")]
@@ -257,6 +399,8 @@ pub(crate) enum ContextToProcessorMsg {
 pub(crate) enum ProcessorToContextMsg {
     DropEventGroup(Vec<NodeEvent>),
     DropSchedule(Box<ScheduleHeapData>),
+    /// An edge fade with the given ID has finished.
+    EdgeFadeFinished(u64),
     #[cfg(feature = "musical_transport")]
     DropTransportState(Box<TransportState>),
     #[cfg(feature = "scheduled_events")]
@@ -105,7 +105,26 @@ impl EventScheduler {
         #[cfg(feature = "scheduled_events")]
         self.truncate_elapsed_events();
 
-        for event in event_group.drain(..) {
+        // State requests are answered immediately, and they are kept in the
+        // event group so that they are sent back to the context along with it.
+        let mut num_state_requests = 0;
+        for i in 0..event_group.len() {
+            let event = &mut event_group[i];
+            let NodeEventType::RequestState { snapshot, filled } = &mut event.event else {
+                continue;
+            };
+
+            if let Some(node_entry) = nodes.get(event.node_id.0) {
+                *filled = node_entry.processor.snapshot_state(&mut ***snapshot);
+            }
+
+            // Move the request to the front while keeping the order of the
+            // remaining events intact.
+            event_group[num_state_requests..=i].rotate_right(1);
+            num_state_requests += 1;
+        }
+
+        for event in event_group.drain(num_state_requests..) {
             if let Some(node_entry) = nodes.get_mut(event.node_id.0) {
                 self.push_event(
                     event,
@@ -584,29 +603,37 @@ impl EventScheduler {
         >],
                           event: ProcEventsIndex,
                           logger: &mut RealtimeLogger,
-                          set_bypassed: &mut Option<bool>| {
-            match event {
-                ProcEventsIndex::Immediate(i) => {
-                    if let Some(event) = immediate_event_buffer
-                        .get(i as usize)
-                        .and_then(|e| e.as_ref())
-                        && let NodeEventType::SetBypassed(bypassed) = &event.event
-                    {
-                        *set_bypassed = Some(*bypassed);
-                        return;
-                    }
-                }
+                          set_bypassed: &mut Option<bool>,
+                          set_muted: &mut Option<bool>,
+                          reset: &mut bool| {
+            let event_type = match event {
+                ProcEventsIndex::Immediate(i) => immediate_event_buffer
+                    .get(i as usize)
+                    .and_then(|e| e.as_ref())
+                    .map(|e| &e.event),
                 #[cfg(feature = "scheduled_events")]
-                ProcEventsIndex::Scheduled(i) => {
-                    if let Some(event) = scheduled_event_arena
-                        .get(i as usize)
-                        .and_then(|e| e.as_ref())
-                        && let NodeEventType::SetBypassed(bypassed) = &event.event.event
-                    {
-                        *set_bypassed = Some(*bypassed);
-                        return;
-                    }
+                ProcEventsIndex::Scheduled(i) => scheduled_event_arena
+                    .get(i as usize)
+                    .and_then(|e| e.as_ref())
+                    .map(|e| &e.event.event),
+            };
+
+            // Bypass, mute, and reset events are handled by the engine instead
+            // of the node.
+            match event_type {
+                Some(NodeEventType::SetBypassed(bypassed)) => {
+                    *set_bypassed = Some(*bypassed);
+                    return;
+                }
+                Some(NodeEventType::SetMuted(muted)) => {
+                    *set_muted = Some(*muted);
+                    return;
                 }
+                Some(NodeEventType::Reset) => {
+                    *reset = true;
+                    return;
+                }
+                _ => {}
             }
 
             if node_event_queue.len() == node_event_queue.capacity() {
@@ -633,14 +660,18 @@ impl EventScheduler {
         #[cfg(feature = "scheduled_events")]
         let mut sorted_event_i = node_entry.event_data.first_sorted_event_index;
 
+        // These are declared outside of the loop so that engine events which
+        // are carried over to the next sub-chunk are not lost.
+        let mut set_bypassed: Option<bool> = None;
+        let mut set_muted: Option<bool> = None;
+        let mut reset = false;
+
         let mut sub_clock_samples = clock_samples;
         let mut frames_processed = 0;
         while frames_processed < block_frames {
             #[allow(unused_mut)]
             let mut sub_chunk_frames = block_frames - frames_processed;
 
-            let mut set_bypassed: Option<bool> = None;
-
             // Add scheduled events to the processing queue.
             #[cfg(feature = "scheduled_events")]
             let mut upcoming_event_slot = None;
@@ -682,6 +713,8 @@ impl EventScheduler {
                         ProcEventsIndex::Scheduled(slot),
                         &mut extra.logger,
                         &mut set_bypassed,
+                        &mut set_muted,
+                        &mut reset,
                     );
                 } else {
                     // Else set the length of this sub-chunk to process up to this event.
@@ -718,6 +751,8 @@ impl EventScheduler {
                     ProcEventsIndex::Immediate(*clump_event_start_i),
                     &mut extra.logger,
                     &mut set_bypassed,
+                    &mut set_muted,
+                    &mut reset,
                 );
 
                 node_entry.event_data.num_immediate_events -= 1;
@@ -741,6 +776,8 @@ impl EventScheduler {
                                 ProcEventsIndex::Immediate(event_i as u32),
                                 &mut extra.logger,
                                 &mut set_bypassed,
+                                &mut set_muted,
+                                &mut reset,
                             );
 
                             node_entry.event_data.num_immediate_events -= 1;
@@ -776,7 +813,9 @@ impl EventScheduler {
                 proc_buffers: &mut proc_buffers,
                 events: &mut node_event_list,
                 extra,
-                set_bypassed,
+                set_bypassed: set_bypassed.take(),
+                set_muted: set_muted.take(),
+                reset: core::mem::take(&mut reset),
             });
 
             // Ensure that all `ArcGc`s have been cleaned up.
@@ -800,6 +839,8 @@ impl EventScheduler {
                     ProcEventsIndex::Scheduled(slot),
                     &mut extra.logger,
                     &mut set_bypassed,
+                    &mut set_muted,
+                    &mut reset,
                 );
             }
 
@@ -813,6 +854,54 @@ impl EventScheduler {
         assert_eq!(node_entry.event_data.num_scheduled_events_this_block, 0);
     }
 
+    /// Returns `true` if the given node has any events to process this block.
+    pub fn node_has_events(node_entry: &NodeEntry) -> bool {
+        #[cfg(feature = "scheduled_events")]
+        if node_entry.event_data.num_scheduled_events_this_block > 0 {
+            return true;
+        }
+
+        node_entry.event_data.num_immediate_events > 0
+    }
+
+    /// Process a node which has no events this block in a single chunk.
+    ///
+    /// Unlike [`EventScheduler::process_node`], this does not access the event
+    /// buffers, so it can be called from any thread.
+    #[expect(clippy::too_many_arguments, reason = "Function needs many arguments")]
+    pub fn process_node_without_events(
+        node_entry: &mut NodeEntry,
+        block_frames: usize,
+        clock_samples: InstantSamples,
+        info: &mut ProcInfo,
+        extra: &mut ProcExtra,
+        proc_event_queue: &mut Vec<ProcEventsIndex>,
+        mut proc_buffers: ProcBuffers,
+        on_sub_chunk: impl FnOnce(ProcessSubChunkInfo),
+    ) {
+        debug_assert!(!Self::node_has_events(node_entry));
+
+        let mut node_event_list = ProcEvents::new(
+            &mut [],
+            #[cfg(feature = "scheduled_events")]
+            &mut [],
+            proc_event_queue,
+        );
+
+        (on_sub_chunk)(ProcessSubChunkInfo {
+            sub_chunk_range: 0..block_frames,
+            sub_clock_samples: clock_samples,
+            node_entry,
+            info,
+            proc_buffers: &mut proc_buffers,
+            events: &mut node_event_list,
+            extra,
+            set_bypassed: None,
+            set_muted: None,
+            reset: false,
+        });
+    }
+
     /// Clean up event buffers
     pub fn cleanup_process_block(&mut self) {
         self.immediate_event_buffer.clear();
@@ -893,6 +982,8 @@ pub(super) struct ProcessSubChunkInfo<'a, 'b, 'c, 'd> {
     pub events: &'a mut ProcEvents<'d>,
     pub extra: &'a mut ProcExtra,
     pub set_bypassed: Option<bool>,
+    pub set_muted: Option<bool>,
+    pub reset: bool,
 }
 
 pub(super) struct NodeEventSchedulerData {
@@ -9,7 +9,7 @@ use firewheel_core::{
 use ringbuf::traits::{Consumer, Producer};
 
 #[cfg(not(feature = "std"))]
-use bevy_platform::prelude::Box;
+use bevy_platform::prelude::{Box, vec};
 
 #[cfg(feature = "musical_transport")]
 use firewheel_core::clock::TransportState;
@@ -18,7 +18,7 @@ use crate::{
     graph::{NodeHeapData, ScheduleHeapData},
     processor::{
         ContextToProcessorMsg, FirewheelProcessorInner, NodeEntry, NodeEventSchedulerData,
-        ProcessorToContextMsg,
+        ProcessorToContextMsg, limiter::OutputLimiter, new_extra_scratch_buffers,
     },
 };
 
@@ -49,6 +49,9 @@ impl FirewheelProcessorInner {
                 ContextToProcessorMsg::SetFlags(flags) => {
                     self.flags = flags;
                 }
+                ContextToProcessorMsg::SetOutputGain(gain) => {
+                    self.output_gain.set_value(gain);
+                }
                 #[cfg(feature = "musical_transport")]
                 ContextToProcessorMsg::SetTransportState(new_transport_state) => {
                     self.set_transport_state(new_transport_state);
@@ -103,6 +106,9 @@ impl FirewheelProcessorInner {
                         processor: node_entry.processor,
                         is_pre_process: false,
                         in_place_buffers: false,
+                        continue_processing_while_silent: false,
+                        bypassed: false,
+                        muted: false,
                     });
                 }
             }
@@ -127,10 +133,26 @@ impl FirewheelProcessorInner {
                             processor: n.processor,
                             prev_output_was_silent: true,
                             event_data: NodeEventSchedulerData::new(n.is_pre_process),
-                            bypass_declick: Declicker::SettledAt1,
+                            // Start out already bypassed so that reconstructed
+                            // processors keep the bypass state of the node.
+                            bypass_declick: if n.bypassed {
+                                Declicker::SettledAt0
+                            } else {
+                                Declicker::SettledAt1
+                            },
                             is_bypassed: false,
+                            // Likewise for the mute state.
+                            mute_declick: if n.muted {
+                                Declicker::SettledAt0
+                            } else {
+                                Declicker::SettledAt1
+                            },
                             is_first_process: true,
                             in_place_buffers: n.in_place_buffers,
+                            continue_processing_while_silent: n.continue_processing_while_silent,
+                            silent_input_frames: 0,
+                            #[cfg(feature = "node_profiling")]
+                            cpu_seconds: 0.0,
                         }
                     )
                     .is_none()
@@ -195,6 +217,19 @@ impl FirewheelProcessorInner {
             );
         }
 
+        if let Some(limiter) = &mut self.output_limiter
+            && (limiter.num_channels() != stream_info.num_stream_out_channels as usize
+                || limiter.max_block_frames() != stream_info.max_block_frames.get() as usize
+                || self.sample_rate != stream_info.sample_rate)
+        {
+            *limiter = OutputLimiter::new(
+                *limiter.config(),
+                stream_info.num_stream_out_channels as usize,
+                stream_info.sample_rate.get(),
+                stream_info.max_block_frames.get() as usize,
+            );
+        }
+
         if self.sample_rate != stream_info.sample_rate {
             self.clock_samples = self
                 .clock_samples
@@ -218,6 +253,8 @@ impl FirewheelProcessorInner {
             self.sample_rate = stream_info.sample_rate;
             self.sample_rate_recip = stream_info.sample_rate_recip;
 
+            self.output_gain.update_sample_rate(stream_info.sample_rate);
+
             self.extra.declick_values = DeclickValues::new(stream_info.declick_frames);
         }
 
@@ -226,6 +263,39 @@ impl FirewheelProcessorInner {
 
             self.extra.scratch_buffers =
                 ConstSequentialBuffer::new(stream_info.max_block_frames.get() as usize);
+            self.output_declick_gain = vec![0.0; stream_info.max_block_frames.get() as usize];
+
+            let num_extra_scratch_buffers = self
+                .extra
+                .extra_scratch_buffers
+                .as_ref()
+                .map(|b| b.num_channels().get())
+                .unwrap_or(0);
+            self.extra.extra_scratch_buffers = new_extra_scratch_buffers(
+                num_extra_scratch_buffers,
+                stream_info.max_block_frames.get() as usize,
+            );
         }
+
+        #[cfg(feature = "std")]
+        if let Some(worker_pool) = &mut self.worker_pool {
+            worker_pool.new_stream(stream_info);
+        }
+
+        // Fade in the output of the new stream to avoid a pop.
+        self.output_declick = Declicker::SettledAt0;
+        self.output_declick.fade_to_1(&self.extra.declick_values);
+    }
+
+    /// Start fading out the final output in preparation for stopping the
+    /// processor.
+    pub fn fade_out_output(&mut self) {
+        self.output_declick.fade_to_0(&self.extra.declick_values);
+    }
+
+    /// Returns `true` if the final output has finished fading out, meaning
+    /// the processor can be stopped without causing a pop.
+    pub fn output_faded_out(&self) -> bool {
+        self.output_declick.disabled() || self.schedule_data.is_none()
     }
 }
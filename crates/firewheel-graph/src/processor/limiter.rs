@@ -0,0 +1,242 @@
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::{Vec, vec};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::{dsp::volume::db_to_amp, mask::SilenceMask};
+
+/// The configuration of the look-ahead brickwall limiter applied to the
+/// final output of the audio graph.
+///
+/// Unlike [`FirewheelFlags::hard_clip_outputs`], the limiter smoothly reduces
+/// the gain of all output channels so that no sample exceeds the ceiling,
+/// avoiding audible distortion.
+///
+/// Note, the limiter delays the output by [`LimiterConfig::lookahead_frames`].
+/// This is reported as the latency of the graph output node, so it is
+/// included in [`FirewheelContext::graph_output_latency_frames`].
+///
+/// [`FirewheelFlags::hard_clip_outputs`]: crate::FirewheelFlags::hard_clip_outputs
+/// [`FirewheelContext::graph_output_latency_frames`]: crate::FirewheelContext::graph_output_latency_frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LimiterConfig {
+    /// The maximum peak level of the output in decibels.
+    ///
+    /// By default this is set to `-0.3`.
+    pub ceiling_db: f32,
+    /// The time in milliseconds it takes for the gain to recover after the
+    /// signal falls back below the ceiling.
+    ///
+    /// By default this is set to `100.0`.
+    pub release_ms: f32,
+    /// The number of frames (samples in a single channel of audio) the
+    /// limiter looks ahead in order to smoothly reduce the gain before a
+    /// peak arrives.
+    ///
+    /// By default this is set to `64`.
+    pub lookahead_frames: u32,
+}
+
+impl Default for LimiterConfig {
+    fn default() -> Self {
+        Self {
+            ceiling_db: -0.3,
+            release_ms: 100.0,
+            lookahead_frames: 64,
+        }
+    }
+}
+
+/// A look-ahead brickwall limiter with linked channels.
+///
+/// The required gain of each frame is held over the look-ahead window, and
+/// then smoothed with a moving average of the same length. Because every value
+/// in the moving average is at most the required gain of the frame leaving the
+/// delay line, the output never exceeds the ceiling.
+pub(crate) struct OutputLimiter {
+    config: LimiterConfig,
+    ceiling_amp: f32,
+    release_coeff: f32,
+    num_channels: usize,
+    max_block_frames: usize,
+
+    /// The delay lines of each channel, each `lookahead_frames` long.
+    delay_lines: Vec<f32>,
+    delay_ptr: usize,
+
+    /// A monotonic queue of `(frame, required gain)` used to find the minimum
+    /// required gain over the look-ahead window.
+    min_queue: Vec<(u64, f32)>,
+    min_queue_start: usize,
+    min_queue_len: usize,
+
+    /// The held gain values of the moving average window.
+    avg_window: Vec<f32>,
+    avg_ptr: usize,
+    avg_sum: f64,
+
+    gain: f32,
+    frame: u64,
+
+    /// The output buffers of each channel, each `max_block_frames` long.
+    out_buffers: Vec<f32>,
+}
+
+impl OutputLimiter {
+    /// Note, this method gets called on the main thread, not the audio thread.
+    pub fn new(
+        config: LimiterConfig,
+        num_channels: usize,
+        sample_rate: u32,
+        max_block_frames: usize,
+    ) -> Self {
+        let lookahead_frames = config.lookahead_frames as usize;
+        let window_frames = lookahead_frames + 1;
+        let release_frames = (config.release_ms / 1_000.0 * sample_rate as f32).max(1.0);
+
+        Self {
+            config,
+            ceiling_amp: db_to_amp(config.ceiling_db),
+            release_coeff: (-1.0 / release_frames).exp(),
+            num_channels,
+            max_block_frames,
+            delay_lines: vec![0.0; lookahead_frames * num_channels],
+            delay_ptr: 0,
+            min_queue: vec![(0, 1.0); window_frames],
+            min_queue_start: 0,
+            min_queue_len: 0,
+            avg_window: vec![1.0; window_frames],
+            avg_ptr: 0,
+            avg_sum: window_frames as f64,
+            gain: 1.0,
+            frame: 0,
+            out_buffers: vec![0.0; max_block_frames * num_channels],
+        }
+    }
+
+    pub fn config(&self) -> &LimiterConfig {
+        &self.config
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    pub fn max_block_frames(&self) -> usize {
+        self.max_block_frames
+    }
+
+    /// Limit the given channels, writing the result into the internal output
+    /// buffers.
+    ///
+    /// Channels marked as silent in `silence_mask` are treated as containing
+    /// only zeros.
+    pub fn process(&mut self, channels: &[&mut [f32]], silence_mask: SilenceMask, frames: usize) {
+        let num_channels = channels.len().min(self.num_channels);
+        let frames = frames.min(self.max_block_frames);
+        let lookahead_frames = self.config.lookahead_frames as usize;
+        let window_frames = lookahead_frames + 1;
+        let window_frames_recip = (window_frames as f64).recip();
+
+        for i in 0..frames {
+            let mut peak: f32 = 0.0;
+            for (ch_i, ch) in channels.iter().enumerate().take(num_channels) {
+                if !silence_mask.is_channel_silent(ch_i) {
+                    peak = peak.max(ch[i].abs());
+                }
+            }
+
+            let required_gain = if peak > self.ceiling_amp {
+                self.ceiling_amp / peak
+            } else {
+                1.0
+            };
+
+            // Hold the minimum required gain over the look-ahead window.
+            let held_gain = self.push_min_queue(required_gain, window_frames);
+
+            // Smooth the held gain with a moving average of the same length.
+            self.avg_sum += f64::from(held_gain) - f64::from(self.avg_window[self.avg_ptr]);
+            self.avg_window[self.avg_ptr] = held_gain;
+            self.avg_ptr += 1;
+            if self.avg_ptr == window_frames {
+                self.avg_ptr = 0;
+            }
+            let smoothed_gain = ((self.avg_sum * window_frames_recip) as f32).clamp(0.0, 1.0);
+
+            self.gain = if smoothed_gain < self.gain {
+                smoothed_gain
+            } else {
+                smoothed_gain + (self.gain - smoothed_gain) * self.release_coeff
+            };
+
+            for (ch_i, ch) in channels.iter().enumerate().take(num_channels) {
+                let input = if silence_mask.is_channel_silent(ch_i) {
+                    0.0
+                } else {
+                    ch[i]
+                };
+
+                let delayed = if lookahead_frames == 0 {
+                    input
+                } else {
+                    let delay_line = &mut self.delay_lines
+                        [ch_i * lookahead_frames..(ch_i + 1) * lookahead_frames];
+                    let delayed = delay_line[self.delay_ptr];
+                    delay_line[self.delay_ptr] = input;
+                    delayed
+                };
+
+                self.out_buffers[(ch_i * self.max_block_frames) + i] = delayed * self.gain;
+            }
+
+            if lookahead_frames > 0 {
+                self.delay_ptr += 1;
+                if self.delay_ptr == lookahead_frames {
+                    self.delay_ptr = 0;
+                }
+            }
+
+            self.frame += 1;
+        }
+    }
+
+    /// The output of the limiter for the given channel from the most recent
+    /// call to [`OutputLimiter::process`].
+    pub fn output(&mut self, ch_i: usize, frames: usize) -> &mut [f32] {
+        let start = ch_i * self.max_block_frames;
+        &mut self.out_buffers[start..start + frames.min(self.max_block_frames)]
+    }
+
+    /// Push the required gain of the current frame and return the minimum
+    /// required gain over the last `window_frames` frames.
+    fn push_min_queue(&mut self, required_gain: f32, window_frames: usize) -> f32 {
+        let capacity = self.min_queue.len();
+
+        // Remove values which are no longer the minimum.
+        while self.min_queue_len > 0 {
+            let back = (self.min_queue_start + self.min_queue_len - 1) % capacity;
+            if self.min_queue[back].1 >= required_gain {
+                self.min_queue_len -= 1;
+            } else {
+                break;
+            }
+        }
+
+        // Remove values which have left the window.
+        while self.min_queue_len > 0
+            && self.min_queue[self.min_queue_start].0 + window_frames as u64 <= self.frame
+        {
+            self.min_queue_start = (self.min_queue_start + 1) % capacity;
+            self.min_queue_len -= 1;
+        }
+
+        let back = (self.min_queue_start + self.min_queue_len) % capacity;
+        self.min_queue[back] = (self.frame, required_gain);
+        self.min_queue_len += 1;
+
+        self.min_queue[self.min_queue_start].1
+    }
+}
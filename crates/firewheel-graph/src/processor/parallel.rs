@@ -0,0 +1,494 @@
+use core::cell::UnsafeCell;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use firewheel_core::{
+    StreamInfo,
+    clock::InstantSamples,
+    dsp::{buffer::ConstSequentialBuffer, declick::DeclickValues},
+    event::ProcEventsIndex,
+    log::RealtimeLogger,
+    node::{ProcExtra, ProcInfo, ProcStore},
+};
+use thunderdome::Arena;
+
+use crate::{
+    graph::{CompiledSchedule, NodeOutputsInfo},
+    processor::{
+        NodeEntry, NodeEntryPtr, event_scheduler::EventScheduler, new_extra_scratch_buffers,
+        process::process_node,
+    },
+};
+
+#[cfg(feature = "node_profiling")]
+use crate::processor::profiling::ProfilerTx;
+
+/// The maximum number of worker threads to spawn in addition to the audio
+/// thread.
+const MAX_WORKER_THREADS: usize = 3;
+
+/// The number of times an idle worker thread checks for a new job before
+/// parking itself.
+///
+/// Spinning burns CPU time on an otherwise idle core, but waking up a parked
+/// thread is a system call which can take tens of microseconds, which would
+/// otherwise be paid for every level of the schedule. Depending on the CPU
+/// (the cost of [`core::hint::spin_loop`] ranges from a few to over a hundred
+/// cycles), this many iterations lasts somewhere between tens of microseconds
+/// and about a millisecond. This is long enough for the workers to stay awake
+/// between the levels of a block, but short enough that they usually park
+/// between blocks.
+const SPIN_ITERATIONS: usize = 1 << 14;
+
+/// A job shared with the worker threads. The arguments are the index of the
+/// thread running the task (where `0` is the audio thread) and the index of
+/// the task.
+type Job<'a> = dyn Fn(usize, usize) + Sync + 'a;
+
+/// The number of worker threads to spawn when processing in parallel.
+pub(crate) fn num_worker_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get().saturating_sub(1))
+        .unwrap_or(1)
+        .clamp(1, MAX_WORKER_THREADS)
+}
+
+/// A small pool of threads which process nodes alongside the audio thread.
+pub(crate) struct WorkerPool {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+    /// The scratch state of each thread, where index `0` belongs to the
+    /// audio thread.
+    scratch: Box<[ScratchCell]>,
+}
+
+impl WorkerPool {
+    /// Spawn a new pool with one worker thread less than the number of
+    /// `thread_loggers`.
+    ///
+    /// Note, this method gets called on the main thread, not the audio thread.
+    pub(crate) fn new(
+        thread_loggers: Vec<RealtimeLogger>,
+        stream_info: &StreamInfo,
+        num_extra_scratch_buffers: usize,
+        node_event_buffer_capacity: usize,
+    ) -> Self {
+        let max_block_frames = stream_info.max_block_frames.get() as usize;
+
+        let scratch: Box<[ScratchCell]> = thread_loggers
+            .into_iter()
+            .map(|logger| {
+                ScratchCell(UnsafeCell::new(ThreadScratch {
+                    extra: ProcExtra {
+                        scratch_buffers: ConstSequentialBuffer::new(max_block_frames),
+                        extra_scratch_buffers: new_extra_scratch_buffers(
+                            num_extra_scratch_buffers,
+                            max_block_frames,
+                        ),
+                        declick_values: DeclickValues::new(stream_info.declick_frames),
+                        logger,
+                        store: ProcStore::with_capacity(0),
+                    },
+                    proc_event_queue: Vec::with_capacity(node_event_buffer_capacity),
+                }))
+            })
+            .collect();
+
+        let shared = Arc::new(Shared {
+            job: AtomicPtr::new(core::ptr::null_mut()),
+            parked: (1..scratch.len()).map(|_| AtomicBool::new(false)).collect(),
+            generation: AtomicUsize::new(0),
+            num_tasks: AtomicUsize::new(0),
+            next_task: AtomicUsize::new(0),
+            remaining_tasks: AtomicUsize::new(0),
+            active_workers: AtomicUsize::new(0),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let block_frames = stream_info.max_block_frames.get();
+        let sample_rate = stream_info.sample_rate.get();
+
+        let threads = (1..scratch.len())
+            .filter_map(|thread_index| {
+                let shared = Arc::clone(&shared);
+
+                std::thread::Builder::new()
+                    .name(format!("firewheel-worker-{thread_index}"))
+                    .spawn(move || {
+                        let _rt_priority_handle = promote_to_real_time(block_frames, sample_rate);
+
+                        worker_loop(&shared, thread_index);
+                    })
+                    .ok()
+            })
+            .collect();
+
+        Self {
+            shared,
+            threads,
+            scratch,
+        }
+    }
+
+    /// Note, this method gets called on the main thread, not the audio thread.
+    pub(crate) fn new_stream(&mut self, stream_info: &StreamInfo) {
+        let max_block_frames = stream_info.max_block_frames.get() as usize;
+
+        for scratch in self.scratch.iter_mut() {
+            let extra = &mut scratch.0.get_mut().extra;
+
+            extra.declick_values = DeclickValues::new(stream_info.declick_frames);
+            extra.scratch_buffers = ConstSequentialBuffer::new(max_block_frames);
+            extra.extra_scratch_buffers = extra
+                .extra_scratch_buffers
+                .as_ref()
+                .and_then(|b| new_extra_scratch_buffers(b.num_channels().get(), max_block_frames));
+        }
+    }
+
+    /// Process the nodes in the schedule, processing the nodes in each level
+    /// of the schedule in parallel.
+    ///
+    /// Nodes with no inputs or outputs and nodes with events this block are
+    /// processed on the audio thread.
+    #[expect(clippy::too_many_arguments, reason = "Function needs many arguments")]
+    pub(crate) fn process_schedule(
+        &self,
+        schedule: &mut CompiledSchedule,
+        node_entries: &mut [NodeEntryPtr],
+        nodes: &mut Arena<NodeEntry>,
+        event_scheduler: &mut EventScheduler,
+        extra: &mut ProcExtra,
+        proc_event_queue: &mut Vec<ProcEventsIndex>,
+        #[cfg(feature = "node_profiling")] profiler_tx: &mut ProfilerTx,
+        block_frames: usize,
+        clock_samples: InstantSamples,
+        info: &mut ProcInfo,
+        force_clear_buffers: bool,
+        node_processed: Option<&mut dyn FnMut(NodeOutputsInfo<'_, '_>)>,
+    ) {
+        // The nodes are not processed in schedule order, so each node is timed
+        // separately and the timings are reported once all nodes are processed.
+        #[cfg(feature = "node_profiling")]
+        let profile_nodes = profiler_tx.is_profiling_nodes();
+
+        schedule.process_pre_proc_nodes(|proc_node_info| {
+            let node_entry = nodes.get_mut(proc_node_info.node_id.0).unwrap();
+
+            #[cfg(feature = "node_profiling")]
+            let start = profile_nodes.then(crate::time::now).flatten();
+
+            let process_status = process_node(
+                proc_node_info,
+                node_entry,
+                Some(&mut *event_scheduler),
+                block_frames,
+                clock_samples,
+                info,
+                extra,
+                proc_event_queue,
+            );
+
+            #[cfg(feature = "node_profiling")]
+            if let Some(start) = start {
+                node_entry.cpu_seconds = start.elapsed().as_secs_f64();
+            }
+
+            process_status
+        });
+
+        // The arena can't be borrowed mutably from multiple threads, so look up
+        // the node entries by the slot of their ID instead.
+        for (index, node_entry) in nodes.iter_mut() {
+            if let Some(ptr) = node_entries.get_mut(index.slot() as usize) {
+                *ptr = NodeEntryPtr(node_entry);
+            }
+        }
+
+        let node_entries = &*node_entries;
+        let info = &*info;
+
+        schedule.process_parallel(
+            block_frames,
+            force_clear_buffers,
+            |level| {
+                // The event buffers are only accessible from the audio thread, so
+                // nodes with events this block are processed there.
+                let num_with_events = level.move_to_front(|node_id| {
+                    // SAFETY: The pointers were just filled in from the arena, and
+                    // no nodes are being processed.
+                    EventScheduler::node_has_events(unsafe {
+                        &*node_entries[node_id.0.slot() as usize].0
+                    })
+                });
+
+                let level = &*level;
+
+                let process_level_node =
+                    |i: usize,
+                     thread_index: usize,
+                     event_scheduler: Option<&mut EventScheduler>| {
+                        let node_id = level.node_id(i);
+
+                        // SAFETY: Each node in the level is processed by exactly one
+                        // thread, and each thread index is only used by one thread at
+                        // a time.
+                        let (node_entry, scratch) = unsafe {
+                            (
+                                &mut *node_entries[node_id.0.slot() as usize].0,
+                                &mut *self.scratch[thread_index].0.get(),
+                            )
+                        };
+
+                        let mut info = info.clone();
+
+                        #[cfg(feature = "node_profiling")]
+                        let start = profile_nodes.then(crate::time::now).flatten();
+
+                        // SAFETY: See above.
+                        unsafe {
+                            level.process_node(i, thread_index, |proc_node_info| {
+                                process_node(
+                                    proc_node_info,
+                                    &mut *node_entry,
+                                    event_scheduler,
+                                    block_frames,
+                                    clock_samples,
+                                    &mut info,
+                                    &mut scratch.extra,
+                                    &mut scratch.proc_event_queue,
+                                )
+                            });
+                        }
+
+                        #[cfg(feature = "node_profiling")]
+                        if let Some(start) = start {
+                            node_entry.cpu_seconds = start.elapsed().as_secs_f64();
+                        }
+                    };
+
+                self.run(
+                    level.len() - num_with_events,
+                    &|thread_index, task_index| {
+                        process_level_node(num_with_events + task_index, thread_index, None);
+                    },
+                    || {
+                        for i in 0..num_with_events {
+                            process_level_node(i, 0, Some(&mut *event_scheduler));
+                        }
+                    },
+                );
+            },
+            node_processed,
+        );
+
+        #[cfg(feature = "node_profiling")]
+        if profile_nodes {
+            let graph_in_node_id = schedule.graph_in_node_id();
+
+            for node_id in schedule
+                .iter_node_ids()
+                .filter(|node_id| *node_id != graph_in_node_id)
+            {
+                profiler_tx.node_completed_in(nodes.get(node_id.0).unwrap().cpu_seconds);
+            }
+        }
+    }
+
+    /// Run `task` for each task index in `0..num_tasks` across the worker threads
+    /// and the calling thread, and wait for all tasks to finish.
+    ///
+    /// `on_calling_thread` is run on the calling thread after the tasks have been
+    /// handed to the worker threads, and before the calling thread starts helping
+    /// with the tasks.
+    fn run(&self, num_tasks: usize, task: &Job<'_>, on_calling_thread: impl FnOnce()) {
+        let shared = &*self.shared;
+
+        // Waking up the worker threads isn't worth it for a single task.
+        if num_tasks <= 1 || self.threads.is_empty() {
+            on_calling_thread();
+
+            for task_index in 0..num_tasks {
+                (task)(0, task_index);
+            }
+
+            return;
+        }
+
+        shared.num_tasks.store(num_tasks, Ordering::Relaxed);
+        shared.next_task.store(0, Ordering::Relaxed);
+        shared.remaining_tasks.store(num_tasks, Ordering::Relaxed);
+        shared
+            .job
+            .store((&raw const task).cast_mut().cast(), Ordering::SeqCst);
+        shared.generation.fetch_add(1, Ordering::SeqCst);
+
+        // Waking up a parked thread is a system call, so only wake up as many
+        // workers as there are tasks to split with the calling thread. Workers
+        // which are still spinning pick up the job on their own.
+        for (thread, parked) in self
+            .threads
+            .iter()
+            .zip(shared.parked.iter())
+            .take(num_tasks - 1)
+        {
+            if parked.swap(false, Ordering::SeqCst) {
+                thread.thread().unpark();
+            }
+        }
+
+        on_calling_thread();
+
+        run_tasks(shared, task, 0);
+
+        while shared.remaining_tasks.load(Ordering::Acquire) > 0 {
+            core::hint::spin_loop();
+        }
+
+        // Make sure that no worker thread can access the job once it goes out
+        // of scope.
+        shared.job.store(core::ptr::null_mut(), Ordering::SeqCst);
+        while shared.active_workers.load(Ordering::SeqCst) > 0 {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+
+        for thread in self.threads.drain(..) {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The scratch state used by a single thread while processing nodes.
+struct ThreadScratch {
+    extra: ProcExtra,
+    proc_event_queue: Vec<ProcEventsIndex>,
+}
+
+struct ScratchCell(UnsafeCell<ThreadScratch>);
+
+// SAFETY: Each thread only accesses the scratch state of its own thread index.
+unsafe impl Sync for ScratchCell {}
+
+/// The state shared between the audio thread and the worker threads.
+struct Shared {
+    /// A pointer to a `&Job` for the current job, or null if there is no job.
+    job: AtomicPtr<()>,
+    /// Whether each worker thread is parked (or about to park).
+    parked: Box<[AtomicBool]>,
+    /// Incremented each time a new job is started.
+    generation: AtomicUsize,
+    /// The number of tasks in the current job.
+    num_tasks: AtomicUsize,
+    /// The index of the next task in the current job to run.
+    next_task: AtomicUsize,
+    /// The number of tasks in the current job which have not finished yet.
+    remaining_tasks: AtomicUsize,
+    /// The number of worker threads which may be accessing the current job.
+    active_workers: AtomicUsize,
+    shutdown: AtomicBool,
+}
+
+fn worker_loop(shared: &Shared, thread_index: usize) {
+    let mut last_generation = 0;
+
+    loop {
+        let mut spins = 0;
+        let generation = loop {
+            if shared.shutdown.load(Ordering::Acquire) {
+                return;
+            }
+
+            let generation = shared.generation.load(Ordering::Acquire);
+            if generation != last_generation {
+                break generation;
+            }
+
+            if spins < SPIN_ITERATIONS {
+                spins += 1;
+                core::hint::spin_loop();
+            } else {
+                let parked = &shared.parked[thread_index - 1];
+                parked.store(true, Ordering::SeqCst);
+
+                // Check again in case a job was started before the flag was set,
+                // in which case the audio thread may not have woken this thread.
+                if shared.generation.load(Ordering::SeqCst) == last_generation
+                    && !shared.shutdown.load(Ordering::SeqCst)
+                {
+                    std::thread::park();
+                }
+
+                parked.store(false, Ordering::SeqCst);
+            }
+        };
+        last_generation = generation;
+
+        shared.active_workers.fetch_add(1, Ordering::SeqCst);
+
+        let job = shared.job.load(Ordering::SeqCst);
+        if !job.is_null() {
+            // SAFETY: `WorkerPool::run` does not return (and thus the job does not
+            // go out of scope) until there are no more active workers.
+            let task = unsafe { *job.cast::<&Job<'_>>() };
+            run_tasks(shared, task, thread_index);
+        }
+
+        shared.active_workers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Give the current thread real-time priority like an audio thread, so that the
+/// audio thread is not left waiting on a worker which was preempted.
+fn promote_to_real_time(
+    max_block_frames: u32,
+    sample_rate: u32,
+) -> Option<audio_thread_priority::RtPriorityHandle> {
+    match audio_thread_priority::promote_current_thread_to_real_time(max_block_frames, sample_rate)
+    {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                "Failed to get realtime priority for audio worker thread. This may cause audio glitches: {:?}",
+                e
+            );
+            #[cfg(feature = "log")]
+            log::warn!(
+                "Failed to get realtime priority for audio worker thread. This may cause audio glitches: {:?}",
+                e
+            );
+            #[cfg(not(any(feature = "tracing", feature = "log")))]
+            let _ = e;
+
+            None
+        }
+    }
+}
+
+/// Claim and run tasks from the current job until there are none left.
+fn run_tasks(shared: &Shared, task: &Job<'_>, thread_index: usize) {
+    let num_tasks = shared.num_tasks.load(Ordering::Relaxed);
+
+    loop {
+        let task_index = shared.next_task.fetch_add(1, Ordering::Relaxed);
+        if task_index >= num_tasks {
+            break;
+        }
+
+        (task)(thread_index, task_index);
+
+        shared.remaining_tasks.fetch_sub(1, Ordering::Release);
+    }
+}
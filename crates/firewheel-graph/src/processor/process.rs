@@ -7,18 +7,27 @@ use firewheel_core::{
     channel_config::MAX_CHANNELS,
     clock::{DurationSamples, InstantSamples},
     dsp::declick::{DeclickFadeCurve, Declicker},
+    event::ProcEventsIndex,
     log::RealtimeLogger,
     mask::{ConnectedMask, ConstantMask, MaskType, SilenceMask},
-    node::{ProcBuffers, ProcInfo, ProcessStatus, StreamStatus},
+    node::{ProcBuffers, ProcExtra, ProcInfo, ProcessStatus, StreamStatus},
+    param::smoother::SmoothedParam,
 };
+use ringbuf::traits::Producer;
 
 use crate::{
     backend::BackendProcessInfo,
     context::FirewheelBitFlags,
-    graph::ProcessNodeInfo,
-    processor::{FirewheelProcessorInner, SharedFlags, event_scheduler::ProcessSubChunkInfo},
+    graph::{NodeOutputsInfo, ProcessNodeInfo},
+    processor::{
+        FirewheelProcessorInner, NodeEntry, ProcessorToContextMsg, RenderTap, SharedFlags,
+        event_scheduler::{EventScheduler, ProcessSubChunkInfo},
+    },
 };
 
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::Vec;
+
 #[cfg(feature = "scheduled_events")]
 use crate::processor::SharedClock;
 use bevy_platform::time::Instant;
@@ -38,6 +47,7 @@ impl FirewheelProcessorInner {
         input: &dyn Adapter<'_, f32>,
         output: &mut dyn AdapterMut<'_, f32>,
         info: BackendProcessInfo,
+        taps: &mut [RenderTap<'_, '_>],
     ) {
         let BackendProcessInfo {
             frames,
@@ -194,6 +204,8 @@ impl FirewheelProcessorInner {
                 process_to_playback_delay,
                 #[cfg(feature = "musical_transport")]
                 &proc_transport_info,
+                taps,
+                frames_processed,
             );
 
             // Copy the output of the audio graph to the output buffer.
@@ -205,6 +217,26 @@ impl FirewheelProcessorInner {
                     block_frames,
                     num_out_channels,
                     |channels: &mut [&mut [f32]], silence_mask| {
+                        apply_output_gain(
+                            channels,
+                            &mut self.output_gain,
+                            num_out_channels,
+                            block_frames,
+                        );
+
+                        let declick_gain = if self.output_declick != Declicker::SettledAt1 {
+                            let gain = &mut self.output_declick_gain[..block_frames];
+                            self.output_declick.process_into_gain_buffer(
+                                gain,
+                                false,
+                                &self.extra.declick_values,
+                                DeclickFadeCurve::Linear,
+                            );
+                            Some(&*gain)
+                        } else {
+                            None
+                        };
+
                         validate_output(
                             channels,
                             &self.flags,
@@ -212,6 +244,43 @@ impl FirewheelProcessorInner {
                             &mut self.extra.logger,
                         );
 
+                        if let Some(limiter) = &mut self.output_limiter {
+                            limiter.process(channels, silence_mask, block_frames);
+
+                            let num_channels = channels
+                                .len()
+                                .min(num_out_channels)
+                                .min(limiter.num_channels());
+
+                            for ch_i in 0..num_channels {
+                                let ch = limiter.output(ch_i, block_frames);
+
+                                if self.flags.contains(FirewheelBitFlags::HARD_CLIP_OUTPUTS) {
+                                    hard_clip(ch);
+                                }
+
+                                if let Some(gain) = declick_gain {
+                                    apply_gain_buffer(ch, gain);
+                                }
+
+                                output.copy_from_slice_to_channel(ch_i, frames_processed, ch);
+                            }
+
+                            return;
+                        }
+
+                        if self.flags.contains(FirewheelBitFlags::HARD_CLIP_OUTPUTS) {
+                            for ch in channels.iter_mut() {
+                                hard_clip(ch);
+                            }
+                        }
+
+                        if let Some(gain) = declick_gain {
+                            for ch in channels.iter_mut() {
+                                apply_gain_buffer(&mut ch[..block_frames], gain);
+                            }
+                        }
+
                         for (ch_i, ch) in channels.iter().enumerate().take(num_out_channels) {
                             if silence_mask.is_channel_silent(ch_i) {
                                 output.fill_frames_with(frames_processed, block_frames, &0.0);
@@ -270,6 +339,8 @@ impl FirewheelProcessorInner {
         dropped_frames: u32,
         process_to_playback_delay: Option<Duration>,
         #[cfg(feature = "musical_transport")] proc_transport_info: &ProcTransportInfo,
+        taps: &mut [RenderTap<'_, '_>],
+        frames_processed: usize,
     ) {
         if self.schedule_data.is_none() {
             return;
@@ -279,9 +350,12 @@ impl FirewheelProcessorInner {
         // -- Prepare process info ------------------------------------------------------------
 
         #[cfg(feature = "musical_transport")]
-        let transport_info = self
-            .proc_transport_state
-            .transport_info(proc_transport_info);
+        let transport_info = self.proc_transport_state.transport_info(
+            proc_transport_info,
+            clock_samples,
+            sample_rate,
+            sample_rate_recip,
+        );
 
         let mut info = ProcInfo {
             frames: block_frames,
@@ -320,355 +394,98 @@ impl FirewheelProcessorInner {
         #[cfg(feature = "node_profiling")]
         self.profiler_tx.begin_node_profiling();
 
-        schedule_data.schedule.process(
-            block_frames,
-            force_clear_buffers,
-            |proc_node_info: ProcessNodeInfo| -> ProcessStatus {
-                let ProcessNodeInfo {
-                    node_id,
-                    in_silence_mask,
-                    out_silence_mask,
-                    in_constant_mask,
-                    out_constant_mask,
-                    in_connected_mask,
-                    out_connected_mask,
-                    proc_buffers,
-                    bypass_declick_buffer,
-                } = proc_node_info;
-
-                let node_entry = self.nodes.get_mut(node_id.0).unwrap();
-
-                // Add the mask information to proc info.
-                info.in_silence_mask = in_silence_mask;
-                info.in_constant_mask = in_constant_mask;
-                info.out_silence_mask = out_silence_mask;
-                info.out_constant_mask = out_constant_mask;
-                info.in_connected_mask = in_connected_mask;
-                info.out_connected_mask = out_connected_mask;
-
-                // Used to keep track of what status this closure should return.
-                let mut prev_process_status = None;
-                let mut final_mask = None;
-
-                let mut is_bypassed = node_entry.bypass_declick == Declicker::SettledAt0;
-                let mut is_bypass_declicking = !node_entry.bypass_declick.has_settled();
-                let has_outputs = !proc_buffers.outputs.is_empty();
-
-                // Process in sub-chunks for each new scheduled event (or process a single
-                // chunk if there are no scheduled events).
-                self.event_scheduler.process_node(
-                    node_id,
-                    node_entry,
-                    block_frames,
-                    clock_samples,
-                    &mut info,
-                    &mut self.extra,
-                    &mut self.proc_event_queue,
-                    proc_buffers,
-                    |proc_sub_chunk_info: ProcessSubChunkInfo| {
-                        let ProcessSubChunkInfo {
-                            sub_chunk_range,
-                            sub_clock_samples,
-                            node_entry,
-                            info,
-                            proc_buffers,
-                            events,
-                            extra,
-                            set_bypassed,
-                        } = proc_sub_chunk_info;
-
-                        let sub_chunk_frames = sub_chunk_range.end - sub_chunk_range.start;
-
-                        if let Some(bypassed) = set_bypassed {
-                            if bypassed {
-                                if node_entry.bypass_declick != Declicker::SettledAt0 {
-                                    if has_outputs {
-                                        node_entry.bypass_declick.fade_to_0(&extra.declick_values);
-                                        is_bypass_declicking = true;
-                                        is_bypassed = false;
-                                    } else {
-                                        node_entry.bypass_declick = Declicker::SettledAt0;
-                                        is_bypass_declicking = false;
-                                        is_bypassed = true;
-                                    }
-                                } // else already bypassed
-                            } else {
-                                if node_entry.bypass_declick != Declicker::SettledAt1 {
-                                    is_bypassed = false;
-
-                                    if has_outputs {
-                                        node_entry.bypass_declick.fade_to_1(&extra.declick_values);
-                                        is_bypass_declicking = true;
-                                    } else {
-                                        node_entry.bypass_declick = Declicker::SettledAt1;
-                                        is_bypass_declicking = false;
-                                    }
-                                } // else already un-bypassed
-                            }
-                        }
-
-                        // Set the timing information for the process info for this sub-chunk.
-                        info.frames = sub_chunk_frames;
-                        info.clock_samples = sub_clock_samples;
-                        info.prev_output_was_silent = node_entry.prev_output_was_silent;
-                        info.did_just_unbypass = false;
-
-                        // Call the node's process method.
-                        let process_status = if node_entry.bypass_declick == Declicker::SettledAt0 {
-                            let did_just_bypass = !node_entry.is_bypassed;
-                            if did_just_bypass {
-                                node_entry.is_bypassed = true;
-                                node_entry.processor.bypassed(true);
-                            }
-
-                            if !events.is_empty() || node_entry.is_first_process {
-                                node_entry.processor.events(info, events, extra);
-                                node_entry.is_first_process = false;
-                            }
-
-                            ProcessStatus::Bypass
-                        } else {
-                            let did_just_unbypass = node_entry.is_bypassed;
-                            if did_just_unbypass {
-                                node_entry.is_bypassed = false;
-                                info.did_just_unbypass = true;
-                                node_entry.processor.bypassed(false);
-                            }
-
-                            if !events.is_empty() || node_entry.is_first_process {
-                                node_entry.processor.events(info, events, extra);
-                                node_entry.is_first_process = false;
-                            }
-
-                            if is_bypass_declicking {
-                                let mut tmp_buffers = bypass_declick_buffer
-                                    .channels_mut::<MAX_CHANNELS>(
-                                        proc_buffers.outputs.len(),
-                                        sub_chunk_frames,
-                                    );
-
-                                if node_entry.in_place_buffers {
-                                    for (out_ch, tmp_ch) in
-                                        proc_buffers.outputs.iter().zip(tmp_buffers.iter_mut())
-                                    {
-                                        tmp_ch[..sub_chunk_frames]
-                                            .copy_from_slice(&out_ch[sub_chunk_range.clone()]);
-                                    }
-                                } else {
-                                    for (in_ch, tmp_ch) in
-                                        proc_buffers.inputs.iter().zip(tmp_buffers.iter_mut())
-                                    {
-                                        tmp_ch[..sub_chunk_frames]
-                                            .copy_from_slice(&in_ch[sub_chunk_range.clone()]);
-                                    }
-
-                                    for tmp_ch in
-                                        tmp_buffers.iter_mut().skip(proc_buffers.inputs.len())
-                                    {
-                                        tmp_ch[..sub_chunk_frames].fill(0.0);
-                                    }
-                                }
-                            }
-
-                            if sub_chunk_frames == block_frames {
-                                // If this is the only sub-chunk (because there are no scheduled
-                                // events), there is no need to edit the buffer slices.
-                                let sub_proc_buffers = ProcBuffers {
-                                    inputs: proc_buffers.inputs,
-                                    outputs: proc_buffers.outputs,
-                                };
-
-                                node_entry.processor.process(info, sub_proc_buffers, extra)
-                            } else {
-                                // Else if there are multiple sub-chunks, edit the range of each
-                                // buffer slice to cover the range of this sub-chunk.
-
-                                let mut sub_inputs: ArrayVec<&[f32], MAX_CHANNELS> =
-                                    ArrayVec::new();
-                                let mut sub_outputs: ArrayVec<&mut [f32], MAX_CHANNELS> =
-                                    ArrayVec::new();
-
-                                // TODO: We can use unsafe slicing here since we know the range is
-                                // always valid.
-                                for ch in proc_buffers.inputs.iter() {
-                                    sub_inputs.push(&ch[sub_chunk_range.clone()]);
-                                }
-                                for ch in proc_buffers.outputs.iter_mut() {
-                                    sub_outputs.push(&mut ch[sub_chunk_range.clone()]);
-                                }
-
-                                let sub_proc_buffers = ProcBuffers {
-                                    inputs: sub_inputs.as_slice(),
-                                    outputs: sub_outputs.as_mut_slice(),
-                                };
-
-                                node_entry.processor.process(info, sub_proc_buffers, extra)
-                            }
-                        };
-
-                        if is_bypass_declicking {
-                            let tmp_buffers = bypass_declick_buffer.channels::<MAX_CHANNELS>(
-                                proc_buffers.outputs.len(),
-                                sub_chunk_frames,
-                            );
-
-                            node_entry.bypass_declick.process_crossfade(
-                                &tmp_buffers,
-                                proc_buffers.outputs,
-                                0..sub_chunk_frames,
-                                sub_chunk_range.clone(),
-                                &extra.declick_values,
-                                DeclickFadeCurve::Linear,
-                            );
-                        }
+        // Copy the outputs of tapped nodes into their buffers.
+        let has_taps = !taps.is_empty();
+        let mut copy_to_taps = |node_outputs: NodeOutputsInfo<'_, '_>| {
+            for tap in taps
+                .iter_mut()
+                .filter(|tap| tap.node_id == node_outputs.node_id)
+            {
+                for (ch_i, (tap_buf, out_buf)) in tap
+                    .buffers
+                    .iter_mut()
+                    .zip(node_outputs.outputs.iter())
+                    .enumerate()
+                {
+                    let Some(tap_buf) =
+                        tap_buf.get_mut(frames_processed..frames_processed + block_frames)
+                    else {
+                        continue;
+                    };
+
+                    if node_outputs.silence_mask.is_channel_silent(ch_i) {
+                        tap_buf.fill(0.0);
+                    } else if node_outputs.constant_mask.is_channel_constant(ch_i) {
+                        tap_buf.fill(out_buf[0]);
+                    } else {
+                        tap_buf.copy_from_slice(&out_buf[..block_frames]);
+                    }
+                }
+            }
+        };
 
-                        node_entry.prev_output_was_silent = match process_status {
-                            ProcessStatus::ClearAllOutputs => true,
-                            ProcessStatus::Bypass => info
-                                .in_silence_mask
-                                .all_channels_silent(proc_buffers.inputs.len()),
-                            ProcessStatus::OutputsModified => false,
-                            ProcessStatus::OutputsModifiedWithMask(out_mask) => match out_mask {
-                                MaskType::Silence(mask) => {
-                                    mask.all_channels_silent(proc_buffers.outputs.len())
-                                }
-                                MaskType::Constant(_) => false,
-                            },
-                        };
+        #[allow(unused_mut)]
+        let mut processed_in_parallel = false;
+
+        // Process nodes which do not depend on each other in parallel if enabled.
+        #[cfg(feature = "std")]
+        if let Some(worker_pool) = &self.worker_pool
+            && schedule_data.schedule.is_parallel()
+        {
+            worker_pool.process_schedule(
+                &mut schedule_data.schedule,
+                &mut schedule_data.node_entries,
+                &mut self.nodes,
+                &mut self.event_scheduler,
+                &mut self.extra,
+                &mut self.proc_event_queue,
+                #[cfg(feature = "node_profiling")]
+                &mut self.profiler_tx,
+                block_frames,
+                clock_samples,
+                &mut info,
+                force_clear_buffers,
+                has_taps.then_some(&mut copy_to_taps as &mut dyn FnMut(NodeOutputsInfo<'_, '_>)),
+            );
 
-                        // If there are multiple sub-chunks, and the node returned a different process
-                        // status this sub-chunk than the previous sub-chunk, then we must manually
-                        // handle the process statuses.
-                        if final_mask.is_none()
-                            && let Some(prev_process_status) = prev_process_status
-                            && prev_process_status != process_status
-                        {
-                            // Handle the process status for the sub-chunk(s) before this
-                            // sub-chunk.
-                            match prev_process_status {
-                                ProcessStatus::ClearAllOutputs => {
-                                    for out_ch in proc_buffers.outputs.iter_mut() {
-                                        out_ch[0..sub_chunk_range.start].fill(0.0);
-                                    }
+            processed_in_parallel = true;
+        }
 
-                                    final_mask = Some(MaskType::Silence(
-                                        SilenceMask::new_all_silent(proc_buffers.outputs.len()),
-                                    ));
-                                }
-                                ProcessStatus::Bypass => {
-                                    for (out_ch, in_ch) in proc_buffers
-                                        .outputs
-                                        .iter_mut()
-                                        .zip(proc_buffers.inputs.iter())
-                                    {
-                                        out_ch[0..sub_chunk_range.start]
-                                            .copy_from_slice(&in_ch[0..sub_chunk_range.start]);
-                                    }
-                                    for out_ch in proc_buffers
-                                        .outputs
-                                        .iter_mut()
-                                        .skip(proc_buffers.inputs.len())
-                                    {
-                                        out_ch[0..sub_chunk_range.start].fill(0.0);
-                                    }
+        if !processed_in_parallel {
+            schedule_data.schedule.process(
+                block_frames,
+                force_clear_buffers,
+                |proc_node_info: ProcessNodeInfo| -> ProcessStatus {
+                    let node_entry = self.nodes.get_mut(proc_node_info.node_id.0).unwrap();
+
+                    let process_status = process_node(
+                        proc_node_info,
+                        node_entry,
+                        Some(&mut self.event_scheduler),
+                        block_frames,
+                        clock_samples,
+                        &mut info,
+                        &mut self.extra,
+                        &mut self.proc_event_queue,
+                    );
 
-                                    final_mask = Some(MaskType::Silence(in_silence_mask));
-                                }
-                                ProcessStatus::OutputsModified => {
-                                    final_mask = Some(MaskType::Silence(SilenceMask::NONE_SILENT));
-                                }
-                                ProcessStatus::OutputsModifiedWithMask(out_mask) => {
-                                    final_mask = Some(out_mask);
-                                }
-                            }
-                        }
-                        prev_process_status = Some(process_status);
-
-                        // If we are manually handling process statuses, handle the process status
-                        // for this sub-chunk.
-                        if let Some(final_mask) = &mut final_mask {
-                            match process_status {
-                                ProcessStatus::ClearAllOutputs => {
-                                    for out_ch in proc_buffers.outputs.iter_mut() {
-                                        out_ch[sub_chunk_range.clone()].fill(0.0);
-                                    }
-                                }
-                                ProcessStatus::Bypass => {
-                                    for (out_ch, in_ch) in proc_buffers
-                                        .outputs
-                                        .iter_mut()
-                                        .zip(proc_buffers.inputs.iter())
-                                    {
-                                        out_ch[sub_chunk_range.clone()]
-                                            .copy_from_slice(&in_ch[sub_chunk_range.clone()]);
-                                    }
-                                    for out_ch in proc_buffers
-                                        .outputs
-                                        .iter_mut()
-                                        .skip(proc_buffers.inputs.len())
-                                    {
-                                        out_ch[sub_chunk_range.clone()].fill(0.0);
-                                    }
+                    #[cfg(feature = "node_profiling")]
+                    self.profiler_tx.node_completed();
 
-                                    if let MaskType::Silence(s) = final_mask {
-                                        s.union_with(in_silence_mask);
-                                    } else {
-                                        *final_mask = MaskType::Silence(SilenceMask::NONE_SILENT);
-                                    }
-                                }
-                                ProcessStatus::OutputsModified => {
-                                    *final_mask = MaskType::Silence(SilenceMask::NONE_SILENT);
-                                }
-                                ProcessStatus::OutputsModifiedWithMask(out_mask) => {
-                                    match out_mask {
-                                        MaskType::Silence(mask) => {
-                                            if let MaskType::Silence(final_mask) = final_mask {
-                                                final_mask.union_with(mask);
-                                            } else {
-                                                *final_mask =
-                                                    MaskType::Silence(SilenceMask::NONE_SILENT);
-                                            }
-                                        }
-                                        MaskType::Constant(mask) => {
-                                            if let MaskType::Constant(final_mask) = final_mask {
-                                                final_mask.union_with(mask);
-
-                                                for (i, buf) in
-                                                    proc_buffers.outputs.iter().enumerate()
-                                                {
-                                                    if final_mask.is_channel_constant(i)
-                                                        && buf[0] != buf[sub_chunk_range.start]
-                                                    {
-                                                        final_mask.set_channel(i, false);
-                                                    }
-                                                }
-                                            } else {
-                                                *final_mask =
-                                                    MaskType::Silence(SilenceMask::NONE_SILENT);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    },
-                );
-
-                // -- Done processing in sub-chunks. Return the final process status. ---------
+                    process_status
+                },
+                has_taps.then_some(&mut copy_to_taps as &mut dyn FnMut(NodeOutputsInfo<'_, '_>)),
+            );
+        }
 
-                #[cfg(feature = "node_profiling")]
-                self.profiler_tx.node_completed();
+        // -- Report any edge fades which have finished ---------------------------------------
 
-                if let Some(final_mask) = final_mask {
-                    // If we manually handled process statuses, return the calculated silence
-                    // mask.
-                    ProcessStatus::OutputsModifiedWithMask(final_mask)
-                } else {
-                    // Else return the process status returned by the node's process method.
-                    prev_process_status.unwrap()
-                }
-            },
-        );
+        let to_graph_tx = &mut self.to_graph_tx;
+        schedule_data.schedule.report_finished_fades(|fade_id| {
+            to_graph_tx
+                .try_push(ProcessorToContextMsg::EdgeFadeFinished(fade_id))
+                .is_ok()
+        });
 
         // -- Clean up event buffers ----------------------------------------------------------
 
@@ -756,12 +573,503 @@ fn validate_output(
                 .store(true, Ordering::Relaxed);
         }
     }
+}
 
-    if flags.contains(FirewheelBitFlags::HARD_CLIP_OUTPUTS) {
-        for ch in output.iter_mut() {
-            for s in ch.iter_mut() {
-                *s = s.clamp(-1.0, 1.0);
+fn apply_output_gain(
+    channels: &mut [&mut [f32]],
+    output_gain: &mut SmoothedParam,
+    num_out_channels: usize,
+    block_frames: usize,
+) {
+    let num_channels = channels.len().min(num_out_channels);
+
+    if output_gain.is_smoothing() {
+        for i in 0..block_frames {
+            let gain = output_gain.next_smoothed();
+
+            for ch in channels[..num_channels].iter_mut() {
+                ch[i] *= gain;
+            }
+        }
+
+        output_gain.settle();
+    } else if output_gain.target_value() != 1.0 {
+        let gain = output_gain.target_value();
+
+        for ch in channels[..num_channels].iter_mut() {
+            for s in ch[..block_frames].iter_mut() {
+                *s *= gain;
+            }
+        }
+    }
+}
+
+fn apply_gain_buffer(output: &mut [f32], gain: &[f32]) {
+    for (s, &g) in output.iter_mut().zip(gain.iter()) {
+        *s *= g;
+    }
+}
+
+fn hard_clip(output: &mut [f32]) {
+    for s in output.iter_mut() {
+        *s = s.clamp(-1.0, 1.0);
+    }
+}
+
+/// Process a single node in the schedule.
+///
+/// If `event_scheduler` is `None`, then the node must not have any events to
+/// process this block.
+#[expect(clippy::too_many_arguments, reason = "Function needs many arguments")]
+pub(super) fn process_node(
+    proc_node_info: ProcessNodeInfo<'_, '_>,
+    node_entry: &mut NodeEntry,
+    event_scheduler: Option<&mut EventScheduler>,
+    block_frames: usize,
+    clock_samples: InstantSamples,
+    info: &mut ProcInfo,
+    extra: &mut ProcExtra,
+    proc_event_queue: &mut Vec<ProcEventsIndex>,
+) -> ProcessStatus {
+    let ProcessNodeInfo {
+        node_id,
+        in_silence_mask,
+        out_silence_mask,
+        in_constant_mask,
+        out_constant_mask,
+        in_connected_mask,
+        out_connected_mask,
+        proc_buffers,
+        bypass_declick_buffer,
+    } = proc_node_info;
+
+    // Add the mask information to proc info.
+    info.in_silence_mask = in_silence_mask;
+    info.in_constant_mask = in_constant_mask;
+    info.out_silence_mask = out_silence_mask;
+    info.out_constant_mask = out_constant_mask;
+    info.in_connected_mask = in_connected_mask;
+    info.out_connected_mask = out_connected_mask;
+
+    // Used to keep track of what status this function should return.
+    let mut prev_process_status = None;
+    let mut final_mask = None;
+
+    let mut is_bypassed = node_entry.bypass_declick == Declicker::SettledAt0;
+    let mut is_bypass_declicking = !node_entry.bypass_declick.has_settled();
+    let has_outputs = !proc_buffers.outputs.is_empty();
+
+    let on_sub_chunk = |proc_sub_chunk_info: ProcessSubChunkInfo| {
+        let ProcessSubChunkInfo {
+            sub_chunk_range,
+            sub_clock_samples,
+            node_entry,
+            info,
+            proc_buffers,
+            events,
+            extra,
+            set_bypassed,
+            set_muted,
+            reset,
+        } = proc_sub_chunk_info;
+
+        let sub_chunk_frames = sub_chunk_range.end - sub_chunk_range.start;
+
+        if let Some(bypassed) = set_bypassed {
+            if bypassed {
+                if node_entry.bypass_declick != Declicker::SettledAt0 {
+                    if has_outputs {
+                        node_entry.bypass_declick.fade_to_0(&extra.declick_values);
+                        is_bypass_declicking = true;
+                        is_bypassed = false;
+                    } else {
+                        node_entry.bypass_declick = Declicker::SettledAt0;
+                        is_bypass_declicking = false;
+                        is_bypassed = true;
+                    }
+                } // else already bypassed
+            } else {
+                if node_entry.bypass_declick != Declicker::SettledAt1 {
+                    is_bypassed = false;
+
+                    if has_outputs {
+                        node_entry.bypass_declick.fade_to_1(&extra.declick_values);
+                        is_bypass_declicking = true;
+                    } else {
+                        node_entry.bypass_declick = Declicker::SettledAt1;
+                        is_bypass_declicking = false;
+                    }
+                } // else already un-bypassed
+            }
+        }
+
+        if reset {
+            node_entry.processor.reset_state();
+        }
+
+        if let Some(muted) = set_muted {
+            if has_outputs {
+                node_entry
+                    .mute_declick
+                    .fade_to_enabled(!muted, &extra.declick_values);
+            } else {
+                node_entry.mute_declick = Declicker::from_enabled(!muted);
+            }
+        }
+
+        // Set the timing information for the process info for this sub-chunk.
+        info.frames = sub_chunk_frames;
+        info.clock_samples = sub_clock_samples;
+        info.prev_output_was_silent = node_entry.prev_output_was_silent;
+        info.did_just_unbypass = false;
+
+        // In-place nodes receive their inputs in the output buffers.
+        let input_silent = (!proc_buffers.inputs.is_empty() || node_entry.in_place_buffers)
+            && info
+                .in_silence_mask
+                .all_channels_silent(proc_buffers.inputs.len())
+            && (!node_entry.in_place_buffers
+                || info
+                    .out_silence_mask
+                    .all_channels_silent(proc_buffers.outputs.len()));
+
+        // Put the node to sleep once its inputs have been silent for
+        // longer than its tail.
+        let tail_elapsed = input_silent
+            && !node_entry.continue_processing_while_silent
+            && events.is_empty()
+            && !node_entry.is_first_process
+            && !node_entry.is_bypassed
+            && !is_bypass_declicking
+            && node_entry
+                .processor
+                .tail_frames()
+                .is_some_and(|tail_frames| node_entry.silent_input_frames >= tail_frames);
+
+        // Skip processing nodes which report that they have nothing to do,
+        // until an event or a non-silent input wakes them up.
+        let idle = (input_silent
+            || (proc_buffers.inputs.is_empty() && !node_entry.in_place_buffers))
+            && events.is_empty()
+            && !node_entry.is_first_process
+            && !node_entry.is_bypassed
+            && !is_bypass_declicking
+            && node_entry.processor.is_idle();
+
+        node_entry.silent_input_frames = if input_silent {
+            node_entry
+                .silent_input_frames
+                .saturating_add(sub_chunk_frames as u64)
+        } else {
+            0
+        };
+
+        // Call the node's process method.
+        let process_status = if tail_elapsed || idle {
+            ProcessStatus::ClearAllOutputs
+        } else if node_entry.bypass_declick == Declicker::SettledAt0 {
+            let did_just_bypass = !node_entry.is_bypassed;
+            if did_just_bypass {
+                node_entry.is_bypassed = true;
+                node_entry.processor.bypassed(true);
+            }
+
+            if !events.is_empty() || node_entry.is_first_process {
+                node_entry.processor.events(info, events, extra);
+                node_entry.is_first_process = false;
+            }
+
+            ProcessStatus::Bypass
+        } else {
+            let did_just_unbypass = node_entry.is_bypassed;
+            if did_just_unbypass {
+                node_entry.is_bypassed = false;
+                info.did_just_unbypass = true;
+                node_entry.processor.bypassed(false);
+            }
+
+            if !events.is_empty() || node_entry.is_first_process {
+                node_entry.processor.events(info, events, extra);
+                node_entry.is_first_process = false;
+            }
+
+            if is_bypass_declicking {
+                let mut tmp_buffers = bypass_declick_buffer
+                    .channels_mut::<MAX_CHANNELS>(proc_buffers.outputs.len(), sub_chunk_frames);
+
+                if node_entry.in_place_buffers {
+                    for (out_ch, tmp_ch) in proc_buffers.outputs.iter().zip(tmp_buffers.iter_mut())
+                    {
+                        tmp_ch[..sub_chunk_frames]
+                            .copy_from_slice(&out_ch[sub_chunk_range.clone()]);
+                    }
+                } else {
+                    for (in_ch, tmp_ch) in proc_buffers.inputs.iter().zip(tmp_buffers.iter_mut()) {
+                        tmp_ch[..sub_chunk_frames].copy_from_slice(&in_ch[sub_chunk_range.clone()]);
+                    }
+
+                    for tmp_ch in tmp_buffers.iter_mut().skip(proc_buffers.inputs.len()) {
+                        tmp_ch[..sub_chunk_frames].fill(0.0);
+                    }
+                }
+            }
+
+            if sub_chunk_frames == block_frames {
+                // If this is the only sub-chunk (because there are no scheduled
+                // events), there is no need to edit the buffer slices.
+                let sub_proc_buffers = ProcBuffers {
+                    inputs: proc_buffers.inputs,
+                    outputs: proc_buffers.outputs,
+                };
+
+                node_entry.processor.process(info, sub_proc_buffers, extra)
+            } else {
+                // Else if there are multiple sub-chunks, edit the range of each
+                // buffer slice to cover the range of this sub-chunk.
+
+                let mut sub_inputs: ArrayVec<&[f32], MAX_CHANNELS> = ArrayVec::new();
+                let mut sub_outputs: ArrayVec<&mut [f32], MAX_CHANNELS> = ArrayVec::new();
+
+                // TODO: We can use unsafe slicing here since we know the range is
+                // always valid.
+                for ch in proc_buffers.inputs.iter() {
+                    sub_inputs.push(&ch[sub_chunk_range.clone()]);
+                }
+                for ch in proc_buffers.outputs.iter_mut() {
+                    sub_outputs.push(&mut ch[sub_chunk_range.clone()]);
+                }
+
+                let sub_proc_buffers = ProcBuffers {
+                    inputs: sub_inputs.as_slice(),
+                    outputs: sub_outputs.as_mut_slice(),
+                };
+
+                node_entry.processor.process(info, sub_proc_buffers, extra)
+            }
+        };
+
+        if is_bypass_declicking {
+            let tmp_buffers = bypass_declick_buffer
+                .channels::<MAX_CHANNELS>(proc_buffers.outputs.len(), sub_chunk_frames);
+
+            node_entry.bypass_declick.process_crossfade(
+                &tmp_buffers,
+                proc_buffers.outputs,
+                0..sub_chunk_frames,
+                sub_chunk_range.clone(),
+                &extra.declick_values,
+                DeclickFadeCurve::Linear,
+            );
+        }
+
+        // Fade the outputs of muted nodes to silence. Muted nodes are still
+        // processed so that they can resume seamlessly once unmuted.
+        let process_status = match node_entry.mute_declick {
+            Declicker::SettledAt1 => process_status,
+            Declicker::SettledAt0 => ProcessStatus::ClearAllOutputs,
+            _ => {
+                match process_status {
+                    ProcessStatus::ClearAllOutputs => {
+                        for out_ch in proc_buffers.outputs.iter_mut() {
+                            out_ch[sub_chunk_range.clone()].fill(0.0);
+                        }
+                    }
+                    ProcessStatus::Bypass if !node_entry.in_place_buffers => {
+                        for (out_ch, in_ch) in proc_buffers
+                            .outputs
+                            .iter_mut()
+                            .zip(proc_buffers.inputs.iter())
+                        {
+                            out_ch[sub_chunk_range.clone()]
+                                .copy_from_slice(&in_ch[sub_chunk_range.clone()]);
+                        }
+                        for out_ch in proc_buffers
+                            .outputs
+                            .iter_mut()
+                            .skip(proc_buffers.inputs.len())
+                        {
+                            out_ch[sub_chunk_range.clone()].fill(0.0);
+                        }
+                    }
+                    _ => {}
+                }
+
+                node_entry.mute_declick.process(
+                    proc_buffers.outputs,
+                    sub_chunk_range.clone(),
+                    &extra.declick_values,
+                    1.0,
+                    DeclickFadeCurve::Linear,
+                );
+
+                match process_status {
+                    ProcessStatus::ClearAllOutputs => ProcessStatus::ClearAllOutputs,
+                    ProcessStatus::OutputsModifiedWithMask(MaskType::Silence(mask)) => {
+                        ProcessStatus::OutputsModifiedWithMask(MaskType::Silence(mask))
+                    }
+                    _ => ProcessStatus::OutputsModified,
+                }
+            }
+        };
+
+        node_entry.prev_output_was_silent = match process_status {
+            ProcessStatus::ClearAllOutputs => true,
+            ProcessStatus::Bypass => info
+                .in_silence_mask
+                .all_channels_silent(proc_buffers.inputs.len()),
+            ProcessStatus::OutputsModified => false,
+            ProcessStatus::OutputsModifiedWithMask(out_mask) => match out_mask {
+                MaskType::Silence(mask) => mask.all_channels_silent(proc_buffers.outputs.len()),
+                MaskType::Constant(_) => false,
+            },
+        };
+
+        // If there are multiple sub-chunks, and the node returned a different process
+        // status this sub-chunk than the previous sub-chunk, then we must manually
+        // handle the process statuses.
+        if final_mask.is_none()
+            && let Some(prev_process_status) = prev_process_status
+            && prev_process_status != process_status
+        {
+            // Handle the process status for the sub-chunk(s) before this
+            // sub-chunk.
+            match prev_process_status {
+                ProcessStatus::ClearAllOutputs => {
+                    for out_ch in proc_buffers.outputs.iter_mut() {
+                        out_ch[0..sub_chunk_range.start].fill(0.0);
+                    }
+
+                    final_mask = Some(MaskType::Silence(SilenceMask::new_all_silent(
+                        proc_buffers.outputs.len(),
+                    )));
+                }
+                ProcessStatus::Bypass => {
+                    for (out_ch, in_ch) in proc_buffers
+                        .outputs
+                        .iter_mut()
+                        .zip(proc_buffers.inputs.iter())
+                    {
+                        out_ch[0..sub_chunk_range.start]
+                            .copy_from_slice(&in_ch[0..sub_chunk_range.start]);
+                    }
+                    for out_ch in proc_buffers
+                        .outputs
+                        .iter_mut()
+                        .skip(proc_buffers.inputs.len())
+                    {
+                        out_ch[0..sub_chunk_range.start].fill(0.0);
+                    }
+
+                    final_mask = Some(MaskType::Silence(in_silence_mask));
+                }
+                ProcessStatus::OutputsModified => {
+                    final_mask = Some(MaskType::Silence(SilenceMask::NONE_SILENT));
+                }
+                ProcessStatus::OutputsModifiedWithMask(out_mask) => {
+                    final_mask = Some(out_mask);
+                }
+            }
+        }
+        prev_process_status = Some(process_status);
+
+        // If we are manually handling process statuses, handle the process status
+        // for this sub-chunk.
+        if let Some(final_mask) = &mut final_mask {
+            match process_status {
+                ProcessStatus::ClearAllOutputs => {
+                    for out_ch in proc_buffers.outputs.iter_mut() {
+                        out_ch[sub_chunk_range.clone()].fill(0.0);
+                    }
+                }
+                ProcessStatus::Bypass => {
+                    for (out_ch, in_ch) in proc_buffers
+                        .outputs
+                        .iter_mut()
+                        .zip(proc_buffers.inputs.iter())
+                    {
+                        out_ch[sub_chunk_range.clone()]
+                            .copy_from_slice(&in_ch[sub_chunk_range.clone()]);
+                    }
+                    for out_ch in proc_buffers
+                        .outputs
+                        .iter_mut()
+                        .skip(proc_buffers.inputs.len())
+                    {
+                        out_ch[sub_chunk_range.clone()].fill(0.0);
+                    }
+
+                    if let MaskType::Silence(s) = final_mask {
+                        s.union_with(in_silence_mask);
+                    } else {
+                        *final_mask = MaskType::Silence(SilenceMask::NONE_SILENT);
+                    }
+                }
+                ProcessStatus::OutputsModified => {
+                    *final_mask = MaskType::Silence(SilenceMask::NONE_SILENT);
+                }
+                ProcessStatus::OutputsModifiedWithMask(out_mask) => match out_mask {
+                    MaskType::Silence(mask) => {
+                        if let MaskType::Silence(final_mask) = final_mask {
+                            final_mask.union_with(mask);
+                        } else {
+                            *final_mask = MaskType::Silence(SilenceMask::NONE_SILENT);
+                        }
+                    }
+                    MaskType::Constant(mask) => {
+                        if let MaskType::Constant(final_mask) = final_mask {
+                            final_mask.union_with(mask);
+
+                            for (i, buf) in proc_buffers.outputs.iter().enumerate() {
+                                if final_mask.is_channel_constant(i)
+                                    && buf[0] != buf[sub_chunk_range.start]
+                                {
+                                    final_mask.set_channel(i, false);
+                                }
+                            }
+                        } else {
+                            *final_mask = MaskType::Silence(SilenceMask::NONE_SILENT);
+                        }
+                    }
+                },
             }
         }
+    };
+
+    if let Some(event_scheduler) = event_scheduler {
+        // Process in sub-chunks for each new scheduled event (or process a single
+        // chunk if there are no scheduled events).
+        event_scheduler.process_node(
+            node_id,
+            node_entry,
+            block_frames,
+            clock_samples,
+            info,
+            extra,
+            proc_event_queue,
+            proc_buffers,
+            on_sub_chunk,
+        );
+    } else {
+        EventScheduler::process_node_without_events(
+            node_entry,
+            block_frames,
+            clock_samples,
+            info,
+            extra,
+            proc_event_queue,
+            proc_buffers,
+            on_sub_chunk,
+        );
+    }
+
+    // -- Done processing in sub-chunks. Return the final process status. ---------
+
+    if let Some(final_mask) = final_mask {
+        // If we manually handled process statuses, return the calculated silence
+        // mask.
+        ProcessStatus::OutputsModifiedWithMask(final_mask)
+    } else {
+        // Else return the process status returned by the node's process method.
+        prev_process_status.unwrap()
     }
 }
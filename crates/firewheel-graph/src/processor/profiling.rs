@@ -31,6 +31,7 @@ pub(crate) fn profiler_channel(
     heap_data.nodes.push(NodeProfileData {
         node_id: graph_out_node_id,
         cpu_usage: 0.0,
+        average_cpu_usage: 0.0,
     });
 
     (
@@ -51,6 +52,8 @@ pub(crate) fn profiler_channel(
             node_profile_start_instant: now,
             #[cfg(feature = "node_profiling")]
             node_schedule_index: 0,
+            #[cfg(feature = "node_profiling")]
+            num_node_profile_loops: 0,
         },
         ProfilerRx { buffer_rx },
     )
@@ -76,6 +79,10 @@ pub(crate) struct ProfilerTx {
     node_profile_start_instant: Instant,
     #[cfg(feature = "node_profiling")]
     node_schedule_index: usize,
+    /// The number of process loops accumulated into the average CPU usage
+    /// of each node since the data was last published.
+    #[cfg(feature = "node_profiling")]
+    num_node_profile_loops: u32,
 }
 
 pub(crate) struct ProfilerHeapData {
@@ -142,8 +149,10 @@ impl ProfilerTx {
                         node_id,
                         // TODO: Try to re-use old cpu usage data.
                         cpu_usage: 0.0,
+                        average_cpu_usage: 0.0,
                     }),
             );
+            self.num_node_profile_loops = 0;
         }
     }
 
@@ -173,7 +182,9 @@ impl ProfilerTx {
             if new_is_profiling_nodes && !self.is_profiling_nodes {
                 for node in self.heap_data.nodes.iter_mut() {
                     node.cpu_usage = 0.0;
+                    node.average_cpu_usage = 0.0;
                 }
+                self.num_node_profile_loops = 0;
             }
 
             self.is_profiling_nodes = new_is_profiling_nodes;
@@ -230,6 +241,22 @@ impl ProfilerTx {
         }
     }
 
+    #[cfg(feature = "node_profiling")]
+    pub fn is_profiling_nodes(&self) -> bool {
+        self.is_profiling_nodes
+    }
+
+    /// Record the time spent processing the next node in the schedule, for
+    /// nodes which were timed separately (i.e. on another thread).
+    #[cfg(feature = "node_profiling")]
+    pub fn node_completed_in(&mut self, cpu_seconds: f64) {
+        if self.is_profiling_nodes {
+            self.heap_data.node_cpu_sums[self.node_schedule_index] +=
+                cpu_seconds * self.total_cpu_seconds_recip;
+            self.node_schedule_index += 1;
+        }
+    }
+
     pub fn process_loop_completed(&mut self) {
         let Some(now) = crate::time::now() else {
             return;
@@ -244,7 +271,10 @@ impl ProfilerTx {
                 .zip(self.heap_data.node_cpu_sums.iter())
             {
                 node.cpu_usage = node.cpu_usage.max(sum);
+                // This holds the total until the data is published.
+                node.average_cpu_usage += sum;
             }
+            self.num_node_profile_loops += 1;
         }
 
         let overall_cpu_usage = now.duration_since(self.proc_start_instant).as_secs_f64()
@@ -286,8 +316,14 @@ impl ProfilerTx {
                         core::mem::swap(&mut data.nodes, new_vec);
                     }
 
+                    let num_loops_recip = f64::from(self.num_node_profile_loops.max(1)).recip();
+
                     data.nodes.clear();
-                    data.nodes.extend_from_slice(&self.heap_data.nodes);
+                    data.nodes
+                        .extend(self.heap_data.nodes.iter().map(|node| NodeProfileData {
+                            average_cpu_usage: node.average_cpu_usage * num_loops_recip,
+                            ..*node
+                        }));
                 } else {
                     data.nodes.clear();
                 }
@@ -303,7 +339,9 @@ impl ProfilerTx {
             if self.is_profiling_nodes {
                 for node in self.heap_data.nodes.iter_mut() {
                     node.cpu_usage = 0.0;
+                    node.average_cpu_usage = 0.0;
                 }
+                self.num_node_profile_loops = 0;
             }
         }
     }
@@ -374,6 +412,7 @@ impl ProfilingData {
         nodes.push(NodeProfileData {
             node_id: graph_out_id,
             cpu_usage: 0.0,
+            average_cpu_usage: 0.0,
         });
 
         Self {
@@ -384,6 +423,16 @@ impl ProfilingData {
             nodes,
         }
     }
+
+    /// Get the profiling information of the given node.
+    ///
+    /// Returns `None` if the node does not exist in the most recent data
+    /// or if [`FirewheelFlags::profile_nodes`](crate::context::FirewheelFlags::profile_nodes)
+    /// is set to `false`.
+    #[cfg(feature = "node_profiling")]
+    pub fn node(&self, node_id: NodeID) -> Option<&NodeProfileData> {
+        self.nodes.iter().find(|node| node.node_id == node_id)
+    }
 }
 
 /// Performance profiling information of a Firewheel audio node.
@@ -402,6 +451,15 @@ pub struct NodeProfileData {
     ///
     /// The value is the maximum value that has occurred since the last time
     /// the profiling information was fetched with
-    /// [`FirewheelContext::profile_info()`](crate::context::FirewheelContext::profile_info).
+    /// [`FirewheelContext::profiling_data()`](crate::context::FirewheelContext::profiling_data).
     pub cpu_usage: f64,
+
+    /// The average CPU usage of this node per process cycle.
+    ///
+    /// This uses the same scale as [`NodeProfileData::cpu_usage`].
+    ///
+    /// The value is the average over all process cycles since the last time
+    /// the profiling information was fetched with
+    /// [`FirewheelContext::profiling_data()`](crate::context::FirewheelContext::profiling_data).
+    pub average_cpu_usage: f64,
 }
@@ -373,6 +373,9 @@ impl ProcTransportState {
     pub fn transport_info(
         &mut self,
         proc_transport_info: &ProcTransportInfo,
+        clock_samples: InstantSamples,
+        sample_rate: NonZeroU32,
+        sample_rate_recip: f64,
     ) -> Option<TransportInfo> {
         self.transport_state
             .transport
@@ -385,6 +388,18 @@ impl ProcTransportState {
                     .then(|| self.transport_start_samples),
                 beats_per_minute: proc_transport_info.beats_per_minute,
                 speed_multiplier: self.current_speed_multiplier,
+                playhead: if *self.transport_state.playing {
+                    transport.samples_to_musical(
+                        clock_samples,
+                        self.transport_start_samples,
+                        self.current_speed_multiplier,
+                        sample_rate,
+                        sample_rate_recip,
+                    )
+                } else {
+                    self.paused_at_musical_time
+                },
+                beats_per_bar: self.transport_state.beats_per_bar,
             })
     }
 
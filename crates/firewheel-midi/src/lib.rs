@@ -0,0 +1,340 @@
+//! Helpers for mapping incoming MIDI messages to Firewheel nodes.
+//!
+//! This crate does not own a MIDI connection. Instead, raw MIDI messages
+//! (i.e. the bytes received in a `midir` input callback) are parsed with
+//! [`MidiInput::parse`], and then either dispatched to a [`NodePool`] with
+//! [`MidiInput::apply_to_pool`], or handled manually using the conversion
+//! utilities in this crate.
+
+use firewheel_core::{
+    diff::Diff,
+    dsp::volume::{DEFAULT_MIN_DB, Volume},
+    event::wmidi::MidiMessage,
+    node::VoiceNode,
+};
+use firewheel_graph::{FirewheelContext, pool::NodePool};
+
+pub use firewheel_core::event::wmidi;
+
+/// The MIDI note number of A4.
+pub const A4_NOTE: u8 = 69;
+/// The frequency of A4 in hertz.
+pub const A4_HZ: f32 = 440.0;
+
+/// The control change number of the sustain (damper) pedal.
+pub const CC_SUSTAIN_PEDAL: u8 = 64;
+/// The control change number of the "all sound off" channel mode message.
+pub const CC_ALL_SOUND_OFF: u8 = 120;
+/// The control change number of the "all notes off" channel mode message.
+pub const CC_ALL_NOTES_OFF: u8 = 123;
+
+/// Convert a MIDI note number to a frequency in hertz, using 12-tone equal
+/// temperament tuned to A4 = 440 Hz.
+///
+/// Fractional note numbers are allowed (i.e. to apply pitch bend).
+pub fn note_to_hz(note: f32) -> f32 {
+    A4_HZ * ((note - A4_NOTE as f32) / 12.0).exp2()
+}
+
+/// Convert a frequency in hertz to a (fractional) MIDI note number, using
+/// 12-tone equal temperament tuned to A4 = 440 Hz.
+pub fn hz_to_note(hz: f32) -> f32 {
+    A4_NOTE as f32 + 12.0 * (hz / A4_HZ).log2()
+}
+
+/// Convert a 7-bit MIDI value (i.e. a velocity or a control change value) to
+/// the range `[0.0, 1.0]`.
+pub fn u7_to_unit(value: u8) -> f32 {
+    f32::from(value.min(127)) / 127.0
+}
+
+/// How a normalized velocity in the range `[0.0, 1.0]` is mapped to a
+/// [`Volume`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VelocityCurve {
+    /// The velocity is used directly as a linear volume.
+    Linear,
+    /// The velocity is mapped linearly to decibels, where a velocity of `1.0`
+    /// is unity gain and a velocity approaching `0.0` is `-range_db` decibels.
+    ///
+    /// This sounds more natural than [`VelocityCurve::Linear`] for most
+    /// instruments.
+    Decibels { range_db: f32 },
+}
+
+impl Default for VelocityCurve {
+    fn default() -> Self {
+        Self::Decibels { range_db: 40.0 }
+    }
+}
+
+impl VelocityCurve {
+    /// Convert a normalized velocity in the range `[0.0, 1.0]` to a volume.
+    ///
+    /// A velocity of `0.0` always results in [`Volume::SILENT`].
+    pub fn velocity_to_volume(&self, velocity: f32) -> Volume {
+        let velocity = velocity.clamp(0.0, 1.0);
+
+        if velocity <= 0.0 {
+            return Volume::SILENT;
+        }
+
+        match *self {
+            Self::Linear => Volume::Linear(velocity),
+            Self::Decibels { range_db } => {
+                let db = -(1.0 - velocity) * range_db.abs();
+
+                if db <= DEFAULT_MIN_DB {
+                    Volume::SILENT
+                } else {
+                    Volume::Decibels(db)
+                }
+            }
+        }
+    }
+}
+
+/// A simplified MIDI channel message which is relevant for playing nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiInput {
+    /// A note was pressed.
+    NoteOn {
+        /// The MIDI channel in the range `[0, 15]`.
+        channel: u8,
+        /// The MIDI note number in the range `[0, 127]`.
+        note: u8,
+        /// The velocity in the range `(0.0, 1.0]`.
+        velocity: f32,
+    },
+    /// A note was released.
+    ///
+    /// A note-on message with a velocity of zero is also parsed as a note-off
+    /// message.
+    NoteOff {
+        /// The MIDI channel in the range `[0, 15]`.
+        channel: u8,
+        /// The MIDI note number in the range `[0, 127]`.
+        note: u8,
+    },
+    /// A control change message.
+    ControlChange {
+        /// The MIDI channel in the range `[0, 15]`.
+        channel: u8,
+        /// The control change number in the range `[0, 127]`.
+        control: u8,
+        /// The value in the range `[0.0, 1.0]`.
+        value: f32,
+    },
+    /// The pitch bend wheel was moved.
+    PitchBend {
+        /// The MIDI channel in the range `[0, 15]`.
+        channel: u8,
+        /// The amount of pitch bend in the range `[-1.0, 1.0]`, where `0.0`
+        /// is the center.
+        amount: f32,
+    },
+}
+
+impl MidiInput {
+    /// Parse a raw MIDI message.
+    ///
+    /// Returns `None` if the message is invalid or if it is not one of the
+    /// supported message types.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        MidiMessage::try_from(bytes)
+            .ok()
+            .and_then(|msg| Self::from_message(&msg))
+    }
+
+    /// Convert a parsed [`MidiMessage`].
+    ///
+    /// Returns `None` if the message is not one of the supported message
+    /// types.
+    pub fn from_message(msg: &MidiMessage) -> Option<Self> {
+        match msg {
+            MidiMessage::NoteOn(channel, note, velocity) => {
+                let velocity = u8::from(*velocity);
+
+                if velocity == 0 {
+                    Some(Self::NoteOff {
+                        channel: channel.index(),
+                        note: u8::from(*note),
+                    })
+                } else {
+                    Some(Self::NoteOn {
+                        channel: channel.index(),
+                        note: u8::from(*note),
+                        velocity: u7_to_unit(velocity),
+                    })
+                }
+            }
+            MidiMessage::NoteOff(channel, note, _) => Some(Self::NoteOff {
+                channel: channel.index(),
+                note: u8::from(*note),
+            }),
+            MidiMessage::ControlChange(channel, control, value) => Some(Self::ControlChange {
+                channel: channel.index(),
+                control: u8::from(control.0),
+                value: u7_to_unit(u8::from(*value)),
+            }),
+            MidiMessage::PitchBendChange(channel, bend) => {
+                // 14-bit value where 8192 is the center.
+                let amount = (f32::from(u16::from(*bend)) - 8192.0) / 8192.0;
+
+                Some(Self::PitchBend {
+                    channel: channel.index(),
+                    amount: amount.clamp(-1.0, 1.0),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The MIDI channel of this message in the range `[0, 15]`.
+    pub fn channel(&self) -> u8 {
+        match *self {
+            Self::NoteOn { channel, .. }
+            | Self::NoteOff { channel, .. }
+            | Self::ControlChange { channel, .. }
+            | Self::PitchBend { channel, .. } => channel,
+        }
+    }
+
+    /// Trigger or release the voices in the given pool.
+    ///
+    /// Note-on and note-off messages are passed to [`NodePool::note_on`] and
+    /// [`NodePool::note_off`], and the "all notes off" and "all sound off"
+    /// control change messages release all voices.
+    ///
+    /// Returns `true` if the message was handled.
+    pub fn apply_to_pool<T: VoiceNode + Diff + Clone + 'static>(
+        &self,
+        pool: &mut NodePool<T>,
+        cx: &mut FirewheelContext,
+    ) -> bool {
+        match *self {
+            Self::NoteOn { note, velocity, .. } => pool.note_on(note, velocity, cx).is_some(),
+            Self::NoteOff { note, .. } => pool.note_off(note, cx),
+            Self::ControlChange { control, .. }
+                if control == CC_ALL_NOTES_OFF || control == CC_ALL_SOUND_OFF =>
+            {
+                pool.all_notes_off(cx);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A mapping from MIDI control change numbers to parameters of a node.
+///
+/// ```ignore
+/// let mut controls = ControlMap::<SvfNode>::new();
+/// // Map the mod wheel to the cutoff frequency.
+/// controls.map(1, |node, value| node.cutoff_hz = 20.0 * 1_000f32.powf(value));
+///
+/// if let Some(MidiInput::ControlChange { control, value, .. }) = MidiInput::parse(bytes) {
+///     controls.apply(control, value, &mut filter_node);
+///     filter_node.update_memo(&mut cx.event_queue(filter_id));
+/// }
+/// ```
+pub struct ControlMap<T> {
+    mappings: Vec<(u8, fn(&mut T, f32))>,
+}
+
+impl<T> Default for ControlMap<T> {
+    fn default() -> Self {
+        Self {
+            mappings: Vec::new(),
+        }
+    }
+}
+
+impl<T> ControlMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the given control change number to a function which sets a
+    /// parameter from a value in the range `[0.0, 1.0]`.
+    ///
+    /// Multiple functions can be mapped to the same control.
+    pub fn map(&mut self, control: u8, f: fn(&mut T, f32)) -> &mut Self {
+        self.mappings.push((control, f));
+        self
+    }
+
+    /// Remove all mappings for the given control change number.
+    pub fn unmap(&mut self, control: u8) {
+        self.mappings.retain(|(c, _)| *c != control);
+    }
+
+    /// Apply a control change to the given parameters.
+    ///
+    /// Returns `true` if the control is mapped.
+    pub fn apply(&self, control: u8, value: f32, params: &mut T) -> bool {
+        let mut mapped = false;
+
+        for (_, f) in self.mappings.iter().filter(|(c, _)| *c == control) {
+            (f)(params, value);
+            mapped = true;
+        }
+
+        mapped
+    }
+
+    /// Apply a control change to the parameters of every voice in a pool.
+    ///
+    /// Returns `true` if the control is mapped.
+    pub fn apply_to_pool(
+        &self,
+        control: u8,
+        value: f32,
+        pool: &mut NodePool<T>,
+        cx: &mut FirewheelContext,
+    ) -> bool
+    where
+        T: VoiceNode + Diff + Clone + 'static,
+    {
+        if !self.mappings.iter().any(|(c, _)| *c == control) {
+            return false;
+        }
+
+        pool.update_all(cx, |params| {
+            self.apply(control, value, params);
+        });
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn note_conversion() {
+        assert_eq!(note_to_hz(69.0), 440.0);
+        assert!((note_to_hz(60.0) - 261.6256).abs() < 0.001);
+        assert!((hz_to_note(note_to_hz(42.5)) - 42.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn parse_note_on_with_zero_velocity() {
+        assert_eq!(
+            MidiInput::parse(&[0x91, 60, 100]),
+            Some(MidiInput::NoteOn {
+                channel: 1,
+                note: 60,
+                velocity: 100.0 / 127.0,
+            })
+        );
+        assert_eq!(
+            MidiInput::parse(&[0x90, 60, 0]),
+            Some(MidiInput::NoteOff {
+                channel: 0,
+                note: 60,
+            })
+        );
+    }
+}
@@ -0,0 +1,418 @@
+//! An ADSR (attack, decay, sustain, release) envelope generator node.
+
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, ChannelCount, MAX_CHANNELS},
+    diff::{Diff, Notify, Patch},
+    dsp::filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, VoiceNode,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+/// The configuration of an [`AdsrNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdsrConfig {
+    /// The number of input channels which are multiplied by the envelope
+    /// (acting as a VCA), in the range `[0, 63]`.
+    ///
+    /// Set this to [`ChannelCount::ZERO`] to only output the envelope.
+    ///
+    /// By default this is set to [`ChannelCount::MONO`].
+    pub channels: ChannelCount,
+}
+
+impl Default for AdsrConfig {
+    fn default() -> Self {
+        Self {
+            channels: ChannelCount::MONO,
+        }
+    }
+}
+
+impl AdsrConfig {
+    fn num_vca_channels(&self) -> u32 {
+        self.channels.get().min(MAX_CHANNELS as u32 - 1)
+    }
+}
+
+/// An ADSR (attack, decay, sustain, release) envelope generator
+///
+/// The node has one input and one output port for each channel in
+/// [`AdsrConfig::channels`]. Each input is multiplied by the envelope and
+/// written to the corresponding output, acting as a simple VCA. The envelope
+/// itself is written to one additional output port after those.
+///
+/// Each stage of the envelope is linear, and the envelope advances
+/// sample-accurately across blocks. Opening the gate while the envelope is
+/// still active restarts the attack stage from the current level, so
+/// retriggering never causes a jump in the output.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdsrNode {
+    /// The time in milliseconds it takes for the envelope to rise from `0.0`
+    /// to `1.0` after the gate is opened.
+    ///
+    /// By default this is set to `10.0`.
+    pub attack_ms: f32,
+    /// The time in milliseconds it takes for the envelope to fall from `1.0`
+    /// to [`AdsrNode::sustain_level`] after the attack stage.
+    ///
+    /// By default this is set to `100.0`.
+    pub decay_ms: f32,
+    /// The level the envelope holds while the gate is open, in the range
+    /// `[0.0, 1.0]`.
+    ///
+    /// By default this is set to `0.7`.
+    pub sustain_level: f32,
+    /// The time in milliseconds it takes for the envelope to fall to `0.0`
+    /// after the gate is closed.
+    ///
+    /// By default this is set to `200.0`.
+    pub release_ms: f32,
+    /// Open (`true`) or close (`false`) the gate.
+    ///
+    /// Because this is a [`Notify`] parameter, opening the gate while it is
+    /// already open retriggers the envelope.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub gate: Notify<bool>,
+    /// The time in seconds of the internal smoothing filter used when
+    /// [`AdsrNode::sustain_level`] changes during the sustain stage.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for AdsrNode {
+    fn default() -> Self {
+        Self {
+            attack_ms: 10.0,
+            decay_ms: 100.0,
+            sustain_level: 0.7,
+            release_ms: 200.0,
+            gate: Notify::new(false),
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl AdsrNode {
+    /// Open the gate, starting (or retriggering) the attack stage.
+    pub fn gate_on(&mut self) {
+        *self.gate = true;
+    }
+
+    /// Close the gate, starting the release stage.
+    pub fn gate_off(&mut self) {
+        *self.gate = false;
+    }
+}
+
+impl VoiceNode for AdsrNode {
+    fn note_on(&mut self, _note: u8, _velocity: f32) {
+        self.gate_on();
+    }
+
+    fn note_off(&mut self) {
+        self.gate_off();
+    }
+}
+
+impl AudioNode for AdsrNode {
+    type Configuration = AdsrConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        let num_vca_channels = config.num_vca_channels();
+
+        Ok(AudioNodeInfo::new()
+            .debug_name("adsr")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::new(num_vca_channels).unwrap(),
+                num_outputs: ChannelCount::new(num_vca_channels + 1).unwrap(),
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let sample_rate = cx.stream_info.sample_rate.get() as f32;
+
+        Ok(Processor {
+            envelope: Envelope::new(self, sample_rate),
+            sustain_level: SmoothedParam::new(
+                self.sustain_level.clamp(0.0, 1.0),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                cx.stream_info.sample_rate,
+            ),
+            params: *self,
+            num_vca_channels: config.num_vca_channels() as usize,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// The state of a linear ADSR envelope.
+struct Envelope {
+    stage: Stage,
+    level: f32,
+    attack_step: f32,
+    decay_step: f32,
+    release_ms: f32,
+    release_step: f32,
+    sample_rate: f32,
+}
+
+impl Envelope {
+    fn new(params: &AdsrNode, sample_rate: f32) -> Self {
+        let mut envelope = Self {
+            stage: Stage::Idle,
+            level: 0.0,
+            attack_step: 0.0,
+            decay_step: 0.0,
+            release_ms: 0.0,
+            release_step: 0.0,
+            sample_rate,
+        };
+        envelope.set_times(params);
+        envelope
+    }
+
+    /// The amount the level changes per frame for a full-scale (`0.0` to
+    /// `1.0`) segment of the given length.
+    fn step(&self, ms: f32) -> f32 {
+        (ms.max(0.0) * 0.001 * self.sample_rate).max(1.0).recip()
+    }
+
+    fn set_times(&mut self, params: &AdsrNode) {
+        self.attack_step = self.step(params.attack_ms);
+        self.decay_step = self.step(params.decay_ms);
+        self.release_ms = params.release_ms;
+
+        if self.stage == Stage::Release {
+            self.release_step = self.level * self.step(self.release_ms);
+        }
+    }
+
+    fn gate_on(&mut self) {
+        self.stage = Stage::Attack;
+    }
+
+    fn gate_off(&mut self) {
+        if self.stage != Stage::Idle {
+            self.stage = Stage::Release;
+            // Release from the current level in exactly `release_ms`.
+            self.release_step = self.level * self.step(self.release_ms);
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.stage == Stage::Idle
+    }
+
+    fn next(&mut self, sustain_level: f32) -> f32 {
+        match self.stage {
+            Stage::Idle => {}
+            Stage::Attack => {
+                self.level += self.attack_step;
+
+                if self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                self.level -= self.decay_step;
+
+                if self.level <= sustain_level {
+                    self.level = sustain_level;
+                    self.stage = Stage::Sustain;
+                }
+            }
+            Stage::Sustain => {
+                self.level = sustain_level;
+            }
+            Stage::Release => {
+                self.level -= self.release_step;
+
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.stage = Stage::Idle;
+                }
+            }
+        }
+
+        self.level
+    }
+
+    fn reset(&mut self) {
+        self.stage = Stage::Idle;
+        self.level = 0.0;
+    }
+}
+
+struct Processor {
+    params: AdsrNode,
+    envelope: Envelope,
+    sustain_level: SmoothedParam,
+    num_vca_channels: usize,
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        let mut times_changed = false;
+
+        for patch in events.drain_patches::<AdsrNode>() {
+            match patch {
+                AdsrNodePatch::AttackMs(_)
+                | AdsrNodePatch::DecayMs(_)
+                | AdsrNodePatch::ReleaseMs(_) => {
+                    times_changed = true;
+                }
+                AdsrNodePatch::SustainLevel(level) => {
+                    self.sustain_level.set_value(level.clamp(0.0, 1.0));
+                }
+                AdsrNodePatch::Gate(gate) => {
+                    if *gate {
+                        self.envelope.gate_on();
+                    } else {
+                        self.envelope.gate_off();
+                    }
+                }
+                AdsrNodePatch::SmoothSeconds(seconds) => {
+                    self.sustain_level
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                }
+            }
+
+            self.params.apply(patch);
+        }
+
+        if times_changed {
+            self.envelope.set_times(&self.params);
+        }
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.envelope.reset();
+        self.sustain_level.reset_to_target();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        if self.envelope.is_idle() {
+            self.sustain_level.reset_to_target();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let (vca_outputs, env_output) = buffers.outputs.split_at_mut(self.num_vca_channels);
+        let env_output = &mut env_output[0][..info.frames];
+
+        // Once the envelope becomes idle it stays at `0.0` for the rest of
+        // the block.
+        for env in env_output.iter_mut() {
+            *env = self.envelope.next(self.sustain_level.next_smoothed());
+        }
+
+        self.sustain_level.settle();
+
+        for (ch_i, (out, input)) in vca_outputs
+            .iter_mut()
+            .zip(buffers.inputs.iter())
+            .enumerate()
+        {
+            if info.in_silence_mask.is_channel_silent(ch_i) {
+                out[..info.frames].fill(0.0);
+                continue;
+            }
+
+            for ((o, &s), &env) in out[..info.frames]
+                .iter_mut()
+                .zip(input[..info.frames].iter())
+                .zip(env_output.iter())
+            {
+                *o = s * env;
+            }
+        }
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.envelope.sample_rate = stream_info.sample_rate.get() as f32;
+        self.envelope.set_times(&self.params);
+        self.sustain_level
+            .update_sample_rate(stream_info.sample_rate);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn envelope_stages() {
+        let params = AdsrNode {
+            attack_ms: 1.0,
+            decay_ms: 1.0,
+            sustain_level: 0.5,
+            release_ms: 2.0,
+            ..Default::default()
+        };
+        // One millisecond is 10 frames.
+        let mut envelope = Envelope::new(&params, 10_000.0);
+
+        assert_eq!(envelope.next(0.5), 0.0);
+
+        envelope.gate_on();
+        for _ in 0..11 {
+            envelope.next(0.5);
+        }
+        assert_eq!(envelope.stage, Stage::Decay);
+
+        for _ in 0..10 {
+            envelope.next(0.5);
+        }
+        assert_eq!(envelope.stage, Stage::Sustain);
+        assert_eq!(envelope.level, 0.5);
+
+        // Retriggering restarts the attack from the current level.
+        envelope.gate_on();
+        assert!(envelope.next(0.5) > 0.5);
+
+        envelope.gate_off();
+        for _ in 0..21 {
+            envelope.next(0.5);
+        }
+        assert!(envelope.is_idle());
+        assert_eq!(envelope.level, 0.0);
+    }
+}
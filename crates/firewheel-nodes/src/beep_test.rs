@@ -9,10 +9,50 @@ use firewheel_core::{
     event::ProcEvents,
     node::{
         AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
-        ProcBuffers, ProcExtra, ProcInfo, ProcessStatus,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
     },
 };
 
+/// How the frequency of a sweep in a [`BeepTestNode`] changes over time.
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SweepCurve {
+    /// The frequency changes by the same amount of hertz every second.
+    Linear,
+    /// The frequency changes by the same amount of octaves every second.
+    ///
+    /// This spends an equal amount of time in each octave, which is usually
+    /// preferable for measuring frequency responses.
+    #[default]
+    Logarithmic,
+}
+
+/// The signal a [`BeepTestNode`] outputs.
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BeepTestMode {
+    /// A steady sine wave at [`BeepTestNode::freq_hz`].
+    #[default]
+    Tone,
+    /// A sine wave sweeping from `start_hz` to `end_hz` (a "chirp").
+    ///
+    /// Frequencies are clamped to the range `[20.0, 20_000.0]`. The sweep
+    /// restarts whenever this mode is set.
+    Sweep {
+        start_hz: f32,
+        end_hz: f32,
+        /// The length of the sweep in seconds.
+        duration_seconds: f32,
+        curve: SweepCurve,
+        /// If `true`, then the sweep restarts from `start_hz` once it
+        /// reaches the end. Otherwise the node outputs silence after the
+        /// sweep has finished.
+        looping: bool,
+    },
+}
+
 /// A simple node that outputs a sine wave, used for testing purposes.
 ///
 /// Note that because this node is for testing purposes, it does not
@@ -24,8 +64,15 @@ use firewheel_core::{
 pub struct BeepTestNode {
     /// The frequency of the sine wave in the range `[20.0, 20_000.0]`. A good
     /// value for testing is `440` (middle C).
+    ///
+    /// This is only used in [`BeepTestMode::Tone`] mode.
     pub freq_hz: f32,
 
+    /// The signal to output.
+    ///
+    /// By default this is set to [`BeepTestMode::Tone`].
+    pub mode: BeepTestMode,
+
     /// The overall volume.
     ///
     /// NOTE, a sine wave at `Volume::Linear(1.0) or Volume::Decibels(0.0)` volume
@@ -38,6 +85,7 @@ impl Default for BeepTestNode {
     fn default() -> Self {
         Self {
             freq_hz: 440.0,
+            mode: BeepTestMode::Tone,
             volume: Volume::Linear(0.5),
         }
     }
@@ -61,18 +109,64 @@ impl AudioNode for BeepTestNode {
         cx: ConstructProcessorContext,
     ) -> Result<impl AudioNodeProcessor, NodeError> {
         Ok(Processor {
+            params: *self,
             phasor: 0.0,
             phasor_inc: self.freq_hz.clamp(20.0, 20_000.0)
                 * cx.stream_info.sample_rate_recip as f32,
             gain: self.volume.amp_clamped(DEFAULT_MIN_AMP),
+            sweep_seconds: 0.0,
+            sample_rate_recip: cx.stream_info.sample_rate_recip,
         })
     }
 }
 
 struct Processor {
+    params: BeepTestNode,
     phasor: f32,
     phasor_inc: f32,
     gain: f32,
+    /// The time in seconds since the start of the current sweep.
+    sweep_seconds: f64,
+    sample_rate_recip: f64,
+}
+
+impl Processor {
+    fn process_sweep(
+        &mut self,
+        out: &mut [f32],
+        start_hz: f32,
+        end_hz: f32,
+        duration_seconds: f32,
+        curve: SweepCurve,
+        looping: bool,
+    ) -> bool {
+        let start_hz = f64::from(start_hz.clamp(20.0, 20_000.0));
+        let end_hz = f64::from(end_hz.clamp(20.0, 20_000.0));
+        let duration_seconds = f64::from(duration_seconds.max(0.001));
+
+        for s in out.iter_mut() {
+            if self.sweep_seconds >= duration_seconds {
+                if !looping {
+                    *s = 0.0;
+                    continue;
+                }
+
+                self.sweep_seconds -= duration_seconds;
+            }
+
+            let t = self.sweep_seconds / duration_seconds;
+            let freq_hz = match curve {
+                SweepCurve::Linear => start_hz + (end_hz - start_hz) * t,
+                SweepCurve::Logarithmic => start_hz * (end_hz / start_hz).powf(t),
+            };
+
+            *s = (self.phasor * core::f32::consts::TAU).sin() * self.gain;
+            self.phasor = (self.phasor + (freq_hz * self.sample_rate_recip) as f32).fract();
+            self.sweep_seconds += self.sample_rate_recip;
+        }
+
+        looping || self.sweep_seconds < duration_seconds
+    }
 }
 
 impl AudioNodeProcessor for Processor {
@@ -85,7 +179,13 @@ impl AudioNodeProcessor for Processor {
                 BeepTestNodePatch::Volume(v) => {
                     self.gain = v.amp_clamped(DEFAULT_MIN_AMP);
                 }
+                BeepTestNodePatch::Mode(_) => {
+                    self.sweep_seconds = 0.0;
+                    self.phasor = 0.0;
+                }
             }
+
+            self.params.apply(patch);
         }
     }
 
@@ -95,11 +195,43 @@ impl AudioNodeProcessor for Processor {
         buffers: ProcBuffers,
         _extra: &mut ProcExtra,
     ) -> ProcessStatus {
-        for s in buffers.outputs[0].iter_mut() {
-            *s = (self.phasor * core::f32::consts::TAU).sin() * self.gain;
-            self.phasor = (self.phasor + self.phasor_inc).fract();
+        match self.params.mode {
+            BeepTestMode::Tone => {
+                for s in buffers.outputs[0].iter_mut() {
+                    *s = (self.phasor * core::f32::consts::TAU).sin() * self.gain;
+                    self.phasor = (self.phasor + self.phasor_inc).fract();
+                }
+            }
+            BeepTestMode::Sweep {
+                start_hz,
+                end_hz,
+                duration_seconds,
+                curve,
+                looping,
+            } => {
+                if !self.process_sweep(
+                    &mut buffers.outputs[0][..],
+                    start_hz,
+                    end_hz,
+                    duration_seconds,
+                    curve,
+                    looping,
+                ) {
+                    return ProcessStatus::ClearAllOutputs;
+                }
+            }
         }
 
         ProcessStatus::OutputsModified
     }
+
+    fn new_stream(
+        &mut self,
+        stream_info: &firewheel_core::StreamInfo,
+        _context: &mut ProcStreamCtx,
+    ) {
+        self.sample_rate_recip = stream_info.sample_rate_recip;
+        self.phasor_inc =
+            self.params.freq_hz.clamp(20.0, 20_000.0) * stream_info.sample_rate_recip as f32;
+    }
 }
@@ -0,0 +1,121 @@
+use firewheel_core::{
+    channel_config::{ChannelConfig, ChannelCount},
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, NodeError,
+        ProcBuffers, ProcExtra, ProcInfo, ProcessStatus,
+    },
+};
+
+/// The configuration for a [`ClosureNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClosureNodeConfig {
+    /// The number of input and output channels.
+    ///
+    /// By default this is set to stereo in and stereo out.
+    pub channel_config: ChannelConfig,
+}
+
+impl Default for ClosureNodeConfig {
+    fn default() -> Self {
+        Self {
+            channel_config: ChannelConfig {
+                num_inputs: ChannelCount::STEREO,
+                num_outputs: ChannelCount::STEREO,
+            },
+        }
+    }
+}
+
+/// A node which runs a user-provided closure on the audio thread, useful for
+/// prototyping a bit of custom DSP without authoring a full node.
+///
+/// The closure is called once per processing block, and it has the same
+/// contract as [`AudioNodeProcessor::process`]. Notably, it must either
+/// completely fill all output buffers or return one of
+/// [`ProcessStatus::ClearAllOutputs`]/[`ProcessStatus::Bypass`].
+///
+/// # Realtime safety
+///
+/// The closure runs on the realtime audio thread, so it *MUST* be realtime
+/// safe. That means it must not:
+///
+/// * Allocate or deallocate memory (i.e. by growing a `Vec`, or by dropping
+///   a `Box` or the last clone of an `Arc`).
+/// * Lock a mutex or wait on any other kind of blocking primitive.
+/// * Perform I/O, such as printing to the console or reading a file.
+/// * Panic.
+///
+/// Failing to uphold these rules will result in audio glitches.
+///
+/// # Construction
+///
+/// The closure is moved onto the audio thread when the node is added to the
+/// graph. The processor may be reconstructed later (i.e. when the audio
+/// stream is restarted), in which case a fresh clone of the original closure
+/// is used. Any state captured by the closure will therefore be reset to the
+/// state it had when this node was created.
+#[derive(Clone)]
+pub struct ClosureNode<F> {
+    f: F,
+}
+
+impl<F> ClosureNode<F>
+where
+    F: FnMut(ProcBuffers<'_, '_>, &ProcInfo) -> ProcessStatus + Clone + Send + 'static,
+{
+    /// Create a new node which runs the given closure on the audio thread.
+    ///
+    /// See the [`ClosureNode`] docs for the realtime-safety requirements of
+    /// the closure.
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F> core::fmt::Debug for ClosureNode<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ClosureNode").finish_non_exhaustive()
+    }
+}
+
+impl<F> AudioNode for ClosureNode<F>
+where
+    F: FnMut(ProcBuffers<'_, '_>, &ProcInfo) -> ProcessStatus + Clone + Send + 'static,
+{
+    type Configuration = ClosureNodeConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("closure")
+            .channel_config(config.channel_config))
+    }
+
+    fn construct_processor(
+        &self,
+        _config: &Self::Configuration,
+        _cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        Ok(Processor { f: self.f.clone() })
+    }
+}
+
+struct Processor<F> {
+    f: F,
+}
+
+impl<F> AudioNodeProcessor for Processor<F>
+where
+    F: FnMut(ProcBuffers<'_, '_>, &ProcInfo) -> ProcessStatus + Send + 'static,
+{
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        (self.f)(buffers, info)
+    }
+}
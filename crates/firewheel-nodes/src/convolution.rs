@@ -11,7 +11,9 @@ use firewheel_core::{
     diff::{Diff, Patch},
     dsp::{
         declick::{DeclickFadeCurve, Declicker},
+        fade::FadeCurve,
         filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+        mix::Mix,
         volume::{DEFAULT_MIN_AMP, Volume},
     },
     node::{
@@ -21,6 +23,8 @@ use firewheel_core::{
     sample_resource::SampleResourceF32,
 };
 
+use crate::dry_wet::DryWetMix;
+
 /// Node configuration for [`ConvolutionNode`].
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
@@ -38,23 +42,125 @@ pub struct ConvolutionNodeConfig {
     /// By default this is set to `4.0`.
     pub max_impulse_length_seconds: f64,
 
-    /// Smaller blocks may reduce latency at the cost of increased CPU usage.
+    /// The number of frames in each partition of the partitioned convolver.
+    ///
+    /// This does not affect latency (see [`ConvolutionMode`]). Smaller
+    /// partitions spread the CPU cost more evenly across blocks, at the cost
+    /// of increased overall CPU usage.
     ///
     /// By default this is set to `1024`.
     pub partition_size: usize,
+
+    /// The number of frames over which the output of the previous impulse
+    /// response is crossfaded into the output of the new impulse response
+    /// when [`ConvolutionNode::impulse_response`] is swapped during playback.
+    ///
+    /// Both impulse responses are convolved while the crossfade is active,
+    /// and the node allocates a second set of convolvers to do so. Note, the
+    /// new impulse response starts with an empty tail.
+    ///
+    /// If this is `0`, then the previous impulse response is faded out
+    /// before the new one is faded in, and no extra convolvers are
+    /// allocated.
+    ///
+    /// By default this is set to `2048`.
+    pub swap_fade_frames: u32,
+
+    /// If `true`, then the node convolves in "true stereo", where each input
+    /// channel is convolved with a separate impulse response for each output
+    /// channel. This preserves cross-channel reflections, at the cost of
+    /// twice the CPU usage.
+    ///
+    /// In this mode [`ConvolutionNodeConfig::channels`] must be
+    /// [`NonZeroChannelCount::STEREO`], and the impulse response should have
+    /// four channels in the order:
+    /// * `0` - Left input to left output
+    /// * `1` - Left input to right output
+    /// * `2` - Right input to left output
+    /// * `3` - Right input to right output
+    ///
+    /// If the impulse response has fewer than four channels, then the
+    /// cross-channel paths are silent and it is used the same way as in the
+    /// regular mode.
+    ///
+    /// By default this is set to `false`.
+    pub true_stereo: bool,
+
+    /// If `true`, and only the first input channel is connected to another
+    /// node (i.e. a mono source is connected to a stereo convolution), then
+    /// the first input channel is fed into every convolution channel. This
+    /// avoids only one output channel receiving the effect.
+    ///
+    /// This is based on which input ports are connected rather than which
+    /// ones are silent, so a hard-panned stereo source is left untouched.
+    ///
+    /// By default this is set to `true`.
+    pub adapt_mono_input: bool,
+
+    /// How the impulse response is convolved with the input.
+    ///
+    /// By default this is set to [`ConvolutionMode::Auto`].
+    pub mode: ConvolutionMode,
+}
+
+/// The maximum length of an impulse response in frames which is convolved
+/// directly in the time domain when using [`ConvolutionMode::Auto`].
+pub const DIRECT_CONVOLUTION_MAX_FRAMES: usize = 256;
+
+/// How a [`ConvolutionNode`] convolves the impulse response with the input.
+///
+/// Both methods have zero latency, since the partitioned convolver also
+/// processes partially filled partitions.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConvolutionMode {
+    /// Use direct convolution for impulse responses with at most
+    /// [`DIRECT_CONVOLUTION_MAX_FRAMES`] frames, and partitioned convolution
+    /// for longer impulse responses.
+    #[default]
+    Auto,
+    /// Always use partitioned FFT convolution. This is the most efficient
+    /// method for long impulse responses.
+    Partitioned,
+    /// Always convolve directly in the time domain. This is efficient and has
+    /// a constant CPU cost per frame for very short impulse responses (i.e.
+    /// early reflections), but it gets very expensive for long ones.
+    Direct,
+}
+
+impl ConvolutionMode {
+    /// The number of frames to preallocate for direct convolution.
+    fn direct_capacity(&self, max_frames: usize) -> usize {
+        match self {
+            Self::Auto => DIRECT_CONVOLUTION_MAX_FRAMES.min(max_frames),
+            Self::Partitioned => 0,
+            Self::Direct => max_frames,
+        }
+    }
 }
 
 /// The default partition size to use with a [`ConvolutionNode`].
 ///
-/// Smaller blocks may reduce latency at the cost of increased CPU usage.
+/// See [`ConvolutionNodeConfig::partition_size`].
 pub const DEFAULT_PARTITION_SIZE: usize = 1024;
 
+/// The latency of a [`ConvolutionNode`] in frames.
+///
+/// Both convolution methods have zero latency (see [`ConvolutionMode`]). The
+/// dry signal is delayed by this amount to stay aligned with the wet signal.
+const LATENCY_FRAMES: u32 = 0;
+
 impl Default for ConvolutionNodeConfig {
     fn default() -> Self {
         Self {
             channels: NonZeroChannelCount::STEREO,
             max_impulse_length_seconds: 4.0,
             partition_size: DEFAULT_PARTITION_SIZE,
+            swap_fade_frames: 2048,
+            true_stereo: false,
+            adapt_mono_input: true,
+            mode: ConvolutionMode::Auto,
         }
     }
 }
@@ -63,6 +169,10 @@ impl Default for ConvolutionNodeConfig {
 ///
 /// Convolution is often used to achieve reverb effects, but is more
 /// computationally expensive than algorithmic reverb.
+///
+/// The dry signal is blended into the output with [`ConvolutionNode::mix`].
+/// It is delayed by the latency of the convolver, so the two signals never
+/// comb filter.
 #[derive(Patch, Diff, Clone, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
 #[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
@@ -85,8 +195,16 @@ pub struct ConvolutionNode {
     /// convolving audio. Values closer to 1.0 may be very loud.
     pub wet_gain: Volume,
 
-    /// Adjusts the time in seconds over which parameters are smoothed for `mix`
-    /// and `wet_gain`.
+    /// The mix between the dry input signal and the wet convolved signal.
+    ///
+    /// The wet signal is scaled by [`ConvolutionNode::wet_gain`] before it
+    /// is mixed.
+    ///
+    /// By default this is set to [`Mix::FULLY_WET`].
+    pub mix: Mix,
+
+    /// Adjusts the time in seconds over which `wet_gain` and `mix` are
+    /// smoothed.
     ///
     /// By default this is set to `0.023` (23ms). This value is chosen to be
     /// roughly equal to a typical block size of 1024 samples (23 ms) to
@@ -100,6 +218,7 @@ impl Default for ConvolutionNode {
             impulse_response: None,
             wet_gain: Volume::Decibels(-20.0),
             pause: false,
+            mix: Mix::FULLY_WET,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
         }
     }
@@ -114,6 +233,7 @@ impl core::fmt::Debug for ConvolutionNode {
         );
         f.field("pause", &self.pause);
         f.field("wet_gain", &self.wet_gain);
+        f.field("mix", &self.mix);
         f.field("smooth_seconds", &self.smooth_seconds);
         f.finish()
     }
@@ -123,8 +243,16 @@ impl AudioNode for ConvolutionNode {
     type Configuration = ConvolutionNodeConfig;
 
     fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        if config.true_stereo && config.channels != NonZeroChannelCount::STEREO {
+            return Err(TrueStereoChannelsError {
+                channels: config.channels.get().get(),
+            }
+            .into());
+        }
+
         Ok(AudioNodeInfo::new()
             .debug_name("convolution")
+            .latency_frames(LATENCY_FRAMES)
             .channel_config(ChannelConfig::new(
                 config.channels.get(),
                 config.channels.get(),
@@ -149,14 +277,31 @@ impl AudioNode for ConvolutionNode {
         let mut tmp_impulse = vec![0.0; max_frames];
         tmp_impulse[0] = 1.0;
 
-        let mut convolver: Vec<FFTConvolver<f32>> = (0..config.channels.get().get())
-            .map(|_| {
-                let mut c = FFTConvolver::default();
-                // TODO: Ask the creator of `fft-convolver` to add a `with_capacity` method.
-                c.init(config.partition_size, &tmp_impulse).unwrap();
-                c
-            })
-            .collect();
+        let direct_capacity = config.mode.direct_capacity(max_frames);
+
+        let new_convolvers = |num_channels: u32| -> Vec<Convolver> {
+            (0..num_channels)
+                .map(|_| {
+                    let mut c = FFTConvolver::default();
+                    // TODO: Ask the creator of `fft-convolver` to add a `with_capacity` method.
+                    c.init(config.partition_size, &tmp_impulse).unwrap();
+                    Convolver::new(c, direct_capacity)
+                })
+                .collect()
+        };
+
+        let num_convolvers = if config.true_stereo {
+            4
+        } else {
+            config.channels.get().get()
+        };
+
+        let mut convolver = new_convolvers(num_convolvers);
+        let incoming_convolver = if config.swap_fade_frames > 0 {
+            new_convolvers(num_convolvers)
+        } else {
+            Vec::new()
+        };
 
         let did_init_first_impulse = if let Some(s) = &self.impulse_response {
             if s.len_frames() > max_frames as u64 {
@@ -167,20 +312,7 @@ impl AudioNode for ConvolutionNode {
                 .into());
             }
 
-            if s.num_channels().get() < config.channels.get().get() as usize {
-                // Assume a mono impulse response and set it to all channels.
-                let impulse_slice = s.channel(0).unwrap();
-
-                for c in convolver.iter_mut() {
-                    c.set_response(impulse_slice).unwrap();
-                    c.reset();
-                }
-            } else {
-                for (ch_i, c) in convolver.iter_mut().enumerate() {
-                    c.set_response(s.channel(ch_i).unwrap()).unwrap();
-                    c.reset();
-                }
-            }
+            load_impulse(&mut convolver, &**s, true, config.true_stereo);
 
             true
         } else {
@@ -190,12 +322,26 @@ impl AudioNode for ConvolutionNode {
         Ok(ConvolutionProcessor {
             params: self.clone(),
             gain: SmoothedParam::new(self.wet_gain.amp(), smooth_config, sample_rate),
+            dry_wet: DryWetMix::new(
+                self.mix,
+                config.channels.get().get() as usize,
+                LATENCY_FRAMES as usize,
+                smooth_config,
+                sample_rate,
+            ),
             declick: Declicker::SettledAt0,
             convolver,
+            incoming_convolver,
             max_frames,
             did_init_first_impulse,
             has_impulse: did_init_first_impulse,
             new_impulse_queued: false,
+            swap_fade_frames: config.swap_fade_frames as usize,
+            swap_fade_frames_left: 0,
+            true_stereo: config.true_stereo,
+            partition_size: config.partition_size,
+            adapt_mono_input: config.adapt_mono_input && config.channels.get().get() > 1,
+            mono_input: false,
         })
     }
 }
@@ -203,12 +349,24 @@ impl AudioNode for ConvolutionNode {
 struct ConvolutionProcessor {
     params: ConvolutionNode,
     gain: SmoothedParam,
+    dry_wet: DryWetMix,
     declick: Declicker,
-    convolver: Vec<FFTConvolver<f32>>,
+    convolver: Vec<Convolver>,
+    /// The convolvers the new impulse response is loaded into while
+    /// crossfading. This is empty if crossfading is disabled.
+    incoming_convolver: Vec<Convolver>,
     max_frames: usize,
     did_init_first_impulse: bool,
     has_impulse: bool,
     new_impulse_queued: bool,
+    swap_fade_frames: usize,
+    swap_fade_frames_left: usize,
+    true_stereo: bool,
+    partition_size: usize,
+    adapt_mono_input: bool,
+    /// Whether only the first input channel is connected, in which case it
+    /// is fed into every convolution channel.
+    mono_input: bool,
 }
 
 impl AudioNodeProcessor for ConvolutionProcessor {
@@ -223,6 +381,9 @@ impl AudioNodeProcessor for ConvolutionProcessor {
                 ConvolutionNodePatch::WetGain(gain) => {
                     self.gain.set_value(gain.amp());
                 }
+                ConvolutionNodePatch::Mix(mix) => {
+                    self.dry_wet.set_mix(mix);
+                }
                 ConvolutionNodePatch::Pause(pause) => {
                     if self.has_impulse {
                         self.declick.fade_to_enabled(!pause, &extra.declick_values);
@@ -231,6 +392,8 @@ impl AudioNodeProcessor for ConvolutionProcessor {
                 ConvolutionNodePatch::SmoothSeconds(smooth_seconds) => {
                     self.gain
                         .set_smooth_seconds(smooth_seconds, info.sample_rate);
+                    self.dry_wet
+                        .set_smooth_seconds(smooth_seconds, info.sample_rate);
                 }
             }
 
@@ -244,8 +407,11 @@ impl AudioNodeProcessor for ConvolutionProcessor {
                     let _ = extra.logger.try_error("Impulse is too long, please increase ConvolutionNodeConfig::max_impulse_len_seconds");
                 } else {
                     self.new_impulse_queued = true;
-                    // Fade out the previous impulse
-                    self.declick.fade_to_0(&extra.declick_values);
+
+                    if !self.can_crossfade() && self.swap_fade_frames_left == 0 {
+                        // Fade out the previous impulse
+                        self.declick.fade_to_0(&extra.declick_values);
+                    }
                 }
             } else {
                 self.declick.fade_to_0(&extra.declick_values);
@@ -255,11 +421,32 @@ impl AudioNodeProcessor for ConvolutionProcessor {
         }
     }
 
+    fn tail_frames(&self) -> Option<u64> {
+        if self.new_impulse_queued || self.swap_fade_frames_left > 0 {
+            // The tail of the previous impulse may still be ringing out.
+            return None;
+        }
+
+        if !self.has_impulse {
+            return Some(0);
+        }
+
+        self.params
+            .impulse_response
+            .as_ref()
+            .map(|s| s.len_frames() + self.partition_size as u64)
+    }
+
     fn bypassed(&mut self, bypassed: bool) {
         if !bypassed {
             self.gain.reset_to_target();
+            self.dry_wet.reset();
             self.declick.reset_to_target();
 
+            if self.swap_fade_frames_left > 0 {
+                self.finish_crossfade();
+            }
+
             for c in self.convolver.iter_mut() {
                 c.reset();
             }
@@ -275,10 +462,31 @@ impl AudioNodeProcessor for ConvolutionProcessor {
         let mut frames_processed = 0;
         let mut output_silent = true;
 
-        if self.new_impulse_queued {
+        self.mono_input = self.adapt_mono_input
+            && info.in_connected_mask.is_channel_connected(0)
+            && (1..buffers.inputs.len()).all(|i| !info.in_connected_mask.is_channel_connected(i));
+
+        if self.new_impulse_queued && self.swap_fade_frames_left == 0 && self.can_crossfade() {
+            self.start_crossfade();
+        }
+
+        if self.swap_fade_frames_left > 0 {
+            // Crossfade from the previous impulse to the new one
+            let proc_frames = self.swap_fade_frames_left.min(info.frames);
+
+            self.convolve_crossfade_block(&mut buffers, 0..proc_frames, extra);
+
+            if self.swap_fade_frames_left == 0 {
+                self.finish_crossfade();
+            }
+
+            frames_processed = proc_frames;
+            output_silent = false;
+        } else if self.new_impulse_queued {
             if self.declick != Declicker::SettledAt0 {
-                // Sanity check
-                assert!(self.declick.trending_towards_zero());
+                if !self.declick.trending_towards_zero() {
+                    self.declick.fade_to_0(&extra.declick_values);
+                }
 
                 // Fade out the previous impulse
                 let proc_frames = self.declick.frames_left().min(info.frames);
@@ -293,26 +501,12 @@ impl AudioNodeProcessor for ConvolutionProcessor {
                 // Finished fading out old impulse, replace with new one
 
                 if let Some(s) = &self.params.impulse_response {
-                    if s.num_channels().get() < self.convolver.len() {
-                        // Assume a mono impulse response and set it to all channels.
-                        let impulse_slice = s.channel(0).unwrap();
-
-                        for c in self.convolver.iter_mut() {
-                            c.set_response(impulse_slice).unwrap();
-
-                            if !self.did_init_first_impulse {
-                                c.reset();
-                            }
-                        }
-                    } else {
-                        for (ch_i, c) in self.convolver.iter_mut().enumerate() {
-                            c.set_response(s.channel(ch_i).unwrap()).unwrap();
-
-                            if !self.did_init_first_impulse {
-                                c.reset();
-                            }
-                        }
-                    }
+                    load_impulse(
+                        &mut self.convolver,
+                        &**s,
+                        !self.did_init_first_impulse,
+                        self.true_stereo,
+                    );
 
                     self.did_init_first_impulse = true;
                     self.has_impulse = true;
@@ -334,18 +528,29 @@ impl AudioNodeProcessor for ConvolutionProcessor {
 
             self.gain.reset_to_target();
 
-            if frames_processed == 0 {
+            if frames_processed == 0 && self.dry_wet.is_fully_wet() {
                 return ProcessStatus::Bypass;
-            } else {
-                // Clear the rest to zeros.
-                for (ch_i, ch) in buffers.outputs.iter_mut().enumerate() {
-                    if !info.out_silence_mask.is_channel_silent(ch_i) {
-                        ch[frames_processed..].fill(0.0);
-                    }
+            }
+
+            // Clear the rest to zeros.
+            for (ch_i, ch) in buffers.outputs.iter_mut().enumerate() {
+                if !info.out_silence_mask.is_channel_silent(ch_i) {
+                    ch[frames_processed..].fill(0.0);
                 }
             }
         }
 
+        let [dry_gain_buffer, wet_gain_buffer] = extra.scratch_buffers.channels_mut::<2>();
+        if self.dry_wet.process(
+            buffers.inputs,
+            buffers.outputs,
+            info.frames,
+            self.mono_input,
+            [dry_gain_buffer, wet_gain_buffer],
+        ) {
+            output_silent = false;
+        }
+
         if output_silent {
             ProcessStatus::outputs_modified_with_silence_mask(info.in_silence_mask)
         } else {
@@ -355,6 +560,95 @@ impl AudioNodeProcessor for ConvolutionProcessor {
 }
 
 impl ConvolutionProcessor {
+    /// Whether the next impulse response can be crossfaded in instead of
+    /// fading out the current one first.
+    fn can_crossfade(&self) -> bool {
+        self.swap_fade_frames > 0 && self.has_impulse && !self.declick.trending_towards_zero()
+    }
+
+    fn start_crossfade(&mut self) {
+        self.new_impulse_queued = false;
+
+        let Some(s) = &self.params.impulse_response else {
+            return;
+        };
+
+        load_impulse(&mut self.incoming_convolver, &**s, true, self.true_stereo);
+
+        self.swap_fade_frames_left = self.swap_fade_frames;
+    }
+
+    fn finish_crossfade(&mut self) {
+        self.swap_fade_frames_left = 0;
+        core::mem::swap(&mut self.convolver, &mut self.incoming_convolver);
+    }
+
+    fn convolve_crossfade_block(
+        &mut self,
+        buffers: &mut ProcBuffers,
+        range: Range<usize>,
+        extra: &mut ProcExtra,
+    ) {
+        let frames = range.end - range.start;
+
+        let mut scratch_buffers = extra.scratch_buffers.all_mut();
+        let (wet_gain_buffer, rest) = scratch_buffers.split_first_mut().unwrap();
+        let (wet_declick_buffer, rest) = rest.split_first_mut().unwrap();
+        let (incoming_buffer, rest) = rest.split_first_mut().unwrap();
+        let incoming_buffer = &mut incoming_buffer[..frames];
+        let cross_buffer = &mut rest[0][..frames];
+
+        self.gain
+            .process_into_buffer(&mut wet_gain_buffer[0..frames]);
+        self.declick.process_into_gain_buffer(
+            &mut wet_declick_buffer[0..frames],
+            false,
+            &extra.declick_values,
+            DeclickFadeCurve::EqualPower3dB,
+        );
+
+        let fade_start = self.swap_fade_frames - self.swap_fade_frames_left;
+        let swap_fade_frames_recip = (self.swap_fade_frames as f32).recip();
+
+        for (ch_i, output) in buffers.outputs.iter_mut().enumerate() {
+            convolve_channel(
+                &mut self.convolver,
+                buffers.inputs,
+                ch_i,
+                range.clone(),
+                &mut output[range.clone()],
+                cross_buffer,
+                self.true_stereo,
+                self.mono_input,
+            );
+            convolve_channel(
+                &mut self.incoming_convolver,
+                buffers.inputs,
+                ch_i,
+                range.clone(),
+                incoming_buffer,
+                cross_buffer,
+                self.true_stereo,
+                self.mono_input,
+            );
+
+            for (i, ((out_s, &in_s), (&g1, &g2))) in output[range.clone()]
+                .iter_mut()
+                .zip(incoming_buffer.iter())
+                .zip(wet_gain_buffer.iter().zip(wet_declick_buffer.iter()))
+                .enumerate()
+            {
+                let (fade_out, fade_in) = FadeCurve::EqualPower3dB
+                    .compute_gains_0_to_1((fade_start + i) as f32 * swap_fade_frames_recip);
+
+                *out_s = (*out_s * fade_out + in_s * fade_in) * g1 * g2;
+            }
+        }
+
+        self.gain.settle();
+        self.swap_fade_frames_left -= frames;
+    }
+
     fn convolve_block(
         &mut self,
         buffers: &mut ProcBuffers,
@@ -364,8 +658,9 @@ impl ConvolutionProcessor {
         let frames = range.end - range.start;
 
         let mut scratch_buffers = extra.scratch_buffers.all_mut();
-        let (wet_gain_buffer, wet_declick_buffer) = scratch_buffers.split_first_mut().unwrap();
-        let wet_declick_buffer = &mut wet_declick_buffer[0];
+        let (wet_gain_buffer, rest) = scratch_buffers.split_first_mut().unwrap();
+        let (wet_declick_buffer, rest) = rest.split_first_mut().unwrap();
+        let cross_buffer = &mut rest[0][..frames];
 
         self.gain
             .process_into_buffer(&mut wet_gain_buffer[0..frames]);
@@ -376,14 +671,17 @@ impl ConvolutionProcessor {
             DeclickFadeCurve::EqualPower3dB,
         );
 
-        for ((conv, input), output) in self
-            .convolver
-            .iter_mut()
-            .zip(buffers.inputs.iter())
-            .zip(buffers.outputs.iter_mut())
-        {
-            conv.process(&input[range.clone()], &mut output[range.clone()])
-                .unwrap();
+        for (ch_i, output) in buffers.outputs.iter_mut().enumerate() {
+            convolve_channel(
+                &mut self.convolver,
+                buffers.inputs,
+                ch_i,
+                range.clone(),
+                &mut output[range.clone()],
+                cross_buffer,
+                self.true_stereo,
+                self.mono_input,
+            );
 
             for ((out_s, &g1), &g2) in output[range.clone()]
                 .iter_mut()
@@ -398,6 +696,185 @@ impl ConvolutionProcessor {
     }
 }
 
+/// Convolve the input for the given output channel into `output`.
+///
+/// In true stereo mode the convolvers are ordered `[LL, LR, RL, RR]`, and
+/// `cross_buffer` is used to sum the two paths leading to the output channel.
+///
+/// If `mono_input` is `true`, then the first input channel is used in place
+/// of every input channel.
+#[expect(clippy::too_many_arguments, reason = "Function needs many arguments")]
+fn convolve_channel(
+    convolvers: &mut [Convolver],
+    inputs: &[&[f32]],
+    ch_i: usize,
+    range: Range<usize>,
+    output: &mut [f32],
+    cross_buffer: &mut [f32],
+    true_stereo: bool,
+    mono_input: bool,
+) {
+    let input = |i: usize| if mono_input { inputs[0] } else { inputs[i] };
+
+    if !true_stereo {
+        convolvers[ch_i].process(&input(ch_i)[range], output);
+        return;
+    }
+
+    convolvers[ch_i].process(&input(0)[range.clone()], output);
+    convolvers[2 + ch_i].process(&input(1)[range], cross_buffer);
+
+    for (out_s, &cross_s) in output.iter_mut().zip(cross_buffer.iter()) {
+        *out_s += cross_s;
+    }
+}
+
+/// Set the given impulse response on all convolvers.
+fn load_impulse(
+    convolvers: &mut [Convolver],
+    impulse: &(dyn SampleResourceF32 + Send + Sync + 'static),
+    reset: bool,
+    true_stereo: bool,
+) {
+    if true_stereo && impulse.num_channels().get() < 4 {
+        // Use the impulse response for the direct paths, and silence the
+        // cross-channel paths.
+        let left = impulse.channel(0).unwrap();
+        let right = impulse.channel(1).unwrap_or(left);
+
+        for (c, response) in
+            convolvers
+                .iter_mut()
+                .zip([left, SILENT_RESPONSE, SILENT_RESPONSE, right])
+        {
+            c.set_response(response);
+
+            if reset {
+                c.reset();
+            }
+        }
+    } else if impulse.num_channels().get() < convolvers.len() {
+        // Assume a mono impulse response and set it to all channels.
+        let impulse_slice = impulse.channel(0).unwrap();
+
+        for c in convolvers.iter_mut() {
+            c.set_response(impulse_slice);
+
+            if reset {
+                c.reset();
+            }
+        }
+    } else {
+        for (ch_i, c) in convolvers.iter_mut().enumerate() {
+            c.set_response(impulse.channel(ch_i).unwrap());
+
+            if reset {
+                c.reset();
+            }
+        }
+    }
+}
+
+const SILENT_RESPONSE: &[f32] = &[0.0];
+
+/// A convolver which convolves short impulse responses directly in the time
+/// domain, and longer impulse responses with partitioned FFT convolution.
+struct Convolver {
+    fft: FFTConvolver<f32>,
+    /// The impulse response used for direct convolution. This is
+    /// preallocated with the direct capacity.
+    direct_response: Vec<f32>,
+    /// A ring buffer of past input frames used for direct convolution.
+    direct_history: Vec<f32>,
+    direct_history_pos: usize,
+    use_direct: bool,
+}
+
+impl Convolver {
+    fn new(fft: FFTConvolver<f32>, direct_capacity: usize) -> Self {
+        Self {
+            fft,
+            direct_response: Vec::with_capacity(direct_capacity),
+            direct_history: vec![0.0; direct_capacity],
+            direct_history_pos: 0,
+            use_direct: false,
+        }
+    }
+
+    fn set_response(&mut self, response: &[f32]) {
+        // An empty impulse response is always handled by the direct path,
+        // which outputs silence for it.
+        let use_direct = response.is_empty() || response.len() <= self.direct_history.len();
+
+        if use_direct {
+            // This does not allocate since the capacity is large enough.
+            self.direct_response.clear();
+            self.direct_response.extend_from_slice(response);
+        } else {
+            self.fft.set_response(response).unwrap();
+        }
+
+        if use_direct != self.use_direct {
+            // Don't let the state of the previous method leak through when
+            // switching back to it later.
+            self.reset();
+            self.use_direct = use_direct;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.fft.reset();
+        self.direct_history.fill(0.0);
+        self.direct_history_pos = 0;
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        if !self.use_direct {
+            self.fft.process(input, output).unwrap();
+            return;
+        }
+
+        if self.direct_response.is_empty() || self.direct_history.is_empty() {
+            output.fill(0.0);
+            return;
+        }
+
+        let history_len = self.direct_history.len();
+
+        for (&in_s, out_s) in input.iter().zip(output.iter_mut()) {
+            self.direct_history[self.direct_history_pos] = in_s;
+
+            // The history is read backwards from the newest frame, wrapping
+            // around to the end of the ring buffer.
+            let (newer, older) = self.direct_history.split_at(self.direct_history_pos + 1);
+            let num_newer = newer.len().min(self.direct_response.len());
+
+            let mut sum = 0.0;
+            for (&h, &r) in newer
+                .iter()
+                .rev()
+                .zip(self.direct_response[..num_newer].iter())
+            {
+                sum += h * r;
+            }
+            for (&h, &r) in older
+                .iter()
+                .rev()
+                .zip(self.direct_response[num_newer..].iter())
+            {
+                sum += h * r;
+            }
+
+            *out_s = sum;
+
+            self.direct_history_pos += 1;
+            if self.direct_history_pos == history_len {
+                self.direct_history_pos = 0;
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ImpulseTooLongError {
     pub got_len_seconds: f64,
@@ -415,3 +892,47 @@ impl core::fmt::Display for ImpulseTooLongError {
         )
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrueStereoChannelsError {
+    pub channels: u32,
+}
+
+impl core::error::Error for TrueStereoChannelsError {}
+
+impl core::fmt::Display for TrueStereoChannelsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "True stereo convolution requires 2 channels, got {}. Please set ConvolutionNodeConfig::channels to NonZeroChannelCount::STEREO",
+            self.channels
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_response_outputs_silence() {
+        let max_frames = 512;
+        let mut tmp_impulse = vec![0.0; max_frames];
+        tmp_impulse[0] = 1.0;
+
+        let mut fft = FFTConvolver::default();
+        fft.init(DEFAULT_PARTITION_SIZE, &tmp_impulse).unwrap();
+
+        let mut convolver = Convolver::new(
+            fft,
+            ConvolutionMode::Partitioned.direct_capacity(max_frames),
+        );
+        convolver.set_response(&[]);
+
+        let input = [1.0; 64];
+        let mut output = [1.0; 64];
+        convolver.process(&input, &mut output);
+
+        assert!(output.iter().all(|&s| s == 0.0));
+    }
+}
@@ -0,0 +1,222 @@
+use bevy_platform::sync::{Arc, atomic::Ordering};
+use core::num::NonZeroU32;
+use firewheel_core::{
+    StreamInfo,
+    atomic_float::AtomicF32,
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Patch},
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+};
+
+use firewheel_core::node::NodeError;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// The sum of squares of a channel below which it is considered to be silent
+/// for the purposes of calculating the correlation.
+const MIN_ENERGY: f32 = 1e-10;
+
+/// A node which measures the correlation between the left and right channels
+/// of a stereo signal, useful for checking mono compatibility. The input is
+/// passed through to the output unchanged.
+///
+/// A correlation of `1.0` means both channels are identical (mono), `0.0`
+/// means the channels are unrelated (wide stereo), and `-1.0` means the
+/// channels are out of phase and will cancel each other out when summed to
+/// mono.
+#[derive(Debug, Diff, Patch, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CorrelationMeterNode {
+    /// The size of the window used for measuring the correlation.
+    ///
+    /// By default this is set to `0.3` (300ms).
+    pub window_size_secs: f32,
+}
+
+impl Default for CorrelationMeterNode {
+    fn default() -> Self {
+        Self {
+            window_size_secs: 300.0 / 1_000.0,
+        }
+    }
+}
+
+/// The state of a [`CorrelationMeterNode`]. This contains the calculated
+/// correlation.
+#[derive(Clone)]
+pub struct CorrelationMeterState {
+    shared_state: Arc<SharedState>,
+}
+
+impl CorrelationMeterState {
+    fn new() -> Self {
+        Self {
+            shared_state: Arc::new(SharedState {
+                correlation: AtomicF32::new(0.0),
+            }),
+        }
+    }
+
+    /// Get the correlation coefficient of the most recent window in the range
+    /// `[-1.0, 1.0]`.
+    ///
+    /// If either channel is silent, or if the node is currently disabled,
+    /// then this will return `0.0`.
+    pub fn correlation(&self) -> f32 {
+        self.shared_state.correlation.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the correlation is negative, meaning that a
+    /// significant part of the signal will cancel out when summed to mono.
+    pub fn is_out_of_phase(&self) -> bool {
+        self.correlation() < 0.0
+    }
+}
+
+impl AudioNode for CorrelationMeterNode {
+    type Configuration = EmptyConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("correlation_meter")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::STEREO,
+                num_outputs: ChannelCount::STEREO,
+            })
+            .custom_state(CorrelationMeterState::new()))
+    }
+
+    fn construct_processor(
+        &self,
+        _config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let window_frames = window_frames(self.window_size_secs, cx.stream_info.sample_rate);
+
+        let custom_state = cx.custom_state::<CorrelationMeterState>().unwrap();
+
+        Ok(Processor {
+            params: *self,
+            shared_state: Arc::clone(&custom_state.shared_state),
+            sums: Sums::default(),
+            num_frames: 0,
+            window_frames,
+        })
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Sums {
+    lr: f32,
+    ll: f32,
+    rr: f32,
+}
+
+struct Processor {
+    params: CorrelationMeterNode,
+    shared_state: Arc<SharedState>,
+    sums: Sums,
+    num_frames: usize,
+    window_frames: usize,
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<CorrelationMeterNode>() {
+            match patch {
+                CorrelationMeterNodePatch::WindowSizeSecs(window_size_secs) => {
+                    let window_frames = window_frames(window_size_secs, info.sample_rate);
+
+                    if self.window_frames != window_frames {
+                        self.window_frames = window_frames;
+
+                        self.sums = Sums::default();
+                        self.num_frames = 0;
+                    }
+                }
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.shared_state.correlation.store(0.0, Ordering::Relaxed);
+
+        self.sums = Sums::default();
+        self.num_frames = 0;
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let input_is_silent = info.in_silence_mask.any_channel_silent(2);
+
+        let mut frames_processed = 0;
+        while frames_processed < info.frames {
+            let process_frames =
+                (info.frames - frames_processed).min(self.window_frames - self.num_frames);
+
+            // If either channel is silent then all of the sums involving that
+            // channel are zero, resulting in a correlation of `0.0`.
+            if !input_is_silent {
+                let range = frames_processed..frames_processed + process_frames;
+
+                for (&l, &r) in buffers.inputs[0][range.clone()]
+                    .iter()
+                    .zip(buffers.inputs[1][range].iter())
+                {
+                    self.sums.lr += l * r;
+                    self.sums.ll += l * l;
+                    self.sums.rr += r * r;
+                }
+            }
+
+            self.num_frames += process_frames;
+            frames_processed += process_frames;
+
+            if self.num_frames == self.window_frames {
+                let correlation = if self.sums.ll > MIN_ENERGY && self.sums.rr > MIN_ENERGY {
+                    (self.sums.lr / (self.sums.ll * self.sums.rr).sqrt()).clamp(-1.0, 1.0)
+                } else {
+                    0.0
+                };
+
+                self.shared_state
+                    .correlation
+                    .store(correlation, Ordering::Relaxed);
+
+                self.sums = Sums::default();
+                self.num_frames = 0;
+            }
+        }
+
+        // The input is passed through unchanged.
+        ProcessStatus::Bypass
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.window_frames = window_frames(self.params.window_size_secs, stream_info.sample_rate);
+
+        self.sums = Sums::default();
+        self.num_frames = 0;
+    }
+}
+
+fn window_frames(window_size_secs: f32, sample_rate: NonZeroU32) -> usize {
+    ((window_size_secs * sample_rate.get() as f32).round() as usize).max(1)
+}
+
+#[derive(Debug)]
+struct SharedState {
+    correlation: AtomicF32,
+}
@@ -2,10 +2,12 @@ use bevy_platform::prelude::Vec;
 use firewheel_core::node::NodeError;
 use firewheel_core::{
     channel_config::{ChannelConfig, NonZeroChannelCount},
+    diff::{Diff, Notify, Patch},
+    event::ProcEvents,
     mask::{MaskType, SilenceMask},
     node::{
         AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
-        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, ResettableNode,
     },
 };
 use smallvec::{SmallVec, smallvec};
@@ -36,11 +38,15 @@ impl Default for DelayCompNodeConfig {
 ///
 /// This can be used to avoid phasing issues (comb filtering) caused by
 /// parallel signal paths having differing latencies.
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
 #[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct DelayCompensationNode;
+pub struct DelayCompensationNode {
+    /// Reset the delay line, clearing any audio which is still buffered.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
+}
 
 impl AudioNode for DelayCompensationNode {
     type Configuration = DelayCompNodeConfig;
@@ -77,6 +83,12 @@ impl AudioNode for DelayCompensationNode {
     }
 }
 
+impl ResettableNode for DelayCompensationNode {
+    fn reset_notify(&mut self) -> &mut Notify<()> {
+        &mut self.reset
+    }
+}
+
 struct Processor {
     buffer: Vec<f32>,
     delay_frames: usize,
@@ -84,14 +96,30 @@ struct Processor {
     num_silent_frames_per_channel: SmallVec<[usize; 4]>,
 }
 
+impl Processor {
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+        self.ptr = 0;
+        self.num_silent_frames_per_channel.fill(self.delay_frames);
+    }
+}
+
 impl AudioNodeProcessor for Processor {
+    fn tail_frames(&self) -> Option<u64> {
+        Some(self.delay_frames as u64)
+    }
+
+    fn events(&mut self, _info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<DelayCompensationNode>() {
+            match patch {
+                DelayCompensationNodePatch::Reset(_) => self.clear(),
+            }
+        }
+    }
+
     fn bypassed(&mut self, bypassed: bool) {
         if !bypassed {
-            self.buffer.fill(0.0);
-            self.ptr = 0;
-            for ch in self.num_silent_frames_per_channel.iter_mut() {
-                *ch = self.buffer.len();
-            }
+            self.clear();
         }
     }
 
@@ -184,7 +212,6 @@ impl AudioNodeProcessor for Processor {
         _stream_info: &firewheel_core::StreamInfo,
         _context: &mut ProcStreamCtx,
     ) {
-        self.buffer.fill(0.0);
-        self.num_silent_frames_per_channel.fill(self.delay_frames);
+        self.clear();
     }
 }
@@ -0,0 +1,197 @@
+//! A delay-compensated dry/wet mix shared by tail effect nodes.
+
+use core::num::NonZeroU32;
+
+use bevy_platform::prelude::Vec;
+use firewheel_core::{
+    dsp::{fade::FadeCurve, mix::Mix},
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+/// Mixes the dry input of an effect into its wet output.
+///
+/// The dry signal is delayed by the latency of the wet signal, so that the two
+/// signals are time-aligned and do not comb filter when summed.
+pub(crate) struct DryWetMix {
+    dry_gain: SmoothedParam,
+    wet_gain: SmoothedParam,
+    /// The delay line of each channel, stored one after the other.
+    delay_buffer: Vec<f32>,
+    latency_frames: usize,
+    ptr: usize,
+}
+
+impl DryWetMix {
+    pub fn new(
+        mix: Mix,
+        num_channels: usize,
+        latency_frames: usize,
+        config: SmootherConfig,
+        sample_rate: NonZeroU32,
+    ) -> Self {
+        let (dry_gain, wet_gain) = mix.compute_gains(FadeCurve::EqualPower3dB);
+
+        let mut delay_buffer = Vec::new();
+        delay_buffer.reserve_exact(num_channels * latency_frames);
+        delay_buffer.resize(num_channels * latency_frames, 0.0);
+
+        Self {
+            dry_gain: SmoothedParam::new(dry_gain, config, sample_rate),
+            wet_gain: SmoothedParam::new(wet_gain, config, sample_rate),
+            delay_buffer,
+            latency_frames,
+            ptr: 0,
+        }
+    }
+
+    pub fn set_mix(&mut self, mix: Mix) {
+        let (dry_gain, wet_gain) = mix.compute_gains(FadeCurve::EqualPower3dB);
+
+        self.dry_gain.set_value(dry_gain);
+        self.wet_gain.set_value(wet_gain);
+    }
+
+    pub fn set_smooth_seconds(&mut self, seconds: f32, sample_rate: NonZeroU32) {
+        self.dry_gain.set_smooth_seconds(seconds, sample_rate);
+        self.wet_gain.set_smooth_seconds(seconds, sample_rate);
+    }
+
+    pub fn update_sample_rate(&mut self, sample_rate: NonZeroU32) {
+        self.dry_gain.update_sample_rate(sample_rate);
+        self.wet_gain.update_sample_rate(sample_rate);
+    }
+
+    /// Returns `true` if the output only contains the wet signal.
+    pub fn is_fully_wet(&self) -> bool {
+        !self.dry_gain.is_smoothing()
+            && !self.wet_gain.is_smoothing()
+            && self.dry_gain.target_value() <= 0.00001
+            && self.wet_gain.target_value() >= 0.99999
+    }
+
+    /// Clear the delayed dry signal and settle the mix.
+    pub fn reset(&mut self) {
+        self.dry_gain.reset_to_target();
+        self.wet_gain.reset_to_target();
+        self.delay_buffer.fill(0.0);
+        self.ptr = 0;
+    }
+
+    /// Mix the dry `inputs` into the wet signal in `outputs`.
+    ///
+    /// This should be called every block so that the delayed dry signal stays
+    /// continuous. If `mono_input` is `true`, then the first input channel is
+    /// used as the dry signal of every channel.
+    ///
+    /// Returns `true` if any of the dry signal was mixed into the outputs.
+    pub fn process(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        frames: usize,
+        mono_input: bool,
+        gain_buffers: [&mut [f32]; 2],
+    ) -> bool {
+        let fully_wet = self.is_fully_wet();
+
+        if fully_wet && self.latency_frames == 0 {
+            return false;
+        }
+
+        let smoothing = self.dry_gain.is_smoothing() || self.wet_gain.is_smoothing();
+        let [dry_gains, wet_gains] = gain_buffers;
+
+        if smoothing {
+            self.dry_gain.process_into_buffer(&mut dry_gains[..frames]);
+            self.wet_gain.process_into_buffer(&mut wet_gains[..frames]);
+        }
+
+        let dry_target = self.dry_gain.target_value();
+        let wet_target = self.wet_gain.target_value();
+
+        for (ch_i, output) in outputs.iter_mut().enumerate() {
+            let input = if mono_input { inputs[0] } else { inputs[ch_i] };
+            let mut ptr = self.ptr;
+
+            for (i, (out_s, &in_s)) in output[..frames]
+                .iter_mut()
+                .zip(input[..frames].iter())
+                .enumerate()
+            {
+                let dry_s = if self.latency_frames == 0 {
+                    in_s
+                } else {
+                    let delay_i = ch_i * self.latency_frames + ptr;
+                    let dry_s = self.delay_buffer[delay_i];
+                    self.delay_buffer[delay_i] = in_s;
+
+                    ptr += 1;
+                    if ptr == self.latency_frames {
+                        ptr = 0;
+                    }
+
+                    dry_s
+                };
+
+                if fully_wet {
+                    continue;
+                }
+
+                *out_s = if smoothing {
+                    *out_s * wet_gains[i] + dry_s * dry_gains[i]
+                } else {
+                    *out_s * wet_target + dry_s * dry_target
+                };
+            }
+        }
+
+        if self.latency_frames > 0 {
+            self.ptr = (self.ptr + frames) % self.latency_frames;
+        }
+
+        if smoothing {
+            self.dry_gain.settle();
+            self.wet_gain.settle();
+        }
+
+        !fully_wet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dry_signal_is_delayed_by_latency() {
+        let mut dry_wet = DryWetMix::new(
+            Mix::FULLY_DRY,
+            1,
+            3,
+            SmootherConfig::default(),
+            NonZeroU32::new(48_000).unwrap(),
+        );
+
+        let input = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let mut gains_0 = [0.0; 2];
+        let mut gains_1 = [0.0; 2];
+        let mut collected = Vec::new();
+
+        // The delay line must carry over between blocks.
+        for block in input.chunks(2) {
+            let mut output = [0.0; 2];
+
+            assert!(dry_wet.process(
+                &[block],
+                &mut [&mut output[..]],
+                2,
+                false,
+                [&mut gains_0, &mut gains_1],
+            ));
+
+            collected.extend_from_slice(&output);
+        }
+
+        assert_eq!(collected, [0.0, 0.0, 0.0, 1.0, 2.0, 3.0]);
+    }
+}
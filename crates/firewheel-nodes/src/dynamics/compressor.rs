@@ -0,0 +1,371 @@
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    StreamInfo,
+    diff::{Diff, Patch},
+    dsp::{
+        filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+        volume::{Volume, amp_to_db, db_to_amp},
+    },
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+use super::{DynamicsNodeConfig, peak_at, time_coeff};
+
+/// A feed-forward compressor with linked channels and an optional sidechain
+/// input
+///
+/// See [`DynamicsNodeConfig::use_sidechain`] for how to use the sidechain.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressorNode {
+    /// The level in decibels above which the signal is compressed.
+    ///
+    /// By default this is set to `-20.0`.
+    pub threshold_db: f32,
+    /// The amount of compression above the threshold, where `4.0` means
+    /// that the output rises by 1 dB for every 4 dB the input rises above
+    /// the threshold. Values are clamped to be `>= 1.0`.
+    ///
+    /// By default this is set to `4.0`.
+    pub ratio: f32,
+    /// The width of the soft knee around the threshold in decibels, where
+    /// `0.0` is a hard knee.
+    ///
+    /// By default this is set to `6.0`.
+    pub knee_db: f32,
+    /// The time in milliseconds it takes the compressor to react to a rise
+    /// in level.
+    ///
+    /// By default this is set to `10.0`.
+    pub attack_ms: f32,
+    /// The time in milliseconds it takes the compressor to recover after
+    /// the level falls.
+    ///
+    /// By default this is set to `100.0`.
+    pub release_ms: f32,
+    /// The gain applied to the output after compression.
+    ///
+    /// By default this is set to [`Volume::UNITY_GAIN`].
+    pub makeup_gain: Volume,
+
+    /// The time in seconds of the internal smoothing filter for the
+    /// threshold and makeup gain.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for CompressorNode {
+    fn default() -> Self {
+        Self {
+            threshold_db: -20.0,
+            ratio: 4.0,
+            knee_db: 6.0,
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            makeup_gain: Volume::UNITY_GAIN,
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl CompressorNode {
+    /// Compute the gain reduction in decibels (a value `<= 0.0`) for the given
+    /// input level in decibels.
+    fn gain_reduction_db(level_db: f32, threshold_db: f32, slope: f32, knee_db: f32) -> f32 {
+        let over_db = level_db - threshold_db;
+
+        if 2.0 * over_db <= -knee_db {
+            0.0
+        } else if 2.0 * over_db.abs() < knee_db {
+            let x = over_db + knee_db * 0.5;
+            slope * x * x / (2.0 * knee_db)
+        } else {
+            slope * over_db
+        }
+    }
+}
+
+impl AudioNode for CompressorNode {
+    type Configuration = DynamicsNodeConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("compressor")
+            .channel_config(config.channel_config()?))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let smoother_config = SmootherConfig {
+            smooth_seconds: self.smooth_seconds,
+            ..Default::default()
+        };
+
+        let mut processor = Processor {
+            params: *self,
+            threshold_db: SmoothedParam::new(
+                self.threshold_db,
+                smoother_config,
+                cx.stream_info.sample_rate,
+            ),
+            makeup_gain: SmoothedParam::new(
+                self.makeup_gain.amp(),
+                smoother_config,
+                cx.stream_info.sample_rate,
+            ),
+            envelope: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            sample_rate: cx.stream_info.sample_rate.get() as f32,
+            channels: config.channels.get().get() as usize,
+            use_sidechain: config.use_sidechain,
+        };
+        processor.update_coeffs();
+
+        Ok(processor)
+    }
+}
+
+struct Processor {
+    params: CompressorNode,
+    threshold_db: SmoothedParam,
+    makeup_gain: SmoothedParam,
+
+    envelope: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    sample_rate: f32,
+
+    channels: usize,
+    use_sidechain: bool,
+}
+
+impl Processor {
+    fn update_coeffs(&mut self) {
+        self.attack_coeff = time_coeff(self.params.attack_ms, self.sample_rate);
+        self.release_coeff = time_coeff(self.params.release_ms, self.sample_rate);
+    }
+
+    /// Process the given number of frames, reading the detection signal from
+    /// the sidechain ports if enabled.
+    fn process_frames(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]], frames: usize) {
+        let detect = if self.use_sidechain {
+            &inputs[self.channels..self.channels * 2]
+        } else {
+            &inputs[..self.channels]
+        };
+
+        let slope = self.params.ratio.max(1.0).recip() - 1.0;
+        let knee_db = self.params.knee_db.max(0.0);
+
+        for i in 0..frames {
+            let peak = peak_at(detect, i);
+
+            let coeff = if peak > self.envelope {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.envelope = peak + coeff * (self.envelope - peak);
+
+            let threshold_db = self.threshold_db.next_smoothed();
+            let makeup_gain = self.makeup_gain.next_smoothed();
+
+            let gain = if self.envelope > 0.0 {
+                db_to_amp(CompressorNode::gain_reduction_db(
+                    amp_to_db(self.envelope),
+                    threshold_db,
+                    slope,
+                    knee_db,
+                ))
+            } else {
+                1.0
+            } * makeup_gain;
+
+            for (in_ch, out_ch) in inputs[..self.channels].iter().zip(outputs.iter_mut()) {
+                out_ch[i] = in_ch[i] * gain;
+            }
+        }
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<CompressorNode>() {
+            match patch {
+                CompressorNodePatch::ThresholdDb(db) => {
+                    self.threshold_db.set_value(db);
+                }
+                CompressorNodePatch::MakeupGain(gain) => {
+                    self.makeup_gain.set_value(gain.amp());
+                }
+                CompressorNodePatch::SmoothSeconds(seconds) => {
+                    self.threshold_db
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                    self.makeup_gain
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                }
+                _ => {}
+            }
+
+            self.params.apply(patch);
+        }
+
+        self.update_coeffs();
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.envelope = 0.0;
+        self.threshold_db.reset_to_target();
+        self.makeup_gain.reset_to_target();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        if info.in_silence_mask.all_channels_silent(self.channels) {
+            // The output is silent regardless of the detection signal.
+            self.envelope = 0.0;
+            self.threshold_db.reset_to_target();
+            self.makeup_gain.reset_to_target();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        self.process_frames(buffers.inputs, buffers.outputs, info.frames);
+
+        self.threshold_db.settle();
+        self.makeup_gain.settle();
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.sample_rate = stream_info.sample_rate.get() as f32;
+        self.threshold_db
+            .update_sample_rate(stream_info.sample_rate);
+        self.makeup_gain.update_sample_rate(stream_info.sample_rate);
+        self.update_coeffs();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::num::NonZeroU32;
+
+    use firewheel_core::channel_config::{MAX_CHANNELS, NonZeroChannelCount};
+
+    use super::*;
+    use crate::dynamics::TooManySidechainChannels;
+
+    fn processor(params: CompressorNode, use_sidechain: bool) -> Processor {
+        let sample_rate = NonZeroU32::new(48_000).unwrap();
+
+        let mut processor = Processor {
+            params,
+            threshold_db: SmoothedParam::new(
+                params.threshold_db,
+                SmootherConfig::default(),
+                sample_rate,
+            ),
+            makeup_gain: SmoothedParam::new(
+                params.makeup_gain.amp(),
+                SmootherConfig::default(),
+                sample_rate,
+            ),
+            envelope: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            sample_rate: sample_rate.get() as f32,
+            channels: 1,
+            use_sidechain,
+        };
+        processor.update_coeffs();
+
+        processor
+    }
+
+    /// A compressor which reacts instantly with a hard knee.
+    fn instant() -> CompressorNode {
+        CompressorNode {
+            threshold_db: -20.0,
+            ratio: 4.0,
+            knee_db: 0.0,
+            attack_ms: 0.0,
+            release_ms: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn gain_reduction() {
+        let mut processor = processor(instant(), false);
+        let mut out = [0.0; 64];
+
+        // 20 dB above the threshold is reduced to 5 dB above it.
+        processor.process_frames(&[&[1.0; 64]], &mut [&mut out], 64);
+        assert!((amp_to_db(out[63]) - -15.0).abs() < 1.0e-3, "{}", out[63]);
+
+        // Signals below the threshold are left untouched.
+        processor.process_frames(&[&[0.01; 64]], &mut [&mut out], 64);
+        assert!((out[63] - 0.01).abs() < 1.0e-6, "{}", out[63]);
+    }
+
+    #[test]
+    fn sidechain_drives_detector() {
+        let mut processor = processor(instant(), true);
+        let mut out = [0.0; 64];
+
+        // A quiet main input is compressed by a loud sidechain.
+        processor.process_frames(&[&[0.01; 64], &[1.0; 64]], &mut [&mut out], 64);
+        assert!(
+            (amp_to_db(out[63]) - (-40.0 - 15.0)).abs() < 1.0e-3,
+            "{}",
+            out[63]
+        );
+
+        // A loud main input is untouched while the sidechain is silent.
+        processor.process_frames(&[&[1.0; 64], &[0.0; 64]], &mut [&mut out], 64);
+        assert_eq!(out[63], 1.0);
+    }
+
+    #[test]
+    fn too_many_sidechain_channels() {
+        let max = DynamicsNodeConfig {
+            channels: NonZeroChannelCount::new(MAX_CHANNELS as u32 / 2).unwrap(),
+            use_sidechain: true,
+        };
+        assert_eq!(
+            max.channel_config().unwrap().num_inputs.get() as usize,
+            MAX_CHANNELS
+        );
+
+        let too_many = DynamicsNodeConfig {
+            channels: NonZeroChannelCount::new(MAX_CHANNELS as u32 / 2 + 1).unwrap(),
+            use_sidechain: true,
+        };
+        assert_eq!(
+            too_many.channel_config(),
+            Err(TooManySidechainChannels {
+                channels: MAX_CHANNELS / 2 + 1
+            })
+        );
+        assert!(CompressorNode::default().info(&too_many).is_err());
+    }
+}
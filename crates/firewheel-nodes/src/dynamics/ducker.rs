@@ -0,0 +1,275 @@
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    StreamInfo,
+    diff::{Diff, Patch},
+    dsp::{filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS, volume::db_to_amp},
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+use super::{DynamicsNodeConfig, peak_at, time_coeff};
+
+/// The amount of ducking below which the ducker is considered fully released.
+const RELEASED_EPSILON: f32 = 0.00001;
+
+/// A node which lowers the volume of its input while a trigger signal is
+/// present, i.e. to duck music under dialog
+///
+/// The trigger signal is connected to the sidechain ports (see
+/// [`DynamicsNodeConfig::use_sidechain`]). If the sidechain is disabled, then
+/// the node is triggered by its own input instead.
+///
+/// Unlike a sidechained [`CompressorNode`](super::compressor::CompressorNode),
+/// the amount of gain reduction does not depend on how loud the trigger is,
+/// only on whether it is above the threshold.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DuckerNode {
+    /// The level in decibels of the trigger signal above which the input
+    /// is ducked.
+    ///
+    /// By default this is set to `-40.0`.
+    pub threshold_db: f32,
+    /// The gain in decibels applied to the input while it is ducked.
+    ///
+    /// By default this is set to `-12.0`.
+    pub depth_db: f32,
+    /// The time in milliseconds it takes to duck the input once the trigger
+    /// rises above the threshold.
+    ///
+    /// By default this is set to `50.0`.
+    pub attack_ms: f32,
+    /// The time in milliseconds the input stays ducked after the trigger
+    /// falls below the threshold. This prevents the volume from pumping in
+    /// short pauses, such as between words.
+    ///
+    /// By default this is set to `150.0`.
+    pub hold_ms: f32,
+    /// The time in milliseconds it takes the input to return to full volume.
+    ///
+    /// By default this is set to `400.0`.
+    pub release_ms: f32,
+
+    /// The time in seconds of the internal smoothing filter for the
+    /// threshold.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for DuckerNode {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            depth_db: -12.0,
+            attack_ms: 50.0,
+            hold_ms: 150.0,
+            release_ms: 400.0,
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl AudioNode for DuckerNode {
+    type Configuration = DynamicsNodeConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("ducker")
+            .channel_config(config.channel_config()?))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let mut processor = Processor {
+            params: *self,
+            threshold: SmoothedParam::new(
+                db_to_amp(self.threshold_db),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                cx.stream_info.sample_rate,
+            ),
+            duck_amount: 0.0,
+            hold_frames_left: 0,
+            depth: 1.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            hold_frames: 0,
+            sample_rate: cx.stream_info.sample_rate.get() as f32,
+            channels: config.channels.get().get() as usize,
+            use_sidechain: config.use_sidechain,
+        };
+        processor.update_coeffs();
+
+        Ok(processor)
+    }
+}
+
+struct Processor {
+    params: DuckerNode,
+    threshold: SmoothedParam,
+
+    /// How much the input is ducked in the range `[0.0, 1.0]`, where `0.0`
+    /// is not ducked at all.
+    duck_amount: f32,
+    hold_frames_left: u32,
+
+    depth: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    hold_frames: u32,
+    sample_rate: f32,
+
+    channels: usize,
+    use_sidechain: bool,
+}
+
+impl Processor {
+    fn update_coeffs(&mut self) {
+        self.depth = db_to_amp(self.params.depth_db.min(0.0));
+        self.attack_coeff = time_coeff(self.params.attack_ms, self.sample_rate);
+        self.release_coeff = time_coeff(self.params.release_ms, self.sample_rate);
+        self.hold_frames = (self.params.hold_ms.max(0.0) * 0.001 * self.sample_rate) as u32;
+    }
+
+    fn reset(&mut self) {
+        self.duck_amount = 0.0;
+        self.hold_frames_left = 0;
+        self.threshold.reset_to_target();
+    }
+
+    fn is_released(&self) -> bool {
+        self.duck_amount == 0.0 && self.hold_frames_left == 0
+    }
+
+    /// Advance the detector by one frame, returning the gain to apply to the
+    /// input.
+    #[inline(always)]
+    fn next_gain(&mut self, detect: &[&[f32]], frame: usize) -> f32 {
+        let threshold = self.threshold.next_smoothed();
+
+        let target = if peak_at(detect, frame) > threshold {
+            self.hold_frames_left = self.hold_frames;
+            1.0
+        } else if self.hold_frames_left > 0 {
+            self.hold_frames_left -= 1;
+            1.0
+        } else {
+            0.0
+        };
+
+        let coeff = if target > self.duck_amount {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.duck_amount = target + coeff * (self.duck_amount - target);
+
+        if target == 0.0 && self.duck_amount < RELEASED_EPSILON {
+            self.duck_amount = 0.0;
+        }
+
+        1.0 - (1.0 - self.depth) * self.duck_amount
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<DuckerNode>() {
+            match patch {
+                DuckerNodePatch::ThresholdDb(db) => {
+                    self.threshold.set_value(db_to_amp(db));
+                }
+                DuckerNodePatch::SmoothSeconds(seconds) => {
+                    self.threshold.set_smooth_seconds(seconds, info.sample_rate);
+                }
+                _ => {}
+            }
+
+            self.params.apply(patch);
+        }
+
+        self.update_coeffs();
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.reset();
+    }
+
+    fn reset_state(&mut self) {
+        self.reset();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let detect = if self.use_sidechain {
+            &buffers.inputs[self.channels..self.channels * 2]
+        } else {
+            &buffers.inputs[..self.channels]
+        };
+
+        let detect_is_silent = if self.use_sidechain {
+            info.in_silence_mask
+                .range_silent(self.channels..self.channels * 2)
+        } else {
+            info.in_silence_mask.all_channels_silent(self.channels)
+        };
+
+        if detect_is_silent && self.is_released() {
+            // There is nothing to duck.
+            self.threshold.reset_to_target();
+
+            return ProcessStatus::Bypass;
+        }
+
+        if info.in_silence_mask.all_channels_silent(self.channels) {
+            // The output is silent, but keep following the trigger so the
+            // input is already ducked once it starts.
+            for i in 0..info.frames {
+                self.next_gain(detect, i);
+            }
+
+            self.threshold.settle();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        for i in 0..info.frames {
+            let gain = self.next_gain(detect, i);
+
+            for (in_ch, out_ch) in buffers.inputs[..self.channels]
+                .iter()
+                .zip(buffers.outputs.iter_mut())
+            {
+                out_ch[i] = in_ch[i] * gain;
+            }
+        }
+
+        self.threshold.settle();
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.sample_rate = stream_info.sample_rate.get() as f32;
+        self.threshold.update_sample_rate(stream_info.sample_rate);
+        self.update_coeffs();
+    }
+}
@@ -0,0 +1,342 @@
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    StreamInfo,
+    diff::{Diff, Patch},
+    dsp::{filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS, volume::db_to_amp},
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+use super::{DynamicsNodeConfig, peak_at, time_coeff};
+
+/// A noise gate with linked channels and an optional sidechain input
+///
+/// The gate opens when the level of the detection signal rises above the
+/// threshold, and closes again once the level has stayed below the threshold
+/// for the hold time.
+///
+/// See [`DynamicsNodeConfig::use_sidechain`] for how to use the sidechain.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GateNode {
+    /// The level in decibels above which the gate opens.
+    ///
+    /// By default this is set to `-40.0`.
+    pub threshold_db: f32,
+    /// The time in milliseconds it takes the gate to open.
+    ///
+    /// By default this is set to `1.0`.
+    pub attack_ms: f32,
+    /// The time in milliseconds the gate stays open after the level falls
+    /// below the threshold.
+    ///
+    /// By default this is set to `50.0`.
+    pub hold_ms: f32,
+    /// The time in milliseconds it takes the gate to close.
+    ///
+    /// By default this is set to `100.0`.
+    pub release_ms: f32,
+    /// The gain in decibels applied to the signal while the gate is closed.
+    ///
+    /// By default this is set to `-80.0`.
+    pub range_db: f32,
+
+    /// The time in seconds of the internal smoothing filter for the
+    /// threshold.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for GateNode {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            attack_ms: 1.0,
+            hold_ms: 50.0,
+            release_ms: 100.0,
+            range_db: -80.0,
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl AudioNode for GateNode {
+    type Configuration = DynamicsNodeConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("gate")
+            .channel_config(config.channel_config()?))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let mut processor = Processor {
+            params: *self,
+            threshold: SmoothedParam::new(
+                db_to_amp(self.threshold_db),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                cx.stream_info.sample_rate,
+            ),
+            gate_gain: 0.0,
+            hold_frames_left: 0,
+            floor: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            hold_frames: 0,
+            sample_rate: cx.stream_info.sample_rate.get() as f32,
+            channels: config.channels.get().get() as usize,
+            use_sidechain: config.use_sidechain,
+        };
+        processor.update_coeffs();
+
+        Ok(processor)
+    }
+}
+
+struct Processor {
+    params: GateNode,
+    threshold: SmoothedParam,
+
+    /// The gain of the gate in the range `[0.0, 1.0]`, where `0.0` is fully
+    /// closed.
+    gate_gain: f32,
+    hold_frames_left: u32,
+
+    floor: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    hold_frames: u32,
+    sample_rate: f32,
+
+    channels: usize,
+    use_sidechain: bool,
+}
+
+impl Processor {
+    fn update_coeffs(&mut self) {
+        self.floor = db_to_amp(self.params.range_db.min(0.0));
+        self.attack_coeff = time_coeff(self.params.attack_ms, self.sample_rate);
+        self.release_coeff = time_coeff(self.params.release_ms, self.sample_rate);
+        self.hold_frames = (self.params.hold_ms.max(0.0) * 0.001 * self.sample_rate) as u32;
+    }
+
+    fn reset(&mut self) {
+        self.gate_gain = 0.0;
+        self.hold_frames_left = 0;
+        self.threshold.reset_to_target();
+    }
+
+    /// Process the given number of frames, reading the detection signal from
+    /// the sidechain ports if enabled.
+    fn process_frames(&mut self, inputs: &[&[f32]], outputs: &mut [&mut [f32]], frames: usize) {
+        let detect = if self.use_sidechain {
+            &inputs[self.channels..self.channels * 2]
+        } else {
+            &inputs[..self.channels]
+        };
+
+        for i in 0..frames {
+            let threshold = self.threshold.next_smoothed();
+
+            let target = if peak_at(detect, i) > threshold {
+                self.hold_frames_left = self.hold_frames;
+                1.0
+            } else if self.hold_frames_left > 0 {
+                self.hold_frames_left -= 1;
+                1.0
+            } else {
+                0.0
+            };
+
+            let coeff = if target > self.gate_gain {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.gate_gain = target + coeff * (self.gate_gain - target);
+
+            let gain = self.floor + (1.0 - self.floor) * self.gate_gain;
+
+            for (in_ch, out_ch) in inputs[..self.channels].iter().zip(outputs.iter_mut()) {
+                out_ch[i] = in_ch[i] * gain;
+            }
+        }
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<GateNode>() {
+            match patch {
+                GateNodePatch::ThresholdDb(db) => {
+                    self.threshold.set_value(db_to_amp(db));
+                }
+                GateNodePatch::SmoothSeconds(seconds) => {
+                    self.threshold.set_smooth_seconds(seconds, info.sample_rate);
+                }
+                _ => {}
+            }
+
+            self.params.apply(patch);
+        }
+
+        self.update_coeffs();
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.reset();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        if info.in_silence_mask.all_channels_silent(self.channels) {
+            // The output is silent regardless of the state of the gate.
+            self.reset();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        self.process_frames(buffers.inputs, buffers.outputs, info.frames);
+
+        self.threshold.settle();
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.sample_rate = stream_info.sample_rate.get() as f32;
+        self.threshold.update_sample_rate(stream_info.sample_rate);
+        self.update_coeffs();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::num::NonZeroU32;
+
+    use firewheel_core::{
+        channel_config::{MAX_CHANNELS, NonZeroChannelCount},
+        dsp::volume::amp_to_db,
+    };
+
+    use super::*;
+
+    fn processor(params: GateNode, use_sidechain: bool) -> Processor {
+        let sample_rate = NonZeroU32::new(48_000).unwrap();
+
+        let mut processor = Processor {
+            params,
+            threshold: SmoothedParam::new(
+                db_to_amp(params.threshold_db),
+                SmootherConfig::default(),
+                sample_rate,
+            ),
+            gate_gain: 0.0,
+            hold_frames_left: 0,
+            floor: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            hold_frames: 0,
+            sample_rate: sample_rate.get() as f32,
+            channels: 1,
+            use_sidechain,
+        };
+        processor.update_coeffs();
+
+        processor
+    }
+
+    /// A gate which opens and closes instantly.
+    fn instant() -> GateNode {
+        GateNode {
+            threshold_db: -40.0,
+            attack_ms: 0.0,
+            hold_ms: 0.0,
+            release_ms: 0.0,
+            range_db: -80.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn gain_reduction() {
+        let mut processor = processor(instant(), false);
+        let mut out = [0.0; 64];
+
+        // Signals above the threshold open the gate.
+        processor.process_frames(&[&[0.5; 64]], &mut [&mut out], 64);
+        assert!((out[63] - 0.5).abs() < 1.0e-6);
+
+        // Signals below the threshold are attenuated by the range.
+        processor.process_frames(&[&[0.001; 64]], &mut [&mut out], 64);
+        assert!((amp_to_db(out[63]) - (-60.0 - 80.0)).abs() < 1.0e-2);
+    }
+
+    #[test]
+    fn hold_keeps_gate_open() {
+        let mut processor = processor(
+            GateNode {
+                hold_ms: 1.0,
+                ..instant()
+            },
+            false,
+        );
+        let mut out = [0.0; 96];
+
+        processor.process_frames(&[&[0.5; 96]], &mut [&mut out], 1);
+
+        // One millisecond is 48 frames.
+        processor.process_frames(&[&[0.001; 96]], &mut [&mut out], 96);
+        assert!((out[47] - 0.001).abs() < 1.0e-6);
+        assert!(out[48] < 0.001 * 1.0e-3);
+    }
+
+    #[test]
+    fn sidechain_drives_detector() {
+        let mut processor = processor(instant(), true);
+        let mut out = [0.0; 64];
+
+        // A quiet main input passes while the sidechain is loud.
+        processor.process_frames(&[&[0.001; 64], &[0.5; 64]], &mut [&mut out], 64);
+        assert!((out[63] - 0.001).abs() < 1.0e-6);
+
+        // A loud main input is gated while the sidechain is silent.
+        processor.process_frames(&[&[0.5; 64], &[0.0; 64]], &mut [&mut out], 64);
+        assert!((amp_to_db(out[63]) - (amp_to_db(0.5) - 80.0)).abs() < 1.0e-2);
+    }
+
+    #[test]
+    fn too_many_sidechain_channels() {
+        let config = DynamicsNodeConfig {
+            channels: NonZeroChannelCount::new(MAX_CHANNELS as u32 / 2 + 1).unwrap(),
+            use_sidechain: true,
+        };
+        assert!(GateNode::default().info(&config).is_err());
+
+        let config = DynamicsNodeConfig {
+            use_sidechain: false,
+            ..config
+        };
+        assert!(GateNode::default().info(&config).is_ok());
+    }
+}
@@ -0,0 +1,97 @@
+//! Dynamics processors, such as compressors, gates, and duckers.
+
+pub mod compressor;
+pub mod ducker;
+pub mod gate;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::channel_config::{
+    ChannelConfig, ChannelCount, MAX_CHANNELS, NonZeroChannelCount,
+};
+
+/// The configuration of a [`CompressorNode`](compressor::CompressorNode),
+/// a [`GateNode`](gate::GateNode), or a [`DuckerNode`](ducker::DuckerNode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DynamicsNodeConfig {
+    /// The number of main input and output channels.
+    ///
+    /// By default this is set to [`NonZeroChannelCount::STEREO`].
+    pub channels: NonZeroChannelCount,
+    /// If `true`, then the node gets an additional set of `channels` input
+    /// ports after the main input ports. The signal on these sidechain ports
+    /// drives the envelope detector instead of the main input.
+    ///
+    /// For example, a stereo compressor with a sidechain has four input
+    /// ports, where ports `0` and `1` are the main input, and ports `2` and
+    /// `3` are the sidechain input. This can be used to duck music under
+    /// voice by connecting the voice to the sidechain ports.
+    ///
+    /// By default this is set to `false`.
+    pub use_sidechain: bool,
+}
+
+impl Default for DynamicsNodeConfig {
+    fn default() -> Self {
+        Self {
+            channels: NonZeroChannelCount::STEREO,
+            use_sidechain: false,
+        }
+    }
+}
+
+impl DynamicsNodeConfig {
+    pub(crate) fn channel_config(&self) -> Result<ChannelConfig, TooManySidechainChannels> {
+        let channels = self.channels.get().get();
+
+        let num_inputs = if self.use_sidechain {
+            if channels as usize * 2 > MAX_CHANNELS {
+                return Err(TooManySidechainChannels {
+                    channels: channels as usize,
+                });
+            }
+
+            channels * 2
+        } else {
+            channels
+        };
+
+        Ok(ChannelConfig {
+            num_inputs: ChannelCount::new(num_inputs).unwrap(),
+            num_outputs: self.channels.get(),
+        })
+    }
+}
+
+/// An error returned when the number of channels of a dynamics node is too
+/// large to also fit the sidechain input ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "A dynamics node with {channels} channels and a sidechain exceeds the maximum of {} input channels",
+    MAX_CHANNELS
+)]
+pub struct TooManySidechainChannels {
+    pub channels: usize,
+}
+
+/// Returns the coefficient of a one-pole filter which reaches ~63% of its
+/// target after the given time in milliseconds.
+fn time_coeff(ms: f32, sample_rate: f32) -> f32 {
+    if ms <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (ms * 0.001 * sample_rate)).exp()
+    }
+}
+
+/// The peak absolute value of the given channels at the given frame.
+#[inline(always)]
+fn peak_at(channels: &[&[f32]], frame: usize) -> f32 {
+    channels
+        .iter()
+        .fold(0.0, |peak: f32, ch| peak.max(ch[frame].abs()))
+}
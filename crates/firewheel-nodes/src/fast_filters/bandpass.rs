@@ -1,9 +1,10 @@
 use super::{MAX_HZ, MIN_HZ};
+use crate::per_channel::PerChannelParam;
 use firewheel_core::node::NodeError;
 use firewheel_core::{
     StreamInfo,
     channel_config::{ChannelConfig, ChannelCount},
-    diff::{Diff, Patch},
+    diff::{Diff, Notify, Patch},
     dsp::{
         coeff_update::{CoeffUpdateFactor, CoeffUpdateMask},
         filter::{
@@ -13,13 +14,14 @@ use firewheel_core::{
             },
             smoothing_filter::DEFAULT_SMOOTH_SECONDS,
         },
+        volume::DEFAULT_MIN_AMP,
     },
     event::ProcEvents,
     node::{
         AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
-        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, ResettableNode,
     },
-    param::smoother::{SmoothedParam, SmootherConfig},
+    param::smoother::SmootherConfig,
 };
 
 pub type FastBandpassMonoNode = FastBandpassNode<1>;
@@ -35,6 +37,16 @@ pub struct FastBandpassNode<const CHANNELS: usize = 2> {
     /// The cutoff frequency in hertz in the range `[20.0, 20480.0]`.
     pub cutoff_hz: f32,
 
+    /// Optional per-channel cutoff frequencies in hertz, which override
+    /// [`FastBandpassNode::cutoff_hz`] for their channel.
+    ///
+    /// By default every channel is `None`, so all channels share the same
+    /// cutoff frequency (stereo-linked). Set a channel to `Some` to filter
+    /// it independently (dual-mono), i.e. for a stereo filter sweep which
+    /// differs on each side.
+    #[cfg_attr(feature = "serde", serde(with = "crate::per_channel::serde_array"))]
+    pub channel_cutoff_hz: [Option<f32>; CHANNELS],
+
     /// The time in seconds of the internal smoothing filter.
     ///
     /// By default this is set to `0.023` (23ms). This value is chosen to be
@@ -54,14 +66,20 @@ pub struct FastBandpassNode<const CHANNELS: usize = 2> {
     ///
     /// By default this is set to `4`.
     pub coeff_update_factor: CoeffUpdateFactor,
+
+    /// Reset the filter, clearing its internal state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
 }
 
 impl<const CHANNELS: usize> Default for FastBandpassNode<CHANNELS> {
     fn default() -> Self {
         Self {
             cutoff_hz: 1_000.0,
+            channel_cutoff_hz: [None; CHANNELS],
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor::default(),
+            reset: Notify::new(()),
         }
     }
 }
@@ -73,8 +91,10 @@ impl<const CHANNELS: usize> FastBandpassNode<CHANNELS> {
     pub const fn from_cutoff_hz(cutoff_hz: f32) -> Self {
         Self {
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor::DEFAULT,
+            reset: Notify::dangling(()),
         }
     }
 }
@@ -100,45 +120,54 @@ impl<const CHANNELS: usize> AudioNode for FastBandpassNode<CHANNELS> {
     ) -> Result<impl AudioNodeProcessor, NodeError> {
         let sample_rate_recip = cx.stream_info.sample_rate_recip as f32;
 
-        let cutoff_hz = self.cutoff_hz.clamp(MIN_HZ, MAX_HZ);
+        let cutoff_hz = PerChannelParam::new(
+            self.cutoff_hz,
+            self.channel_cutoff_hz,
+            MIN_HZ,
+            MAX_HZ,
+            SmootherConfig {
+                smooth_seconds: self.smooth_seconds,
+                ..Default::default()
+            },
+            cx.stream_info.sample_rate,
+        );
+
+        let (lpf_coeff, hpf_coeff) = calc_coeffs(cutoff_hz.target_values(), sample_rate_recip);
 
         Ok(Processor {
             lpf: OnePoleIirLPFSimd::default(),
-            lpf_coeff: OnePoleIirLPFCoeffSimd::<CHANNELS>::splat(OnePoleIirLPFCoeff::new(
-                cutoff_hz,
-                sample_rate_recip,
-            )),
+            lpf_coeff,
             hpf: OnePoleIirHPFSimd::default(),
-            hpf_coeff: OnePoleIirHPFCoeffSimd::<CHANNELS>::splat(OnePoleIirHPFCoeff::new(
-                cutoff_hz,
-                sample_rate_recip,
-            )),
-            cutoff_hz: SmoothedParam::new(
-                cutoff_hz,
-                SmootherConfig {
-                    smooth_seconds: self.smooth_seconds,
-                    ..Default::default()
-                },
-                cx.stream_info.sample_rate,
-            ),
+            hpf_coeff,
+            cutoff_hz,
             coeff_update_mask: self.coeff_update_factor.mask(),
             cutoff_changed: false,
         })
     }
 }
 
+impl<const CHANNELS: usize> ResettableNode for FastBandpassNode<CHANNELS> {
+    fn reset_notify(&mut self) -> &mut Notify<()> {
+        &mut self.reset
+    }
+}
+
 #[cold]
 #[inline(never)]
 fn calc_coeffs<const CHANNELS: usize>(
-    cutoff_hz: f32,
+    cutoff_hz: [f32; CHANNELS],
     sample_rate_recip: f32,
 ) -> (
     OnePoleIirLPFCoeffSimd<CHANNELS>,
     OnePoleIirHPFCoeffSimd<CHANNELS>,
 ) {
     (
-        OnePoleIirLPFCoeffSimd::splat(OnePoleIirLPFCoeff::new(cutoff_hz, sample_rate_recip)),
-        OnePoleIirHPFCoeffSimd::splat(OnePoleIirHPFCoeff::new(cutoff_hz, sample_rate_recip)),
+        OnePoleIirLPFCoeffSimd::new(
+            &cutoff_hz.map(|hz| OnePoleIirLPFCoeff::new(hz, sample_rate_recip)),
+        ),
+        OnePoleIirHPFCoeffSimd::new(
+            &cutoff_hz.map(|hz| OnePoleIirHPFCoeff::new(hz, sample_rate_recip)),
+        ),
     )
 }
 
@@ -148,7 +177,7 @@ struct Processor<const CHANNELS: usize> {
     lpf_coeff: OnePoleIirLPFCoeffSimd<CHANNELS>,
     hpf_coeff: OnePoleIirHPFCoeffSimd<CHANNELS>,
 
-    cutoff_hz: SmoothedParam,
+    cutoff_hz: PerChannelParam<CHANNELS>,
     coeff_update_mask: CoeffUpdateMask,
     cutoff_changed: bool,
 }
@@ -167,7 +196,11 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             match patch {
                 FastBandpassNodePatch::CutoffHz(cutoff) => {
                     self.cutoff_changed = true;
-                    self.cutoff_hz.set_value(cutoff.clamp(MIN_HZ, MAX_HZ));
+                    self.cutoff_hz.set_value(cutoff);
+                }
+                FastBandpassNodePatch::ChannelCutoffHz((ch, cutoff)) => {
+                    self.cutoff_changed = true;
+                    self.cutoff_hz.set_channel_value(ch, cutoff);
                 }
                 FastBandpassNodePatch::SmoothSeconds(seconds) => {
                     self.cutoff_hz.set_smooth_seconds(seconds, info.sample_rate);
@@ -175,6 +208,10 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
                 FastBandpassNodePatch::CoeffUpdateFactor(f) => {
                     self.coeff_update_mask = f.mask();
                 }
+                FastBandpassNodePatch::Reset(_) => {
+                    self.lpf.reset();
+                    self.hpf.reset();
+                }
             }
         }
     }
@@ -183,13 +220,17 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
         self.reset();
     }
 
+    fn reset_state(&mut self) {
+        self.reset();
+    }
+
     fn process(
         &mut self,
         info: &ProcInfo,
         buffers: ProcBuffers,
         _extra: &mut ProcExtra,
     ) -> ProcessStatus {
-        if info.in_silence_mask.all_channels_silent(CHANNELS) {
+        if info.can_skip_effect(CHANNELS, true) {
             // Outputs will be silent, so no need to process.
 
             // Reset the smoothers and filters since they don't need to smooth any
@@ -235,16 +276,20 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             }
 
             if self.cutoff_hz.settle() {
-                (self.lpf_coeff, self.hpf_coeff) =
-                    calc_coeffs(self.cutoff_hz.target_value(), info.sample_rate_recip as f32);
+                (self.lpf_coeff, self.hpf_coeff) = calc_coeffs(
+                    self.cutoff_hz.target_values(),
+                    info.sample_rate_recip as f32,
+                );
             }
         } else {
             // The cutoff parameter is not currently smoothing, so we can optimize by
             // only updating the filter coefficients once.
             if self.cutoff_changed {
                 self.cutoff_changed = false;
-                (self.lpf_coeff, self.hpf_coeff) =
-                    calc_coeffs(self.cutoff_hz.target_value(), info.sample_rate_recip as f32);
+                (self.lpf_coeff, self.hpf_coeff) = calc_coeffs(
+                    self.cutoff_hz.target_values(),
+                    info.sample_rate_recip as f32,
+                );
             }
 
             for i in 0..info.frames {
@@ -265,13 +310,13 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             }
         }
 
-        ProcessStatus::OutputsModified
+        buffers.tail_status(info, CHANNELS, DEFAULT_MIN_AMP)
     }
 
     fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
         self.cutoff_hz.update_sample_rate(stream_info.sample_rate);
         (self.lpf_coeff, self.hpf_coeff) = calc_coeffs(
-            self.cutoff_hz.target_value(),
+            self.cutoff_hz.target_values(),
             stream_info.sample_rate_recip as f32,
         );
         self.reset();
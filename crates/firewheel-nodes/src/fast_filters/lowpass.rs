@@ -1,22 +1,24 @@
 use super::{MAX_HZ, MIN_HZ};
+use crate::per_channel::PerChannelParam;
 use firewheel_core::node::NodeError;
 use firewheel_core::{
     StreamInfo,
     channel_config::{ChannelConfig, ChannelCount},
-    diff::{Diff, Patch},
+    diff::{Diff, Notify, Patch},
     dsp::{
         coeff_update::{CoeffUpdateFactor, CoeffUpdateMask},
         filter::{
             single_pole_iir::{OnePoleIirLPFCoeff, OnePoleIirLPFCoeffSimd, OnePoleIirLPFSimd},
             smoothing_filter::DEFAULT_SMOOTH_SECONDS,
         },
+        volume::DEFAULT_MIN_AMP,
     },
     event::ProcEvents,
     node::{
         AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
-        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, ResettableNode,
     },
-    param::smoother::{SmoothedParam, SmootherConfig},
+    param::smoother::SmootherConfig,
 };
 
 pub type FastLowpassMonoNode = FastLowpassNode<1>;
@@ -31,6 +33,16 @@ pub struct FastLowpassNode<const CHANNELS: usize = 2> {
     /// The cutoff frequency in hertz in the range `[20.0, 20480.0]`.
     pub cutoff_hz: f32,
 
+    /// Optional per-channel cutoff frequencies in hertz, which override
+    /// [`FastLowpassNode::cutoff_hz`] for their channel.
+    ///
+    /// By default every channel is `None`, so all channels share the same
+    /// cutoff frequency (stereo-linked). Set a channel to `Some` to filter
+    /// it independently (dual-mono), i.e. for a stereo filter sweep which
+    /// differs on each side.
+    #[cfg_attr(feature = "serde", serde(with = "crate::per_channel::serde_array"))]
+    pub channel_cutoff_hz: [Option<f32>; CHANNELS],
+
     /// The time in seconds of the internal smoothing filter.
     ///
     /// By default this is set to `0.023` (23ms). This value is chosen to be
@@ -50,14 +62,20 @@ pub struct FastLowpassNode<const CHANNELS: usize = 2> {
     ///
     /// By default this is set to `4`.
     pub coeff_update_factor: CoeffUpdateFactor,
+
+    /// Reset the filter, clearing its internal state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
 }
 
 impl<const CHANNELS: usize> Default for FastLowpassNode<CHANNELS> {
     fn default() -> Self {
         Self {
             cutoff_hz: 1_000.0,
+            channel_cutoff_hz: [None; CHANNELS],
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor::default(),
+            reset: Notify::new(()),
         }
     }
 }
@@ -69,8 +87,10 @@ impl<const CHANNELS: usize> FastLowpassNode<CHANNELS> {
     pub const fn from_cutoff_hz(cutoff_hz: f32) -> Self {
         Self {
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor::DEFAULT,
+            reset: Notify::dangling(()),
         }
     }
 }
@@ -96,42 +116,48 @@ impl<const CHANNELS: usize> AudioNode for FastLowpassNode<CHANNELS> {
     ) -> Result<impl AudioNodeProcessor, NodeError> {
         let sample_rate_recip = cx.stream_info.sample_rate_recip as f32;
 
-        let cutoff_hz = self.cutoff_hz.clamp(MIN_HZ, MAX_HZ);
+        let cutoff_hz = PerChannelParam::new(
+            self.cutoff_hz,
+            self.channel_cutoff_hz,
+            MIN_HZ,
+            MAX_HZ,
+            SmootherConfig {
+                smooth_seconds: self.smooth_seconds,
+                ..Default::default()
+            },
+            cx.stream_info.sample_rate,
+        );
 
         Ok(Processor {
             filter: OnePoleIirLPFSimd::default(),
-            coeff: OnePoleIirLPFCoeffSimd::<CHANNELS>::splat(OnePoleIirLPFCoeff::new(
-                cutoff_hz,
-                sample_rate_recip,
-            )),
-            cutoff_hz: SmoothedParam::new(
-                cutoff_hz,
-                SmootherConfig {
-                    smooth_seconds: self.smooth_seconds,
-                    ..Default::default()
-                },
-                cx.stream_info.sample_rate,
-            ),
+            coeff: calc_coeff(cutoff_hz.target_values(), sample_rate_recip),
+            cutoff_hz,
             coeff_update_mask: self.coeff_update_factor.mask(),
             cutoff_changed: false,
         })
     }
 }
 
+impl<const CHANNELS: usize> ResettableNode for FastLowpassNode<CHANNELS> {
+    fn reset_notify(&mut self) -> &mut Notify<()> {
+        &mut self.reset
+    }
+}
+
 #[cold]
 #[inline(never)]
 fn calc_coeff<const CHANNELS: usize>(
-    cutoff_hz: f32,
+    cutoff_hz: [f32; CHANNELS],
     sample_rate_recip: f32,
 ) -> OnePoleIirLPFCoeffSimd<CHANNELS> {
-    OnePoleIirLPFCoeffSimd::splat(OnePoleIirLPFCoeff::new(cutoff_hz, sample_rate_recip))
+    OnePoleIirLPFCoeffSimd::new(&cutoff_hz.map(|hz| OnePoleIirLPFCoeff::new(hz, sample_rate_recip)))
 }
 
 struct Processor<const CHANNELS: usize> {
     filter: OnePoleIirLPFSimd<CHANNELS>,
     coeff: OnePoleIirLPFCoeffSimd<CHANNELS>,
 
-    cutoff_hz: SmoothedParam,
+    cutoff_hz: PerChannelParam<CHANNELS>,
     coeff_update_mask: CoeffUpdateMask,
     cutoff_changed: bool,
 }
@@ -149,7 +175,11 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             match patch {
                 FastLowpassNodePatch::CutoffHz(cutoff) => {
                     self.cutoff_changed = true;
-                    self.cutoff_hz.set_value(cutoff.clamp(MIN_HZ, MAX_HZ));
+                    self.cutoff_hz.set_value(cutoff);
+                }
+                FastLowpassNodePatch::ChannelCutoffHz((ch, cutoff)) => {
+                    self.cutoff_changed = true;
+                    self.cutoff_hz.set_channel_value(ch, cutoff);
                 }
                 FastLowpassNodePatch::SmoothSeconds(seconds) => {
                     self.cutoff_hz.set_smooth_seconds(seconds, info.sample_rate);
@@ -157,6 +187,9 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
                 FastLowpassNodePatch::CoeffUpdateFactor(f) => {
                     self.coeff_update_mask = f.mask();
                 }
+                FastLowpassNodePatch::Reset(_) => {
+                    self.filter.reset();
+                }
             }
         }
     }
@@ -165,13 +198,17 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
         self.reset();
     }
 
+    fn reset_state(&mut self) {
+        self.reset();
+    }
+
     fn process(
         &mut self,
         info: &ProcInfo,
         buffers: ProcBuffers,
         _extra: &mut ProcExtra,
     ) -> ProcessStatus {
-        if info.in_silence_mask.all_channels_silent(CHANNELS) {
+        if info.can_skip_effect(CHANNELS, true) {
             // Outputs will be silent, so no need to process.
 
             // Reset the smoothers and filters since they don't need to smooth any
@@ -215,16 +252,20 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             }
 
             if self.cutoff_hz.settle() {
-                self.coeff =
-                    calc_coeff(self.cutoff_hz.target_value(), info.sample_rate_recip as f32);
+                self.coeff = calc_coeff(
+                    self.cutoff_hz.target_values(),
+                    info.sample_rate_recip as f32,
+                );
             }
         } else {
             // The cutoff parameter is not currently smoothing, so we can optimize by
             // only updating the filter coefficients once.
             if self.cutoff_changed {
                 self.cutoff_changed = false;
-                self.coeff =
-                    calc_coeff(self.cutoff_hz.target_value(), info.sample_rate_recip as f32);
+                self.coeff = calc_coeff(
+                    self.cutoff_hz.target_values(),
+                    info.sample_rate_recip as f32,
+                );
             }
 
             for i in 0..info.frames {
@@ -244,13 +285,13 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             }
         }
 
-        ProcessStatus::OutputsModified
+        buffers.tail_status(info, CHANNELS, DEFAULT_MIN_AMP)
     }
 
     fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
         self.cutoff_hz.update_sample_rate(stream_info.sample_rate);
         self.coeff = calc_coeff(
-            self.cutoff_hz.target_value(),
+            self.cutoff_hz.target_values(),
             stream_info.sample_rate_recip as f32,
         );
     }
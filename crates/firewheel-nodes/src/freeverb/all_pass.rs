@@ -18,7 +18,7 @@ impl AllPass {
         let output = -input + delayed;
 
         // in the original version of freeverb this is a member which is never modified
-        let feedback = 0.5;
+        let feedback = super::freeverb::ALLPASS_FEEDBACK;
 
         self.delay_line
             .write_and_advance(input + delayed * feedback);
@@ -9,8 +9,14 @@ pub struct DelayLine {
 impl DelayLine {
     pub fn new(length: usize) -> Self {
         // No need to carry extra capacity around.
+        Self::with_capacity(length, length)
+    }
+
+    /// Create a delay line which can be resized up to `capacity` without
+    /// allocating.
+    pub fn with_capacity(length: usize, capacity: usize) -> Self {
         let mut buffer = Vec::new();
-        buffer.reserve_exact(length);
+        buffer.reserve_exact(capacity.max(length));
         buffer.extend(core::iter::repeat_n(0.0, length));
 
         Self { buffer, index: 0 }
@@ -1,4 +1,7 @@
-use super::{all_pass::AllPass, comb::Comb};
+use super::{all_pass::AllPass, comb::Comb, delay_line::DelayLine};
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 const FIXED_GAIN: f64 = 0.015;
 
@@ -10,6 +13,14 @@ const OFFSET_ROOM: f64 = 0.7;
 
 const STEREO_SPREAD: usize = 23;
 
+/// The maximum pre-delay in seconds.
+pub(super) const MAX_PRE_DELAY_SECONDS: f64 = 0.25;
+
+pub(super) const ALLPASS_FEEDBACK: f64 = 0.5;
+/// The amplitude below which the tail of the reverb is considered to have
+/// decayed (-100 dB).
+const TAIL_MIN_AMP: f64 = 0.00001;
+
 const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
 const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
 
@@ -24,13 +35,31 @@ pub struct Freeverb {
     input_gain: f64,
     dampening: f64,
     room_size: f64,
-    frozen: bool,
+    /// How frozen the reverb is in the range `[0.0, 1.0]`.
+    freeze: f64,
+    pre_delay: DelayLine,
+    pre_delay_seconds: f64,
+    pre_delay_frames: usize,
+    sample_rate: usize,
 }
 
 fn adjust_length(length: usize, sr: usize) -> usize {
     (length as f64 * sr as f64 / 44100.0) as usize
 }
 
+fn new_pre_delay(sample_rate: usize) -> DelayLine {
+    DelayLine::with_capacity(
+        1,
+        (MAX_PRE_DELAY_SECONDS * sample_rate as f64).ceil() as usize,
+    )
+}
+
+/// Convert the cutoff frequency of the comb dampening filter to the
+/// equivalent unitless dampening value passed to [`Freeverb::set_dampening`].
+pub(super) fn dampening_from_hz(hz: f64, sample_rate: usize) -> f64 {
+    (-core::f64::consts::TAU * hz.max(0.0) / sample_rate as f64).exp() / SCALE_DAMPENING
+}
+
 impl Freeverb {
     pub fn new(sample_rate: usize) -> Self {
         let mut freeverb = Freeverb {
@@ -56,20 +85,31 @@ impl Freeverb {
             width: 0.0,
             dampening: 0.0,
             room_size: 0.0,
-            frozen: false,
+            freeze: 0.0,
+            pre_delay: new_pre_delay(sample_rate),
+            pre_delay_seconds: 0.0,
+            pre_delay_frames: 0,
+            sample_rate,
         };
 
         freeverb.set_wet(1.0);
         freeverb.set_width(0.5);
         freeverb.set_dampening(0.5);
         freeverb.set_room_size(0.5);
-        freeverb.set_frozen(false);
+        freeverb.set_freeze(0.0);
+        freeverb.update_combs();
 
         freeverb
     }
 
     pub fn tick(&mut self, input: (f64, f64)) -> (f64, f64) {
-        let input_mixed = (input.0 + input.1) * FIXED_GAIN * self.input_gain;
+        let mut input_mixed = (input.0 + input.1) * FIXED_GAIN * self.input_gain;
+
+        if self.pre_delay_frames > 0 {
+            let delayed = self.pre_delay.read();
+            self.pre_delay.write_and_advance(input_mixed);
+            input_mixed = delayed;
+        }
 
         let mut out = (0.0, 0.0);
 
@@ -110,10 +150,25 @@ impl Freeverb {
         )
     }
 
-    fn set_frozen(&mut self, frozen: bool) {
-        self.frozen = frozen;
-        self.input_gain = if frozen { 0.0 } else { 1.0 };
-        self.update_combs();
+    /// Set how frozen the reverb is in the range `[0.0, 1.0]`.
+    ///
+    /// When fully frozen, the input is muted and the combs sustain the
+    /// current tail indefinitely. Values in between crossfade to that state.
+    pub fn set_freeze(&mut self, value: f64) {
+        self.freeze = value;
+        self.input_gain = 1.0 - value;
+    }
+
+    /// Set the delay before the input reaches the reverb network, up to
+    /// [`MAX_PRE_DELAY_SECONDS`].
+    pub fn set_pre_delay_seconds(&mut self, seconds: f64) {
+        self.pre_delay_seconds = seconds.clamp(0.0, MAX_PRE_DELAY_SECONDS);
+        self.pre_delay_frames = (self.pre_delay_seconds * self.sample_rate as f64).round() as usize;
+
+        if self.pre_delay_frames > 0 {
+            // This does not allocate since the capacity is large enough.
+            self.pre_delay.resize(self.pre_delay_frames);
+        }
     }
 
     pub fn set_room_size(&mut self, value: f64) {
@@ -121,11 +176,8 @@ impl Freeverb {
     }
 
     pub fn update_combs(&mut self) {
-        let (feedback, dampening) = if self.frozen {
-            (1.0, 0.0)
-        } else {
-            (self.room_size, self.dampening)
-        };
+        let feedback = self.room_size + (1.0 - self.room_size) * self.freeze;
+        let dampening = self.dampening * (1.0 - self.freeze);
 
         for combs in self.combs.iter_mut() {
             combs.0.set_feedback(feedback);
@@ -146,9 +198,38 @@ impl Freeverb {
             l.reset();
             r.reset();
         }
+
+        self.pre_delay.reset();
+    }
+
+    /// A conservative estimate of the number of frames it takes for the
+    /// output to decay to silence after the input goes silent, or `None` if
+    /// the reverb is (partially) frozen.
+    pub fn tail_frames(&self) -> Option<u64> {
+        if self.freeze > 0.0 {
+            return None;
+        }
+
+        let passes = |feedback: f64| (TAIL_MIN_AMP.ln() / feedback.ln()).ceil() as u64;
+
+        // The dampening filter only makes the combs decay faster, so it is
+        // ignored here.
+        let comb_len = adjust_length(COMB_TUNING[7] + STEREO_SPREAD, self.sample_rate) as u64;
+        let allpass_len: u64 = ALLPASS_TUNING
+            .iter()
+            .map(|&len| adjust_length(len + STEREO_SPREAD, self.sample_rate) as u64)
+            .sum();
+
+        Some(
+            self.pre_delay_frames as u64
+                + comb_len * passes(self.room_size)
+                + allpass_len * passes(ALLPASS_FEEDBACK),
+        )
     }
 
     pub fn resize(&mut self, sample_rate: usize) {
+        self.sample_rate = sample_rate;
+
         for (i, (l, r)) in self.combs.iter_mut().enumerate() {
             l.resize(adjust_length(COMB_TUNING[i], sample_rate));
             r.resize(adjust_length(COMB_TUNING[i] + STEREO_SPREAD, sample_rate));
@@ -161,6 +242,9 @@ impl Freeverb {
                 sample_rate,
             ));
         }
+
+        self.pre_delay = new_pre_delay(sample_rate);
+        self.set_pre_delay_seconds(self.pre_delay_seconds);
     }
 }
 
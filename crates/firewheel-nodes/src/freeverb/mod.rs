@@ -4,6 +4,8 @@
 #![allow(missing_docs)]
 #![allow(clippy::module_inception)]
 
+use core::num::NonZeroU32;
+
 use firewheel_core::dsp::coeff_update::{CoeffUpdateFactor, CoeffUpdateMask};
 use firewheel_core::node::NodeError;
 use firewheel_core::{
@@ -11,17 +13,20 @@ use firewheel_core::{
     diff::{Diff, Notify, Patch},
     dsp::{
         declick::{DeclickFadeCurve, DeclickValues, Declicker},
+        mix::Mix,
         volume::DEFAULT_MIN_AMP,
     },
     event::ProcEvents,
     node::{
-        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
-        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, ResettableNode,
     },
     param::smoother::{SmoothedParam, SmootherConfig},
 };
 
+use crate::dry_wet::DryWetMix;
 use crate::freeverb::freeverb::Freeverb;
+use crate::stereo_effect::StereoEffectConfig;
 
 mod all_pass;
 mod comb;
@@ -32,6 +37,9 @@ mod freeverb;
 ///
 /// Freeverb tends to have a somewhat metallic sound, but
 /// its minimal computational cost makes it highly versatile.
+///
+/// The dry signal is blended into the output with [`FreeverbNode::mix`].
+/// The reverb adds no latency, so the two signals never comb filter.
 #[derive(Diff, Patch, Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::component::Component))]
 #[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
@@ -47,17 +55,54 @@ pub struct FreeverbNode {
     ///
     /// Values near zero will produce a dark or muffled sound,
     /// while values near one will sound bright or metallic.
+    ///
+    /// This is ignored if [`FreeverbNode::damping_hz`] is set.
     pub damping: f32,
 
+    /// If set, the cutoff frequency in hertz of the lowpass filter which
+    /// damps the high frequencies of the tail, overriding
+    /// [`FreeverbNode::damping`].
+    ///
+    /// Lower values produce a darker tail.
+    ///
+    /// By default this is set to `None`.
+    pub damping_hz: Option<f32>,
+
+    /// The delay in milliseconds before the input reaches the reverb, in the
+    /// range `[0.0, 250.0]`.
+    ///
+    /// A short pre-delay separates the dry signal from the onset of the
+    /// reverb, which can help keep the source intelligible. The dry signal
+    /// is not delayed. Changing this while audio is playing may cause an
+    /// audible click.
+    ///
+    /// By default this is set to `0.0`.
+    pub pre_delay_ms: f32,
+
     /// Set the left/right blending, expressed from 0 to 1.
     pub width: f32,
 
+    /// The mix between the dry input signal and the wet reverb signal.
+    ///
+    /// Pausing the reverb does not affect the dry signal.
+    ///
+    /// By default this is set to [`Mix::FULLY_WET`].
+    pub mix: Mix,
+
     /// Pause the reverb processing.
     ///
     /// This prevents a reverb tail from ringing out when you
     /// want all sound to momentarily pause.
     pub pause: bool,
 
+    /// Freeze the reverb, holding the current tail indefinitely.
+    ///
+    /// While frozen, the input is muted and the tail does not decay, creating
+    /// an infinite sustain. Unfreezing smoothly resumes the normal decay.
+    ///
+    /// By default this is set to `false`.
+    pub freeze: bool,
+
     /// Reset the reverb, clearing its internal state.
     #[cfg_attr(feature = "serde", serde(skip))]
     pub reset: Notify<()>,
@@ -88,8 +133,12 @@ impl Default for FreeverbNode {
         FreeverbNode {
             room_size: 0.5,
             damping: 0.5,
+            damping_hz: None,
+            pre_delay_ms: 0.0,
             width: 0.5,
+            mix: Mix::FULLY_WET,
             pause: false,
+            freeze: false,
             reset: Notify::new(()),
             smooth_seconds: 0.015,
             coeff_update_factor: CoeffUpdateFactor::default(),
@@ -98,7 +147,7 @@ impl Default for FreeverbNode {
 }
 
 impl AudioNode for FreeverbNode {
-    type Configuration = EmptyConfig;
+    type Configuration = StereoEffectConfig;
 
     fn info(&self, _: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
         Ok(AudioNodeInfo::new()
@@ -111,10 +160,12 @@ impl AudioNode for FreeverbNode {
 
     fn construct_processor(
         &self,
-        _: &Self::Configuration,
+        config: &Self::Configuration,
         cx: ConstructProcessorContext,
     ) -> Result<impl AudioNodeProcessor, NodeError> {
-        let freeverb = freeverb::Freeverb::new(cx.stream_info.sample_rate.get() as usize);
+        let mut freeverb = freeverb::Freeverb::new(cx.stream_info.sample_rate.get() as usize);
+        freeverb.set_pre_delay_seconds(self.pre_delay_ms as f64 / 1_000.0);
+
         let smoother_config = SmootherConfig {
             smooth_seconds: self.smooth_seconds,
             ..Default::default()
@@ -123,7 +174,7 @@ impl AudioNode for FreeverbNode {
         let mut processor = FreeverbProcessor {
             freeverb,
             damping: SmoothedParam::new(
-                self.damping.clamp(0.0, 1.0),
+                damping_target(self.damping, self.damping_hz, cx.stream_info.sample_rate),
                 smoother_config,
                 cx.stream_info.sample_rate,
             ),
@@ -137,6 +188,16 @@ impl AudioNode for FreeverbNode {
                 smoother_config,
                 cx.stream_info.sample_rate,
             ),
+            freeze: SmoothedParam::new(
+                freeze_amount(self.freeze),
+                smoother_config,
+                cx.stream_info.sample_rate,
+            ),
+            // The pre-delay only delays the reverb, so the reverb adds no
+            // latency and the dry signal does not need to be delayed.
+            dry_wet: DryWetMix::new(self.mix, 2, 0, smoother_config, cx.stream_info.sample_rate),
+            damping_param: self.damping,
+            damping_hz: self.damping_hz,
             paused: self.pause,
             pause_declicker: if self.pause {
                 Declicker::SettledAt0
@@ -145,6 +206,7 @@ impl AudioNode for FreeverbNode {
             },
             values: DeclickValues::new(cx.stream_info.declick_frames),
             coeff_update_mask: self.coeff_update_factor.mask(),
+            config: *config,
         };
 
         processor.apply_parameters();
@@ -153,15 +215,26 @@ impl AudioNode for FreeverbNode {
     }
 }
 
+impl ResettableNode for FreeverbNode {
+    fn reset_notify(&mut self) -> &mut Notify<()> {
+        &mut self.reset
+    }
+}
+
 struct FreeverbProcessor {
     freeverb: freeverb::Freeverb,
     damping: SmoothedParam,
     width: SmoothedParam,
     room_size: SmoothedParam,
+    freeze: SmoothedParam,
+    dry_wet: DryWetMix,
+    damping_param: f32,
+    damping_hz: Option<f32>,
     paused: bool,
     pause_declicker: Declicker,
     values: DeclickValues,
     coeff_update_mask: CoeffUpdateMask,
+    config: StereoEffectConfig,
 }
 
 impl FreeverbProcessor {
@@ -170,9 +243,11 @@ impl FreeverbProcessor {
         self.damping.reset_to_target();
         self.room_size.reset_to_target();
         self.width.reset_to_target();
+        self.freeze.reset_to_target();
 
         if reset_reverb {
             self.freeverb.reset();
+            self.dry_wet.reset();
         }
     }
 }
@@ -182,7 +257,23 @@ impl AudioNodeProcessor for FreeverbProcessor {
         for patch in events.drain_patches::<FreeverbNode>() {
             match patch {
                 FreeverbNodePatch::Damping(value) => {
-                    self.damping.set_value(value.clamp(0.0, 1.0));
+                    self.damping_param = value;
+                    self.damping.set_value(damping_target(
+                        self.damping_param,
+                        self.damping_hz,
+                        info.sample_rate,
+                    ));
+                }
+                FreeverbNodePatch::DampingHz(value) => {
+                    self.damping_hz = value;
+                    self.damping.set_value(damping_target(
+                        self.damping_param,
+                        self.damping_hz,
+                        info.sample_rate,
+                    ));
+                }
+                FreeverbNodePatch::PreDelayMs(value) => {
+                    self.freeverb.set_pre_delay_seconds(value as f64 / 1_000.0);
                 }
                 FreeverbNodePatch::RoomSize(value) => {
                     self.room_size.set_value(value.clamp(0.0, 1.0));
@@ -190,6 +281,12 @@ impl AudioNodeProcessor for FreeverbProcessor {
                 FreeverbNodePatch::Width(value) => {
                     self.width.set_value(value.clamp(0.0, 1.0));
                 }
+                FreeverbNodePatch::Mix(value) => {
+                    self.dry_wet.set_mix(value);
+                }
+                FreeverbNodePatch::Freeze(value) => {
+                    self.freeze.set_value(freeze_amount(value));
+                }
                 FreeverbNodePatch::Reset(_) => {
                     self.freeverb.reset();
                 }
@@ -207,6 +304,8 @@ impl AudioNodeProcessor for FreeverbProcessor {
                     self.room_size.set_smooth_seconds(value, info.sample_rate);
                     self.width.set_smooth_seconds(value, info.sample_rate);
                     self.damping.set_smooth_seconds(value, info.sample_rate);
+                    self.freeze.set_smooth_seconds(value, info.sample_rate);
+                    self.dry_wet.set_smooth_seconds(value, info.sample_rate);
                 }
                 FreeverbNodePatch::CoeffUpdateFactor(value) => {
                     self.coeff_update_mask = value.mask();
@@ -215,26 +314,59 @@ impl AudioNodeProcessor for FreeverbProcessor {
         }
     }
 
+    fn tail_frames(&self) -> Option<u64> {
+        if self.room_size.is_smoothing() || self.freeze.is_smoothing() {
+            // The decay time is still changing.
+            return None;
+        }
+
+        self.freeverb.tail_frames()
+    }
+
     fn bypassed(&mut self, bypassed: bool) {
         if !bypassed {
             self.reset(true);
         }
     }
 
+    fn reset_state(&mut self) {
+        self.freeverb.reset();
+    }
+
     fn process(
         &mut self,
         info: &ProcInfo,
         buffers: ProcBuffers,
-        _: &mut ProcExtra,
+        extra: &mut ProcExtra,
     ) -> ProcessStatus {
         let all_silent = info.in_silence_mask.all_channels_silent(2);
 
-        if (self.paused && self.pause_declicker.has_settled())
-            || (all_silent && info.prev_output_was_silent)
-        {
+        if (self.paused && self.pause_declicker.has_settled()) || info.can_skip_effect(2, true) {
             self.reset(false);
 
-            return ProcessStatus::ClearAllOutputs;
+            if self.dry_wet.is_fully_wet() {
+                return ProcessStatus::ClearAllOutputs;
+            }
+
+            // Only pass through the dry signal.
+            let (in_l, in_r) = self
+                .config
+                .stereo_inputs(buffers.inputs, info.in_silence_mask);
+
+            for out in buffers.outputs[..2].iter_mut() {
+                out[..info.frames].fill(0.0);
+            }
+
+            let [dry_gain_buffer, wet_gain_buffer] = extra.scratch_buffers.channels_mut::<2>();
+            self.dry_wet.process(
+                &[in_l, in_r],
+                &mut buffers.outputs[..2],
+                info.frames,
+                false,
+                [dry_gain_buffer, wet_gain_buffer],
+            );
+
+            return buffers.check_for_silence_on_outputs(DEFAULT_MIN_AMP);
         }
 
         if !all_silent && info.prev_output_was_silent {
@@ -242,29 +374,34 @@ impl AudioNodeProcessor for FreeverbProcessor {
             self.apply_parameters();
         }
 
-        assert!(buffers.inputs[0].len() >= info.frames);
-        assert!(buffers.inputs[1].len() >= info.frames);
+        let (in_l, in_r) = self
+            .config
+            .stereo_inputs(buffers.inputs, info.in_silence_mask);
+
+        assert!(in_l.len() >= info.frames);
+        assert!(in_r.len() >= info.frames);
         assert!(buffers.outputs[0].len() >= info.frames);
         assert!(buffers.outputs[1].len() >= info.frames);
 
         // just take the slow path if any are smoothing
-        if self.damping.is_smoothing() || self.room_size.is_smoothing() || self.width.is_smoothing()
+        if self.damping.is_smoothing()
+            || self.room_size.is_smoothing()
+            || self.width.is_smoothing()
+            || self.freeze.is_smoothing()
         {
             for frame in 0..info.frames {
                 let damping = self.damping.next_smoothed();
                 let room_size = self.room_size.next_smoothed();
                 let width = self.width.next_smoothed();
+                let freeze = self.freeze.next_smoothed();
 
                 // we assume setting these values is more expensive than
                 // calculating their smoothing
                 if self.coeff_update_mask.do_update(frame) {
-                    calc_coeffs(&mut self.freeverb, damping, room_size, width);
+                    calc_coeffs(&mut self.freeverb, damping, room_size, width, freeze);
                 }
 
-                let (left, right) = self.freeverb.tick((
-                    buffers.inputs[0][frame] as f64,
-                    buffers.inputs[1][frame] as f64,
-                ));
+                let (left, right) = self.freeverb.tick((in_l[frame] as f64, in_r[frame] as f64));
 
                 buffers.outputs[0][frame] = left as f32;
                 buffers.outputs[1][frame] = right as f32;
@@ -273,12 +410,10 @@ impl AudioNodeProcessor for FreeverbProcessor {
             self.damping.settle();
             self.room_size.settle();
             self.width.settle();
+            self.freeze.settle();
         } else {
             for frame in 0..info.frames {
-                let (left, right) = self.freeverb.tick((
-                    buffers.inputs[0][frame] as f64,
-                    buffers.inputs[1][frame] as f64,
-                ));
+                let (left, right) = self.freeverb.tick((in_l[frame] as f64, in_r[frame] as f64));
 
                 buffers.outputs[0][frame] = left as f32;
                 buffers.outputs[1][frame] = right as f32;
@@ -310,14 +445,32 @@ impl AudioNodeProcessor for FreeverbProcessor {
             );
         }
 
+        let [dry_gain_buffer, wet_gain_buffer] = extra.scratch_buffers.channels_mut::<2>();
+        self.dry_wet.process(
+            &[in_l, in_r],
+            &mut buffers.outputs[..2],
+            info.frames,
+            false,
+            [dry_gain_buffer, wet_gain_buffer],
+        );
+
         ProcessStatus::OutputsModified
     }
 
     fn new_stream(&mut self, stream_info: &firewheel_core::StreamInfo, _proc: &mut ProcStreamCtx) {
         self.freeverb.resize(stream_info.sample_rate.get() as usize);
         self.damping.update_sample_rate(stream_info.sample_rate);
+        // The damping coefficient depends on the sample rate when it is
+        // given as a cutoff frequency.
+        self.damping.set_value(damping_target(
+            self.damping_param,
+            self.damping_hz,
+            stream_info.sample_rate,
+        ));
         self.width.update_sample_rate(stream_info.sample_rate);
         self.room_size.update_sample_rate(stream_info.sample_rate);
+        self.freeze.update_sample_rate(stream_info.sample_rate);
+        self.dry_wet.update_sample_rate(stream_info.sample_rate);
         self.reset(true);
     }
 }
@@ -329,16 +482,30 @@ impl FreeverbProcessor {
         self.freeverb
             .set_room_size(self.room_size.target_value() as f64);
         self.freeverb.set_width(self.width.target_value() as f64);
+        self.freeverb.set_freeze(self.freeze.target_value() as f64);
         self.freeverb.update_combs();
     }
 }
 
+/// The unitless damping value passed to [`Freeverb::set_dampening`].
+fn damping_target(damping: f32, damping_hz: Option<f32>, sample_rate: NonZeroU32) -> f32 {
+    match damping_hz {
+        Some(hz) => freeverb::dampening_from_hz(hz as f64, sample_rate.get() as usize) as f32,
+        None => damping.clamp(0.0, 1.0),
+    }
+}
+
+fn freeze_amount(freeze: bool) -> f32 {
+    if freeze { 1.0 } else { 0.0 }
+}
+
 #[cold]
 #[inline(never)]
-fn calc_coeffs(freeverb: &mut Freeverb, damping: f32, room_size: f32, width: f32) {
+fn calc_coeffs(freeverb: &mut Freeverb, damping: f32, room_size: f32, width: f32, freeze: f32) {
     freeverb.set_dampening(damping as f64);
     freeverb.set_room_size(room_size as f64);
     freeverb.set_width(width as f64);
+    freeverb.set_freeze(freeze as f64);
 
     freeverb.update_combs();
 }
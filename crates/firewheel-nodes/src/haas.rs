@@ -0,0 +1,322 @@
+use core::num::NonZeroU32;
+
+use bevy_platform::prelude::Vec;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Patch},
+    dsp::volume::DEFAULT_MIN_AMP,
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, NodeError,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+};
+
+/// The default value of [`HaasConfig::max_delay_ms`].
+pub const DEFAULT_MAX_HAAS_DELAY_MS: f32 = 40.0;
+
+/// The configuration for a [`HaasNode`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HaasConfig {
+    /// The maximum value of [`HaasNode::delay_ms`]. This determines the size
+    /// of the delay line which is allocated.
+    ///
+    /// By default this is set to `40.0` (40ms).
+    pub max_delay_ms: f32,
+}
+
+impl Default for HaasConfig {
+    fn default() -> Self {
+        Self {
+            max_delay_ms: DEFAULT_MAX_HAAS_DELAY_MS,
+        }
+    }
+}
+
+/// The channel which is delayed by a [`HaasNode`].
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HaasSide {
+    /// Delay the left channel, making the sound appear to come from the
+    /// right.
+    Left,
+    /// Delay the right channel, making the sound appear to come from the
+    /// left.
+    #[default]
+    Right,
+}
+
+impl HaasSide {
+    const fn channel(&self) -> usize {
+        match self {
+            Self::Left => 0,
+            Self::Right => 1,
+        }
+    }
+}
+
+/// A node which widens a stereo signal by delaying one of its channels by a
+/// few milliseconds (the Haas or precedence effect).
+///
+/// Unlike a mid/side widener, this can make even a mono source sound wide.
+/// However, summing the output to mono results in comb filtering. Enable
+/// [`HaasNode::mono_compatible`] if the output may be summed to mono.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HaasNode {
+    /// The delay in milliseconds, in the range
+    /// `[0.0, HaasConfig::max_delay_ms]`.
+    ///
+    /// Delays of around `1.0` to `30.0` milliseconds are perceived as width
+    /// rather than as a distinct echo.
+    ///
+    /// Changing this while audio is playing may cause an audible click.
+    ///
+    /// By default this is set to `10.0` (10ms).
+    pub delay_ms: f32,
+    /// The channel which is delayed.
+    ///
+    /// By default this is set to [`HaasSide::Right`].
+    pub side: HaasSide,
+    /// If `true`, then instead of replacing the delayed channel, the delayed
+    /// signal is added to that channel and subtracted from the other one.
+    /// This way the delayed signal cancels out when the output is summed to
+    /// mono, leaving the original signal without any comb filtering.
+    ///
+    /// By default this is set to `false`.
+    pub mono_compatible: bool,
+}
+
+impl Default for HaasNode {
+    fn default() -> Self {
+        Self {
+            delay_ms: 10.0,
+            side: HaasSide::Right,
+            mono_compatible: false,
+        }
+    }
+}
+
+impl AudioNode for HaasNode {
+    type Configuration = HaasConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("haas")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::STEREO,
+                num_outputs: ChannelCount::STEREO,
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let sample_rate = cx.stream_info.sample_rate;
+
+        let mut delay = HaasDelay::new(config.max_delay_ms, sample_rate);
+        delay.set_delay_ms(self.delay_ms, sample_rate);
+
+        Ok(Processor {
+            params: *self,
+            config: *config,
+            delay,
+            silent_input_frames: usize::MAX,
+            sample_rate,
+        })
+    }
+}
+
+/// A delay line for the delayed channel.
+struct HaasDelay {
+    buffer: Vec<f32>,
+    write_ptr: usize,
+    delay_frames: usize,
+}
+
+impl HaasDelay {
+    fn new(max_delay_ms: f32, sample_rate: NonZeroU32) -> Self {
+        let max_delay_frames = ms_to_frames(max_delay_ms, sample_rate);
+
+        let mut buffer = Vec::new();
+        buffer.reserve_exact(max_delay_frames + 1);
+        buffer.resize(max_delay_frames + 1, 0.0);
+
+        Self {
+            buffer,
+            write_ptr: 0,
+            delay_frames: 0,
+        }
+    }
+
+    fn set_delay_ms(&mut self, delay_ms: f32, sample_rate: NonZeroU32) {
+        self.delay_frames = ms_to_frames(delay_ms, sample_rate).min(self.buffer.len() - 1);
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_ptr = 0;
+    }
+
+    #[inline]
+    fn process(&mut self, s: f32) -> f32 {
+        let len = self.buffer.len();
+
+        self.buffer[self.write_ptr] = s;
+
+        let read_ptr = if self.write_ptr >= self.delay_frames {
+            self.write_ptr - self.delay_frames
+        } else {
+            self.write_ptr + len - self.delay_frames
+        };
+
+        self.write_ptr += 1;
+        if self.write_ptr == len {
+            self.write_ptr = 0;
+        }
+
+        self.buffer[read_ptr]
+    }
+}
+
+struct Processor {
+    params: HaasNode,
+    config: HaasConfig,
+    delay: HaasDelay,
+    /// The number of frames since the input was last not silent. This is
+    /// `usize::MAX` if the delay line is empty.
+    silent_input_frames: usize,
+    sample_rate: NonZeroU32,
+}
+
+impl Processor {
+    fn clear(&mut self) {
+        if self.silent_input_frames != usize::MAX {
+            self.delay.clear();
+            self.silent_input_frames = usize::MAX;
+        }
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, _info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<HaasNode>() {
+            match patch {
+                HaasNodePatch::DelayMs(delay_ms) => {
+                    self.delay.set_delay_ms(delay_ms, self.sample_rate);
+                }
+                HaasNodePatch::Side(_) => {
+                    // Don't let the previous delayed channel leak into the
+                    // new one.
+                    self.delay.clear();
+                }
+                HaasNodePatch::MonoCompatible(_) => {}
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn bypassed(&mut self, bypassed: bool) {
+        if !bypassed {
+            self.clear();
+        }
+    }
+
+    fn reset_state(&mut self) {
+        self.clear();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let input_is_silent = info.in_silence_mask.all_channels_silent(2);
+
+        // The delayed channel lags behind the input, so the node can only be
+        // skipped once the last non-silent input has left the delay line.
+        if input_is_silent && self.silent_input_frames >= self.delay.delay_frames {
+            self.clear();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let delayed_ch = self.params.side.channel();
+        let other_ch = 1 - delayed_ch;
+
+        let (delayed_in, other_in) = (
+            &buffers.inputs[delayed_ch][..info.frames],
+            &buffers.inputs[other_ch][..info.frames],
+        );
+        let (out_l, out_r) = buffers.outputs.split_at_mut(1);
+        let (delayed_out, other_out) = if delayed_ch == 0 {
+            (&mut out_l[0][..info.frames], &mut out_r[0][..info.frames])
+        } else {
+            (&mut out_r[0][..info.frames], &mut out_l[0][..info.frames])
+        };
+
+        if self.params.mono_compatible {
+            for (((&delayed_in_s, &other_in_s), delayed_out_s), other_out_s) in delayed_in
+                .iter()
+                .zip(other_in.iter())
+                .zip(delayed_out.iter_mut())
+                .zip(other_out.iter_mut())
+            {
+                let d = self.delay.process(delayed_in_s);
+
+                *delayed_out_s = delayed_in_s + d;
+                *other_out_s = other_in_s - d;
+            }
+        } else {
+            for (&in_s, out_s) in delayed_in.iter().zip(delayed_out.iter_mut()) {
+                *out_s = self.delay.process(in_s);
+            }
+
+            other_out.copy_from_slice(other_in);
+        }
+
+        if input_is_silent {
+            self.silent_input_frames = self.silent_input_frames.saturating_add(info.frames);
+        } else {
+            self.silent_input_frames = 0;
+        }
+
+        buffers.tail_status(info, 2, DEFAULT_MIN_AMP)
+    }
+
+    fn tail_frames(&self) -> Option<u64> {
+        Some(self.delay.delay_frames as u64)
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        if stream_info.sample_rate == self.sample_rate {
+            return;
+        }
+
+        self.sample_rate = stream_info.sample_rate;
+
+        self.delay = HaasDelay::new(self.config.max_delay_ms, self.sample_rate);
+        self.delay
+            .set_delay_ms(self.params.delay_ms, self.sample_rate);
+        self.silent_input_frames = usize::MAX;
+    }
+}
+
+fn ms_to_frames(ms: f32, sample_rate: NonZeroU32) -> usize {
+    (f64::from(ms.max(0.0)) * f64::from(sample_rate.get()) / 1_000.0).round() as usize
+}
@@ -0,0 +1,159 @@
+//! A node that generates unit impulses, useful for capturing the impulse
+//! response of other nodes.
+
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Notify, Patch},
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+};
+
+/// A node that outputs a single impulse, or a periodic train of impulses
+/// (Mono output only)
+///
+/// An impulse is output as soon as the node starts processing. Connect the
+/// output of this node to the node being measured, and record the output of
+/// that node to capture its impulse response.
+///
+/// Note that because this node is for testing purposes, it does not
+/// bother with parameter smoothing.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImpulseNode {
+    /// The time in seconds between impulses. If this is `0.0`, then only a
+    /// single impulse is output.
+    ///
+    /// Changing this value restarts the period from the current frame.
+    ///
+    /// By default this is set to `0.0`.
+    pub period_seconds: f32,
+    /// The raw amplitude of each impulse.
+    ///
+    /// By default this is set to `1.0`.
+    pub amplitude: f32,
+    /// Output an impulse on the next frame.
+    ///
+    /// If the impulse train is periodic, then the period restarts from this
+    /// impulse.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub trigger: Notify<()>,
+}
+
+impl Default for ImpulseNode {
+    fn default() -> Self {
+        Self {
+            period_seconds: 0.0,
+            amplitude: 1.0,
+            trigger: Notify::new(()),
+        }
+    }
+}
+
+impl AudioNode for ImpulseNode {
+    type Configuration = EmptyConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("impulse")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::ZERO,
+                num_outputs: ChannelCount::MONO,
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        _config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        Ok(Processor {
+            params: *self,
+            frames_until_impulse: Some(0),
+            sample_rate: cx.stream_info.sample_rate.get(),
+        })
+    }
+}
+
+struct Processor {
+    params: ImpulseNode,
+    /// The number of frames until the next impulse, or `None` if no impulse
+    /// is pending.
+    frames_until_impulse: Option<u64>,
+    sample_rate: u32,
+}
+
+impl Processor {
+    fn period_frames(&self) -> Option<u64> {
+        (self.params.period_seconds > 0.0).then(|| {
+            ((f64::from(self.params.period_seconds) * f64::from(self.sample_rate)).round() as u64)
+                .max(1)
+        })
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, _info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<ImpulseNode>() {
+            let restart_period = matches!(patch, ImpulseNodePatch::PeriodSeconds(_));
+            let trigger = matches!(patch, ImpulseNodePatch::Trigger(_));
+
+            self.params.apply(patch);
+
+            if trigger {
+                self.frames_until_impulse = Some(0);
+            } else if restart_period {
+                self.frames_until_impulse = self.period_frames();
+            }
+        }
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let Some(mut frames_until_impulse) = self.frames_until_impulse else {
+            return ProcessStatus::ClearAllOutputs;
+        };
+
+        if frames_until_impulse >= info.frames as u64 {
+            self.frames_until_impulse = Some(frames_until_impulse - info.frames as u64);
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let out = &mut buffers.outputs[0][..info.frames];
+        out.fill(0.0);
+
+        let period_frames = self.period_frames();
+
+        loop {
+            out[frames_until_impulse as usize] = self.params.amplitude;
+
+            let Some(period_frames) = period_frames else {
+                self.frames_until_impulse = None;
+                break;
+            };
+
+            frames_until_impulse += period_frames;
+
+            if frames_until_impulse >= info.frames as u64 {
+                self.frames_until_impulse = Some(frames_until_impulse - info.frames as u64);
+                break;
+            }
+        }
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.sample_rate = stream_info.sample_rate.get();
+    }
+}
@@ -39,6 +39,57 @@ pub mod fast_rms;
 #[cfg(feature = "triple_buffer")]
 pub mod triple_buffer;
 
+#[cfg(feature = "tremolo")]
+pub mod tremolo;
+
+#[cfg(feature = "phaser")]
+pub mod phaser;
+
+#[cfg(feature = "dynamics")]
+pub mod dynamics;
+
+#[cfg(feature = "impulse")]
+pub mod impulse;
+
+#[cfg(feature = "recorder")]
+pub mod recorder;
+
+#[cfg(feature = "adsr")]
+pub mod adsr;
+
+#[cfg(feature = "oscillator")]
+pub mod oscillator;
+
+#[cfg(feature = "upmix")]
+pub mod upmix;
+
+#[cfg(feature = "spectrum")]
+pub mod spectrum;
+
+#[cfg(feature = "pitch_detect")]
+pub mod pitch_detect;
+
+#[cfg(feature = "correlation_meter")]
+pub mod correlation_meter;
+
+#[cfg(feature = "closure")]
+pub mod closure;
+
+#[cfg(feature = "tap_delay")]
+pub mod tap_delay;
+
+#[cfg(feature = "haas")]
+pub mod haas;
+
+#[cfg(any(feature = "freeverb", feature = "tremolo", feature = "phaser"))]
+pub mod stereo_effect;
+
+#[cfg(any(feature = "fast_filters", feature = "svf"))]
+mod per_channel;
+
+#[cfg(any(feature = "freeverb", feature = "convolution"))]
+mod dry_wet;
+
 mod stereo_to_mono;
 
 pub use stereo_to_mono::StereoToMonoNode;
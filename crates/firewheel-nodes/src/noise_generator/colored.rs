@@ -0,0 +1,272 @@
+//! A node that generates noise with a selectable spectral tilt.
+
+use core::f32::consts::FRAC_1_SQRT_2;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Notify, Patch},
+    dsp::{
+        filter::{
+            single_pole_iir::{OnePoleIirLPF, OnePoleIirLPFCoeff},
+            smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+        },
+        volume::{DEFAULT_MIN_AMP, Volume},
+    },
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
+        ProcBuffers, ProcExtra, ProcInfo, ProcessStatus,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+use super::{DEFAULT_SEED, pink::PinkNoise, rng, rng_state_from_seed};
+
+/// The cutoff of the leaky integrator used to generate brown noise. Below
+/// this frequency the spectrum is flat, which prevents the signal from
+/// drifting away from zero.
+const BROWN_CUTOFF_HZ: f32 = 20.0;
+
+/// The gain applied to differentiated pink noise so that blue noise has
+/// roughly the same RMS level as white noise.
+const BLUE_GAIN: f32 = 3.55;
+
+/// The spectral tilt of a [`ColoredNoiseGenNode`].
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseColor {
+    /// Equal power per frequency (0 dB per octave)
+    #[default]
+    White,
+    /// Equal power per octave (-3 dB per octave)
+    Pink,
+    /// Also known as red noise (-6 dB per octave)
+    ///
+    /// This is white noise passed through a leaky integrator.
+    Brown,
+    /// +3 dB per octave
+    ///
+    /// This is differentiated pink noise.
+    Blue,
+    /// +6 dB per octave
+    ///
+    /// This is white noise passed through a differentiator.
+    Violet,
+}
+
+/// A node that generates noise with a selectable spectral tilt (Mono
+/// output only)
+///
+/// The brown, blue, and violet variants are scaled to have roughly the same
+/// RMS level as white noise, so their peaks may exceed `1.0`.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColoredNoiseGenNode {
+    /// The spectral tilt of the generated noise.
+    ///
+    /// Note, changing this value resets the internal filter state, which may
+    /// cause an audible click.
+    ///
+    /// By default this is set to [`NoiseColor::White`].
+    pub color: NoiseColor,
+    /// The overall volume.
+    ///
+    /// Note, noise is really loud, so prefer to use a value like
+    /// `Volume::Linear(0.4)` or `Volume::Decibels(-18.0)`.
+    pub volume: Volume,
+    /// The time in seconds of the internal smoothing filter.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+    /// The seed of the random number generator.
+    ///
+    /// The same seed always produces the same sequence of samples. Changing
+    /// this value reseeds the generator and restarts the sequence.
+    ///
+    /// By default this is set to `17`.
+    pub seed: u64,
+    /// Restart the sequence of samples from the current seed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
+}
+
+impl Default for ColoredNoiseGenNode {
+    fn default() -> Self {
+        Self {
+            color: NoiseColor::default(),
+            volume: Volume::Linear(0.4),
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+            seed: DEFAULT_SEED,
+            reset: Notify::new(()),
+        }
+    }
+}
+
+impl AudioNode for ColoredNoiseGenNode {
+    type Configuration = EmptyConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("colored_noise_gen")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::ZERO,
+                num_outputs: ChannelCount::MONO,
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        _config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let brown_coeff =
+            OnePoleIirLPFCoeff::new(BROWN_CUTOFF_HZ, cx.stream_info.sample_rate_recip as f32);
+
+        Ok(Processor {
+            gain: SmoothedParam::new(
+                self.volume.amp_clamped(DEFAULT_MIN_AMP),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                cx.stream_info.sample_rate,
+            ),
+            params: *self,
+            fpd: rng_state_from_seed(self.seed),
+            pink: PinkNoise::new(self.seed),
+            brown_filter: OnePoleIirLPF::default(),
+            // The RMS gain of the filter is `sqrt(a0 / (1 + b1))`, so compensate
+            // for it to keep the level consistent with white noise.
+            brown_gain: ((1.0 + brown_coeff.b1) / brown_coeff.a0).sqrt(),
+            brown_coeff,
+            prev_sample: 0.0,
+        })
+    }
+}
+
+// The realtime processor counterpart to your node.
+struct Processor {
+    params: ColoredNoiseGenNode,
+    gain: SmoothedParam,
+
+    // white noise generator state
+    fpd: i32,
+    pink: PinkNoise,
+
+    brown_filter: OnePoleIirLPF,
+    brown_coeff: OnePoleIirLPFCoeff,
+    brown_gain: f32,
+
+    // the previous sample of the differentiator
+    prev_sample: f32,
+}
+
+impl Processor {
+    /// Restart the sequence of samples from the given seed.
+    fn reset(&mut self, seed: u64) {
+        self.fpd = rng_state_from_seed(seed);
+        self.pink.reset(seed);
+        self.reset_filters();
+    }
+
+    fn reset_filters(&mut self) {
+        self.brown_filter.reset();
+        self.prev_sample = 0.0;
+    }
+
+    #[inline(always)]
+    fn next_white(&mut self) -> f32 {
+        // Get a random normalized value in the range `[-1.0, 1.0]`.
+        rng(&mut self.fpd) as f32 * (1.0 / 2_147_483_648.0)
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<ColoredNoiseGenNode>() {
+            match patch {
+                ColoredNoiseGenNodePatch::Color(color) => {
+                    if color != self.params.color {
+                        self.reset_filters();
+                    }
+                }
+                ColoredNoiseGenNodePatch::Volume(vol) => {
+                    self.gain.set_value(vol.amp_clamped(DEFAULT_MIN_AMP));
+                }
+                ColoredNoiseGenNodePatch::SmoothSeconds(seconds) => {
+                    self.gain.set_smooth_seconds(seconds, info.sample_rate);
+                }
+                ColoredNoiseGenNodePatch::Seed(seed) => {
+                    self.reset(seed);
+                }
+                ColoredNoiseGenNodePatch::Reset(_) => {
+                    self.reset(self.params.seed);
+                }
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn process(
+        &mut self,
+        _info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        if self.gain.has_settled_at_or_below(DEFAULT_MIN_AMP) {
+            self.gain.reset_to_target();
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        match self.params.color {
+            NoiseColor::White => {
+                for s in buffers.outputs[0].iter_mut() {
+                    *s = self.next_white() * self.gain.next_smoothed();
+                }
+            }
+            NoiseColor::Pink => {
+                for s in buffers.outputs[0].iter_mut() {
+                    *s = self.pink.next_sample() * self.gain.next_smoothed();
+                }
+            }
+            NoiseColor::Brown => {
+                for s in buffers.outputs[0].iter_mut() {
+                    let white = self.next_white();
+                    let r = self.brown_filter.process(white, self.brown_coeff) * self.brown_gain;
+
+                    *s = r * self.gain.next_smoothed();
+                }
+            }
+            NoiseColor::Blue => {
+                for s in buffers.outputs[0].iter_mut() {
+                    let pink = self.pink.next_sample();
+                    let r = (pink - self.prev_sample) * BLUE_GAIN;
+                    self.prev_sample = pink;
+
+                    *s = r * self.gain.next_smoothed();
+                }
+            }
+            NoiseColor::Violet => {
+                for s in buffers.outputs[0].iter_mut() {
+                    let white = self.next_white();
+                    let r = (white - self.prev_sample) * FRAC_1_SQRT_2;
+                    self.prev_sample = white;
+
+                    *s = r * self.gain.next_smoothed();
+                }
+            }
+        }
+
+        ProcessStatus::OutputsModified
+    }
+}
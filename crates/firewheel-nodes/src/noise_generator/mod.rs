@@ -1,2 +1,28 @@
+pub mod colored;
 pub mod pink;
 pub mod white;
+
+/// The default seed of the noise generators.
+const DEFAULT_SEED: u64 = 17;
+
+/// Convert a seed into the state of the xorshift random number generator
+/// used by the noise generators. The state cannot be zero.
+fn rng_state_from_seed(seed: u64) -> i32 {
+    let state = (seed ^ (seed >> 32)) as u32 as i32;
+
+    if state == 0 {
+        DEFAULT_SEED as i32
+    } else {
+        state
+    }
+}
+
+/// Advance the state of the xorshift random number generator.
+#[inline(always)]
+fn rng(fpd: &mut i32) -> i32 {
+    *fpd ^= *fpd << 13;
+    *fpd ^= *fpd >> 17;
+    *fpd ^= *fpd << 5;
+
+    *fpd
+}
@@ -5,19 +5,21 @@
 use firewheel_core::node::NodeError;
 use firewheel_core::{
     channel_config::{ChannelConfig, ChannelCount},
-    diff::{Diff, Patch},
+    diff::{Diff, Notify, Patch},
     dsp::{
         filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
         volume::{DEFAULT_MIN_AMP, Volume},
     },
     event::ProcEvents,
     node::{
-        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
-        ProcExtra, ProcInfo, ProcessStatus,
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
+        ProcBuffers, ProcExtra, ProcInfo, ProcessStatus,
     },
     param::smoother::{SmoothedParam, SmootherConfig},
 };
 
+use super::{DEFAULT_SEED, rng, rng_state_from_seed};
+
 const COEFF_A: [i32; 5] = [14055, 12759, 10733, 12273, 15716];
 const COEFF_SUM: [i16; 5] = [22347, 27917, 29523, 29942, 30007];
 
@@ -38,6 +40,16 @@ pub struct PinkNoiseGenNode {
     /// roughly equal to a typical block size of 1024 samples (23 ms) to
     /// eliminate stair-stepping for most games.
     pub smooth_seconds: f32,
+    /// The seed of the random number generator.
+    ///
+    /// The same seed always produces the same sequence of samples. Changing
+    /// this value reseeds the generator and restarts the sequence.
+    ///
+    /// By default this is set to `17`.
+    pub seed: u64,
+    /// Restart the sequence of samples from the current seed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
 }
 
 impl Default for PinkNoiseGenNode {
@@ -45,28 +57,21 @@ impl Default for PinkNoiseGenNode {
         Self {
             volume: Volume::Linear(0.4),
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+            seed: DEFAULT_SEED,
+            reset: Notify::new(()),
         }
     }
 }
 
-/// The configuration for a [`PinkNoiseGenNode`]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
-#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct PinkNoiseGenConfig {
-    /// The starting seed. This cannot be zero.
-    pub seed: i32,
-}
-
-impl Default for PinkNoiseGenConfig {
-    fn default() -> Self {
-        Self { seed: 17 }
-    }
-}
+/// The former configuration for a [`PinkNoiseGenNode`].
+///
+/// The node now uses [`EmptyConfig`]. To migrate, replace
+/// `PinkNoiseGenConfig { seed }` with [`PinkNoiseGenNode::seed`].
+#[deprecated(note = "use `EmptyConfig` and set `PinkNoiseGenNode::seed` instead")]
+pub type PinkNoiseGenConfig = EmptyConfig;
 
 impl AudioNode for PinkNoiseGenNode {
-    type Configuration = PinkNoiseGenConfig;
+    type Configuration = EmptyConfig;
 
     fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
         Ok(AudioNodeInfo::new()
@@ -79,12 +84,9 @@ impl AudioNode for PinkNoiseGenNode {
 
     fn construct_processor(
         &self,
-        config: &Self::Configuration,
+        _config: &Self::Configuration,
         cx: ConstructProcessorContext,
     ) -> Result<impl AudioNodeProcessor, NodeError> {
-        // Seed cannot be zero.
-        let seed = if config.seed == 0 { 17 } else { config.seed };
-
         Ok(Processor {
             gain: SmoothedParam::new(
                 self.volume.amp_clamped(DEFAULT_MIN_AMP),
@@ -95,9 +97,7 @@ impl AudioNode for PinkNoiseGenNode {
                 cx.stream_info.sample_rate,
             ),
             params: *self,
-            fpd: seed,
-            contrib: [0; 5],
-            accum: 0,
+            generator: PinkNoise::new(self.seed),
         })
     }
 }
@@ -106,13 +106,7 @@ impl AudioNode for PinkNoiseGenNode {
 struct Processor {
     params: PinkNoiseGenNode,
     gain: SmoothedParam,
-
-    // white noise generator state
-    fpd: i32,
-
-    // filter stage contributions
-    contrib: [i32; 5],
-    accum: i32,
+    generator: PinkNoise,
 }
 
 impl AudioNodeProcessor for Processor {
@@ -125,6 +119,12 @@ impl AudioNodeProcessor for Processor {
                 PinkNoiseGenNodePatch::SmoothSeconds(seconds) => {
                     self.gain.set_smooth_seconds(seconds, info.sample_rate);
                 }
+                PinkNoiseGenNodePatch::Seed(seed) => {
+                    self.generator.reset(seed);
+                }
+                PinkNoiseGenNodePatch::Reset(_) => {
+                    self.generator.reset(self.params.seed);
+                }
             }
 
             self.params.apply(patch);
@@ -143,42 +143,63 @@ impl AudioNodeProcessor for Processor {
         }
 
         for s in buffers.outputs[0].iter_mut() {
-            // i16[0,32767]
-            let randu: i16 = (rng(&mut self.fpd) & 0x7fff) as i16;
-
-            // i32[-32768,32767]
-            let r_bytes = rng(&mut self.fpd).to_ne_bytes();
-            let randv: i32 = i16::from_ne_bytes([r_bytes[0], r_bytes[1]]) as i32;
-
-            if randu < COEFF_SUM[0] {
-                update_contrib::<0>(&mut self.accum, &mut self.contrib, randv);
-            } else if randu < COEFF_SUM[1] {
-                update_contrib::<1>(&mut self.accum, &mut self.contrib, randv);
-            } else if randu < COEFF_SUM[2] {
-                update_contrib::<2>(&mut self.accum, &mut self.contrib, randv);
-            } else if randu < COEFF_SUM[3] {
-                update_contrib::<3>(&mut self.accum, &mut self.contrib, randv);
-            } else if randu < COEFF_SUM[4] {
-                update_contrib::<4>(&mut self.accum, &mut self.contrib, randv);
-            }
-
-            // Get a random normalized value in the range `[-1.0, 1.0]`.
-            let r = self.accum as f32 * (1.0 / 2_147_483_648.0);
-
-            *s = r * self.gain.next_smoothed();
+            *s = self.generator.next_sample() * self.gain.next_smoothed();
         }
 
         ProcessStatus::OutputsModified
     }
 }
 
-#[inline(always)]
-fn rng(fpd: &mut i32) -> i32 {
-    *fpd ^= *fpd << 13;
-    *fpd ^= *fpd >> 17;
-    *fpd ^= *fpd << 5;
+/// The state of a pink noise generator.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PinkNoise {
+    // white noise generator state
+    fpd: i32,
+
+    // filter stage contributions
+    contrib: [i32; 5],
+    accum: i32,
+}
 
-    *fpd
+impl PinkNoise {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            fpd: rng_state_from_seed(seed),
+            contrib: [0; 5],
+            accum: 0,
+        }
+    }
+
+    /// Restart the sequence of samples from the given seed.
+    pub fn reset(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// Generate the next sample in the range `[-1.0, 1.0]`.
+    #[inline(always)]
+    pub fn next_sample(&mut self) -> f32 {
+        // i16[0,32767]
+        let randu: i16 = (rng(&mut self.fpd) & 0x7fff) as i16;
+
+        // i32[-32768,32767]
+        let r_bytes = rng(&mut self.fpd).to_ne_bytes();
+        let randv: i32 = i16::from_ne_bytes([r_bytes[0], r_bytes[1]]) as i32;
+
+        if randu < COEFF_SUM[0] {
+            update_contrib::<0>(&mut self.accum, &mut self.contrib, randv);
+        } else if randu < COEFF_SUM[1] {
+            update_contrib::<1>(&mut self.accum, &mut self.contrib, randv);
+        } else if randu < COEFF_SUM[2] {
+            update_contrib::<2>(&mut self.accum, &mut self.contrib, randv);
+        } else if randu < COEFF_SUM[3] {
+            update_contrib::<3>(&mut self.accum, &mut self.contrib, randv);
+        } else if randu < COEFF_SUM[4] {
+            update_contrib::<4>(&mut self.accum, &mut self.contrib, randv);
+        }
+
+        // Get a random normalized value in the range `[-1.0, 1.0]`.
+        self.accum as f32 * (1.0 / 2_147_483_648.0)
+    }
 }
 
 #[inline(always)]
@@ -3,19 +3,21 @@
 use firewheel_core::node::NodeError;
 use firewheel_core::{
     channel_config::{ChannelConfig, ChannelCount},
-    diff::{Diff, Patch},
+    diff::{Diff, Notify, Patch},
     dsp::{
         filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
         volume::{DEFAULT_MIN_AMP, Volume},
     },
     event::ProcEvents,
     node::{
-        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
-        ProcExtra, ProcInfo, ProcessStatus,
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
+        ProcBuffers, ProcExtra, ProcInfo, ProcessStatus,
     },
     param::smoother::{SmoothedParam, SmootherConfig},
 };
 
+use super::{DEFAULT_SEED, rng_state_from_seed};
+
 /// A simple node that generates white noise (Mono output only)
 #[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
@@ -33,6 +35,16 @@ pub struct WhiteNoiseGenNode {
     /// roughly equal to a typical block size of 1024 samples (23 ms) to
     /// eliminate stair-stepping for most games.
     pub smooth_seconds: f32,
+    /// The seed of the random number generator.
+    ///
+    /// The same seed always produces the same sequence of samples. Changing
+    /// this value reseeds the generator and restarts the sequence.
+    ///
+    /// By default this is set to `17`.
+    pub seed: u64,
+    /// Restart the sequence of samples from the current seed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
 }
 
 impl Default for WhiteNoiseGenNode {
@@ -40,28 +52,21 @@ impl Default for WhiteNoiseGenNode {
         Self {
             volume: Volume::Linear(0.4),
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+            seed: DEFAULT_SEED,
+            reset: Notify::new(()),
         }
     }
 }
 
-/// The configuration for a [`WhiteNoiseGenNode`]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
-#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct WhiteNoiseGenConfig {
-    /// The starting seed. This cannot be zero.
-    pub seed: i32,
-}
-
-impl Default for WhiteNoiseGenConfig {
-    fn default() -> Self {
-        Self { seed: 17 }
-    }
-}
+/// The former configuration for a [`WhiteNoiseGenNode`].
+///
+/// The node now uses [`EmptyConfig`]. To migrate, replace
+/// `WhiteNoiseGenConfig { seed }` with [`WhiteNoiseGenNode::seed`].
+#[deprecated(note = "use `EmptyConfig` and set `WhiteNoiseGenNode::seed` instead")]
+pub type WhiteNoiseGenConfig = EmptyConfig;
 
 impl AudioNode for WhiteNoiseGenNode {
-    type Configuration = WhiteNoiseGenConfig;
+    type Configuration = EmptyConfig;
 
     fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
         Ok(AudioNodeInfo::new()
@@ -74,14 +79,11 @@ impl AudioNode for WhiteNoiseGenNode {
 
     fn construct_processor(
         &self,
-        config: &Self::Configuration,
+        _config: &Self::Configuration,
         cx: ConstructProcessorContext,
     ) -> Result<impl AudioNodeProcessor, NodeError> {
-        // Seed cannot be zero.
-        let seed = if config.seed == 0 { 17 } else { config.seed };
-
         Ok(Processor {
-            fpd: seed,
+            fpd: rng_state_from_seed(self.seed),
             gain: SmoothedParam::new(
                 self.volume.amp_clamped(DEFAULT_MIN_AMP),
                 SmootherConfig {
@@ -102,6 +104,13 @@ struct Processor {
     gain: SmoothedParam,
 }
 
+impl Processor {
+    /// Restart the sequence of samples from the given seed.
+    fn reset(&mut self, seed: u64) {
+        self.fpd = rng_state_from_seed(seed);
+    }
+}
+
 impl AudioNodeProcessor for Processor {
     fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
         for patch in events.drain_patches::<WhiteNoiseGenNode>() {
@@ -112,6 +121,12 @@ impl AudioNodeProcessor for Processor {
                 WhiteNoiseGenNodePatch::SmoothSeconds(seconds) => {
                     self.gain.set_smooth_seconds(seconds, info.sample_rate);
                 }
+                WhiteNoiseGenNodePatch::Seed(seed) => {
+                    self.reset(seed);
+                }
+                WhiteNoiseGenNodePatch::Reset(_) => {
+                    self.reset(self.params.seed);
+                }
             }
 
             self.params.apply(patch);
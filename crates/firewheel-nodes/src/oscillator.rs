@@ -0,0 +1,476 @@
+//! A band-limited oscillator node.
+
+use core::f32::consts::TAU;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Patch},
+    dsp::{
+        filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+        volume::{DEFAULT_MIN_AMP, Volume},
+    },
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, VoiceNode,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+pub const DEFAULT_MIN_FREQ_HZ: f32 = 1.0;
+pub const DEFAULT_MAX_FREQ_HZ: f32 = 20_000.0;
+pub const MAX_DETUNE_CENTS: f32 = 2_400.0;
+/// The maximum number of unison voices in an [`OscillatorNode`].
+pub const MAX_UNISON_VOICES: u32 = 16;
+pub const MAX_DETUNE_SPREAD_CENTS: f32 = 200.0;
+
+/// The shape of the waveform of an [`OscillatorNode`].
+///
+/// All waveforms except [`Waveform::Sine`] are band-limited with PolyBLEP
+/// (or PolyBLAMP for [`Waveform::Triangle`]), which greatly reduces aliasing
+/// compared to their naive counterparts.
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+}
+
+/// The configuration of an [`OscillatorNode`]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OscillatorConfig {
+    /// If `true`, then the node has a stereo output and the unison voices
+    /// are spread across the stereo field by [`OscillatorNode::stereo_width`].
+    /// Otherwise the node has a mono output.
+    ///
+    /// By default this is set to `false`.
+    pub stereo: bool,
+}
+
+/// A monophonic oscillator node which outputs a band-limited sine,
+/// triangle, saw, or square wave
+///
+/// Setting [`OscillatorNode::voices`] above `1` stacks detuned copies of the
+/// waveform (unison), i.e. for a "supersaw" sound.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OscillatorNode {
+    /// The frequency of the oscillator in hertz, in the range
+    /// `[1.0, 20_000.0]`.
+    ///
+    /// By default this is set to `440.0`.
+    pub frequency_hz: f32,
+    /// The shape of the waveform.
+    ///
+    /// Changing the waveform takes effect immediately and is not smoothed.
+    ///
+    /// By default this is set to [`Waveform::Sine`].
+    pub waveform: Waveform,
+    /// The amount to detune the oscillator from `frequency_hz` in cents,
+    /// in the range `[-2400.0, 2400.0]`.
+    ///
+    /// By default this is set to `0.0`.
+    pub detune: f32,
+    /// The overall volume.
+    ///
+    /// NOTE, an oscillator at `Volume::Linear(1.0) or Volume::Decibels(0.0)`
+    /// volume is *LOUD*, prefer to use a value `Volume::Linear(0.5) or
+    /// Volume::Decibels(-12.0)`.
+    ///
+    /// By default this is set to `Volume::Linear(0.5)`.
+    pub volume: Volume,
+    /// The number of unison voices in the range `[1, 16]`.
+    ///
+    /// Each voice is detuned by a different amount within
+    /// [`OscillatorNode::detune_spread`], and the voices are summed with gain
+    /// compensation so that the overall loudness stays roughly the same.
+    /// Each voice costs about as much CPU as a single oscillator.
+    ///
+    /// By default this is set to `1`.
+    pub voices: u32,
+    /// The total spread in cents between the lowest and the highest unison
+    /// voice, in the range `[0.0, 200.0]`.
+    ///
+    /// This has no effect when [`OscillatorNode::voices`] is `1`.
+    ///
+    /// By default this is set to `20.0`.
+    pub detune_spread: f32,
+    /// How far the unison voices are spread across the stereo field in the
+    /// range `[0.0, 1.0]`, where `0.0` is mono and `1.0` pans the outermost
+    /// voices hard left and right.
+    ///
+    /// This has no effect unless [`OscillatorConfig::stereo`] is `true`.
+    ///
+    /// By default this is set to `0.5`.
+    pub stereo_width: f32,
+    /// The time in seconds of the internal smoothing filter.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for OscillatorNode {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 440.0,
+            waveform: Waveform::Sine,
+            detune: 0.0,
+            volume: Volume::Linear(0.5),
+            voices: 1,
+            detune_spread: 20.0,
+            stereo_width: 0.5,
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl OscillatorNode {
+    /// The frequency of the oscillator in hertz after applying the detune.
+    pub fn detuned_frequency_hz(&self) -> f32 {
+        self.frequency_hz
+            .clamp(DEFAULT_MIN_FREQ_HZ, DEFAULT_MAX_FREQ_HZ)
+            * (self.detune.clamp(-MAX_DETUNE_CENTS, MAX_DETUNE_CENTS) / 1_200.0).exp2()
+    }
+}
+
+/// Playing an [`OscillatorNode`] as a voice sets its frequency from the note
+/// and its volume from the velocity, where a velocity of `1.0` is the default
+/// volume of `Volume::Linear(0.5)`. Releasing the note fades it to silence
+/// over [`OscillatorNode::smooth_seconds`].
+impl VoiceNode for OscillatorNode {
+    fn note_on(&mut self, note: u8, velocity: f32) {
+        self.frequency_hz = 440.0 * ((f32::from(note) - 69.0) / 12.0).exp2();
+        self.volume = Volume::Linear(0.5 * velocity.clamp(0.0, 1.0));
+    }
+
+    fn note_off(&mut self) {
+        self.volume = Volume::SILENT;
+    }
+}
+
+impl AudioNode for OscillatorNode {
+    type Configuration = OscillatorConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("oscillator")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::ZERO,
+                num_outputs: if config.stereo {
+                    ChannelCount::STEREO
+                } else {
+                    ChannelCount::MONO
+                },
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let smoother_config = SmootherConfig {
+            smooth_seconds: self.smooth_seconds,
+            ..Default::default()
+        };
+
+        let mut processor = Processor {
+            frequency_hz: SmoothedParam::new(
+                self.detuned_frequency_hz(),
+                smoother_config,
+                cx.stream_info.sample_rate,
+            ),
+            gain: SmoothedParam::new(
+                self.volume.amp_clamped(DEFAULT_MIN_AMP),
+                smoother_config,
+                cx.stream_info.sample_rate,
+            ),
+            params: *self,
+            voices: [Voice::default(); MAX_UNISON_VOICES as usize],
+            num_voices: 1,
+            voice_gain: 1.0,
+            sample_rate_recip: cx.stream_info.sample_rate_recip as f32,
+            stereo: config.stereo,
+        };
+
+        // Start each voice at a different phase so that the voices don't
+        // all line up (and sound like a single loud voice) at the start.
+        for (i, voice) in processor.voices.iter_mut().enumerate() {
+            voice.phase = wrap_phase(i as f32 * 0.618_034);
+        }
+        processor.update_voices();
+
+        Ok(processor)
+    }
+}
+
+/// The PolyBLEP residual for a unit step at phase `0.0`, where `dt` is the
+/// phase increment per frame.
+fn poly_blep(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let t = t / dt;
+        t + t - t * t - 1.0
+    } else if t > 1.0 - dt {
+        let t = (t - 1.0) / dt;
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// The PolyBLAMP residual for a unit change in slope at phase `0.0`, where
+/// `dt` is the phase increment per frame.
+fn poly_blamp(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let t = t / dt - 1.0;
+        -(1.0 / 3.0) * t * t * t
+    } else if t > 1.0 - dt {
+        let t = (t - 1.0) / dt + 1.0;
+        (1.0 / 3.0) * t * t * t
+    } else {
+        0.0
+    }
+}
+
+/// The phase increment per frame for the given frequency.
+///
+/// This is kept below the Nyquist frequency so that the PolyBLEP residuals
+/// around each discontinuity never overlap.
+fn phase_inc(frequency_hz: f32, sample_rate_recip: f32) -> f32 {
+    (frequency_hz * sample_rate_recip).min(0.45)
+}
+
+/// Wrap the given phase into the range `[0.0, 1.0)`.
+fn wrap_phase(phase: f32) -> f32 {
+    phase - phase.floor()
+}
+
+impl Waveform {
+    /// Get the band-limited value of the waveform in the range `[-1.0, 1.0]`
+    /// at the given phase in the range `[0.0, 1.0)`, where `dt` is the phase
+    /// increment per frame.
+    fn value_at(&self, t: f32, dt: f32) -> f32 {
+        match self {
+            Self::Sine => (t * TAU).sin(),
+            Self::Triangle => {
+                let naive = 1.0 - 4.0 * (t - 0.5).abs();
+                naive + 4.0 * dt * (poly_blamp(t, dt) - poly_blamp(wrap_phase(t + 0.5), dt))
+            }
+            Self::Saw => 2.0 * t - 1.0 - poly_blep(t, dt),
+            Self::Square => {
+                let naive = if t < 0.5 { 1.0 } else { -1.0 };
+                naive + poly_blep(t, dt) - poly_blep(wrap_phase(t + 0.5), dt)
+            }
+        }
+    }
+}
+
+/// The state of a single unison voice.
+#[derive(Default, Debug, Clone, Copy)]
+struct Voice {
+    /// The phase of the voice in the range `[0.0, 1.0)`.
+    phase: f32,
+    /// The ratio of the frequency of this voice to the base frequency.
+    freq_ratio: f32,
+    gain_l: f32,
+    gain_r: f32,
+}
+
+struct Processor {
+    params: OscillatorNode,
+    frequency_hz: SmoothedParam,
+    gain: SmoothedParam,
+
+    voices: [Voice; MAX_UNISON_VOICES as usize],
+    num_voices: usize,
+    /// The gain compensation applied to the sum of the voices.
+    voice_gain: f32,
+    sample_rate_recip: f32,
+    stereo: bool,
+}
+
+impl Processor {
+    /// Recalculate the detune and the panning of each unison voice.
+    fn update_voices(&mut self) {
+        self.num_voices = self.params.voices.clamp(1, MAX_UNISON_VOICES) as usize;
+        // The voices are uncorrelated, so their powers (not their amplitudes)
+        // add up.
+        self.voice_gain = (self.num_voices as f32).sqrt().recip();
+
+        let spread = self
+            .params
+            .detune_spread
+            .clamp(0.0, MAX_DETUNE_SPREAD_CENTS);
+        let width = self.params.stereo_width.clamp(0.0, 1.0);
+
+        for (i, voice) in self.voices[..self.num_voices].iter_mut().enumerate() {
+            // The position of this voice in the range `[-1.0, 1.0]`.
+            let position = if self.num_voices > 1 {
+                (2 * i) as f32 / (self.num_voices - 1) as f32 - 1.0
+            } else {
+                0.0
+            };
+
+            voice.freq_ratio = (position * spread * 0.5 / 1_200.0).exp2();
+
+            // Only attenuate the opposite channel so that a centered voice
+            // has the same loudness as in mono.
+            let pan = position * width;
+            voice.gain_l = (1.0 - pan).min(1.0);
+            voice.gain_r = (1.0 + pan).min(1.0);
+        }
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        let mut frequency_changed = false;
+        let mut voices_changed = false;
+
+        for patch in events.drain_patches::<OscillatorNode>() {
+            match patch {
+                OscillatorNodePatch::FrequencyHz(_) | OscillatorNodePatch::Detune(_) => {
+                    frequency_changed = true;
+                }
+                OscillatorNodePatch::Volume(volume) => {
+                    self.gain.set_value(volume.amp_clamped(DEFAULT_MIN_AMP));
+                }
+                OscillatorNodePatch::Voices(_)
+                | OscillatorNodePatch::DetuneSpread(_)
+                | OscillatorNodePatch::StereoWidth(_) => {
+                    voices_changed = true;
+                }
+                OscillatorNodePatch::SmoothSeconds(seconds) => {
+                    self.frequency_hz
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                    self.gain.set_smooth_seconds(seconds, info.sample_rate);
+                }
+                _ => {}
+            }
+
+            self.params.apply(patch);
+        }
+
+        if frequency_changed {
+            self.frequency_hz
+                .set_value(self.params.detuned_frequency_hz());
+        }
+        if voices_changed {
+            self.update_voices();
+        }
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.frequency_hz.reset_to_target();
+        self.gain.reset_to_target();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        if self.gain.has_settled_at(0.0) {
+            // Keep the phases running so that they stay continuous.
+            let frequency_hz = self.frequency_hz.target_value();
+            for voice in self.voices[..self.num_voices].iter_mut() {
+                let dt = phase_inc(frequency_hz * voice.freq_ratio, self.sample_rate_recip);
+                voice.phase = wrap_phase(voice.phase + dt * info.frames as f32);
+            }
+            self.frequency_hz.reset_to_target();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let waveform = self.params.waveform;
+        let sample_rate_recip = self.sample_rate_recip;
+        let voices = &mut self.voices[..self.num_voices];
+
+        if self.stereo {
+            let (out_l, out_r) = buffers.outputs.split_first_mut().unwrap();
+            let out_l = &mut out_l[..info.frames];
+            let out_r = &mut out_r[0][..info.frames];
+
+            for (l, r) in out_l.iter_mut().zip(out_r.iter_mut()) {
+                let frequency_hz = self.frequency_hz.next_smoothed();
+                let gain = self.gain.next_smoothed() * self.voice_gain;
+
+                let (mut sum_l, mut sum_r) = (0.0, 0.0);
+                for voice in voices.iter_mut() {
+                    let dt = phase_inc(frequency_hz * voice.freq_ratio, sample_rate_recip);
+                    let s = waveform.value_at(voice.phase, dt);
+
+                    sum_l += s * voice.gain_l;
+                    sum_r += s * voice.gain_r;
+                    voice.phase = wrap_phase(voice.phase + dt);
+                }
+
+                *l = sum_l * gain;
+                *r = sum_r * gain;
+            }
+        } else {
+            for s in buffers.outputs[0][..info.frames].iter_mut() {
+                let frequency_hz = self.frequency_hz.next_smoothed();
+                let gain = self.gain.next_smoothed() * self.voice_gain;
+
+                let mut sum = 0.0;
+                for voice in voices.iter_mut() {
+                    let dt = phase_inc(frequency_hz * voice.freq_ratio, sample_rate_recip);
+
+                    sum += waveform.value_at(voice.phase, dt);
+                    voice.phase = wrap_phase(voice.phase + dt);
+                }
+
+                *s = sum * gain;
+            }
+        }
+
+        self.frequency_hz.settle();
+        self.gain.settle();
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.sample_rate_recip = stream_info.sample_rate_recip as f32;
+        self.frequency_hz
+            .update_sample_rate(stream_info.sample_rate);
+        self.gain.update_sample_rate(stream_info.sample_rate);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn band_limited_saw_is_smoothed_at_wrap() {
+        let dt = 0.05;
+
+        // The naive saw jumps from `1.0` to `-1.0` when the phase wraps, but
+        // the band-limited saw spreads that jump across the frames on either
+        // side of it.
+        let before = Waveform::Saw.value_at(1.0 - dt * 0.5, dt);
+        let after = Waveform::Saw.value_at(dt * 0.5, dt);
+
+        assert!((before - after).abs() < 1.5);
+        assert!(Waveform::Saw.value_at(0.5, dt).abs() < 1e-6);
+    }
+}
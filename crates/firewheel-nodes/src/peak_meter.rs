@@ -1,13 +1,16 @@
 #[cfg(not(feature = "std"))]
 use num_traits::Float;
 
-use bevy_platform::sync::{Arc, atomic::Ordering};
+use bevy_platform::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
 use firewheel_core::node::NodeError;
 use firewheel_core::{
     atomic_float::AtomicF32,
     channel_config::{ChannelConfig, ChannelCount},
     diff::{Diff, Patch},
-    dsp::volume::{DbMeterNormalizer, amp_to_db},
+    dsp::volume::{DbMeterNormalizer, amp_to_db, db_to_amp},
     event::ProcEvents,
     node::{
         AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
@@ -166,12 +169,46 @@ pub type PeakMeterStereoNode = PeakMeterNode<2>;
 
 /// A node that calculates the peak amplitude of a signal, and then sends that value
 /// to [`PeakMeterState`].
-#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// In addition to the raw peak value of each processed block, this node also
+/// computes a decaying peak value and a held peak value with sample-accurate
+/// ballistics, so that meters don't depend on the frame rate of the UI.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
 #[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PeakMeterNode<const NUM_CHANNELS: usize = 2> {
     pub enabled: bool,
+
+    /// The amount of time in seconds that the held peak value will hold
+    /// its value before it starts to decay.
+    ///
+    /// By default this is set to `1.0`.
+    pub hold_seconds: f32,
+
+    /// The rate at which the decaying peak value (and the held peak value
+    /// once its hold time has elapsed) falls, in decibels per second.
+    ///
+    /// By default this is set to `20.0`.
+    pub decay_db_per_second: f32,
+
+    /// If the absolute value of any sample exceeds this threshold (in
+    /// decibels), then that channel is flagged as clipped in
+    /// [`PeakMeterState`] until [`PeakMeterState::clear_clip`] is called.
+    ///
+    /// By default this is set to `0.0` (0 dBFS).
+    pub clip_threshold_db: f32,
+}
+
+impl<const NUM_CHANNELS: usize> Default for PeakMeterNode<NUM_CHANNELS> {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hold_seconds: 1.0,
+            decay_db_per_second: 20.0,
+            clip_threshold_db: 0.0,
+        }
+    }
 }
 
 pub type PeakMeterMonoState = PeakMeterState<1>;
@@ -191,6 +228,9 @@ impl<const NUM_CHANNELS: usize> PeakMeterState<NUM_CHANNELS> {
         Self {
             shared_state: Arc::new(SharedState {
                 peak_gains: core::array::from_fn(|_| AtomicF32::new(0.0)),
+                current_gains: core::array::from_fn(|_| AtomicF32::new(0.0)),
+                held_peak_gains: core::array::from_fn(|_| AtomicF32::new(0.0)),
+                clip_mask: AtomicU64::new(0),
             }),
         }
     }
@@ -204,13 +244,79 @@ impl<const NUM_CHANNELS: usize> PeakMeterState<NUM_CHANNELS> {
     /// If the node is currently disabled, then this will return a value
     /// of `f32::NEG_INFINITY` (silence) for all channels.
     pub fn peak_gain_db(&self, min_db: f32) -> [f32; NUM_CHANNELS] {
-        core::array::from_fn(|i| {
-            let db = amp_to_db(self.shared_state.peak_gains[i].load(Ordering::Relaxed));
-            if db <= min_db { f32::NEG_INFINITY } else { db }
-        })
+        load_db(&self.shared_state.peak_gains, min_db)
+    }
+
+    /// Get the latest decaying peak values for each channel in decibels.
+    ///
+    /// This value jumps up to new peaks immediately, and then falls at the
+    /// rate set by [`PeakMeterNode::decay_db_per_second`].
+    ///
+    /// * `min_db` - If a peak value is less than or equal to this value, then it
+    ///   will be clamped to `f32::NEG_INFINITY` (silence). (You can use
+    ///   [firewheel_core::dsp::volume::DEFAULT_MIN_DB].)
+    ///
+    /// If the node is currently disabled, then this will return a value
+    /// of `f32::NEG_INFINITY` (silence) for all channels.
+    pub fn current_db(&self, min_db: f32) -> [f32; NUM_CHANNELS] {
+        load_db(&self.shared_state.current_gains, min_db)
+    }
+
+    /// Get the latest held peak values for each channel in decibels.
+    ///
+    /// This value holds the maximum peak for [`PeakMeterNode::hold_seconds`],
+    /// and then falls at the rate set by [`PeakMeterNode::decay_db_per_second`].
+    ///
+    /// * `min_db` - If a peak value is less than or equal to this value, then it
+    ///   will be clamped to `f32::NEG_INFINITY` (silence). (You can use
+    ///   [firewheel_core::dsp::volume::DEFAULT_MIN_DB].)
+    ///
+    /// If the node is currently disabled, then this will return a value
+    /// of `f32::NEG_INFINITY` (silence) for all channels.
+    pub fn held_peak_db(&self, min_db: f32) -> [f32; NUM_CHANNELS] {
+        load_db(&self.shared_state.held_peak_gains, min_db)
+    }
+
+    /// Returns `true` if any channel has clipped since the last call to
+    /// [`PeakMeterState::clear_clip`].
+    ///
+    /// See [`PeakMeterNode::clip_threshold_db`].
+    pub fn clipped(&self) -> bool {
+        self.clip_mask() != 0
+    }
+
+    /// Returns which channels have clipped since the last call to
+    /// [`PeakMeterState::clear_clip`].
+    ///
+    /// See [`PeakMeterNode::clip_threshold_db`].
+    pub fn clipped_channels(&self) -> [bool; NUM_CHANNELS] {
+        let mask = self.clip_mask();
+        core::array::from_fn(|i| mask & (1 << i) != 0)
+    }
+
+    /// A bit mask of the channels which have clipped since the last call to
+    /// [`PeakMeterState::clear_clip`]. The first bit (`0x1`) is the first
+    /// channel, the second bit is the second channel, and so on.
+    pub fn clip_mask(&self) -> u64 {
+        self.shared_state.clip_mask.load(Ordering::Relaxed)
+    }
+
+    /// Clear the clip indicators of all channels.
+    pub fn clear_clip(&self) {
+        self.shared_state.clip_mask.store(0, Ordering::Relaxed);
     }
 }
 
+fn load_db<const NUM_CHANNELS: usize>(
+    gains: &[AtomicF32; NUM_CHANNELS],
+    min_db: f32,
+) -> [f32; NUM_CHANNELS] {
+    core::array::from_fn(|i| {
+        let db = amp_to_db(gains[i].load(Ordering::Relaxed));
+        if db <= min_db { f32::NEG_INFINITY } else { db }
+    })
+}
+
 impl<const NUM_CHANNELS: usize> AudioNode for PeakMeterNode<NUM_CHANNELS> {
     type Configuration = EmptyConfig;
 
@@ -229,36 +335,123 @@ impl<const NUM_CHANNELS: usize> AudioNode for PeakMeterNode<NUM_CHANNELS> {
         _config: &Self::Configuration,
         cx: ConstructProcessorContext,
     ) -> Result<impl AudioNodeProcessor, NodeError> {
-        Ok(Processor {
+        let mut processor = Processor {
             params: *self,
             shared_state: Arc::clone(
                 &cx.custom_state::<PeakMeterState<NUM_CHANNELS>>()
                     .unwrap()
                     .shared_state,
             ),
-        })
+            ballistics: [Ballistics::default(); NUM_CHANNELS],
+            decay_coeff: 0.0,
+            hold_frames: 0,
+            clip_threshold_amp: db_to_amp(self.clip_threshold_db),
+        };
+
+        processor.update_coeffs(cx.stream_info.sample_rate.get() as f32);
+
+        Ok(processor)
     }
 }
 
 struct SharedState<const NUM_CHANNELS: usize> {
     peak_gains: [AtomicF32; NUM_CHANNELS],
+    current_gains: [AtomicF32; NUM_CHANNELS],
+    held_peak_gains: [AtomicF32; NUM_CHANNELS],
+    clip_mask: AtomicU64,
+}
+
+/// Values below this amplitude (around -160dB) are flushed to zero.
+const MIN_GAIN: f32 = 1.0e-8;
+
+/// The state of the peak-hold and decay ballistics of a single channel.
+#[derive(Default, Debug, Clone, Copy)]
+struct Ballistics {
+    current: f32,
+    held_peak: f32,
+    hold_frames_left: usize,
+}
+
+impl Ballistics {
+    fn process(&mut self, samples: &[f32], decay_coeff: f32, hold_frames: usize) {
+        for &s in samples.iter() {
+            let s = s.abs();
+
+            self.current = (self.current * decay_coeff).max(s);
+
+            if s >= self.held_peak {
+                self.held_peak = s;
+                self.hold_frames_left = hold_frames;
+            } else if self.hold_frames_left > 0 {
+                self.hold_frames_left -= 1;
+            } else {
+                self.held_peak *= decay_coeff;
+            }
+        }
+
+        self.flush_to_zero();
+    }
+
+    fn process_silence(&mut self, frames: usize, decay_coeff: f32) {
+        self.current *= decay_coeff.powi(frames as i32);
+
+        if self.hold_frames_left >= frames {
+            self.hold_frames_left -= frames;
+        } else {
+            self.held_peak *= decay_coeff.powi((frames - self.hold_frames_left) as i32);
+            self.hold_frames_left = 0;
+        }
+
+        self.flush_to_zero();
+    }
+
+    fn flush_to_zero(&mut self) {
+        if self.current < MIN_GAIN {
+            self.current = 0.0;
+        }
+        if self.held_peak < MIN_GAIN {
+            self.held_peak = 0.0;
+        }
+    }
 }
 
 struct Processor<const NUM_CHANNELS: usize> {
     params: PeakMeterNode<NUM_CHANNELS>,
     shared_state: Arc<SharedState<NUM_CHANNELS>>,
+    ballistics: [Ballistics; NUM_CHANNELS],
+    /// The amount the decaying values are multiplied by each frame.
+    decay_coeff: f32,
+    hold_frames: usize,
+    clip_threshold_amp: f32,
 }
 
 impl<const NUM_CHANNELS: usize> Processor<NUM_CHANNELS> {
     fn reset(&mut self) {
-        for ch in self.shared_state.peak_gains.iter() {
-            ch.store(0.0, Ordering::Relaxed);
+        for ((peak, current), held_peak) in self
+            .shared_state
+            .peak_gains
+            .iter()
+            .zip(self.shared_state.current_gains.iter())
+            .zip(self.shared_state.held_peak_gains.iter())
+        {
+            peak.store(0.0, Ordering::Relaxed);
+            current.store(0.0, Ordering::Relaxed);
+            held_peak.store(0.0, Ordering::Relaxed);
         }
+
+        self.ballistics = [Ballistics::default(); NUM_CHANNELS];
+    }
+
+    fn update_coeffs(&mut self, sample_rate: f32) {
+        let decay_db_per_frame = self.params.decay_db_per_second.max(0.0) / sample_rate;
+
+        self.decay_coeff = 10.0f32.powf(-decay_db_per_frame / 20.0);
+        self.hold_frames = (self.params.hold_seconds.max(0.0) * sample_rate).round() as usize;
     }
 }
 
 impl<const NUM_CHANNELS: usize> AudioNodeProcessor for Processor<NUM_CHANNELS> {
-    fn events(&mut self, _info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
         let was_enabled = self.params.enabled;
 
         for patch in events.drain_patches::<PeakMeterNode<NUM_CHANNELS>>() {
@@ -268,6 +461,9 @@ impl<const NUM_CHANNELS: usize> AudioNodeProcessor for Processor<NUM_CHANNELS> {
         if was_enabled && !self.params.enabled {
             self.reset();
         }
+
+        self.clip_threshold_amp = db_to_amp(self.params.clip_threshold_db);
+        self.update_coeffs(info.sample_rate.get() as f32);
     }
 
     fn bypassed(&mut self, _bypassed: bool) {
@@ -284,22 +480,74 @@ impl<const NUM_CHANNELS: usize> AudioNodeProcessor for Processor<NUM_CHANNELS> {
             return ProcessStatus::Bypass;
         }
 
-        for (i, (in_ch, peak_shared)) in buffers
+        for (i, (in_ch, ballistics)) in buffers
             .inputs
             .iter()
-            .zip(self.shared_state.peak_gains.iter())
+            .zip(self.ballistics.iter_mut())
             .enumerate()
         {
             if info.in_silence_mask.is_channel_silent(i) {
-                peak_shared.store(0.0, Ordering::Relaxed);
+                self.shared_state.peak_gains[i].store(0.0, Ordering::Relaxed);
+
+                ballistics.process_silence(info.frames, self.decay_coeff);
             } else {
-                peak_shared.store(
-                    firewheel_core::dsp::algo::max_peak(in_ch),
-                    Ordering::Relaxed,
-                );
+                let peak = firewheel_core::dsp::algo::max_peak(in_ch);
+
+                self.shared_state.peak_gains[i].store(peak, Ordering::Relaxed);
+
+                if peak > self.clip_threshold_amp {
+                    self.shared_state
+                        .clip_mask
+                        .fetch_or(1 << i, Ordering::Relaxed);
+                }
+
+                ballistics.process(&in_ch[..info.frames], self.decay_coeff, self.hold_frames);
             }
+
+            self.shared_state.current_gains[i].store(ballistics.current, Ordering::Relaxed);
+            self.shared_state.held_peak_gains[i].store(ballistics.held_peak, Ordering::Relaxed);
         }
 
         ProcessStatus::Bypass
     }
+
+    fn new_stream(
+        &mut self,
+        stream_info: &firewheel_core::StreamInfo,
+        _context: &mut firewheel_core::node::ProcStreamCtx,
+    ) {
+        self.update_coeffs(stream_info.sample_rate.get() as f32);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hold_then_decay() {
+        let sample_rate = 1000.0;
+        // 20 dB per 100 frames
+        let decay_coeff = 10.0f32.powf(-(200.0 / sample_rate) / 20.0);
+        let hold_frames = 50;
+
+        let mut b = Ballistics::default();
+        let mut samples = [0.0; 200];
+        samples[0] = 1.0;
+
+        b.process(&samples[..50], decay_coeff, hold_frames);
+        assert_eq!(b.held_peak, 1.0);
+        assert!(b.current < 1.0);
+
+        b.process(&samples[50..], decay_coeff, hold_frames);
+        assert!(b.held_peak < 1.0);
+        assert!((amp_to_db(b.held_peak) - -29.8).abs() < 0.5);
+        assert!((amp_to_db(b.current) - -39.8).abs() < 0.5);
+
+        let mut silent = Ballistics::default();
+        silent.process(&samples[..1], decay_coeff, hold_frames);
+        silent.process_silence(199, decay_coeff);
+        assert!((silent.held_peak - b.held_peak).abs() < 1.0e-4);
+        assert!((silent.current - b.current).abs() < 1.0e-4);
+    }
 }
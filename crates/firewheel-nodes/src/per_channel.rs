@@ -0,0 +1,161 @@
+use core::num::NonZeroU32;
+
+use firewheel_core::param::smoother::{SmoothedParam, SmootherConfig};
+
+/// A smoothed parameter which is shared by all channels (stereo-linked),
+/// but which can optionally be overridden for individual channels
+/// (dual-mono).
+pub(crate) struct PerChannelParam<const CHANNELS: usize> {
+    smoothers: [SmoothedParam; CHANNELS],
+    linked_value: f32,
+    channel_values: [Option<f32>; CHANNELS],
+    min: f32,
+    max: f32,
+}
+
+impl<const CHANNELS: usize> PerChannelParam<CHANNELS> {
+    pub fn new(
+        linked_value: f32,
+        channel_values: [Option<f32>; CHANNELS],
+        min: f32,
+        max: f32,
+        config: SmootherConfig,
+        sample_rate: NonZeroU32,
+    ) -> Self {
+        Self {
+            smoothers: core::array::from_fn(|ch| {
+                SmoothedParam::new(
+                    channel_values[ch].unwrap_or(linked_value).clamp(min, max),
+                    config,
+                    sample_rate,
+                )
+            }),
+            linked_value,
+            channel_values,
+            min,
+            max,
+        }
+    }
+
+    fn target(&self, ch: usize) -> f32 {
+        self.channel_values[ch]
+            .unwrap_or(self.linked_value)
+            .clamp(self.min, self.max)
+    }
+
+    /// Set the value shared by all channels which are not overridden.
+    pub fn set_value(&mut self, value: f32) {
+        self.linked_value = value;
+
+        for ch in 0..CHANNELS {
+            if self.channel_values[ch].is_none() {
+                self.smoothers[ch].set_value(self.target(ch));
+            }
+        }
+    }
+
+    /// Override the value for a single channel, or set it back to the
+    /// shared value with `None`.
+    pub fn set_channel_value(&mut self, ch: usize, value: Option<f32>) {
+        if ch >= CHANNELS {
+            return;
+        }
+
+        self.channel_values[ch] = value;
+        self.smoothers[ch].set_value(self.target(ch));
+    }
+
+    pub fn target_values(&self) -> [f32; CHANNELS] {
+        core::array::from_fn(|ch| self.smoothers[ch].target_value())
+    }
+
+    pub fn next_smoothed(&mut self) -> [f32; CHANNELS] {
+        core::array::from_fn(|ch| self.smoothers[ch].next_smoothed())
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.smoothers.iter().any(|s| s.is_smoothing())
+    }
+
+    /// Settle the smoothers that are close enough to their target values.
+    ///
+    /// Returns `true` if all channels are settled.
+    pub fn settle(&mut self) -> bool {
+        self.smoothers
+            .iter_mut()
+            .fold(true, |settled, s| s.settle() && settled)
+    }
+
+    pub fn reset_to_target(&mut self) {
+        for s in self.smoothers.iter_mut() {
+            s.reset_to_target();
+        }
+    }
+
+    pub fn set_smooth_seconds(&mut self, seconds: f32, sample_rate: NonZeroU32) {
+        for s in self.smoothers.iter_mut() {
+            s.set_smooth_seconds(seconds, sample_rate);
+        }
+    }
+
+    pub fn update_sample_rate(&mut self, sample_rate: NonZeroU32) {
+        for s in self.smoothers.iter_mut() {
+            s.update_sample_rate(sample_rate);
+        }
+    }
+}
+
+/// Serde does not support arrays with a generic length, so per-channel
+/// parameters are (de)serialized as a sequence instead.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_array {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    #[cfg(not(feature = "std"))]
+    use bevy_platform::prelude::Vec;
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+        array: &[T; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(array.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[T; N], D::Error> {
+        let values = Vec::<T>::deserialize(deserializer)?;
+        let len = values.len();
+
+        values
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"one value per channel"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn channel_overrides() {
+        let sample_rate = NonZeroU32::new(48_000).unwrap();
+        let mut param = PerChannelParam::<2>::new(
+            1_000.0,
+            [None, Some(50_000.0)],
+            20.0,
+            20_480.0,
+            SmootherConfig::default(),
+            sample_rate,
+        );
+
+        assert_eq!(param.target_values(), [1_000.0, 20_480.0]);
+
+        param.set_value(500.0);
+        assert_eq!(param.target_values(), [500.0, 20_480.0]);
+
+        param.set_channel_value(1, None);
+        param.set_channel_value(0, Some(2_000.0));
+        assert_eq!(param.target_values(), [2_000.0, 500.0]);
+    }
+}
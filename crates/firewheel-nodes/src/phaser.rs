@@ -0,0 +1,414 @@
+//! A phaser effect built from a cascade of allpass filters whose cutoff
+//! frequency is swept by an internal LFO (low frequency oscillator).
+
+use core::f32::consts::TAU;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Notify, Patch},
+    dsp::{
+        coeff_update::{CoeffUpdateFactor, CoeffUpdateMask},
+        filter::{
+            smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+            svf::{SvfCoeff, SvfCoeffSimd, SvfStateSimd},
+        },
+    },
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, ResettableNode,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+use crate::stereo_effect::StereoEffectConfig;
+
+pub const DEFAULT_MIN_RATE_HZ: f32 = 0.01;
+pub const DEFAULT_MAX_RATE_HZ: f32 = 20.0;
+pub const DEFAULT_MIN_CENTER_HZ: f32 = 40.0;
+pub const DEFAULT_MAX_CENTER_HZ: f32 = 10_000.0;
+pub const MAX_FEEDBACK: f32 = 0.95;
+/// The maximum number of allpass stages in a [`PhaserNode`].
+pub const MAX_STAGES: u32 = 12;
+
+/// The number of octaves the cutoff frequency of the allpass stages is
+/// swept above and below [`PhaserNode::center_hz`] when the depth is `1.0`.
+const SWEEP_OCTAVES: f32 = 2.0;
+/// A low Q gives each allpass stage a wide phase transition, which results
+/// in the broad, smooth notches typical of a phaser.
+const STAGE_Q: f32 = 0.5;
+const MIN_CUTOFF_HZ: f32 = 20.0;
+
+/// A phaser effect node
+///
+/// The input is passed through a cascade of allpass stages whose cutoff
+/// frequency is modulated around [`PhaserNode::center_hz`] by a sine LFO.
+/// Mixing the phase-shifted signal with the dry signal produces notches in
+/// the spectrum which sweep up and down.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaserNode {
+    /// The number of allpass stages in the range `[1, 12]`.
+    ///
+    /// Each stage adds one notch to the spectrum.
+    ///
+    /// By default this is set to `4`.
+    pub stages: u32,
+    /// The rate of the LFO in hertz, in the range `[0.01, 20.0]`.
+    ///
+    /// By default this is set to `0.5`.
+    pub rate_hz: f32,
+    /// How far the LFO sweeps the allpass stages around the center frequency
+    /// in the range `[0.0, 1.0]`, where `1.0` is two octaves in each direction.
+    ///
+    /// By default this is set to `0.7`.
+    pub depth: f32,
+    /// The amount of the output of the last stage which is fed back into the
+    /// first stage, in the range `[-0.95, 0.95]`.
+    ///
+    /// Higher values produce a more resonant effect.
+    ///
+    /// By default this is set to `0.3`.
+    pub feedback: f32,
+    /// The mix between the dry and the phase-shifted signal in the range
+    /// `[0.0, 1.0]`, where `0.0` is fully dry and `1.0` is fully wet.
+    ///
+    /// The notches are the deepest at `0.5`.
+    ///
+    /// By default this is set to `0.5`.
+    pub mix: f32,
+    /// The frequency in hertz the allpass stages are modulated around, in the
+    /// range `[40.0, 10_000.0]`.
+    ///
+    /// By default this is set to `800.0`.
+    pub center_hz: f32,
+    /// The phase offset of the right channel's LFO relative to the left
+    /// channel's LFO in the range `[0.0, 1.0)`, where `0.5` is half a cycle.
+    ///
+    /// By default this is set to `0.0`.
+    pub stereo_phase_offset: f32,
+    /// The time in seconds of the internal smoothing filter.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+    /// An exponent representing the rate at which the coefficients of the
+    /// allpass stages are updated as the LFO sweeps.
+    ///
+    /// Smaller values will produce less "stair-stepping" artifacts,
+    /// but will also consume more CPU.
+    ///
+    /// The resulting number of frames (samples in a single channel of audio)
+    /// that will elapse between each update is calculated as
+    /// `2^coeff_update_factor`.
+    ///
+    /// By default this is set to `4`.
+    pub coeff_update_factor: CoeffUpdateFactor,
+    /// Reset the phaser, clearing the state of its allpass stages.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
+}
+
+impl Default for PhaserNode {
+    fn default() -> Self {
+        Self {
+            stages: 4,
+            rate_hz: 0.5,
+            depth: 0.7,
+            feedback: 0.3,
+            mix: 0.5,
+            center_hz: 800.0,
+            stereo_phase_offset: 0.0,
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+            coeff_update_factor: CoeffUpdateFactor::default(),
+            reset: Notify::new(()),
+        }
+    }
+}
+
+impl AudioNode for PhaserNode {
+    type Configuration = StereoEffectConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("phaser")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::STEREO,
+                num_outputs: ChannelCount::STEREO,
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let smoother_config = SmootherConfig {
+            smooth_seconds: self.smooth_seconds,
+            ..Default::default()
+        };
+        let sample_rate = cx.stream_info.sample_rate;
+
+        Ok(Processor {
+            depth: SmoothedParam::new(self.depth.clamp(0.0, 1.0), smoother_config, sample_rate),
+            feedback: SmoothedParam::new(
+                self.feedback.clamp(-MAX_FEEDBACK, MAX_FEEDBACK),
+                smoother_config,
+                sample_rate,
+            ),
+            mix: SmoothedParam::new(self.mix.clamp(0.0, 1.0), smoother_config, sample_rate),
+            center_hz: SmoothedParam::new(
+                self.center_hz
+                    .clamp(DEFAULT_MIN_CENTER_HZ, DEFAULT_MAX_CENTER_HZ),
+                smoother_config,
+                sample_rate,
+            ),
+            phase_offset: SmoothedParam::new(
+                wrap_phase(self.stereo_phase_offset),
+                smoother_config,
+                sample_rate,
+            ),
+            stages: [SvfStateSimd::splat(Default::default()); MAX_STAGES as usize],
+            num_stages: clamp_stages(self.stages),
+            coeff: SvfCoeffSimd::splat(SvfCoeff::NO_OP),
+            coeff_update_mask: self.coeff_update_factor.mask(),
+            last_wet: [0.0; 2],
+            params: *self,
+            phase: 0.0,
+            sample_rate_recip: cx.stream_info.sample_rate_recip,
+            max_cutoff_hz: sample_rate.get() as f32 * 0.45,
+            config: *config,
+        })
+    }
+}
+
+impl ResettableNode for PhaserNode {
+    fn reset_notify(&mut self) -> &mut Notify<()> {
+        &mut self.reset
+    }
+}
+
+/// Wrap the given phase into the range `[0.0, 1.0)`.
+fn wrap_phase(phase: f32) -> f32 {
+    phase - phase.floor()
+}
+
+fn clamp_stages(stages: u32) -> usize {
+    stages.clamp(1, MAX_STAGES) as usize
+}
+
+struct Processor {
+    params: PhaserNode,
+    depth: SmoothedParam,
+    feedback: SmoothedParam,
+    mix: SmoothedParam,
+    center_hz: SmoothedParam,
+    phase_offset: SmoothedParam,
+
+    stages: [SvfStateSimd<2>; MAX_STAGES as usize],
+    num_stages: usize,
+    coeff: SvfCoeffSimd<2>,
+    coeff_update_mask: CoeffUpdateMask,
+    /// The output of the last stage in the previous frame, used for feedback.
+    last_wet: [f32; 2],
+
+    /// The phase of the LFO in the range `[0.0, 1.0)`.
+    phase: f64,
+    sample_rate_recip: f64,
+    max_cutoff_hz: f32,
+    config: StereoEffectConfig,
+}
+
+impl Processor {
+    fn reset_smoothers(&mut self) {
+        self.depth.reset_to_target();
+        self.feedback.reset_to_target();
+        self.mix.reset_to_target();
+        self.center_hz.reset_to_target();
+        self.phase_offset.reset_to_target();
+    }
+
+    fn reset_filters(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+        self.last_wet = [0.0; 2];
+    }
+
+    fn cutoff_hz(&self, center_hz: f32, depth: f32, phase: f32) -> f32 {
+        let lfo = (phase * TAU).sin();
+
+        (center_hz * (depth * SWEEP_OCTAVES * lfo).exp2()).clamp(MIN_CUTOFF_HZ, self.max_cutoff_hz)
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<PhaserNode>() {
+            match patch {
+                PhaserNodePatch::Stages(stages) => {
+                    let num_stages = clamp_stages(stages);
+
+                    // Stages which were inactive hold stale state.
+                    for stage in self.stages[self.num_stages..num_stages].iter_mut() {
+                        stage.reset();
+                    }
+
+                    self.num_stages = num_stages;
+                }
+                PhaserNodePatch::Depth(depth) => {
+                    self.depth.set_value(depth.clamp(0.0, 1.0));
+                }
+                PhaserNodePatch::Feedback(feedback) => {
+                    self.feedback
+                        .set_value(feedback.clamp(-MAX_FEEDBACK, MAX_FEEDBACK));
+                }
+                PhaserNodePatch::Mix(mix) => {
+                    self.mix.set_value(mix.clamp(0.0, 1.0));
+                }
+                PhaserNodePatch::CenterHz(center_hz) => {
+                    self.center_hz
+                        .set_value(center_hz.clamp(DEFAULT_MIN_CENTER_HZ, DEFAULT_MAX_CENTER_HZ));
+                }
+                PhaserNodePatch::StereoPhaseOffset(offset) => {
+                    self.phase_offset.set_value(wrap_phase(offset));
+                }
+                PhaserNodePatch::SmoothSeconds(seconds) => {
+                    self.depth.set_smooth_seconds(seconds, info.sample_rate);
+                    self.feedback.set_smooth_seconds(seconds, info.sample_rate);
+                    self.mix.set_smooth_seconds(seconds, info.sample_rate);
+                    self.center_hz.set_smooth_seconds(seconds, info.sample_rate);
+                    self.phase_offset
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                }
+                PhaserNodePatch::CoeffUpdateFactor(factor) => {
+                    self.coeff_update_mask = factor.mask();
+                }
+                PhaserNodePatch::Reset(_) => {
+                    self.reset_filters();
+                }
+                _ => {}
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.reset_smoothers();
+        self.reset_filters();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let start_phase = self.phase;
+        let phase_inc = f64::from(
+            self.params
+                .rate_hz
+                .clamp(DEFAULT_MIN_RATE_HZ, DEFAULT_MAX_RATE_HZ),
+        ) * self.sample_rate_recip;
+
+        // Keep the LFO running even when there is no input so that it stays
+        // continuous.
+        self.phase = (start_phase + phase_inc * info.frames as f64).fract();
+
+        if info.can_skip_effect(2, false) {
+            self.reset_smoothers();
+            self.reset_filters();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        if self.mix.has_settled_at(0.0) && !self.config.sum_mono_input {
+            self.reset_filters();
+
+            return ProcessStatus::Bypass;
+        }
+
+        let (in1, in2) = self
+            .config
+            .stereo_inputs(buffers.inputs, info.in_silence_mask);
+        let in1 = &in1[..info.frames];
+        let in2 = &in2[..info.frames];
+        let (out1, out2) = buffers.outputs.split_first_mut().unwrap();
+        let out1 = &mut out1[..info.frames];
+        let out2 = &mut out2[0][..info.frames];
+
+        let sample_rate_recip = self.sample_rate_recip as f32;
+
+        for i in 0..info.frames {
+            let depth = self.depth.next_smoothed();
+            let feedback = self.feedback.next_smoothed();
+            let mix = self.mix.next_smoothed();
+            let center_hz = self.center_hz.next_smoothed();
+            let phase_offset = self.phase_offset.next_smoothed();
+
+            if self.coeff_update_mask.do_update(i) {
+                let phase_l = (start_phase + phase_inc * i as f64).fract() as f32;
+                let phase_r = (phase_l + phase_offset).fract();
+
+                self.coeff = SvfCoeffSimd::new(&[
+                    SvfCoeff::allpass(
+                        self.cutoff_hz(center_hz, depth, phase_l),
+                        STAGE_Q,
+                        sample_rate_recip,
+                    ),
+                    SvfCoeff::allpass(
+                        self.cutoff_hz(center_hz, depth, phase_r),
+                        STAGE_Q,
+                        sample_rate_recip,
+                    ),
+                ]);
+            }
+
+            let mut wet = [
+                in1[i] + self.last_wet[0] * feedback,
+                in2[i] + self.last_wet[1] * feedback,
+            ];
+            for stage in self.stages[..self.num_stages].iter_mut() {
+                wet = stage.process(wet, &self.coeff);
+            }
+            self.last_wet = wet;
+
+            out1[i] = in1[i] * (1.0 - mix) + wet[0] * mix;
+            out2[i] = in2[i] * (1.0 - mix) + wet[1] * mix;
+        }
+
+        self.depth.settle();
+        self.feedback.settle();
+        self.mix.settle();
+        self.center_hz.settle();
+        self.phase_offset.settle();
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(
+        &mut self,
+        stream_info: &firewheel_core::StreamInfo,
+        _context: &mut ProcStreamCtx,
+    ) {
+        self.sample_rate_recip = stream_info.sample_rate_recip;
+        self.max_cutoff_hz = stream_info.sample_rate.get() as f32 * 0.45;
+
+        self.depth.update_sample_rate(stream_info.sample_rate);
+        self.feedback.update_sample_rate(stream_info.sample_rate);
+        self.mix.update_sample_rate(stream_info.sample_rate);
+        self.center_hz.update_sample_rate(stream_info.sample_rate);
+        self.phase_offset
+            .update_sample_rate(stream_info.sample_rate);
+
+        self.reset_filters();
+    }
+}
@@ -0,0 +1,452 @@
+//! A node which estimates the fundamental frequency of a signal using the YIN
+//! algorithm, i.e. for tuners and pitch-following effects.
+
+use bevy_platform::sync::{Arc, atomic::Ordering};
+use core::num::NonZeroU32;
+use firewheel_core::{
+    StreamInfo,
+    atomic_float::AtomicF32,
+    channel_config::{ChannelConfig, NonZeroChannelCount},
+    diff::{Diff, Patch},
+    dsp::volume::DEFAULT_MIN_AMP,
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, NodeError,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+};
+
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::{Vec, vec};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// The threshold of the cumulative mean normalized difference below which a
+/// lag is considered to be a candidate period.
+const YIN_THRESHOLD: f32 = 0.15;
+
+/// The configuration of a [`PitchDetectNode`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitchDetectConfig {
+    /// The number of channels. The input is passed through to the output
+    /// unchanged, and all channels are summed to mono before being analyzed.
+    ///
+    /// By default this is set to [`NonZeroChannelCount::MONO`].
+    pub channels: NonZeroChannelCount,
+    /// The largest value [`PitchDetectNode::window_size_secs`] can be set to.
+    /// The analysis buffers for this size are allocated up front.
+    ///
+    /// By default this is set to `0.1` (100ms).
+    pub max_window_size_secs: f32,
+}
+
+impl Default for PitchDetectConfig {
+    fn default() -> Self {
+        Self {
+            channels: NonZeroChannelCount::MONO,
+            max_window_size_secs: 100.0 / 1_000.0,
+        }
+    }
+}
+
+/// A node which estimates the fundamental frequency of its input using the
+/// YIN algorithm. The input is passed through to the output unchanged.
+///
+/// Read the latest estimate on the main thread with [`PitchDetectState`].
+#[derive(Debug, Diff, Patch, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitchDetectNode {
+    /// The lowest frequency that can be detected, in hertz.
+    ///
+    /// By default this is set to `60.0`.
+    pub min_frequency_hz: f32,
+    /// The highest frequency that can be detected, in hertz.
+    ///
+    /// By default this is set to `1200.0`.
+    pub max_frequency_hz: f32,
+    /// The size of the analysis window in seconds. The window must be at
+    /// least two periods of [`PitchDetectNode::min_frequency_hz`] long, so
+    /// the lowest detectable frequency is raised if this is too short.
+    ///
+    /// Larger windows are more accurate, but respond slower and are more
+    /// expensive to compute.
+    ///
+    /// This is clamped to [`PitchDetectConfig::max_window_size_secs`].
+    ///
+    /// By default this is set to `0.05` (50ms).
+    pub window_size_secs: f32,
+}
+
+impl Default for PitchDetectNode {
+    fn default() -> Self {
+        Self {
+            min_frequency_hz: 60.0,
+            max_frequency_hz: 1200.0,
+            window_size_secs: 50.0 / 1_000.0,
+        }
+    }
+}
+
+/// The state of a [`PitchDetectNode`]. This contains the latest estimate.
+#[derive(Clone)]
+pub struct PitchDetectState {
+    shared_state: Arc<SharedState>,
+}
+
+impl PitchDetectState {
+    fn new() -> Self {
+        Self {
+            shared_state: Arc::new(SharedState {
+                frequency_hz: AtomicF32::new(0.0),
+                confidence: AtomicF32::new(0.0),
+            }),
+        }
+    }
+
+    /// The detected fundamental frequency in hertz.
+    ///
+    /// If no pitch was detected (i.e. the input is silent or noisy), then
+    /// this will return `0.0`.
+    pub fn frequency_hz(&self) -> f32 {
+        self.shared_state.frequency_hz.load(Ordering::Relaxed)
+    }
+
+    /// How confident the detector is in [`PitchDetectState::frequency_hz`],
+    /// in the range `[0.0, 1.0]`.
+    ///
+    /// Clean, periodic signals typically have a confidence above `0.9`.
+    pub fn confidence(&self) -> f32 {
+        self.shared_state.confidence.load(Ordering::Relaxed)
+    }
+
+    /// Returns the detected frequency in hertz if its confidence is greater
+    /// than or equal to `min_confidence`.
+    pub fn pitch(&self, min_confidence: f32) -> Option<f32> {
+        let frequency_hz = self.frequency_hz();
+
+        if frequency_hz > 0.0 && self.confidence() >= min_confidence {
+            Some(frequency_hz)
+        } else {
+            None
+        }
+    }
+}
+
+impl AudioNode for PitchDetectNode {
+    type Configuration = PitchDetectConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("pitch_detect")
+            .channel_config(ChannelConfig {
+                num_inputs: config.channels.get(),
+                num_outputs: config.channels.get(),
+            })
+            .custom_state(PitchDetectState::new()))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let sample_rate = cx.stream_info.sample_rate;
+        let custom_state = cx.custom_state::<PitchDetectState>().unwrap();
+
+        let mut processor = Processor {
+            params: *self,
+            config: *config,
+            shared_state: Arc::clone(&custom_state.shared_state),
+            sample_rate,
+            ring: Vec::new(),
+            ring_ptr: 0,
+            window_frames: 0,
+            frames_since_analysis: 0,
+            num_silent_frames: 0,
+            window: Vec::new(),
+            difference: Vec::new(),
+        };
+        processor.allocate(sample_rate);
+
+        Ok(processor)
+    }
+}
+
+struct Processor {
+    params: PitchDetectNode,
+    config: PitchDetectConfig,
+    shared_state: Arc<SharedState>,
+    sample_rate: NonZeroU32,
+
+    /// The most recent `window_frames` frames of input, where `ring_ptr`
+    /// points to the oldest frame.
+    ring: Vec<f32>,
+    ring_ptr: usize,
+    window_frames: usize,
+    frames_since_analysis: usize,
+    num_silent_frames: usize,
+
+    window: Vec<f32>,
+    difference: Vec<f32>,
+}
+
+impl Processor {
+    fn max_window_frames(&self, sample_rate: NonZeroU32) -> usize {
+        secs_to_frames(self.config.max_window_size_secs, sample_rate).max(4)
+    }
+
+    fn allocate(&mut self, sample_rate: NonZeroU32) {
+        let max_window_frames = self.max_window_frames(sample_rate);
+
+        self.sample_rate = sample_rate;
+        self.ring = vec![0.0; max_window_frames];
+        self.window = vec![0.0; max_window_frames];
+        self.difference = vec![0.0; max_window_frames / 2 + 1];
+
+        self.update_window_frames();
+        self.reset();
+    }
+
+    fn update_window_frames(&mut self) {
+        self.window_frames = secs_to_frames(self.params.window_size_secs, self.sample_rate)
+            .clamp(4, self.ring.len());
+    }
+
+    fn reset(&mut self) {
+        self.ring.fill(0.0);
+        self.ring_ptr = 0;
+        self.frames_since_analysis = 0;
+        self.num_silent_frames = 0;
+
+        self.publish(0.0, 0.0);
+    }
+
+    fn publish(&self, frequency_hz: f32, confidence: f32) {
+        self.shared_state
+            .frequency_hz
+            .store(frequency_hz, Ordering::Relaxed);
+        self.shared_state
+            .confidence
+            .store(confidence, Ordering::Relaxed);
+    }
+
+    fn analyze(&mut self) {
+        let n = self.window_frames;
+        let sample_rate = self.sample_rate.get() as f32;
+
+        // Unwrap the ring buffer from oldest to newest frame.
+        let (head, tail) = self.ring[..n].split_at(self.ring_ptr);
+        for (out, s) in self.window[..n]
+            .iter_mut()
+            .zip(tail.iter().chain(head.iter()))
+        {
+            *out = *s;
+        }
+
+        let max_lag =
+            ((sample_rate / self.params.min_frequency_hz.max(1.0)).ceil() as usize).clamp(2, n / 2);
+        let min_lag = ((sample_rate / self.params.max_frequency_hz.max(1.0)).floor() as usize)
+            .clamp(1, max_lag - 1);
+        let integration_frames = n - max_lag;
+
+        // Cumulative mean normalized difference function.
+        self.difference[0] = 1.0;
+        let mut running_sum = 0.0;
+        for lag in 1..=max_lag {
+            let mut d = 0.0;
+            for (a, b) in self.window[..integration_frames]
+                .iter()
+                .zip(self.window[lag..lag + integration_frames].iter())
+            {
+                let delta = a - b;
+                d += delta * delta;
+            }
+
+            running_sum += d;
+            self.difference[lag] = if running_sum > 0.0 {
+                d * lag as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        // Find the first dip below the threshold, and then follow it down to
+        // its local minimum.
+        let mut period = None;
+        let mut lag = min_lag;
+        while lag <= max_lag {
+            if self.difference[lag] < YIN_THRESHOLD {
+                while lag < max_lag && self.difference[lag + 1] < self.difference[lag] {
+                    lag += 1;
+                }
+                period = Some(lag);
+                break;
+            }
+            lag += 1;
+        }
+
+        let Some(lag) = period else {
+            self.publish(0.0, 0.0);
+            return;
+        };
+
+        // Refine the period with parabolic interpolation.
+        let refined_lag = if lag > 1 && lag < max_lag {
+            let s0 = self.difference[lag - 1];
+            let s1 = self.difference[lag];
+            let s2 = self.difference[lag + 1];
+            let denom = s0 - 2.0 * s1 + s2;
+
+            if denom.abs() > f32::EPSILON {
+                lag as f32 + 0.5 * (s0 - s2) / denom
+            } else {
+                lag as f32
+            }
+        } else {
+            lag as f32
+        };
+
+        let confidence = (1.0 - self.difference[lag]).clamp(0.0, 1.0);
+
+        self.publish(sample_rate / refined_lag, confidence);
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, _info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<PitchDetectNode>() {
+            let window_changed = matches!(patch, PitchDetectNodePatch::WindowSizeSecs(_));
+
+            self.params.apply(patch);
+
+            if window_changed {
+                let prev_window_frames = self.window_frames;
+                self.update_window_frames();
+
+                if self.window_frames != prev_window_frames {
+                    self.reset();
+                }
+            }
+        }
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.reset();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let num_inputs = buffers.inputs.len();
+        let hop = self.window_frames / 2;
+
+        if info.in_silence_mask.all_channels_silent(num_inputs) {
+            if self.num_silent_frames >= self.window_frames {
+                // The window is already filled with silence, so there is no
+                // pitch to detect.
+                return ProcessStatus::Bypass;
+            }
+
+            self.num_silent_frames += info.frames;
+        } else {
+            self.num_silent_frames = 0;
+        }
+
+        let gain = (num_inputs as f32).recip();
+
+        for i in 0..info.frames {
+            let mut s = 0.0;
+            for (ch_i, ch) in buffers.inputs.iter().enumerate() {
+                if !info.in_silence_mask.is_channel_silent(ch_i) {
+                    s += ch[i];
+                }
+            }
+
+            self.ring[self.ring_ptr] = s * gain;
+            self.ring_ptr += 1;
+            if self.ring_ptr == self.window_frames {
+                self.ring_ptr = 0;
+            }
+
+            self.frames_since_analysis += 1;
+            if self.frames_since_analysis >= hop {
+                self.frames_since_analysis = 0;
+
+                if self.num_silent_frames >= self.window_frames
+                    || self.ring[..self.window_frames]
+                        .iter()
+                        .all(|s| s.abs() < DEFAULT_MIN_AMP)
+                {
+                    self.publish(0.0, 0.0);
+                } else {
+                    self.analyze();
+                }
+            }
+        }
+
+        // The input is passed through unchanged.
+        ProcessStatus::Bypass
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        if stream_info.sample_rate != self.sample_rate {
+            self.allocate(stream_info.sample_rate);
+        } else {
+            self.reset();
+        }
+    }
+}
+
+fn secs_to_frames(secs: f32, sample_rate: NonZeroU32) -> usize {
+    (secs.max(0.0) * sample_rate.get() as f32).round() as usize
+}
+
+#[derive(Debug)]
+struct SharedState {
+    frequency_hz: AtomicF32,
+    confidence: AtomicF32,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detects_sine_pitch() {
+        let sample_rate = NonZeroU32::new(48_000).unwrap();
+        let state = PitchDetectState::new();
+
+        let mut processor = Processor {
+            params: PitchDetectNode::default(),
+            config: PitchDetectConfig::default(),
+            shared_state: Arc::clone(&state.shared_state),
+            sample_rate,
+            ring: Vec::new(),
+            ring_ptr: 0,
+            window_frames: 0,
+            frames_since_analysis: 0,
+            num_silent_frames: 0,
+            window: Vec::new(),
+            difference: Vec::new(),
+        };
+        processor.allocate(sample_rate);
+
+        for i in 0..processor.window_frames {
+            let phase = i as f32 * 220.0 / sample_rate.get() as f32;
+            processor.ring[i] = (phase * core::f32::consts::TAU).sin() * 0.5;
+        }
+        processor.analyze();
+
+        let frequency_hz = state.frequency_hz();
+        assert!((frequency_hz - 220.0).abs() < 1.0, "{frequency_hz}");
+        assert!(state.confidence() > 0.9);
+    }
+}
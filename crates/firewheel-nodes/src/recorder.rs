@@ -0,0 +1,299 @@
+use bevy_platform::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+use core::num::NonZeroU32;
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, ChannelCount, NonZeroChannelCount},
+    diff::{Diff, Patch},
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::Vec;
+
+/// The configuration of a [`RecorderNode`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecorderConfig {
+    /// The number of channels to record.
+    pub channels: NonZeroChannelCount,
+
+    /// The capacity of the ring buffer between the audio thread and the
+    /// main thread in units of seconds.
+    ///
+    /// The recorded audio must be drained with [`RecorderState::drain`]
+    /// more often than this, or else frames will be dropped.
+    ///
+    /// By default this is set to `1.0`.
+    pub capacity_seconds: f64,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            channels: NonZeroChannelCount::STEREO,
+            capacity_seconds: 1.0,
+        }
+    }
+}
+
+/// A node that records the audio at its inputs so that it can be
+/// captured to memory or to disk on another thread.
+///
+/// The recorded audio is sent through a lock-free ring buffer, and it
+/// should be periodically drained with [`RecorderState::drain`] (i.e. once
+/// every update).
+///
+/// This node has no outputs, so connect it in parallel to the point in
+/// the graph you wish to capture.
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecorderNode {
+    /// Whether or not the node is currently recording.
+    ///
+    /// By default this is set to `false`.
+    pub recording: bool,
+}
+
+/// The state of a [`RecorderNode`], used to retrieve the recorded audio.
+#[derive(Clone)]
+pub struct RecorderState {
+    num_channels: NonZeroChannelCount,
+    shared: Arc<SharedState>,
+}
+
+struct SharedState {
+    active_state: Mutex<Option<ActiveState>>,
+    recorded_frames: AtomicU64,
+    dropped_frames: AtomicU64,
+}
+
+struct ActiveState {
+    consumer: ringbuf::HeapCons<f32>,
+    sample_rate: NonZeroU32,
+}
+
+impl RecorderState {
+    /// The number of channels being recorded.
+    pub fn num_channels(&self) -> NonZeroChannelCount {
+        self.num_channels
+    }
+
+    /// Returns `true` if the node is currently active.
+    pub fn is_active(&self) -> bool {
+        self.shared.active_state.lock().unwrap().is_some()
+    }
+
+    /// The sample rate of the recorded audio.
+    ///
+    /// If the node is not currently active, then this will return `None`.
+    pub fn sample_rate(&self) -> Option<NonZeroU32> {
+        self.shared
+            .active_state
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.sample_rate)
+    }
+
+    /// Move all recorded audio which is currently in the ring buffer into
+    /// the given de-interleaved channels, appending to the end of each one.
+    ///
+    /// If there are fewer channels in `channels` than the number of recorded
+    /// channels, then the extra recorded channels are discarded.
+    ///
+    /// Returns the number of frames that were appended.
+    pub fn drain(&self, channels: &mut [Vec<f32>]) -> usize {
+        let mut active_state = self.shared.active_state.lock().unwrap();
+        let Some(active_state) = active_state.as_mut() else {
+            return 0;
+        };
+
+        let num_channels = self.num_channels.get().get() as usize;
+        let frames = active_state.consumer.occupied_len() / num_channels;
+
+        if frames == 0 {
+            return 0;
+        }
+
+        for ch in channels.iter_mut() {
+            ch.reserve(frames);
+        }
+
+        let (s1, s2) = active_state.consumer.as_slices();
+        let mut samples = s1.iter().chain(s2.iter());
+
+        for _ in 0..frames {
+            for ch_i in 0..num_channels {
+                let s = *samples.next().unwrap();
+
+                if let Some(ch) = channels.get_mut(ch_i) {
+                    ch.push(s);
+                }
+            }
+        }
+
+        active_state.consumer.skip(frames * num_channels);
+
+        frames
+    }
+
+    /// The total number of frames which have been recorded since the node
+    /// was created, including any frames which have not been drained yet.
+    ///
+    /// This does not include dropped frames.
+    pub fn recorded_frames(&self) -> u64 {
+        self.shared.recorded_frames.load(Ordering::Relaxed)
+    }
+
+    /// The total number of frames which were dropped because the ring buffer
+    /// was full.
+    ///
+    /// If this is non-zero, then drain the recorded audio more often or
+    /// increase [`RecorderConfig::capacity_seconds`].
+    pub fn dropped_frames(&self) -> u64 {
+        self.shared.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Reset the counters for [`RecorderState::recorded_frames`] and
+    /// [`RecorderState::dropped_frames`] back to zero.
+    pub fn reset_frame_counts(&self) {
+        self.shared.recorded_frames.store(0, Ordering::Relaxed);
+        self.shared.dropped_frames.store(0, Ordering::Relaxed);
+    }
+}
+
+fn capacity_samples(config: &RecorderConfig, sample_rate: NonZeroU32) -> usize {
+    let frames = (config.capacity_seconds * sample_rate.get() as f64).round() as usize;
+    frames.max(1) * config.channels.get().get() as usize
+}
+
+impl AudioNode for RecorderNode {
+    type Configuration = RecorderConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("recorder")
+            .channel_config(ChannelConfig {
+                num_inputs: config.channels.get(),
+                num_outputs: ChannelCount::ZERO,
+            })
+            .custom_state(RecorderState {
+                num_channels: config.channels,
+                shared: Arc::new(SharedState {
+                    active_state: Mutex::new(None),
+                    recorded_frames: AtomicU64::new(0),
+                    dropped_frames: AtomicU64::new(0),
+                }),
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        mut cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let sample_rate = cx.stream_info.sample_rate;
+
+        let (producer, consumer) =
+            ringbuf::HeapRb::<f32>::new(capacity_samples(config, sample_rate)).split();
+
+        let state = cx.custom_state_mut::<RecorderState>().unwrap();
+
+        *state.shared.active_state.lock().unwrap() = Some(ActiveState {
+            consumer,
+            sample_rate,
+        });
+
+        Ok(Processor {
+            producer: Some(producer),
+            config: *config,
+            recording: self.recording,
+            shared: Arc::clone(&state.shared),
+        })
+    }
+}
+
+struct Processor {
+    producer: Option<ringbuf::HeapProd<f32>>,
+    config: RecorderConfig,
+    recording: bool,
+
+    // The processor only locks this when a new stream has started.
+    shared: Arc<SharedState>,
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, _info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<RecorderNode>() {
+            match patch {
+                RecorderNodePatch::Recording(recording) => self.recording = recording,
+            }
+        }
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        if !self.recording {
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let Some(producer) = self.producer.as_mut() else {
+            return ProcessStatus::ClearAllOutputs;
+        };
+
+        let num_channels = buffers.inputs.len();
+        let frames = info.frames.min(producer.vacant_len() / num_channels);
+
+        if frames > 0 {
+            producer
+                .push_iter((0..frames).flat_map(|i| buffers.inputs.iter().map(move |ch| ch[i])));
+
+            self.shared
+                .recorded_frames
+                .fetch_add(frames as u64, Ordering::Relaxed);
+        }
+
+        if frames < info.frames {
+            self.shared
+                .dropped_frames
+                .fetch_add((info.frames - frames) as u64, Ordering::Relaxed);
+        }
+
+        ProcessStatus::ClearAllOutputs
+    }
+
+    fn stream_stopped(&mut self, _context: &mut ProcStreamCtx) {
+        *self.shared.active_state.lock().unwrap() = None;
+        self.producer = None;
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        let (producer, consumer) =
+            ringbuf::HeapRb::<f32>::new(capacity_samples(&self.config, stream_info.sample_rate))
+                .split();
+
+        *self.shared.active_state.lock().unwrap() = Some(ActiveState {
+            consumer,
+            sample_rate: stream_info.sample_rate,
+        });
+
+        self.producer = Some(producer);
+    }
+}
@@ -34,9 +34,10 @@ use firewheel_core::{
     collector::ArcGc,
     diff::{Diff, Notify, ParamPath, Patch},
     dsp::{
-        buffer::InstanceBuffer,
-        declick::{DeclickFadeCurve, Declicker},
-        volume::{DEFAULT_MIN_AMP, Volume},
+        buffer::{InstanceBuffer, SequentialBuffer},
+        declick::{DeclickFadeCurve, DeclickValues, Declicker},
+        fade::FadeCurve,
+        volume::{DEFAULT_MIN_AMP, Volume, db_to_amp},
     },
     event::{NodeEventType, ParamData, ProcEvents},
     mask::{MaskType, SilenceMask},
@@ -49,15 +50,21 @@ use firewheel_core::{
 
 #[cfg(feature = "scheduled_events")]
 use firewheel_core::clock::EventInstant;
+#[cfg(feature = "musical_transport")]
+use firewheel_core::clock::{AudioClock, DurationMusical};
 
 pub const MAX_OUT_CHANNELS: usize = 8;
 pub const DEFAULT_NUM_DECLICKERS: usize = 2;
 pub const MIN_PLAYBACK_SPEED: f64 = 0.0000001;
 
+mod callback;
 mod resampler;
 mod resource;
+mod sample_set;
 
+pub use self::callback::{CallbackSample, CallbackSampleFeeder};
 pub use self::resource::{SamplerNodeResource, StreamedSample};
+pub use self::sample_set::{SampleSet, VelocityLayer};
 
 use self::resampler::Resampler;
 
@@ -83,6 +90,48 @@ pub struct SamplerConfig {
     /// The quality of the resampling algorithm used when changing the playback
     /// speed.
     pub speed_quality: PlaybackSpeedQuality,
+    /// If `true`, then the sample resource is kept when the sample rate of the
+    /// audio stream changes, and it is resampled on the fly to play at the
+    /// correct rate.
+    ///
+    /// Resources which know their native sample rate (see
+    /// `SampleResourceInfo::sample_rate`) are always played at the correct
+    /// rate. Otherwise the resource is assumed to have been loaded at the
+    /// sample rate of the stream at the time.
+    ///
+    /// If `false`, then the sample resource is dropped and the user must
+    /// reload it (see [`SamplerState::needs_reload`]).
+    ///
+    /// By default this is set to `false`.
+    pub auto_reload_on_rate_change: bool,
+    /// If `Some`, then the length of the declicking fades (when starting,
+    /// pausing, or stopping a sample) of this node is set to the given number
+    /// of frames, instead of the global default derived from
+    /// `FirewheelConfig::declick_seconds`.
+    ///
+    /// Shorter fades are useful for percussive samples, while longer fades
+    /// are useful for pads. Note, this value is in frames, so it is not
+    /// adjusted when the sample rate of the stream changes.
+    ///
+    /// By default this is set to `None`.
+    pub declick_frames_override: Option<u32>,
+    /// The maximum amount of random pitch variation in semitones. Each time
+    /// the sample is started from a playhead (not when resuming), the
+    /// playback speed is multiplied by a random pitch offset in the range
+    /// `[-pitch_variation, pitch_variation]`.
+    ///
+    /// This is useful for making repeated sound effects such as footsteps
+    /// sound less repetitive.
+    ///
+    /// By default this is set to `0.0` (no variation).
+    pub pitch_variation: f32,
+    /// The maximum amount of random volume variation in decibels. Each time
+    /// the sample is started from a playhead (not when resuming), the volume
+    /// is offset by a random amount in the range
+    /// `[-volume_variation, volume_variation]`.
+    ///
+    /// By default this is set to `0.0` (no variation).
+    pub volume_variation: f32,
 }
 
 impl Default for SamplerConfig {
@@ -91,6 +140,10 @@ impl Default for SamplerConfig {
             channels: NonZeroChannelCount::STEREO,
             num_declickers: DEFAULT_NUM_DECLICKERS as u32,
             speed_quality: PlaybackSpeedQuality::default(),
+            auto_reload_on_rate_change: false,
+            declick_frames_override: None,
+            pitch_variation: 0.0,
+            volume_variation: 0.0,
         }
     }
 }
@@ -108,6 +161,15 @@ pub enum PlaybackSpeedQuality {
     /// More specifically, this uses a linear resampling algorithm with no
     /// antialiasing filter.
     LinearFast,
+    /// Medium quality, moderate performance. Recommended for musical use
+    /// cases where samples are pitched up.
+    ///
+    /// More specifically, this uses a linear resampling algorithm, and a 4th
+    /// order Butterworth low-pass filter is applied to the sample before it
+    /// is resampled when the playback speed is above `1.0`. The cutoff of
+    /// the filter tracks the Nyquist frequency of the effective read rate
+    /// to reduce aliasing.
+    LinearAntiAliased,
     // TODO: more quality options
 }
 
@@ -161,6 +223,36 @@ pub struct SamplerNode {
     ///
     /// By default this is set to `0.00001` (-100 decibels).
     pub min_gain: f32,
+    /// The length in seconds of the fade out applied before the sample is
+    /// stopped. If this is `0.0`, then the sample is stopped with only a short
+    /// declick.
+    ///
+    /// Use [`SamplerNode::stop_with_fade`] to set this.
+    ///
+    /// By default this is set to `0.0`.
+    pub stop_fade_seconds: f32,
+    /// The curve of the fade out applied before the sample is stopped.
+    ///
+    /// By default this is set to [`FadeCurve::EqualPower3dB`].
+    pub stop_fade_curve: FadeCurve,
+    /// The length in seconds over which changes to [`SamplerNode::speed`]
+    /// are glided. If this is `0.0`, then the speed changes immediately.
+    ///
+    /// Use [`SamplerNode::set_speed_glide`] to set this.
+    ///
+    /// By default this is set to `0.0`.
+    pub speed_glide_seconds: f32,
+    /// The shape of the glide used when [`SamplerNode::speed`] changes.
+    ///
+    /// By default this is set to [`SpeedGlideCurve::Linear`].
+    pub speed_glide_curve: SpeedGlideCurve,
+    /// The velocity in the range `[0.0, 1.0]` used to select a velocity layer
+    /// when playback is started with a [`SampleSet`].
+    ///
+    /// This has no effect when playing a single sample.
+    ///
+    /// By default this is set to `1.0`.
+    pub velocity: f32,
 }
 
 impl Default for SamplerNode {
@@ -174,6 +266,11 @@ impl Default for SamplerNode {
             mono_to_stereo: true,
             crossfade_on_seek: true,
             min_gain: DEFAULT_MIN_AMP,
+            stop_fade_seconds: 0.0,
+            stop_fade_curve: FadeCurve::EqualPower3dB,
+            speed_glide_seconds: 0.0,
+            speed_glide_curve: SpeedGlideCurve::Linear,
+            velocity: 1.0,
         }
     }
 }
@@ -217,6 +314,57 @@ impl SamplerNode {
         NodeEventType::Custom(OwnedGc::new(Box::new(Some(sample))))
     }
 
+    /// Crossfade from the currently playing sample into the given sample
+    /// resource over the given number of seconds, using an equal-power curve.
+    /// The new sample is played from the beginning at the given volume.
+    ///
+    /// Unlike the short declick applied when the sample is changed with
+    /// [`SamplerNode::set_sample_event`], this fade is meant to be audible,
+    /// i.e. for musical transitions between looping ambiences.
+    ///
+    /// This sets [`SamplerNode::volume`], and returns the event which must
+    /// be sent to the node. The `play` state of this node is not modified,
+    /// so this should be used while the sample is playing. If no sample is
+    /// currently playing, then the new sample is faded in from silence.
+    ///
+    /// Starting another crossfade while one is still in progress declicks
+    /// the sample which was still being faded out.
+    pub fn crossfade_to<T: SampleResource + Send + Sync + 'static>(
+        &mut self,
+        sample: T,
+        volume: Volume,
+        seconds: f32,
+    ) -> NodeEventType {
+        self.crossfade_to_resource(SamplerNodeResource::from_sample(sample), volume, seconds)
+    }
+
+    /// The same as [`SamplerNode::crossfade_to`], but with the given sample
+    /// resource.
+    pub fn crossfade_to_resource(
+        &mut self,
+        sample: SamplerNodeResource,
+        volume: Volume,
+        seconds: f32,
+    ) -> NodeEventType {
+        self.volume = volume;
+
+        NodeEventType::Custom(OwnedGc::new(Box::new(Some(CrossfadeToEvent {
+            sample,
+            volume,
+            seconds: seconds.max(0.0),
+        }))))
+    }
+
+    /// Returns an event to set a [`SampleSet`] for a sampler node, replacing
+    /// any single sample resource.
+    ///
+    /// A sample from the set is selected each time playback is started from
+    /// a playhead (not when resuming), based on [`SamplerNode::velocity`] and
+    /// a round-robin index which advances on every start.
+    pub fn set_sample_set_event(sample_set: ArcGc<SampleSet>) -> NodeEventType {
+        NodeEventType::Custom(OwnedGc::new(Box::new(Some(sample_set))))
+    }
+
     /// Returns an event type to sync the `volume` parameter.
     pub fn sync_volume_event(&self) -> NodeEventType {
         NodeEventType::Param {
@@ -301,6 +449,39 @@ impl SamplerNode {
         *self.play = true;
     }
 
+    /// Start/restart the sample in this node on the next point on the grid of
+    /// the given musical subdivision.
+    ///
+    /// This returns the time the sample should start at. Send the diff of
+    /// this node as scheduled events at that time, e.g. with
+    /// `FirewheelContext::event_queue_scheduled`.
+    ///
+    /// If the musical transport is not currently playing, then `None` is
+    /// returned, in which case the events should be sent immediately.
+    ///
+    /// * `clock` - The current time of the audio clock.
+    /// * `subdivision` - The grid to quantize to in musical beats, where
+    ///   `DurationMusical(1.0)` is one beat.
+    #[cfg(feature = "musical_transport")]
+    pub fn start_or_restart_quantized(
+        &mut self,
+        clock: &AudioClock,
+        subdivision: DurationMusical,
+    ) -> Option<EventInstant> {
+        self.start_or_restart();
+
+        EventInstant::next_musical_subdivision(clock, subdivision)
+    }
+
+    /// Start/restart the sample in this node with the given velocity in the
+    /// range `[0.0, 1.0]`.
+    ///
+    /// The velocity selects the velocity layer when a [`SampleSet`] is used.
+    pub fn start_or_restart_with_velocity(&mut self, velocity: f32) {
+        self.velocity = velocity;
+        self.start_or_restart();
+    }
+
     /// Play the sample in this node from the given playhead.
     pub fn start_from(&mut self, from: PlayFrom) {
         self.play_from = from;
@@ -324,9 +505,86 @@ impl SamplerNode {
     /// the beginning.
     pub fn stop(&mut self) {
         self.play_from = PlayFrom::BEGINNING;
+        self.stop_fade_seconds = 0.0;
         *self.play = false;
     }
 
+    /// Fade out the sample over the given number of seconds, and then stop
+    /// sample playback.
+    ///
+    /// If the sample is started again while it is fading out, then the fade
+    /// is cancelled. Restarting the sample crossfades the old playhead into
+    /// the new one, and resuming fades the sample back in from its current
+    /// volume.
+    ///
+    /// Calling [`SamplerNode::resume`] after the fade has finished will
+    /// restart the sample from the beginning.
+    pub fn stop_with_fade(&mut self, seconds: f32, curve: FadeCurve) {
+        self.play_from = PlayFrom::BEGINNING;
+        self.stop_fade_seconds = seconds.max(0.0);
+        self.stop_fade_curve = curve;
+        *self.play = false;
+    }
+
+    /// Set the playback speed immediately.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+        self.speed_glide_seconds = 0.0;
+    }
+
+    /// Glide the playback speed linearly from its current value to `target`
+    /// over the given number of seconds.
+    ///
+    /// This can be used for effects such as a turntable stop or a tape stop.
+    /// If `target` is `0.0`, then the sample is paused once the glide has
+    /// finished, and [`SamplerNode::resume`] will continue from where it
+    /// came to rest. In that case, set a new speed before resuming.
+    ///
+    /// If the sample is not currently playing, then the glide starts from
+    /// rest (a speed of `0.0`) once the sample is played.
+    pub fn set_speed_glide(&mut self, target: f64, seconds: f32) {
+        self.set_speed_glide_with_curve(target, seconds, SpeedGlideCurve::Linear);
+    }
+
+    /// Same as [`SamplerNode::set_speed_glide`], but with the given curve.
+    pub fn set_speed_glide_with_curve(
+        &mut self,
+        target: f64,
+        seconds: f32,
+        curve: SpeedGlideCurve,
+    ) {
+        self.speed = target.max(0.0);
+        self.speed_glide_seconds = seconds.max(0.0);
+        self.speed_glide_curve = curve;
+
+        if self.speed <= MIN_PLAYBACK_SPEED {
+            self.play_from = PlayFrom::Resume;
+        }
+    }
+
+    /// Slow the sample down to a stop over the given number of seconds, like a
+    /// tape machine or turntable losing power, and then pause it.
+    ///
+    /// This uses [`SpeedGlideCurve::EaseIn`], so the pitch droops gently at
+    /// first and then falls away quickly towards the end.
+    ///
+    /// Use [`SamplerNode::tape_start`] to bring the sample back up to speed.
+    pub fn tape_stop(&mut self, seconds: f32) {
+        self.set_speed_glide_with_curve(0.0, seconds, SpeedGlideCurve::EaseIn);
+    }
+
+    /// Resume the sample and speed it up from rest to its original speed
+    /// (`1.0`) over the given number of seconds, like a tape machine or
+    /// turntable spinning up.
+    ///
+    /// This uses [`SpeedGlideCurve::EaseOut`], so the pitch rises quickly at
+    /// first and then settles gently onto the original pitch. If the sample
+    /// is already playing, then the glide starts from its current speed.
+    pub fn tape_start(&mut self, seconds: f32) {
+        self.set_speed_glide_with_curve(1.0, seconds, SpeedGlideCurve::EaseOut);
+        self.resume();
+    }
+
     /// Returns `true` if the current state is set to restart the sample.
     pub fn start_or_restart_requested(&self) -> bool {
         *self.play && self.play_from == PlayFrom::BEGINNING
@@ -386,6 +644,87 @@ impl SamplerState {
         DurationSeconds(self.playhead_frames().0 as f64 / sample_rate.get() as f64)
     }
 
+    /// Get the length of the sample resource currently loaded in the processor
+    /// in units of frames (samples of a single channel of audio).
+    ///
+    /// Returns `None` if the processor has no sample resource.
+    pub fn len_frames(&self) -> Option<u64> {
+        let mut channel = self.channel.lock().unwrap();
+        let s = channel.proc_state_output.read();
+
+        s.has_sample_resource.then_some(s.sample_len_frames)
+    }
+
+    /// Returns `true` if the processor dropped its sample resource because the
+    /// sample rate of the audio stream changed, meaning a new sample resource
+    /// needs to be set.
+    ///
+    /// This never happens if [`SamplerConfig::auto_reload_on_rate_change`]
+    /// is `true`.
+    pub fn needs_reload(&self) -> bool {
+        self.channel
+            .lock()
+            .unwrap()
+            .proc_state_output
+            .read()
+            .needs_reload
+    }
+
+    /// Get the current position of the playhead as a fraction of the length
+    /// of the loaded sample resource in the range `[0.0, 1.0]`, useful for
+    /// progress bars.
+    ///
+    /// Returns `None` if the processor has no sample resource, or if the
+    /// sample resource has an empty or unbounded length.
+    pub fn playhead_normalized(&self) -> Option<f32> {
+        let (playhead_frames, len_frames) = {
+            let mut channel = self.channel.lock().unwrap();
+            let s = channel.proc_state_output.read();
+
+            if !s.has_sample_resource {
+                return None;
+            }
+
+            (s.playhead_frames, s.sample_len_frames)
+        };
+
+        if len_frames == 0 || len_frames == u64::MAX {
+            return None;
+        }
+
+        Some((playhead_frames as f64 / len_frames as f64).clamp(0.0, 1.0) as f32)
+    }
+
+    /// Get the number of frames (samples of a single channel of audio) left
+    /// in the sample resource before playback finishes, including any
+    /// remaining repeats. This is useful for scheduling events to happen
+    /// right when the sample ends.
+    ///
+    /// Note, this is in units of frames of the sample resource, so it does
+    /// not take [`SamplerNode::speed`] (or any sample rate conversion) into
+    /// account. It also does not include the silent gaps of
+    /// [`RepeatMode::RepeatWithGap`].
+    ///
+    /// Returns `Some(0)` if playback is stopped. Returns `None` if the
+    /// processor has no sample resource, if the sample resource has an
+    /// unbounded length, or if the sample is repeating endlessly.
+    pub fn frames_remaining(&self) -> Option<u64> {
+        let s = *self.channel.lock().unwrap().proc_state_output.read();
+
+        if !s.has_sample_resource || s.sample_len_frames == u64::MAX || s.repeats_endlessly {
+            return None;
+        }
+
+        if s.playback_state == PlaybackState::Stopped {
+            return Some(0);
+        }
+
+        Some(
+            s.sample_len_frames.saturating_sub(s.playhead_frames)
+                + s.loops_remaining.saturating_mul(s.sample_len_frames),
+        )
+    }
+
     /// Get the current playback state of the processor at this instant in time.
     pub fn playback_state(&self) -> PlaybackState {
         self.channel
@@ -528,6 +867,24 @@ pub struct CurrentProcessorState {
     pub playback_age_frames: u64,
     /// Whether or not the processor currently has a sample resource.
     pub has_sample_resource: bool,
+    /// The length of the current sample resource in frames (samples in a
+    /// single channel of audio), or `0` if there is no sample resource.
+    ///
+    /// Sample resources with an unbounded length (such as a
+    /// [`CallbackSample`]) report `u64::MAX`.
+    pub sample_len_frames: u64,
+    /// Whether or not the sample resource was dropped because the sample
+    /// rate of the audio stream changed. This is reset once a new sample
+    /// resource is set.
+    pub needs_reload: bool,
+    /// The number of times the sample will still loop back to the start
+    /// before it finishes playing.
+    ///
+    /// This is meaningless if [`CurrentProcessorState::repeats_endlessly`]
+    /// is `true`.
+    pub loops_remaining: u64,
+    /// Whether or not the current [`RepeatMode`] repeats the sample endlessly.
+    pub repeats_endlessly: bool,
 }
 
 /// The current playback state of a [`SamplerNode`]'s processor.
@@ -621,6 +978,45 @@ impl Patch for PlayFrom {
     }
 }
 
+/// The shape of a glide between two playback speeds, see
+/// [`SamplerNode::speed_glide_curve`].
+///
+/// Each curve maps the normalized position in the glide `t` (from `0.0` to
+/// `1.0`) to the fraction of the way from the start speed to the target
+/// speed.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Diff, Patch)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeedGlideCurve {
+    /// The speed changes at a constant rate (`t`).
+    #[default]
+    Linear,
+    /// The speed changes slowly at first and then quickly towards the end
+    /// (`t^2`).
+    ///
+    /// When slowing down, this gives the "pitch droop" of a tape stop.
+    EaseIn,
+    /// The speed changes quickly at first and then slowly towards the end
+    /// (`1 - (1 - t)^2`).
+    ///
+    /// When speeding up, this gives the feel of a tape machine spinning up.
+    EaseOut,
+}
+
+impl SpeedGlideCurve {
+    /// Map the normalized position in the glide `t` (from `0.0` to `1.0`)
+    /// to the fraction of the way from the start speed to the target speed.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+        }
+    }
+}
+
 /// How many times a sample should be repeated.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Diff, Patch)]
 #[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
@@ -633,9 +1029,26 @@ pub enum RepeatMode {
     RepeatMultiple { num_times_to_repeat: u32 },
     /// Repeat the sample endlessly.
     RepeatEndlessly,
+    /// Repeat the sample endlessly, with a silent gap between each repeat.
+    ///
+    /// This is useful for ambient one-shots, such as a bird chirping every
+    /// few seconds.
+    RepeatWithGap {
+        /// The length of the silent gap between repeats in frames (samples
+        /// in a single channel of audio).
+        gap_frames: u64,
+        /// A random number of frames in the range `[0, jitter_frames]`
+        /// which is added to each gap. Set to `0` for a constant gap.
+        jitter_frames: u64,
+    },
 }
 
 impl RepeatMode {
+    /// Returns `true` if the sample should loop back to the start
+    /// immediately once it has reached the end.
+    ///
+    /// Note, this returns `false` for [`RepeatMode::RepeatWithGap`] since
+    /// the gap is inserted by the processor before restarting the sample.
     pub fn do_loop(&self, num_times_looped_back: u64) -> bool {
         match self {
             Self::PlayOnce => false,
@@ -643,6 +1056,7 @@ impl RepeatMode {
                 num_times_to_repeat,
             } => num_times_looped_back < num_times_to_repeat as u64,
             Self::RepeatEndlessly => true,
+            Self::RepeatWithGap { .. } => false,
         }
     }
 }
@@ -671,7 +1085,7 @@ impl AudioNode for SamplerNode {
             Some(InstanceBuffer::<f32>::new(
                 config.num_declickers as usize,
                 NonZeroUsize::new(config.channels.get().get() as usize).unwrap(),
-                cx.stream_info.declick_frames.get() as usize,
+                stop_declick_frames(config, cx.stream_info),
             ))
         };
 
@@ -722,24 +1136,49 @@ impl AudioNode for SamplerNode {
             proc_state,
             shared_proc_state,
             loaded_sample_state: None,
+            sample_set: None,
+            round_robin_index: 0,
             declicker: Declicker::SettledAt1,
+            declick_values: config.declick_frames_override.map(|frames| {
+                DeclickValues::new(NonZeroU32::new(frames).unwrap_or(NonZeroU32::MIN))
+            }),
             stop_declicker_buffers,
             stop_declickers: smallvec::smallvec![StopDeclickerState::default(); config.num_declickers as usize],
             num_active_stop_declickers: 0,
             resampler: Some(Resampler::new(config.speed_quality)),
             speed: self.speed.max(MIN_PLAYBACK_SPEED),
+            rate_ratio: 1.0,
+            sample_rate: cx.stream_info.sample_rate,
+            speed_glide: None,
             playing,
             paused,
             #[cfg(feature = "scheduled_events")]
             queued_playback_instant: None,
+            stop_fade: None,
             min_gain: self.min_gain.max(0.0),
             max_block_frames,
             num_out_channels: config.channels.get().get() as usize,
             is_first_process: true,
+            rng: rng_seed(cx.node_id.0.to_bits()),
+            variation_speed: 1.0,
+            variation_gain: 1.0,
+            crossfade: None,
+            crossfade_buffer: Some(SequentialBuffer::new(
+                NonZeroUsize::new(config.channels.get().get() as usize).unwrap(),
+                max_block_frames,
+            )),
         })
     }
 }
 
+/// The length of the stop declicker buffers in frames.
+fn stop_declick_frames(config: &SamplerConfig, stream_info: &StreamInfo) -> usize {
+    config
+        .declick_frames_override
+        .map(|frames| frames.max(1) as usize)
+        .unwrap_or(stream_info.declick_frames.get() as usize)
+}
+
 struct SamplerProcessor {
     config: SamplerConfig,
     params: SamplerNode,
@@ -748,7 +1187,15 @@ struct SamplerProcessor {
 
     loaded_sample_state: Option<LoadedSampleState>,
 
+    sample_set: Option<ArcGc<SampleSet>>,
+    /// Advanced every time a sample is selected from the sample set.
+    round_robin_index: usize,
+
     declicker: Declicker,
+    /// The declick values used by this node if
+    /// [`SamplerConfig::declick_frames_override`] is set. Otherwise the
+    /// global declick values in [`ProcExtra`] are used.
+    declick_values: Option<DeclickValues>,
 
     playing: bool,
     paused: bool,
@@ -759,22 +1206,97 @@ struct SamplerProcessor {
 
     resampler: Option<Resampler>,
     speed: f64,
+    /// The ratio of the sample rate of the loaded sample resource to the
+    /// sample rate of the stream.
+    rate_ratio: f64,
+    sample_rate: NonZeroU32,
+    speed_glide: Option<SpeedGlideState>,
 
     #[cfg(feature = "scheduled_events")]
     queued_playback_instant: Option<EventInstant>,
 
+    stop_fade: Option<StopFadeState>,
+
     min_gain: f32,
 
     max_block_frames: usize,
     num_out_channels: usize,
     is_first_process: bool,
+
+    /// The state of the random number generator used for
+    /// [`RepeatMode::RepeatWithGap`] and for the random variations
+    /// applied when the sample is triggered.
+    rng: u32,
+    /// The random speed multiplier chosen when the sample was last triggered.
+    variation_speed: f64,
+    /// The random gain chosen when the sample was last triggered.
+    variation_gain: f32,
+
+    crossfade: Option<CrossfadeState>,
+    /// The buffer the outgoing sample of a crossfade is rendered into.
+    crossfade_buffer: Option<SequentialBuffer<f32>>,
 }
 
 impl SamplerProcessor {
     fn sync_proc_state(&mut self) {
+        let num_times_looped_back = self
+            .loaded_sample_state
+            .as_ref()
+            .map(|s| s.num_times_looped_back)
+            .unwrap_or(0);
+
+        (
+            self.proc_state.loops_remaining,
+            self.proc_state.repeats_endlessly,
+        ) = match self.params.repeat_mode {
+            RepeatMode::PlayOnce => (0, false),
+            RepeatMode::RepeatMultiple {
+                num_times_to_repeat,
+            } => (
+                u64::from(num_times_to_repeat).saturating_sub(num_times_looped_back),
+                false,
+            ),
+            RepeatMode::RepeatEndlessly | RepeatMode::RepeatWithGap { .. } => (0, true),
+        };
+
         self.shared_proc_state.write(self.proc_state);
     }
 
+    /// The speed at which frames are read from the sample resource, taking
+    /// sample rate conversion into account.
+    fn playback_speed(&self) -> f64 {
+        self.speed * self.rate_ratio * self.variation_speed
+    }
+
+    /// The gain to play the sample at, taking the random volume variation
+    /// into account.
+    fn sample_gain(&self) -> f32 {
+        let gain = self.params.volume.amp_clamped(self.min_gain) * self.variation_gain;
+
+        if gain > 0.99999 && gain < 1.00001 {
+            1.0
+        } else {
+            gain
+        }
+    }
+
+    /// Choose new random pitch and volume variations for the next playback.
+    fn randomize_variation(&mut self) {
+        self.variation_speed = if self.config.pitch_variation > 0.0 {
+            let semitones = next_rng_bipolar(&mut self.rng) * self.config.pitch_variation;
+            2.0f64.powf(semitones as f64 / 12.0)
+        } else {
+            1.0
+        };
+
+        self.variation_gain = if self.config.volume_variation > 0.0 {
+            let db = next_rng_bipolar(&mut self.rng) * self.config.volume_variation;
+            db_to_amp(db)
+        } else {
+            1.0
+        };
+    }
+
     /// Returns `true` if the sample has finished playing, and also
     /// returns the number of channels that were filled.
     fn process_internal(
@@ -784,7 +1306,7 @@ impl SamplerProcessor {
         looping: bool,
         extra: &mut ProcExtra,
     ) -> (bool, usize) {
-        let (finished_playing, mut channels_filled) = if self.speed != 1.0 {
+        let (finished_playing, mut channels_filled) = if self.playback_speed() != 1.0 {
             // Get around borrow checker.
             let mut resampler = self.resampler.take().unwrap();
 
@@ -808,7 +1330,9 @@ impl SamplerProcessor {
             self.declicker.process(
                 buffers,
                 0..frames,
-                &extra.declick_values,
+                self.declick_values
+                    .as_ref()
+                    .unwrap_or(&extra.declick_values),
                 state.gain,
                 DeclickFadeCurve::EqualPower3dB,
             );
@@ -841,6 +1365,8 @@ impl SamplerProcessor {
         range_in_buffer: Range<usize>,
         looping: bool,
     ) -> (bool, usize) {
+        let speed = self.playback_speed();
+
         let Some(state) = self.loaded_sample_state.as_mut() else {
             return (true, 0);
         };
@@ -864,8 +1390,14 @@ impl SamplerProcessor {
                         state.playhead_frames,
                     );
                 }
-                SamplerNodeResource::Streamed(_) => {
-                    todo!()
+                SamplerNodeResource::Streamed(sample) => {
+                    fill_from_streamed(
+                        sample.get_mut(),
+                        buffers,
+                        range_in_buffer.start..range_in_buffer.start + first_copy_frames,
+                        state.playhead_frames,
+                        speed,
+                    );
                 }
             }
 
@@ -890,8 +1422,15 @@ impl SamplerProcessor {
                                 0,
                             );
                         }
-                        SamplerNodeResource::Streamed(_) => {
-                            todo!()
+                        SamplerNodeResource::Streamed(sample) => {
+                            fill_from_streamed(
+                                sample.get_mut(),
+                                buffers,
+                                range_in_buffer.start + frames_copied
+                                    ..range_in_buffer.start + frames_copied + copy_frames,
+                                0,
+                                speed,
+                            );
                         }
                     }
 
@@ -938,44 +1477,244 @@ impl SamplerProcessor {
             // Fade out the sample into a temporary look-ahead
             // buffer to declick.
 
-            self.declicker.fade_to_0(&extra.declick_values);
+            self.declicker.fade_to_0(
+                self.declick_values
+                    .as_ref()
+                    .unwrap_or(&extra.declick_values),
+            );
+
+            // Continue from the current volume of the long fade out and the
+            // crossfade (if any).
+            let mut gain = self.stop_fade.as_ref().map(|f| f.gain()).unwrap_or(1.0);
+            if let Some(crossfade) = &self.crossfade {
+                gain *= crossfade.gains(0).1;
+            }
+
+            self.fade_out_into_stop_declicker(gain, extra);
+        }
+
+        self.cancel_crossfade(extra);
+        self.reset_playhead();
+    }
+
+    /// Render the fade out of the current sample into a free stop declicker
+    /// (if there is one), scaled by the given gain.
+    ///
+    /// The declicker must already be fading out.
+    fn fade_out_into_stop_declicker(&mut self, gain: f32, extra: &mut ProcExtra) {
+        // Work around the borrow checker.
+        if let Some(mut stop_declicker_buffers) = self.stop_declicker_buffers.take() {
+            if self.num_active_stop_declickers < stop_declicker_buffers.num_instances() {
+                let declicker_i = self
+                    .stop_declickers
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, d)| if d.frames_left == 0 { Some(i) } else { None })
+                    .unwrap();
+
+                let n_channels = self.num_channels_filled();
+
+                let fade_out_frames = stop_declicker_buffers.frames();
+
+                self.stop_declickers[declicker_i].frames_left = fade_out_frames;
+                self.stop_declickers[declicker_i].channels = n_channels;
+
+                let mut tmp_buffers = stop_declicker_buffers
+                    .instance_mut::<MAX_OUT_CHANNELS>(declicker_i, n_channels, fade_out_frames)
+                    .unwrap();
+
+                self.process_internal(&mut tmp_buffers, fade_out_frames, false, extra);
+
+                if gain != 1.0 {
+                    for b in tmp_buffers.iter_mut() {
+                        for s in b[..fade_out_frames].iter_mut() {
+                            *s *= gain;
+                        }
+                    }
+                }
+
+                self.num_active_stop_declickers += 1;
+            }
+
+            self.stop_declicker_buffers = Some(stop_declicker_buffers);
+        }
+    }
+
+    /// Swap the state of the current sample with the given outgoing sample
+    /// of a crossfade.
+    fn swap_outgoing_sample(&mut self, out: &mut OutgoingSample) {
+        core::mem::swap(&mut self.loaded_sample_state, &mut out.state);
+        core::mem::swap(&mut self.resampler, &mut out.resampler);
+        core::mem::swap(&mut self.rate_ratio, &mut out.rate_ratio);
+        core::mem::swap(&mut self.variation_speed, &mut out.variation_speed);
+        core::mem::swap(&mut self.declicker, &mut out.declicker);
+    }
+
+    /// Cancel the current crossfade (if any), declicking the sample which is
+    /// being faded out.
+    fn cancel_crossfade(&mut self, extra: &mut ProcExtra) {
+        self.declick_crossfade_out(extra);
+        self.crossfade = None;
+    }
+
+    /// Declick the sample which is being faded out by the current crossfade
+    /// (if any). The fade in of the current sample is left untouched.
+    fn declick_crossfade_out(&mut self, extra: &mut ProcExtra) {
+        let Some(crossfade) = &mut self.crossfade else {
+            return;
+        };
+
+        if let Some(mut out) = crossfade.out_sample.take() {
+            let gain = out.gain * crossfade.gains(0).0;
+
+            self.swap_outgoing_sample(&mut out);
+
+            self.declicker.fade_to_0(
+                self.declick_values
+                    .as_ref()
+                    .unwrap_or(&extra.declick_values),
+            );
+            self.fade_out_into_stop_declicker(gain, extra);
+
+            self.swap_outgoing_sample(&mut out);
+        }
+    }
+
+    /// Start crossfading from the currently playing sample into a new one.
+    fn start_crossfade(
+        &mut self,
+        crossfade: CrossfadeToEvent,
+        sample_rate: NonZeroU32,
+        extra: &mut ProcExtra,
+    ) {
+        let total_frames = ((crossfade.seconds * sample_rate.get() as f32).round() as usize).max(1);
+
+        // The current sample may still be fading in from a previous crossfade.
+        let mut out_gain = self.crossfade.as_ref().map(|c| c.gains(0).1).unwrap_or(1.0);
+
+        // Only one sample can be faded out at a time.
+        self.cancel_crossfade(extra);
+
+        let out_sample = if self.playing && self.loaded_sample_state.is_some() {
+            if let Some(stop_fade) = self.stop_fade.take() {
+                out_gain *= stop_fade.gain();
+            }
+
+            let mut out = OutgoingSample {
+                state: None,
+                resampler: Some(Resampler::new(self.config.speed_quality)),
+                rate_ratio: 1.0,
+                variation_speed: 1.0,
+                declicker: Declicker::SettledAt1,
+                gain: out_gain,
+            };
+
+            self.swap_outgoing_sample(&mut out);
+
+            Some(out)
+        } else {
+            self.stop(extra);
+
+            None
+        };
 
+        self.loaded_sample_state = None;
+        self.sample_set = None;
+        self.speed_glide = None;
+
+        self.params.volume = crossfade.volume;
+        self.randomize_variation();
+        self.load_sample(crossfade.sample);
+
+        self.playing = true;
+        self.paused = false;
+        self.proc_state.has_sample_resource = true;
+        self.proc_state.needs_reload = false;
+        self.proc_state.playhead_frames = 0;
+        self.proc_state.playback_age_frames = 0;
+        self.proc_state.playback_state = PlaybackState::Playing;
+
+        self.crossfade = Some(CrossfadeState {
+            out_sample,
+            position_frames: 0,
+            total_frames,
+        });
+    }
+
+    /// Render the outgoing sample of the current crossfade (if any) and mix
+    /// it into the output buffers.
+    ///
+    /// Returns the number of channels that were filled.
+    fn process_crossfade(
+        &mut self,
+        buffers: &mut [&mut [f32]],
+        frames: usize,
+        extra: &mut ProcExtra,
+    ) -> usize {
+        let Some(mut crossfade) = self.crossfade.take() else {
+            return 0;
+        };
+
+        let mut channels_filled = 0;
+
+        if let Some(mut out) = crossfade.out_sample.take() {
             // Work around the borrow checker.
-            if let Some(mut stop_declicker_buffers) = self.stop_declicker_buffers.take() {
-                if self.num_active_stop_declickers < stop_declicker_buffers.num_instances() {
-                    let declicker_i = self
-                        .stop_declickers
-                        .iter()
-                        .enumerate()
-                        .find_map(|(i, d)| if d.frames_left == 0 { Some(i) } else { None })
-                        .unwrap();
+            let mut crossfade_buffer = self.crossfade_buffer.take().unwrap();
+
+            self.swap_outgoing_sample(&mut out);
 
-                    let n_channels = self.num_channels_filled();
+            let looping = self.params.repeat_mode.do_loop(
+                self.loaded_sample_state
+                    .as_ref()
+                    .unwrap()
+                    .num_times_looped_back,
+            );
 
-                    let fade_out_frames = stop_declicker_buffers.frames();
+            let mut tmp_buffers =
+                crossfade_buffer.channels_mut::<MAX_OUT_CHANNELS>(self.num_out_channels, frames);
 
-                    self.stop_declickers[declicker_i].frames_left = fade_out_frames;
-                    self.stop_declickers[declicker_i].channels = n_channels;
+            let (finished, n_channels) =
+                self.process_internal(&mut tmp_buffers, frames, looping, extra);
 
-                    let mut tmp_buffers = stop_declicker_buffers
-                        .instance_mut::<MAX_OUT_CHANNELS>(declicker_i, n_channels, fade_out_frames)
-                        .unwrap();
+            self.swap_outgoing_sample(&mut out);
 
-                    self.process_internal(&mut tmp_buffers, fade_out_frames, false, extra);
+            for i in 0..frames {
+                let gain = out.gain * crossfade.gains(i).0;
 
-                    self.num_active_stop_declickers += 1;
+                for (out_buf, tmp_buf) in buffers[..n_channels]
+                    .iter_mut()
+                    .zip(tmp_buffers[..n_channels].iter())
+                {
+                    out_buf[i] += tmp_buf[i] * gain;
                 }
+            }
+
+            channels_filled = n_channels;
+
+            self.crossfade_buffer = Some(crossfade_buffer);
 
-                self.stop_declicker_buffers = Some(stop_declicker_buffers);
+            if !finished {
+                crossfade.out_sample = Some(out);
             }
         }
 
+        crossfade.position_frames += frames;
+        if crossfade.position_frames < crossfade.total_frames {
+            self.crossfade = Some(crossfade);
+        }
+
+        channels_filled
+    }
+
+    fn reset_playhead(&mut self) {
         if let Some(state) = &mut self.loaded_sample_state {
             state.playhead_frames = 0;
             state.num_times_looped_back = 0;
+            state.gap_frames_left = 0;
         }
 
         self.declicker.reset_to_1();
+        self.stop_fade = None;
 
         if let Some(resampler) = &mut self.resampler {
             resampler.reset();
@@ -983,16 +1722,24 @@ impl SamplerProcessor {
     }
 
     fn load_sample(&mut self, sample: SamplerNodeResource) {
-        let mut gain = self.params.volume.amp_clamped(self.min_gain);
-        if gain > 0.99999 && gain < 1.00001 {
-            gain = 1.0;
-        }
+        let gain = self.sample_gain();
+
+        self.rate_ratio = if self.config.auto_reload_on_rate_change {
+            sample
+                .sample_rate()
+                .map(|rate| rate.get() as f64 / self.sample_rate.get() as f64)
+                .unwrap_or(1.0)
+        } else {
+            1.0
+        };
 
         let (sample_len_frames, sample_num_channels) = match &sample {
             SamplerNodeResource::InMemory(s) => (s.len_frames(), s.num_channels()),
             SamplerNodeResource::Streamed(s) => (s.len_frames(), s.num_channels()),
         };
 
+        self.proc_state.sample_len_frames = sample_len_frames;
+
         let sample_mono_to_stereo = self.params.mono_to_stereo
             && self.num_out_channels > 1
             && sample_num_channels.get() == 1;
@@ -1005,8 +1752,108 @@ impl SamplerProcessor {
             gain,
             playhead_frames: 0,
             num_times_looped_back: 0,
+            gap_frames_left: 0,
         });
     }
+
+    /// The same as [`SamplerProcessor::process_internal`], but also handles
+    /// the silent gap between repeats for [`RepeatMode::RepeatWithGap`].
+    fn process_with_gap(
+        &mut self,
+        buffers: &mut [&mut [f32]],
+        frames: usize,
+        looping: bool,
+        extra: &mut ProcExtra,
+    ) -> (bool, usize) {
+        let speed = self.playback_speed();
+        let state = self.loaded_sample_state.as_mut().unwrap();
+
+        if state.gap_frames_left >= frames as u64 {
+            // The whole block is silent.
+            state.gap_frames_left -= frames as u64;
+            return (false, 0);
+        }
+
+        let gap_frames = state.gap_frames_left as usize;
+        state.gap_frames_left = 0;
+
+        // The number of output frames until the end of the sample is reached.
+        let frames_until_end =
+            ((state.sample_len_frames - state.playhead_frames) as f64 / speed).ceil() as usize;
+
+        let (finished, channels_filled) = if gap_frames > 0 {
+            let mut sub_buffers: SmallVec<[&mut [f32]; MAX_OUT_CHANNELS]> = buffers
+                .iter_mut()
+                .map(|b| &mut b[gap_frames..frames])
+                .collect();
+
+            let (finished, channels_filled) =
+                self.process_internal(&mut sub_buffers, frames - gap_frames, looping, extra);
+
+            for b in buffers[..channels_filled].iter_mut() {
+                b[..gap_frames].fill(0.0);
+            }
+
+            (finished, channels_filled)
+        } else {
+            self.process_internal(buffers, frames, looping, extra)
+        };
+
+        let RepeatMode::RepeatWithGap {
+            gap_frames: repeat_gap_frames,
+            jitter_frames,
+        } = self.params.repeat_mode
+        else {
+            return (finished, channels_filled);
+        };
+
+        if !finished {
+            return (finished, channels_filled);
+        }
+
+        let jitter = if jitter_frames > 0 {
+            u64::from(next_rng(&mut self.rng)) % (jitter_frames + 1)
+        } else {
+            0
+        };
+
+        // Account for the silent frames at the end of this block after the
+        // sample finished.
+        let silent_frames_in_block = (frames - gap_frames).saturating_sub(frames_until_end);
+
+        let state = self.loaded_sample_state.as_mut().unwrap();
+        state.playhead_frames = 0;
+        state.num_times_looped_back += 1;
+        state.gap_frames_left =
+            (repeat_gap_frames + jitter).saturating_sub(silent_frames_in_block as u64);
+
+        if let Some(resampler) = &mut self.resampler {
+            resampler.reset();
+        }
+
+        (false, channels_filled)
+    }
+}
+
+fn rng_seed(seed: u64) -> u32 {
+    let state = (seed ^ (seed >> 32)) as u32;
+
+    // The state of a xorshift random number generator cannot be zero.
+    if state == 0 { 17 } else { state }
+}
+
+/// Advance the state of the xorshift random number generator.
+fn next_rng(state: &mut u32) -> u32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+
+    *state
+}
+
+/// Returns a random value in the range `[-1.0, 1.0]`.
+fn next_rng_bipolar(state: &mut u32) -> f32 {
+    (next_rng(state) as f64 / u32::MAX as f64 * 2.0 - 1.0) as f32
 }
 
 impl AudioNodeProcessor for SamplerProcessor {
@@ -1020,6 +1867,8 @@ impl AudioNodeProcessor for SamplerProcessor {
             None
         };
         let mut new_sample = None;
+        let mut new_sample_set = None;
+        let mut new_crossfade = None;
         let mut repeat_mode_changed = false;
         let mut speed_changed = false;
         let mut volume_changed = false;
@@ -1036,6 +1885,18 @@ impl AudioNodeProcessor for SamplerProcessor {
                 continue;
             }
 
+            let mut set = None;
+            if event.downcast_swap::<Option<ArcGc<SampleSet>>>(&mut set) {
+                new_sample_set = Some(set);
+                continue;
+            }
+
+            let mut crossfade = None;
+            if event.downcast_swap::<Option<CrossfadeToEvent>>(&mut crossfade) {
+                new_crossfade = crossfade;
+                continue;
+            }
+
             if let Some(patch) = SamplerNode::patch_event(&event) {
                 match patch {
                     SamplerNodePatch::Volume(_) => volume_changed = true,
@@ -1069,6 +1930,18 @@ impl AudioNodeProcessor for SamplerProcessor {
                 continue;
             }
 
+            let mut set = None;
+            if event.downcast_swap::<Option<ArcGc<SampleSet>>>(&mut set) {
+                new_sample_set = Some(set);
+                continue;
+            }
+
+            let mut crossfade = None;
+            if event.downcast_swap::<Option<CrossfadeToEvent>>(&mut crossfade) {
+                new_crossfade = crossfade;
+                continue;
+            }
+
             if let Some(patch) = SamplerNode::patch_event(&event) {
                 match patch {
                     SamplerNodePatch::Volume(_) => volume_changed = true,
@@ -1094,26 +1967,60 @@ impl AudioNodeProcessor for SamplerProcessor {
         }
 
         if speed_changed {
-            self.speed = self.params.speed.max(MIN_PLAYBACK_SPEED);
+            let mut target = self.params.speed.max(MIN_PLAYBACK_SPEED);
 
-            if self.speed > 0.99999 && self.speed < 1.00001 {
-                self.speed = 1.0;
+            if target > 0.99999 && target < 1.00001 {
+                target = 1.0;
+            }
+
+            let glide_frames = (self.params.speed_glide_seconds as f64
+                * info.sample_rate.get() as f64)
+                .round() as usize;
+
+            // A sample which is not playing is at rest, so the glide starts
+            // from zero once it is played.
+            let start = if self.playing {
+                self.speed
+            } else {
+                MIN_PLAYBACK_SPEED
+            };
+
+            if glide_frames > 0 && target != start {
+                self.speed = start;
+                self.speed_glide = Some(SpeedGlideState {
+                    start,
+                    target,
+                    curve: self.params.speed_glide_curve,
+                    position_frames: 0,
+                    total_frames: glide_frames,
+                    pause_when_done: self.params.speed <= MIN_PLAYBACK_SPEED,
+                });
+            } else {
+                self.speed = target;
+                self.speed_glide = None;
             }
         }
 
-        if volume_changed && let Some(loaded_sample) = &mut self.loaded_sample_state {
-            loaded_sample.gain = self.params.volume.amp_clamped(self.min_gain);
-            if loaded_sample.gain > 0.99999 && loaded_sample.gain < 1.00001 {
-                loaded_sample.gain = 1.0;
+        if volume_changed {
+            let gain = self.sample_gain();
+
+            if let Some(loaded_sample) = &mut self.loaded_sample_state {
+                loaded_sample.gain = gain;
             }
         }
 
-        if repeat_mode_changed && let Some(loaded_sample) = &mut self.loaded_sample_state {
-            loaded_sample.num_times_looped_back = 0;
+        if repeat_mode_changed {
+            if let Some(loaded_sample) = &mut self.loaded_sample_state {
+                loaded_sample.num_times_looped_back = 0;
+            }
+
+            proc_state_changed = true;
         }
 
         if let Some(maybe_sample) = new_sample {
             self.proc_state.has_sample_resource = maybe_sample.is_some();
+            self.proc_state.sample_len_frames = 0;
+            self.proc_state.needs_reload = false;
             proc_state_changed = true;
 
             self.stop(extra);
@@ -1128,12 +2035,44 @@ impl AudioNodeProcessor for SamplerProcessor {
             }
 
             self.loaded_sample_state = None;
+            self.sample_set = None;
 
             if let Some(sample) = maybe_sample {
                 self.load_sample(sample);
             }
         }
 
+        if let Some(maybe_sample_set) = new_sample_set {
+            self.proc_state.has_sample_resource =
+                maybe_sample_set.as_ref().is_some_and(|set| !set.is_empty());
+            self.proc_state.sample_len_frames = 0;
+            self.proc_state.needs_reload = false;
+            proc_state_changed = true;
+
+            self.stop(extra);
+
+            self.loaded_sample_state = None;
+            self.sample_set = maybe_sample_set;
+            self.round_robin_index = 0;
+        }
+
+        if new_playing == Some(true)
+            && self.params.play_from != PlayFrom::Resume
+            && let Some(sample) = self
+                .sample_set
+                .as_ref()
+                .and_then(|set| set.select(self.params.velocity, self.round_robin_index))
+                .cloned()
+        {
+            self.round_robin_index = self.round_robin_index.wrapping_add(1);
+
+            // Fade out the previously selected sample (if it is playing).
+            self.stop(extra);
+
+            self.load_sample(SamplerNodeResource::InMemory(sample));
+            proc_state_changed = true;
+        }
+
         if let Some(mut new_playing) = new_playing {
             self.paused = false;
             self.proc_state.playback_age_frames = 0;
@@ -1145,7 +2084,12 @@ impl AudioNodeProcessor for SamplerProcessor {
                 if self.params.play_from == PlayFrom::Resume {
                     // Resume
                     if self.playing && !is_first_process {
-                        // Sample is already playing, no need to do anything.
+                        // Sample is already playing, no need to do anything other
+                        // than fading back in if it is currently fading out.
+                        if let Some(stop_fade) = &mut self.stop_fade {
+                            stop_fade.fading_in = true;
+                        }
+
                         #[cfg(feature = "scheduled_events")]
                         {
                             self.queued_playback_instant = None;
@@ -1155,8 +2099,13 @@ impl AudioNodeProcessor for SamplerProcessor {
                     }
                 } else {
                     // Play from the given playhead
+                    self.randomize_variation();
+                    let gain = self.sample_gain();
+
                     if let Some(loaded_sample_state) = &mut self.loaded_sample_state {
+                        loaded_sample_state.gain = gain;
                         loaded_sample_state.num_times_looped_back = 0;
+                        loaded_sample_state.gap_frames_left = 0;
                         playhead_frames_at_play_instant =
                             Some(self.params.play_from.as_frames(info.sample_rate).unwrap());
                     } else {
@@ -1168,6 +2117,11 @@ impl AudioNodeProcessor for SamplerProcessor {
                 }
 
                 if let Some(playhead_frames_at_play_instant) = playhead_frames_at_play_instant {
+                    if self.stop_fade.is_some() {
+                        // Cancel the fade out by crossfading into the new playhead.
+                        self.stop(extra);
+                    }
+
                     let loaded_sample_state = self.loaded_sample_state.as_mut().unwrap();
                     let prev_playhead_frames = loaded_sample_state.playhead_frames;
 
@@ -1216,6 +2170,22 @@ impl AudioNodeProcessor for SamplerProcessor {
                                     loaded_sample_state.num_times_looped_back += 1;
                                 }
                             }
+                            RepeatMode::RepeatWithGap { gap_frames, .. } => {
+                                // Ignore the jitter when skipping ahead.
+                                let period = loaded_sample_state.sample_len_frames + gap_frames;
+
+                                loaded_sample_state.num_times_looped_back +=
+                                    new_playhead_frames / period;
+                                new_playhead_frames %= period;
+
+                                if new_playhead_frames >= loaded_sample_state.sample_len_frames {
+                                    // The playhead lands in the gap.
+                                    loaded_sample_state.num_times_looped_back += 1;
+                                    loaded_sample_state.gap_frames_left =
+                                        period - new_playhead_frames;
+                                    new_playhead_frames = 0;
+                                }
+                            }
                         }
                     }
 
@@ -1238,7 +2208,11 @@ impl AudioNodeProcessor for SamplerProcessor {
                         || (self.num_active_stop_declickers > 0 && self.params.crossfade_on_seek)
                     {
                         self.declicker.reset_to_0();
-                        self.declicker.fade_to_1(&extra.declick_values);
+                        self.declicker.fade_to_1(
+                            self.declick_values
+                                .as_ref()
+                                .unwrap_or(&extra.declick_values),
+                        );
                     } else {
                         self.declicker.reset_to_1();
                     }
@@ -1250,8 +2224,35 @@ impl AudioNodeProcessor for SamplerProcessor {
                 }
             } else if self.params.play_from == PlayFrom::Resume {
                 // Pause
-                self.declicker.fade_to_0(&extra.declick_values);
+                self.declick_crossfade_out(extra);
+                self.declicker.fade_to_0(
+                    self.declick_values
+                        .as_ref()
+                        .unwrap_or(&extra.declick_values),
+                );
                 self.paused = true;
+            } else if self.params.stop_fade_seconds > 0.0
+                && self.playing
+                && self.loaded_sample_state.is_some()
+            {
+                // Fade out before stopping. The sample keeps playing until the
+                // fade has finished.
+                match &mut self.stop_fade {
+                    Some(stop_fade) => stop_fade.fading_in = false,
+                    None => {
+                        self.stop_fade = Some(StopFadeState {
+                            curve: self.params.stop_fade_curve,
+                            position_frames: 0,
+                            total_frames: ((self.params.stop_fade_seconds as f64
+                                * info.sample_rate.get() as f64)
+                                .round() as usize)
+                                .max(1),
+                            fading_in: false,
+                        });
+                    }
+                }
+
+                new_playing = true;
             } else {
                 // Stop
                 self.stop(extra);
@@ -1269,6 +2270,11 @@ impl AudioNodeProcessor for SamplerProcessor {
             };
         }
 
+        if let Some(crossfade) = new_crossfade {
+            self.start_crossfade(crossfade, info.sample_rate, extra);
+            proc_state_changed = true;
+        }
+
         if proc_state_changed {
             self.sync_proc_state();
         }
@@ -1277,6 +2283,7 @@ impl AudioNodeProcessor for SamplerProcessor {
     fn bypassed(&mut self, _bypassed: bool) {
         self.declicker.reset_to_target();
         self.num_active_stop_declickers = 0;
+        self.crossfade = None;
     }
 
     fn process(
@@ -1287,7 +2294,10 @@ impl AudioNodeProcessor for SamplerProcessor {
     ) -> ProcessStatus {
         let currently_processing_sample = self.currently_processing_sample();
 
-        if !currently_processing_sample && self.num_active_stop_declickers == 0 {
+        if !currently_processing_sample
+            && self.num_active_stop_declickers == 0
+            && self.crossfade.is_none()
+        {
             return ProcessStatus::ClearAllOutputs;
         }
 
@@ -1301,11 +2311,41 @@ impl AudioNodeProcessor for SamplerProcessor {
                 .repeat_mode
                 .do_loop(sample_state.num_times_looped_back);
 
-            let (finished, n_channels) =
-                self.process_internal(buffers.outputs, info.frames, looping, extra);
+            // The resampler ramps the speed across the block from the speed of
+            // the previous block to the new speed, so advancing the speed once
+            // per block results in a smooth glide.
+            let mut glide_reached_zero = false;
+            if let Some(glide) = &mut self.speed_glide {
+                glide.position_frames =
+                    (glide.position_frames + info.frames).min(glide.total_frames);
+                self.speed = glide.speed();
+
+                if glide.position_frames == glide.total_frames {
+                    glide_reached_zero = glide.pause_when_done;
+                    self.speed_glide = None;
+                }
+            }
+
+            let (mut finished, n_channels) =
+                self.process_with_gap(buffers.outputs, info.frames, looping, extra);
 
             num_filled_channels = n_channels;
 
+            if let Some(crossfade) = &self.crossfade {
+                crossfade.fade_in(&mut buffers.outputs[..n_channels], info.frames);
+            }
+
+            if let Some(stop_fade) = &mut self.stop_fade
+                && stop_fade.process(&mut buffers.outputs[..n_channels], info.frames)
+            {
+                if stop_fade.fading_in {
+                    self.stop_fade = None;
+                } else {
+                    self.reset_playhead();
+                    finished = true;
+                }
+            }
+
             self.proc_state.playhead_frames =
                 self.loaded_sample_state.as_ref().unwrap().playhead_frames;
 
@@ -1313,6 +2353,17 @@ impl AudioNodeProcessor for SamplerProcessor {
                 self.playing = false;
                 self.proc_state.playback_state = PlaybackState::Stopped;
                 self.proc_state.last_finished_playback_id = self.proc_state.playback_id;
+            } else if glide_reached_zero && self.playing && self.stop_fade.is_none() {
+                // The playhead has come to rest, so pause (keeping the current
+                // playhead) and declick the held output.
+                self.declicker.fade_to_0(
+                    self.declick_values
+                        .as_ref()
+                        .unwrap_or(&extra.declick_values),
+                );
+                self.playing = false;
+                self.paused = true;
+                self.proc_state.playback_state = PlaybackState::Paused;
             } else {
                 self.proc_state.playback_age_frames = self
                     .proc_state
@@ -1334,6 +2385,11 @@ impl AudioNodeProcessor for SamplerProcessor {
             }
         }
 
+        if self.crossfade.is_some() {
+            let n_channels = self.process_crossfade(buffers.outputs, info.frames, extra);
+            num_filled_channels = num_filled_channels.max(n_channels);
+        }
+
         if self.num_active_stop_declickers > 0 {
             let tmp_buffers = self.stop_declicker_buffers.as_ref().unwrap();
             let fade_out_frames = tmp_buffers.frames();
@@ -1381,7 +2437,26 @@ impl AudioNodeProcessor for SamplerProcessor {
         ProcessStatus::OutputsModifiedWithMask(MaskType::Silence(out_silence_mask))
     }
 
+    fn is_idle(&self) -> bool {
+        !self.currently_processing_sample()
+            && self.num_active_stop_declickers == 0
+            && self.crossfade.is_none()
+    }
+
     fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.crossfade = None;
+
+        if self
+            .crossfade_buffer
+            .as_ref()
+            .is_none_or(|b| b.frames() < stream_info.max_block_frames.get() as usize)
+        {
+            self.crossfade_buffer = Some(SequentialBuffer::new(
+                NonZeroUsize::new(self.num_out_channels).unwrap(),
+                stream_info.max_block_frames.get() as usize,
+            ));
+        }
+
         if stream_info.sample_rate != stream_info.prev_sample_rate {
             self.stop_declicker_buffers = if self.config.num_declickers == 0 {
                 None
@@ -1389,13 +2464,42 @@ impl AudioNodeProcessor for SamplerProcessor {
                 Some(InstanceBuffer::<f32>::new(
                     self.config.num_declickers as usize,
                     NonZeroUsize::new(self.config.channels.get().get() as usize).unwrap(),
-                    stream_info.declick_frames.get() as usize,
+                    stop_declick_frames(&self.config, stream_info),
                 ))
             };
 
+            let prev_sample_rate = self.sample_rate;
+            self.sample_rate = stream_info.sample_rate;
+
+            if self.config.auto_reload_on_rate_change {
+                // Keep playing the sample resource, and resample it to the new
+                // sample rate.
+                if let Some(state) = &self.loaded_sample_state {
+                    self.rate_ratio = match state.sample.sample_rate() {
+                        Some(rate) => rate.get() as f64 / self.sample_rate.get() as f64,
+                        None => {
+                            self.rate_ratio * prev_sample_rate.get() as f64
+                                / self.sample_rate.get() as f64
+                        }
+                    };
+                }
+
+                return;
+            }
+
             // The sample rate has changed, meaning that the sample resources now have
-            // the incorrect sample rate and the user must reload them.
+            // the incorrect sample rate and the user must reload them. (The
+            // `SampleCache` in `firewheel-symphonium` can do this in bulk.)
+            self.proc_state.needs_reload =
+                self.loaded_sample_state.is_some() || self.sample_set.is_some();
             self.loaded_sample_state = None;
+            self.sample_set = None;
+            self.stop_fade = None;
+            if let Some(glide) = self.speed_glide.take() {
+                self.speed = glide.target;
+            }
+            self.proc_state.has_sample_resource = false;
+            self.proc_state.sample_len_frames = 0;
             self.playing = false;
             self.paused = false;
             self.proc_state.playback_state = PlaybackState::Stopped;
@@ -1404,6 +2508,26 @@ impl AudioNodeProcessor for SamplerProcessor {
     }
 }
 
+/// Fill the given range of the buffers from a streamed sample, clearing any
+/// frames which could not be filled in time.
+fn fill_from_streamed(
+    sample: &mut dyn StreamedSample,
+    buffers: &mut [&mut [f32]],
+    range_in_buffer: Range<usize>,
+    start_frame: u64,
+    speed: f64,
+) {
+    let frames_filled =
+        sample.fill_buffers(buffers, range_in_buffer.clone(), start_frame, speed, false);
+
+    if frames_filled < range_in_buffer.end - range_in_buffer.start {
+        let n_channels = buffers.len().min(sample.num_channels().get());
+        for b in buffers[..n_channels].iter_mut() {
+            b[range_in_buffer.start + frames_filled..range_in_buffer.end].fill(0.0);
+        }
+    }
+}
+
 struct LoadedSampleState {
     sample: SamplerNodeResource,
     sample_len_frames: u64,
@@ -1412,6 +2536,9 @@ struct LoadedSampleState {
     gain: f32,
     playhead_frames: u64,
     num_times_looped_back: u64,
+    /// The number of frames left in the silent gap before the sample is
+    /// repeated when using [`RepeatMode::RepeatWithGap`].
+    gap_frames_left: u64,
 }
 
 #[derive(Default, Clone, Copy)]
@@ -1419,3 +2546,133 @@ struct StopDeclickerState {
     frames_left: usize,
     channels: usize,
 }
+
+/// The state of a glide started with [`SamplerNode::set_speed_glide`].
+#[derive(Clone, Copy)]
+struct SpeedGlideState {
+    start: f64,
+    target: f64,
+    curve: SpeedGlideCurve,
+    position_frames: usize,
+    total_frames: usize,
+    /// Whether to pause the sample once the speed has glided to zero.
+    pause_when_done: bool,
+}
+
+impl SpeedGlideState {
+    fn speed(&self) -> f64 {
+        if self.position_frames >= self.total_frames {
+            return self.target;
+        }
+
+        let t = self.position_frames as f64 / self.total_frames as f64;
+
+        (self.start + (self.target - self.start) * self.curve.apply(t)).max(MIN_PLAYBACK_SPEED)
+    }
+}
+
+/// The state of a fade out started with [`SamplerNode::stop_with_fade`].
+#[derive(Clone, Copy)]
+/// An event which crossfades from the currently playing sample into a new one.
+///
+/// See [`SamplerNode::crossfade_to`].
+struct CrossfadeToEvent {
+    sample: SamplerNodeResource,
+    volume: Volume,
+    seconds: f32,
+}
+
+/// The state of a sample which is being faded out by a crossfade.
+struct OutgoingSample {
+    state: Option<LoadedSampleState>,
+    resampler: Option<Resampler>,
+    rate_ratio: f64,
+    variation_speed: f64,
+    declicker: Declicker,
+    /// The gain of the sample at the start of the crossfade.
+    gain: f32,
+}
+
+struct CrossfadeState {
+    /// The sample which is being faded out. This is `None` if no sample was
+    /// playing when the crossfade started, or if it has finished playing.
+    out_sample: Option<OutgoingSample>,
+    position_frames: usize,
+    total_frames: usize,
+}
+
+impl CrossfadeState {
+    /// Returns the gains of the outgoing and incoming samples at the given
+    /// frame offset from the current position.
+    fn gains(&self, offset: usize) -> (f32, f32) {
+        FadeCurve::EqualPower3dB.compute_gains_0_to_1(
+            ((self.position_frames + offset) as f32 / self.total_frames as f32).min(1.0),
+        )
+    }
+
+    /// Apply the fade in of the incoming sample to the given buffers.
+    fn fade_in(&self, buffers: &mut [&mut [f32]], frames: usize) {
+        for i in 0..frames {
+            let gain = self.gains(i).1;
+            for b in buffers.iter_mut() {
+                b[i] *= gain;
+            }
+        }
+    }
+}
+
+struct StopFadeState {
+    curve: FadeCurve,
+    /// The position in the fade, where `0` is full volume and `total_frames`
+    /// is silence.
+    position_frames: usize,
+    total_frames: usize,
+    /// Whether the fade was cancelled by resuming, in which case the fade is
+    /// played backwards.
+    fading_in: bool,
+}
+
+impl StopFadeState {
+    fn gain(&self) -> f32 {
+        self.curve
+            .compute_gains_0_to_1(self.position_frames as f32 / self.total_frames as f32)
+            .0
+    }
+
+    /// Apply the fade to the given buffers.
+    ///
+    /// Returns `true` if the fade has finished, either because the signal has
+    /// fully faded out, or because it has fully faded back in.
+    fn process(&mut self, buffers: &mut [&mut [f32]], frames: usize) -> bool {
+        for i in 0..frames {
+            if self.fading_in {
+                if self.position_frames == 0 {
+                    return true;
+                }
+
+                self.position_frames -= 1;
+            } else {
+                if self.position_frames >= self.total_frames {
+                    for b in buffers.iter_mut() {
+                        b[i..frames].fill(0.0);
+                    }
+
+                    return true;
+                }
+
+                self.position_frames += 1;
+            }
+
+            let gain = self.gain();
+            for b in buffers.iter_mut() {
+                b[i] *= gain;
+            }
+        }
+
+        if self.fading_in {
+            self.position_frames == 0
+        } else {
+            self.position_frames >= self.total_frames
+        }
+    }
+}
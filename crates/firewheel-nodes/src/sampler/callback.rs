@@ -0,0 +1,223 @@
+use core::{
+    num::{NonZeroU32, NonZeroUsize},
+    ops::Range,
+};
+
+use firewheel_core::sample_resource::SampleResourceInfo;
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::{Vec, vec};
+
+use super::StreamedSample;
+
+/// A streamed sample resource that plays back audio produced on demand by a
+/// user-provided callback, such as a software synthesizer or a decoder.
+///
+/// The callback runs on a separate thread via a [`CallbackSampleFeeder`],
+/// which writes into a ring buffer that this resource reads from. Reading
+/// from the ring buffer never blocks. If the feeder does not keep up, then
+/// the missing frames are played back as silence.
+///
+/// Because the content is produced on demand, this resource has an
+/// effectively infinite length, and it always continues from where it last
+/// left off. This has a few limitations:
+///
+/// * Seeking and playing backwards are not supported. If the sampler requests
+///   any frame other than the next one, then nothing is filled and the
+///   sampler outputs silence. The only exception is frame `0`, so that
+///   restarting playback from the beginning continues the generated content.
+/// * The playback speed is applied by the sampler node, which resamples the
+///   frames read from this resource, so changing the speed changes how fast
+///   the content is consumed rather than how it is generated.
+///
+/// Use [`SamplerNodeResource::from_streamed`](super::SamplerNodeResource::from_streamed)
+/// or [`SamplerNode::set_streamed_sample_event`](super::SamplerNode::set_streamed_sample_event)
+/// to play it in a sampler node.
+pub struct CallbackSample {
+    cons: ExclusiveCons,
+    num_channels: NonZeroUsize,
+    sample_rate: Option<NonZeroU32>,
+    /// The frame in this resource of the next frame in the ring buffer.
+    next_frame: u64,
+}
+
+impl CallbackSample {
+    /// Construct a new callback sample resource and its feeder.
+    ///
+    /// * `num_channels` - The number of channels the callback produces.
+    /// * `sample_rate` - The sample rate of the produced audio, or `None` if
+    ///   it is the same as the sample rate of the audio stream.
+    /// * `capacity_frames` - The capacity of the ring buffer in frames. This
+    ///   should be comfortably larger than both the block size of the audio
+    ///   stream and the amount of frames the feeder produces at once.
+    /// * `callback` - The callback producing the audio. It is given a slice
+    ///   of interleaved samples to fill, and it returns the number of frames
+    ///   it wrote.
+    ///
+    /// Consider calling [`CallbackSampleFeeder::feed`] once before playback
+    /// starts so that the ring buffer is prefilled.
+    pub fn new<F>(
+        num_channels: NonZeroUsize,
+        sample_rate: Option<NonZeroU32>,
+        capacity_frames: usize,
+        callback: F,
+    ) -> (Self, CallbackSampleFeeder<F>)
+    where
+        F: FnMut(&mut [f32]) -> usize + Send + 'static,
+    {
+        let capacity = capacity_frames.max(1) * num_channels.get();
+
+        let (prod, cons) = ringbuf::HeapRb::<f32>::new(capacity).split();
+
+        (
+            Self {
+                cons: ExclusiveCons(cons),
+                num_channels,
+                sample_rate,
+                next_frame: 0,
+            },
+            CallbackSampleFeeder {
+                prod,
+                callback,
+                num_channels,
+                scratch: vec![0.0; capacity],
+            },
+        )
+    }
+
+    /// The number of frames currently buffered and ready to be read.
+    pub fn buffered_frames(&mut self) -> usize {
+        self.cons.0.occupied_len() / self.num_channels.get()
+    }
+
+    /// Returns `true` if reading can continue at the given frame, see the
+    /// limitations in the documentation of [`CallbackSample`].
+    fn can_read_from(&self, frame: u64) -> bool {
+        frame == self.next_frame || frame == 0
+    }
+}
+
+/// The consumer of the ring buffer, which can only be accessed mutably.
+struct ExclusiveCons(ringbuf::HeapCons<f32>);
+
+// # Safety
+//
+// The consumer is only ever accessed through `&mut ExclusiveCons`, so it is
+// guaranteed that it can only be accessed by one thread at a time.
+unsafe impl Sync for ExclusiveCons {}
+
+impl SampleResourceInfo for CallbackSample {
+    fn num_channels(&self) -> NonZeroUsize {
+        self.num_channels
+    }
+
+    fn len_frames(&self) -> u64 {
+        u64::MAX
+    }
+
+    fn sample_rate(&self) -> Option<NonZeroU32> {
+        self.sample_rate
+    }
+}
+
+impl StreamedSample for CallbackSample {
+    fn fill_buffers(
+        &mut self,
+        out_buffer: &mut [&mut [f32]],
+        out_buffer_range: Range<usize>,
+        start_frame: u64,
+        _speed: f64,
+        is_playing_backwards: bool,
+    ) -> usize {
+        if is_playing_backwards || !self.can_read_from(start_frame) {
+            return 0;
+        }
+
+        let num_channels = self.num_channels.get();
+        let frames = self
+            .buffered_frames()
+            .min(out_buffer_range.end - out_buffer_range.start);
+
+        let (s1, s2) = self.cons.0.as_slices();
+        let mut samples = s1.iter().chain(s2.iter());
+
+        for i in out_buffer_range.start..out_buffer_range.start + frames {
+            for ch_i in 0..num_channels {
+                let s = *samples.next().unwrap();
+
+                if let Some(b) = out_buffer.get_mut(ch_i) {
+                    b[i] = s;
+                }
+            }
+        }
+
+        self.cons.0.skip(frames * num_channels);
+        self.next_frame = start_frame + frames as u64;
+
+        frames
+    }
+
+    fn range_is_ready(&mut self, range: Range<u64>) -> bool {
+        self.can_read_from(range.start) && self.buffered_frames() as u64 >= range.end - range.start
+    }
+
+    fn cache_new_starting_frame(&mut self, _frame: u64, _speed: f64, _will_play_backwards: bool) {
+        // Generated content always continues from where it left off.
+    }
+}
+
+/// Feeds a [`CallbackSample`] with audio produced by its callback.
+///
+/// This should live on a separate thread from the audio thread.
+pub struct CallbackSampleFeeder<F> {
+    prod: ringbuf::HeapProd<f32>,
+    callback: F,
+    num_channels: NonZeroUsize,
+    scratch: Vec<f32>,
+}
+
+impl<F> CallbackSampleFeeder<F>
+where
+    F: FnMut(&mut [f32]) -> usize + Send + 'static,
+{
+    /// Call the callback to fill all vacant space in the ring buffer.
+    ///
+    /// Returns the number of frames that were written.
+    pub fn feed(&mut self) -> usize {
+        let num_channels = self.num_channels.get();
+        let vacant_frames = self.prod.vacant_len() / num_channels;
+
+        if vacant_frames == 0 {
+            return 0;
+        }
+
+        let scratch = &mut self.scratch[..vacant_frames * num_channels];
+        let frames = (self.callback)(scratch).min(vacant_frames);
+
+        self.prod.push_slice(&scratch[..frames * num_channels]) / num_channels
+    }
+
+    /// Returns `true` if the corresponding [`CallbackSample`] has been
+    /// dropped.
+    pub fn is_orphaned(&self) -> bool {
+        !self.prod.read_is_held()
+    }
+
+    /// Repeatedly feed the ring buffer, sleeping for `poll_interval` whenever
+    /// it is full, until the corresponding [`CallbackSample`] is dropped.
+    ///
+    /// This blocks the current thread, so it is intended to be used like so:
+    ///
+    /// ```ignore
+    /// std::thread::spawn(move || feeder.run(Duration::from_millis(5)));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn run(mut self, poll_interval: std::time::Duration) {
+        while !self.is_orphaned() {
+            if self.feed() == 0 {
+                std::thread::sleep(poll_interval);
+            }
+        }
+    }
+}
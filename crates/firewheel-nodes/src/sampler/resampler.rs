@@ -1,17 +1,28 @@
 use core::ops::Range;
-use firewheel_core::node::ProcExtra;
+use firewheel_core::{
+    dsp::filter::{
+        butterworth::Q_BUTTERWORTH_ORD2,
+        svf::{SvfCoeff, SvfState},
+    },
+    node::ProcExtra,
+};
 
 #[cfg(not(feature = "std"))]
 use num_traits::Float;
 
 use super::{MAX_OUT_CHANNELS, PlaybackSpeedQuality, SamplerProcessor};
 
+/// The cutoff of the anti-aliasing filter as a fraction of the Nyquist
+/// frequency of the effective read rate. This is slightly below `1.0` to
+/// account for the slope of the filter.
+const ANTI_ALIAS_CUTOFF_RATIO: f32 = 0.9;
+
 pub(super) struct Resampler {
     fract_in_frame: f64,
     is_first_process: bool,
     prev_speed: f64,
-    _quality: PlaybackSpeedQuality,
     wraparound_buffer: [[f32; 2]; MAX_OUT_CHANNELS],
+    anti_alias_filter: Option<AntiAliasFilter>,
 }
 
 impl Resampler {
@@ -20,8 +31,11 @@ impl Resampler {
             fract_in_frame: 0.0,
             is_first_process: true,
             prev_speed: 1.0,
-            _quality: quality,
             wraparound_buffer: [[0.0; 2]; MAX_OUT_CHANNELS],
+            anti_alias_filter: match quality {
+                PlaybackSpeedQuality::LinearFast => None,
+                PlaybackSpeedQuality::LinearAntiAliased => Some(AntiAliasFilter::new()),
+            },
         }
     }
 
@@ -38,12 +52,12 @@ impl Resampler {
         assert_ne!(total_out_frames, 0);
 
         let in_frame_start = if self.is_first_process {
-            self.prev_speed = processor.speed;
+            self.prev_speed = processor.playback_speed();
             self.fract_in_frame = 0.0;
 
             0.0
         } else {
-            self.fract_in_frame + processor.speed
+            self.fract_in_frame + processor.playback_speed()
         };
 
         let out_frame_to_in_frame = |out_frame: f64, in_frame_start: f64, speed: f64| -> f64 {
@@ -64,7 +78,13 @@ impl Resampler {
         let copy_start = if self.is_first_process { 0 } else { 2 };
         let mut finished_playing = false;
 
-        if self.prev_speed == processor.speed {
+        if let Some(filter) = &mut self.anti_alias_filter {
+            // Use the highest speed in this block so the filter never lets
+            // through frequencies which would alias.
+            filter.set_speed(self.prev_speed.max(processor.playback_speed()));
+        }
+
+        if self.prev_speed == processor.playback_speed() {
             self.resample_linear_inner(
                 out_frame_to_in_frame,
                 in_frame_start,
@@ -80,7 +100,8 @@ impl Resampler {
                 &mut finished_playing,
             );
         } else {
-            let half_accel = 0.5 * (processor.speed - self.prev_speed) / total_out_frames as f64;
+            let half_accel =
+                0.5 * (processor.playback_speed() - self.prev_speed) / total_out_frames as f64;
 
             self.resample_linear_inner(
                 |out_frame: f64, in_frame_start: f64, speed: f64| {
@@ -100,7 +121,7 @@ impl Resampler {
             );
         }
 
-        self.prev_speed = processor.speed;
+        self.prev_speed = processor.playback_speed();
         self.is_first_process = false;
 
         (finished_playing, num_channels)
@@ -147,6 +168,13 @@ impl Resampler {
                 if finished {
                     *finished_playing = true;
                 }
+
+                if let Some(filter) = &mut self.anti_alias_filter {
+                    filter.process(
+                        &mut scratch_buffers[..num_channels],
+                        copy_start..input_frames,
+                    );
+                }
             }
 
             let max_block_frames_minus_1 = processor.max_block_frames - 1;
@@ -271,5 +299,75 @@ impl Resampler {
     pub fn reset(&mut self) {
         self.fract_in_frame = 0.0;
         self.is_first_process = true;
+
+        if let Some(filter) = &mut self.anti_alias_filter {
+            filter.reset();
+        }
+    }
+}
+
+/// A low-pass filter which is applied to the sample before it is resampled
+/// when the playback speed is above `1.0`.
+struct AntiAliasFilter {
+    coeffs: [SvfCoeff; 2],
+    states: [[SvfState; 2]; MAX_OUT_CHANNELS],
+    /// The playback speed the coefficients were computed for, or `None` if
+    /// the filter is inactive.
+    speed: Option<f64>,
+}
+
+impl AntiAliasFilter {
+    fn new() -> Self {
+        Self {
+            coeffs: [SvfCoeff::NO_OP; 2],
+            states: [[SvfState::default(); 2]; MAX_OUT_CHANNELS],
+            speed: None,
+        }
+    }
+
+    fn set_speed(&mut self, speed: f64) {
+        if speed <= 1.0 {
+            self.speed = None;
+            return;
+        }
+
+        if self.speed == Some(speed) {
+            return;
+        }
+
+        if self.speed.is_none() {
+            // Don't let stale state from a previous activation leak through.
+            self.reset();
+        }
+
+        // The cutoff is normalized to the sample rate of the sample resource,
+        // so a sample rate of `1.0` is used here.
+        self.coeffs = SvfCoeff::lowpass_ord4(
+            0.5 * ANTI_ALIAS_CUTOFF_RATIO / speed as f32,
+            Q_BUTTERWORTH_ORD2,
+            1.0,
+        );
+        self.speed = Some(speed);
+    }
+
+    fn process(&mut self, buffers: &mut [&mut [f32]], range: Range<usize>) {
+        if self.speed.is_none() {
+            return;
+        }
+
+        for (buf, states) in buffers.iter_mut().zip(self.states.iter_mut()) {
+            for s in buf[range.clone()].iter_mut() {
+                let s0 = states[0].process(*s, &self.coeffs[0]);
+                *s = states[1].process(s0, &self.coeffs[1]);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for states in self.states.iter_mut() {
+            for state in states.iter_mut() {
+                state.reset();
+            }
+        }
     }
 }
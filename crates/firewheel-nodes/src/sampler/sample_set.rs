@@ -0,0 +1,136 @@
+use firewheel_core::{collector::ArcGc, sample_resource::SampleResource};
+
+#[cfg(not(feature = "std"))]
+use bevy_platform::prelude::Vec;
+
+/// A set of in-memory samples for a [`SamplerNode`](super::SamplerNode),
+/// split into velocity layers which each hold one or more round-robin
+/// variants.
+///
+/// When playback is started, the sampler picks the layer matching
+/// [`SamplerNode::velocity`](super::SamplerNode::velocity), and then the next
+/// variant in that layer. This avoids the "machine gun" effect of the exact
+/// same sample being played over and over.
+#[derive(Default, Clone)]
+pub struct SampleSet {
+    layers: Vec<VelocityLayer>,
+}
+
+/// A layer in a [`SampleSet`] which is played for a range of velocities.
+#[derive(Clone)]
+pub struct VelocityLayer {
+    /// The lowest velocity (inclusive) this layer is played for, in the range
+    /// `[0.0, 1.0]`.
+    pub min_velocity: f32,
+    /// The highest velocity (inclusive) this layer is played for, in the
+    /// range `[0.0, 1.0]`.
+    pub max_velocity: f32,
+    /// The round-robin variants of this layer, which are played in order.
+    pub variants: Vec<ArcGc<dyn SampleResource + Send + Sync + 'static>>,
+}
+
+impl SampleSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer which is played for velocities in the range
+    /// `[min_velocity, max_velocity]`.
+    ///
+    /// Layers with no variants are ignored.
+    pub fn with_layer(
+        mut self,
+        min_velocity: f32,
+        max_velocity: f32,
+        variants: impl IntoIterator<Item = ArcGc<dyn SampleResource + Send + Sync + 'static>>,
+    ) -> Self {
+        self.add_layer(VelocityLayer {
+            min_velocity,
+            max_velocity,
+            variants: variants.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Add a layer to this set.
+    ///
+    /// Layers with no variants are ignored.
+    pub fn add_layer(&mut self, layer: VelocityLayer) {
+        if !layer.variants.is_empty() {
+            self.layers.push(layer);
+        }
+    }
+
+    /// The layers in this set.
+    pub fn layers(&self) -> &[VelocityLayer] {
+        &self.layers
+    }
+
+    /// Returns `true` if this set contains no samples.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Select the sample to play for the given velocity and round-robin index.
+    ///
+    /// If no layer covers the velocity, then the layer with the closest range
+    /// is used. If multiple layers cover the velocity, then the first one
+    /// which was added is used.
+    ///
+    /// Returns `None` if this set is empty.
+    pub fn select(
+        &self,
+        velocity: f32,
+        round_robin_index: usize,
+    ) -> Option<&ArcGc<dyn SampleResource + Send + Sync + 'static>> {
+        let layer = self
+            .layers
+            .iter()
+            .find(|l| velocity >= l.min_velocity && velocity <= l.max_velocity)
+            .or_else(|| {
+                self.layers
+                    .iter()
+                    .min_by(|a, b| a.distance_to(velocity).total_cmp(&b.distance_to(velocity)))
+            })?;
+
+        layer.variants.get(round_robin_index % layer.variants.len())
+    }
+}
+
+impl VelocityLayer {
+    fn distance_to(&self, velocity: f32) -> f32 {
+        if velocity < self.min_velocity {
+            self.min_velocity - velocity
+        } else {
+            velocity - self.max_velocity
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample(len: usize) -> ArcGc<dyn SampleResource + Send + Sync + 'static> {
+        vec![vec![0.0f32; len]].into()
+    }
+
+    #[test]
+    fn select_layer_and_variant() {
+        let set = SampleSet::new()
+            .with_layer(0.0, 0.5, [sample(1), sample(2)])
+            .with_layer(0.6, 0.9, [sample(3)]);
+
+        let selected = |velocity, index| set.select(velocity, index).unwrap().len_frames();
+
+        assert_eq!(selected(0.2, 0), 1);
+        assert_eq!(selected(0.2, 1), 2);
+        assert_eq!(selected(0.2, 2), 1);
+        assert_eq!(selected(0.7, 5), 3);
+        // Falls back to the closest layer.
+        assert_eq!(selected(1.0, 0), 3);
+        assert_eq!(selected(0.54, 0), 1);
+
+        assert!(SampleSet::new().select(0.5, 0).is_none());
+    }
+}
@@ -0,0 +1,546 @@
+//! A node which measures the frequency spectrum of a signal, i.e. for
+//! visualizers.
+
+use core::{f32::consts::TAU, num::NonZeroU32};
+
+use bevy_platform::sync::{Arc, Mutex, MutexGuard};
+use realfft::{RealFftPlanner, RealToComplex, num_complex::Complex};
+
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, ChannelCount, NonZeroChannelCount},
+    diff::{Diff, Patch},
+    dsp::volume::DEFAULT_MIN_AMP,
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, NodeError,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+};
+
+/// The size of the FFT used by a [`SpectrumNode`].
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FftSize {
+    S256,
+    S512,
+    S1024,
+    #[default]
+    S2048,
+    S4096,
+    S8192,
+    S16384,
+}
+
+impl FftSize {
+    const ALL: [Self; 7] = [
+        Self::S256,
+        Self::S512,
+        Self::S1024,
+        Self::S2048,
+        Self::S4096,
+        Self::S8192,
+        Self::S16384,
+    ];
+
+    /// The number of frames in the FFT.
+    pub const fn frames(&self) -> usize {
+        256 << (*self as usize)
+    }
+
+    /// The number of frequency bins produced by the FFT.
+    pub const fn num_bins(&self) -> usize {
+        self.frames() / 2 + 1
+    }
+}
+
+/// The window function applied before the FFT in a [`SpectrumNode`].
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpectrumWindow {
+    /// No window. This has the best frequency resolution, but causes a lot
+    /// of spectral leakage.
+    Rectangular,
+    /// A good general-purpose window.
+    #[default]
+    Hann,
+    /// Less spectral leakage than [`SpectrumWindow::Hann`] at the cost of
+    /// wider peaks.
+    Blackman,
+}
+
+impl SpectrumWindow {
+    /// The value of the window at frame `i` of a window of length `n`.
+    fn value_at(&self, i: usize, n: usize) -> f32 {
+        let x = i as f32 / n as f32;
+
+        match self {
+            Self::Rectangular => 1.0,
+            Self::Hann => 0.5 - 0.5 * (TAU * x).cos(),
+            Self::Blackman => 0.42 - 0.5 * (TAU * x).cos() + 0.08 * (2.0 * TAU * x).cos(),
+        }
+    }
+}
+
+/// The configuration of a [`SpectrumNode`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpectrumConfig {
+    /// The number of input channels. All channels are summed to mono before
+    /// being analyzed.
+    ///
+    /// By default this is set to [`NonZeroChannelCount::STEREO`].
+    pub channels: NonZeroChannelCount,
+    /// The largest FFT size that can be used. The buffers for this size are
+    /// allocated up front, so that changing [`SpectrumNode::fft_size`] is
+    /// realtime-safe.
+    ///
+    /// By default this is set to [`FftSize::S8192`].
+    pub max_fft_size: FftSize,
+}
+
+impl Default for SpectrumConfig {
+    fn default() -> Self {
+        Self {
+            channels: NonZeroChannelCount::STEREO,
+            max_fft_size: FftSize::S8192,
+        }
+    }
+}
+
+/// A node which runs a windowed FFT on its input, and exposes the magnitude
+/// of each frequency bin to the main thread with [`SpectrumState`].
+///
+/// The FFT is run every half of an FFT size (50% overlap).
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpectrumNode {
+    /// The size of the FFT. Larger sizes have a better frequency resolution,
+    /// but a worse time resolution.
+    ///
+    /// This is clamped to [`SpectrumConfig::max_fft_size`].
+    ///
+    /// By default this is set to [`FftSize::S2048`].
+    pub fft_size: FftSize,
+    /// The window function applied before the FFT.
+    ///
+    /// By default this is set to [`SpectrumWindow::Hann`].
+    pub window: SpectrumWindow,
+    /// How much the previous magnitudes are blended into the new magnitudes,
+    /// in the range `[0.0, 1.0)`. Higher values result in a smoother and
+    /// slower moving spectrum.
+    ///
+    /// By default this is set to `0.8`.
+    pub smoothing: f32,
+}
+
+impl Default for SpectrumNode {
+    fn default() -> Self {
+        Self {
+            fft_size: FftSize::default(),
+            window: SpectrumWindow::default(),
+            smoothing: 0.8,
+        }
+    }
+}
+
+/// The state of a [`SpectrumNode`], used to read the latest magnitudes on
+/// the main thread.
+#[derive(Clone)]
+pub struct SpectrumState {
+    active_state: Arc<Mutex<Option<ActiveState>>>,
+}
+
+impl SpectrumState {
+    /// Get the latest spectrum data.
+    pub fn output<'a>(&'a mut self) -> SpectrumDataGuard<'a> {
+        SpectrumDataGuard {
+            guarded_state: self.active_state.lock().unwrap(),
+        }
+    }
+}
+
+struct ActiveState {
+    consumer: triple_buffer::Output<SpectrumBuffer>,
+    sample_rate: NonZeroU32,
+}
+
+pub struct SpectrumData<'a> {
+    /// The linear magnitude of each frequency bin, where bin `i` is centered
+    /// at `i * sample_rate / fft_size` hertz. A full-scale sine wave has a
+    /// magnitude of roughly `1.0`.
+    ///
+    /// Use [`firewheel_core::dsp::volume::amp_to_db`] to convert these to
+    /// decibels.
+    pub magnitudes: &'a [f32],
+
+    /// The size of the FFT which produced this data.
+    pub fft_size: FftSize,
+
+    /// The sample rate of the analyzed audio.
+    pub sample_rate: NonZeroU32,
+
+    /// A value equal to how many times the data has been updated since the
+    /// node was first created. This can be used to quickly check if the data
+    /// differs from the previous read.
+    pub generation: u64,
+}
+
+impl<'a> SpectrumData<'a> {
+    /// The center frequency of the given bin in hertz.
+    pub fn bin_frequency_hz(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate.get() as f32 / self.fft_size.frames() as f32
+    }
+}
+
+pub struct SpectrumDataGuard<'a> {
+    guarded_state: MutexGuard<'a, Option<ActiveState>>,
+}
+
+impl<'a> SpectrumDataGuard<'a> {
+    /// Returns `true` if the node is currently active.
+    pub fn is_active(&self) -> bool {
+        self.guarded_state.is_some()
+    }
+
+    /// Get the latest spectrum data.
+    ///
+    /// If the node is not currently active, then this will return `None`.
+    pub fn data<'b>(&'b mut self) -> Option<SpectrumData<'b>> {
+        self.guarded_state.as_mut().map(|s| {
+            let sample_rate = s.sample_rate;
+            let c = s.consumer.read();
+            SpectrumData {
+                magnitudes: &c.magnitudes[..c.fft_size.num_bins()],
+                fft_size: c.fft_size,
+                sample_rate,
+                generation: c.generation,
+            }
+        })
+    }
+}
+
+impl AudioNode for SpectrumNode {
+    type Configuration = SpectrumConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("spectrum")
+            .channel_config(ChannelConfig {
+                num_inputs: config.channels.get(),
+                num_outputs: ChannelCount::ZERO,
+            })
+            .custom_state(SpectrumState {
+                active_state: Arc::new(Mutex::new(None)),
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        mut cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let max_frames = config.max_fft_size.frames();
+        let max_bins = config.max_fft_size.num_bins();
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let ffts: Vec<Arc<dyn RealToComplex<f32>>> = FftSize::ALL
+            .iter()
+            .filter(|size| **size <= config.max_fft_size)
+            .map(|size| planner.plan_fft_forward(size.frames()))
+            .collect();
+        let scratch_len = ffts.iter().map(|f| f.get_scratch_len()).max().unwrap_or(0);
+
+        let fft_size = self.fft_size.min(config.max_fft_size);
+
+        let (producer, consumer) =
+            triple_buffer::triple_buffer(&SpectrumBuffer::new(max_bins, fft_size, 0));
+
+        let state = cx.custom_state_mut::<SpectrumState>().unwrap();
+        *state.active_state.lock().unwrap() = Some(ActiveState {
+            consumer,
+            sample_rate: cx.stream_info.sample_rate,
+        });
+        let active_state = Arc::clone(&state.active_state);
+
+        let mut processor = Processor {
+            params: *self,
+            config: *config,
+            fft_size,
+            ffts,
+            ring: vec![0.0; max_frames],
+            ring_ptr: 0,
+            frames_since_fft: 0,
+            num_silent_frames: max_frames,
+            published_silence: true,
+            window: vec![0.0; max_frames],
+            window_sum: 0.0,
+            fft_in: vec![0.0; max_frames],
+            fft_out: vec![Complex::default(); max_bins],
+            scratch: vec![Complex::default(); scratch_len],
+            smoothed: vec![0.0; max_bins],
+            producer: Some(producer),
+            active_state,
+            generation: 0,
+        };
+        processor.update_window();
+
+        Ok(processor)
+    }
+}
+
+struct Processor {
+    params: SpectrumNode,
+    config: SpectrumConfig,
+    fft_size: FftSize,
+    /// A plan for every FFT size up to the maximum size.
+    ffts: Vec<Arc<dyn RealToComplex<f32>>>,
+
+    /// The most recent `fft_size` frames of input, where `ring_ptr` points to
+    /// the oldest frame.
+    ring: Vec<f32>,
+    ring_ptr: usize,
+    frames_since_fft: usize,
+    num_silent_frames: usize,
+    published_silence: bool,
+
+    window: Vec<f32>,
+    window_sum: f32,
+    fft_in: Vec<f32>,
+    fft_out: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    smoothed: Vec<f32>,
+
+    producer: Option<triple_buffer::Input<SpectrumBuffer>>,
+    // The processor only uses this when a new stream has started.
+    active_state: Arc<Mutex<Option<ActiveState>>>,
+    generation: u64,
+}
+
+impl Processor {
+    fn update_window(&mut self) {
+        let n = self.fft_size.frames();
+
+        for (i, w) in self.window[..n].iter_mut().enumerate() {
+            *w = self.params.window.value_at(i, n);
+        }
+
+        self.window_sum = self.window[..n].iter().sum();
+    }
+
+    fn reset(&mut self) {
+        self.ring.fill(0.0);
+        self.ring_ptr = 0;
+        self.frames_since_fft = 0;
+        self.smoothed.fill(0.0);
+        self.num_silent_frames = self.ring.len();
+    }
+
+    fn run_fft(&mut self) {
+        let Some(producer) = self.producer.as_mut() else {
+            return;
+        };
+
+        let n = self.fft_size.frames();
+        let num_bins = self.fft_size.num_bins();
+
+        // Unwrap the ring buffer from oldest to newest frame.
+        let (head, tail) = self.ring[..n].split_at(self.ring_ptr);
+        for ((out, s), w) in self.fft_in[..n]
+            .iter_mut()
+            .zip(tail.iter().chain(head.iter()))
+            .zip(self.window[..n].iter())
+        {
+            *out = s * w;
+        }
+
+        let fft = &self.ffts[self.fft_size as usize];
+        let _ = fft.process_with_scratch(
+            &mut self.fft_in[..n],
+            &mut self.fft_out[..num_bins],
+            &mut self.scratch[..fft.get_scratch_len()],
+        );
+
+        let norm = if self.window_sum > 0.0 {
+            2.0 / self.window_sum
+        } else {
+            0.0
+        };
+        let smoothing = self.params.smoothing.clamp(0.0, 0.999);
+
+        let mut max_magnitude: f32 = 0.0;
+        {
+            let buffer = producer.input_buffer_mut();
+
+            for ((c, smoothed), out) in self.fft_out[..num_bins]
+                .iter()
+                .zip(self.smoothed[..num_bins].iter_mut())
+                .zip(buffer.magnitudes[..num_bins].iter_mut())
+            {
+                *smoothed = smoothing * *smoothed + (1.0 - smoothing) * c.norm() * norm;
+                *out = *smoothed;
+                max_magnitude = max_magnitude.max(*smoothed);
+            }
+
+            self.generation += 1;
+            buffer.generation = self.generation;
+            buffer.fft_size = self.fft_size;
+        }
+
+        producer.publish();
+
+        self.published_silence = max_magnitude < DEFAULT_MIN_AMP;
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, _info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<SpectrumNode>() {
+            match patch {
+                SpectrumNodePatch::FftSize(fft_size) => {
+                    let fft_size = fft_size.min(self.config.max_fft_size);
+
+                    if self.fft_size != fft_size {
+                        self.fft_size = fft_size;
+                        self.reset();
+                        self.update_window();
+                    }
+                }
+                SpectrumNodePatch::Window(window) => {
+                    self.params.window = window;
+                    self.update_window();
+                }
+                _ => {}
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn bypassed(&mut self, bypassed: bool) {
+        if bypassed {
+            self.reset();
+
+            if let Some(producer) = self.producer.as_mut() {
+                {
+                    let buffer = producer.input_buffer_mut();
+                    buffer.magnitudes.fill(0.0);
+
+                    self.generation += 1;
+                    buffer.generation = self.generation;
+                    buffer.fft_size = self.fft_size;
+                }
+
+                producer.publish();
+            }
+
+            self.published_silence = true;
+        }
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let n = self.fft_size.frames();
+        let hop = n / 2;
+
+        let num_inputs = buffers.inputs.len();
+        if info.in_silence_mask.all_channels_silent(num_inputs) {
+            self.num_silent_frames = self.num_silent_frames.saturating_add(info.frames);
+
+            if self.num_silent_frames >= n && self.published_silence {
+                // The previous publish already contained silence, so no need
+                // to keep analyzing.
+                return ProcessStatus::ClearAllOutputs;
+            }
+        } else {
+            self.num_silent_frames = 0;
+        }
+
+        let gain = (num_inputs as f32).recip();
+
+        for i in 0..info.frames {
+            let mut s = 0.0;
+            for (ch_i, ch) in buffers.inputs.iter().enumerate() {
+                if !info.in_silence_mask.is_channel_silent(ch_i) {
+                    s += ch[i];
+                }
+            }
+
+            self.ring[self.ring_ptr] = s * gain;
+            self.ring_ptr += 1;
+            if self.ring_ptr == n {
+                self.ring_ptr = 0;
+            }
+
+            self.frames_since_fft += 1;
+            if self.frames_since_fft >= hop {
+                self.frames_since_fft = 0;
+                self.run_fft();
+            }
+        }
+
+        ProcessStatus::ClearAllOutputs
+    }
+
+    fn stream_stopped(&mut self, _context: &mut ProcStreamCtx) {
+        *self.active_state.lock().unwrap() = None;
+        self.producer = None;
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        self.reset();
+        self.published_silence = true;
+        self.generation += 1;
+
+        let (producer, consumer) = triple_buffer::triple_buffer(&SpectrumBuffer::new(
+            self.config.max_fft_size.num_bins(),
+            self.fft_size,
+            self.generation,
+        ));
+
+        *self.active_state.lock().unwrap() = Some(ActiveState {
+            consumer,
+            sample_rate: stream_info.sample_rate,
+        });
+
+        self.producer = Some(producer);
+    }
+}
+
+// A wrapper to ensure that the triple buffer uses `reserve_exact` when cloning
+// the initial buffers.
+struct SpectrumBuffer {
+    magnitudes: Vec<f32>,
+    fft_size: FftSize,
+    generation: u64,
+}
+
+impl SpectrumBuffer {
+    fn new(max_bins: usize, fft_size: FftSize, generation: u64) -> Self {
+        let mut magnitudes = Vec::new();
+        magnitudes.reserve_exact(max_bins);
+        magnitudes.resize(max_bins, 0.0);
+
+        Self {
+            magnitudes,
+            fft_size,
+            generation,
+        }
+    }
+}
+
+impl Clone for SpectrumBuffer {
+    fn clone(&self) -> Self {
+        Self::new(self.magnitudes.len(), self.fft_size, self.generation)
+    }
+}
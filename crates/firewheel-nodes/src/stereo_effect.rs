@@ -0,0 +1,44 @@
+//! Configuration shared by stereo effect nodes.
+
+use firewheel_core::mask::SilenceMask;
+
+/// The configuration of a stereo effect node, such as a
+/// [`FreeverbNode`](crate::freeverb::FreeverbNode) or a
+/// [`TremoloNode`](crate::tremolo::TremoloNode)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StereoEffectConfig {
+    /// If `true`, then whenever only one of the two input channels is
+    /// non-silent, that channel is fed into both channels of the effect.
+    ///
+    /// This prevents a mono source which is only connected to one of the
+    /// input ports from, for example, only being reverberated on the left
+    /// side.
+    ///
+    /// By default this is set to `false`.
+    pub sum_mono_input: bool,
+}
+
+impl StereoEffectConfig {
+    /// Returns the left and right input channels to feed into the effect.
+    pub(crate) fn stereo_inputs<'a>(
+        &self,
+        inputs: &[&'a [f32]],
+        in_silence_mask: SilenceMask,
+    ) -> (&'a [f32], &'a [f32]) {
+        if self.sum_mono_input {
+            match (
+                in_silence_mask.is_channel_silent(0),
+                in_silence_mask.is_channel_silent(1),
+            ) {
+                (false, true) => return (inputs[0], inputs[0]),
+                (true, false) => return (inputs[1], inputs[1]),
+                _ => {}
+            }
+        }
+
+        (inputs[0], inputs[1])
+    }
+}
@@ -44,7 +44,7 @@ impl AudioNodeProcessor for StereoToMonoProcessor {
         buffers: ProcBuffers,
         _extra: &mut ProcExtra,
     ) -> ProcessStatus {
-        if info.in_silence_mask.all_channels_silent(2) {
+        if info.can_skip_effect(2, false) {
             return ProcessStatus::ClearAllOutputs;
         }
 
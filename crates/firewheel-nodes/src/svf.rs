@@ -4,7 +4,7 @@ use firewheel_core::node::NodeError;
 use firewheel_core::{
     StreamInfo,
     channel_config::{ChannelConfig, ChannelCount},
-    diff::{Diff, Patch},
+    diff::{Diff, Notify, Patch},
     dsp::{
         coeff_update::{CoeffUpdateFactor, CoeffUpdateMask},
         filter::{
@@ -12,16 +12,18 @@ use firewheel_core::{
             smoothing_filter::DEFAULT_SMOOTH_SECONDS,
             svf::{SvfCoeff, SvfCoeffSimd, SvfStateSimd},
         },
-        volume::{Volume, db_to_amp},
+        volume::{DEFAULT_MIN_AMP, Volume, db_to_amp},
     },
     event::ProcEvents,
     node::{
         AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
-        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus, ResettableNode,
     },
     param::smoother::{SmoothedParam, SmootherConfig},
 };
 
+use crate::per_channel::PerChannelParam;
+
 pub const DEFAULT_Q: f32 = Q_BUTTERWORTH_ORD2;
 
 pub const DEFAULT_MIN_HZ: f32 = 20.0;
@@ -112,6 +114,15 @@ pub struct SvfNode<const CHANNELS: usize = 2> {
 
     /// The cutoff frequency in hertz in the range `[20.0, 20480.0]`.
     pub cutoff_hz: f32,
+    /// Optional per-channel cutoff frequencies in hertz, which override
+    /// [`SvfNode::cutoff_hz`] for their channel.
+    ///
+    /// By default every channel is `None`, so all channels share the same
+    /// cutoff frequency (stereo-linked). Set a channel to `Some` to filter
+    /// it independently (dual-mono), i.e. for a stereo filter sweep which
+    /// differs on each side.
+    #[cfg_attr(feature = "serde", serde(with = "crate::per_channel::serde_array"))]
+    pub channel_cutoff_hz: [Option<f32>; CHANNELS],
     /// The quality (q) factor
     ///
     /// This is also sometimes referred to as "bandwidth", but note the
@@ -150,6 +161,10 @@ pub struct SvfNode<const CHANNELS: usize = 2> {
     ///
     /// By default this is set to `4`.
     pub coeff_update_factor: CoeffUpdateFactor,
+
+    /// Reset the filter, clearing its internal state.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub reset: Notify<()>,
 }
 
 impl<const CHANNELS: usize> Default for SvfNode<CHANNELS> {
@@ -157,10 +172,12 @@ impl<const CHANNELS: usize> Default for SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::Lowpass,
             cutoff_hz: 1_000.0,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor: DEFAULT_Q,
             gain: Volume::Decibels(0.0),
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor::default(),
+            reset: Notify::new(()),
         }
     }
 }
@@ -175,10 +192,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::Lowpass,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain: Volume::UNITY_GAIN,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -191,10 +210,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::LowpassX2,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain: Volume::UNITY_GAIN,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -207,10 +228,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::Highpass,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain: Volume::UNITY_GAIN,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -223,10 +246,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::HighpassX2,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain: Volume::UNITY_GAIN,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -239,10 +264,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::Bandpass,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain: Volume::UNITY_GAIN,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -256,10 +283,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::LowShelf,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -273,10 +302,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::HighShelf,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -290,10 +321,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::Bell,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -306,10 +339,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::Notch,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain: Volume::UNITY_GAIN,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -322,10 +357,12 @@ impl<const CHANNELS: usize> SvfNode<CHANNELS> {
         Self {
             filter_type: SvfType::Allpass,
             cutoff_hz,
+            channel_cutoff_hz: [None; CHANNELS],
             q_factor,
             gain: Volume::UNITY_GAIN,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             coeff_update_factor: CoeffUpdateFactor(5),
+            reset: Notify::dangling(()),
         }
     }
 
@@ -482,9 +519,6 @@ impl<const CHANNELS: usize> AudioNode for SvfNode<CHANNELS> {
         config: &Self::Configuration,
         cx: ConstructProcessorContext,
     ) -> Result<impl AudioNodeProcessor, NodeError> {
-        let cutoff_hz = self
-            .cutoff_hz
-            .clamp(config.freq_range.start, config.freq_range.end);
         let q_factor = self
             .q_factor
             .clamp(config.q_range.start, config.q_range.end);
@@ -503,8 +537,11 @@ impl<const CHANNELS: usize> AudioNode for SvfNode<CHANNELS> {
             filter_0_coeff: SvfCoeffSimd::<CHANNELS>::default(),
             filter_1_coeff: SvfCoeffSimd::<CHANNELS>::default(),
             filter_type: self.filter_type,
-            cutoff_hz: SmoothedParam::new(
-                cutoff_hz,
+            cutoff_hz: PerChannelParam::new(
+                self.cutoff_hz,
+                self.channel_cutoff_hz,
+                config.freq_range.start,
+                config.freq_range.end,
                 SmootherConfig {
                     smooth_seconds: self.smooth_seconds,
                     ..Default::default()
@@ -527,7 +564,6 @@ impl<const CHANNELS: usize> AudioNode for SvfNode<CHANNELS> {
                 },
                 cx.stream_info.sample_rate,
             ),
-            freq_range: config.freq_range.clone(),
             q_range: config.q_range.clone(),
             gain_range: min_gain..max_gain,
             coeff_update_mask: self.coeff_update_factor.mask(),
@@ -535,7 +571,7 @@ impl<const CHANNELS: usize> AudioNode for SvfNode<CHANNELS> {
         };
 
         new_self.update_coefficients(
-            new_self.cutoff_hz.target_value(),
+            new_self.cutoff_hz.target_values(),
             new_self.q_factor.target_value(),
             new_self.gain.target_value(),
             cx.stream_info.sample_rate_recip as f32,
@@ -545,6 +581,12 @@ impl<const CHANNELS: usize> AudioNode for SvfNode<CHANNELS> {
     }
 }
 
+impl<const CHANNELS: usize> ResettableNode for SvfNode<CHANNELS> {
+    fn reset_notify(&mut self) -> &mut Notify<()> {
+        &mut self.reset
+    }
+}
+
 struct Processor<const CHANNELS: usize> {
     filter_0: SvfStateSimd<CHANNELS>,
     filter_1: SvfStateSimd<CHANNELS>,
@@ -554,11 +596,10 @@ struct Processor<const CHANNELS: usize> {
     filter_1_coeff: SvfCoeffSimd<CHANNELS>,
 
     filter_type: SvfType,
-    cutoff_hz: SmoothedParam,
+    cutoff_hz: PerChannelParam<CHANNELS>,
     q_factor: SmoothedParam,
     gain: SmoothedParam,
 
-    freq_range: Range<f32>,
     q_range: Range<f32>,
     gain_range: Range<f32>,
     coeff_update_mask: CoeffUpdateMask,
@@ -568,75 +609,86 @@ struct Processor<const CHANNELS: usize> {
 impl<const CHANNELS: usize> Processor<CHANNELS> {
     #[cold]
     #[inline(never)]
-    fn update_coefficients(&mut self, cutoff_hz: f32, q: f32, gain: f32, sample_rate_recip: f32) {
+    fn update_coefficients(
+        &mut self,
+        cutoff_hz: [f32; CHANNELS],
+        q: f32,
+        gain: f32,
+        sample_rate_recip: f32,
+    ) {
         match self.filter_type {
             SvfType::Lowpass => {
                 self.num_filters = 1;
 
-                self.filter_0_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::lowpass_ord2(cutoff_hz, q, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::lowpass_ord2(hz, q, sample_rate_recip)),
+                );
             }
             SvfType::LowpassX2 => {
                 self.num_filters = 2;
 
-                let [coeff_0, coeff_1] = SvfCoeff::lowpass_ord4(cutoff_hz, q, sample_rate_recip);
-                self.filter_0_coeff = SvfCoeffSimd::splat(coeff_0);
-                self.filter_1_coeff = SvfCoeffSimd::splat(coeff_1);
+                let coeffs = cutoff_hz.map(|hz| SvfCoeff::lowpass_ord4(hz, q, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(&coeffs.map(|[coeff_0, _]| coeff_0));
+                self.filter_1_coeff = SvfCoeffSimd::new(&coeffs.map(|[_, coeff_1]| coeff_1));
             }
             SvfType::Highpass => {
                 self.num_filters = 1;
 
-                self.filter_0_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::highpass_ord2(cutoff_hz, q, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::highpass_ord2(hz, q, sample_rate_recip)),
+                );
             }
             SvfType::HighpassX2 => {
                 self.num_filters = 2;
 
-                let [coeff_0, coeff_1] = SvfCoeff::highpass_ord4(cutoff_hz, q, sample_rate_recip);
-                self.filter_0_coeff = SvfCoeffSimd::splat(coeff_0);
-                self.filter_1_coeff = SvfCoeffSimd::splat(coeff_1);
+                let coeffs = cutoff_hz.map(|hz| SvfCoeff::highpass_ord4(hz, q, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(&coeffs.map(|[coeff_0, _]| coeff_0));
+                self.filter_1_coeff = SvfCoeffSimd::new(&coeffs.map(|[_, coeff_1]| coeff_1));
             }
             SvfType::Bandpass => {
                 self.num_filters = 2;
 
-                self.filter_0_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::lowpass_ord2(cutoff_hz, q, sample_rate_recip));
-                self.filter_1_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::highpass_ord2(cutoff_hz, q, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::lowpass_ord2(hz, q, sample_rate_recip)),
+                );
+                self.filter_1_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::highpass_ord2(hz, q, sample_rate_recip)),
+                );
             }
             SvfType::LowShelf => {
                 self.num_filters = 1;
 
-                self.filter_0_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::low_shelf(cutoff_hz, q, gain, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::low_shelf(hz, q, gain, sample_rate_recip)),
+                );
             }
             SvfType::HighShelf => {
                 self.num_filters = 1;
 
-                self.filter_0_coeff = SvfCoeffSimd::splat(SvfCoeff::high_shelf(
-                    cutoff_hz,
-                    q,
-                    gain,
-                    sample_rate_recip,
-                ));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::high_shelf(hz, q, gain, sample_rate_recip)),
+                );
             }
             SvfType::Bell => {
                 self.num_filters = 1;
 
-                self.filter_0_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::bell(cutoff_hz, q, gain, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::bell(hz, q, gain, sample_rate_recip)),
+                );
             }
             SvfType::Notch => {
                 self.num_filters = 1;
 
-                self.filter_0_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::notch(cutoff_hz, q, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::notch(hz, q, sample_rate_recip)),
+                );
             }
             SvfType::Allpass => {
                 self.num_filters = 1;
 
-                self.filter_0_coeff =
-                    SvfCoeffSimd::splat(SvfCoeff::allpass(cutoff_hz, q, sample_rate_recip));
+                self.filter_0_coeff = SvfCoeffSimd::new(
+                    &cutoff_hz.map(|hz| SvfCoeff::allpass(hz, q, sample_rate_recip)),
+                );
             }
         }
 
@@ -765,8 +817,11 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
                 }
                 SvfNodePatch::CutoffHz(cutoff) => {
                     self.params_changed = true;
-                    self.cutoff_hz
-                        .set_value(cutoff.clamp(self.freq_range.start, self.freq_range.end));
+                    self.cutoff_hz.set_value(cutoff);
+                }
+                SvfNodePatch::ChannelCutoffHz((ch, cutoff)) => {
+                    self.params_changed = true;
+                    self.cutoff_hz.set_channel_value(ch, cutoff);
                 }
                 SvfNodePatch::QFactor(q_factor) => {
                     self.params_changed = true;
@@ -787,6 +842,10 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
                 SvfNodePatch::CoeffUpdateFactor(f) => {
                     self.coeff_update_mask = f.mask();
                 }
+                SvfNodePatch::Reset(_) => {
+                    self.filter_0.reset();
+                    self.filter_1.reset();
+                }
             }
         }
     }
@@ -795,6 +854,10 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
         self.reset();
     }
 
+    fn reset_state(&mut self) {
+        self.reset();
+    }
+
     fn process(
         &mut self,
         info: &ProcInfo,
@@ -804,7 +867,11 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
         // Make sure that in-place buffer processing is being handled correctly.
         debug_assert_eq!(buffers.inputs.len(), 0);
 
-        if info.out_silence_mask.all_channels_silent(CHANNELS) {
+        // This node processes in-place, so the output buffers initially hold
+        // the input.
+        let input_silent = info.out_silence_mask.all_channels_silent(CHANNELS);
+
+        if input_silent && info.prev_output_was_silent {
             // Outputs will be silent, so no need to process.
 
             // Reset the smoothers and filters since they don't need to smooth any
@@ -830,7 +897,7 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
 
             if self.cutoff_hz.settle() && self.q_factor.settle() && self.gain.settle() {
                 self.update_coefficients(
-                    self.cutoff_hz.target_value(),
+                    self.cutoff_hz.target_values(),
                     self.q_factor.target_value(),
                     self.gain.target_value(),
                     info.sample_rate_recip as f32,
@@ -842,7 +909,7 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             if self.params_changed {
                 self.params_changed = false;
                 self.update_coefficients(
-                    self.cutoff_hz.target_value(),
+                    self.cutoff_hz.target_values(),
                     self.q_factor.target_value(),
                     self.gain.target_value(),
                     info.sample_rate_recip as f32,
@@ -890,7 +957,12 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
             }
         }
 
-        ProcessStatus::OutputsModified
+        if input_silent {
+            // Keep processing until the tail of the filter has decayed.
+            buffers.check_for_silence_on_outputs(DEFAULT_MIN_AMP)
+        } else {
+            ProcessStatus::OutputsModified
+        }
     }
 
     fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
@@ -899,7 +971,7 @@ impl<const CHANNELS: usize> AudioNodeProcessor for Processor<CHANNELS> {
         self.gain.update_sample_rate(stream_info.sample_rate);
 
         self.update_coefficients(
-            self.cutoff_hz.target_value(),
+            self.cutoff_hz.target_values(),
             self.q_factor.target_value(),
             self.gain.target_value(),
             stream_info.sample_rate_recip as f32,
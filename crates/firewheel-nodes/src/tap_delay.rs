@@ -0,0 +1,377 @@
+use core::num::NonZeroU32;
+
+use bevy_platform::prelude::Vec;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, NonZeroChannelCount},
+    diff::{Diff, Patch},
+    dsp::{
+        filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+        volume::{DEFAULT_MIN_AMP, Volume},
+    },
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, NodeError,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+/// The number of taps in a [`TapDelayNode`].
+pub const MAX_DELAY_TAPS: usize = 8;
+
+/// The default value of [`TapDelayConfig::max_delay_ms`].
+pub const DEFAULT_MAX_TAP_DELAY_MS: f32 = 100.0;
+
+/// The configuration for a [`TapDelayNode`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TapDelayConfig {
+    /// The number of input and output channels. Each channel is delayed
+    /// independently.
+    ///
+    /// By default this is set to [`NonZeroChannelCount::STEREO`].
+    pub channels: NonZeroChannelCount,
+    /// The maximum value of [`DelayTap::delay_ms`]. This determines the size
+    /// of the delay line which is allocated.
+    ///
+    /// By default this is set to `100.0` (100ms).
+    pub max_delay_ms: f32,
+}
+
+impl Default for TapDelayConfig {
+    fn default() -> Self {
+        Self {
+            channels: NonZeroChannelCount::STEREO,
+            max_delay_ms: DEFAULT_MAX_TAP_DELAY_MS,
+        }
+    }
+}
+
+/// A single tap of a [`TapDelayNode`].
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DelayTap {
+    /// The delay of this tap in milliseconds, in the range
+    /// `[0.0, TapDelayConfig::max_delay_ms]`.
+    ///
+    /// Changing this while audio is playing may cause an audible click.
+    ///
+    /// By default this is set to `0.0`.
+    pub delay_ms: f32,
+    /// The gain of this tap.
+    ///
+    /// By default this is set to [`Volume::SILENT`], meaning the tap is
+    /// disabled.
+    pub gain: Volume,
+}
+
+impl DelayTap {
+    /// A tap which is disabled.
+    pub const DISABLED: Self = Self {
+        delay_ms: 0.0,
+        gain: Volume::SILENT,
+    };
+
+    /// Construct a new tap with the given delay in milliseconds and gain.
+    pub const fn new(delay_ms: f32, gain: Volume) -> Self {
+        Self { delay_ms, gain }
+    }
+}
+
+impl Default for DelayTap {
+    fn default() -> Self {
+        Self::DISABLED
+    }
+}
+
+/// A node which sums delayed copies of its input, without any feedback.
+///
+/// This is the building block for early reflections and slap-back echoes,
+/// and it can be placed in front of a reverb node.
+///
+/// Each tap is addressed by its index in [`TapDelayNode::taps`], so changing
+/// a single tap only sends the fields of that tap which changed.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TapDelayNode {
+    /// The taps of the delay line.
+    ///
+    /// By default all taps are disabled.
+    pub taps: [DelayTap; MAX_DELAY_TAPS],
+    /// The time in seconds of the internal smoothing filter for the tap
+    /// gains.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for TapDelayNode {
+    fn default() -> Self {
+        Self {
+            taps: [DelayTap::DISABLED; MAX_DELAY_TAPS],
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl AudioNode for TapDelayNode {
+    type Configuration = TapDelayConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("tap_delay")
+            .channel_config(ChannelConfig {
+                num_inputs: config.channels.get(),
+                num_outputs: config.channels.get(),
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let sample_rate = cx.stream_info.sample_rate;
+
+        let mut processor = Processor {
+            params: *self,
+            config: *config,
+            gains: self.taps.map(|tap| {
+                SmoothedParam::new(
+                    tap.gain.amp_clamped(DEFAULT_MIN_AMP),
+                    SmootherConfig {
+                        smooth_seconds: self.smooth_seconds,
+                        ..Default::default()
+                    },
+                    sample_rate,
+                )
+            }),
+            delay_frames: [0; MAX_DELAY_TAPS],
+            buffer: Vec::new(),
+            buffer_frames: 0,
+            buffer_is_clear: true,
+            write_ptr: 0,
+            silent_input_frames: usize::MAX,
+            sample_rate,
+        };
+        processor.allocate_buffer();
+
+        Ok(processor)
+    }
+}
+
+struct Processor {
+    params: TapDelayNode,
+    config: TapDelayConfig,
+    gains: [SmoothedParam; MAX_DELAY_TAPS],
+    delay_frames: [usize; MAX_DELAY_TAPS],
+
+    /// The delay line of each channel, stored one after the other.
+    buffer: Vec<f32>,
+    /// The number of frames in the delay line of each channel.
+    buffer_frames: usize,
+    buffer_is_clear: bool,
+    write_ptr: usize,
+    /// The number of frames since the input was last not silent. This is
+    /// `usize::MAX` if the delay line is empty.
+    silent_input_frames: usize,
+
+    sample_rate: NonZeroU32,
+}
+
+impl Processor {
+    fn allocate_buffer(&mut self) {
+        let max_delay_frames = ms_to_frames(self.config.max_delay_ms, self.sample_rate);
+        let buffer_len = (max_delay_frames + 1) * self.config.channels.get().get() as usize;
+
+        self.buffer = Vec::new();
+        self.buffer.reserve_exact(buffer_len);
+        self.buffer.resize(buffer_len, 0.0);
+
+        self.buffer_frames = max_delay_frames + 1;
+        self.buffer_is_clear = true;
+        self.write_ptr = 0;
+        self.silent_input_frames = usize::MAX;
+
+        for (delay_frames, tap) in self.delay_frames.iter_mut().zip(self.params.taps.iter()) {
+            *delay_frames = ms_to_frames(tap.delay_ms, self.sample_rate).min(max_delay_frames);
+        }
+    }
+
+    fn clear(&mut self) {
+        if !self.buffer_is_clear {
+            self.buffer.fill(0.0);
+            self.buffer_is_clear = true;
+        }
+
+        self.write_ptr = 0;
+        self.silent_input_frames = usize::MAX;
+    }
+
+    fn tap_is_active(&self, tap_i: usize) -> bool {
+        !self.gains[tap_i].has_settled_at(0.0)
+    }
+
+    /// The delay in frames of the longest active tap.
+    fn max_active_delay_frames(&self) -> usize {
+        (0..MAX_DELAY_TAPS)
+            .filter(|&tap_i| self.tap_is_active(tap_i))
+            .map(|tap_i| self.delay_frames[tap_i])
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<TapDelayNode>() {
+            match patch {
+                TapDelayNodePatch::Taps((tap_i, DelayTapPatch::DelayMs(delay_ms))) => {
+                    self.delay_frames[tap_i] =
+                        ms_to_frames(delay_ms, info.sample_rate).min(self.buffer_frames - 1);
+                }
+                TapDelayNodePatch::Taps((tap_i, DelayTapPatch::Gain(gain))) => {
+                    self.gains[tap_i].set_value(gain.amp_clamped(DEFAULT_MIN_AMP));
+                }
+                TapDelayNodePatch::SmoothSeconds(seconds) => {
+                    for gain in self.gains.iter_mut() {
+                        gain.set_smooth_seconds(seconds, info.sample_rate);
+                    }
+                }
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn bypassed(&mut self, bypassed: bool) {
+        for gain in self.gains.iter_mut() {
+            gain.reset_to_target();
+        }
+
+        if !bypassed {
+            self.clear();
+        }
+    }
+
+    fn reset_state(&mut self) {
+        self.clear();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let channels = buffers.inputs.len();
+        let input_is_silent = info.in_silence_mask.all_channels_silent(channels);
+
+        // The output of a tap can lag behind its input by up to the delay of
+        // the tap, so the node can only be skipped once its longest active
+        // tap has played back the last non-silent input.
+        if input_is_silent && self.silent_input_frames >= self.max_active_delay_frames() {
+            for gain in self.gains.iter_mut() {
+                gain.reset_to_target();
+            }
+            self.clear();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let mut active_taps = [false; MAX_DELAY_TAPS];
+        for (tap_i, active) in active_taps.iter_mut().enumerate() {
+            *active = self.tap_is_active(tap_i);
+        }
+
+        let mut gains = [0.0; MAX_DELAY_TAPS];
+
+        for i in 0..info.frames {
+            for (gain, smoother) in gains.iter_mut().zip(self.gains.iter_mut()) {
+                *gain = smoother.next_smoothed();
+            }
+
+            for ((in_buf, out_buf), delay_buf) in buffers
+                .inputs
+                .iter()
+                .zip(buffers.outputs.iter_mut())
+                .zip(self.buffer.chunks_exact_mut(self.buffer_frames))
+            {
+                delay_buf[self.write_ptr] = in_buf[i];
+
+                let mut sum = 0.0;
+                for ((&active, &delay_frames), &gain) in active_taps
+                    .iter()
+                    .zip(self.delay_frames.iter())
+                    .zip(gains.iter())
+                {
+                    if !active {
+                        continue;
+                    }
+
+                    let read_ptr = if self.write_ptr >= delay_frames {
+                        self.write_ptr - delay_frames
+                    } else {
+                        self.write_ptr + self.buffer_frames - delay_frames
+                    };
+
+                    sum += delay_buf[read_ptr] * gain;
+                }
+
+                out_buf[i] = sum;
+            }
+
+            self.write_ptr += 1;
+            if self.write_ptr == self.buffer_frames {
+                self.write_ptr = 0;
+            }
+        }
+
+        for gain in self.gains.iter_mut() {
+            gain.settle();
+        }
+
+        self.buffer_is_clear = false;
+
+        if input_is_silent {
+            self.silent_input_frames = self.silent_input_frames.saturating_add(info.frames);
+        } else {
+            self.silent_input_frames = 0;
+        }
+
+        buffers.tail_status(info, channels, DEFAULT_MIN_AMP)
+    }
+
+    fn tail_frames(&self) -> Option<u64> {
+        Some(self.max_active_delay_frames() as u64)
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        if stream_info.sample_rate == self.sample_rate {
+            return;
+        }
+
+        self.sample_rate = stream_info.sample_rate;
+        self.allocate_buffer();
+
+        for gain in self.gains.iter_mut() {
+            gain.update_sample_rate(self.sample_rate);
+        }
+    }
+}
+
+fn ms_to_frames(ms: f32, sample_rate: NonZeroU32) -> usize {
+    (f64::from(ms.max(0.0)) * f64::from(sample_rate.get()) / 1_000.0).round() as usize
+}
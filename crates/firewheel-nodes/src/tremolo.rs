@@ -0,0 +1,348 @@
+//! A node that modulates the amplitude or the stereo balance of a signal
+//! with an internal LFO (low frequency oscillator).
+
+use core::f32::consts::TAU;
+
+use num_traits::Float;
+
+use firewheel_core::node::NodeError;
+use firewheel_core::{
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Patch},
+    dsp::{fade::FadeCurve, filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS},
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
+        ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+use crate::stereo_effect::StereoEffectConfig;
+
+pub const DEFAULT_MIN_RATE_HZ: f32 = 0.01;
+pub const DEFAULT_MAX_RATE_HZ: f32 = 40.0;
+
+/// What the LFO of a [`TremoloNode`] modulates.
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TremoloMode {
+    /// Modulate the amplitude of both channels.
+    #[default]
+    Tremolo,
+    /// Modulate the balance between the left and right channels.
+    ///
+    /// Only the channel opposite to the current pan direction is attenuated,
+    /// so the signal is never boosted.
+    AutoPan,
+}
+
+/// The shape of the LFO of a [`TremoloNode`].
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LfoWaveform {
+    #[default]
+    Sine,
+    Triangle,
+    /// Note, the edges of a square wave are not smoothed, so a high depth may
+    /// cause audible clicks.
+    Square,
+    SawUp,
+    SawDown,
+}
+
+impl LfoWaveform {
+    /// Get the value of the waveform in the range `[-1.0, 1.0]` at the given
+    /// phase in the range `[0.0, 1.0)`.
+    pub fn value_at(&self, phase: f32) -> f32 {
+        match self {
+            Self::Sine => (phase * TAU).sin(),
+            Self::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Self::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Self::SawUp => 2.0 * phase - 1.0,
+            Self::SawDown => 1.0 - 2.0 * phase,
+        }
+    }
+}
+
+/// A node that modulates the amplitude (tremolo) or the stereo balance
+/// (auto-pan) of a stereo signal with an internal LFO
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TremoloNode {
+    /// What the LFO modulates.
+    ///
+    /// By default this is set to [`TremoloMode::Tremolo`].
+    pub mode: TremoloMode,
+    /// The shape of the LFO.
+    ///
+    /// By default this is set to [`LfoWaveform::Sine`].
+    pub waveform: LfoWaveform,
+    /// The rate of the LFO in hertz, in the range `[0.01, 40.0]`.
+    ///
+    /// This is ignored while the LFO is synced to the musical transport.
+    ///
+    /// By default this is set to `4.0`.
+    pub rate_hz: f32,
+    /// The amount of modulation in the range `[0.0, 1.0]`, where `0.0` has
+    /// no effect.
+    ///
+    /// By default this is set to `0.5`.
+    pub depth: f32,
+    /// The phase offset of the right channel's LFO relative to the left
+    /// channel's LFO in the range `[0.0, 1.0)`, where `0.5` is half a cycle.
+    ///
+    /// This is only used in [`TremoloMode::Tremolo`] mode.
+    ///
+    /// By default this is set to `0.0`.
+    pub stereo_phase_offset: f32,
+    /// If this is `Some`, then the length of a single cycle of the LFO in
+    /// beats, and the phase of the LFO is locked to the playhead of the
+    /// musical transport while it is playing.
+    ///
+    /// This has no effect when the `musical_transport` feature is disabled.
+    ///
+    /// By default this is set to `None`.
+    pub sync_beats: Option<f32>,
+    /// The time in seconds of the internal smoothing filter.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for TremoloNode {
+    fn default() -> Self {
+        Self {
+            mode: TremoloMode::default(),
+            waveform: LfoWaveform::default(),
+            rate_hz: 4.0,
+            depth: 0.5,
+            stereo_phase_offset: 0.0,
+            sync_beats: None,
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl AudioNode for TremoloNode {
+    type Configuration = StereoEffectConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("tremolo")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::STEREO,
+                num_outputs: ChannelCount::STEREO,
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        Ok(Processor {
+            depth: SmoothedParam::new(
+                self.depth.clamp(0.0, 1.0),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                cx.stream_info.sample_rate,
+            ),
+            phase_offset: SmoothedParam::new(
+                wrap_phase(self.stereo_phase_offset),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                cx.stream_info.sample_rate,
+            ),
+            params: *self,
+            phase: 0.0,
+            sample_rate_recip: cx.stream_info.sample_rate_recip,
+            config: *config,
+        })
+    }
+}
+
+/// Wrap the given phase into the range `[0.0, 1.0)`.
+fn wrap_phase<T: Float>(phase: T) -> T {
+    phase - phase.floor()
+}
+
+struct Processor {
+    params: TremoloNode,
+    depth: SmoothedParam,
+    phase_offset: SmoothedParam,
+
+    /// The phase of the LFO in the range `[0.0, 1.0)`.
+    phase: f64,
+    sample_rate_recip: f64,
+    config: StereoEffectConfig,
+}
+
+impl Processor {
+    /// Returns the phase of the LFO at the start of this block and the amount
+    /// the phase advances per frame.
+    fn block_phase(&self, info: &ProcInfo) -> (f64, f64) {
+        #[cfg(feature = "musical_transport")]
+        if let Some(beats) = self.params.sync_beats.filter(|b| *b > 0.0)
+            && let Some(playhead) = info.playhead_range()
+        {
+            let cycles_per_beat = f64::from(beats).recip();
+
+            return (
+                wrap_phase(playhead.start.0 * cycles_per_beat),
+                (playhead.end.0 - playhead.start.0) * cycles_per_beat / info.frames.max(1) as f64,
+            );
+        }
+
+        let _ = info;
+
+        (
+            self.phase,
+            f64::from(
+                self.params
+                    .rate_hz
+                    .clamp(DEFAULT_MIN_RATE_HZ, DEFAULT_MAX_RATE_HZ),
+            ) * self.sample_rate_recip,
+        )
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<TremoloNode>() {
+            match patch {
+                TremoloNodePatch::Depth(depth) => {
+                    self.depth.set_value(depth.clamp(0.0, 1.0));
+                }
+                TremoloNodePatch::StereoPhaseOffset(offset) => {
+                    self.phase_offset.set_value(wrap_phase(offset));
+                }
+                TremoloNodePatch::SmoothSeconds(seconds) => {
+                    self.depth.set_smooth_seconds(seconds, info.sample_rate);
+                    self.phase_offset
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                }
+                _ => {}
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn bypassed(&mut self, _bypassed: bool) {
+        self.depth.reset_to_target();
+        self.phase_offset.reset_to_target();
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        let (start_phase, phase_inc) = self.block_phase(info);
+
+        // Keep the LFO running even when there is no input so that it stays
+        // continuous.
+        self.phase = (start_phase + phase_inc * info.frames as f64).fract();
+
+        if info.can_skip_effect(2, false) {
+            self.depth.reset_to_target();
+            self.phase_offset.reset_to_target();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        if self.depth.has_settled_at(0.0) && !self.config.sum_mono_input {
+            return ProcessStatus::Bypass;
+        }
+
+        let (in1, in2) = self
+            .config
+            .stereo_inputs(buffers.inputs, info.in_silence_mask);
+        let in1 = &in1[..info.frames];
+        let in2 = &in2[..info.frames];
+        let (out1, out2) = buffers.outputs.split_first_mut().unwrap();
+        let out1 = &mut out1[..info.frames];
+        let out2 = &mut out2[0][..info.frames];
+
+        if self.depth.has_settled_at(0.0) {
+            // Still copy the inputs so that a mono input is duplicated to
+            // both outputs.
+            out1.copy_from_slice(in1);
+            out2.copy_from_slice(in2);
+
+            return ProcessStatus::OutputsModified;
+        }
+
+        let waveform = self.params.waveform;
+
+        match self.params.mode {
+            TremoloMode::Tremolo => {
+                for i in 0..info.frames {
+                    let depth = self.depth.next_smoothed();
+                    let phase_offset = self.phase_offset.next_smoothed();
+
+                    let phase_l = (start_phase + phase_inc * i as f64).fract() as f32;
+                    let phase_r = (phase_l + phase_offset).fract();
+
+                    // Map the LFO to the range `[0.0, 1.0]`.
+                    let lfo_l = 0.5 + 0.5 * waveform.value_at(phase_l);
+                    let lfo_r = 0.5 + 0.5 * waveform.value_at(phase_r);
+
+                    out1[i] = in1[i] * (1.0 - depth * (1.0 - lfo_l));
+                    out2[i] = in2[i] * (1.0 - depth * (1.0 - lfo_r));
+                }
+            }
+            TremoloMode::AutoPan => {
+                for i in 0..info.frames {
+                    let depth = self.depth.next_smoothed();
+
+                    let phase = (start_phase + phase_inc * i as f64).fract() as f32;
+                    let pan = waveform.value_at(phase) * depth;
+
+                    let (gain_l, gain_r) = if pan >= 0.0 {
+                        (FadeCurve::EqualPower3dB.compute_gains_0_to_1(pan).0, 1.0)
+                    } else {
+                        (1.0, FadeCurve::EqualPower3dB.compute_gains_0_to_1(-pan).0)
+                    };
+
+                    out1[i] = in1[i] * gain_l;
+                    out2[i] = in2[i] * gain_r;
+                }
+            }
+        }
+
+        self.depth.settle();
+        self.phase_offset.settle();
+
+        ProcessStatus::OutputsModified
+    }
+
+    fn new_stream(
+        &mut self,
+        stream_info: &firewheel_core::StreamInfo,
+        _context: &mut ProcStreamCtx,
+    ) {
+        self.sample_rate_recip = stream_info.sample_rate_recip;
+        self.depth.update_sample_rate(stream_info.sample_rate);
+        self.phase_offset
+            .update_sample_rate(stream_info.sample_rate);
+    }
+}
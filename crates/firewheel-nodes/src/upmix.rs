@@ -0,0 +1,410 @@
+//! A node which derives center and surround channels from a stereo signal.
+
+use core::{f32::consts::FRAC_1_SQRT_2, num::NonZeroU32};
+
+use bevy_platform::prelude::Vec;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use firewheel_core::{
+    StreamInfo,
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Patch},
+    dsp::{
+        filter::{
+            single_pole_iir::{
+                OnePoleIirHPF, OnePoleIirHPFCoeff, OnePoleIirLPF, OnePoleIirLPFCoeff,
+            },
+            smoothing_filter::DEFAULT_SMOOTH_SECONDS,
+        },
+        volume::{DEFAULT_MIN_AMP, Volume},
+    },
+    event::ProcEvents,
+    node::{
+        AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, NodeError,
+        ProcBuffers, ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
+    },
+    param::smoother::{SmoothedParam, SmootherConfig},
+};
+
+pub const DEFAULT_MAX_SURROUND_DELAY_SECONDS: f32 = 0.05;
+
+/// The cutoff of the highpass filter applied to the surround channels.
+const SURROUND_HIGHPASS_HZ: f32 = 100.0;
+/// The cutoff of the lowpass filter applied to the surround channels.
+const SURROUND_LOWPASS_HZ: f32 = 7_000.0;
+
+/// The output channel layout of an [`UpmixNode`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpmixLayout {
+    /// Four channels in the order `[L, R, Ls, Rs]`.
+    Quad,
+    /// Six channels in the order `[L, R, C, LFE, Ls, Rs]`.
+    ///
+    /// The LFE channel is left silent.
+    #[default]
+    Surround5_1,
+}
+
+impl UpmixLayout {
+    /// The number of output channels in this layout.
+    pub const fn num_channels(&self) -> usize {
+        match self {
+            Self::Quad => 4,
+            Self::Surround5_1 => 6,
+        }
+    }
+
+    const fn center_channel(&self) -> Option<usize> {
+        match self {
+            Self::Quad => None,
+            Self::Surround5_1 => Some(2),
+        }
+    }
+
+    const fn lfe_channel(&self) -> Option<usize> {
+        match self {
+            Self::Quad => None,
+            Self::Surround5_1 => Some(3),
+        }
+    }
+
+    /// The indices of the left and right surround channels.
+    const fn surround_channels(&self) -> (usize, usize) {
+        match self {
+            Self::Quad => (2, 3),
+            Self::Surround5_1 => (4, 5),
+        }
+    }
+}
+
+/// The configuration for an [`UpmixNode`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpmixConfig {
+    /// The output channel layout.
+    ///
+    /// By default this is set to [`UpmixLayout::Surround5_1`].
+    pub layout: UpmixLayout,
+    /// The maximum value of [`UpmixNode::surround_delay_seconds`]. This
+    /// determines the size of the delay line which is allocated.
+    ///
+    /// By default this is set to `0.05` (50ms).
+    pub max_surround_delay_seconds: f32,
+}
+
+impl Default for UpmixConfig {
+    fn default() -> Self {
+        Self {
+            layout: UpmixLayout::default(),
+            max_surround_delay_seconds: DEFAULT_MAX_SURROUND_DELAY_SECONDS,
+        }
+    }
+}
+
+/// A node which upmixes a stereo signal to a surround layout (a passive
+/// "pseudo-surround" matrix decoder).
+///
+/// The left and right channels are passed through unchanged. The center
+/// channel is derived from `(L + R) / √2`, and the surround channels are
+/// derived from a band-limited and delayed `(L − R) / √2`, which contains the
+/// ambience and the wide elements of the stereo mix.
+///
+/// This lets a game target surround outputs without authoring multichannel
+/// content.
+#[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpmixNode {
+    /// The level of the center channel.
+    ///
+    /// This has no effect with [`UpmixLayout::Quad`].
+    ///
+    /// By default this is set to [`Volume::UNITY_GAIN`].
+    pub center: Volume,
+    /// The level of the surround channels.
+    ///
+    /// By default this is set to [`Volume::UNITY_GAIN`].
+    pub surround: Volume,
+    /// The delay of the surround channels in seconds, in the range
+    /// `[0.0, UpmixConfig::max_surround_delay_seconds]`.
+    ///
+    /// Delaying the surround channels relative to the front channels makes
+    /// sounds appear to come from the front (the precedence effect).
+    ///
+    /// Changing this while audio is playing may cause an audible click.
+    ///
+    /// By default this is set to `0.015` (15ms).
+    pub surround_delay_seconds: f32,
+    /// The time in seconds of the internal smoothing filter.
+    ///
+    /// By default this is set to `0.023` (23ms). This value is chosen to be
+    /// roughly equal to a typical block size of 1024 samples (23 ms) to
+    /// eliminate stair-stepping for most games.
+    pub smooth_seconds: f32,
+}
+
+impl Default for UpmixNode {
+    fn default() -> Self {
+        Self {
+            center: Volume::UNITY_GAIN,
+            surround: Volume::UNITY_GAIN,
+            surround_delay_seconds: 0.015,
+            smooth_seconds: DEFAULT_SMOOTH_SECONDS,
+        }
+    }
+}
+
+impl AudioNode for UpmixNode {
+    type Configuration = UpmixConfig;
+
+    fn info(&self, config: &Self::Configuration) -> Result<AudioNodeInfo, NodeError> {
+        Ok(AudioNodeInfo::new()
+            .debug_name("upmix")
+            .channel_config(ChannelConfig {
+                num_inputs: ChannelCount::STEREO,
+                num_outputs: ChannelCount::from(config.layout.num_channels()),
+            }))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: ConstructProcessorContext,
+    ) -> Result<impl AudioNodeProcessor, NodeError> {
+        let sample_rate = cx.stream_info.sample_rate;
+        let sample_rate_recip = cx.stream_info.sample_rate_recip as f32;
+
+        let mut delay = SurroundDelay::new(config.max_surround_delay_seconds, sample_rate);
+        delay.set_delay_seconds(self.surround_delay_seconds, sample_rate);
+
+        Ok(Processor {
+            center_gain: SmoothedParam::new(
+                self.center.amp_clamped(DEFAULT_MIN_AMP),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                sample_rate,
+            ),
+            surround_gain: SmoothedParam::new(
+                self.surround.amp_clamped(DEFAULT_MIN_AMP),
+                SmootherConfig {
+                    smooth_seconds: self.smooth_seconds,
+                    ..Default::default()
+                },
+                sample_rate,
+            ),
+            params: *self,
+            config: *config,
+            delay,
+            hpf: OnePoleIirHPF::default(),
+            hpf_coeff: OnePoleIirHPFCoeff::new(SURROUND_HIGHPASS_HZ, sample_rate_recip),
+            lpf: OnePoleIirLPF::default(),
+            lpf_coeff: OnePoleIirLPFCoeff::new(SURROUND_LOWPASS_HZ, sample_rate_recip),
+            sample_rate,
+        })
+    }
+}
+
+/// A delay line for the surround channels.
+struct SurroundDelay {
+    buffer: Vec<f32>,
+    write_ptr: usize,
+    delay_frames: usize,
+}
+
+impl SurroundDelay {
+    fn new(max_delay_seconds: f32, sample_rate: NonZeroU32) -> Self {
+        let max_delay_frames =
+            (f64::from(max_delay_seconds.max(0.0)) * f64::from(sample_rate.get())).ceil() as usize;
+
+        let mut buffer = Vec::new();
+        buffer.reserve_exact(max_delay_frames + 1);
+        buffer.resize(max_delay_frames + 1, 0.0);
+
+        Self {
+            buffer,
+            write_ptr: 0,
+            delay_frames: 0,
+        }
+    }
+
+    fn set_delay_seconds(&mut self, seconds: f32, sample_rate: NonZeroU32) {
+        let frames = (f64::from(seconds.max(0.0)) * f64::from(sample_rate.get())).round() as usize;
+
+        self.delay_frames = frames.min(self.buffer.len() - 1);
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_ptr = 0;
+    }
+
+    #[inline]
+    fn process(&mut self, s: f32) -> f32 {
+        let len = self.buffer.len();
+
+        self.buffer[self.write_ptr] = s;
+
+        let read_ptr = if self.write_ptr >= self.delay_frames {
+            self.write_ptr - self.delay_frames
+        } else {
+            self.write_ptr + len - self.delay_frames
+        };
+
+        self.write_ptr += 1;
+        if self.write_ptr == len {
+            self.write_ptr = 0;
+        }
+
+        self.buffer[read_ptr]
+    }
+}
+
+struct Processor {
+    params: UpmixNode,
+    config: UpmixConfig,
+    center_gain: SmoothedParam,
+    surround_gain: SmoothedParam,
+
+    delay: SurroundDelay,
+    hpf: OnePoleIirHPF,
+    hpf_coeff: OnePoleIirHPFCoeff,
+    lpf: OnePoleIirLPF,
+    lpf_coeff: OnePoleIirLPFCoeff,
+
+    sample_rate: NonZeroU32,
+}
+
+impl Processor {
+    fn reset(&mut self) {
+        self.delay.clear();
+        self.hpf.reset();
+        self.lpf.reset();
+    }
+}
+
+impl AudioNodeProcessor for Processor {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
+        for patch in events.drain_patches::<UpmixNode>() {
+            match patch {
+                UpmixNodePatch::Center(volume) => {
+                    self.center_gain
+                        .set_value(volume.amp_clamped(DEFAULT_MIN_AMP));
+                }
+                UpmixNodePatch::Surround(volume) => {
+                    self.surround_gain
+                        .set_value(volume.amp_clamped(DEFAULT_MIN_AMP));
+                }
+                UpmixNodePatch::SurroundDelaySeconds(seconds) => {
+                    self.delay.set_delay_seconds(seconds, self.sample_rate);
+                }
+                UpmixNodePatch::SmoothSeconds(seconds) => {
+                    self.center_gain
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                    self.surround_gain
+                        .set_smooth_seconds(seconds, info.sample_rate);
+                }
+            }
+
+            self.params.apply(patch);
+        }
+    }
+
+    fn bypassed(&mut self, bypassed: bool) {
+        self.center_gain.reset_to_target();
+        self.surround_gain.reset_to_target();
+
+        if !bypassed {
+            self.reset();
+        }
+    }
+
+    fn process(
+        &mut self,
+        info: &ProcInfo,
+        buffers: ProcBuffers,
+        _extra: &mut ProcExtra,
+    ) -> ProcessStatus {
+        if info.can_skip_effect(2, true) {
+            self.center_gain.reset_to_target();
+            self.surround_gain.reset_to_target();
+            self.hpf.reset();
+            self.lpf.reset();
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let layout = self.config.layout;
+        let (ls, rs) = layout.surround_channels();
+
+        let in_l = &buffers.inputs[0][..info.frames];
+        let in_r = &buffers.inputs[1][..info.frames];
+
+        buffers.outputs[0][..info.frames].copy_from_slice(in_l);
+        buffers.outputs[1][..info.frames].copy_from_slice(in_r);
+
+        if let Some(lfe) = layout.lfe_channel()
+            && !info.out_silence_mask.is_channel_silent(lfe)
+        {
+            buffers.outputs[lfe][..info.frames].fill(0.0);
+        }
+
+        for i in 0..info.frames {
+            let (l, r) = (in_l[i], in_r[i]);
+
+            let center_gain = self.center_gain.next_smoothed();
+            let surround_gain = self.surround_gain.next_smoothed();
+
+            if let Some(c) = layout.center_channel() {
+                buffers.outputs[c][i] = (l + r) * FRAC_1_SQRT_2 * center_gain;
+            }
+
+            let side = (l - r) * FRAC_1_SQRT_2;
+            let side = self
+                .lpf
+                .process(self.hpf.process(side, self.hpf_coeff), self.lpf_coeff);
+            let s = self.delay.process(side) * surround_gain;
+
+            buffers.outputs[ls][i] = s;
+            buffers.outputs[rs][i] = s;
+        }
+
+        self.center_gain.settle();
+        self.surround_gain.settle();
+
+        buffers.tail_status(info, 2, DEFAULT_MIN_AMP)
+    }
+
+    fn tail_frames(&self) -> Option<u64> {
+        Some(self.delay.delay_frames as u64)
+    }
+
+    fn new_stream(&mut self, stream_info: &StreamInfo, _context: &mut ProcStreamCtx) {
+        if stream_info.sample_rate == self.sample_rate {
+            return;
+        }
+
+        self.sample_rate = stream_info.sample_rate;
+        let sample_rate_recip = stream_info.sample_rate_recip as f32;
+
+        self.delay = SurroundDelay::new(self.config.max_surround_delay_seconds, self.sample_rate);
+        self.delay
+            .set_delay_seconds(self.params.surround_delay_seconds, self.sample_rate);
+
+        self.hpf_coeff = OnePoleIirHPFCoeff::new(SURROUND_HIGHPASS_HZ, sample_rate_recip);
+        self.lpf_coeff = OnePoleIirLPFCoeff::new(SURROUND_LOWPASS_HZ, sample_rate_recip);
+        self.hpf.reset();
+        self.lpf.reset();
+
+        self.center_gain.update_sample_rate(self.sample_rate);
+        self.surround_gain.update_sample_rate(self.sample_rate);
+    }
+}
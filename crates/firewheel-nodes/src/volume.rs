@@ -1,10 +1,12 @@
+use core::any::Any;
+
 use firewheel_core::node::NodeError;
 use firewheel_core::{
     channel_config::{ChannelConfig, NonZeroChannelCount},
     diff::{Diff, Patch},
     dsp::{
         filter::smoothing_filter::DEFAULT_SMOOTH_SECONDS,
-        volume::{DEFAULT_MIN_AMP, Volume},
+        volume::{DEFAULT_MIN_AMP, Volume, amp_to_db_clamped},
     },
     event::ProcEvents,
     mask::MaskType,
@@ -12,7 +14,11 @@ use firewheel_core::{
         AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, ProcBuffers,
         ProcExtra, ProcInfo, ProcStreamCtx, ProcessStatus,
     },
-    param::smoother::{SmoothedParam, SmootherConfig},
+    param::{
+        automation::AutomationScale,
+        smoother::{SmoothedParam, SmootherConfig},
+        snapshot::ParamValueSnapshot,
+    },
 };
 
 /// The configuration of a [`VolumeNode`]
@@ -40,6 +46,12 @@ impl Default for VolumeNodeConfig {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VolumeNode {
     /// The volume to apply to the signal
+    ///
+    /// This can be automated with `FirewheelContext::schedule_automation`,
+    /// where the values of the automation are in decibels.
+    ///
+    /// The currently audible volume in decibels can be read back with
+    /// `FirewheelContext::request_param_value`.
     pub volume: Volume,
 
     /// The time in seconds of the internal smoothing filter.
@@ -47,11 +59,15 @@ pub struct VolumeNode {
     /// By default this is set to `0.023` (23ms). This value is chosen to be
     /// roughly equal to a typical block size of 1024 samples (23 ms) to
     /// eliminate stair-stepping for most games.
+    ///
+    /// This cannot be automated.
     pub smooth_seconds: f32,
     /// If the resulting gain (in raw amplitude, not decibels) is less
     /// than or equal to this value, then the gain will be clamped to
     /// `0.0` (silence).
     ///
+    /// This cannot be automated.
+    ///
     /// By default this is set to `0.00001` (-100 decibels).
     pub min_gain: f32,
 }
@@ -171,10 +187,23 @@ struct VolumeProcessor {
 }
 
 impl AudioNodeProcessor for VolumeProcessor {
-    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, _extra: &mut ProcExtra) {
-        for patch in events.drain_patches::<VolumeNode>() {
-            match patch {
-                VolumeNodePatch::Volume(v) => {
+    fn events(&mut self, info: &ProcInfo, events: &mut ProcEvents, extra: &mut ProcExtra) {
+        for (patch, automation) in events.drain_patches_with_automation::<VolumeNode>() {
+            match (patch, automation) {
+                (VolumeNodePatch::SmoothSeconds(_) | VolumeNodePatch::MinGain(_), Some(_)) => {
+                    let _ = extra
+                        .logger
+                        .try_error("Only the volume of a VolumeNode can be automated");
+                }
+                (VolumeNodePatch::Volume(_), Some(automation)) => {
+                    // The values of volume automations are in decibels.
+                    self.gain.start_automation(
+                        automation,
+                        AutomationScale::Decibels,
+                        info.sample_rate,
+                    );
+                }
+                (VolumeNodePatch::Volume(v), None) => {
                     let mut gain = v.amp_clamped(self.min_gain);
                     if gain > 0.99999 && gain < 1.00001 {
                         gain = 1.0;
@@ -186,10 +215,10 @@ impl AudioNodeProcessor for VolumeProcessor {
                         self.gain.reset_to_target();
                     }
                 }
-                VolumeNodePatch::SmoothSeconds(seconds) => {
+                (VolumeNodePatch::SmoothSeconds(seconds), _) => {
                     self.gain.set_smooth_seconds(seconds, info.sample_rate);
                 }
-                VolumeNodePatch::MinGain(min_gain) => {
+                (VolumeNodePatch::MinGain(min_gain), _) => {
                     self.min_gain = min_gain.max(0.0);
                 }
             }
@@ -200,6 +229,13 @@ impl AudioNodeProcessor for VolumeProcessor {
         self.gain.reset_to_target();
     }
 
+    fn snapshot_state(&self, snapshot: &mut dyn Any) -> bool {
+        ParamValueSnapshot::fill(snapshot, |path| match path {
+            [0] => Some(amp_to_db_clamped(self.gain.current_value(), self.min_gain)),
+            _ => None,
+        })
+    }
+
     fn process(
         &mut self,
         info: &ProcInfo,
@@ -209,7 +245,15 @@ impl AudioNodeProcessor for VolumeProcessor {
         if info.in_silence_mask.all_channels_silent(self.num_channels) {
             // All channels are silent, so there is no need to process. Also reset
             // the filter since it doesn't need to smooth anything.
-            self.gain.reset_to_target();
+            if self.gain.is_automating() {
+                // Keep the automation in time with the rest of the graph.
+                for _ in 0..info.frames {
+                    self.gain.next_smoothed();
+                }
+                self.gain.settle();
+            } else {
+                self.gain.reset_to_target();
+            }
 
             return ProcessStatus::ClearAllOutputs;
         }
@@ -307,3 +351,149 @@ impl AudioNodeProcessor for VolumeProcessor {
         self.gain.update_sample_rate(stream_info.sample_rate);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use core::{num::NonZeroU32, time::Duration};
+
+    #[cfg(not(feature = "std"))]
+    use bevy_platform::prelude::vec;
+    use firewheel_core::{
+        clock::InstantSamples,
+        diff::ParamPath,
+        dsp::{buffer::ConstSequentialBuffer, declick::DeclickValues, volume::db_to_amp},
+        event::{NodeEvent, NodeEventType, ProcEventsIndex},
+        log::{RealtimeLoggerConfig, realtime_logger},
+        mask::{ConnectedMask, ConstantMask, SilenceMask},
+        node::{NodeID, ProcStore, StreamStatus},
+        param::automation::{Automation, AutomationCurve},
+    };
+
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 1_000;
+    const FRAMES: usize = 128;
+
+    fn processor(volume: Volume) -> VolumeProcessor {
+        VolumeProcessor {
+            gain: SmoothedParam::new(
+                volume.amp(),
+                SmootherConfig::default(),
+                NonZeroU32::new(SAMPLE_RATE).unwrap(),
+            ),
+            num_channels: 1,
+            min_gain: DEFAULT_MIN_AMP,
+        }
+    }
+
+    fn proc_info() -> ProcInfo {
+        ProcInfo {
+            frames: FRAMES,
+            in_silence_mask: SilenceMask::default(),
+            out_silence_mask: SilenceMask::default(),
+            in_constant_mask: ConstantMask::default(),
+            out_constant_mask: ConstantMask::default(),
+            in_connected_mask: ConnectedMask::default(),
+            out_connected_mask: ConnectedMask::default(),
+            prev_output_was_silent: false,
+            sample_rate: NonZeroU32::new(SAMPLE_RATE).unwrap(),
+            sample_rate_recip: (SAMPLE_RATE as f64).recip(),
+            clock_samples: InstantSamples(0),
+            total_cpu_seconds_recip: 0.0,
+            duration_since_stream_start: Duration::ZERO,
+            stream_status: StreamStatus::empty(),
+            dropped_frames: 0,
+            process_to_playback_delay: None,
+            did_just_unbypass: false,
+            #[cfg(feature = "musical_transport")]
+            transport_info: None,
+        }
+    }
+
+    fn proc_extra() -> ProcExtra {
+        ProcExtra {
+            scratch_buffers: ConstSequentialBuffer::new(FRAMES),
+            extra_scratch_buffers: None,
+            declick_values: DeclickValues::new(NonZeroU32::new(16).unwrap()),
+            logger: realtime_logger(RealtimeLoggerConfig::default()).0,
+            store: ProcStore::with_capacity(0),
+        }
+    }
+
+    /// Send an automation event for the parameter at `path`, and then process
+    /// a block of ones.
+    fn automate_and_process(
+        processor: &mut VolumeProcessor,
+        path: u32,
+        automation: Automation,
+    ) -> [f32; FRAMES] {
+        let info = proc_info();
+        let mut extra = proc_extra();
+
+        let mut immediate_events = [Some(NodeEvent {
+            node_id: NodeID::DANGLING,
+            #[cfg(feature = "scheduled_events")]
+            time: None,
+            event: NodeEventType::Automation {
+                automation,
+                path: ParamPath::Single(path),
+            },
+        })];
+        let mut indices = vec![ProcEventsIndex::Immediate(0)];
+        processor.events(
+            &info,
+            &mut ProcEvents::new(
+                &mut immediate_events,
+                #[cfg(feature = "scheduled_events")]
+                &mut [],
+                &mut indices,
+            ),
+            &mut extra,
+        );
+
+        let mut out = [0.0; FRAMES];
+        processor.process(
+            &info,
+            ProcBuffers {
+                inputs: &[&[1.0; FRAMES]],
+                outputs: &mut [&mut out],
+            },
+            &mut extra,
+        );
+
+        out
+    }
+
+    #[test]
+    fn volume_follows_automation_curve() {
+        let mut processor = processor(Volume::UNITY_GAIN);
+
+        // Fade from 0 dB to -20 dB over the first 100 frames.
+        let out = automate_and_process(
+            &mut processor,
+            0,
+            Automation::new(AutomationCurve::Linear, 0.0, -20.0, 0.1),
+        );
+
+        assert!((out[0] - 1.0).abs() < 1.0e-6, "{}", out[0]);
+        assert!((out[50] - db_to_amp(-10.0)).abs() < 1.0e-4, "{}", out[50]);
+        assert!((out[100] - 0.1).abs() < 1.0e-4, "{}", out[100]);
+        assert!((out[FRAMES - 1] - 0.1).abs() < 1.0e-4);
+        assert!(!processor.gain.is_automating());
+    }
+
+    #[test]
+    fn min_gain_is_not_automatable() {
+        let mut processor = processor(Volume::Decibels(-10.0));
+
+        // If this were applied, then the volume would be clamped to silence.
+        let out = automate_and_process(
+            &mut processor,
+            2,
+            Automation::new(AutomationCurve::Linear, 0.0, 0.5, 0.1),
+        );
+
+        assert_eq!(processor.min_gain, DEFAULT_MIN_AMP);
+        assert!((out[FRAMES - 1] - db_to_amp(-10.0)).abs() < 1.0e-6);
+    }
+}
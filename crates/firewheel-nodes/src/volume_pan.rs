@@ -17,6 +17,22 @@ use firewheel_core::{
     param::smoother::{SmoothedParam, SmootherConfig},
 };
 
+/// How the pan value of a [`VolumePanNode`] is applied to a stereo signal.
+#[derive(Default, Diff, Patch, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(bevy_reflect::Reflect))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PanMode {
+    /// Pan the signal across the stereo field using the pan law, where both
+    /// channels are attenuated when centered.
+    #[default]
+    Pan,
+    /// Attenuate only the channel opposite to the pan direction, leaving
+    /// centered content untouched.
+    ///
+    /// The pan law is used as the attenuation curve.
+    Balance,
+}
+
 /// A node that applies volume and panning to a stereo signal
 #[derive(Diff, Patch, Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::prelude::Component))]
@@ -31,7 +47,22 @@ pub struct VolumePanNode {
     /// The algorithm used to map the normalized panning value in the range
     /// `[-1.0, 1.0]` to the corresponding gain values for the left and right
     /// channels.
+    ///
+    /// Common pan laws map to the following curves:
+    /// * -3dB: [`FadeCurve::EqualPower3dB`]
+    /// * -4.5dB: [`FadeCurve::EqualPower4_5dB`]
+    /// * -6dB: [`FadeCurve::EqualPower6dB`]
+    /// * linear (-6dB): [`FadeCurve::Linear`]
+    ///
+    /// Changing this value is smoothed in the same way as changing the
+    /// pan amount.
+    ///
+    /// By default this is set to [`FadeCurve::EqualPower3dB`].
     pub pan_law: FadeCurve,
+    /// How the pan value is applied to the signal.
+    ///
+    /// By default this is set to [`PanMode::Pan`].
+    pub mode: PanMode,
 
     /// The time in seconds of the internal smoothing filter.
     ///
@@ -58,6 +89,7 @@ impl VolumePanNode {
             volume,
             pan,
             pan_law: FadeCurve::EqualPower3dB,
+            mode: PanMode::Pan,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             min_gain: DEFAULT_MIN_AMP,
         }
@@ -74,6 +106,7 @@ impl VolumePanNode {
             volume: Volume::UNITY_GAIN,
             pan,
             pan_law: FadeCurve::EqualPower3dB,
+            mode: PanMode::Pan,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             min_gain: DEFAULT_MIN_AMP,
         }
@@ -87,6 +120,7 @@ impl VolumePanNode {
             volume,
             pan: 0.0,
             pan_law: FadeCurve::EqualPower3dB,
+            mode: PanMode::Pan,
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             min_gain: DEFAULT_MIN_AMP,
         }
@@ -118,7 +152,16 @@ impl VolumePanNode {
     pub fn compute_gains(&self, min_amp: f32) -> (f32, f32) {
         let global_gain = self.volume.amp_clamped(min_amp);
 
-        let (mut gain_l, mut gain_r) = self.pan_law.compute_gains_neg1_to_1(self.pan);
+        let (mut gain_l, mut gain_r) = match self.mode {
+            PanMode::Pan => self.pan_law.compute_gains_neg1_to_1(self.pan),
+            PanMode::Balance => {
+                if self.pan >= 0.0 {
+                    (self.pan_law.compute_gains_0_to_1(self.pan).0, 1.0)
+                } else {
+                    (1.0, self.pan_law.compute_gains_0_to_1(-self.pan).0)
+                }
+            }
+        };
 
         gain_l *= global_gain;
         gain_r *= global_gain;
@@ -140,6 +183,7 @@ impl Default for VolumePanNode {
             volume: Volume::default(),
             pan: 0.0,
             pan_law: FadeCurve::default(),
+            mode: PanMode::default(),
             smooth_seconds: DEFAULT_SMOOTH_SECONDS,
             min_gain: DEFAULT_MIN_AMP,
         }
@@ -0,0 +1,193 @@
+use core::num::NonZeroU32;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use firewheel_core::{collector::ArcGc, sample_resource::SampleResource};
+
+type DynSampleResource = ArcGc<dyn SampleResource + Send + Sync + 'static>;
+
+/// A cache of loaded sample resources, keyed by file path and target
+/// sample rate.
+///
+/// Loading the same file (at the same sample rate) more than once will
+/// return the previously loaded resource instead of decoding it again.
+///
+/// Cached resources are kept alive until [`SampleCache::prune`] is called
+/// while no other references to them exist, at which point they are handed
+/// off to the [`Collector`][firewheel_core::collector::Collector] to be
+/// deallocated.
+///
+/// ```no_run
+/// # use firewheel_symphonium::{SampleCache, dyn_symphonium_resource};
+/// # use core::num::NonZeroU32;
+/// # let sample_rate = NonZeroU32::new(44100).unwrap();
+/// let mut cache = SampleCache::new();
+///
+/// let load = |path: &std::path::Path, sample_rate: Option<NonZeroU32>| {
+///     let probed = symphonium::probe_from_file(path, None).map_err(|_| ())?;
+///     let decoded = symphonium::decode(
+///         probed,
+///         &symphonium::DecodeConfig::default(),
+///         sample_rate,
+///         None,
+///         None,
+///     )
+///     .map_err(|_| ())?;
+///
+///     Ok::<_, ()>(dyn_symphonium_resource(decoded))
+/// };
+///
+/// let a = cache.get_or_load(load, "assets/kick.wav", Some(sample_rate)).unwrap();
+/// // The file is only decoded once.
+/// let b = cache.get_or_load(load, "assets/kick.wav", Some(sample_rate)).unwrap();
+/// ```
+#[derive(Default)]
+pub struct SampleCache {
+    entries: HashMap<(PathBuf, Option<NonZeroU32>), DynSampleResource>,
+}
+
+impl SampleCache {
+    /// Construct a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the resource for the given path and target sample rate, loading
+    /// it with `loader` if it is not already cached.
+    ///
+    /// The `loader` is only called on a cache miss, and it receives the
+    /// same path and sample rate passed to this method. If the loader
+    /// returns an error, then nothing is cached.
+    pub fn get_or_load<E>(
+        &mut self,
+        loader: impl FnOnce(&Path, Option<NonZeroU32>) -> Result<DynSampleResource, E>,
+        path: impl AsRef<Path>,
+        sample_rate: Option<NonZeroU32>,
+    ) -> Result<DynSampleResource, E> {
+        let key = (path.as_ref().to_path_buf(), sample_rate);
+
+        if let Some(resource) = self.entries.get(&key) {
+            return Ok(resource.clone());
+        }
+
+        let resource = (loader)(&key.0, sample_rate)?;
+
+        self.entries.insert(key, resource.clone());
+
+        Ok(resource)
+    }
+
+    /// Re-load all cached resources which were loaded for a different target
+    /// sample rate so that they match the new `sample_rate`.
+    ///
+    /// Call this after the audio stream has been restarted with a different
+    /// sample rate (i.e. when [`StreamInfo::sample_rate`] differs from
+    /// [`StreamInfo::prev_sample_rate`]). The `loader` is called at most once
+    /// for each unique path, even if that path was cached for multiple sample
+    /// rates. Entries that were loaded without a target sample rate are left
+    /// untouched.
+    ///
+    /// This returns a list of `(old, new)` resources. Since sampler nodes clear
+    /// their sample when the sample rate changes, use this list to re-send the
+    /// new resource to every node which was using the old one (i.e. by matching
+    /// with [`ArcGc::ptr_eq`]), followed by a sync of its play state.
+    ///
+    /// If the loader returns an error, then the entries which were already
+    /// re-loaded stay updated and the rest are left as they were.
+    ///
+    /// [`StreamInfo::sample_rate`]: firewheel_core::StreamInfo::sample_rate
+    /// [`StreamInfo::prev_sample_rate`]: firewheel_core::StreamInfo::prev_sample_rate
+    pub fn update_sample_rate<E>(
+        &mut self,
+        mut loader: impl FnMut(&Path, Option<NonZeroU32>) -> Result<DynSampleResource, E>,
+        sample_rate: NonZeroU32,
+    ) -> Result<Vec<(DynSampleResource, DynSampleResource)>, E> {
+        let mut stale: Vec<(PathBuf, NonZeroU32)> = self
+            .entries
+            .keys()
+            .filter_map(|(path, sr)| match sr {
+                Some(sr) if *sr != sample_rate => Some((path.clone(), *sr)),
+                _ => None,
+            })
+            .collect();
+
+        // Group the entries by path so each file is only loaded once.
+        stale.sort_unstable();
+
+        let mut replaced = Vec::with_capacity(stale.len());
+        let mut new_resource: Option<(PathBuf, DynSampleResource)> = None;
+
+        for (path, old_sample_rate) in stale {
+            let resource = match &new_resource {
+                Some((p, resource)) if *p == path => resource.clone(),
+                _ => {
+                    let resource = match self.entries.get(&(path.clone(), Some(sample_rate))) {
+                        Some(resource) => resource.clone(),
+                        None => (loader)(&path, Some(sample_rate))?,
+                    };
+
+                    self.entries
+                        .insert((path.clone(), Some(sample_rate)), resource.clone());
+                    new_resource = Some((path.clone(), resource.clone()));
+
+                    resource
+                }
+            };
+
+            if let Some(old) = self.entries.remove(&(path, Some(old_sample_rate))) {
+                replaced.push((old, resource));
+            }
+        }
+
+        Ok(replaced)
+    }
+
+    /// Get the cached resource for the given path and target sample rate,
+    /// if it exists.
+    pub fn get(
+        &self,
+        path: impl AsRef<Path>,
+        sample_rate: Option<NonZeroU32>,
+    ) -> Option<DynSampleResource> {
+        self.entries
+            .get(&(path.as_ref().to_path_buf(), sample_rate))
+            .cloned()
+    }
+
+    /// Remove the given entry from the cache, returning it if it existed.
+    pub fn remove(
+        &mut self,
+        path: impl AsRef<Path>,
+        sample_rate: Option<NonZeroU32>,
+    ) -> Option<DynSampleResource> {
+        self.entries
+            .remove(&(path.as_ref().to_path_buf(), sample_rate))
+    }
+
+    /// Drop all cached resources which are no longer referenced anywhere
+    /// else (including by any nodes on the audio thread).
+    ///
+    /// The dropped resources are deallocated on the next call to
+    /// [`GlobalRtGc::collect`][firewheel_core::collector::GlobalRtGc::collect].
+    pub fn prune(&mut self) {
+        self.entries
+            .retain(|_, resource| ArcGc::strong_count(resource) > 1);
+    }
+
+    /// Remove all entries from the cache.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The number of cached resources.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no cached resources.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -3,6 +3,12 @@ use core::{
     ops::{Index, IndexMut, Range},
 };
 
+mod cache;
+mod transform;
+
+pub use cache::SampleCache;
+pub use transform::{LoadTransform, LoadTransformInfo, dyn_symphonium_resource_f32_transformed};
+
 use firewheel_core::{
     collector::ArcGc,
     sample_resource::{SampleResource, SampleResourceF32, SampleResourceInfo},
@@ -0,0 +1,158 @@
+use firewheel_core::{
+    collector::ArcGc,
+    dsp::volume::{amp_to_db, db_to_amp},
+    sample_resource::SampleResourceF32,
+};
+
+use crate::SymphoniumAudioF32;
+
+/// Transformations applied to decoded audio when it is loaded, i.e. to get
+/// game sound effects ready to play without post-processing them by hand.
+///
+/// The transformations are applied in the order: trim silence, normalize,
+/// and then fade in/out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadTransform {
+    /// If `Some`, then leading and trailing frames where all channels are
+    /// below this threshold in decibels are removed.
+    ///
+    /// By default this is set to `None`.
+    pub trim_silence_db: Option<f32>,
+    /// If `Some`, then the audio is scaled so that its peak amplitude is equal
+    /// to this value in decibels.
+    ///
+    /// By default this is set to `None`.
+    pub normalize_to_db: Option<f32>,
+    /// The length of a linear fade in applied to the start of the audio, in
+    /// milliseconds. Set to `0.0` to disable.
+    ///
+    /// By default this is set to `0.0`.
+    pub fade_in_ms: f32,
+    /// The length of a linear fade out applied to the end of the audio, in
+    /// milliseconds. Set to `0.0` to disable.
+    ///
+    /// By default this is set to `0.0`.
+    pub fade_out_ms: f32,
+}
+
+impl Default for LoadTransform {
+    fn default() -> Self {
+        Self {
+            trim_silence_db: None,
+            normalize_to_db: None,
+            fade_in_ms: 0.0,
+            fade_out_ms: 0.0,
+        }
+    }
+}
+
+/// Information about a [`LoadTransform`] which was applied to some audio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadTransformInfo {
+    /// The linear gain applied by normalization. This is `1.0` if no
+    /// normalization was applied.
+    ///
+    /// This can be used to keep volumes consistent, i.e. by applying the
+    /// inverse gain on playback.
+    pub gain: f32,
+    /// The number of frames removed from the start of the audio.
+    pub trimmed_start_frames: usize,
+    /// The number of frames removed from the end of the audio.
+    pub trimmed_end_frames: usize,
+}
+
+impl LoadTransformInfo {
+    /// The gain applied by normalization in decibels.
+    pub fn gain_db(&self) -> f32 {
+        amp_to_db(self.gain)
+    }
+}
+
+impl SymphoniumAudioF32 {
+    /// Apply the given [`LoadTransform`] to this audio in place.
+    ///
+    /// This is not realtime-safe, and is meant to be used right after the
+    /// audio is decoded.
+    pub fn apply_transform(&mut self, transform: &LoadTransform) -> LoadTransformInfo {
+        let mut info = LoadTransformInfo {
+            gain: 1.0,
+            trimmed_start_frames: 0,
+            trimmed_end_frames: 0,
+        };
+
+        let data = &mut self.0.data;
+        let frames = data.first().map(|ch| ch.len()).unwrap_or(0);
+
+        if let Some(trim_silence_db) = transform.trim_silence_db {
+            let threshold = db_to_amp(trim_silence_db);
+            let is_loud = |i: usize| data.iter().any(|ch| ch[i].abs() > threshold);
+
+            let start = (0..frames).find(|&i| is_loud(i)).unwrap_or(frames);
+            let end = (start..frames)
+                .rev()
+                .find(|&i| is_loud(i))
+                .map(|i| i + 1)
+                .unwrap_or(start);
+
+            info.trimmed_start_frames = start;
+            info.trimmed_end_frames = frames - end;
+
+            for ch in data.iter_mut() {
+                ch.truncate(end);
+                ch.drain(..start);
+                ch.shrink_to_fit();
+            }
+        }
+
+        if let Some(normalize_to_db) = transform.normalize_to_db {
+            let peak = data
+                .iter()
+                .flat_map(|ch| ch.iter())
+                .fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+            if peak > 0.0 {
+                info.gain = db_to_amp(normalize_to_db) / peak;
+
+                for s in data.iter_mut().flat_map(|ch| ch.iter_mut()) {
+                    *s *= info.gain;
+                }
+            }
+        }
+
+        let frames = data.first().map(|ch| ch.len()).unwrap_or(0);
+        let sample_rate = self.0.sample_rate.get() as f32;
+        let ms_to_frames =
+            |ms: f32| ((ms.max(0.0) * sample_rate / 1_000.0).round() as usize).min(frames);
+
+        let fade_in_frames = ms_to_frames(transform.fade_in_ms);
+        let fade_out_frames = ms_to_frames(transform.fade_out_ms);
+
+        for ch in data.iter_mut() {
+            for (i, s) in ch[..fade_in_frames].iter_mut().enumerate() {
+                *s *= i as f32 / fade_in_frames as f32;
+            }
+
+            for (i, s) in ch[frames - fade_out_frames..].iter_mut().rev().enumerate() {
+                *s *= i as f32 / fade_out_frames as f32;
+            }
+        }
+
+        info
+    }
+}
+
+/// A helper method to apply a [`LoadTransform`] to a
+/// [`symphonium::DecodedAudioF32`] resource and convert it into a type
+/// erased [`SampleResourceF32`].
+pub fn dyn_symphonium_resource_f32_transformed(
+    data: symphonium::DecodedAudioF32,
+    transform: &LoadTransform,
+) -> (
+    ArcGc<dyn SampleResourceF32 + Send + Sync + 'static>,
+    LoadTransformInfo,
+) {
+    let mut audio = SymphoniumAudioF32(data);
+    let info = audio.apply_transform(transform);
+
+    (audio.into_dyn_resource(), info)
+}
@@ -0,0 +1,409 @@
+//! A Web Audio backend for Firewheel which runs the audio graph inside of an
+//! [`AudioWorkletProcessor`](https://developer.mozilla.org/en-US/docs/Web/API/AudioWorkletProcessor).
+//!
+//! This backend requires the WASM module to be built with shared memory
+//! (the `atomics` and `bulk-memory` target features), and the JS glue to be
+//! generated with `wasm-bindgen --target web`. See the README of this crate
+//! for more information.
+#![cfg(target_family = "wasm")]
+
+#[cfg(not(target_feature = "atomics"))]
+compile_error!(
+    "firewheel-web requires shared memory. Build with `-C target-feature=+atomics,+bulk-memory`."
+);
+
+use core::{num::NonZeroU32, time::Duration};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use audioadapter_buffers::direct::{InterleavedSlice, SequentialSlice};
+use firewheel_core::node::StreamStatus;
+use firewheel_graph::{
+    ActivateInfo, FirewheelContext,
+    backend::BackendProcessInfo,
+    error::{ActivateError, CompileGraphError},
+    processor::FirewheelProcessor,
+};
+use js_sys::{Array, Float32Array};
+use wasm_bindgen::{JsCast, JsValue, prelude::wasm_bindgen};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AudioContext, AudioContextOptions, AudioWorkletNode, AudioWorkletNodeOptions, Blob,
+    BlobPropertyBag, Url,
+};
+
+#[cfg(all(feature = "log", not(feature = "tracing")))]
+use log::{error, info};
+#[cfg(feature = "tracing")]
+use tracing::{error, info};
+
+/// The number of frames in a single render quantum of the Web Audio API.
+pub const RENDER_QUANTUM_FRAMES: usize = 128;
+
+/// The name the processor is registered with in `worklet.js`.
+const PROCESSOR_NAME: &str = "firewheel-processor";
+
+/// The configuration of a Web Audio stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebAudioConfig {
+    /// The desired sample rate to use. Set to `None` to use the browser's
+    /// default sample rate.
+    ///
+    /// By default this is set to `None`.
+    pub desired_sample_rate: Option<u32>,
+
+    /// The number of output channels.
+    ///
+    /// This is clamped to the maximum number of channels supported by the
+    /// output device.
+    ///
+    /// By default this is set to `2`.
+    pub num_out_channels: u32,
+
+    /// The desired latency in seconds. Set to `None` to let the browser
+    /// choose the latency for interactive playback.
+    ///
+    /// By default this is set to `None`.
+    pub latency_hint_seconds: Option<f64>,
+}
+
+impl Default for WebAudioConfig {
+    fn default() -> Self {
+        Self {
+            desired_sample_rate: None,
+            num_out_channels: 2,
+            latency_hint_seconds: None,
+        }
+    }
+}
+
+/// A Web Audio stream running a [`FirewheelProcessor`] in an audio worklet.
+///
+/// The audio stream is automatically stopped when this struct is dropped.
+pub struct WebAudioStream {
+    audio_context: AudioContext,
+    node: AudioWorkletNode,
+    is_running: Arc<AtomicBool>,
+}
+
+impl WebAudioStream {
+    /// Create a new audio stream with the given [`FirewheelContext`].
+    ///
+    /// Note, browsers only allow audio to start after a user interaction
+    /// with the page. If the stream was created outside of a user gesture,
+    /// then call [`WebAudioStream::resume`] once one has occurred.
+    pub async fn new(
+        cx: &mut FirewheelContext,
+        config: WebAudioConfig,
+    ) -> Result<Self, StartStreamError> {
+        info!("Attempting to start Web Audio stream...");
+
+        if cx.is_active() {
+            return Err(StartStreamError::AlreadyActive);
+        }
+
+        let options = AudioContextOptions::new();
+        if let Some(sample_rate) = config.desired_sample_rate {
+            options.set_sample_rate(sample_rate as f32);
+        }
+        if let Some(latency) = config.latency_hint_seconds {
+            options.set_latency_hint(&JsValue::from_f64(latency));
+        }
+
+        let audio_context = AudioContext::new_with_context_options(&options)?;
+
+        // Make sure the polyfills get included in the JS glue.
+        nop();
+
+        let module_url = worklet_module_url()?;
+        let res = JsFuture::from(audio_context.audio_worklet()?.add_module(&module_url)?).await;
+        Url::revoke_object_url(&module_url)?;
+        res?;
+
+        let destination = audio_context.destination();
+        let num_out_channels = config
+            .num_out_channels
+            .clamp(1, destination.max_channel_count().max(1));
+
+        let sample_rate = audio_context.sample_rate() as u32;
+
+        let process_to_playback_delay = Some(Duration::from_secs_f64(
+            audio_context.base_latency().max(0.0),
+        ));
+
+        let processor = cx.activate(ActivateInfo {
+            sample_rate: NonZeroU32::new(sample_rate).unwrap(),
+            max_block_frames: NonZeroU32::new(RENDER_QUANTUM_FRAMES as u32).unwrap(),
+            num_stream_in_channels: 0,
+            num_stream_out_channels: num_out_channels,
+            input_to_output_latency_seconds: 0.0,
+        })?;
+
+        let is_running = Arc::new(AtomicBool::new(true));
+
+        let handle = FirewheelWorkletProcessor::new(
+            processor,
+            sample_rate,
+            num_out_channels as usize,
+            process_to_playback_delay,
+            Arc::clone(&is_running),
+        )
+        .pack();
+
+        // The worklet runs on a separate thread which shares this module's
+        // memory, so the processor is handed over as a raw pointer.
+        let processor_options = Array::of3(
+            &wasm_bindgen::module(),
+            &wasm_bindgen::memory(),
+            &JsValue::from(handle),
+        );
+
+        let node_options = AudioWorkletNodeOptions::new();
+        node_options.set_number_of_inputs(0);
+        node_options.set_number_of_outputs(1);
+        node_options.set_output_channel_count(&Array::of1(&JsValue::from(num_out_channels)));
+        node_options.set_processor_options(Some(processor_options.as_ref()));
+
+        let node =
+            match AudioWorkletNode::new_with_options(&audio_context, PROCESSOR_NAME, &node_options)
+            {
+                Ok(node) => node,
+                Err(e) => {
+                    // SAFETY: The handle was never received by the worklet, so
+                    // this is the only owner of it.
+                    drop(unsafe { FirewheelWorkletProcessor::unpack(handle) });
+                    return Err(e.into());
+                }
+            };
+
+        // From here on, dropping the stream also stops the processor in the
+        // worklet.
+        let stream = Self {
+            audio_context,
+            node,
+            is_running,
+        };
+
+        stream.node.connect_with_audio_node(&destination)?;
+
+        let _ = stream.audio_context.resume()?;
+
+        info!(
+            "Successfully started Web Audio stream: sample rate: {}, output channels: {}",
+            sample_rate, num_out_channels
+        );
+
+        Ok(stream)
+    }
+
+    /// Resume the audio stream.
+    ///
+    /// Browsers only allow audio to start after a user interaction with the
+    /// page, so this should be called from inside of a user gesture handler
+    /// if the stream was created outside of one.
+    pub fn resume(&self) {
+        if let Err(e) = self.audio_context.resume() {
+            error!("Failed to resume Web Audio stream: {:?}", e);
+        }
+    }
+
+    /// Returns `true` if the audio stream is currently running.
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::Relaxed)
+    }
+
+    /// The underlying Web Audio context.
+    pub fn audio_context(&self) -> &AudioContext {
+        &self.audio_context
+    }
+}
+
+impl Drop for WebAudioStream {
+    fn drop(&mut self) {
+        // Tell the worklet to drop the processor so that the Firewheel
+        // context can be activated again.
+        if let Ok(port) = self.node.port() {
+            let _ = port.post_message(&JsValue::from_str("stop"));
+        }
+
+        let _ = self.node.disconnect();
+        let _ = self.audio_context.close();
+    }
+}
+
+/// The processor which is run inside of the audio worklet.
+///
+/// This is only exported for use by the worklet's JS code.
+#[doc(hidden)]
+#[wasm_bindgen]
+pub struct FirewheelWorkletProcessor {
+    processor: FirewheelProcessor,
+    out_buffer: Vec<f32>,
+    num_out_channels: usize,
+    frames_processed: u64,
+    sample_rate_recip: f64,
+    process_to_playback_delay: Option<Duration>,
+    is_running: Arc<AtomicBool>,
+}
+
+impl FirewheelWorkletProcessor {
+    fn new(
+        processor: FirewheelProcessor,
+        sample_rate: u32,
+        num_out_channels: usize,
+        process_to_playback_delay: Option<Duration>,
+        is_running: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            processor,
+            out_buffer: vec![0.0; num_out_channels * RENDER_QUANTUM_FRAMES],
+            num_out_channels,
+            frames_processed: 0,
+            sample_rate_recip: (sample_rate as f64).recip(),
+            process_to_playback_delay,
+            is_running,
+        }
+    }
+
+    fn pack(self) -> usize {
+        Box::into_raw(Box::new(self)) as usize
+    }
+}
+
+#[wasm_bindgen]
+impl FirewheelWorkletProcessor {
+    /// # Safety
+    /// `handle` must have been created with `FirewheelWorkletProcessor::pack`,
+    /// and must only be unpacked once.
+    pub unsafe fn unpack(handle: usize) -> Self {
+        // SAFETY: Upheld by the caller.
+        *unsafe { Box::from_raw(handle as *mut Self) }
+    }
+
+    /// Process the next render quantum, where `outputs` is the array of
+    /// channels of the worklet's first output.
+    pub fn process(&mut self, outputs: Array) -> bool {
+        let channels: Vec<Float32Array> = outputs
+            .iter()
+            .take(self.num_out_channels)
+            .map(|ch| ch.unchecked_into())
+            .collect();
+
+        let total_frames = channels
+            .first()
+            .map(|ch| ch.length() as usize)
+            .unwrap_or(RENDER_QUANTUM_FRAMES);
+
+        // The render quantum is currently always 128 frames, but future
+        // versions of the Web Audio API may allow it to be configured.
+        let mut offset = 0;
+        while offset < total_frames {
+            let frames = (total_frames - offset).min(RENDER_QUANTUM_FRAMES);
+            let out_buffer = &mut self.out_buffer[..self.num_out_channels * frames];
+
+            self.processor.process(
+                &InterleavedSlice::new(&[] as &[f32], 0, frames).unwrap(),
+                &mut SequentialSlice::new_mut(out_buffer, self.num_out_channels, frames).unwrap(),
+                BackendProcessInfo {
+                    frames,
+                    process_timestamp: None,
+                    duration_since_stream_start: Duration::from_secs_f64(
+                        self.frames_processed as f64 * self.sample_rate_recip,
+                    ),
+                    input_stream_status: StreamStatus::empty(),
+                    output_stream_status: StreamStatus::empty(),
+                    dropped_frames: 0,
+                    process_to_playback_delay: self.process_to_playback_delay,
+                },
+            );
+
+            for (ch, out_ch) in channels.iter().zip(out_buffer.chunks_exact(frames)) {
+                ch.subarray(offset as u32, (offset + frames) as u32)
+                    .copy_from(out_ch);
+            }
+
+            offset += frames;
+            self.frames_processed += frames as u64;
+        }
+
+        true
+    }
+}
+
+impl Drop for FirewheelWorkletProcessor {
+    fn drop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[wasm_bindgen(module = "/src/polyfill.js")]
+extern "C" {
+    fn nop();
+}
+
+#[wasm_bindgen]
+extern "C" {
+    type ImportMeta;
+
+    #[wasm_bindgen(method, getter)]
+    fn url(this: &ImportMeta) -> String;
+
+    #[wasm_bindgen(thread_local_v2, js_namespace = import, js_name = meta)]
+    static IMPORT_META: ImportMeta;
+}
+
+/// Create an object URL of the worklet module, which imports the JS glue
+/// generated by wasm-bindgen.
+fn worklet_module_url() -> Result<String, JsValue> {
+    let header = format!(
+        "import * as bindgen from '{}';\n\n",
+        IMPORT_META.with(ImportMeta::url)
+    );
+
+    let options = BlobPropertyBag::new();
+    options.set_type("text/javascript");
+
+    Url::create_object_url_with_blob(&Blob::new_with_str_sequence_and_options(
+        &Array::of2(
+            &JsValue::from_str(&header),
+            &JsValue::from_str(include_str!("worklet.js")),
+        ),
+        &options,
+    )?)
+}
+
+/// An error occurred while trying to start a Web Audio stream.
+#[derive(Debug, thiserror::Error)]
+pub enum StartStreamError {
+    /// The Firewheel context is already active. Either it has never been activated
+    /// or the [`FirewheelProcessor`] counterpart has not been dropped yet.
+    ///
+    /// Note, in rare cases where the audio thread crashes without cleanly
+    /// dropping its contents, this may never succeed. Consider adding a
+    /// timeout to avoid deadlocking.
+    #[error("Failed to activate Firewheel context: The Firewheel context is already active")]
+    AlreadyActive,
+    /// The audio graph failed to compile.
+    #[error("Failed to activate Firewheel context: Audio graph failed to compile: {0}")]
+    GraphCompileError(#[from] CompileGraphError),
+    /// A call into the Web Audio API failed.
+    #[error("Web Audio error: {0}")]
+    WebAudioError(String),
+}
+
+impl From<ActivateError> for StartStreamError {
+    fn from(e: ActivateError) -> Self {
+        match e {
+            ActivateError::AlreadyActive => Self::AlreadyActive,
+            ActivateError::GraphCompileError(e) => Self::GraphCompileError(e),
+        }
+    }
+}
+
+impl From<JsValue> for StartStreamError {
+    fn from(e: JsValue) -> Self {
+        Self::WebAudioError(format!("{:?}", e))
+    }
+}
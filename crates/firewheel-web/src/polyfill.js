@@ -0,0 +1,28 @@
+// The `AudioWorkletGlobalScope` does not provide `TextDecoder` or
+// `TextEncoder`, which the JS glue generated by wasm-bindgen expects to
+// exist when it is first evaluated.
+if (!globalThis.TextDecoder) {
+    globalThis.TextDecoder = class TextDecoder {
+        decode(arg) {
+            if (typeof arg !== "undefined") {
+                throw Error("TextDecoder stub called");
+            } else {
+                return "";
+            }
+        }
+    };
+}
+
+if (!globalThis.TextEncoder) {
+    globalThis.TextEncoder = class TextEncoder {
+        encode(arg) {
+            if (typeof arg !== "undefined") {
+                throw Error("TextEncoder stub called");
+            } else {
+                return new Uint8Array(0);
+            }
+        }
+    };
+}
+
+export function nop() {}
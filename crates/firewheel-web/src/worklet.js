@@ -0,0 +1,29 @@
+// `bindgen` is imported by the header prepended to this file at runtime,
+// which points to the JS glue generated by wasm-bindgen.
+
+registerProcessor("firewheel-processor", class FirewheelProcessor extends AudioWorkletProcessor {
+    constructor(options) {
+        super();
+
+        const [module, memory, handle] = options.processorOptions;
+        bindgen.initSync({ module, memory });
+        this.processor = bindgen.FirewheelWorkletProcessor.unpack(handle);
+
+        this.port.onmessage = (event) => {
+            if (event.data === "stop" && this.processor) {
+                // Dropping the processor notifies the Firewheel context that
+                // the stream has stopped.
+                this.processor.free();
+                this.processor = null;
+            }
+        };
+    }
+
+    process(inputs, outputs) {
+        if (!this.processor) {
+            return false;
+        }
+
+        return this.processor.process(outputs[0]);
+    }
+});
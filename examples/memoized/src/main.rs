@@ -29,6 +29,7 @@ fn main() {
     let mut beep_test_node = Memo::new(BeepTestNode {
         freq_hz: BEEP_FREQUENCY_HZ,
         volume: BEEP_VOLUME,
+        ..Default::default()
     });
 
     let beep_test_id = cx
@@ -0,0 +1,90 @@
+use std::{sync::mpsc, time::Duration};
+
+use firewheel::{
+    cpal::CpalStream,
+    midi::MidiInput,
+    nodes::oscillator::{OscillatorNode, Waveform},
+    pool::NodePool,
+    FirewheelContext,
+};
+
+const NUM_VOICES: usize = 8;
+const UPDATE_INTERVAL: Duration = Duration::from_millis(5);
+
+fn main() {
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(tracing::Level::DEBUG)
+            .finish(),
+    )
+    .unwrap();
+
+    let midi_in = midir::MidiInput::new("firewheel midi_synth").unwrap();
+    let ports = midi_in.ports();
+    let Some(port) = ports.first() else {
+        println!("No MIDI input ports found");
+        return;
+    };
+    println!(
+        "Connecting to MIDI input port \"{}\"",
+        midi_in.port_name(port).unwrap_or_default()
+    );
+
+    // The MIDI callback is called on another thread, so send the messages
+    // to the main thread where the Firewheel context lives.
+    let (tx, rx) = mpsc::channel();
+    let _connection = midi_in
+        .connect(
+            port,
+            "firewheel-midi-synth-in",
+            move |_timestamp, bytes, _| {
+                if let Some(input) = MidiInput::parse(bytes) {
+                    let _ = tx.send(input);
+                }
+            },
+            (),
+        )
+        .unwrap();
+
+    let mut cx = FirewheelContext::new(Default::default());
+    let mut stream = CpalStream::new(&mut cx, Default::default()).unwrap();
+
+    let graph_out_id = cx.graph_out_node_id();
+
+    let mut pool = NodePool::new(
+        OscillatorNode {
+            waveform: Waveform::Saw,
+            voices: 3,
+            ..Default::default()
+        },
+        None,
+        NUM_VOICES,
+        graph_out_id,
+        &[(0, 0), (0, 1)],
+        &mut cx,
+    )
+    .unwrap();
+
+    println!("Play some notes! Press Ctrl+C to quit.");
+
+    loop {
+        for input in rx.try_iter() {
+            input.apply_to_pool(&mut pool, &mut cx);
+        }
+
+        // Update the firewheel context.
+        // This must be called regularly (i.e. once every frame).
+        if let Err(e) = cx.update() {
+            tracing::error!("{:?}", &e);
+        }
+
+        // Log any stream errors/warnings that have occurred.
+        stream.log_status();
+
+        if !stream.all_streams_ok() {
+            break;
+        }
+
+        std::thread::sleep(UPDATE_INTERVAL);
+    }
+}
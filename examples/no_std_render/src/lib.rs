@@ -0,0 +1,70 @@
+//! Renders a beep through a volume node without the standard library, using
+//! the allocator-only [`ManualBackend`].
+//!
+//! On an embedded target, [`ManualBackend::process`] would typically be
+//! called from the audio driver's callback instead of a loop.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use core::num::NonZeroU32;
+
+use firewheel::{
+    backend::{ManualBackend, ManualBackendConfig},
+    dsp::volume::Volume,
+    nodes::{beep_test::BeepTestNode, volume::VolumeNode},
+    FirewheelContext,
+};
+
+pub const SAMPLE_RATE: u32 = 48_000;
+pub const BLOCK_FRAMES: usize = 256;
+
+/// Render the given number of blocks, and return the peak amplitude of the
+/// output.
+pub fn render(num_blocks: usize) -> f32 {
+    let mut cx = FirewheelContext::new(Default::default());
+
+    let mut backend = ManualBackend::new(
+        &mut cx,
+        ManualBackendConfig {
+            sample_rate: NonZeroU32::new(SAMPLE_RATE).unwrap(),
+            max_block_frames: NonZeroU32::new(BLOCK_FRAMES as u32).unwrap(),
+            num_in_channels: 0,
+            num_out_channels: 2,
+        },
+    )
+    .unwrap();
+
+    let beep_test_id = cx
+        .add_node(
+            BeepTestNode {
+                volume: Volume::Linear(0.5),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+    let volume_id = cx.add_node(VolumeNode::from_linear(0.5), None).unwrap();
+    let graph_out_id = cx.graph_out_node_id();
+
+    cx.connect(beep_test_id, volume_id, &[(0, 0), (0, 1)], false)
+        .unwrap();
+    cx.connect_stereo(volume_id, graph_out_id, false).unwrap();
+
+    let mut output = vec![0.0; BLOCK_FRAMES * 2];
+    let mut peak: f32 = 0.0;
+
+    for _ in 0..num_blocks {
+        // Update the firewheel context.
+        // This must be called regularly (i.e. once every frame).
+        cx.update().unwrap();
+
+        backend.process(&[], &mut output, BLOCK_FRAMES);
+
+        peak = output.iter().fold(peak, |peak, &s| peak.max(s).max(-s));
+    }
+
+    peak
+}
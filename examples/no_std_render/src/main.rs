@@ -0,0 +1,9 @@
+fn main() {
+    println!("Rendering a beep without the standard library...");
+
+    // Render one second of audio.
+    let num_blocks = no_std_render::SAMPLE_RATE as usize / no_std_render::BLOCK_FRAMES;
+    let peak = no_std_render::render(num_blocks);
+
+    println!("finished, peak amplitude: {peak}");
+}
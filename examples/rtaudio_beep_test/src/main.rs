@@ -25,6 +25,7 @@ fn main() {
     let beep_test_node = BeepTestNode {
         freq_hz: BEEP_FREQUENCY_HZ,
         volume: BEEP_VOLUME,
+        ..Default::default()
     };
 
     let beep_test_id = cx
@@ -45,7 +45,7 @@ impl AudioSystem {
 
         let graph_out = cx.graph_out_node_id();
 
-        let peak_meter_node = PeakMeterNode::<2> { enabled: true };
+        let peak_meter_node = PeakMeterNode::<2>::default();
         let peak_meter_smoother = PeakMeterSmoother::<2>::new(Default::default());
 
         let peak_meter_id = cx
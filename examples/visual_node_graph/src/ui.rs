@@ -8,7 +8,7 @@ use egui_snarl::{
 };
 use firewheel::{
     diff::Memo,
-    dsp::{fade::FadeCurve, mix::Mix},
+    dsp::{fade::FadeCurve, mix::Mix, volume::PerceptualCurve},
     nodes::{
         beep_test::BeepTestNode,
         convolution::ConvolutionNode,
@@ -444,12 +444,12 @@ impl<'a> SnarlViewer<GuiAudioNode> for DemoViewer<'a> {
 
             match &mut node.node {
                 GuiAudioNodeType::BeepTest { params } => {
-                    let mut linear_volume = params.volume.linear();
+                    let mut volume = params.volume.perceptual(PerceptualCurve::default());
                     if ui
-                        .add(egui::Slider::new(&mut linear_volume, 0.0..=1.0).text("volume"))
+                        .add(egui::Slider::new(&mut volume, 0.0..=1.0).text("volume"))
                         .changed()
                     {
-                        params.volume = Volume::Linear(linear_volume);
+                        params.volume = Volume::from_perceptual(volume, PerceptualCurve::default());
                     }
 
                     ui.add(
@@ -690,12 +690,12 @@ impl<'a> SnarlViewer<GuiAudioNode> for DemoViewer<'a> {
                             }
                         });
 
-                    let mut volume = params.volume.linear();
+                    let mut volume = params.volume.perceptual(PerceptualCurve::default());
                     if ui
                         .add(egui::Slider::new(&mut volume, 0.0..=1.0).text("volume"))
                         .changed()
                     {
-                        params.volume = Volume::Linear(volume);
+                        params.volume = Volume::from_perceptual(volume, PerceptualCurve::default());
                     }
 
                     let mut repeat = matches!(params.repeat_mode, RepeatMode::RepeatEndlessly);
@@ -758,12 +758,14 @@ fn fade_curve_ui(ui: &mut Ui, curve: &mut FadeCurve) {
     egui::ComboBox::from_label("fade curve")
         .selected_text(match curve {
             FadeCurve::EqualPower3dB => "Equal Power 3dB",
+            FadeCurve::EqualPower4_5dB => "Equal Power 4.5dB",
             FadeCurve::EqualPower6dB => "Equal Power 6dB",
             FadeCurve::SquareRoot => "Square Root",
             FadeCurve::Linear => "Linear",
         })
         .show_ui(ui, |ui| {
             ui.selectable_value(curve, FadeCurve::EqualPower3dB, "Equal Power 3dB");
+            ui.selectable_value(curve, FadeCurve::EqualPower4_5dB, "Equal Power 4.5dB");
             ui.selectable_value(curve, FadeCurve::EqualPower6dB, "Equal Power 6dB");
             ui.selectable_value(curve, FadeCurve::SquareRoot, "Square Root");
             ui.selectable_value(curve, FadeCurve::Linear, "Linear");
@@ -804,12 +806,12 @@ fn convolution_ui(ui: &mut Ui, params: &mut Memo<ConvolutionNode>, audio_system:
             }
         });
 
-    let mut linear_volume = params.wet_gain.linear();
+    let mut wet_gain = params.wet_gain.perceptual(PerceptualCurve::default());
     if ui
-        .add(egui::Slider::new(&mut linear_volume, 0.0..=1.0).text("wet gain"))
+        .add(egui::Slider::new(&mut wet_gain, 0.0..=1.0).text("wet gain"))
         .changed()
     {
-        params.wet_gain = Volume::Linear(linear_volume);
+        params.wet_gain = Volume::from_perceptual(wet_gain, PerceptualCurve::default());
     }
 
     ui.horizontal(|ui| {
@@ -0,0 +1,212 @@
+//! A helper for ducking one node under another, i.e. to lower the volume of
+//! music while dialog is playing.
+
+use firewheel_core::{
+    channel_config::NonZeroChannelCount,
+    diff::Memo,
+    node::{NodeError, NodeID},
+};
+use firewheel_graph::{
+    FirewheelContext,
+    error::AddEdgeError,
+    graph::{Connection, EdgeID},
+};
+use firewheel_nodes::dynamics::{DynamicsNodeConfig, ducker::DuckerNode};
+
+/// An error occurred while attempting to add ducking with [`add_ducking`].
+#[derive(Debug, thiserror::Error)]
+pub enum DuckingError {
+    /// The given trigger node was not found in the graph.
+    #[error("Could not add ducking: could not find trigger node with ID {0:?}")]
+    TriggerNotFound(NodeID),
+    /// The given target node was not found in the graph.
+    #[error("Could not add ducking: could not find target node with ID {0:?}")]
+    TargetNotFound(NodeID),
+    /// The given node has no output ports.
+    #[error("Could not add ducking: node {0:?} has no output ports")]
+    NoOutputs(NodeID),
+    /// The ducker node could not be added to the graph.
+    #[error("Could not add ducking: {0}")]
+    NodeError(NodeError),
+    /// The ducker node could not be connected, i.e. because the trigger node
+    /// is downstream of the target node.
+    #[error("Could not add ducking: {0}")]
+    AddEdgeError(#[from] AddEdgeError),
+}
+
+/// Automatically lower the volume of `target_node` while `trigger_node` is
+/// producing sound.
+///
+/// This inserts a [`DuckerNode`] after `target_node`. All of the existing
+/// output connections of `target_node` are moved to the ducker, and the
+/// outputs of `trigger_node` are connected to the sidechain of the ducker.
+/// The existing connections of `trigger_node` are left untouched.
+///
+/// The parameters of the ducking (threshold, depth, attack, hold, and
+/// release) can be changed later with [`Ducking::params`] and
+/// [`Ducking::update`].
+///
+/// If this returns an error, then the audio graph has not been modified.
+pub fn add_ducking(
+    cx: &mut FirewheelContext,
+    trigger_node: NodeID,
+    target_node: NodeID,
+    params: DuckerNode,
+) -> Result<Ducking, DuckingError> {
+    let target_outputs = cx
+        .node_channel_config(target_node)
+        .ok_or(DuckingError::TargetNotFound(target_node))?
+        .num_outputs;
+    let trigger_outputs = cx
+        .node_channel_config(trigger_node)
+        .ok_or(DuckingError::TriggerNotFound(trigger_node))?
+        .num_outputs
+        .get();
+
+    let channels = NonZeroChannelCount::new(target_outputs.get())
+        .ok_or(DuckingError::NoOutputs(target_node))?;
+    if trigger_outputs == 0 {
+        return Err(DuckingError::NoOutputs(trigger_node));
+    }
+
+    let ducker_node = cx
+        .add_node(
+            params,
+            Some(DynamicsNodeConfig {
+                channels,
+                use_sidechain: true,
+            }),
+        )
+        .map_err(DuckingError::NodeError)?;
+
+    let num_channels = channels.get().get();
+
+    let moved: Vec<(Connection, f32)> = cx
+        .edges()
+        .filter(|edge| edge.src_node == target_node)
+        .map(|edge| {
+            (
+                Connection::new(edge.src_node, edge.src_port, edge.dst_node, edge.dst_port),
+                cx.edge_gain(edge.id).unwrap_or(1.0),
+            )
+        })
+        .collect();
+
+    let remove: Vec<Connection> = moved.iter().map(|(c, _)| *c).collect();
+
+    // The target feeds the main input of the ducker, and the trigger feeds
+    // the sidechain. Any extra trigger channels are folded onto the
+    // sidechain, which is fine since only the peak level matters.
+    let mut add: Vec<Connection> = (0..num_channels)
+        .map(|ch| Connection::new(target_node, ch, ducker_node, ch))
+        .chain((0..trigger_outputs).map(|ch| {
+            Connection::new(
+                trigger_node,
+                ch,
+                ducker_node,
+                num_channels + (ch % num_channels),
+            )
+        }))
+        .collect();
+    let num_input_edges = add.len();
+    add.extend(
+        remove
+            .iter()
+            .map(|c| Connection::new(ducker_node, c.src_port, c.dst_node, c.dst_port)),
+    );
+
+    let edge_ids = match cx.rewire(&remove, &add, true) {
+        Ok(edge_ids) => edge_ids,
+        Err(e) => {
+            let _ = cx.remove_node(ducker_node);
+            return Err(e.into());
+        }
+    };
+
+    for (&edge_id, &(_, gain)) in edge_ids[num_input_edges..].iter().zip(moved.iter()) {
+        if gain != 1.0 {
+            cx.set_edge_gain(edge_id, gain);
+        }
+    }
+
+    Ok(Ducking {
+        params: Memo::new(params),
+        ducker_node,
+        trigger_node,
+        target_node,
+    })
+}
+
+/// A handle to ducking which was added with [`add_ducking`].
+pub struct Ducking {
+    /// The parameters of the ducker node. Call [`Ducking::update`] after
+    /// modifying these to send the changes to the audio graph.
+    pub params: Memo<DuckerNode>,
+    ducker_node: NodeID,
+    trigger_node: NodeID,
+    target_node: NodeID,
+}
+
+impl Ducking {
+    /// The ID of the [`DuckerNode`] inserted into the graph.
+    pub fn ducker_node(&self) -> NodeID {
+        self.ducker_node
+    }
+
+    /// The ID of the node whose output triggers the ducking.
+    pub fn trigger_node(&self) -> NodeID {
+        self.trigger_node
+    }
+
+    /// The ID of the node which is ducked.
+    pub fn target_node(&self) -> NodeID {
+        self.target_node
+    }
+
+    /// Send any changes made to [`Ducking::params`] to the audio graph.
+    pub fn update(&mut self, cx: &mut FirewheelContext) {
+        self.params
+            .update_memo(&mut cx.event_queue(self.ducker_node));
+    }
+
+    /// Remove the ducker node from the graph, connecting the target node
+    /// directly to the nodes it was routed to again.
+    ///
+    /// On success, this returns the IDs of the restored edges.
+    pub fn remove(self, cx: &mut FirewheelContext) -> Result<Vec<EdgeID>, AddEdgeError> {
+        let moved: Vec<(Connection, f32)> = cx
+            .edges()
+            .filter(|edge| edge.src_node == self.ducker_node)
+            .map(|edge| {
+                (
+                    Connection::new(
+                        self.target_node,
+                        edge.src_port,
+                        edge.dst_node,
+                        edge.dst_port,
+                    ),
+                    cx.edge_gain(edge.id).unwrap_or(1.0),
+                )
+            })
+            .collect();
+
+        let mut edge_ids = Vec::with_capacity(moved.len());
+
+        if cx.contains_node(self.target_node) {
+            let add: Vec<Connection> = moved.iter().map(|(c, _)| *c).collect();
+
+            edge_ids.extend(cx.rewire(&[], &add, false)?);
+
+            for (&edge_id, &(_, gain)) in edge_ids.iter().zip(moved.iter()) {
+                if gain != 1.0 {
+                    cx.set_edge_gain(edge_id, gain);
+                }
+            }
+        }
+
+        // This only fails for the graph input and output nodes.
+        let _ = cx.remove_node(self.ducker_node);
+
+        Ok(edge_ids)
+    }
+}
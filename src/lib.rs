@@ -11,5 +11,14 @@ pub use firewheel_cpal as cpal;
 #[cfg(feature = "rtaudio")]
 pub use firewheel_rtaudio as rtaudio;
 
+#[cfg(feature = "web")]
+pub use firewheel_web as web;
+
+#[cfg(feature = "midi")]
+pub use firewheel_midi as midi;
+
 #[cfg(feature = "symphonium")]
 pub use firewheel_symphonium::*;
+
+#[cfg(feature = "dynamics_nodes")]
+pub mod ducking;